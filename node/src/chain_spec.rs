@@ -155,6 +155,7 @@ fn testnet_genesis(
 		},
 		transaction_payment: Default::default(),
 		assets: Default::default(),
+		oracle_feeders: Default::default(),
 		lending: Default::default(),
 	}
 }
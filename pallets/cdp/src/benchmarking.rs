@@ -0,0 +1,166 @@
+//! Benchmarking setup for the cdp pallet
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Cdp;
+use frame_benchmarking::v2::*;
+use frame_support::{
+	sp_runtime::{FixedPointNumber, SaturatedConversion},
+	traits::fungibles::Inspect,
+};
+use frame_system::RawOrigin;
+use lending::{BenchmarkHelper, LendingBenchmarkHelper};
+
+const SEED: u32 = 0;
+const ASSET: u32 = 1;
+const POOL_ID: u32 = 1_000;
+const BALANCE: u128 = 1_000_000_000_000;
+
+fn asset_id<T: Config>(id: u32) -> AssetIdOf<T> {
+	T::BenchmarkHelper::asset_id(id)
+}
+
+fn units<T: Config>(balance: u128) -> BalanceOf<T> {
+	balance.saturated_into()
+}
+
+// Moves the clock forward, so that the stability fee accrues
+fn set_time<T: Config + pallet_timestamp::Config>(seconds: u32) {
+	pallet_timestamp::Pallet::<T>::set_timestamp((seconds * 1_000).into());
+}
+
+// Opens a vault of `who` locking the LP tokens of its supply to the lending pool of
+// `asset_id::<T>(ASSET)`, a collateral of the CDPs valued at par with the stablecoin
+fn open_vault<T: Config>(who: &T::AccountId)
+where
+	T::Lending: LendingBenchmarkHelper<AssetIdOf<T>>,
+{
+	let (asset, stable) = (asset_id::<T>(ASSET), T::StableAsset::get());
+	T::Lending::create_asset(stable);
+	if T::Lending::lp_token(asset).is_none() {
+		T::Lending::create_pool(asset_id::<T>(POOL_ID), asset);
+	}
+	T::Lending::set_price(asset, stable, Rate::one());
+	Cdp::<T>::do_set_collateral(
+		asset,
+		Ratio::from_percent(50),
+		Ratio::from_percent(80),
+		Rate::from_rational(5, 100),
+		units::<T>(BALANCE),
+	)
+	.unwrap();
+	T::Fungibles::mint_into(asset, who, units::<T>(BALANCE)).unwrap();
+	T::Lending::supply(who, asset, units::<T>(BALANCE / 2)).unwrap();
+	Cdp::<T>::do_lock_collateral(who, asset, units::<T>(BALANCE / 4)).unwrap();
+}
+
+#[benchmarks(where
+	T: pallet_timestamp::Config,
+	T::Lending: LendingBenchmarkHelper<AssetIdOf<T>>,
+)]
+mod benchmarks {
+	use super::*;
+
+	// the stability fee accrued at the former parameters is minted
+	#[benchmark]
+	fn set_collateral() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let owner: T::AccountId = account("owner", 0, SEED);
+		open_vault::<T>(&owner);
+		Cdp::<T>::do_mint_stable(&owner, asset_id::<T>(ASSET), units::<T>(BALANCE / 100)).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			asset_id::<T>(ASSET),
+			Ratio::from_percent(60),
+			Ratio::from_percent(80),
+			Rate::from_rational(5, 100),
+			units::<T>(BALANCE),
+		);
+
+		let collateral = Collaterals::<T>::get(asset_id::<T>(ASSET)).unwrap();
+		assert_eq!(collateral.collateral_factor, Ratio::from_percent(60));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn lock_collateral() {
+		let caller: T::AccountId = whitelisted_caller();
+		open_vault::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), units::<T>(BALANCE / 8));
+
+		let vault = Vaults::<T>::get(&caller, asset_id::<T>(ASSET)).unwrap();
+		assert_eq!(vault.collateral, units::<T>(BALANCE / 4 + BALANCE / 8));
+	}
+
+	// the vault with a debt is checked for collateralization
+	#[benchmark]
+	fn unlock_collateral() {
+		let caller: T::AccountId = whitelisted_caller();
+		open_vault::<T>(&caller);
+		Cdp::<T>::do_mint_stable(&caller, asset_id::<T>(ASSET), units::<T>(BALANCE / 100)).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), units::<T>(BALANCE / 8));
+
+		let vault = Vaults::<T>::get(&caller, asset_id::<T>(ASSET)).unwrap();
+		assert_eq!(vault.collateral, units::<T>(BALANCE / 8));
+	}
+
+	#[benchmark]
+	fn mint_stable() {
+		let caller: T::AccountId = whitelisted_caller();
+		open_vault::<T>(&caller);
+		Cdp::<T>::do_mint_stable(&caller, asset_id::<T>(ASSET), units::<T>(BALANCE / 100)).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), units::<T>(BALANCE / 100));
+
+		assert!(T::Fungibles::balance(T::StableAsset::get(), &caller) >= units::<T>(BALANCE / 50));
+	}
+
+	#[benchmark]
+	fn burn_stable() {
+		let caller: T::AccountId = whitelisted_caller();
+		open_vault::<T>(&caller);
+		Cdp::<T>::do_mint_stable(&caller, asset_id::<T>(ASSET), units::<T>(BALANCE / 100)).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), units::<T>(BALANCE / 200));
+
+		let vault = Vaults::<T>::get(&caller, asset_id::<T>(ASSET)).unwrap();
+		assert!(!vault.scaled_debt.is_zero());
+	}
+
+	// the collateral of the vault loses three quarters of its value
+	#[benchmark]
+	fn liquidate_vault() {
+		let caller: T::AccountId = whitelisted_caller();
+		let owner: T::AccountId = account("owner", 0, SEED);
+		open_vault::<T>(&owner);
+		Cdp::<T>::do_mint_stable(&owner, asset_id::<T>(ASSET), units::<T>(BALANCE / 10)).unwrap();
+		T::Fungibles::mint_into(T::StableAsset::get(), &caller, units::<T>(BALANCE)).unwrap();
+		T::Lending::set_price(
+			asset_id::<T>(ASSET),
+			T::StableAsset::get(),
+			Rate::from_rational(1, 4),
+		);
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), owner.clone(), asset_id::<T>(ASSET));
+
+		assert!(!Vaults::<T>::contains_key(&owner, asset_id::<T>(ASSET)));
+	}
+
+	impl_benchmark_test_suite!(Cdp, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,685 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # The CDP pallet of Kylix
+//!
+//! ## Overview
+//!
+//! The CDP pallet mints the stablecoin of the protocol, e.g. kUSD, against the LP tokens of the
+//! lending pools locked in the vaults of the accounts. The LP tokens keep earning the interest
+//! of their lending pool while they are locked, and the debt of the vaults accrues a stability
+//! fee minted to the treasury. The vaults whose debt exceeds the value of their collateral
+//! weighted with its liquidation threshold are liquidated.
+//!
+//! The pallet builds on the money market through the `Lending` trait of the lending pallet,
+//! which values and moves the LP tokens locked.
+//!
+//! Implemented Extrinsics:
+//!
+//! 0. set_collateral()
+//! 1. lock_collateral()
+//! 2. unlock_collateral()
+//! 3. mint_stable()
+//! 4. burn_stable()
+//! 5. liquidate_vault()
+
+pub use frame_support::{
+	pallet_prelude::*,
+	sp_runtime::{
+		traits::{AccountIdConversion, CheckedMul, CheckedSub, EnsureAdd, One, Zero},
+		Rounding,
+	},
+	traits::{
+		fungibles::{self, Mutate},
+		tokens::{Fortitude, Precision},
+		Contains, UnixTime,
+	},
+	PalletId,
+};
+pub use frame_system::pallet_prelude::*;
+use lending::{math, Lending, Rate, Ratio, Timestamp};
+pub use pallet::*;
+
+/// Fungible Asset Id
+pub type AssetIdOf<T> = <T as Config>::AssetId;
+
+/// Account Type Definition
+pub type AccountOf<T> = <T as frame_system::Config>::AccountId;
+
+/// Fungible Balance
+pub type BalanceOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<AccountOf<T>>>::Balance;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+/// The parameters and the debt of a collateral of the CDPs, i.e. the LP tokens of the lending
+/// pool of an asset locked to mint the `StableAsset` against
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Collateral<Balance> {
+	/// The share of the value of the locked collateral the stablecoin is minted up to
+	pub collateral_factor: Ratio,
+	/// The share of the value of the locked collateral the debt of a vault is liquidated above
+	pub liquidation_threshold: Ratio,
+	/// The annual stability fee accruing on the debt
+	pub stability_fee: Rate,
+	/// The maximum debt minted against the collateral
+	pub debt_ceiling: Balance,
+	/// The debt minted against the collateral, scaled by `debt_index`
+	pub scaled_debt: Balance,
+	/// The index of the debt at `last_accrued_at`, compounding at the stability fee
+	pub debt_index: Rate,
+	pub last_accrued_at: Timestamp,
+}
+
+/// The LP tokens locked by an account and the stablecoin it minted against them, scaled by the
+/// debt index of the collateral
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default)]
+pub struct Vault<Balance> {
+	pub collateral: Balance,
+	pub scaled_debt: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The account of the pallet, holding the LP tokens locked in the vaults.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The identifier of the assets, the LP tokens of the lending pools as well as the
+		/// `StableAsset`.
+		type AssetId: Parameter + MaxEncodedLen + Copy;
+
+		/// Type to access the Assets Pallet, minting and burning the `StableAsset`.
+		type Fungibles: fungibles::Inspect<Self::AccountId, AssetId = Self::AssetId>
+			+ fungibles::Mutate<Self::AccountId>;
+
+		/// The money market whose LP tokens are locked in the vaults, e.g. the lending pallet.
+		type Lending: Lending<Self::AccountId, AssetIdOf<Self>, BalanceOf<Self>>;
+
+		/// The stablecoin of the protocol, e.g. kUSD, minted against the LP tokens locked in the
+		/// vaults. The asset must exist, the pallet mints and burns it.
+		#[pallet::constant]
+		type StableAsset: Get<AssetIdOf<Self>>;
+
+		/// The treasury account receiving the stability fee.
+		#[pallet::constant]
+		type TreasuryAccount: Get<Self::AccountId>;
+
+		/// The wall clock the stability fee accrues with, per second whatever the block time.
+		type Time: UnixTime;
+
+		/// The origin which sets the collaterals of the CDPs.
+		type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Builds the asset ids of the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: lending::BenchmarkHelper<AssetIdOf<Self>>;
+	}
+
+	/// The parameters and the debt of the collaterals of the CDPs, by underlying asset of the
+	/// lending pool whose LP tokens are locked
+	#[pallet::storage]
+	pub type Collaterals<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Collateral<BalanceOf<T>>, OptionQuery>;
+
+	/// The vaults of the CDPs
+	/// AccountId, asset_id => Vault
+	#[pallet::storage]
+	pub type Vaults<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Vault<BalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The parameters of the LP tokens of the lending pool of `asset` as a collateral of the
+		/// CDPs were set
+		CollateralSet {
+			asset: AssetIdOf<T>,
+			collateral_factor: Ratio,
+			liquidation_threshold: Ratio,
+			stability_fee: Rate,
+			debt_ceiling: BalanceOf<T>,
+		},
+		/// `balance` of the LP tokens of the lending pool of `asset` were locked in the vault of
+		/// `who`
+		CollateralLocked {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		},
+		/// `balance` of the LP tokens of the lending pool of `asset` were unlocked from the vault
+		/// of `who`
+		CollateralUnlocked {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		},
+		/// `balance` of the stablecoin was minted to `who` against its vault
+		StableMinted {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		},
+		/// `balance` of the stablecoin of `who` was burned to pay the debt of its vault back
+		StableBurned {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		},
+		/// The vault of `owner` was liquidated by `who`, who burned its `debt` and seized its
+		/// `collateral` LP tokens
+		VaultLiquidated {
+			who: T::AccountId,
+			owner: T::AccountId,
+			asset: AssetIdOf<T>,
+			debt: BalanceOf<T>,
+			collateral: BalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The balance is zero
+		AmountTooLow,
+		/// The asset has no lending pool
+		LendingPoolDoesNotExist,
+		/// The collateral factor exceeds the liquidation threshold
+		InvalidRiskParameters,
+		/// The LP tokens of the lending pool are not a collateral of the CDPs
+		CollateralNotAllowed,
+		/// The account has no vault against the collateral
+		VaultDoesNotExist,
+		/// The debt ceiling of the collateral would be exceeded
+		DebtCeilingReached,
+		/// The vault holds fewer LP tokens
+		InsufficientCollateral,
+		/// The debt of the vault would exceed the collateral factor of its collateral
+		NotEnoughCollateral,
+		/// The vault has no shortfall
+		VaultNotLiquidatable,
+		/// An arithmetic operation overflowed
+		OverflowError,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Sets the parameters of the LP tokens of a lending pool as a collateral of the CDPs,
+		/// after accruing the stability fee at the former ones.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `collateral_factor`: The share of the value of the collateral minted up to.
+		/// - `liquidation_threshold`: The share of the value of the collateral the vaults are
+		///   liquidated above.
+		/// - `stability_fee`: The annual fee accruing on the debt.
+		/// - `debt_ceiling`: The maximum debt minted against the collateral, zero to stop the
+		///   minting.
+		///
+		/// # Emits
+		/// - `CollateralSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `InvalidRiskParameters`: The collateral factor exceeds the liquidation threshold.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_collateral())]
+		pub fn set_collateral(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			collateral_factor: Ratio,
+			liquidation_threshold: Ratio,
+			stability_fee: Rate,
+			debt_ceiling: BalanceOf<T>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_set_collateral(
+				asset,
+				collateral_factor,
+				liquidation_threshold,
+				stability_fee,
+				debt_ceiling,
+			)?;
+			Self::deposit_event(Event::CollateralSet {
+				asset,
+				collateral_factor,
+				liquidation_threshold,
+				stability_fee,
+				debt_ceiling,
+			});
+			Ok(())
+		}
+
+		/// Locks LP tokens of a lending pool in the vault of the caller against its collateral,
+		/// opening the vault if needed.
+		///
+		/// # Parameters
+		/// - `origin`: The owner of the vault.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `balance`: The LP tokens to lock.
+		///
+		/// # Emits
+		/// - `CollateralLocked`
+		///
+		/// # Errors
+		/// - `CollateralNotAllowed`: The LP tokens are not a collateral of the CDPs.
+		/// - The errors of the transfer of the LP tokens by the `Lending`.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::lock_collateral())]
+		pub fn lock_collateral(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_lock_collateral(&who, asset, balance)?;
+			Self::deposit_event(Event::CollateralLocked { who, asset, balance });
+			Ok(())
+		}
+
+		/// Unlocks LP tokens from the vault of the caller, as long as its debt remains within
+		/// the collateral factor of its collateral. The vault is closed once empty.
+		///
+		/// # Parameters
+		/// - `origin`: The owner of the vault.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `balance`: The LP tokens to unlock.
+		///
+		/// # Emits
+		/// - `CollateralUnlocked`
+		///
+		/// # Errors
+		/// - `VaultDoesNotExist`: The caller has no vault against the collateral.
+		/// - `InsufficientCollateral`: The vault holds fewer LP tokens.
+		/// - `NotEnoughCollateral`: The vault would be undercollateralized.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::unlock_collateral())]
+		pub fn unlock_collateral(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_unlock_collateral(&who, asset, balance)?;
+			Self::deposit_event(Event::CollateralUnlocked { who, asset, balance });
+			Ok(())
+		}
+
+		/// Mints the `StableAsset` to the caller against the collateral locked in its vault.
+		///
+		/// # Parameters
+		/// - `origin`: The owner of the vault.
+		/// - `asset`: The underlying asset of the lending pool of the collateral.
+		/// - `balance`: The stablecoin to mint.
+		///
+		/// # Emits
+		/// - `StableMinted`
+		///
+		/// # Errors
+		/// - `VaultDoesNotExist`: The caller has no vault against the collateral.
+		/// - `DebtCeilingReached`: The debt ceiling of the collateral would be exceeded.
+		/// - `NotEnoughCollateral`: The vault would be undercollateralized.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::mint_stable())]
+		pub fn mint_stable(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_mint_stable(&who, asset, balance)?;
+			Self::deposit_event(Event::StableMinted { who, asset, balance });
+			Ok(())
+		}
+
+		/// Burns the `StableAsset` of the caller to pay the debt of its vault back, stability
+		/// fee included.
+		///
+		/// # Parameters
+		/// - `origin`: The owner of the vault.
+		/// - `asset`: The underlying asset of the lending pool of the collateral.
+		/// - `balance`: The stablecoin to burn, capped to the debt of the vault.
+		///
+		/// # Emits
+		/// - `StableBurned`
+		///
+		/// # Errors
+		/// - `VaultDoesNotExist`: The caller has no vault against the collateral.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::burn_stable())]
+		pub fn burn_stable(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let balance = Self::do_burn_stable(&who, asset, balance)?;
+			Self::deposit_event(Event::StableBurned { who, asset, balance });
+			Ok(())
+		}
+
+		/// Liquidates a vault with a shortfall, i.e. whose debt exceeds the value of its
+		/// collateral weighted with the liquidation threshold. The liquidator burns the whole
+		/// debt in the `StableAsset` and seizes all the LP tokens locked.
+		///
+		/// # Parameters
+		/// - `origin`: The liquidator.
+		/// - `owner`: The owner of the vault.
+		/// - `asset`: The underlying asset of the lending pool of the collateral.
+		///
+		/// # Emits
+		/// - `VaultLiquidated`
+		///
+		/// # Errors
+		/// - `VaultDoesNotExist`: The owner has no vault against the collateral.
+		/// - `VaultNotLiquidatable`: The vault has no shortfall.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::liquidate_vault())]
+		pub fn liquidate_vault(
+			origin: OriginFor<T>,
+			owner: T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (debt, collateral) = Self::do_liquidate_vault(&who, &owner, asset)?;
+			Self::deposit_event(Event::VaultLiquidated { who, owner, asset, debt, collateral });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding the LP tokens locked in the vaults. As any holder of LP tokens, it
+		/// is subject to the supply caps of their lending pools.
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		fn now_in_seconds() -> Timestamp {
+			T::Time::now().as_secs()
+		}
+
+		/// Sets the parameters of the LP tokens of the lending pool of `asset` as a collateral of
+		/// the CDPs, after accruing the stability fee at the former ones. The collateral factor
+		/// can not exceed the liquidation threshold.
+		pub fn do_set_collateral(
+			asset: AssetIdOf<T>,
+			collateral_factor: Ratio,
+			liquidation_threshold: Ratio,
+			stability_fee: Rate,
+			debt_ceiling: BalanceOf<T>,
+		) -> DispatchResult {
+			ensure!(T::Lending::lp_token(asset).is_some(), Error::<T>::LendingPoolDoesNotExist);
+			ensure!(collateral_factor <= liquidation_threshold, Error::<T>::InvalidRiskParameters);
+			let collateral = match Collaterals::<T>::contains_key(asset) {
+				true => Collateral {
+					collateral_factor,
+					liquidation_threshold,
+					stability_fee,
+					debt_ceiling,
+					..Self::accrue_stability_fee(asset)?
+				},
+				false => Collateral {
+					collateral_factor,
+					liquidation_threshold,
+					stability_fee,
+					debt_ceiling,
+					scaled_debt: Zero::zero(),
+					debt_index: Rate::one(),
+					last_accrued_at: Self::now_in_seconds(),
+				},
+			};
+			Collaterals::<T>::insert(asset, collateral);
+			Ok(())
+		}
+
+		/// Accrues the stability fee on the debt minted against the collateral of `asset` since
+		/// the last accrual, minting the fee to the `TreasuryAccount` so that the supply of the
+		/// stablecoin matches the debt of the vaults. Returns the collateral updated.
+		fn accrue_stability_fee(
+			asset: AssetIdOf<T>,
+		) -> Result<Collateral<BalanceOf<T>>, DispatchError> {
+			let mut collateral =
+				Collaterals::<T>::get(asset).ok_or(Error::<T>::CollateralNotAllowed)?;
+			let now = Self::now_in_seconds();
+			if collateral.last_accrued_at < now {
+				let debt =
+					math::mul_rate(collateral.scaled_debt, collateral.debt_index, Rounding::Down)
+						.ok_or(Error::<T>::OverflowError)?;
+				let growth = math::compounded_interest(
+					collateral.stability_fee,
+					now - collateral.last_accrued_at,
+				)
+				.ok_or(Error::<T>::OverflowError)?;
+				collateral.debt_index =
+					collateral.debt_index.checked_mul(&growth).ok_or(Error::<T>::OverflowError)?;
+				collateral.last_accrued_at = now;
+
+				let fee =
+					math::mul_rate(collateral.scaled_debt, collateral.debt_index, Rounding::Down)
+						.ok_or(Error::<T>::OverflowError)?
+						.saturating_sub(debt);
+				if !fee.is_zero() {
+					T::Fungibles::mint_into(
+						T::StableAsset::get(),
+						&T::TreasuryAccount::get(),
+						fee,
+					)?;
+				}
+				Collaterals::<T>::insert(asset, &collateral);
+			}
+			Ok(collateral)
+		}
+
+		/// Returns the debt of `vault` in the stablecoin and the value of its collateral, i.e.
+		/// the assets its LP tokens redeem for, in the stablecoin
+		fn vault_debt_and_value(
+			asset: AssetIdOf<T>,
+			collateral: &Collateral<BalanceOf<T>>,
+			vault: &Vault<BalanceOf<T>>,
+		) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+			let debt = math::mul_rate(vault.scaled_debt, collateral.debt_index, Rounding::Up)
+				.ok_or(Error::<T>::OverflowError)?;
+			let redeemable = T::Lending::redeemable_balance(asset, vault.collateral)?;
+			let value = T::Lending::equivalent_balance(asset, redeemable, T::StableAsset::get())?;
+			Ok((debt, value))
+		}
+
+		/// Ensures that the debt of `vault` does not exceed the collateral factor of the value of
+		/// its collateral
+		fn ensure_vault_collateralized(
+			asset: AssetIdOf<T>,
+			collateral: &Collateral<BalanceOf<T>>,
+			vault: &Vault<BalanceOf<T>>,
+		) -> DispatchResult {
+			let (debt, value) = Self::vault_debt_and_value(asset, collateral, vault)?;
+			ensure!(
+				debt <= collateral.collateral_factor.mul_floor(value),
+				Error::<T>::NotEnoughCollateral
+			);
+			Ok(())
+		}
+
+		/// Returns the shortfall of the vault of `who` against the collateral of `asset`, that is
+		/// the part of its debt not covered by its collateral weighted with the liquidation
+		/// threshold. The vault is healthy when the shortfall is zero.
+		pub fn vault_shortfall(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let collateral = Self::accrue_stability_fee(asset)?;
+			let vault = Vaults::<T>::get(who, asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+			let (debt, value) = Self::vault_debt_and_value(asset, &collateral, &vault)?;
+			Ok(debt.saturating_sub(collateral.liquidation_threshold.mul_floor(value)))
+		}
+
+		/// Locks `balance` of the LP tokens of the lending pool of `asset` of `who` in its vault
+		pub fn do_lock_collateral(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+			ensure!(Collaterals::<T>::contains_key(asset), Error::<T>::CollateralNotAllowed);
+			T::Lending::transfer_lp_tokens(who, &Self::account_id(), asset, balance)?;
+			Vaults::<T>::try_mutate(who, asset, |vault| -> DispatchResult {
+				let vault = vault.get_or_insert_with(Default::default);
+				vault.collateral = vault.collateral.ensure_add(balance)?;
+				Ok(())
+			})
+		}
+
+		/// Unlocks `balance` of the LP tokens locked in the vault of `who` against the collateral
+		/// of `asset`, as long as the vault remains collateralized. The vault is closed once
+		/// empty.
+		pub fn do_unlock_collateral(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let collateral = Self::accrue_stability_fee(asset)?;
+			let mut vault = Vaults::<T>::get(who, asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+			vault.collateral = vault
+				.collateral
+				.checked_sub(&balance)
+				.ok_or(Error::<T>::InsufficientCollateral)?;
+			Self::ensure_vault_collateralized(asset, &collateral, &vault)?;
+
+			T::Lending::transfer_lp_tokens(&Self::account_id(), who, asset, balance)?;
+			match vault == Default::default() {
+				true => Vaults::<T>::remove(who, asset),
+				false => Vaults::<T>::insert(who, asset, vault),
+			}
+			Ok(())
+		}
+
+		/// Mints `balance` of the stablecoin to `who` against the collateral locked in its vault,
+		/// within the collateral factor of the collateral and its debt ceiling
+		pub fn do_mint_stable(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+			let mut collateral = Self::accrue_stability_fee(asset)?;
+			let mut vault = Vaults::<T>::get(who, asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+
+			let scaled_balance = math::div_rate(balance, collateral.debt_index, Rounding::Up)
+				.ok_or(Error::<T>::OverflowError)?;
+			collateral.scaled_debt = collateral.scaled_debt.ensure_add(scaled_balance)?;
+			let total_debt =
+				math::mul_rate(collateral.scaled_debt, collateral.debt_index, Rounding::Up)
+					.ok_or(Error::<T>::OverflowError)?;
+			ensure!(total_debt <= collateral.debt_ceiling, Error::<T>::DebtCeilingReached);
+			vault.scaled_debt = vault.scaled_debt.ensure_add(scaled_balance)?;
+			Self::ensure_vault_collateralized(asset, &collateral, &vault)?;
+
+			T::Fungibles::mint_into(T::StableAsset::get(), who, balance)?;
+			Collaterals::<T>::insert(asset, collateral);
+			Vaults::<T>::insert(who, asset, vault);
+			Ok(())
+		}
+
+		/// Burns up to `balance` of the stablecoin of `who` to pay the debt of its vault against
+		/// the collateral of `asset` back. Returns the balance burned.
+		pub fn do_burn_stable(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+			let mut collateral = Self::accrue_stability_fee(asset)?;
+			let mut vault = Vaults::<T>::get(who, asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+
+			let debt = math::mul_rate(vault.scaled_debt, collateral.debt_index, Rounding::Up)
+				.ok_or(Error::<T>::OverflowError)?;
+			let balance = balance.min(debt);
+			let scaled_balance = match balance == debt {
+				true => vault.scaled_debt,
+				false => math::div_rate(balance, collateral.debt_index, Rounding::Down)
+					.ok_or(Error::<T>::OverflowError)?,
+			};
+			T::Fungibles::burn_from(
+				T::StableAsset::get(),
+				who,
+				balance,
+				Precision::Exact,
+				Fortitude::Polite,
+			)?;
+			vault.scaled_debt = vault.scaled_debt.saturating_sub(scaled_balance);
+			collateral.scaled_debt = collateral.scaled_debt.saturating_sub(scaled_balance);
+			Collaterals::<T>::insert(asset, collateral);
+			Vaults::<T>::insert(who, asset, vault);
+			Ok(balance)
+		}
+
+		/// Liquidates the vault of `who` against the collateral of `asset` with a shortfall: the
+		/// `liquidator` burns the whole debt in the stablecoin and seizes all the LP tokens
+		/// locked. Returns the debt burned and the LP tokens seized.
+		pub fn do_liquidate_vault(
+			liquidator: &T::AccountId,
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+			ensure!(
+				!Self::vault_shortfall(who, asset)?.is_zero(),
+				Error::<T>::VaultNotLiquidatable
+			);
+			let mut collateral =
+				Collaterals::<T>::get(asset).ok_or(Error::<T>::CollateralNotAllowed)?;
+			let vault = Vaults::<T>::take(who, asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+
+			let debt = math::mul_rate(vault.scaled_debt, collateral.debt_index, Rounding::Up)
+				.ok_or(Error::<T>::OverflowError)?;
+			T::Fungibles::burn_from(
+				T::StableAsset::get(),
+				liquidator,
+				debt,
+				Precision::Exact,
+				Fortitude::Polite,
+			)?;
+			T::Lending::transfer_lp_tokens(
+				&Self::account_id(),
+				liquidator,
+				asset,
+				vault.collateral,
+			)?;
+			collateral.scaled_debt = collateral.scaled_debt.saturating_sub(vault.scaled_debt);
+			Collaterals::<T>::insert(asset, collateral);
+			Ok((debt, vault.collateral))
+		}
+	}
+
+	/// The assets the CDPs rely on beyond their lending pools: the stablecoin and the underlying
+	/// assets of the collaterals
+	impl<T: Config> Contains<AssetIdOf<T>> for Pallet<T> {
+		fn contains(asset: &AssetIdOf<T>) -> bool {
+			*asset == T::StableAsset::get() || Collaterals::<T>::contains_key(asset)
+		}
+	}
+}
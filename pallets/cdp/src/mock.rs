@@ -0,0 +1,275 @@
+use crate as cdp;
+use frame_support::{
+	derive_impl, parameter_types,
+	traits::{
+		fungibles::{Inspect, Mutate},
+		tokens::Preservation,
+		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64,
+	},
+	weights::constants::RocksDbWeight,
+	PalletId,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use lending::Lending;
+use sp_core::H256;
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup, One},
+	BuildStorage, DispatchError, DispatchResult, FixedPointNumber, FixedU128,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u128;
+
+pub const ALICE: u128 = 1;
+pub const BOB: u128 = 2;
+pub const TREASURY: u128 = 99;
+
+/// The account holding the assets supplied to the `MockLending`
+pub const LENDING_ACCOUNT: u128 = 1_000;
+
+pub const DOT: u32 = 1;
+pub const KUSD: u32 = 30;
+/// The LP token of the lending pool of DOT
+pub const DOT_LP: u32 = 101;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		Assets: pallet_assets,
+		Timestamp: pallet_timestamp,
+		Cdp: cdp,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = RocksDbWeight;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Nonce = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u128;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Block = Block;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type Balance = Balance;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+	type ReserveIdentifier = [u8; 8];
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type FreezeIdentifier = ();
+	type MaxLocks = ConstU32<10>;
+	type MaxReserves = ();
+	type MaxHolds = ConstU32<10>;
+	type MaxFreezes = ConstU32<10>;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<1>;
+	type WeightInfo = ();
+}
+
+impl pallet_assets::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type RemoveItemsLimit = ConstU32<1000>;
+	type AssetId = u32;
+	type AssetIdParameter = codec::Compact<u32>;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<Self::AccountId>>;
+	type ForceOrigin = EnsureRoot<Self::AccountId>;
+	type AssetDeposit = ConstU128<100>;
+	type AssetAccountDeposit = ConstU128<1>;
+	type MetadataDepositBase = ConstU128<10>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type CallbackHandle = ();
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+parameter_types! {
+	pub const CdpPalletId: PalletId = PalletId(*b"kylixcdp");
+	pub const TreasuryAccount: u128 = TREASURY;
+}
+
+impl cdp::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = CdpPalletId;
+	type AssetId = u32;
+	type Fungibles = Assets;
+	type Lending = MockLending;
+	type StableAsset = ConstU32<KUSD>;
+	type TreasuryAccount = TreasuryAccount;
+	type Time = Timestamp;
+	type ManagerOrigin = EnsureRoot<u128>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+thread_local! {
+	static POOLS: RefCell<BTreeMap<u32, u32>> = RefCell::new(BTreeMap::new());
+	static PRICES: RefCell<BTreeMap<(u32, u32), FixedU128>> = RefCell::new(BTreeMap::new());
+	static SUPPLY_INDEX: RefCell<FixedU128> = RefCell::new(FixedU128::one());
+}
+
+/// Models the money market of the lending pallet: the LP tokens of its lending pools are minted
+/// one for one against the assets supplied, and redeem for the assets at the supply index. The
+/// assets are valued at the prices set by the tests.
+pub struct MockLending;
+impl MockLending {
+	/// Creates the lending pool of `asset`, whose LP token is `lp_token`
+	pub fn create_pool(lp_token: u32, asset: u32) {
+		create_asset(lp_token);
+		POOLS.with(|pools| pools.borrow_mut().insert(asset, lp_token));
+	}
+
+	/// Sets the price of `asset` in `quote`
+	pub fn set_price(asset: u32, quote: u32, price: FixedU128) {
+		PRICES.with(|prices| prices.borrow_mut().insert((asset, quote), price));
+	}
+
+	/// Sets the index the LP tokens of all the lending pools redeem at
+	pub fn set_supply_index(index: FixedU128) {
+		SUPPLY_INDEX.with(|supply_index| *supply_index.borrow_mut() = index);
+	}
+
+	fn reset() {
+		POOLS.with(|pools| pools.borrow_mut().clear());
+		PRICES.with(|prices| prices.borrow_mut().clear());
+		Self::set_supply_index(FixedU128::one());
+	}
+}
+impl Lending<u128, u32, Balance> for MockLending {
+	fn supply(who: &u128, asset: u32, balance: Balance) -> DispatchResult {
+		let lp_token = Self::lp_token(asset).ok_or(DispatchError::Other("no lending pool"))?;
+		<Assets as Mutate<u128>>::transfer(
+			asset,
+			who,
+			&LENDING_ACCOUNT,
+			balance,
+			Preservation::Expendable,
+		)?;
+		<Assets as Mutate<u128>>::mint_into(lp_token, who, balance)?;
+		Ok(())
+	}
+
+	fn withdraw(_: &u128, _: u32, _: Balance) -> Result<Balance, DispatchError> {
+		Err(DispatchError::Other("not supported by the mock"))
+	}
+
+	fn borrow(_: &u128, _: u32, _: Balance, _: u32, _: Balance) -> Result<Balance, DispatchError> {
+		Err(DispatchError::Other("not supported by the mock"))
+	}
+
+	fn repay_for(_: &u128, _: &u128, _: u32, _: Balance, _: u32) -> DispatchResult {
+		Err(DispatchError::Other("not supported by the mock"))
+	}
+
+	fn collateral_value(_: &u128, _: u32, _: u32) -> Result<Balance, DispatchError> {
+		Err(DispatchError::Other("not supported by the mock"))
+	}
+
+	fn lp_token(asset: u32) -> Option<u32> {
+		POOLS.with(|pools| pools.borrow().get(&asset).copied())
+	}
+
+	fn redeemable_balance(asset: u32, lp_tokens: Balance) -> Result<Balance, DispatchError> {
+		Self::lp_token(asset).ok_or(DispatchError::Other("no lending pool"))?;
+		Ok(SUPPLY_INDEX.with(|index| index.borrow().saturating_mul_int(lp_tokens)))
+	}
+
+	fn transfer_lp_tokens(who: &u128, dest: &u128, asset: u32, balance: Balance) -> DispatchResult {
+		let lp_token = Self::lp_token(asset).ok_or(DispatchError::Other("no lending pool"))?;
+		<Assets as Mutate<u128>>::transfer(lp_token, who, dest, balance, Preservation::Expendable)?;
+		Ok(())
+	}
+
+	fn equivalent_balance(
+		asset: u32,
+		balance: Balance,
+		quote: u32,
+	) -> Result<Balance, DispatchError> {
+		if asset == quote {
+			return Ok(balance);
+		}
+		let price = PRICES
+			.with(|prices| prices.borrow().get(&(asset, quote)).copied())
+			.ok_or(DispatchError::Other("no price"))?;
+		Ok(price.saturating_mul_int(balance))
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl lending::LendingBenchmarkHelper<u32> for MockLending {
+	fn create_asset(asset: u32) {
+		create_asset(asset);
+	}
+
+	fn create_pool(lp_token: u32, asset: u32) {
+		create_asset(asset);
+		MockLending::create_pool(lp_token, asset);
+	}
+
+	fn set_price(asset: u32, quote: u32, price: FixedU128) {
+		MockLending::set_price(asset, quote, price);
+	}
+}
+
+/// Creates `asset` if needed
+pub fn create_asset(asset: u32) {
+	if !<Assets as Inspect<u128>>::asset_exists(asset) {
+		frame_support::assert_ok!(Assets::force_create(
+			RuntimeOrigin::root(),
+			asset.into(),
+			ALICE,
+			true,
+			1
+		));
+	}
+}
+
+/// Returns the balance of `asset` of `who`
+pub fn balance(asset: u32, who: u128) -> Balance {
+	<Assets as Inspect<u128>>::balance(asset, &who)
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	MockLending::reset();
+	let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
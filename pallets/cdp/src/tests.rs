@@ -0,0 +1,160 @@
+use crate::{mock::*, Collaterals, Error, Event, Vaults};
+use frame_support::{assert_noop, assert_ok, traits::fungibles::Mutate};
+use lending::{Lending, SECONDS_PER_YEAR};
+use sp_runtime::{traits::Zero, FixedPointNumber, FixedU128, Permill};
+
+// Creates DOT, its lending pool and kUSD, and supplies 500_000 DOT of ALICE, valued at par
+// with kUSD
+fn setup_supply() {
+	create_asset(DOT);
+	create_asset(KUSD);
+	assert_ok!(Assets::mint_into(DOT, &ALICE, 1_000_000));
+	MockLending::create_pool(DOT_LP, DOT);
+	MockLending::set_price(DOT, KUSD, FixedU128::from(1));
+	assert_ok!(MockLending::supply(&ALICE, DOT, 500_000));
+}
+
+fn set_collateral(debt_ceiling: u128) {
+	assert_ok!(Cdp::set_collateral(
+		RuntimeOrigin::root(),
+		DOT,
+		Permill::from_percent(50),
+		Permill::from_percent(80),
+		FixedU128::from_rational(5, 100),
+		debt_ceiling
+	));
+}
+
+#[test]
+fn stablecoin_is_minted_against_the_locked_lp_tokens_and_liquidated() {
+	new_test_ext().execute_with(|| {
+		setup_supply();
+		assert_noop!(
+			Cdp::lock_collateral(RuntimeOrigin::signed(ALICE), DOT, 400_000),
+			Error::<Test>::CollateralNotAllowed
+		);
+		assert_noop!(
+			Cdp::set_collateral(
+				RuntimeOrigin::root(),
+				DOT,
+				Permill::from_percent(90),
+				Permill::from_percent(80),
+				FixedU128::from_rational(5, 100),
+				150_000
+			),
+			Error::<Test>::InvalidRiskParameters
+		);
+		assert_noop!(
+			Cdp::set_collateral(
+				RuntimeOrigin::root(),
+				KUSD,
+				Permill::from_percent(50),
+				Permill::from_percent(80),
+				FixedU128::from_rational(5, 100),
+				150_000
+			),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		set_collateral(200_000);
+
+		// ALICE locks 400_000 kTokens worth 400_000 kUSD
+		assert_ok!(Cdp::lock_collateral(RuntimeOrigin::signed(ALICE), DOT, 400_000));
+		assert_eq!(balance(DOT_LP, Cdp::account_id()), 400_000);
+		assert_noop!(
+			Cdp::mint_stable(RuntimeOrigin::signed(ALICE), DOT, 210_000),
+			Error::<Test>::DebtCeilingReached
+		);
+		assert_ok!(Cdp::mint_stable(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		System::assert_last_event(
+			Event::StableMinted { who: ALICE, asset: DOT, balance: 100_000 }.into(),
+		);
+		assert_eq!(balance(KUSD, ALICE), 100_000);
+
+		// the debt is kept within half of the value of the collateral
+		assert_noop!(
+			Cdp::unlock_collateral(RuntimeOrigin::signed(ALICE), DOT, 300_000),
+			Error::<Test>::NotEnoughCollateral
+		);
+		assert_ok!(Cdp::unlock_collateral(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_noop!(
+			Cdp::mint_stable(RuntimeOrigin::signed(ALICE), DOT, 50_001),
+			Error::<Test>::NotEnoughCollateral
+		);
+		assert_ok!(Cdp::burn_stable(RuntimeOrigin::signed(ALICE), DOT, 40_000));
+		assert_eq!(balance(KUSD, ALICE), 60_000);
+		assert_noop!(
+			Cdp::liquidate_vault(RuntimeOrigin::signed(BOB), ALICE, DOT),
+			Error::<Test>::VaultNotLiquidatable
+		);
+
+		// a year of stability fee is minted to the treasury, then DOT falls to 0.25 kUSD
+		Timestamp::set_timestamp(SECONDS_PER_YEAR * 1_000);
+		MockLending::set_price(DOT, KUSD, FixedU128::from_rational(1, 4));
+		// 75_000 kUSD of collateral covers 60_000 of the debt, the rest is shortfall
+		let shortfall = Cdp::vault_shortfall(&ALICE, DOT).unwrap();
+		let fee = balance(KUSD, TREASURY);
+		assert!(fee > 3_000 && fee < 3_100);
+		let debt = 60_000 + shortfall;
+		assert!(debt == 60_000 + fee || debt == 60_000 + fee + 1);
+
+		// BOB burns the whole debt and seizes all the kTokens
+		assert_ok!(Assets::mint_into(KUSD, &BOB, 100_000));
+		assert_ok!(Cdp::liquidate_vault(RuntimeOrigin::signed(BOB), ALICE, DOT));
+		System::assert_last_event(
+			Event::VaultLiquidated {
+				who: BOB,
+				owner: ALICE,
+				asset: DOT,
+				debt,
+				collateral: 300_000,
+			}
+			.into(),
+		);
+		assert_eq!(balance(KUSD, BOB), 100_000 - debt);
+		assert_eq!(balance(DOT_LP, BOB), 300_000);
+		assert!(Vaults::<Test>::get(ALICE, DOT).is_none());
+		assert!(Collaterals::<Test>::get(DOT).unwrap().scaled_debt.is_zero());
+	});
+}
+
+#[test]
+fn locked_lp_tokens_are_valued_at_the_assets_they_redeem_for() {
+	new_test_ext().execute_with(|| {
+		setup_supply();
+		set_collateral(1_000_000);
+		assert_noop!(
+			Cdp::lock_collateral(RuntimeOrigin::signed(ALICE), DOT, 0),
+			Error::<Test>::AmountTooLow
+		);
+		assert_ok!(Cdp::lock_collateral(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_noop!(
+			Cdp::mint_stable(RuntimeOrigin::signed(ALICE), DOT, 50_001),
+			Error::<Test>::NotEnoughCollateral
+		);
+
+		// the interest earned by the locked LP tokens backs more of the stablecoin
+		MockLending::set_supply_index(FixedU128::from_rational(3, 2));
+		assert_ok!(Cdp::mint_stable(RuntimeOrigin::signed(ALICE), DOT, 75_000));
+		assert_noop!(
+			Cdp::unlock_collateral(RuntimeOrigin::signed(ALICE), DOT, 1),
+			Error::<Test>::NotEnoughCollateral
+		);
+		assert_noop!(
+			Cdp::unlock_collateral(RuntimeOrigin::signed(BOB), DOT, 1),
+			Error::<Test>::VaultDoesNotExist
+		);
+
+		// the vault is closed once repaid and emptied
+		assert_ok!(Cdp::burn_stable(RuntimeOrigin::signed(ALICE), DOT, 1_000_000));
+		System::assert_last_event(
+			Event::StableBurned { who: ALICE, asset: DOT, balance: 75_000 }.into(),
+		);
+		assert_noop!(
+			Cdp::unlock_collateral(RuntimeOrigin::signed(ALICE), DOT, 100_001),
+			Error::<Test>::InsufficientCollateral
+		);
+		assert_ok!(Cdp::unlock_collateral(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert!(Vaults::<Test>::get(ALICE, DOT).is_none());
+		assert_eq!(balance(DOT_LP, ALICE), 500_000);
+	});
+}
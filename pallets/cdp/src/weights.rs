@@ -0,0 +1,201 @@
+//! Weights for the cdp pallet
+//!
+//! The weights are estimated from the storage accesses of the benchmarks in `benchmarking.rs`
+//! until they are generated on the reference hardware with the command below. The proof sizes
+//! allow 3_600 bytes per storage read, the size of the proof of an entry of an `Assets` account.
+
+// Command:
+// ./target/release/kylix-node
+// benchmark
+// pallet
+// --chain
+// dev
+// --pallet
+// cdp
+// --extrinsic
+// *
+// --steps=50
+// --repeat=20
+// --wasm-execution=compiled
+// --output
+// pallets/cdp/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for cdp.
+pub trait WeightInfo {
+	fn set_collateral() -> Weight;
+	fn lock_collateral() -> Weight;
+	fn unlock_collateral() -> Weight;
+	fn mint_stable() -> Weight;
+	fn burn_stable() -> Weight;
+	fn liquidate_vault() -> Weight;
+}
+
+/// Weights for cdp using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Cdp Collaterals (r:2 w:2)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:1 w:1)
+	fn set_collateral() -> Weight {
+		Weight::from_parts(30_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending KTokenFreezes (r:1 w:0)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Lending KTokenCheckpoints (r:2 w:2)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	fn lock_collateral() -> Weight {
+		Weight::from_parts(70_000_000, 46_800)
+			.saturating_add(T::DbWeight::get().reads(13_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:1)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Lending KTokenFreezes (r:1 w:0)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Lending KTokenCheckpoints (r:2 w:2)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	fn unlock_collateral() -> Weight {
+		Weight::from_parts(90_000_000, 64_800)
+			.saturating_add(T::DbWeight::get().reads(18_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:2)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn mint_stable() -> Weight {
+		Weight::from_parts(60_000_000, 32_400)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:2)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn burn_stable() -> Weight {
+		Weight::from_parts(40_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Cdp Collaterals (r:2 w:2)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Lending KTokenFreezes (r:1 w:0)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Lending KTokenCheckpoints (r:2 w:2)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn liquidate_vault() -> Weight {
+		Weight::from_parts(105_000_000, 72_000)
+			.saturating_add(T::DbWeight::get().reads(20_u64))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Cdp Collaterals (r:2 w:2)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:1 w:1)
+	fn set_collateral() -> Weight {
+		Weight::from_parts(30_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending KTokenFreezes (r:1 w:0)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Lending KTokenCheckpoints (r:2 w:2)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	fn lock_collateral() -> Weight {
+		Weight::from_parts(70_000_000, 46_800)
+			.saturating_add(RocksDbWeight::get().reads(13_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:1)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Lending KTokenFreezes (r:1 w:0)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Lending KTokenCheckpoints (r:2 w:2)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	fn unlock_collateral() -> Weight {
+		Weight::from_parts(90_000_000, 64_800)
+			.saturating_add(RocksDbWeight::get().reads(18_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:2)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn mint_stable() -> Weight {
+		Weight::from_parts(60_000_000, 32_400)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Cdp Collaterals (r:1 w:2)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn burn_stable() -> Weight {
+		Weight::from_parts(40_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Cdp Collaterals (r:2 w:2)
+	/// Storage: Cdp Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Lending KTokenFreezes (r:1 w:0)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Lending KTokenCheckpoints (r:2 w:2)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn liquidate_vault() -> Weight {
+		Weight::from_parts(105_000_000, 72_000)
+			.saturating_add(RocksDbWeight::get().reads(20_u64))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+	}
+}
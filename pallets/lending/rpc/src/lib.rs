@@ -0,0 +1,115 @@
+//! JSON-RPC methods of the lending pallet, wrapping the `LendingApi` runtime API for the
+//! clients which do not speak runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+pub use lending_runtime_api::LendingApi as LendingRuntimeApi;
+use lending_runtime_api::{AccountOverview, PoolInfo, ProtocolTotals};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+
+#[rpc(client, server)]
+pub trait LendingApi<BlockHash, AccountId, AssetId, Balance> {
+	/// Returns the statistics of all the lending pools.
+	#[method(name = "lending_getPools")]
+	fn pools(&self, at: Option<BlockHash>) -> RpcResult<Vec<PoolInfo<AssetId, Balance>>>;
+
+	/// Returns the totals of all the lending pools valued in the base asset.
+	#[method(name = "lending_getProtocolStats")]
+	fn protocol_stats(&self, at: Option<BlockHash>) -> RpcResult<ProtocolTotals<Balance>>;
+
+	/// Returns the positions of `who` valued with the asset prices, its health factor and
+	/// remaining borrow power.
+	#[method(name = "lending_getAccount")]
+	fn account(
+		&self,
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<AccountOverview<AssetId, Balance>>;
+}
+
+/// Provides the RPC methods to query the lending pools.
+pub struct Lending<C, P> {
+	client: Arc<C>,
+	_marker: PhantomData<P>,
+}
+
+impl<C, P> Lending<C, P> {
+	/// Creates a new instance of the lending RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error(message: &'static str, e: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(
+		Error::RuntimeError.into(),
+		message,
+		Some(format!("{:?}", e)),
+	))
+	.into()
+}
+
+#[async_trait]
+impl<C, Block, AccountId, AssetId, Balance>
+	LendingApiServer<<Block as BlockT>::Hash, AccountId, AssetId, Balance> for Lending<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: LendingRuntimeApi<Block, AccountId, AssetId, Balance>,
+	AccountId: Codec + Send + Sync + 'static,
+	AssetId: Codec + Send + Sync + 'static,
+	Balance: Codec + Send + Sync + 'static,
+{
+	fn pools(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<PoolInfo<AssetId, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		api.pools(at)
+			.map_err(|e| runtime_error("Unable to query the lending pools.", e))
+	}
+
+	fn protocol_stats(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<ProtocolTotals<Balance>> {
+		let api = self.client.runtime_api();
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		api.protocol_stats(at)
+			.map_err(|e| runtime_error("Unable to query the protocol statistics.", e))
+	}
+
+	fn account(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<AccountOverview<AssetId, Balance>> {
+		let api = self.client.runtime_api();
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		api.account_overview(at, who)
+			.map_err(|e| runtime_error("Unable to query the account overview.", e))
+	}
+}
@@ -0,0 +1,73 @@
+//! Runtime API definition of the lending pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+pub use lending::{
+	AccountOverview, AccountSnapshot, Action, BorrowPosition, MaxBorrow, Page, PoolInfo,
+	ProtocolTotals, Simulation, SupplyPosition,
+};
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Queries of the lending pools, computed with the protocol math of the pallet.
+	pub trait LendingApi<AccountId, AssetId, Balance>
+	where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// Returns the statistics of the lending pool of `asset`, if it exists.
+		fn pool(asset: AssetId) -> Option<PoolInfo<AssetId, Balance>>;
+
+		/// Returns the statistics of all the lending pools.
+		fn pools() -> Vec<PoolInfo<AssetId, Balance>>;
+
+		/// Returns a page of up to `limit` lending pools after the pool of `start_after`, or from
+		/// the first pool if `None`, with the cursor of the next page.
+		fn pools_page(
+			start_after: Option<AssetId>,
+			limit: u32,
+		) -> Page<PoolInfo<AssetId, Balance>, AssetId>;
+
+		/// Returns a page of up to `limit` supplied positions of all the accounts after the
+		/// position `start_after`, or from the first position if `None`.
+		fn supply_positions_page(
+			start_after: Option<(AccountId, AssetId)>,
+			limit: u32,
+		) -> Page<(AccountId, SupplyPosition<AssetId, Balance>), (AccountId, AssetId)>;
+
+		/// Returns a page of up to `limit` borrowed positions of all the accounts after the
+		/// position `start_after`, or from the first position if `None`.
+		fn borrow_positions_page(
+			start_after: Option<(AccountId, AssetId, AssetId)>,
+			limit: u32,
+		) -> Page<(AccountId, BorrowPosition<AssetId, Balance>), (AccountId, AssetId, AssetId)>;
+
+		/// Returns the totals of all the lending pools valued in the base asset.
+		fn protocol_stats() -> ProtocolTotals<Balance>;
+
+		/// Returns the supplied and borrowed positions of `who` valued with the asset prices,
+		/// its health factor and remaining borrow power.
+		fn account_overview(who: AccountId) -> AccountOverview<AssetId, Balance>;
+
+		/// Returns the kTokens of `who` in the lending pool of `asset`, the repayable balance of
+		/// its borrows of `asset` and the exchange rate of the kTokens.
+		fn account_snapshot(
+			who: AccountId,
+			asset: AssetId,
+		) -> Result<AccountSnapshot<Balance>, DispatchError>;
+
+		/// Evaluates the `action` of `who` against the current state without dispatching it, and
+		/// returns its resulting health factor and the new state of the lending pool.
+		fn simulate(
+			who: AccountId,
+			action: Action<AssetId, Balance>,
+		) -> Result<Simulation<AssetId, Balance>, DispatchError>;
+
+		/// Returns the largest balance of `asset` `who` can borrow in a single borrow, with the
+		/// collateral to post for it, or `None` if it can borrow nothing.
+		fn max_borrowable(who: AccountId, asset: AssetId) -> Option<MaxBorrow<AssetId, Balance>>;
+	}
+}
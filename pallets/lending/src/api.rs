@@ -0,0 +1,523 @@
+use crate::*;
+use frame_support::{
+	sp_std::{collections::btree_set::BTreeSet, vec::Vec},
+	storage::{with_transaction, TransactionOutcome},
+};
+
+/// The statistics of a lending pool, as returned by the `LendingApi` runtime API
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct PoolInfo<AssetId, Balance> {
+	pub asset: AssetId,             // the underlying asset of the lending pool
+	pub id: AssetId,                // the lending pool id, the asset id of its kTokens
+	pub activated: bool,            // is the pool active or in pending state?
+	pub utilisation: Ratio,         // borrowed / (borrowed + reserve)
+	pub average_utilisation: Ratio, // the utilisation averaged over the UtilisationAveragePeriod
+	pub supply_apy: Rate,           // the annual supply interest rate
+	pub borrow_apy: Rate,           // the annual borrow interest rate
+	pub total_supplied: Balance,    // reserve + borrowed
+	pub total_borrowed: Balance,    // the borrowed principal
+	pub reserve_balance: Balance,   // the liquidity available in the pool
+	pub reserve_factor: Ratio,      // the share of the interest kept by the protocol
+	pub exchange_rate: Rate,        // the underlying assets per kToken, i.e. the supply index
+	pub collateral_factor: Ratio,   // the borrowing power of the supplied assets
+	pub borrow_fee: Ratio,          // the origination fee charged on borrows
+}
+
+/// A supplied position of an account, as returned by the `LendingApi` runtime API
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct SupplyPosition<AssetId, Balance> {
+	pub asset: AssetId,         // the underlying asset of the lending pool
+	pub balance: Balance,       // the supplied balance including the earned interest
+	pub value: Option<Balance>, // the value of the balance, if the asset has a price
+}
+
+/// A borrowed position of an account, as returned by the `LendingApi` runtime API
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct BorrowPosition<AssetId, Balance> {
+	pub asset: AssetId,
+	pub collateral_asset: AssetId,
+	pub balance: Balance, // the repayable balance including the interest
+	pub collateral_balance: Balance,
+	pub value: Option<Balance>,
+	pub collateral_value: Option<Balance>,
+}
+
+/// The position of an account in a lending pool, as returned by `get_account_snapshot`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AccountSnapshot<Balance> {
+	pub k_token_balance: Balance, // the kTokens of the account
+	pub borrow_balance: Balance,  // the repayable balance of its borrows, including the interest
+	pub exchange_rate: Rate,      // the underlying assets per kToken, i.e. the supply index
+}
+
+/// The positions and the health of an account, as returned by the `LendingApi` runtime API
+///
+/// The values are expressed in the pricing unit of the asset prices, the `BaseAsset`. Positions
+/// without a price are left out of the totals.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AccountOverview<AssetId, Balance> {
+	pub supplied: Vec<SupplyPosition<AssetId, Balance>>,
+	pub borrowed: Vec<BorrowPosition<AssetId, Balance>>,
+	pub total_supplied_value: Balance,
+	pub total_borrowed_value: Balance,
+	pub total_collateral_value: Balance,
+	// sum(collateral_value * liquidation_threshold) / total_borrowed_value, None without debt
+	pub health_factor: Option<Rate>,
+	// sum(collateral_value * collateral_factor) - total_borrowed_value
+	pub borrow_power: Balance,
+}
+
+/// A page of the lending pools or positions, as returned by the paged `LendingApi` runtime APIs
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct Page<Item, Cursor> {
+	pub items: Vec<Item>,
+	// the key to query the next page after, `None` on the last page
+	pub next: Option<Cursor>,
+}
+
+/// The maximum number of entries read for a page
+const MAX_PAGE_SIZE: u32 = 1_000;
+
+/// Reads up to `limit` keys, capped to `MAX_PAGE_SIZE`, into a page of the items `item` returns
+/// for them. The keys without an item, e.g. the emptied positions, are skipped but still count
+/// towards the limit.
+fn paged<K: Clone, Item>(
+	mut keys: impl Iterator<Item = K>,
+	limit: u32,
+	item: impl Fn(&K) -> Option<Item>,
+) -> Page<Item, K> {
+	let mut items = Vec::new();
+	let mut last = None;
+	for key in keys.by_ref().take(limit.clamp(1, MAX_PAGE_SIZE) as usize) {
+		items.extend(item(&key));
+		last = Some(key);
+	}
+	Page { items, next: keys.next().and(last) }
+}
+
+/// The largest borrow of an account in a lending pool, as returned by the `LendingApi` runtime
+/// API, with the collateral to post for it
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct MaxBorrow<AssetId, Balance> {
+	pub balance: Balance,            // the balance to borrow
+	pub collateral_asset: AssetId,   // the asset to post as collateral
+	pub collateral_balance: Balance, // the whole balance of the collateral asset of the account
+}
+
+/// The share of the borrow power left unused by the `max_borrowable` quotes, so that they stay
+/// valid while the prices move and the interest accrues until the borrow is included
+const MAX_BORROW_MARGIN: Ratio = Permill::from_percent(1);
+
+/// A hypothetical action of an account, evaluated by the `simulate` runtime API
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum Action<AssetId, Balance> {
+	Supply {
+		asset: AssetId,
+		balance: Balance,
+	},
+	Withdraw {
+		asset: AssetId,
+		balance: Balance,
+	},
+	Borrow {
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+		collateral_balance: Balance,
+	},
+	Repay {
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+	},
+}
+
+/// The state after a hypothetical action, as returned by the `simulate` runtime API
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct Simulation<AssetId, Balance> {
+	pub health_factor: Option<Rate>, // the health factor of the account
+	pub borrow_power: Balance,       // the remaining borrow power of the account
+	pub pool: PoolInfo<AssetId, Balance>, // the new rates and balances of the lending pool
+}
+
+impl<T: Config> Pallet<T> {
+	/// Returns the statistics of the lending pool of `asset` with the interest accrued up to
+	/// now, or `None` if the pool does not exist.
+	pub fn pool_info(asset: AssetIdOf<T>) -> Option<PoolInfo<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		let mut pool = LendingPoolStorage::<T>::try_get(AssetPool::<T>::from(asset)).ok()?;
+		pool.update_indexes().ok()?;
+
+		Some(PoolInfo {
+			asset,
+			id: pool.id,
+			activated: pool.activated,
+			utilisation: pool.utilisation_ratio().ok()?,
+			average_utilisation: pool.average_utilisation,
+			supply_apy: pool.supply_interest_rate().ok()?,
+			borrow_apy: pool.borrow_interest_rate().ok()?,
+			total_supplied: pool.reserve_balance.checked_add(&pool.borrowed_balance)?,
+			total_borrowed: pool.borrowed_balance,
+			reserve_balance: pool.reserve_balance,
+			reserve_factor: pool.reserve_factor(),
+			exchange_rate: pool.supply_index,
+			collateral_factor: pool.collateral_factor(),
+			borrow_fee: pool.borrow_fee,
+		})
+	}
+
+	/// Returns the statistics of all the lending pools.
+	pub fn pools_info() -> Vec<PoolInfo<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		LendingPoolStorage::<T>::iter_values()
+			.filter_map(|pool| Self::pool_info(pool.lend_token_id))
+			.collect()
+	}
+
+	/// Returns a page of up to `limit` lending pools after the pool of `start_after`, or from
+	/// the first pool if `None`, so that the pools can be synced without a single huge response.
+	pub fn pools_page(
+		start_after: Option<AssetIdOf<T>>,
+		limit: u32,
+	) -> Page<PoolInfo<AssetIdOf<T>, AssetBalanceOf<T>>, AssetIdOf<T>> {
+		let pools = match start_after {
+			Some(asset) => LendingPoolStorage::<T>::iter_from(
+				LendingPoolStorage::<T>::hashed_key_for(AssetPool::<T>::from(asset)),
+			),
+			None => LendingPoolStorage::<T>::iter(),
+		};
+		paged(pools.map(|(_, pool)| pool.lend_token_id), limit, |asset| Self::pool_info(*asset))
+	}
+
+	/// Returns a page of up to `limit` supplied positions of all the accounts after the position
+	/// `start_after`, or from the first position if `None`.
+	pub fn supply_positions_page(
+		start_after: Option<(T::AccountId, AssetIdOf<T>)>,
+		limit: u32,
+	) -> Page<
+		(T::AccountId, SupplyPosition<AssetIdOf<T>, AssetBalanceOf<T>>),
+		(T::AccountId, AssetIdOf<T>),
+	> {
+		let keys = match start_after {
+			Some(key) => SupplyIndexStorage::<T>::iter_keys_from(
+				SupplyIndexStorage::<T>::hashed_key_for(key),
+			),
+			None => SupplyIndexStorage::<T>::iter_keys(),
+		};
+		paged(keys, limit, |(who, asset)| Some((who.clone(), Self::supply_position(who, *asset)?)))
+	}
+
+	/// Returns a page of up to `limit` borrowed positions of all the accounts after the position
+	/// `start_after`, or from the first position if `None`.
+	pub fn borrow_positions_page(
+		start_after: Option<(T::AccountId, AssetIdOf<T>, AssetIdOf<T>)>,
+		limit: u32,
+	) -> Page<
+		(T::AccountId, BorrowPosition<AssetIdOf<T>, AssetBalanceOf<T>>),
+		(T::AccountId, AssetIdOf<T>, AssetIdOf<T>),
+	> {
+		let keys = match start_after {
+			Some(key) => Borrows::<T>::iter_keys_from(Borrows::<T>::hashed_key_for(key)),
+			None => Borrows::<T>::iter_keys(),
+		};
+		paged(keys, limit, |(who, asset, collateral_asset)| {
+			Some((who.clone(), Self::borrow_position(who, *asset, *collateral_asset)?))
+		})
+	}
+
+	/// Returns the totals of all the lending pools valued in the `BaseAsset`, as of the last
+	/// update of each pool.
+	pub fn protocol_stats() -> ProtocolTotals<AssetBalanceOf<T>> {
+		ProtocolStats::<T>::get()
+	}
+
+	/// Returns the value of `balance` of `asset` in the pricing unit, the `BaseAsset`, if the
+	/// asset has a price.
+	pub fn asset_value(
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> Option<AssetBalanceOf<T>> {
+		let base = T::BaseAsset::get();
+		if asset == base {
+			return Some(balance);
+		}
+		let (price, quote) = match AssetPrices::<T>::get((asset, base)) {
+			Some(price) => (price, FixedU128::one()),
+			None => (FixedU128::one(), AssetPrices::<T>::get((base, asset))?),
+		};
+		math::convert(
+			balance,
+			Self::asset_decimals(asset),
+			price,
+			quote,
+			Self::asset_decimals(base),
+			Rounding::Down,
+		)
+	}
+
+	/// Returns the supplied position of `who` in the lending pool of `asset`, if any. Unlike
+	/// `account_overview`, its cost is bounded, so that it can be queried on chain.
+	pub fn supply_position(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+	) -> Option<SupplyPosition<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))?;
+		let scaled_balance = T::Fungibles::balance(pool.id, who);
+		if scaled_balance.is_zero() {
+			return None;
+		}
+		pool.update_indexes().ok()?;
+		let balance = pool.accrued_deposit(scaled_balance).ok()?;
+		Some(SupplyPosition { asset, balance, value: Self::asset_value(asset, balance) })
+	}
+
+	/// Returns the borrowed position of `who` in `asset` against `collateral_asset`, if any.
+	/// Unlike `account_overview`, its cost is bounded, so that it can be queried on chain.
+	pub fn borrow_position(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Option<BorrowPosition<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		let loan = Borrows::<T>::get((who, asset, collateral_asset))?;
+		let mut pool = Self::borrow_pool(who, asset, collateral_asset).ok()?;
+		pool.update_indexes().ok()?;
+		let balance = Self::repayable_balance(who, asset, collateral_asset, &pool, &loan).ok()?;
+		Some(BorrowPosition {
+			asset,
+			collateral_asset,
+			balance,
+			collateral_balance: loan.collateral_balance,
+			value: Self::asset_value(asset, balance),
+			collateral_value: Self::asset_value(collateral_asset, loan.collateral_balance),
+		})
+	}
+
+	/// Returns the kTokens of `who` in the lending pool of `asset`, the repayable balance of
+	/// its borrows of `asset` across its collaterals and the exchange rate of the kTokens, with
+	/// the interest accrued up to now. Its cost is bounded by the `MaxMarketsPerAccount`, so
+	/// that it can be queried on chain.
+	pub fn get_account_snapshot(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+	) -> Result<AccountSnapshot<AssetBalanceOf<T>>, DispatchError> {
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+
+		let mut borrow_balance = AssetBalanceOf::<T>::zero();
+		for (_, collateral_asset) in AccountBorrows::<T>::get(who)
+			.into_iter()
+			.filter(|(borrowed, _)| *borrowed == asset)
+		{
+			let Some(loan) = Borrows::<T>::get((who, asset, collateral_asset)) else { continue };
+			// the borrows owed to a retired pool accrue at its own borrow index
+			let balance = if RetiredBorrows::<T>::contains_key((who, asset, collateral_asset)) {
+				let mut retired = Self::borrow_pool(who, asset, collateral_asset)?;
+				retired.update_indexes()?;
+				Self::repayable_balance(who, asset, collateral_asset, &retired, &loan)?
+			} else {
+				Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?
+			};
+			borrow_balance =
+				borrow_balance.checked_add(&balance).ok_or(Error::<T>::OverflowError)?;
+		}
+
+		Ok(AccountSnapshot {
+			k_token_balance: T::Fungibles::balance(pool.id, who),
+			borrow_balance,
+			exchange_rate: pool.supply_index,
+		})
+	}
+
+	/// Returns the supplied and borrowed positions of `who`, valued with the asset prices, with
+	/// its health factor and remaining borrow power.
+	pub fn account_overview(
+		who: &T::AccountId,
+	) -> AccountOverview<AssetIdOf<T>, AssetBalanceOf<T>> {
+		let zero = AssetBalanceOf::<T>::zero();
+		let mut overview = AccountOverview {
+			supplied: Vec::new(),
+			borrowed: Vec::new(),
+			total_supplied_value: zero,
+			total_borrowed_value: zero,
+			total_collateral_value: zero,
+			health_factor: None,
+			borrow_power: zero,
+		};
+
+		for mut pool in LendingPoolStorage::<T>::iter_values() {
+			let scaled_balance = T::Fungibles::balance(pool.id, who);
+			if scaled_balance.is_zero() || pool.update_indexes().is_err() {
+				continue;
+			}
+			let Ok(balance) = pool.accrued_deposit(scaled_balance) else { continue };
+			let value = Self::asset_value(pool.lend_token_id, balance);
+			overview.total_supplied_value =
+				overview.total_supplied_value.saturating_add(value.unwrap_or(zero));
+			overview
+				.supplied
+				.push(SupplyPosition { asset: pool.lend_token_id, balance, value });
+		}
+
+		let mut liquidation_value = zero;
+		let mut borrow_limit = zero;
+		for ((account, asset, collateral_asset), loan) in Borrows::<T>::iter() {
+			if &account != who {
+				continue;
+			}
+			let Ok(mut pool) = Self::borrow_pool(who, asset, collateral_asset) else {
+				continue;
+			};
+			if pool.update_indexes().is_err() {
+				continue;
+			}
+			let Ok(balance) = Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)
+			else {
+				continue
+			};
+			let value = Self::asset_value(asset, balance);
+			let collateral_value = Self::asset_value(collateral_asset, loan.collateral_balance);
+			if let (Some(value), Some(collateral_value)) = (value, collateral_value) {
+				overview.total_borrowed_value = overview.total_borrowed_value.saturating_add(value);
+				overview.total_collateral_value =
+					overview.total_collateral_value.saturating_add(collateral_value);
+				liquidation_value = liquidation_value
+					.saturating_add(pool.liquidation_threshold().mul_floor(collateral_value));
+				borrow_limit = borrow_limit
+					.saturating_add(pool.collateral_factor().mul_floor(collateral_value));
+			}
+			overview.borrowed.push(BorrowPosition {
+				asset,
+				collateral_asset,
+				balance,
+				collateral_balance: loan.collateral_balance,
+				value,
+				collateral_value,
+			});
+		}
+
+		if !overview.total_borrowed_value.is_zero() {
+			overview.health_factor = Rate::checked_from_rational(
+				liquidation_value.saturated_into::<u128>(),
+				overview.total_borrowed_value.saturated_into::<u128>(),
+			);
+		}
+		overview.borrow_power = borrow_limit.saturating_sub(overview.total_borrowed_value);
+		overview
+	}
+
+	/// Evaluates the `action` of `who` against the current state without dispatching it, and
+	/// returns the resulting health factor of `who` and state of the lending pool. The state
+	/// changes are always rolled back.
+	pub fn simulate(
+		who: &T::AccountId,
+		action: Action<AssetIdOf<T>, AssetBalanceOf<T>>,
+	) -> Result<Simulation<AssetIdOf<T>, AssetBalanceOf<T>>, DispatchError> {
+		with_transaction(|| TransactionOutcome::Rollback(Self::do_simulate(who, action)))
+	}
+
+	fn do_simulate(
+		who: &T::AccountId,
+		action: Action<AssetIdOf<T>, AssetBalanceOf<T>>,
+	) -> Result<Simulation<AssetIdOf<T>, AssetBalanceOf<T>>, DispatchError> {
+		let asset = match action {
+			Action::Supply { asset, balance } => {
+				Self::do_supply(who, asset, balance)?;
+				asset
+			},
+			Action::Withdraw { asset, balance } => {
+				Self::do_withdrawal(who, asset, balance, Precision::Exact)?;
+				asset
+			},
+			Action::Borrow { asset, balance, collateral_asset, collateral_balance } => {
+				Self::do_borrow(who, asset, balance, collateral_asset, collateral_balance, false)?;
+				asset
+			},
+			Action::Repay { asset, balance, collateral_asset } => {
+				Self::do_repay(who, asset, balance, collateral_asset)?;
+				asset
+			},
+		};
+
+		let overview = Self::account_overview(who);
+		let pool = Self::pool_info(asset).ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		Ok(Simulation {
+			health_factor: overview.health_factor,
+			borrow_power: overview.borrow_power,
+			pool,
+		})
+	}
+
+	/// Returns the largest balance of `asset` `who` can borrow in a single `borrow`, against
+	/// the collateral asset of its account which borrows the most, or `None` if it can borrow
+	/// nothing. The quote is capped to the collateral factor, the liquidity and the outflow cap
+	/// of the pool, keeps the `MAX_BORROW_MARGIN`, and is checked by simulating the borrow.
+	pub fn max_borrowable(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+	) -> Option<MaxBorrow<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		Self::ensure_no_shortfall(who).ok()?;
+		let mut collaterals: BTreeSet<_> =
+			AssetPrices::<T>::iter_keys().flat_map(|(base, quote)| [base, quote]).collect();
+		collaterals.extend(DexLpCollaterals::<T>::iter_keys());
+		collaterals.remove(&asset);
+		collaterals
+			.into_iter()
+			.filter_map(|collateral_asset| {
+				Self::max_borrowable_against(who, asset, collateral_asset)
+			})
+			.max_by_key(|quote| quote.balance)
+	}
+
+	/// Returns the largest balance of `asset` `who` can borrow against its whole balance of
+	/// `collateral_asset`, like `max_borrowable`
+	pub fn max_borrowable_against(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Option<MaxBorrow<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))?;
+		pool.update_indexes().ok()?;
+		let collateral_balance = Self::reducible_collateral(who, collateral_asset);
+		if collateral_balance.is_zero() {
+			return None;
+		}
+
+		let value =
+			Self::get_equivalent_asset_amount(who, asset, collateral_asset, collateral_balance)
+				.ok()?;
+		let mut balance = pool.max_borrow_amount(value).ok()?;
+		if let Some(lp_asset) = LpTokenPools::<T>::get(collateral_asset) {
+			balance = LpCollateralFactor::<T>::get(lp_asset)?.mul_floor(balance);
+		}
+		balance = balance.min(pool.reserve_balance);
+		if let Some(cap) = OutflowCaps::<T>::get(asset) {
+			balance = balance.min(cap.saturating_sub(Self::decayed_outflow(asset)));
+		}
+		if let Some(cap) = BorrowCaps::<T>::get(asset) {
+			balance = balance.min(cap.saturating_sub(pool.borrowed_balance));
+		}
+		balance = balance.saturating_sub(MAX_BORROW_MARGIN.mul_ceil(balance));
+
+		// the fees, the minimum borrow and the checks of the pool are left to the simulation
+		let action = Action::Borrow { asset, balance, collateral_asset, collateral_balance };
+		Self::simulate(who, action).ok()?;
+		Some(MaxBorrow { balance, collateral_asset, collateral_balance })
+	}
+}
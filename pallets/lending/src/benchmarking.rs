@@ -1,35 +1,1359 @@
-//! Benchmarking setup for pallet-template
+//! Benchmarking setup for the lending pallet
 #![cfg(feature = "runtime-benchmarks")]
 use super::*;
 
 #[allow(unused)]
-use crate::Pallet as Template;
+use crate::Pallet as Lending;
 use frame_benchmarking::v2::*;
+use frame_support::sp_std::{boxed::Box, vec::Vec};
 use frame_system::RawOrigin;
 
-#[benchmarks]
+const SEED: u32 = 0;
+const ASSET: u32 = 1;
+const COLLATERAL: u32 = 2;
+const REWARD: u32 = 3;
+const REDENOMINATED: u32 = 5;
+const POOL_ID: u32 = 1_000;
+const REWARD_POOL_ID: u32 = 1_001;
+const REPLACEMENT_POOL_ID: u32 = 1_002;
+const BALANCE: u128 = 1_000_000_000_000;
+
+fn asset_id<T: Config>(id: u32) -> AssetIdOf<T> {
+	T::BenchmarkHelper::asset_id(id)
+}
+
+fn units<T: Config>(balance: u128) -> AssetBalanceOf<T> {
+	balance.saturated_into()
+}
+
+fn referral_code() -> ReferralCode {
+	ReferralCode::truncate_from(b"KYLIX-REFERRALS!".to_vec())
+}
+
+// Moves the clock forward, so that the interest and the rewards accrue
+fn set_time<T: Config + pallet_timestamp::Config>(seconds: u32) {
+	pallet_timestamp::Pallet::<T>::set_timestamp((seconds * 1_000).into());
+}
+
+// Creates `asset` if needed and mints `balance` of it into `who`
+fn mint<T: Config>(asset: AssetIdOf<T>, who: &T::AccountId, balance: u128) {
+	if !T::Fungibles::asset_exists(asset) {
+		T::Fungibles::create(asset, Lending::<T>::account_id(), true, One::one()).unwrap();
+	}
+	T::Fungibles::mint_into(asset, who, units::<T>(balance)).unwrap();
+}
+
+// Creates an active lending pool of `asset` supplied by a creator account
+fn create_pool<T: Config>(id: AssetIdOf<T>, asset: AssetIdOf<T>) {
+	let creator: T::AccountId = account("creator", id, SEED);
+	mint::<T>(asset, &creator, BALANCE);
+	Lending::<T>::do_create_lending_pool(&creator, id, asset, units::<T>(BALANCE / 2)).unwrap();
+	Lending::<T>::do_activate_lending_pool(asset).unwrap();
+}
+
+// Creates a reward program on the lending pool of `asset`, with a funded rewards pot
+fn create_reward_program<T: Config>(asset: AssetIdOf<T>, vesting: Option<VestingTerms>) {
+	Lending::<T>::do_set_reward_program(asset, asset_id::<T>(REWARD), units::<T>(1_000), vesting)
+		.unwrap();
+	mint::<T>(asset_id::<T>(REWARD), &Lending::<T>::rewards_account_id(), BALANCE);
+}
+
+// Registers a referral code and attaches it to `who`
+fn refer<T: Config>(who: &T::AccountId) {
+	let referrer: T::AccountId = account("referrer", 0, SEED);
+	if !ReferralCodes::<T>::contains_key(referral_code()) {
+		Lending::<T>::do_register_referral_code(&referrer, referral_code()).unwrap();
+	}
+	Lending::<T>::attach_referral(who, Some(referral_code())).unwrap();
+}
+
+// Credits `who` with rewards accrued in the lending pool of `asset`
+fn accrue_rewards<T: Config>(who: &T::AccountId, asset: AssetIdOf<T>) {
+	UserRewards::<T>::mutate(who, asset, |user| user.accrued = units::<T>(1_000));
+	RewardLiabilities::<T>::mutate(asset_id::<T>(REWARD), |liabilities| {
+		*liabilities = liabilities.saturating_add(units::<T>(1_000))
+	});
+}
+
+// Opens a referred borrow of `who` against collateral valued at twice its debt
+fn create_borrow<T: Config>(who: &T::AccountId) {
+	create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+	mint::<T>(asset_id::<T>(COLLATERAL), who, BALANCE);
+	AssetPrices::<T>::insert((asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)), FixedU128::from(1));
+	refer::<T>(who);
+	Lending::<T>::do_borrow(
+		who,
+		asset_id::<T>(ASSET),
+		units::<T>(BALANCE / 8),
+		asset_id::<T>(COLLATERAL),
+		units::<T>(BALANCE / 4),
+		false,
+	)
+	.unwrap();
+}
+
+// Registers `who` as a keeper
+fn register_keeper<T: Config>(who: &T::AccountId) {
+	let bond = T::KeeperBond::get();
+	<T::NativeBalance as fungible::Mutate<_>>::set_balance(who, bond.saturating_mul(2u32.into()));
+	Lending::<T>::do_register_keeper(who).unwrap();
+}
+
+// Opens `m` healthy borrows of `who` in the lending pool of `asset_id::<T>(ASSET)`, with as many
+// collaterals
+fn open_borrows<T: Config>(who: &T::AccountId, m: u32) {
+	for i in 0..m {
+		let collateral = asset_id::<T>(100 + i);
+		mint::<T>(collateral, who, BALANCE);
+		AssetPrices::<T>::insert((collateral, asset_id::<T>(ASSET)), FixedU128::from(1));
+		Lending::<T>::do_borrow(
+			who,
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 1_000),
+			collateral,
+			units::<T>(BALANCE / 500),
+			false,
+		)
+		.unwrap();
+	}
+}
+
+#[benchmarks(where T: pallet_timestamp::Config)]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32.into();
+	fn create_lending_pool() {
 		let caller: T::AccountId = whitelisted_caller();
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			asset_id::<T>(POOL_ID),
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 2),
+		);
+
+		assert!(LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset_id::<T>(ASSET))));
+	}
+
+	#[benchmark]
+	fn activate_lending_pool() {
+		let caller: T::AccountId = whitelisted_caller();
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+		Lending::<T>::do_create_lending_pool(
+			&caller,
+			asset_id::<T>(POOL_ID),
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 2),
+		)
+		.unwrap();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset_id::<T>(ASSET));
+
+		assert!(LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset_id::<T>(ASSET)))
+			.unwrap()
+			.is_active());
+	}
+
+	#[benchmark]
+	fn supply() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+		Lending::<T>::do_register_referral_code(&account("referrer", 0, SEED), referral_code())
+			.unwrap();
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 2),
+			Some(referral_code()),
+		);
+
+		assert!(Referrals::<T>::contains_key(&caller));
+	}
+
+	// the borrows of the caller are checked for a shortfall
+	#[benchmark]
+	fn withdraw(m: Linear<0, { T::MaxMarketsPerAccount::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+		Lending::<T>::do_supply(&caller, asset_id::<T>(ASSET), units::<T>(BALANCE / 2)).unwrap();
+		open_borrows::<T>(&caller, m);
+		set_time::<T>(60);
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), units::<T>(BALANCE / 4));
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert!(!T::Fungibles::balance(asset_id::<T>(POOL_ID), &caller).is_zero());
 	}
 
+	// the borrows of the caller are checked for a shortfall, the new borrow takes the last slot
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
+	fn borrow(m: Linear<0, { T::MaxMarketsPerAccount::get() - 1 }>) {
 		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		open_borrows::<T>(&caller, m);
+		mint::<T>(asset_id::<T>(COLLATERAL), &caller, BALANCE);
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from(1),
+		);
+		Lending::<T>::do_register_referral_code(&account("referrer", 0, SEED), referral_code())
+			.unwrap();
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 8),
+			asset_id::<T>(COLLATERAL),
+			units::<T>(BALANCE / 4),
+			Some(referral_code()),
+		);
+
+		assert!(Borrows::<T>::contains_key((
+			caller,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+	}
+
+	// a partial repayment of a referred borrow with interest due and a protocol fee
+	#[benchmark]
+	fn repay() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_borrow::<T>(&caller);
+		ProtocolFee::<T>::put(Ratio::from_percent(10));
+		set_time::<T>(30 * 24 * 60 * 60);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 16),
+			asset_id::<T>(COLLATERAL),
+		);
+
+		assert!(Borrows::<T>::contains_key((
+			caller,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+	}
+
+	#[benchmark]
+	fn claim_rewards(p: Linear<1, { T::MaxMarketsPerAccount::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		for i in 0..p {
+			let asset = asset_id::<T>(10 + i);
+			create_pool::<T>(asset_id::<T>(POOL_ID + 10 + i), asset);
+			create_reward_program::<T>(asset, None);
+			mint::<T>(asset, &caller, BALANCE);
+			Lending::<T>::do_supply(&caller, asset, units::<T>(BALANCE / 2)).unwrap();
+			accrue_rewards::<T>(&caller, asset);
+		}
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), None, None);
+
+		assert!(UserRewards::<T>::iter_prefix_values(&caller).all(|user| user.accrued.is_zero()));
+	}
+
+	#[benchmark]
+	fn deactivate_lending_pool() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset_id::<T>(ASSET));
+	}
+
+	#[benchmark]
+	fn update_pool_rate_model() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset_id::<T>(ASSET));
+	}
+
+	#[benchmark]
+	fn update_pool_kink() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset_id::<T>(ASSET));
+	}
+
+	#[benchmark]
+	fn set_asset_price() -> Result<(), BenchmarkError> {
+		let origin =
+			T::OracleOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			asset_id::<T>(COLLATERAL),
+			asset_id::<T>(ASSET),
+			FixedU128::from(2),
+		);
+
+		assert_eq!(
+			AssetPrices::<T>::get((asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET))),
+			Some(FixedU128::from(2))
+		);
+		Ok(())
+	}
+
+	// updates an existing program, accruing its rewards first
+	#[benchmark]
+	fn set_reward_program() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		let vesting = Some(VestingTerms { cliff: 60, duration: 3_600 });
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(REWARD),
+			units::<T>(2_000),
+			vesting,
+		);
+
+		assert_eq!(RewardPrograms::<T>::get(asset_id::<T>(ASSET)).unwrap().vesting, vesting);
+		Ok(())
+	}
+
+	// a partial release, the schedule is kept
+	#[benchmark]
+	fn claim_vested_rewards() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(
+			asset_id::<T>(ASSET),
+			Some(VestingTerms { cliff: 0, duration: 3_600 }),
+		);
+		accrue_rewards::<T>(&caller, asset_id::<T>(ASSET));
+		Lending::<T>::do_claim_rewards(&caller, asset_id::<T>(ASSET), &caller).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET));
+
+		assert!(RewardVesting::<T>::contains_key(&caller, asset_id::<T>(ASSET)));
+	}
+
+	#[benchmark]
+	fn compound_rewards() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_pool::<T>(asset_id::<T>(REWARD_POOL_ID), asset_id::<T>(REWARD));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		accrue_rewards::<T>(&caller, asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET));
+
+		assert!(!T::Fungibles::balance(asset_id::<T>(REWARD_POOL_ID), &caller).is_zero());
+	}
+
+	#[benchmark]
+	fn set_auto_compound() {
+		let caller: T::AccountId = whitelisted_caller();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), true);
+
+		assert!(AutoCompound::<T>::get(&caller, asset_id::<T>(ASSET)));
+	}
+
+	// compounds on behalf of a user, paying the keeper a tip
+	#[benchmark]
+	fn compound_rewards_for() {
+		let caller: T::AccountId = whitelisted_caller();
+		let who: T::AccountId = account("user", 0, SEED);
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_pool::<T>(asset_id::<T>(REWARD_POOL_ID), asset_id::<T>(REWARD));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		accrue_rewards::<T>(&who, asset_id::<T>(ASSET));
+		AutoCompound::<T>::insert(&who, asset_id::<T>(ASSET), true);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), who.clone(), asset_id::<T>(ASSET));
+
+		assert!(!T::Fungibles::balance(asset_id::<T>(REWARD_POOL_ID), &who).is_zero());
+	}
+
+	#[benchmark]
+	fn update_reward_boost() {
+		let caller: T::AccountId = whitelisted_caller();
+		let who: T::AccountId = account("user", 0, SEED);
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		mint::<T>(asset_id::<T>(ASSET), &who, BALANCE);
+		Lending::<T>::do_supply(&who, asset_id::<T>(ASSET), units::<T>(BALANCE / 2)).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), who.clone(), asset_id::<T>(ASSET));
+
+		assert!(!UserRewards::<T>::get(&who, asset_id::<T>(ASSET)).accrued.is_zero());
+	}
+
+	// funds a paused program, resuming its emission
+	#[benchmark]
+	fn fund_rewards() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		RewardPrograms::<T>::mutate(asset_id::<T>(ASSET), |program| {
+			program.as_mut().unwrap().paused = true
+		});
+		mint::<T>(asset_id::<T>(REWARD), &caller, BALANCE);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset_id::<T>(ASSET), units::<T>(BALANCE / 2));
+
+		assert!(!RewardPrograms::<T>::get(asset_id::<T>(ASSET)).unwrap().paused);
+	}
+
+	#[benchmark]
+	fn register_referral_code() {
+		let caller: T::AccountId = whitelisted_caller();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), referral_code());
+
+		assert_eq!(ReferralCodes::<T>::get(referral_code()), Some(caller));
+	}
+
+	#[benchmark]
+	fn claim_referral_earnings() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		Lending::<T>::do_register_referral_code(&caller, referral_code()).unwrap();
+		ReferralEarnings::<T>::insert(referral_code(), asset_id::<T>(ASSET), units::<T>(1_000));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), referral_code(), asset_id::<T>(ASSET));
+
+		assert!(ReferralEarnings::<T>::get(referral_code(), asset_id::<T>(ASSET)).is_zero());
+	}
+
+	#[benchmark]
+	fn set_borrow_fee() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), Ratio::from_percent(1));
+
+		let pool =
+			LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset_id::<T>(ASSET))).unwrap();
+		assert_eq!(pool.borrow_fee, Ratio::from_percent(1));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_protocol_fee() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, Ratio::from_percent(10));
+
+		assert_eq!(ProtocolFee::<T>::get(), Ratio::from_percent(10));
+		Ok(())
+	}
+
+	// liquidates a referred borrow with interest due and a protocol fee, the borrower using the
+	// other slots for borrows against as many collaterals, none of them the repaid asset
+	#[benchmark]
+	fn liquidate_borrow(m: Linear<0, { T::MaxMarketsPerAccount::get() - 1 }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		open_borrows::<T>(&borrower, m);
+		ProtocolFee::<T>::put(Ratio::from_percent(10));
+		set_time::<T>(30 * 24 * 60 * 60);
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from_rational(1, 4),
+		);
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+		// the keeper assigned to the liquidation missed it, for the last time before its slashing
+		let keeper: T::AccountId = account("keeper", 0, SEED);
+		register_keeper::<T>(&keeper);
+		Keepers::<T>::mutate(&keeper, |info| {
+			info.as_mut().unwrap().misses = T::MaxKeeperMisses::get().saturating_sub(1)
+		});
+		LiquidationAssignments::<T>::insert(
+			(borrower.clone(), asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL)),
+			(keeper, BlockNumberFor::<T>::zero()),
+		);
+		frame_system::Pallet::<T>::set_block_number(1u32.into());
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			borrower.clone(),
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+		);
+
+		assert!(!Borrows::<T>::contains_key((
+			borrower,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+	}
+
+	#[benchmark]
+	fn accrue_interest() {
+		create_borrow::<T>(&account("borrower", 0, SEED));
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::None, asset_id::<T>(ASSET));
+
+		let pool =
+			LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset_id::<T>(ASSET))).unwrap();
+		assert_eq!(pool.last_accrued_interest_at, 60);
+	}
+
+	#[benchmark]
+	fn set_min_borrow() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), units::<T>(1_000));
+
+		assert_eq!(MinBorrow::<T>::get(asset_id::<T>(ASSET)), units::<T>(1_000));
+		Ok(())
+	}
+
+	// writes off a borrow repaid down to a single unit whose collateral lost its value
+	#[benchmark]
+	fn write_off_dust_debt() {
+		let caller: T::AccountId = whitelisted_caller();
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		Lending::<T>::do_repay(
+			&borrower,
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE / 8 - 1),
+			asset_id::<T>(COLLATERAL),
+		)
+		.unwrap();
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from_rational(1, 4),
+		);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			borrower.clone(),
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+		);
+
+		assert!(!Borrows::<T>::contains_key((
+			borrower,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+	}
+
+	// the sender and the recipient both earn rewards
+	#[benchmark]
+	fn transfer_lp_tokens() {
+		let caller: T::AccountId = whitelisted_caller();
+		let dest: T::AccountId = account("dest", 0, SEED);
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+		mint::<T>(asset_id::<T>(ASSET), &dest, BALANCE);
+		Lending::<T>::do_supply(&caller, asset_id::<T>(ASSET), units::<T>(BALANCE / 2)).unwrap();
+		Lending::<T>::do_supply(&dest, asset_id::<T>(ASSET), units::<T>(BALANCE / 2)).unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(ASSET),
+			dest.clone(),
+			units::<T>(BALANCE / 4),
+		);
+
+		assert!(!UserRewards::<T>::get(&caller, asset_id::<T>(ASSET)).accrued.is_zero());
+	}
+
+	#[benchmark]
+	fn set_lp_collateral_factor() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), Some(Ratio::from_percent(50)));
+
+		assert_eq!(
+			LpCollateralFactor::<T>::get(asset_id::<T>(ASSET)),
+			Some(Ratio::from_percent(50))
+		);
+		Ok(())
+	}
+
+	// rebalances a borrow at a stable rate of zero in a fully utilised pool
+	#[benchmark]
+	fn rebalance_stable_rate() {
+		let caller: T::AccountId = whitelisted_caller();
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		let key = (borrower.clone(), asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+		StableRatePremium::<T>::insert(asset_id::<T>(ASSET), Ratio::from_percent(1));
+		StableBorrows::<T>::insert(
+			&key,
+			StableRate { rate: Rate::zero(), index: Rate::one(), updated_at: 0 },
+		);
+		LendingPoolStorage::<T>::mutate(AssetPool::<T>::from(asset_id::<T>(ASSET)), |pool| {
+			let pool = pool.as_mut().unwrap();
+			pool.borrowed_balance = pool.borrowed_balance.saturating_add(pool.reserve_balance);
+			pool.reserve_balance = One::one();
+		});
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			borrower.clone(),
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+		);
+
+		assert!(!StableBorrows::<T>::get(&key).unwrap().rate.is_zero());
+	}
+
+	#[benchmark]
+	fn set_stable_rate_premium() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), Some(Ratio::from_percent(2)));
+
+		assert_eq!(StableRatePremium::<T>::get(asset_id::<T>(ASSET)), Some(Ratio::from_percent(2)));
+		Ok(())
+	}
+
+	// switches a variable borrow to the stable rate
+	#[benchmark]
+	fn switch_borrow_rate_mode() {
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		StableRatePremium::<T>::insert(asset_id::<T>(ASSET), Ratio::from_percent(1));
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(borrower.clone()), asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+
+		assert!(StableBorrows::<T>::contains_key((
+			borrower,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+	}
+
+	#[benchmark]
+	fn set_borrow_rate_bounds() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let bounds = (Rate::from_rational(1, 100), Rate::from_rational(1, 2));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), Some(bounds));
+
+		assert_eq!(BorrowRateBounds::<T>::get(asset_id::<T>(ASSET)), Some(bounds));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_outflow_cap() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let cap = units::<T>(1_000_000);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), Some(cap));
+
+		assert_eq!(OutflowCaps::<T>::get(asset_id::<T>(ASSET)), Some(cap));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_maintenance_mode() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, true);
+
+		assert!(MaintenanceMode::<T>::get());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn announce_param_change() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let change = ParamChange::CollateralFactor(Ratio::from_percent(60));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), change);
+
+		assert!(PendingParamChanges::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn execute_param_change() {
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let change = ParamChange::CollateralFactor(Ratio::from_percent(60));
+		let (id, execute_at) =
+			Lending::<T>::do_announce_param_change(asset_id::<T>(ASSET), change).unwrap();
+		frame_system::Pallet::<T>::set_block_number(execute_at);
+
+		#[extrinsic_call]
+		_(RawOrigin::Root, id);
+
+		let pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset_id::<T>(ASSET)));
+		assert_eq!(pool.unwrap().collateral_factor, Ratio::from_percent(60));
+	}
+
+	#[benchmark]
+	fn cancel_param_change() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let change = ParamChange::CollateralFactor(Ratio::from_percent(60));
+		let (id, _) = Lending::<T>::do_announce_param_change(asset_id::<T>(ASSET), change).unwrap();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, id);
+
+		assert!(!PendingParamChanges::<T>::contains_key(id));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_permissioned_pool() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), true);
+
+		assert!(PermissionedPools::<T>::get(asset_id::<T>(ASSET)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_whitelisted() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let who: T::AccountId = account("user", 0, SEED);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), who.clone(), true);
+
+		assert!(PoolWhitelist::<T>::get(asset_id::<T>(ASSET), &who));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_liquidate() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		ProtocolFee::<T>::put(Ratio::from_percent(10));
+		set_time::<T>(30 * 24 * 60 * 60);
+		mint::<T>(asset_id::<T>(ASSET), &T::TreasuryAccount::get(), BALANCE);
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			borrower.clone(),
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+		);
+
+		assert!(!Borrows::<T>::contains_key((
+			borrower,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+		Ok(())
+	}
+
+	// the borrows of all the pools are iterated to sum up the collaterals owed
+	#[benchmark]
+	fn rescue_funds(b: Linear<1, 1_000>) -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_borrow::<T>(&account("borrower", 0, SEED));
+		for i in 1..b {
+			let key = (account("borrower", i, SEED), asset_id::<T>(REWARD), asset_id::<T>(ASSET));
+			Borrows::<T>::insert(key, UserBorrow::<T>::default());
+		}
+		let pool_account = Lending::<T>::pool_account_id(asset_id::<T>(POOL_ID));
+		mint::<T>(asset_id::<T>(COLLATERAL), &pool_account, BALANCE);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, Some(asset_id::<T>(ASSET)), asset_id::<T>(COLLATERAL), b + 1);
+
+		assert_eq!(
+			T::Fungibles::balance(asset_id::<T>(COLLATERAL), &T::TreasuryAccount::get()),
+			units::<T>(BALANCE)
+		);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_update_pool_params() {
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let update = PoolParamsUpdate {
+			collateral_factor: Some(Ratio::from_percent(50)),
+			liquidation_threshold: Some(Ratio::from_percent(60)),
+			reserve_factor: Some(Ratio::from_percent(20)),
+			borrow_fee: Some(Ratio::from_percent(1)),
+			min_borrow: Some(units::<T>(1_000)),
+			outflow_cap: Some(Some(units::<T>(BALANCE))),
+			borrow_cap: Some(Some(units::<T>(BALANCE))),
+		};
+
+		#[extrinsic_call]
+		_(RawOrigin::Root, asset_id::<T>(ASSET), update);
+
+		assert_eq!(MinBorrow::<T>::get(asset_id::<T>(ASSET)), units::<T>(1_000));
+	}
+
+	// skipped without the `PositionNfts`
+	#[benchmark]
+	fn wrap_position() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		create_borrow::<T>(&caller);
+
+		#[block]
+		{
+			Lending::<T>::do_wrap_position(
+				&caller,
+				asset_id::<T>(ASSET),
+				asset_id::<T>(COLLATERAL),
+			)
+			.map_err(|_| BenchmarkError::Weightless)?;
+		}
+
+		assert!(Positions::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn transfer_position() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		create_borrow::<T>(&caller);
+		let id = Lending::<T>::do_wrap_position(
+			&caller,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+		)
+		.map_err(|_| BenchmarkError::Weightless)?;
+		let dest: T::AccountId = account("dest", 0, SEED);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), id, dest.clone());
+
+		assert_eq!(T::PositionNfts::owner(id), Some(dest));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unwrap_position() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		create_borrow::<T>(&caller);
+		let id = Lending::<T>::do_wrap_position(
+			&caller,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+		)
+		.map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), id);
+
+		assert!(Borrows::<T>::contains_key((
+			caller,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL)
+		)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn approve_debt() {
+		let caller: T::AccountId = whitelisted_caller();
+		let from: T::AccountId = account("from", 0, SEED);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			from.clone(),
+			asset_id::<T>(ASSET),
+			units::<T>(BALANCE),
+		);
+
+		assert!(!DebtAllowances::<T>::get((caller, from, asset_id::<T>(ASSET))).is_zero());
+	}
+
+	// the borrows of the recipient are checked for a shortfall, the debt moves onto the first one
+	#[benchmark]
+	fn transfer_debt(m: Linear<1, { T::MaxMarketsPerAccount::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, SEED);
+		create_borrow::<T>(&caller);
+		open_borrows::<T>(&recipient, m);
+		DebtAllowances::<T>::insert(
+			(&recipient, &caller, asset_id::<T>(ASSET)),
+			units::<T>(BALANCE),
+		);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+			units::<T>(BALANCE / 1_000),
+			recipient.clone(),
+			asset_id::<T>(100),
+		);
+
+		assert!(!DebtAllowances::<T>::get((recipient, caller, asset_id::<T>(ASSET))).is_zero());
+	}
+
+	// bans LP tokens of the DEX, accepting them takes a liquidity pool the benchmarks can not
+	// create on an arbitrary DEX
+	#[benchmark]
+	fn set_dex_lp_collateral() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		DexLpCollaterals::<T>::insert(
+			asset_id::<T>(POOL_ID),
+			(asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL)),
+		);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(POOL_ID), false);
+
+		assert!(!DexLpCollaterals::<T>::contains_key(asset_id::<T>(POOL_ID)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn register_keeper() {
+		let caller: T::AccountId = whitelisted_caller();
+		<T::NativeBalance as fungible::Mutate<_>>::set_balance(
+			&caller,
+			T::KeeperBond::get().saturating_mul(2u32.into()),
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()));
+
+		assert!(Keepers::<T>::contains_key(&caller));
+	}
+
+	#[benchmark]
+	fn deregister_keeper() {
+		let caller: T::AccountId = whitelisted_caller();
+		register_keeper::<T>(&caller);
+		frame_system::Pallet::<T>::set_block_number(1u32.into());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()));
+
+		assert!(!Keepers::<T>::contains_key(&caller));
+	}
+
+	// assigns a liquidation to the keeper after the cursor
+	#[benchmark]
+	fn assign_liquidation() {
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from_rational(1, 4),
+		);
+		for i in 0..2 {
+			register_keeper::<T>(&account("keeper", i, SEED));
+		}
+		KeeperCursor::<T>::put(Keepers::<T>::iter_keys().next().unwrap());
+		let key = (borrower.clone(), asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+
+		#[extrinsic_call]
+		_(RawOrigin::None, borrower, asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+
+		assert!(LiquidationAssignments::<T>::contains_key(key));
+	}
+
+	#[benchmark]
+	fn margin_call() {
+		let borrower: T::AccountId = account("borrower", 0, SEED);
+		create_borrow::<T>(&borrower);
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from_rational(1, 4),
+		);
+		let key = (borrower.clone(), asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+
+		#[extrinsic_call]
+		_(RawOrigin::None, borrower, asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+
+		assert!(MarginCalls::<T>::contains_key(key));
+	}
+
+	// answers a margin call, clearing it
+	#[benchmark]
+	fn add_collateral() {
+		let caller: T::AccountId = whitelisted_caller();
+		create_borrow::<T>(&caller);
+		let key = (caller.clone(), asset_id::<T>(ASSET), asset_id::<T>(COLLATERAL));
+		MarginCalls::<T>::insert(&key, BlockNumberFor::<T>::zero());
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			asset_id::<T>(ASSET),
+			asset_id::<T>(COLLATERAL),
+			units::<T>(BALANCE / 4),
+		);
+
+		assert!(!MarginCalls::<T>::contains_key(key));
+	}
+
+	// skipped without a `PriceReference` deviating from the price
+	#[benchmark]
+	fn report_price_deviation() -> Result<(), BenchmarkError> {
+		create_borrow::<T>(&account("borrower", 0, SEED));
+		create_pool::<T>(asset_id::<T>(REWARD_POOL_ID), asset_id::<T>(COLLATERAL));
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from(1_000),
+		);
+
+		#[block]
+		{
+			Lending::<T>::do_report_price_deviation(
+				asset_id::<T>(COLLATERAL),
+				asset_id::<T>(ASSET),
+			)
+			.map_err(|_| BenchmarkError::Weightless)?;
+		}
+
+		assert!(PausedPools::<T>::get(asset_id::<T>(ASSET)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_pool_paused() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), true);
+
+		assert!(PausedPools::<T>::get(asset_id::<T>(ASSET)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn approve_operator() {
+		let caller: T::AccountId = whitelisted_caller();
+		let operator: T::AccountId = account("operator", 0, SEED);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), operator.clone(), Some(OperatorPermissions::Full));
+
+		assert_eq!(Operators::<T>::get(caller, operator), Some(OperatorPermissions::Full));
+	}
+
+	// the weight of the call dispatched is added to the one of `operate`
+	#[benchmark]
+	fn operate() {
+		let caller: T::AccountId = whitelisted_caller();
+		let who: T::AccountId = account("owner", 0, SEED);
+		let from: T::AccountId = account("from", 0, SEED);
+		Operators::<T>::insert(&who, &caller, OperatorPermissions::Full);
+		let call = Call::<T>::approve_debt {
+			from: from.clone(),
+			asset: asset_id::<T>(ASSET),
+			allowance: units::<T>(BALANCE),
+		};
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), who.clone(), Box::new(call));
+
+		assert!(!DebtAllowances::<T>::get((who, from, asset_id::<T>(ASSET))).is_zero());
+	}
+
+	#[benchmark]
+	fn set_loyalty_tiers() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let count = T::MaxLoyaltyTiers::get();
+		let max_bonus = T::MaxLoyaltyBonus::get().deconstruct();
+		let tiers: BoundedVec<LoyaltyTier, T::MaxLoyaltyTiers> = (1..=count)
+			.map(|i| LoyaltyTier {
+				min_duration: i as Timestamp * 86_400,
+				bonus: Permill::from_parts(max_bonus / count * i),
+			})
+			.collect::<Vec<_>>()
+			.try_into()
+			.unwrap();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), tiers);
+
+		assert_eq!(LoyaltyTiers::<T>::get(asset_id::<T>(ASSET)).len() as u32, count);
+		Ok(())
+	}
+
+	// full repayments of `n` borrows with interest due and a protocol fee
+	#[benchmark]
+	fn repay_many(n: Linear<1, { T::MaxMarketsPerAccount::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		open_borrows::<T>(&caller, n);
+		mint::<T>(asset_id::<T>(ASSET), &caller, BALANCE);
+		ProtocolFee::<T>::put(Ratio::from_percent(10));
+		set_time::<T>(30 * 24 * 60 * 60);
+		let repayments: BoundedVec<_, T::MaxMarketsPerAccount> = (0..n)
+			.map(|i| (asset_id::<T>(ASSET), asset_id::<T>(100 + i), units::<T>(BALANCE / 500)))
+			.collect::<Vec<_>>()
+			.try_into()
+			.unwrap();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), repayments);
+
+		assert!(AccountBorrows::<T>::get(&caller).is_empty());
+	}
+
+	// every account supplies and borrows, adding two entries to read, whose rewards accrue
+	#[benchmark]
+	fn replace_lending_pool(e: Linear<0, 2_000>) -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from(1),
+		);
+		for i in 0..e / 2 {
+			let who: T::AccountId = account("position", i, SEED);
+			mint::<T>(asset_id::<T>(ASSET), &who, BALANCE / 1_000);
+			mint::<T>(asset_id::<T>(COLLATERAL), &who, BALANCE / 1_000);
+			Lending::<T>::do_supply(&who, asset_id::<T>(ASSET), units::<T>(BALANCE / 10_000))
+				.unwrap();
+			Lending::<T>::do_borrow(
+				&who,
+				asset_id::<T>(ASSET),
+				units::<T>(BALANCE / 10_000),
+				asset_id::<T>(COLLATERAL),
+				units::<T>(BALANCE / 5_000),
+				false,
+			)
+			.unwrap();
+		}
+		let witness = Positions::<T>::iter_keys().count() +
+			Borrows::<T>::iter_keys().count() +
+			SupplyIndexStorage::<T>::iter_keys().count() +
+			ReferralEarnings::<T>::iter_keys().count();
+		let update = PoolParamsUpdate {
+			collateral_factor: Some(Ratio::from_percent(40)),
+			..Default::default()
+		};
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(REPLACEMENT_POOL_ID),
+			update,
+			witness as u32,
+		);
+
+		assert!(RetiredPools::<T>::contains_key(asset_id::<T>(POOL_ID)));
+		assert_eq!(RetiredBorrows::<T>::iter_keys().count() as u32, e / 2);
+		Ok(())
+	}
+
+	// the supply and the `MaxMarketsPerAccount` borrows of each of the `a` accounts migrate
+	#[benchmark]
+	fn migrate_positions(a: Linear<1, MAX_POSITION_MIGRATIONS>) {
+		let caller: T::AccountId = whitelisted_caller();
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		create_reward_program::<T>(asset_id::<T>(ASSET), None);
+		let accounts: Vec<T::AccountId> = (0..a).map(|i| account("migrated", i, SEED)).collect();
+		for who in accounts.iter() {
+			mint::<T>(asset_id::<T>(ASSET), who, BALANCE);
+			Lending::<T>::do_supply(who, asset_id::<T>(ASSET), units::<T>(BALANCE / 2)).unwrap();
+			open_borrows::<T>(who, T::MaxMarketsPerAccount::get());
+		}
+		Lending::<T>::do_replace_pool(
+			asset_id::<T>(ASSET),
+			asset_id::<T>(REPLACEMENT_POOL_ID),
+			&Default::default(),
+			u32::MAX,
+		)
+		.unwrap();
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			asset_id::<T>(POOL_ID),
+			asset_id::<T>(REPLACEMENT_POOL_ID),
+			accounts.clone().try_into().unwrap(),
+		);
+
+		assert!(RetiredBorrows::<T>::iter_keys().next().is_none());
+		for who in accounts.iter() {
+			assert!(!T::Fungibles::balance(asset_id::<T>(REPLACEMENT_POOL_ID), who).is_zero());
+		}
+	}
+
+	#[benchmark]
+	fn redenominate_pool(e: Linear<0, 2_000>) -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		AssetPrices::<T>::insert(
+			(asset_id::<T>(COLLATERAL), asset_id::<T>(ASSET)),
+			FixedU128::from(1),
+		);
+		// every borrower adds a borrow and the markets of its account to the entries read
+		for i in 0..e / 2 {
+			let borrower: T::AccountId = account("borrower", i, SEED);
+			mint::<T>(asset_id::<T>(COLLATERAL), &borrower, BALANCE / 1_000);
+			Lending::<T>::do_borrow(
+				&borrower,
+				asset_id::<T>(ASSET),
+				units::<T>(BALANCE / 10_000),
+				asset_id::<T>(COLLATERAL),
+				units::<T>(BALANCE / 5_000),
+				false,
+			)
+			.unwrap();
+		}
+		mint::<T>(asset_id::<T>(REDENOMINATED), &account("creator", 0, SEED), 1);
+		let witness = Borrows::<T>::iter_keys().count() +
+			AccountBorrows::<T>::iter_keys().count() +
+			SupplyIndexStorage::<T>::iter_keys().count() +
+			AssetPrices::<T>::iter_keys().count();
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			asset_id::<T>(ASSET),
+			asset_id::<T>(REDENOMINATED),
+			FixedU128::from(100),
+			witness as u32,
+		);
+
+		let pool = AssetPool::<T>::from(asset_id::<T>(REDENOMINATED));
+		assert!(LendingPoolStorage::<T>::contains_key(pool));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_account_supply_cap() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let cap = Permill::from_percent(10);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), Some(cap));
+
+		assert_eq!(AccountSupplyCaps::<T>::get(asset_id::<T>(ASSET)), Some(cap));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_account_tier() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let who: T::AccountId = account("institution", 0, SEED);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, who.clone(), Some(AccountTier::Institutional));
+
+		assert_eq!(AccountTiers::<T>::get(&who), Some(AccountTier::Institutional));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_tier_terms() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let terms = TierTerms { max_supply: Some(units::<T>(1_000_000)) };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), AccountTier::Retail, Some(terms));
+
+		assert_eq!(PoolTierTerms::<T>::get(asset_id::<T>(ASSET), AccountTier::Retail), Some(terms));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_risk_parameter() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+		let (parameter, value) = (RiskParameter::CollateralFactor, Permill::from_percent(40));
+
 		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), parameter, Some(value));
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		assert_eq!(RiskParameterOverrides::<T>::get(asset_id::<T>(ASSET), parameter), Some(value));
+		Ok(())
 	}
 
-	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
+	impl_benchmark_test_suite!(Lending, crate::mock::new_test_ext(), crate::mock::Test);
 }
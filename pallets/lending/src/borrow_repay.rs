@@ -19,6 +19,9 @@ pub struct UserBorrow<T: Config> {
 	pub borrowed_balance: AssetBalanceOf<T>,
 	pub collateral_asset: AssetIdOf<T>,
 	pub collateral_balance: AssetBalanceOf<T>,
+	/// The principal of the borrow, telling the interest apart on repayment. Loans opened
+	/// before principals were tracked have none.
+	pub principal: Option<AssetBalanceOf<T>>,
 }
 
 impl<T: Config> UserBorrow<T> {
@@ -32,6 +35,12 @@ impl<T: Config> UserBorrow<T> {
 			.collateral_balance
 			.checked_add(&b.collateral_balance)
 			.ok_or(Error::<T>::OverflowError)?;
+		// untracked principals stay untracked
+		self.principal = match (self.principal, b.principal) {
+			(Some(principal), Some(increase)) =>
+				Some(principal.checked_add(&increase).ok_or(Error::<T>::OverflowError)?),
+			_ => None,
+		};
 
 		Ok(())
 	}
@@ -52,4 +61,27 @@ impl<T: Config> UserBorrow<T> {
 			.ok_or(Error::<T>::OverflowError)?;
 		Ok(())
 	}
+
+	/// Splits a repayment into the repaid principal and the paid interest, pro rata of the
+	/// outstanding interest
+	/// 	interest = pay / repayable_balance * (repayable_balance - principal)
+	/// rounded up, and deducts the repaid principal. Loans without a tracked principal are
+	/// considered interest free.
+	pub fn split_repayment(
+		&mut self,
+		pay: AssetBalanceOf<T>,
+		repayable_balance: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), Error<T>> {
+		let Some(principal) = self.principal else {
+			return Ok((pay, AssetBalanceOf::<T>::zero()));
+		};
+
+		let interest_due = repayable_balance.saturating_sub(principal);
+		let interest = math::mul_div(pay, interest_due, repayable_balance, Rounding::Up)
+			.ok_or(Error::<T>::OverflowError)?;
+		let repaid_principal = pay.saturating_sub(interest);
+
+		self.principal = Some(principal.saturating_sub(repaid_principal));
+		Ok((repaid_principal, interest))
+	}
 }
@@ -0,0 +1,70 @@
+use crate::*;
+use frame_support::storage::with_storage_layer;
+
+impl<T: Config> Pallet<T> {
+	/// Sweeps the supply positions from the `CleanupCursor` on, within the `remaining_weight`.
+	/// Returns the consumed weight.
+	pub fn sweep_positions(remaining_weight: Weight) -> Weight {
+		let per_position = T::DbWeight::get().reads_writes(8, 6);
+		let mut consumed = T::DbWeight::get().reads_writes(1, 1);
+		if remaining_weight.any_lt(consumed.saturating_add(per_position)) {
+			return Weight::zero();
+		}
+
+		// carry on after the cursor, the sweep starts over once the cursor reached the end
+		let positions = match CleanupCursor::<T>::get() {
+			Some(key) => SupplyIndexStorage::<T>::iter_keys_from(
+				SupplyIndexStorage::<T>::hashed_key_for(key),
+			),
+			None => SupplyIndexStorage::<T>::iter_keys(),
+		};
+
+		let mut last = None;
+		for (who, asset) in positions {
+			if remaining_weight.any_lt(consumed.saturating_add(per_position)) {
+				break;
+			}
+			consumed.saturating_accrue(per_position);
+			if let Err(e) = with_storage_layer(|| Self::sweep_position(&who, asset)) {
+				log::warn!(target: "runtime::lending", "failed to sweep a position: {:?}", e);
+			}
+			last = Some((who, asset));
+		}
+
+		match last {
+			Some(key) => CleanupCursor::<T>::put(key),
+			None => CleanupCursor::<T>::kill(),
+		}
+		consumed
+	}
+
+	/// Sweeps the supply position of `who` in the lending pool of `asset` if its balance is
+	/// below the `DustBalance`. The LP tokens are burnt, leaving the underlying assets to the
+	/// pool reserves, and the empty storage entries of the position are removed. The dead
+	/// shares are never swept.
+	pub fn sweep_position(who: &T::AccountId, asset: AssetIdOf<T>) -> DispatchResult {
+		if who == &Self::dead_shares_account_id() {
+			return Ok(());
+		}
+		let pool = LendingPoolStorage::<T>::try_get(AssetPool::<T>::from(asset)).ok();
+		let balance =
+			pool.as_ref().map_or(Zero::zero(), |pool| T::Fungibles::balance(pool.id, who));
+		if balance >= T::DustBalance::get() {
+			return Ok(());
+		}
+
+		if let Some(pool) = pool.filter(|_| !balance.is_zero()) {
+			Self::accrue_rewards(who, asset)?;
+			T::Fungibles::burn_from(pool.id, who, balance, Precision::Exact, Fortitude::Force)?;
+			Self::accrue_rewards(who, asset)?;
+			Self::deposit_event(Event::DustSwept { who: who.clone(), asset, balance });
+		}
+
+		SupplyIndexStorage::<T>::remove((who, asset));
+		let reward = UserRewards::<T>::get(who, asset);
+		if reward.accrued.is_zero() && reward.working_balance.is_zero() {
+			UserRewards::<T>::remove(who, asset);
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,104 @@
+use crate::*;
+use frame_support::traits::{
+	fungible::{hold::Mutate as _, Inspect as _, Mutate as _},
+	tokens::Restriction,
+};
+
+impl<T: Config> Pallet<T> {
+	/// Returns whether `asset` is the `NativeAsset`, the collateral held in place in the
+	/// accounts of the borrowers
+	pub fn is_native_asset(asset: AssetIdOf<T>) -> bool {
+		T::NativeAsset::get() == Some(asset)
+	}
+
+	/// Returns the balance of `collateral_asset` `who` can post as collateral
+	pub(crate) fn reducible_collateral(
+		who: &T::AccountId,
+		collateral_asset: AssetIdOf<T>,
+	) -> AssetBalanceOf<T> {
+		if Self::is_native_asset(collateral_asset) {
+			return T::NativeBalance::reducible_balance(
+				who,
+				Preservation::Preserve,
+				Fortitude::Polite,
+			);
+		}
+		T::Fungibles::reducible_balance(
+			collateral_asset,
+			who,
+			Preservation::Preserve,
+			Fortitude::Polite,
+		)
+	}
+
+	/// Posts `balance` of `collateral_asset` of `who` as the collateral of a borrow from the
+	/// pool of `pool_account`. The native currency is held in the account of `who`, so that it
+	/// keeps the provenance of its funds, the other assets move to the pool account.
+	pub(crate) fn post_collateral(
+		who: &T::AccountId,
+		collateral_asset: AssetIdOf<T>,
+		pool_account: &T::AccountId,
+		balance: AssetBalanceOf<T>,
+		preservation: Preservation,
+	) -> DispatchResult {
+		if Self::is_native_asset(collateral_asset) {
+			return T::NativeBalance::hold(&HoldReason::BorrowCollateral.into(), who, balance);
+		}
+		T::Fungibles::transfer(collateral_asset, who, pool_account, balance, preservation)?;
+		Ok(())
+	}
+
+	/// Pays `balance` of the collateral `collateral_asset` of the borrow of `who` out to `dest`.
+	/// The native currency is released from hold, then transferred from `who` when seized by
+	/// another account, the other assets leave the pool account.
+	pub(crate) fn release_collateral(
+		who: &T::AccountId,
+		collateral_asset: AssetIdOf<T>,
+		pool_account: &T::AccountId,
+		dest: &T::AccountId,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		if Self::is_native_asset(collateral_asset) {
+			T::NativeBalance::release(
+				&HoldReason::BorrowCollateral.into(),
+				who,
+				balance,
+				Precision::Exact,
+			)?;
+			if who != dest {
+				T::NativeBalance::transfer(who, dest, balance, Preservation::Expendable)?;
+			}
+			return Ok(());
+		}
+		T::Fungibles::transfer(
+			collateral_asset,
+			pool_account,
+			dest,
+			balance,
+			Preservation::Expendable,
+		)?;
+		Ok(())
+	}
+
+	/// Moves the hold on `balance` of the native collateral of a borrow from `from` to `to`
+	/// along with the borrow. The other assets stay in the pool account.
+	pub(crate) fn move_collateral(
+		from: &T::AccountId,
+		to: &T::AccountId,
+		collateral_asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		if Self::is_native_asset(collateral_asset) {
+			T::NativeBalance::transfer_on_hold(
+				&HoldReason::BorrowCollateral.into(),
+				from,
+				to,
+				balance,
+				Precision::Exact,
+				Restriction::OnHold,
+				Fortitude::Polite,
+			)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,30 @@
+use crate::*;
+
+/// Screens the accounts of the lending operations, e.g. against the sanctions lists or the KYC
+/// records of another pallet. The operations rejected fail with the error of the check, whatever
+/// their entry point: an extrinsic, the `Lending` trait or a remote origin.
+pub trait ComplianceCheck<AccountId, AssetId, Balance> {
+	/// Checks that `who` may supply `balance` of `asset`
+	fn check_supply(who: &AccountId, asset: AssetId, balance: Balance) -> DispatchResult;
+
+	/// Checks that `who` may borrow `balance` of `asset`
+	fn check_borrow(who: &AccountId, asset: AssetId, balance: Balance) -> DispatchResult;
+
+	/// Checks that `who` may withdraw `balance` of `asset`
+	fn check_withdraw(who: &AccountId, asset: AssetId, balance: Balance) -> DispatchResult;
+}
+
+/// All the accounts are allowed
+impl<AccountId, AssetId, Balance> ComplianceCheck<AccountId, AssetId, Balance> for () {
+	fn check_supply(_who: &AccountId, _asset: AssetId, _balance: Balance) -> DispatchResult {
+		Ok(())
+	}
+
+	fn check_borrow(_who: &AccountId, _asset: AssetId, _balance: Balance) -> DispatchResult {
+		Ok(())
+	}
+
+	fn check_withdraw(_who: &AccountId, _asset: AssetId, _balance: Balance) -> DispatchResult {
+		Ok(())
+	}
+}
@@ -0,0 +1,35 @@
+use crate::*;
+
+/// Delivers assets to the accounts of other chains, e.g. with reserve transfers sent through the
+/// `XcmRouter` of the runtime.
+pub trait TransferToChain<AccountId, AssetId, Balance, Location> {
+	/// Transfers `balance` of `asset` out of the account of `who` to the beneficiary `dest` of
+	/// another chain.
+	fn transfer_to_chain(
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+		dest: Location,
+	) -> DispatchResult;
+
+	/// The weight of a transfer to another chain
+	fn transfer_weight() -> Weight;
+}
+
+/// No other chain can be reached
+impl<AccountId, AssetId, Balance, Location> TransferToChain<AccountId, AssetId, Balance, Location>
+	for ()
+{
+	fn transfer_to_chain(
+		_who: &AccountId,
+		_asset: AssetId,
+		_balance: Balance,
+		_dest: Location,
+	) -> DispatchResult {
+		Err(DispatchError::Other("no other chain can be reached"))
+	}
+
+	fn transfer_weight() -> Weight {
+		Weight::zero()
+	}
+}
@@ -0,0 +1,103 @@
+use crate::*;
+
+impl<T: Config> Pallet<T> {
+	/// Moves up to `balance` of the debt of the borrow of `from` in `asset` against
+	/// `collateral_asset` onto the borrow of `to` in `asset` against `to_collateral_asset`, out of
+	/// the allowance `to` approved for `from`, unless both borrows are of the same account. The
+	/// collaterals stay where they are, but the collateral of a borrow left without debt is
+	/// released. Only the borrows at the variable rate share a debt index and can exchange debt.
+	/// Both accounts must be left without a shortfall. Returns the debt moved.
+	pub fn do_transfer_debt(
+		from: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+		to: &T::AccountId,
+		to_collateral_asset: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+		let from_key = (from.clone(), asset, collateral_asset);
+		let to_key = (to.clone(), asset, to_collateral_asset);
+		ensure!(from_key != to_key, Error::<T>::BorrowAlreadyExists);
+		Self::ensure_not_retired(from, asset, collateral_asset)?;
+		Self::ensure_not_retired(to, asset, to_collateral_asset)?;
+		ensure!(
+			!StableBorrows::<T>::contains_key(&from_key) &&
+				!StableBorrows::<T>::contains_key(&to_key),
+			Error::<T>::BorrowRateModeMismatch
+		);
+
+		let asset_pool = AssetPool::<T>::from(asset);
+		let mut pool =
+			LendingPoolStorage::<T>::get(&asset_pool).ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+		let mut loan = Borrows::<T>::get(&from_key).ok_or(Error::<T>::LoanDoesNotExists)?;
+		let mut to_loan = Borrows::<T>::get(&to_key).ok_or(Error::<T>::LoanDoesNotExists)?;
+
+		let repayable_balance =
+			Self::repayable_balance(from, asset, collateral_asset, &pool, &loan)?;
+		let balance = balance.min(repayable_balance);
+		let is_full_transfer = balance == repayable_balance;
+		ensure!(
+			is_full_transfer ||
+				repayable_balance.ensure_sub(balance)? >= MinBorrow::<T>::get(asset),
+			Error::<T>::BorrowTooSmall
+		);
+		if from != to {
+			DebtAllowances::<T>::try_mutate((to, from, asset), |allowance| {
+				*allowance = allowance.checked_sub(&balance).ok_or(Error::<T>::DebtNotApproved)?;
+				Ok::<(), Error<T>>(())
+			})?;
+		}
+
+		// both borrows are at the debt index of the pool, the scaled debt moves as it is
+		let scaled_balance = match is_full_transfer {
+			true => loan.borrowed_balance,
+			false => math::div_rate(balance, pool.borrow_index, Rounding::Down)
+				.ok_or(Error::<T>::OverflowError)?,
+		};
+		let principal = match loan.principal {
+			Some(principal) => Some(
+				math::mul_div(principal, scaled_balance, loan.borrowed_balance, Rounding::Down)
+					.ok_or(Error::<T>::OverflowError)?,
+			),
+			None => None,
+		};
+		to_loan.increase_borrow(&UserBorrow {
+			borrowed_asset: asset,
+			borrowed_balance: scaled_balance,
+			collateral_asset: to_collateral_asset,
+			collateral_balance: Zero::zero(),
+			principal,
+		})?;
+		Borrows::<T>::insert(&to_key, to_loan);
+
+		if is_full_transfer {
+			Borrows::<T>::remove(&from_key);
+			MarginCalls::<T>::remove(&from_key);
+			AccountBorrows::<T>::mutate(from, |borrows| {
+				borrows.retain(|borrow| borrow != &(asset, collateral_asset))
+			});
+			Self::checkpoint_k_tokens(from, collateral_asset);
+			Self::release_collateral(
+				from,
+				collateral_asset,
+				&Self::pool_account_id(pool.id),
+				from,
+				loan.collateral_balance,
+			)?;
+		} else {
+			loan.repay_partial(scaled_balance, Zero::zero())?;
+			loan.principal = match (loan.principal, principal) {
+				(Some(remaining), Some(moved)) => Some(remaining.saturating_sub(moved)),
+				_ => None,
+			};
+			Borrows::<T>::insert(&from_key, loan);
+		}
+		LendingPoolStorage::<T>::insert(&asset_pool, pool);
+
+		Self::ensure_no_shortfall(from)?;
+		Self::ensure_no_shortfall(to)?;
+		Ok(balance)
+	}
+}
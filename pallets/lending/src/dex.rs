@@ -0,0 +1,58 @@
+use crate::*;
+
+/// The liquidity pools of a DEX, e.g. the `pallet-asset-conversion`, whose LP tokens may be
+/// accepted as collateral
+pub trait DexPools<AssetId, Balance> {
+	/// The assets of the liquidity pool of `lp_token`, `None` if it is not an LP token of the DEX
+	fn pool_assets(lp_token: AssetId) -> Option<(AssetId, AssetId)>;
+
+	/// The reserves of the assets of the liquidity pool of `lp_token`, in the order of
+	/// `pool_assets`, and the LP tokens issued
+	fn pool_reserves(lp_token: AssetId) -> Option<(Balance, Balance, Balance)>;
+}
+
+/// No DEX is available
+impl<AssetId, Balance> DexPools<AssetId, Balance> for () {
+	fn pool_assets(_lp_token: AssetId) -> Option<(AssetId, AssetId)> {
+		None
+	}
+
+	fn pool_reserves(_lp_token: AssetId) -> Option<(Balance, Balance, Balance)> {
+		None
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Accepts the LP tokens `lp_token` of the DEX as collateral, or bans them
+	pub fn do_set_dex_lp_collateral(
+		lp_token: AssetIdOf<T>,
+		enabled: bool,
+	) -> Result<Option<(AssetIdOf<T>, AssetIdOf<T>)>, DispatchError> {
+		let assets = match enabled {
+			true => Some(T::DexPools::pool_assets(lp_token).ok_or(Error::<T>::NotDexLpToken)?),
+			false => None,
+		};
+		DexLpCollaterals::<T>::set(lp_token, assets);
+		Ok(assets)
+	}
+
+	/// Returns the value in `asset` of the liquidity pool of the LP tokens `lp_token` of the DEX
+	/// and the LP tokens issued. The pool is valued at its fair value, twice the geometric mean
+	/// of the values of its reserves at the oracle prices, which the reserves of a constant
+	/// product pool can not be moved away from by a swap, so that the value of the LP tokens
+	/// can not be inflated by skewing the pool.
+	pub(crate) fn dex_lp_value(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		lp_token: AssetIdOf<T>,
+		(asset1, asset2): (AssetIdOf<T>, AssetIdOf<T>),
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let (reserve1, reserve2, issued) =
+			T::DexPools::pool_reserves(lp_token).ok_or(Error::<T>::NotDexLpToken)?;
+		ensure!(!issued.is_zero(), ArithmeticError::DivisionByZero);
+		let value1 = Self::get_equivalent_asset_amount(who, asset, asset1, reserve1)?;
+		let value2 = Self::get_equivalent_asset_amount(who, asset, asset2, reserve2)?;
+		let value = math::fair_lp_value(value1, value2).ok_or(ArithmeticError::Overflow)?;
+		Ok((value, issued))
+	}
+}
@@ -0,0 +1,77 @@
+use crate::*;
+
+impl<T: Config> Pallet<T> {
+	/// Returns the kTokens `lp_token` of `who` frozen for any reason, the largest of their
+	/// freezes as they overlap
+	pub fn k_tokens_frozen(who: &T::AccountId, lp_token: AssetIdOf<T>) -> AssetBalanceOf<T> {
+		KTokenFreezes::<T>::get(who, lp_token)
+			.iter()
+			.map(|(_, balance)| *balance)
+			.max()
+			.unwrap_or_else(Zero::zero)
+	}
+
+	/// Returns the kTokens `lp_token` of `who` which can be redeemed, transferred or locked as
+	/// collateral, neither frozen nor held
+	pub fn reducible_k_tokens(who: &T::AccountId, lp_token: AssetIdOf<T>) -> AssetBalanceOf<T> {
+		T::Fungibles::reducible_balance(lp_token, who, Preservation::Expendable, Fortitude::Polite)
+			.saturating_sub(Self::k_tokens_frozen(who, lp_token))
+	}
+
+	/// Ensures that `balance` of the asset `lp_token` of `who` may leave the account, out of
+	/// its frozen kTokens
+	pub(crate) fn ensure_k_tokens_unfrozen(
+		who: &T::AccountId,
+		lp_token: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let frozen = Self::k_tokens_frozen(who, lp_token);
+		if frozen.is_zero() {
+			return Ok(());
+		}
+		let remaining = T::Fungibles::balance(lp_token, who).saturating_sub(balance);
+		ensure!(remaining >= frozen, Error::<T>::KTokensFrozen);
+		Ok(())
+	}
+
+	/// Freezes `balance` of the kTokens `lp_token` of `who` for `reason`, in place of its
+	/// earlier freeze for the reason, or thaws them with a zero `balance`. The programs locking
+	/// the kTokens, e.g. staking or boosts, do so without taking custody of them: they keep
+	/// earning the interests and the rewards of `who`.
+	pub fn set_k_token_freeze(
+		reason: &T::RuntimeFreezeReason,
+		who: &T::AccountId,
+		lp_token: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(LpTokenPools::<T>::contains_key(lp_token), Error::<T>::NotKToken);
+		ensure!(T::Fungibles::balance(lp_token, who) >= balance, Error::<T>::InsufficientKTokens);
+		KTokenFreezes::<T>::try_mutate_exists(who, lp_token, |freezes| -> DispatchResult {
+			let mut entries = freezes.take().unwrap_or_default();
+			entries.retain(|(r, _)| r != reason);
+			if !balance.is_zero() {
+				entries
+					.try_push((*reason, balance))
+					.map_err(|_| Error::<T>::TooManyKTokenFreezes)?;
+			}
+			*freezes = (!entries.is_empty()).then_some(entries);
+			Ok(())
+		})?;
+		Self::deposit_event(Event::KTokensFrozen {
+			who: who.clone(),
+			lp_token,
+			reason: *reason,
+			balance,
+		});
+		Ok(())
+	}
+
+	/// Thaws the kTokens `lp_token` of `who` frozen for `reason`
+	pub fn thaw_k_tokens(
+		reason: &T::RuntimeFreezeReason,
+		who: &T::AccountId,
+		lp_token: AssetIdOf<T>,
+	) -> DispatchResult {
+		Self::set_k_token_freeze(reason, who, lp_token, Zero::zero())
+	}
+}
@@ -0,0 +1,140 @@
+//! Applies random sequences of supplies, withdrawals, borrows, repayments, liquidations, price
+//! moves and time jumps to the mock runtime, and checks the `try_state` invariants after each
+//! step, so that an accounting drift is caught at the operation introducing it.
+//!
+//! Run with `cargo test -p lending --features fuzz fuzz`. The runs are seeded from `0` to
+//! `FUZZ_RUNS`, `FUZZ_SEED` replays the single run of a failing seed.
+
+use crate::mock::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sp_runtime::FixedU128;
+
+const DOT: u32 = 1;
+const KYL: u32 = 2;
+const ASSETS: [u32; 2] = [DOT, KYL];
+const ACCOUNTS: [u128; 4] = [11, 12, 13, 14];
+const LIQUIDATOR: u128 = 15;
+const INITIAL_BALANCE: u128 = 1_000_000_000;
+const RUNS: u64 = 32;
+const STEPS: usize = 200;
+
+#[derive(Debug)]
+enum Operation {
+	Supply { who: u128, asset: u32, balance: u128 },
+	Withdraw { who: u128, asset: u32, balance: u128 },
+	Borrow { who: u128, asset: u32, balance: u128, collateral_asset: u32, collateral: u128 },
+	Repay { who: u128, asset: u32, balance: u128, collateral_asset: u32 },
+	Liquidate { borrower: u128, asset: u32, collateral_asset: u32 },
+	// the price of KYL in DOT, in hundredths
+	Reprice { cents: u64 },
+	Advance { seconds: u64 },
+}
+
+impl Operation {
+	fn random(rng: &mut StdRng) -> Self {
+		let who = ACCOUNTS[rng.gen_range(0..ACCOUNTS.len())];
+		let asset = ASSETS[rng.gen_range(0..ASSETS.len())];
+		let other = if asset == DOT { KYL } else { DOT };
+		let balance = rng.gen_range(1..=INITIAL_BALANCE / 10);
+		match rng.gen_range(0..7) {
+			0 => Operation::Supply { who, asset, balance },
+			1 => Operation::Withdraw { who, asset, balance },
+			2 => Operation::Borrow {
+				who,
+				asset,
+				balance,
+				collateral_asset: other,
+				collateral: balance.saturating_mul(rng.gen_range(1..=3)),
+			},
+			3 => Operation::Repay { who, asset, balance, collateral_asset: other },
+			4 => Operation::Liquidate { borrower: who, asset, collateral_asset: other },
+			5 => Operation::Reprice { cents: rng.gen_range(10..=1_000) },
+			_ => Operation::Advance { seconds: rng.gen_range(1..=30 * 24 * 60 * 60) },
+		}
+	}
+
+	// The operations are expected to fail often, e.g. without the balances or the health they
+	// require: the invariants must hold whatever their outcome
+	fn apply(self) {
+		let signed = RuntimeOrigin::signed;
+		let _ = match self {
+			Operation::Supply { who, asset, balance } =>
+				TemplateModule::supply(signed(who), asset, balance, None),
+			Operation::Withdraw { who, asset, balance } => TemplateModule::withdraw(
+				signed(who),
+				asset,
+				balance,
+			)
+			.map(|_| ())
+			.map_err(|e| e.error),
+			Operation::Borrow { who, asset, balance, collateral_asset, collateral } =>
+				TemplateModule::borrow(
+					signed(who),
+					asset,
+					balance,
+					collateral_asset,
+					collateral,
+					None,
+				)
+				.map(|_| ())
+				.map_err(|e| e.error),
+			Operation::Repay { who, asset, balance, collateral_asset } =>
+				TemplateModule::repay(signed(who), asset, balance, collateral_asset),
+			Operation::Liquidate { borrower, asset, collateral_asset } =>
+				TemplateModule::liquidate_borrow(
+					signed(LIQUIDATOR),
+					borrower,
+					asset,
+					collateral_asset,
+				),
+			Operation::Reprice { cents } => {
+				MockOracle::set_price(KYL, DOT, FixedU128::from_rational(cents.into(), 100));
+				Ok(())
+			},
+			Operation::Advance { seconds } => {
+				Timestamp::set_timestamp(Timestamp::get() + seconds * 1_000);
+				System::set_block_number(System::block_number() + 1);
+				Ok(())
+			},
+		};
+	}
+}
+
+fn run(seed: u64) {
+	let mut builder = ExtBuilder::default().with_price(KYL, DOT, FixedU128::from(1));
+	for asset in ASSETS {
+		let holders = ACCOUNTS
+			.iter()
+			.chain([&ASSET_OWNER, &LIQUIDATOR])
+			.map(|who| (*who, INITIAL_BALANCE))
+			.collect();
+		builder = builder.with_asset(asset, holders);
+	}
+	builder
+		.with_pool(0, DOT, ASSET_OWNER, INITIAL_BALANCE / 2)
+		.with_pool(12, KYL, ASSET_OWNER, INITIAL_BALANCE / 2)
+		.build()
+		.execute_with(|| {
+			let mut rng = StdRng::seed_from_u64(seed);
+			for step in 0..STEPS {
+				let operation = Operation::random(&mut rng);
+				let description = format!("{operation:?}");
+				operation.apply();
+				if let Err(e) = TemplateModule::do_try_state() {
+					panic!("seed {seed}, step {step}: {description} broke an invariant: {e:?}");
+				}
+			}
+		});
+}
+
+fn env_var(name: &str) -> Option<u64> {
+	std::env::var(name).ok().map(|value| value.parse().expect("a number"))
+}
+
+#[test]
+fn random_operations_preserve_the_invariants() {
+	match env_var("FUZZ_SEED") {
+		Some(seed) => run(seed),
+		None => (0..env_var("FUZZ_RUNS").unwrap_or(RUNS)).for_each(run),
+	}
+}
@@ -0,0 +1,69 @@
+use crate::*;
+
+/// Callbacks of the lending operations, so that other pallets, e.g. points programs, analytics
+/// or insurance, can react to them. They are called once the operation has succeeded, whatever
+/// its entry point: an extrinsic, the `Lending` trait or a remote origin.
+pub trait LendingHooks<AccountId, AssetId, Balance> {
+	/// `who` supplied `balance` of `asset`
+	fn on_supply(who: &AccountId, asset: AssetId, balance: Balance);
+
+	/// `who` borrowed `balance` of `asset` against `collateral_balance` of `collateral_asset`
+	fn on_borrow(
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+		collateral_balance: Balance,
+	);
+
+	/// `payer` repaid `balance` of the borrow of `who` in `asset` against `collateral_asset`,
+	/// including on liquidation
+	fn on_repay(
+		payer: &AccountId,
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+	);
+
+	/// `liquidator` liquidated the borrow of `who` in `asset` against `collateral_asset`,
+	/// repaying `balance`
+	fn on_liquidation(
+		liquidator: &AccountId,
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+	);
+}
+
+impl<AccountId, AssetId, Balance> LendingHooks<AccountId, AssetId, Balance> for () {
+	fn on_supply(_who: &AccountId, _asset: AssetId, _balance: Balance) {}
+
+	fn on_borrow(
+		_who: &AccountId,
+		_asset: AssetId,
+		_balance: Balance,
+		_collateral_asset: AssetId,
+		_collateral_balance: Balance,
+	) {
+	}
+
+	fn on_repay(
+		_payer: &AccountId,
+		_who: &AccountId,
+		_asset: AssetId,
+		_balance: Balance,
+		_collateral_asset: AssetId,
+	) {
+	}
+
+	fn on_liquidation(
+		_liquidator: &AccountId,
+		_who: &AccountId,
+		_asset: AssetId,
+		_balance: Balance,
+		_collateral_asset: AssetId,
+	) {
+	}
+}
@@ -0,0 +1,148 @@
+use crate::*;
+use frame_support::traits::fungible::Mutate as _;
+
+/// A liquidator registered with a bond for the exclusivity of the liquidations detected by the
+/// offchain worker
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Keeper<Balance, BlockNumber> {
+	/// The bond, in the native currency, slashed after `MaxKeeperMisses` missed liquidations
+	pub bond: Balance,
+	/// The liquidations missed in a row
+	pub misses: u32,
+	/// The keeper can not deregister before this block, so that the liquidations assigned to
+	/// it are settled, and its misses recorded, first
+	pub busy_until: BlockNumber,
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account holding the bonds of the keepers
+	pub fn keepers_account_id() -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(b"keepers")
+	}
+
+	/// Registers `who` as a keeper, bonding the `KeeperBond`
+	pub fn do_register_keeper(who: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+		ensure!(!Keepers::<T>::contains_key(who), Error::<T>::KeeperAlreadyRegistered);
+		let bond = T::KeeperBond::get();
+		T::NativeBalance::transfer(who, &Self::keepers_account_id(), bond, Preservation::Preserve)?;
+		Keepers::<T>::insert(who, Keeper { bond, misses: 0, busy_until: Zero::zero() });
+		Ok(bond)
+	}
+
+	/// Deregisters the keeper `who` and returns its bond, once the liquidations assigned to it
+	/// are settled
+	pub fn do_deregister_keeper(who: &T::AccountId) -> Result<BalanceOf<T>, DispatchError> {
+		let keeper = Keepers::<T>::get(who).ok_or(Error::<T>::NotKeeper)?;
+		ensure!(
+			frame_system::Pallet::<T>::block_number() > keeper.busy_until,
+			Error::<T>::KeeperBusy
+		);
+		T::NativeBalance::transfer(
+			&Self::keepers_account_id(),
+			who,
+			keeper.bond,
+			Preservation::Expendable,
+		)?;
+		Keepers::<T>::remove(who);
+		Ok(keeper.bond)
+	}
+
+	/// Assigns the liquidation of the borrow of `borrower` to the next keeper in turn, the only
+	/// one allowed to liquidate it for the `KeeperExclusivity`. Returns the keeper and the last
+	/// block of its exclusivity.
+	pub fn do_assign_liquidation(
+		borrower: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<(T::AccountId, BlockNumberFor<T>), DispatchError> {
+		let shortfall = Self::shortfall(borrower, asset, collateral_asset)?;
+		ensure!(!shortfall.is_zero(), Error::<T>::BorrowNotLiquidatable);
+		ensure!(!Self::stall_grace_period(), Error::<T>::StallGracePeriod);
+		ensure!(
+			Self::grace_period_over(borrower, asset, collateral_asset, shortfall)?,
+			Error::<T>::MarginCallGracePeriod
+		);
+		let key = (borrower.clone(), asset, collateral_asset);
+		ensure!(
+			!LiquidationAssignments::<T>::contains_key(&key),
+			Error::<T>::LiquidationAlreadyAssigned
+		);
+
+		// the keepers take turns, carrying on after the cursor and starting over at the end
+		let next = match KeeperCursor::<T>::get() {
+			Some(last) => Keepers::<T>::iter_keys_from(Keepers::<T>::hashed_key_for(last)).next(),
+			None => None,
+		};
+		let keeper =
+			next.or_else(|| Keepers::<T>::iter_keys().next()).ok_or(Error::<T>::NoKeepers)?;
+		let until =
+			frame_system::Pallet::<T>::block_number().saturating_add(T::KeeperExclusivity::get());
+
+		// the misses of the keeper are recorded by the fallback liquidation of the offchain
+		// worker, submitted at its next scan after the exclusivity
+		Keepers::<T>::mutate(&keeper, |info| {
+			if let Some(info) = info {
+				info.busy_until =
+					info.busy_until.max(until.saturating_add(T::OffchainScanInterval::get()));
+			}
+		});
+		KeeperCursor::<T>::put(&keeper);
+		LiquidationAssignments::<T>::insert(&key, (keeper.clone(), until));
+		Ok((keeper, until))
+	}
+
+	/// Settles the assignment of the liquidation of the borrow of `borrower` by `liquidator`:
+	/// the borrow is reserved to its keeper during the exclusivity, the keeper acting clears its
+	/// misses and anyone else liquidating it afterwards records a miss of the keeper, whose
+	/// bond goes to the `TreasuryAccount` at the `MaxKeeperMisses`-th miss in a row
+	pub(crate) fn settle_liquidation_assignment(
+		liquidator: &T::AccountId,
+		borrower: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> DispatchResult {
+		let key = (borrower.clone(), asset, collateral_asset);
+		let Some((keeper, until)) = LiquidationAssignments::<T>::take(&key) else {
+			return Ok(());
+		};
+		if &keeper == liquidator {
+			Keepers::<T>::mutate(&keeper, |info| {
+				if let Some(info) = info {
+					info.misses = 0;
+				}
+			});
+			return Ok(());
+		}
+		ensure!(frame_system::Pallet::<T>::block_number() > until, Error::<T>::LiquidationReserved);
+
+		let Some(mut info) = Keepers::<T>::get(&keeper) else {
+			return Ok(());
+		};
+		info.misses = info.misses.saturating_add(1);
+		Self::deposit_event(Event::KeeperMissed { who: keeper.clone(), misses: info.misses });
+		if info.misses < T::MaxKeeperMisses::get() {
+			Keepers::<T>::insert(&keeper, info);
+			return Ok(());
+		}
+
+		T::NativeBalance::transfer(
+			&Self::keepers_account_id(),
+			&T::TreasuryAccount::get(),
+			info.bond,
+			Preservation::Expendable,
+		)?;
+		Keepers::<T>::remove(&keeper);
+		Self::deposit_event(Event::KeeperSlashed { who: keeper, bond: info.bond });
+		Ok(())
+	}
+
+	/// Returns whether the liquidation of the borrow of `borrower` is reserved to a keeper
+	pub fn liquidation_reserved(
+		borrower: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> bool {
+		LiquidationAssignments::<T>::get((borrower, asset, collateral_asset))
+			.is_some_and(|(_, until)| frame_system::Pallet::<T>::block_number() <= until)
+	}
+}
@@ -25,6 +25,71 @@
 ///! 7. deactivate_lending_pool()
 ///! 8. update_pool_rate_model()
 ///! 9. update_pool_kink()
+///! 10. set_asset_price()
+///! 11. set_reward_program()
+///! 12. claim_vested_rewards()
+///! 13. compound_rewards()
+///! 14. set_auto_compound()
+///! 15. compound_rewards_for()
+///! 16. update_reward_boost()
+///! 17. fund_rewards()
+///! 18. register_referral_code()
+///! 19. claim_referral_earnings()
+///! 20. set_borrow_fee()
+///! 21. set_protocol_fee()
+///! 22. liquidate_borrow()
+///! 23. accrue_interest()
+///! 24. set_min_borrow()
+///! 25. write_off_dust_debt()
+///! 26. withdraw_best_effort()
+///! 27. supply_from_remote()
+///! 28. borrow_to_chain()
+///! 29. liquidate_borrow_with_swap()
+///! 30. transfer_lp_tokens()
+///! 31. set_lp_collateral_factor()
+///! 32. borrow_stable()
+///! 33. rebalance_stable_rate()
+///! 34. set_stable_rate_premium()
+///! 35. switch_borrow_rate_mode()
+///! 36. borrow_fixed_term()
+///! 37. set_borrow_rate_bounds()
+///! 38. set_outflow_cap()
+///! 39. set_maintenance_mode()
+///! 40. announce_param_change()
+///! 41. execute_param_change()
+///! 42. cancel_param_change()
+///! 43. set_permissioned_pool()
+///! 44. set_whitelisted()
+///! 45. force_liquidate()
+///! 46. self_liquidate()
+///! 47. rescue_funds()
+///! 48. force_update_pool_params()
+///! 49. wrap_position()
+///! 50. transfer_position()
+///! 51. unwrap_position()
+///! 52. approve_debt()
+///! 53. transfer_debt()
+///! 69. set_dex_lp_collateral()
+///! 70. register_keeper()
+///! 71. deregister_keeper()
+///! 72. assign_liquidation()
+///! 73. margin_call()
+///! 74. add_collateral()
+///! 75. report_price_deviation()
+///! 76. set_pool_paused()
+///! 77. approve_operator()
+///! 78. operate()
+///! 79. withdraw_all()
+///! 80. withdraw_shares()
+///! 81. set_loyalty_tiers()
+///! 82. repay_many()
+///! 83. replace_lending_pool()
+///! 84. migrate_positions()
+///! 85. redenominate_pool()
+///! 86. set_account_supply_cap()
+///! 87. set_account_tier()
+///! 88. set_tier_terms()
+///! 89. set_risk_parameter()
 ///!
 ///
 /// TODO:
@@ -36,22 +101,26 @@
 ///! Use case
 pub use frame_support::{
 	pallet_prelude::*,
-	sp_runtime::{FixedU128, Permill, SaturatedConversion},
+	sp_runtime::{FixedU128, Permill, Rounding, SaturatedConversion},
 	traits::{fungible, fungibles},
 };
 pub use frame_support::{
 	sp_runtime,
-	sp_runtime::traits::{
-		AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero,
+	sp_runtime::{
+		traits::{
+			AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, EnsureAdd,
+			EnsureSub, One, Zero,
+		},
+		ArithmeticError,
 	},
 	traits::{
 		fungibles::{Create, Inspect, Mutate},
 		tokens::{Fortitude, Precision, Preservation},
-		Time as MomentTime,
+		Contains, UnixTime,
 	},
 	DefaultNoBound, PalletId,
 };
-pub use frame_system::pallet_prelude::*;
+pub use frame_system::{offchain::SendTransactionTypes, pallet_prelude::*};
 pub use pallet::*;
 pub use sp_runtime::FixedPointNumber;
 
@@ -59,7 +128,7 @@ pub use sp_runtime::FixedPointNumber;
 pub type AccountOf<T> = <T as frame_system::Config>::AccountId;
 
 /// Fungible Asset Id
-pub type AssetIdOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<AccountOf<T>>>::AssetId;
+pub type AssetIdOf<T> = <T as Config>::AssetId;
 
 /// Fungible Balance
 pub type AssetBalanceOf<T> =
@@ -72,18 +141,136 @@ pub type BalanceOf<T> = <<T as Config>::NativeBalance as fungible::Inspect<Accou
 pub type Timestamp = u64;
 pub type Rate = FixedU128;
 pub type Ratio = Permill;
-pub type LendingPoolId = u32;
 pub const SECONDS_PER_YEAR: u64 = 365u64 * 24 * 60 * 60;
 
+/// Builds the asset ids of the benchmarks, whatever the asset id type of the runtime.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AssetId> {
+	/// The asset id numbered `id`
+	fn asset_id(id: u32) -> AssetId;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<AssetId: From<u32>> BenchmarkHelper<AssetId> for () {
+	fn asset_id(id: u32) -> AssetId {
+		id.into()
+	}
+}
+
 mod borrow_repay;
 use borrow_repay::UserBorrow;
 
-#[cfg(test)]
-mod mock;
+mod rewards;
+pub use rewards::{
+	GovernanceLocks, RewardProgram, UserReward, VestingSchedule, VestingTerms,
+	MAX_VESTING_SCHEDULES,
+};
+
+mod referral;
+pub use referral::ReferralCode;
+
+mod liquidation;
+
+mod stable;
+pub use stable::StableRate;
+
+mod stats;
+pub use stats::ProtocolTotals;
+
+mod offchain;
+
+mod cleanup;
+
+mod cross_chain;
+pub use cross_chain::TransferToChain;
+
+mod swap;
+pub use swap::SwapProvider;
+
+mod market;
+pub use market::Lending;
+#[cfg(feature = "runtime-benchmarks")]
+pub use market::LendingBenchmarkHelper;
+
+mod hooks;
+pub use hooks::LendingHooks;
+
+mod compliance;
+pub use compliance::ComplianceCheck;
+
+mod maintenance;
+pub use maintenance::MaintenanceFilter;
+
+mod params;
+pub use params::{
+	ParamChange, ParamChangeId, PendingParamChange, PoolParamsUpdate, RiskParameter,
+};
+
+mod rescue;
+
+mod debt;
+
+mod position;
+pub use position::{NonfungiblesPositions, PositionId, PositionNfts};
+
+mod dex;
+pub use dex::DexPools;
+
+mod keeper;
+pub use keeper::Keeper;
+
+mod margin;
+
+mod price_guard;
+pub use price_guard::PriceReference;
+
+mod operator;
+pub use operator::OperatorPermissions;
+
+mod snapshot;
+
+mod freeze;
+
+mod lp_tokens;
+pub use lp_tokens::LpTokenGuard;
+
+mod collateral;
+
+mod loyalty;
+pub use loyalty::LoyaltyTier;
+
+mod stall;
+
+mod replacement;
+pub use replacement::MAX_POSITION_MIGRATIONS;
+
+mod redenomination;
+
+mod tier;
+pub use tier::{AccountTier, AccountTierProvider, TierTerms};
+
+pub mod math;
+
+#[cfg(any(feature = "try-runtime", test))]
+mod try_state;
+
+pub mod migrations;
+
+mod api;
+pub use api::{
+	AccountOverview, AccountSnapshot, Action, BorrowPosition, MaxBorrow, Page, PoolInfo,
+	Simulation, SupplyPosition,
+};
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(all(test, feature = "fuzz"))]
+mod fuzz;
+
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 pub mod weights;
@@ -92,37 +279,339 @@ pub use weights::*;
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
+	use frame_support::{
+		dispatch::GetDispatchInfo,
+		sp_std::boxed::Box,
+		traits::{
+			schedule::v3::Named as ScheduleNamed, PalletsOriginOf, QueryPreimage, StorePreimage,
+		},
+	};
+
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	#[pallet::config]
-	pub trait Config: frame_system::Config {
+	pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
 		#[pallet::constant]
 		type PalletId: Get<PalletId>;
 
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
+		/// The overarching freeze reason, the reasons the kTokens are frozen for by this pallet
+		/// and by the programs locking them, e.g. staking or boosts
+		type RuntimeFreezeReason: From<FreezeReason> + Parameter + MaxEncodedLen + Copy;
+
+		/// The overarching hold reason, the native collateral of the borrows is held for
+		type RuntimeHoldReason: From<HoldReason>;
+
 		/// Type to access the Balances Pallet.
 		type NativeBalance: fungible::Inspect<Self::AccountId>
 			+ fungible::Mutate<Self::AccountId>
-			+ fungible::hold::Inspect<Self::AccountId>
+			+ fungible::hold::Inspect<Self::AccountId, Reason = Self::RuntimeHoldReason>
 			+ fungible::hold::Mutate<Self::AccountId>
 			+ fungible::freeze::Inspect<Self::AccountId>
 			+ fungible::freeze::Mutate<Self::AccountId>;
 
+		/// The identifier of the assets, the lent assets as well as the LP tokens of the pools.
+		type AssetId: Parameter + MaxEncodedLen + Ord + Copy + Default;
+
 		/// Type to access the Assets Pallet.
-		type Fungibles: fungibles::Inspect<Self::AccountId, Balance = BalanceOf<Self>, AssetId = u32>
-			+ fungibles::Mutate<Self::AccountId>
-			+ fungibles::Create<Self::AccountId>;
+		///
+		/// It may route the assets to several asset providers by asset id, e.g. to the local and
+		/// the foreign instances of the Assets Pallet. The LP tokens of a pool are then created in
+		/// the provider its id is routed to.
+		///
+		/// The decimals of the assets are read from their metadata to value them against each
+		/// other, an asset without metadata has no decimals.
+		///
+		/// The LP tokens may only move through the pallet: the Assets Pallet holding them should
+		/// have the pallet as its `Freezer`, and be wrapped into the `LpTokenGuard`.
+		type Fungibles: fungibles::Inspect<
+				Self::AccountId,
+				Balance = BalanceOf<Self>,
+				AssetId = Self::AssetId,
+			> + fungibles::Mutate<Self::AccountId>
+			+ fungibles::Create<Self::AccountId>
+			+ fungibles::metadata::Inspect<Self::AccountId>;
+
+		/// The asset the prices are quoted in as a last resort, the pricing unit of the values
+		/// returned by the runtime API.
+		#[pallet::constant]
+		type BaseAsset: Get<Self::AssetId>;
+
+		/// The native currency among the assets, if it is routed by the `Fungibles`. As a
+		/// collateral, it is held in place in the accounts of the borrowers through the
+		/// `NativeBalance` rather than moved to the pool accounts.
+		#[pallet::constant]
+		type NativeAsset: Get<Option<Self::AssetId>>;
 
 		/// The origin which can add or remove LendingPools and update LendingPools TODO
-		// type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// For the time being it manages the reward programs of the lending pools.
+		/// The governance of another chain can be admitted along with the local one, e.g. with
+		/// `EitherOfDiverse<EnsureRoot, EnsureXcm<IsMajorityOfBody<RelayLocation, ExecutiveBody>>>`
+		/// for the XCM origins of the relay chain governance converted by the `OriginConverter`
+		/// of the XCM executor.
+		type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The origin which can set the prices of the assets, e.g. the oracle feeding them. The
+		/// liquidations, margin calls and price deviation pauses of the lending pools all rely on
+		/// these prices.
+		type OracleOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The origin of the users of other chains, resolving to the local account derived from
+		/// their remote location. In a parachain runtime, the XCM origins of the sibling
+		/// parachains (`ensure_sibling_para`) converted with the location to account converter
+		/// of the XCM executor.
+		type RemoteOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+
+		/// The locations of the accounts of other chains, e.g. XCM `Location`s.
+		type Location: Parameter;
+
+		/// Delivers the borrowed assets to other chains, e.g. with reserve transfers sent through
+		/// the `XcmRouter`.
+		type TransferToChain: TransferToChain<
+			Self::AccountId,
+			AssetIdOf<Self>,
+			AssetBalanceOf<Self>,
+			Self::Location,
+		>;
+
+		/// The DEX the seized collateral is swapped on for the repaid asset by the liquidations
+		/// with a swap, e.g. the `pallet-asset-conversion`.
+		type SwapProvider: SwapProvider<Self::AccountId, AssetIdOf<Self>, AssetBalanceOf<Self>>;
+
+		/// The liquidity pools of the DEX whose LP tokens may be accepted as collateral, e.g. the
+		/// `pallet-asset-conversion`.
+		type DexPools: DexPools<AssetIdOf<Self>, AssetBalanceOf<Self>>;
+
+		/// The callbacks of the supplies, borrows, repayments and liquidations.
+		type LendingHooks: LendingHooks<Self::AccountId, AssetIdOf<Self>, AssetBalanceOf<Self>>;
+
+		/// Screens the accounts supplying, borrowing and withdrawing, e.g. with a sanctions or a
+		/// KYC pallet.
+		type ComplianceCheck: ComplianceCheck<
+			Self::AccountId,
+			AssetIdOf<Self>,
+			AssetBalanceOf<Self>,
+		>;
+
+		/// Provides the tiers of the accounts without one assigned by `set_account_tier`, e.g.
+		/// from an identity pallet.
+		type AccountTierProvider: AccountTierProvider<Self::AccountId>;
+
+		/// The NFTs of the borrows wrapped into transferable positions, e.g. the
+		/// `NonfungiblesPositions` of the `pallet-nfts`.
+		type PositionNfts: PositionNfts<Self::AccountId>;
+
+		/// The assets the pallets built on the `Lending` trait use beyond the lending pools, e.g.
+		/// the collaterals of the CDPs, which a redenomination or a replacement of their lending
+		/// pools can not carry over.
+		type AssetsInUse: Contains<Self::AssetId>;
 
 		/// Weight information for extrinsics in this pallet.
 		type WeightInfo: WeightInfo;
 
-		type Time: MomentTime;
+		/// The wall clock the interest accrues and the rewards emit with, per second whatever the
+		/// block time.
+		type Time: UnixTime;
+
+		/// The share of compounded rewards paid to the keeper compounding on behalf of a user.
+		#[pallet::constant]
+		type CompoundTip: Get<Permill>;
+
+		/// The maximum number of lending pools an account can participate in, and of borrows it
+		/// can hold, each against its own collateral.
+		#[pallet::constant]
+		type MaxMarketsPerAccount: Get<u32>;
+
+		/// The maximum number of lending pools
+		#[pallet::constant]
+		type MaxPools: Get<u32>;
+
+		/// The governance tokens locked by accounts, boosting their rewards.
+		type GovernanceLocks: GovernanceLocks<Self::AccountId, BalanceOf<Self>>;
+
+		/// The maximum multiplier applied to the rewards of an account thanks to its locked
+		/// governance tokens.
+		#[pallet::constant]
+		type MaxRewardBoost: Get<FixedU128>;
+
+		/// The maximum number of the loyalty tiers of a lending pool
+		#[pallet::constant]
+		type MaxLoyaltyTiers: Get<u32>;
+
+		/// The maximum bonus on the rewards of the long-term suppliers of a lending pool
+		#[pallet::constant]
+		type MaxLoyaltyBonus: Get<Permill>;
+
+		/// The share of the protocol interest revenue of referred borrows routed to the referrer.
+		#[pallet::constant]
+		type ReferralShare: Get<Permill>;
+
+		/// The treasury account receiving the protocol fee on the interest revenue.
+		#[pallet::constant]
+		type TreasuryAccount: Get<Self::AccountId>;
+
+		/// The number of blocks between two scans of the lending pools and the borrows by the
+		/// offchain worker.
+		#[pallet::constant]
+		type OffchainScanInterval: Get<BlockNumberFor<Self>>;
+
+		/// The base priority of the unsigned keeper transactions submitted by the offchain worker.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// Whether the liquidations submitted by the offchain worker swap the seized collateral
+		/// for the repaid asset, so that the treasury needs no inventory of the borrowed assets.
+		#[pallet::constant]
+		type SwapOffchainLiquidations: Get<bool>;
+
+		/// The bond of the keepers, in the native currency
+		#[pallet::constant]
+		type KeeperBond: Get<BalanceOf<Self>>;
+
+		/// The number of blocks a keeper has the exclusivity of a liquidation detected by the
+		/// offchain worker
+		#[pallet::constant]
+		type KeeperExclusivity: Get<BlockNumberFor<Self>>;
+
+		/// The number of liquidations a keeper can miss in a row before its bond is slashed
+		#[pallet::constant]
+		type MaxKeeperMisses: Get<u32>;
+
+		/// The number of blocks after the margin call of a borrow with a shortfall before a
+		/// third party can liquidate it, zero for none
+		#[pallet::constant]
+		type MarginCallGracePeriod: Get<BlockNumberFor<Self>>;
+
+		/// The shortfall, as a share of the debt, beyond which a borrow can be liquidated
+		/// without a grace period
+		#[pallet::constant]
+		type MarginCallSeverity: Get<Ratio>;
+
+		/// The gap between the timestamps of two consecutive blocks, in seconds, beyond which
+		/// the chain is considered stalled, e.g. by an outage of the relay chain
+		#[pallet::constant]
+		type StallThreshold: Get<Timestamp>;
+
+		/// The period following a stall of the chain, in seconds, during which the borrows can
+		/// not be liquidated, zero for none
+		#[pallet::constant]
+		type StallGracePeriod: Get<Timestamp>;
+
+		/// The secondary source of the prices the offchain worker checks the `AssetPrices`
+		/// against, e.g. another oracle or the time-weighted prices of a DEX.
+		type PriceReference: PriceReference<AssetIdOf<Self>>;
+
+		/// The deviation of a price from its reference, as a share of the reference, beyond
+		/// which the lending pools of its assets are paused
+		#[pallet::constant]
+		type MaxPriceDeviation: Get<Ratio>;
+
+		/// The maximum number of lending pools visited per block to accrue their interest.
+		#[pallet::constant]
+		type MaxPoolsPerBlock: Get<u32>;
+
+		/// The supply positions below this balance of LP tokens are swept into the pool reserves
+		/// by `on_idle`, and the debts below this balance can be written off by keepers.
+		#[pallet::constant]
+		type DustBalance: Get<AssetBalanceOf<Self>>;
+
+		/// The minimum first deposit of a lending pool, required to create it
+		#[pallet::constant]
+		type MinPoolLiquidity: Get<BalanceOf<Self>>;
+
+		/// The LP tokens of the first deposit of a lending pool locked forever in the dead shares
+		/// account, so that the exchange rate of a pool can not be inflated by its last supplier
+		#[pallet::constant]
+		type DeadShares: Get<AssetBalanceOf<Self>>;
+
+		/// The utilisation of a lending pool from which the borrows at a stable rate below its
+		/// current stable rate can be rebalanced to it
+		#[pallet::constant]
+		type StableRateRebalanceUtilisation: Get<Ratio>;
+
+		/// The time in seconds after the maturity of a fixed-term borrow during which it accrues
+		/// at the penalty rate before it can be liquidated
+		#[pallet::constant]
+		type TermGracePeriod: Get<Timestamp>;
+
+		/// The annual rate added to the rate of the fixed-term borrows past their maturity
+		#[pallet::constant]
+		type TermPenaltyRate: Get<Rate>;
+
+		/// The time in seconds over which the utilisation of the lending pools is averaged
+		#[pallet::constant]
+		type UtilisationAveragePeriod: Get<Timestamp>;
+
+		/// The longest time in seconds the interest of a lending pool accrues over at once. A
+		/// pool left untouched for longer accrues the interest of this period only, so that its
+		/// indexes can not overflow and block the pool.
+		#[pallet::constant]
+		type MaxAccrualPeriod: Get<Timestamp>;
+
+		/// The number of blocks over which the outflows of a lending pool, i.e. its withdrawals
+		/// and borrows, are capped by its `OutflowCaps`
+		#[pallet::constant]
+		type OutflowWindow: Get<BlockNumberFor<Self>>;
+
+		/// The minimum number of blocks between a supply to a lending pool and the next
+		/// withdrawal from it by the same account, zero for none
+		#[pallet::constant]
+		type WithdrawCooldown: Get<BlockNumberFor<Self>>;
+
+		/// The minimum number of blocks between two snapshots of the rates of a lending pool
+		#[pallet::constant]
+		type RateSnapshotInterval: Get<BlockNumberFor<Self>>;
+
+		/// The maximum number of snapshots of the rates kept per lending pool, the oldest ones
+		/// make way for the new ones
+		#[pallet::constant]
+		type MaxRateSnapshots: Get<u32>;
+
+		/// The number of blocks of an era of the kToken snapshots, the balances of the kTokens
+		/// are snapshotted at the start of each era
+		#[pallet::constant]
+		type KTokenSnapshotEra: Get<BlockNumberFor<Self>>;
+
+		/// The maximum number of checkpoints of the kToken balances kept per account and
+		/// kToken, the oldest ones make way for the new ones
+		#[pallet::constant]
+		type MaxKTokenCheckpoints: Get<u32>;
+
+		/// The maximum number of the reasons the kTokens of an account may be frozen for at once
+		#[pallet::constant]
+		type MaxKTokenFreezes: Get<u32>;
+
+		/// The overarching call type, the announced parameter changes are scheduled as calls of
+		/// this pallet.
+		type RuntimeCall: Parameter
+			+ From<Call<Self>>
+			+ IsType<<Self as frame_system::Config>::RuntimeCall>;
+
+		/// Schedules the execution of the announced parameter changes, e.g. the
+		/// `pallet-scheduler`.
+		type Scheduler: ScheduleNamed<
+			BlockNumberFor<Self>,
+			<Self as Config>::RuntimeCall,
+			PalletsOriginOf<Self>,
+		>;
+
+		/// Bounds the scheduled calls, e.g. the `pallet-preimage`.
+		type Preimages: QueryPreimage + StorePreimage;
+
+		/// The number of blocks between the announcement of a change of the risk parameters of
+		/// a lending pool and its execution, leaving the borrowers time to adjust
+		#[pallet::constant]
+		type ParamChangeDelay: Get<BlockNumberFor<Self>>;
+
+		/// Builds the asset ids of the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AssetId>;
 	}
 
 	/// The AssetPool definition. Used as the KEY in the lending pool storage
@@ -192,6 +681,15 @@ pub mod pallet {
 		pub fn kink(&self) -> Rate {
 			self.kink
 		}
+
+		pub fn new(base_rate: Rate, slope1: Rate, slope2: Rate, kink: Rate) -> Self {
+			InterestRateModel { base_rate, slope1, slope2, kink }
+		}
+
+		/// The borrow rate is computed relative to the kink, which must lie in (0, 1]
+		pub fn is_valid(&self) -> bool {
+			!self.kink.is_zero() && self.kink <= Rate::one()
+		}
 	}
 
 	/// Definition of the Lending Pool Reserve Entity
@@ -214,7 +712,7 @@ pub mod pallet {
 	)]
 	#[scale_info(skip_type_params(T))]
 	pub struct LendingPool<T: Config> {
-		pub id: LendingPoolId,           // the lending pool id
+		pub id: AssetIdOf<T>, // the lending pool id, the asset id of its LP tokens
 		pub lend_token_id: AssetIdOf<T>, // the lending token id
 
 		pub reserve_balance: AssetBalanceOf<T>, // the reserve supplied to the lending pool
@@ -241,11 +739,17 @@ pub mod pallet {
 		                                          * accrued interest */
 		pub borrow_index: Rate, // accumulator of the total earned interest rate
 		pub supply_index: Rate, // accumulator of the total earned interest rate
+
+		pub borrow_fee: Ratio,     // the origination fee charged on borrows
+		pub created_at: Timestamp, // the timestamp of the creation of the pool
+
+		// the utilisation averaged over the `UtilisationAveragePeriod`, weighted by time
+		pub average_utilisation: Ratio,
 	}
 	impl<T: Config> LendingPool<T> {
 		// let's create a default reserve lending pool
 		pub fn from(
-			id: LendingPoolId,
+			id: AssetIdOf<T>,
 			lend_token_id: AssetIdOf<T>,
 			balance: AssetBalanceOf<T>,
 		) -> Result<Self, Error<T>> {
@@ -270,6 +774,10 @@ pub mod pallet {
 				last_accrued_interest_at: Pallet::<T>::now_in_seconds(),
 				borrow_index: Rate::one(),
 				supply_index: Rate::one(),
+
+				borrow_fee: Ratio::zero(),
+				created_at: Pallet::<T>::now_in_seconds(),
+				average_utilisation: Ratio::zero(),
 			};
 			pool.update_indexes()?;
 			Ok(pool)
@@ -299,7 +807,17 @@ pub mod pallet {
 		///
 		/// if (utilisation_ratio > kink)
 		/// 	base_rate + slope1 + ((utilisation_ratio - kink)/(1 - kink)) * slope2
+		///
+		/// within the `BorrowRateBounds` of the pool, if any
 		pub fn borrow_interest_rate(&self) -> Result<Rate, Error<T>> {
+			let rate = self.model_borrow_rate()?;
+			Ok(match BorrowRateBounds::<T>::get(self.lend_token_id) {
+				Some((floor, cap)) => rate.max(floor).min(cap),
+				None => rate,
+			})
+		}
+
+		fn model_borrow_rate(&self) -> Result<Rate, Error<T>> {
 			if self.borrowed_balance.is_zero() || self.reserve_balance.is_zero() {
 				return Ok(Rate::zero());
 			}
@@ -359,7 +877,7 @@ pub mod pallet {
 			let utilisation_ratio = self.utilisation_ratio()?;
 
 			let reserved = Permill::from_percent(100)
-				.checked_sub(&self.reserve_factor)
+				.checked_sub(&self.reserve_factor())
 				.ok_or(Error::<T>::OverflowError)?;
 
 			let res = borrow_rate
@@ -381,29 +899,27 @@ pub mod pallet {
 
 		/// Calculates scaled balance as
 		/// scaled_balance = balance / supply_index
+		/// rounded down for the LP tokens to mint, up for the ones to burn
 		pub fn scaled_supply_balance(
 			&self,
 			deposit: AssetBalanceOf<T>,
+			rounding: Rounding,
 		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let scaled_balance = FixedU128::from_inner(deposit.saturated_into())
-				.checked_div(&self.supply_index)
-				.ok_or(Error::<T>::OverflowError)?
-				.into_inner()
-				.saturated_into();
+			let scaled_balance = math::div_rate(deposit, self.supply_index, rounding)
+				.ok_or(Error::<T>::OverflowError)?;
 			Ok(scaled_balance)
 		}
 
 		/// Calculates scaled balance for borrow case as
 		/// scaled_balance = balance / borrow_index
+		/// rounded up for the debt to record, down for the debt to clear
 		pub fn scaled_borrow_balance(
 			&self,
 			borrowed_balance: AssetBalanceOf<T>,
+			rounding: Rounding,
 		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let scaled_balance = FixedU128::from_inner(borrowed_balance.saturated_into())
-				.checked_div(&self.borrow_index)
-				.ok_or(Error::<T>::OverflowError)?
-				.into_inner()
-				.saturated_into();
+			let scaled_balance = math::div_rate(borrowed_balance, self.borrow_index, rounding)
+				.ok_or(Error::<T>::OverflowError)?;
 			Ok(scaled_balance)
 		}
 
@@ -414,69 +930,25 @@ pub mod pallet {
 		/// # Arguments
 		/// rate: Annual supply interest rate
 		fn calculate_linear_interest(&self) -> Result<Rate, Error<T>> {
-			let dur: u64 = Pallet::<T>::now_in_seconds()
-				.checked_sub(self.last_accrued_interest_at)
-				.ok_or(Error::<T>::OverflowError)?;
-			let rate_factor = self
-				.supply_interest_rate()?
-				.checked_mul(&FixedU128::from(dur as u128))
-				.ok_or(Error::<T>::OverflowError)?;
-			let accumulated_rate = rate_factor
-				.checked_div(&(SECONDS_PER_YEAR as u128).into())
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_add(&FixedU128::one())
-				.ok_or(Error::<T>::OverflowError)?;
-			Ok(accumulated_rate)
+			let dur: u64 = self.accrual_period()?;
+			math::linear_interest(self.supply_interest_rate()?, dur)
+				.ok_or(Error::<T>::OverflowError)
 		}
 
-		/// Calculate compounded interest
-		/// Borrow interest compounds every second. This is achieved by an approximation of binomial
-		/// expansion to the third term.
-		/// BinomalExpansion:(1+x)^n = 1+ nx + (n/2)(n−1)*x^2 + (n/6)(n−1)(n−2)x^3 +...
-		/// Where n = t, number of periods and x = r, rate per second
-		/// Interest:(1+r) t ≈1 + rt + t/2 * (t−1) * r^2 + (t/6) * (t−1) * (t−2) * r^3
-		fn calculate_compunded_interest(&self) -> Result<Rate, Error<T>> {
-			let rate = self
-				.borrow_interest_rate()?
-				.checked_div(&(SECONDS_PER_YEAR as u128).into())
-				.ok_or(Error::<T>::OverflowError)?;
+		/// The time in seconds since the last accrual, up to the `MaxAccrualPeriod`
+		fn accrual_period(&self) -> Result<u64, Error<T>> {
 			let t = Pallet::<T>::now_in_seconds()
 				.checked_sub(self.last_accrued_interest_at)
 				.ok_or(Error::<T>::OverflowError)?;
-			let t_minus_one = t.checked_sub(1u64).ok_or(Error::<T>::OverflowError)?;
-			let t_minus_two = t.checked_sub(2u64).ok_or(Error::<T>::OverflowError)?;
-			let rate_square = rate.checked_mul(&rate).ok_or(Error::<T>::OverflowError)?;
-			let rate_cube = rate_square.checked_mul(&rate).ok_or(Error::<T>::OverflowError)?;
-
-			let first_term =
-				rate.checked_mul(&(t as u128).into()).ok_or(Error::<T>::OverflowError)?;
-
-			let second_term = FixedU128::from(t as u128)
-				.checked_mul(&(t_minus_one as u128).into())
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_mul(&rate_square)
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_div(&(2u128).into())
-				.ok_or(Error::<T>::OverflowError)?;
-
-			let third_term = FixedU128::from(t as u128)
-				.checked_mul(&(t_minus_one as u128).into())
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_mul(&(t_minus_two as u128).into())
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_mul(&rate_cube)
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_div(&(6u128).into())
-				.ok_or(Error::<T>::OverflowError)?;
-			let interest = FixedU128::one()
-				.checked_add(&first_term)
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_add(&second_term)
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_add(&third_term)
-				.ok_or(Error::<T>::OverflowError)?;
+			Ok(t.min(T::MaxAccrualPeriod::get()))
+		}
 
-			Ok(interest)
+		/// Calculate compounded interest
+		/// Borrow interest compounds every second, see `math::compounded_interest`.
+		fn calculate_compunded_interest(&self) -> Result<Rate, Error<T>> {
+			let t = self.accrual_period()?;
+			math::compounded_interest(self.borrow_interest_rate()?, t)
+				.ok_or(Error::<T>::OverflowError)
 		}
 
 		fn update_supply_index(&mut self) -> Result<(), Error<T>> {
@@ -495,25 +967,57 @@ pub mod pallet {
 			Ok(())
 		}
 
-		fn update_indexes(&mut self) -> Result<(), Error<T>> {
-			if self.last_accrued_interest_at < Pallet::<T>::now_in_seconds() {
+		/// Moves the average utilisation towards the utilisation since the last accrual, by the
+		/// share of the `UtilisationAveragePeriod` elapsed since, i.e. an exponential moving
+		/// average weighted by time
+		fn update_average_utilisation(&mut self, now: Timestamp) -> Result<(), Error<T>> {
+			let period = T::UtilisationAveragePeriod::get();
+			let elapsed = now.saturating_sub(self.last_accrued_interest_at).min(period);
+			let weight = Ratio::from_rational(elapsed, period);
+			self.average_utilisation = (weight.left_from_one() * self.average_utilisation)
+				.checked_add(&(weight * self.utilisation_ratio()?))
+				.ok_or(Error::<T>::OverflowError)?;
+			Ok(())
+		}
+
+		/// Accrues the interest since the last accrual into the indexes, and emits an
+		/// `InterestAccrued` event with the interest on the borrowed balance and the share of it
+		/// kept in the reserves
+		pub(crate) fn update_indexes(&mut self) -> Result<(), Error<T>> {
+			let now = Pallet::<T>::now_in_seconds();
+			if self.last_accrued_interest_at < now {
+				let old_borrow_index = self.borrow_index;
+				self.update_average_utilisation(now)?;
 				self.update_supply_index()?;
 				self.udpate_borrow_index()?;
+				self.last_accrued_interest_at = now;
+
+				let growth = self
+					.borrow_index
+					.checked_div(&old_borrow_index)
+					.ok_or(Error::<T>::OverflowError)?;
+				let interest = math::mul_rate(self.borrowed_balance, growth, Rounding::Down)
+					.ok_or(Error::<T>::OverflowError)?
+					.saturating_sub(self.borrowed_balance);
+				Pallet::<T>::deposit_event(Event::InterestAccrued {
+					asset: self.lend_token_id,
+					borrow_index: self.borrow_index,
+					interest,
+					reserves: self.reserve_factor().mul_floor(interest),
+				});
 			}
 			Ok(())
 		}
 
 		/// Calculates accrued deposit as
 		/// accrued_deposit = deposit * supply_index
+		/// rounded down
 		pub fn accrued_deposit(
 			&self,
 			balance: AssetBalanceOf<T>,
 		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let a_deposit = FixedU128::from_inner(balance.saturated_into())
-				.checked_mul(&self.supply_index)
-				.ok_or(Error::<T>::OverflowError)?
-				.into_inner()
-				.saturated_into();
+			let a_deposit = math::mul_rate(balance, self.supply_index, Rounding::Down)
+				.ok_or(Error::<T>::OverflowError)?;
 			Ok(a_deposit)
 		}
 
@@ -526,41 +1030,45 @@ pub mod pallet {
 			Ok(())
 		}
 
-		/// Update pool: move assets from borrowed_balance reserved_balance on repayment
-		pub fn move_asset_on_repay(&mut self, balance: AssetBalanceOf<T>) -> Result<(), Error<T>> {
+		/// Update pool: move the repaid principal from borrowed_balance to reserved_balance, and
+		/// add the paid interest to reserved_balance
+		pub fn move_asset_on_repay(
+			&mut self,
+			principal: AssetBalanceOf<T>,
+			interest: AssetBalanceOf<T>,
+		) -> Result<(), Error<T>> {
 			self.borrowed_balance =
-				self.borrowed_balance.checked_sub(&balance).ok_or(Error::<T>::OverflowError)?;
-			self.reserve_balance =
-				self.reserve_balance.checked_add(&balance).ok_or(Error::<T>::OverflowError)?;
+				self.borrowed_balance.checked_sub(&principal).ok_or(Error::<T>::OverflowError)?;
+			self.reserve_balance = self
+				.reserve_balance
+				.checked_add(&principal)
+				.and_then(|b| b.checked_add(&interest))
+				.ok_or(Error::<T>::OverflowError)?;
 			Ok(())
 		}
 
 		/// Calculate the loan amount
 		/// max_loan_amount = collateral_balance * collatoral_factor
+		/// rounded down
 		pub fn max_borrow_amount(
 			&self,
 			collateral_balance: AssetBalanceOf<T>,
 		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let factor: Rate = self.collateral_factor.into();
-			let max_loan_amount = FixedU128::from_inner(collateral_balance.saturated_into())
-				.checked_mul(&factor)
-				.ok_or(Error::<T>::OverflowError)?
-				.into_inner()
-				.saturated_into();
+			let max_loan_amount =
+				math::mul_rate(collateral_balance, self.collateral_factor().into(), Rounding::Down)
+					.ok_or(Error::<T>::OverflowError)?;
 			Ok(max_loan_amount)
 		}
 
 		/// Calculate the repayable amount including borrow interests
 		/// _amount = borrow_balance * borrow_index
+		/// rounded up
 		pub fn repayable_amount(
 			&self,
 			borrow_balance: AssetBalanceOf<T>,
 		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let r = FixedU128::from_inner(borrow_balance.saturated_into())
-				.checked_mul(&self.borrow_index)
-				.ok_or(Error::<T>::OverflowError)?
-				.into_inner()
-				.saturated_into();
+			let r = math::mul_rate(borrow_balance, self.borrow_index, Rounding::Up)
+				.ok_or(Error::<T>::OverflowError)?;
 			Ok(r)
 		}
 	}
@@ -569,11 +1077,16 @@ pub mod pallet {
 	///
 	/// Lending pools defined for the assets
 	///
-	/// StorageMap AssetPool { AssetId } => LendingPool { PoolId, Balance }
+	/// CountedStorageMap AssetPool { AssetId } => LendingPool { PoolId, Balance }, up to
+	/// `MaxPools` of them
+	///
+	/// An asset has a single live lending pool. The governance may replace it with
+	/// `replace_lending_pool`, retiring the former pool to `RetiredPools` until its positions
+	/// are migrated with `migrate_positions`.
 	#[pallet::storage]
 	#[pallet::getter(fn reserve_pools)]
 	pub type LendingPoolStorage<T> =
-		StorageMap<_, Blake2_128Concat, AssetPool<T>, LendingPool<T>, ValueQuery>;
+		CountedStorageMap<_, Blake2_128Concat, AssetPool<T>, LendingPool<T>, OptionQuery>;
 
 	// Now we need to define the properties of the underlying asset used in the lending pool
 	#[derive(
@@ -635,6 +1148,15 @@ pub mod pallet {
 		}
 	}
 
+	/// A snapshot of the rates of a lending pool
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+	pub struct RateSnapshot<BlockNumber> {
+		pub block: BlockNumber,
+		pub borrow_rate: Rate,
+		pub supply_rate: Rate,
+		pub exchange_rate: Rate, // the underlying assets per LP token, i.e. the supply index
+	}
+
 	/// Kylix runtime storage items
 	///
 	/// Lending pools Assets Properties
@@ -657,12 +1179,35 @@ pub mod pallet {
 	pub type SupplyIndexStorage<T: Config> =
 		StorageMap<_, Blake2_128Concat, (AccountOf<T>, AssetIdOf<T>), SupplyIndex, ValueQuery>;
 
+	/// The block of the last supply of accounts to the lending pools, until their next
+	/// withdrawal once the `WithdrawCooldown` elapsed
+	#[pallet::storage]
+	pub type LastSupplies<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(AccountOf<T>, AssetIdOf<T>),
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
 	/// The borrow status of accounts
 	/// (AccountId, corrowed_asset_id, collateral_asset_id) => UserBorrow details
 	#[pallet::storage]
 	pub type Borrows<T: Config> =
 		StorageMap<_, Blake2_128Concat, (AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>), UserBorrow<T>>;
 
+	/// The borrows of every account, indexing `Borrows` by account, up to
+	/// `MaxMarketsPerAccount` of them
+	/// AccountId => [(borrowed_asset_id, collateral_asset_id)]
+	#[pallet::storage]
+	pub type AccountBorrows<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		BoundedVec<(AssetIdOf<T>, AssetIdOf<T>), T::MaxMarketsPerAccount>,
+		ValueQuery,
+	>;
+
 	/// The storage to hold prices of assets w.r.t. other other assets
 	/// This is the dummy storage, ideally this functionality would be implemented in a dedicatd
 	/// pallet sotres (asset_id1, asset_id2) => FixedU128
@@ -670,85 +1215,1189 @@ pub mod pallet {
 	pub type AssetPrices<T: Config> =
 		StorageMap<_, Blake2_128Concat, (AssetIdOf<T>, AssetIdOf<T>), FixedU128, OptionQuery>;
 
-	#[pallet::genesis_config]
-	#[derive(frame_support::DefaultNoBound)]
-	pub struct GenesisConfig<T: Config> {
-		pub _marker: PhantomData<T>,
-	}
+	/// The reward programs of the lending pools
+	/// asset_id => RewardProgram
+	#[pallet::storage]
+	pub type RewardPrograms<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, RewardProgram<T>, OptionQuery>;
 
-	#[pallet::genesis_build]
-	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
-		fn build(&self) {
-			let max_exchange_rate: u128 = 1_000_000_000_000_000_000; // 1
-			let min_exchange_rate: u128 = 20_000_000_000_000_000; // 0.02
+	/// The reward distribution state of accounts for lending pools
+	/// (AccountId, asset_id) => UserReward
+	#[pallet::storage]
+	pub type UserRewards<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		UserReward<T>,
+		ValueQuery,
+	>;
+
+	/// The claimed rewards of accounts still locked in vesting schedules, one per claim
+	/// (AccountId, asset_id) => [VestingSchedule]
+	#[pallet::storage]
+	pub type RewardVesting<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BoundedVec<VestingSchedule<T>, ConstU32<MAX_VESTING_SCHEDULES>>,
+		OptionQuery,
+	>;
+
+	/// The rewards owed by the rewards pot, accrued or vesting but not paid out yet
+	/// reward_asset_id => Balance
+	#[pallet::storage]
+	pub type RewardLiabilities<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, AssetBalanceOf<T>, ValueQuery>;
 
-			let rmax = Rate::from_inner(max_exchange_rate);
-			let rmin = Rate::from_inner(min_exchange_rate);
+	/// The accounts which allow keepers to compound their rewards on their behalf
+	/// (AccountId, asset_id) => bool
+	#[pallet::storage]
+	pub type AutoCompound<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		bool,
+		ValueQuery,
+	>;
+
+	/// The registered referral codes and their owners
+	/// ReferralCode => AccountId
+	#[pallet::storage]
+	pub type ReferralCodes<T: Config> =
+		StorageMap<_, Blake2_128Concat, ReferralCode, AccountOf<T>, OptionQuery>;
 
-			MinMaxExchangeRate::<T>::put((rmin, rmax));
-		}
-	}
+	/// The referral codes attached to the referred accounts
+	/// AccountId => ReferralCode
+	#[pallet::storage]
+	pub type Referrals<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, ReferralCode, OptionQuery>;
 
-	/// Events to inform users when important changes are made.
-	#[pallet::event]
-	#[pallet::generate_deposit(pub(super) fn deposit_event)]
-	pub enum Event<T: Config> {
-		DepositSupplied { who: T::AccountId, asset: AssetIdOf<T>, balance: BalanceOf<T> },
-		DepositWithdrawn { who: T::AccountId, balance: BalanceOf<T> },
-		DepositBorrowed { who: T::AccountId, balance: BalanceOf<T> },
-		DepositRepaid { who: T::AccountId, balance: BalanceOf<T> },
-		RewardsClaimed { who: T::AccountId, balance: BalanceOf<T> },
-		LendingPoolAdded { who: T::AccountId, asset: AssetIdOf<T> },
-		LendingPoolRemoved { who: T::AccountId },
-		LendingPoolActivated { who: T::AccountId, asset: AssetIdOf<T> },
-		LendingPoolDeactivated { who: T::AccountId, asset: AssetIdOf<T> },
-		LendingPoolRateModelUpdated { who: T::AccountId, asset: AssetIdOf<T> },
-		LendingPoolKinkUpdated { who: T::AccountId, asset: AssetIdOf<T> },
-		LPTokenMinted { who: T::AccountId, asset: AssetIdOf<T>, balance: AssetBalanceOf<T> },
-		AssetPriceAdded { asset_1: AssetIdOf<T>, asset_2: AssetIdOf<T>, price: FixedU128 },
-	}
+	/// The earnings of the referral codes not claimed yet
+	/// (ReferralCode, asset_id) => Balance
+	#[pallet::storage]
+	pub type ReferralEarnings<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ReferralCode,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		AssetBalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// The share of all the interest revenue redirected to the treasury, zero when the fee
+	/// switch is off
+	#[pallet::storage]
+	pub type ProtocolFee<T: Config> = StorageValue<_, Ratio, ValueQuery>;
 
-	// Errors inform users that something went wrong.
-	#[pallet::error]
-	pub enum Error<T> {
-		/// Lending Pool does not exist
-		LendingPoolDoesNotExist,
-		/// Lending Pool already exists
-		LendingPoolAlreadyExists,
-		/// Lending Pool already activated
-		LendingPoolAlreadyActivated,
-		/// Lending Pool already deactivated
-		LendingPoolAlreadyDeactivated,
-		/// Lending Pool is not active or has been deprecated
-		LendingPoolNotActive,
-		/// The balance amount to supply is not valid
-		InvalidLiquiditySupply,
-		/// The balance amount to withdraw is not valid
-		InvalidLiquidityWithdrawal,
-		/// The user has not enough liquidity
-		NotEnoughLiquiditySupply,
-		/// The user wants to withdraw more than allowed!
-		NotEnoughElegibleLiquidityToWithdraw,
-		/// Lending Pool is empty
-		LendingPoolIsEmpty,
-		/// The classic Overflow Error
-		OverflowError,
-		/// The ID already exists
-		IdAlreadyExists,
-		/// The user has not enough collateral assets
-		NotEnoughCollateral,
-		/// The Loan being repayed does not exists
-		LoanDoesNotExists,
-		/// Price of the asset can not be zero
-		InvalidAssetPrice,
-		/// The price of the asset is not avaialble
-		AssetPriceNotSet,
-	}
+	/// The minimum debt of the borrows of the lending pools by underlying asset, zero when
+	/// there is none
+	#[pallet::storage]
+	pub type MinBorrow<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, AssetBalanceOf<T>, ValueQuery>;
 
-	#[pallet::call]
-	impl<T: Config> Pallet<T> {
-		/// The `create_lending_pool` function allows a user to Create a new reserve and then
-		/// supply it with some liquidity. Given an asset and its amount, it creates a
+	/// The underlying assets of the lending pools by LP token
+	#[pallet::storage]
+	pub type LpTokenPools<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, AssetIdOf<T>, OptionQuery>;
+
+	/// The share of the collateral factor at which the LP tokens of the lending pools are
+	/// accepted as collateral, by underlying asset. The LP tokens of a lending pool without one
+	/// are not accepted, so that no leverage is looped through the lending pools.
+	#[pallet::storage]
+	pub type LpCollateralFactor<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Ratio, OptionQuery>;
+
+	/// The premium over the variable borrow rate at which the stable rates of the borrows from
+	/// the lending pools are fixed, by underlying asset. The lending pools without one offer no
+	/// stable rate.
+	#[pallet::storage]
+	pub type StableRatePremium<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Ratio, OptionQuery>;
+
+	/// The maturities of the fixed-term borrows, by the key of the borrow. The fixed-term
+	/// borrows are at a stable rate.
+	#[pallet::storage]
+	pub type BorrowMaturities<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>),
+		Timestamp,
+		OptionQuery,
+	>;
+
+	/// The floor and the cap of the borrow rates of the lending pools, by underlying asset. They
+	/// bound whatever rate the interest rate model of the pool yields.
+	#[pallet::storage]
+	pub type BorrowRateBounds<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, (Rate, Rate), OptionQuery>;
+
+	/// The caps on the outflows of the lending pools over the `OutflowWindow`, by underlying
+	/// asset. The lending pools without one are not throttled.
+	#[pallet::storage]
+	pub type OutflowCaps<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, AssetBalanceOf<T>, OptionQuery>;
+
+	/// The caps on the balances borrowed from the lending pools, by underlying asset. The
+	/// lending pools without one can be borrowed up to their reserves.
+	#[pallet::storage]
+	pub type BorrowCaps<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, AssetBalanceOf<T>, OptionQuery>;
+
+	/// The outflows of the lending pools and the block they were last recorded at, by
+	/// underlying asset. They decay linearly to zero over the `OutflowWindow`.
+	#[pallet::storage]
+	pub type PoolOutflows<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		(AssetBalanceOf<T>, BlockNumberFor<T>),
+		OptionQuery,
+	>;
+
+	/// The snapshots of the rates of the lending pools taken by the interest accruals, oldest
+	/// first, by underlying asset
+	#[pallet::storage]
+	pub type RateSnapshots<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BoundedVec<RateSnapshot<BlockNumberFor<T>>, T::MaxRateSnapshots>,
+		ValueQuery,
+	>;
+
+	/// The balances of the kTokens of the accounts at the start of the eras they changed in,
+	/// oldest first
+	/// (AccountId, lp_token) => [(era, balance)]
+	#[pallet::storage]
+	pub type KTokenCheckpoints<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BoundedVec<(BlockNumberFor<T>, AssetBalanceOf<T>), T::MaxKTokenCheckpoints>,
+		ValueQuery,
+	>;
+
+	/// The freezes of the kTokens of the accounts, overlapping each other
+	/// (AccountId, lp_token) => [(reason, balance)]
+	#[pallet::storage]
+	pub type KTokenFreezes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BoundedVec<(T::RuntimeFreezeReason, AssetBalanceOf<T>), T::MaxKTokenFreezes>,
+		ValueQuery,
+	>;
+
+	/// Whether the LP tokens are being moved by the `LpTokenGuard`, which unfreezes them in the
+	/// meantime. It is never left set.
+	#[pallet::storage]
+	pub type LpTokensUnfrozen<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// The loyalty tiers of the suppliers of the lending pools, by underlying asset
+	#[pallet::storage]
+	pub type LoyaltyTiers<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BoundedVec<LoyaltyTier, T::MaxLoyaltyTiers>,
+		ValueQuery,
+	>;
+
+	/// The timestamps the accounts have been supplying to the lending pools continuously since
+	/// (AccountId, asset) => timestamp
+	#[pallet::storage]
+	pub type SupplyingSince<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Timestamp,
+		OptionQuery,
+	>;
+
+	/// The totals of the lending pools valued in the `BaseAsset` at their last update, by
+	/// underlying asset
+	#[pallet::storage]
+	pub type PoolTotals<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		ProtocolTotals<AssetBalanceOf<T>>,
+		ValueQuery,
+	>;
+
+	/// The totals of all the lending pools valued in the `BaseAsset`, i.e. the sum of their
+	/// `PoolTotals`, so that the value locked in the protocol is known without iterating them
+	#[pallet::storage]
+	pub type ProtocolStats<T: Config> =
+		StorageValue<_, ProtocolTotals<AssetBalanceOf<T>>, ValueQuery>;
+
+	/// Whether the calls of the pallet are blocked chain-wide by the `MaintenanceFilter`, but
+	/// for the repayments
+	#[pallet::storage]
+	pub type MaintenanceMode<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// The lending pools only the accounts of their `PoolWhitelist` can supply to or borrow
+	/// from, by underlying asset
+	#[pallet::storage]
+	pub type PermissionedPools<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, bool, ValueQuery>;
+
+	/// The accounts admitted to the permissioned lending pools
+	/// (asset_id, AccountId) => bool
+	#[pallet::storage]
+	pub type PoolWhitelist<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Blake2_128Concat,
+		AccountOf<T>,
+		bool,
+		ValueQuery,
+	>;
+
+	/// The id of the next parameter change announced
+	#[pallet::storage]
+	pub type NextParamChangeId<T: Config> = StorageValue<_, ParamChangeId, ValueQuery>;
+
+	/// The parameter changes announced and not executed yet, by id
+	#[pallet::storage]
+	pub type PendingParamChanges<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		ParamChangeId,
+		PendingParamChange<AssetIdOf<T>, BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// The risk parameters of the lending pools overridden by the governance, in effect in place
+	/// of those stored in the pools
+	/// asset_id, parameter => Ratio
+	#[pallet::storage]
+	pub type RiskParameterOverrides<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Blake2_128Concat,
+		RiskParameter,
+		Ratio,
+		OptionQuery,
+	>;
+
+	/// The debt accounts accept to take over from other accounts, by lending pool
+	/// (AccountId, from AccountId, asset_id) => Balance
+	#[pallet::storage]
+	pub type DebtAllowances<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(AccountOf<T>, AccountOf<T>, AssetIdOf<T>),
+		AssetBalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// The id of the next position wrapped
+	#[pallet::storage]
+	pub type NextPositionId<T: Config> = StorageValue<_, PositionId, ValueQuery>;
+
+	/// The borrows wrapped into positions, held by the `position_account_id` of each position
+	/// PositionId => (borrowed_asset_id, collateral_asset_id)
+	#[pallet::storage]
+	pub type Positions<T: Config> =
+		StorageMap<_, Blake2_128Concat, PositionId, (AssetIdOf<T>, AssetIdOf<T>), OptionQuery>;
+
+	/// The assets of the liquidity pools of the DEX by LP token, for the LP tokens accepted as
+	/// collateral, which are valued at the fair value of their pool
+	#[pallet::storage]
+	pub type DexLpCollaterals<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, (AssetIdOf<T>, AssetIdOf<T>), OptionQuery>;
+
+	/// The liquidators registered as keepers
+	#[pallet::storage]
+	pub type Keepers<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Keeper<BalanceOf<T>, BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// The last keeper assigned a liquidation, the next assignment goes to the keeper after it
+	#[pallet::storage]
+	pub type KeeperCursor<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	/// The keepers the liquidations detected by the offchain worker are assigned to and the last
+	/// block of their exclusivity, by the key of the borrow
+	#[pallet::storage]
+	pub type LiquidationAssignments<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, AssetIdOf<T>, AssetIdOf<T>),
+		(T::AccountId, BlockNumberFor<T>),
+		OptionQuery,
+	>;
+
+	/// Whether the borrows from and the withdrawals of the lending pools are paused, by
+	/// underlying asset, e.g. on a deviation of the price of the asset from its reference
+	#[pallet::storage]
+	pub type PausedPools<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, bool, ValueQuery>;
+
+	/// The operators approved by the accounts, with their permissions
+	/// (AccountId, operator) => OperatorPermissions
+	#[pallet::storage]
+	pub type Operators<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AccountOf<T>,
+		OperatorPermissions,
+		OptionQuery,
+	>;
+
+	/// The blocks the margin calls of the borrows with a shortfall were issued in, by the key
+	/// of the borrow. A margin call is cleared once its borrow is topped up to health or closed.
+	#[pallet::storage]
+	pub type MarginCalls<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AccountId, AssetIdOf<T>, AssetIdOf<T>),
+		BlockNumberFor<T>,
+		OptionQuery,
+	>;
+
+	/// The stable rates of the borrows at a stable rate, by the key of the borrow
+	#[pallet::storage]
+	pub type StableBorrows<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>),
+		StableRate,
+		OptionQuery,
+	>;
+
+	/// The timestamp of the last block, in seconds, recorded by `on_finalize` to detect the
+	/// stalls of the chain
+	#[pallet::storage]
+	pub type LastBlockTimestamp<T: Config> = StorageValue<_, Timestamp, OptionQuery>;
+
+	/// The end of the grace period following the last stall of the chain, in seconds, before
+	/// which the borrows can not be liquidated
+	#[pallet::storage]
+	pub type StallGraceUntil<T: Config> = StorageValue<_, Timestamp, OptionQuery>;
+
+	/// The last lending pool visited by the interest accrual in `on_initialize`, the next block
+	/// carries on from the following one
+	#[pallet::storage]
+	pub type AccrualCursor<T: Config> = StorageValue<_, AssetIdOf<T>, OptionQuery>;
+
+	/// The last supply position visited by the dust sweep in `on_idle`
+	/// (AccountId, asset_id)
+	#[pallet::storage]
+	pub type CleanupCursor<T: Config> = StorageValue<_, (AccountOf<T>, AssetIdOf<T>), OptionQuery>;
+
+	/// The progress of the lazy migration of the positions to the current storage layout, `None`
+	/// once it is done. Until then the calls of the pallet are blocked by the `MigrationFilter`.
+	#[pallet::storage]
+	pub type MigrationCursor<T: Config> =
+		StorageValue<_, migrations::MigrationStage<T>, OptionQuery>;
+
+	/// The lending pools replaced with `replace_lending_pool`, by LP token, until their
+	/// positions are migrated to the pools replacing them
+	/// lp_token_id => LendingPool
+	#[pallet::storage]
+	pub type RetiredPools<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, LendingPool<T>, OptionQuery>;
+
+	/// The borrows owed to a retired lending pool until migrated, with the LP token of the pool
+	/// (AccountId, asset_id, collateral_asset_id) => lp_token_id
+	#[pallet::storage]
+	pub type RetiredBorrows<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>),
+		AssetIdOf<T>,
+		OptionQuery,
+	>;
+
+	/// The caps on the share of a lending pool a single account may hold, by underlying asset.
+	/// The lending pools without one are not capped.
+	#[pallet::storage]
+	pub type AccountSupplyCaps<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Permill, OptionQuery>;
+
+	/// The tiers assigned to the accounts by the `ManagerOrigin`, taking precedence over the
+	/// `AccountTierProvider`
+	#[pallet::storage]
+	pub type AccountTiers<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, AccountTier, OptionQuery>;
+
+	/// The terms of the lending pools for the accounts of each tier, by underlying asset. The
+	/// lending pools without terms are open to all the tiers, the others only to the tiers they
+	/// have terms for.
+	/// (asset_id, AccountTier) => TierTerms
+	#[pallet::storage]
+	pub type PoolTierTerms<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Blake2_128Concat,
+		AccountTier,
+		TierTerms<AssetBalanceOf<T>>,
+		OptionQuery,
+	>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		pub _marker: PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			let max_exchange_rate: u128 = 1_000_000_000_000_000_000; // 1
+			let min_exchange_rate: u128 = 20_000_000_000_000_000; // 0.02
+
+			let rmax = Rate::from_inner(max_exchange_rate);
+			let rmin = Rate::from_inner(min_exchange_rate);
+
+			MinMaxExchangeRate::<T>::put((rmin, rmax));
+		}
+	}
+
+	/// The reasons the kTokens are frozen for by the programs of the protocol. The frozen
+	/// kTokens stay with their owner, but can not be withdrawn, transferred nor locked as
+	/// collateral.
+	#[pallet::composite_enum]
+	pub enum FreezeReason {
+		/// The kTokens staked in a staking program
+		#[codec(index = 0)]
+		Staking,
+		/// The kTokens locked to boost the rewards of their owner
+		#[codec(index = 1)]
+		Boost,
+	}
+
+	/// The reasons the native currency is held for by the pallet
+	#[pallet::composite_enum]
+	pub enum HoldReason {
+		/// The native currency posted as the collateral of a borrow
+		#[codec(index = 0)]
+		BorrowCollateral,
+	}
+
+	/// Events to inform users when important changes are made.
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// `minted` LP tokens were minted for the supply of `balance`. `exchange_rate` and
+		/// `borrow_index` are the supply and the borrow index of the pool after the supply,
+		/// `lp_tokens` the LP tokens of `who` and `utilisation` the utilisation of the pool.
+		DepositSupplied {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			minted: AssetBalanceOf<T>,
+			exchange_rate: Rate,
+			borrow_index: Rate,
+			lp_tokens: AssetBalanceOf<T>,
+			utilisation: Ratio,
+		},
+		/// `burned` LP tokens were burned for the withdrawal of `balance`, with the state after
+		/// the withdrawal like `DepositSupplied`
+		DepositWithdrawn {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			burned: AssetBalanceOf<T>,
+			exchange_rate: Rate,
+			borrow_index: Rate,
+			lp_tokens: AssetBalanceOf<T>,
+			utilisation: Ratio,
+		},
+		/// `balance` was borrowed, `fee` of it kept as the origination fee. `debt` is the debt
+		/// of the borrow and `utilisation` the utilisation of the pool after the borrow.
+		DepositBorrowed {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			fee: BalanceOf<T>,
+			exchange_rate: Rate,
+			borrow_index: Rate,
+			debt: AssetBalanceOf<T>,
+			utilisation: Ratio,
+		},
+		BorrowSentToChain {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			dest: T::Location,
+		},
+		/// `balance` was repaid, with the state after the repayment like `DepositBorrowed`
+		DepositRepaid {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			exchange_rate: Rate,
+			borrow_index: Rate,
+			debt: AssetBalanceOf<T>,
+			utilisation: Ratio,
+		},
+		RewardsClaimed {
+			who: T::AccountId,
+			dest: T::AccountId,
+			balance: BalanceOf<T>,
+		},
+		LendingPoolAdded {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		},
+		LendingPoolRemoved {
+			who: T::AccountId,
+		},
+		LendingPoolActivated {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		},
+		LendingPoolDeactivated {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		},
+		LendingPoolRateModelUpdated {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		},
+		LendingPoolKinkUpdated {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		},
+		LPTokenMinted {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		AssetPriceAdded {
+			asset_1: AssetIdOf<T>,
+			asset_2: AssetIdOf<T>,
+			price: FixedU128,
+		},
+		RewardProgramUpdated {
+			asset: AssetIdOf<T>,
+			reward_asset: AssetIdOf<T>,
+			speed: AssetBalanceOf<T>,
+			vesting: Option<VestingTerms>,
+		},
+		RewardsVested {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		RewardsReleased {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		RewardsCompounded {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		AutoCompoundSet {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			enabled: bool,
+		},
+		RewardBoostUpdated {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		RewardsFunded {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		RewardEmissionPaused {
+			asset: AssetIdOf<T>,
+		},
+		RewardEmissionResumed {
+			asset: AssetIdOf<T>,
+		},
+		ReferralCodeRegistered {
+			who: T::AccountId,
+			code: ReferralCode,
+		},
+		ReferralAttached {
+			who: T::AccountId,
+			code: ReferralCode,
+		},
+		ReferralClaimed {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		BorrowFeeSet {
+			asset: AssetIdOf<T>,
+			fee: Ratio,
+		},
+		ProtocolFeeSet {
+			fee: Ratio,
+		},
+		/// `who` repaid `balance` of the borrow of `borrower` and seized its collateral
+		BorrowLiquidated {
+			who: T::AccountId,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			exchange_rate: Rate,
+			borrow_index: Rate,
+		},
+		/// `who` settled its borrow by selling `sold` of its collateral for the `balance` of debt
+		BorrowSelfLiquidated {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			sold: AssetBalanceOf<T>,
+		},
+		/// The borrow of `borrower` was settled at the oracle prices by the governance: the
+		/// treasury repaid `balance` and seized `seized` of the collateral, the rest went back to
+		/// the borrower
+		BorrowForceLiquidated {
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			seized: AssetBalanceOf<T>,
+		},
+		DustSwept {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		MinBorrowSet {
+			asset: AssetIdOf<T>,
+			min_borrow: AssetBalanceOf<T>,
+		},
+		DustDebtWrittenOff {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		LPTokenTransferred {
+			who: T::AccountId,
+			dest: T::AccountId,
+			balance: AssetBalanceOf<T>,
+		},
+		LpCollateralFactorSet {
+			asset: AssetIdOf<T>,
+			factor: Option<Ratio>,
+		},
+		StableRateRebalanced {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			rate: Rate,
+		},
+		StableRatePremiumSet {
+			asset: AssetIdOf<T>,
+			premium: Option<Ratio>,
+		},
+		BorrowRateSwitched {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			from: Rate,
+			to: Rate,
+		},
+		FixedTermBorrowed {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			rate: Rate,
+			maturity: u64,
+		},
+		BorrowRateBoundsSet {
+			asset: AssetIdOf<T>,
+			bounds: Option<(Rate, Rate)>,
+		},
+		OutflowCapSet {
+			asset: AssetIdOf<T>,
+			cap: Option<AssetBalanceOf<T>>,
+		},
+		/// The lending pool accrued `interest` on its borrowed balance since its last accrual,
+		/// of which `reserves` are kept by the protocol
+		InterestAccrued {
+			asset: AssetIdOf<T>,
+			borrow_index: Rate,
+			interest: AssetBalanceOf<T>,
+			reserves: AssetBalanceOf<T>,
+		},
+		MaintenanceModeSet {
+			enabled: bool,
+		},
+		/// The parameter change `id` of the lending pool was announced, to be executed at
+		/// block `execute_at`
+		ParamChangeAnnounced {
+			id: ParamChangeId,
+			asset: AssetIdOf<T>,
+			change: ParamChange,
+			execute_at: BlockNumberFor<T>,
+		},
+		ParamChangeExecuted {
+			id: ParamChangeId,
+			asset: AssetIdOf<T>,
+			change: ParamChange,
+		},
+		ParamChangeCancelled {
+			id: ParamChangeId,
+		},
+		/// The risk parameter `parameter` of the lending pool of `asset` is overridden with
+		/// `value`, or back to the one of the pool with `None`
+		RiskParameterSet {
+			asset: AssetIdOf<T>,
+			parameter: RiskParameter,
+			value: Option<Ratio>,
+		},
+		PermissionedPoolSet {
+			asset: AssetIdOf<T>,
+			permissioned: bool,
+		},
+		WhitelistUpdated {
+			asset: AssetIdOf<T>,
+			who: T::AccountId,
+			whitelisted: bool,
+		},
+		/// `balance` of `token`, held by `account` over what it owes, went to the treasury
+		FundsRescued {
+			account: T::AccountId,
+			token: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		/// The parameters of the lending pool were updated at once by the governance
+		PoolParamsUpdated {
+			asset: AssetIdOf<T>,
+			update: PoolParamsUpdate<AssetBalanceOf<T>>,
+		},
+		/// The borrow of `who` was wrapped into the position `id`, whose NFT `who` owns
+		PositionWrapped {
+			who: T::AccountId,
+			id: PositionId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		},
+		/// The position `id` was transferred from `from` to `to`
+		PositionTransferred {
+			id: PositionId,
+			from: T::AccountId,
+			to: T::AccountId,
+		},
+		/// The NFT of the position `id` was burned, its borrow went back to `who`
+		PositionUnwrapped {
+			who: T::AccountId,
+			id: PositionId,
+		},
+		/// `who` accepts to take over up to `allowance` of the debt of `from` in `asset`
+		DebtApproved {
+			who: T::AccountId,
+			from: T::AccountId,
+			asset: AssetIdOf<T>,
+			allowance: AssetBalanceOf<T>,
+		},
+		/// `balance` of debt in `asset` moved from a borrow of `from` to a borrow of `to`
+		DebtTransferred {
+			from: T::AccountId,
+			to: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			to_collateral_asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		DexLpCollateralSet {
+			lp_token: AssetIdOf<T>,
+			assets: Option<(AssetIdOf<T>, AssetIdOf<T>)>,
+		},
+		KeeperRegistered {
+			who: T::AccountId,
+			bond: BalanceOf<T>,
+		},
+		KeeperDeregistered {
+			who: T::AccountId,
+			bond: BalanceOf<T>,
+		},
+		LiquidationAssigned {
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			keeper: T::AccountId,
+			until: BlockNumberFor<T>,
+		},
+		KeeperMissed {
+			who: T::AccountId,
+			misses: u32,
+		},
+		KeeperSlashed {
+			who: T::AccountId,
+			bond: BalanceOf<T>,
+		},
+		MarginCall {
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			shortfall: AssetBalanceOf<T>,
+			until: BlockNumberFor<T>,
+		},
+		CollateralAdded {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		},
+		PriceDeviationReported {
+			asset: AssetIdOf<T>,
+			quote: AssetIdOf<T>,
+			price: Rate,
+			reference: Rate,
+		},
+		PoolPauseSet {
+			asset: AssetIdOf<T>,
+			paused: bool,
+		},
+		/// `who` approved `operator` with `permissions`, or revoked it when `None`
+		OperatorApproved {
+			who: T::AccountId,
+			operator: T::AccountId,
+			permissions: Option<OperatorPermissions>,
+		},
+		/// `operator` dispatched a call on behalf of `who`
+		OperatorCalled {
+			who: T::AccountId,
+			operator: T::AccountId,
+		},
+		/// `balance` of the kTokens `lp_token` of `who` are frozen for `reason`, thawed when zero
+		KTokensFrozen {
+			who: T::AccountId,
+			lp_token: AssetIdOf<T>,
+			reason: T::RuntimeFreezeReason,
+			balance: AssetBalanceOf<T>,
+		},
+		/// The loyalty tiers of the lending pool of `asset` are set
+		LoyaltyTiersSet {
+			asset: AssetIdOf<T>,
+			tiers: BoundedVec<LoyaltyTier, T::MaxLoyaltyTiers>,
+		},
+		/// The chain stalled for `gap` seconds, the liquidations are suppressed `until`
+		ChainStallDetected {
+			gap: Timestamp,
+			until: Timestamp,
+		},
+		/// `who` repaid its borrows of the (asset, collateral asset, balance) `repayments`
+		BorrowsRepaid {
+			who: T::AccountId,
+			repayments:
+				BoundedVec<(AssetIdOf<T>, AssetIdOf<T>, BalanceOf<T>), T::MaxMarketsPerAccount>,
+		},
+		/// The lending pool of `asset` is replaced with the pool of LP token `new_pool`, the
+		/// pool of LP token `old_pool` retired along with its `borrows`
+		LendingPoolReplaced {
+			asset: AssetIdOf<T>,
+			old_pool: AssetIdOf<T>,
+			new_pool: AssetIdOf<T>,
+			borrows: u32,
+		},
+		/// The position of `who` in the retired pool `old_pool` is migrated to `new_pool`, its
+		/// `supply` and its `debt` with it
+		PositionMigrated {
+			who: T::AccountId,
+			old_pool: AssetIdOf<T>,
+			new_pool: AssetIdOf<T>,
+			supply: AssetBalanceOf<T>,
+			debt: AssetBalanceOf<T>,
+		},
+		/// The lending pool of `asset` is remapped to `new_asset`, a balance of `asset` being
+		/// worth `ratio` times as much of `new_asset`, along with its `positions`
+		PoolRedenominated {
+			asset: AssetIdOf<T>,
+			new_asset: AssetIdOf<T>,
+			ratio: Rate,
+			positions: u32,
+		},
+		/// The share of the lending pool of `asset` a single account may hold is capped to `cap`,
+		/// or uncapped with `None`
+		AccountSupplyCapSet {
+			asset: AssetIdOf<T>,
+			cap: Option<Permill>,
+		},
+		/// `who` is assigned the `tier`, or left to the `AccountTierProvider` with `None`
+		AccountTierSet {
+			who: T::AccountId,
+			tier: Option<AccountTier>,
+		},
+		/// The lending pool of `asset` opens to the accounts of `tier` on `terms`, or closes to
+		/// them with `None`
+		TierTermsSet {
+			asset: AssetIdOf<T>,
+			tier: AccountTier,
+			terms: Option<TierTerms<AssetBalanceOf<T>>>,
+		},
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Lending Pool does not exist
+		LendingPoolDoesNotExist,
+		/// Lending Pool already exists
+		LendingPoolAlreadyExists,
+		/// There are `MaxPools` lending pools already
+		TooManyPools,
+		/// Lending Pool already activated
+		LendingPoolAlreadyActivated,
+		/// Lending Pool already deactivated
+		LendingPoolAlreadyDeactivated,
+		/// Lending Pool is not active or has been deprecated
+		LendingPoolNotActive,
+		/// The user has not enough liquidity
+		NotEnoughLiquiditySupply,
+		/// The user wants to withdraw more than allowed!
+		NotEnoughElegibleLiquidityToWithdraw,
+		/// Lending Pool is empty
+		LendingPoolIsEmpty,
+		/// The classic Overflow Error
+		OverflowError,
+		/// The ID already exists
+		IdAlreadyExists,
+		/// The user has not enough collateral assets
+		NotEnoughCollateral,
+		/// The Loan being repayed does not exists
+		LoanDoesNotExists,
+		/// Price of the asset can not be zero
+		InvalidAssetPrice,
+		/// The price of the asset is not available
+		PriceUnavailable,
+		/// The lending pool has no reward program
+		RewardProgramDoesNotExist,
+		/// The reward asset of an existing reward program can not be changed
+		RewardAssetMismatch,
+		/// The vesting cliff must not exceed a non-zero vesting duration
+		InvalidVestingTerms,
+		/// The user has no rewards vesting in the lending pool
+		NoVestedRewards,
+		/// Rewards of a program with vesting can not be compounded
+		CannotCompoundVestedRewards,
+		/// The user did not allow keepers to compound on their behalf
+		AutoCompoundNotEnabled,
+		/// The referral code can not be empty
+		InvalidReferralCode,
+		/// The referral code is already registered
+		ReferralCodeAlreadyExists,
+		/// The referral code is not registered
+		ReferralCodeDoesNotExist,
+		/// Accounts can not refer themselves
+		SelfReferral,
+		/// The referral code is owned by another account
+		NotReferralCodeOwner,
+		/// The referral code has no earnings to claim
+		NoReferralEarnings,
+		/// The borrow fee must be lower than 100%
+		InvalidBorrowFee,
+		/// The protocol fee must be lower than 100%
+		InvalidProtocolFee,
+		/// The borrow has no shortfall and can not be liquidated
+		BorrowNotLiquidatable,
+		/// The interest of the lending pool can not be accrued at this time
+		NoInterestToAccrue,
+		/// A borrow of the account has a shortfall, it must be repaid or liquidated first
+		AccountHasShortfall,
+		/// The account holds `MaxMarketsPerAccount` borrows already
+		TooManyMarkets,
+		/// The balance to supply is worth less than one LP token
+		SupplyTooSmall,
+		/// The debt of the borrow would be below the minimum borrow of the lending pool
+		BorrowTooSmall,
+		/// The debt of the borrow is not below the `DustBalance`
+		DebtNotDust,
+		/// The LP tokens of the lending pool are not accepted as collateral
+		LpTokenCollateralNotAllowed,
+		/// The lending pool offers no stable rate
+		StableBorrowNotAllowed,
+		/// The borrow is at the other rate mode, which is fixed at its origination
+		BorrowRateModeMismatch,
+		/// The utilisation of the lending pool or the stable rate of the borrow are too low
+		StableRateNotRebalanceable,
+		/// A fixed-term borrow can not be added to nor change its rate
+		FixedTermBorrowNotExtendable,
+		/// The term of a fixed-term borrow can not be zero
+		InvalidBorrowTerm,
+		/// The quoted rate of the borrow is above the maximum rate of the borrower
+		BorrowRateTooHigh,
+		/// The floor of the borrow rate is above its cap
+		InvalidRateBounds,
+		/// The outflows of the lending pool reached its cap, try again in a few blocks
+		OutflowCapExceeded,
+		/// The supply can not be withdrawn before the `WithdrawCooldown` elapsed
+		WithdrawalInCooldown,
+		/// The balance of the operation can not be zero
+		AmountTooLow,
+		/// The reserves of the lending pool do not cover the balance withdrawn or borrowed
+		InsufficientLiquidity,
+		/// The user does not hold the collateral balance to lock
+		InsufficientCollateral,
+		/// The supply would exceed a supply cap of the account: its share of the lending pool
+		/// under the `AccountSupplyCaps`, or the `max_supply` of its tier
+		SupplyCapExceeded,
+		/// The borrow would take the principal borrowed from the lending pool beyond its
+		/// `BorrowCaps`
+		BorrowCapExceeded,
+		/// The collateral factor would exceed the liquidation threshold, or the kink of the rate
+		/// model is not in (0, 1]
+		InvalidRiskParameters,
+		/// The parameter change was not announced, or was executed or cancelled already
+		ParamChangeDoesNotExist,
+		/// The delay of the parameter change has not elapsed yet
+		ParamChangeNotDue,
+		/// The lending pool is permissioned and the account is not on its whitelist
+		NotWhitelisted,
+		/// The account holds no balance of the token over what it owes
+		NothingToRescue,
+		/// There are more entries to read than the witness of the call
+		BadWitness,
+		/// The position was not wrapped, or was unwrapped already
+		PositionDoesNotExist,
+		/// The account does not own the NFT of the position
+		NotPositionOwner,
+		/// The account holds a borrow in the same asset against the same collateral already
+		BorrowAlreadyExists,
+		/// The recipient did not approve to take over as much debt from the account
+		DebtNotApproved,
+		/// The asset is not an LP token of the DEX
+		NotDexLpToken,
+		/// The account is a keeper already
+		KeeperAlreadyRegistered,
+		/// The account is not a keeper
+		NotKeeper,
+		/// The keeper has liquidations assigned which are not settled yet
+		KeeperBusy,
+		/// No keeper is registered
+		NoKeepers,
+		/// The liquidation of the borrow is assigned to a keeper already
+		LiquidationAlreadyAssigned,
+		/// The liquidation of the borrow is reserved to the keeper it is assigned to
+		LiquidationReserved,
+		/// The margin call of the borrow is issued already
+		MarginCallAlreadyIssued,
+		/// The grace period of the margin call of the borrow is not over
+		MarginCallGracePeriod,
+		/// The borrows from and the withdrawals of the lending pool are paused
+		PoolPaused,
+		/// The price does not deviate from its reference beyond the `MaxPriceDeviation`
+		PriceWithinDeviation,
+		/// The caller is not approved as an operator of the account
+		OperatorNotApproved,
+		/// The permissions of the operator do not allow the call
+		OperatorNotPermitted,
+		/// The first deposit of the lending pool is below the `MinPoolLiquidity`
+		InitialLiquidityTooLow,
+		/// The asset is not the LP token of a lending pool
+		NotKToken,
+		/// The account holds fewer kTokens than frozen
+		InsufficientKTokens,
+		/// The kTokens are frozen
+		KTokensFrozen,
+		/// The kTokens of the account are frozen for `MaxKTokenFreezes` reasons already
+		TooManyKTokenFreezes,
+		/// The loyalty tiers are not strictly increasing or their bonus exceeds the
+		/// `MaxLoyaltyBonus`
+		InvalidLoyaltyTiers,
+		/// The liquidations are suppressed for the grace period following a stall of the chain
+		StallGracePeriod,
+		/// A borrow is repaid more than once in the same call
+		DuplicateRepayment,
+		/// The lending pool can not be replaced: it has stable-rate, fixed-term or wrapped
+		/// borrows, or its LP tokens are used as collateral or beyond the lending pools
+		PoolNotReplaceable,
+		/// The lending pool is not retired
+		PoolNotRetired,
+		/// The lending pool does not replace the retired one
+		NotReplacementPool,
+		/// The account has no position in the retired lending pool
+		NothingToMigrate,
+		/// The borrow is owed to a retired lending pool, it must be migrated first
+		BorrowNotMigrated,
+		/// The lending pool can not be redenominated: the ratio is zero, the new asset does not
+		/// exist or is the native one, or the asset is used beyond its lending pool
+		InvalidRedenomination,
+		/// The lending pool is not open to the tier of the account
+		TierNotEligible,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_block_number: BlockNumberFor<T>) -> Weight {
+			// the weight of the stall detection in `on_finalize`
+			let stall_detection = T::DbWeight::get().reads_writes(1, 2);
+			if MigrationCursor::<T>::exists() {
+				return migrations::v1::MigrateToV1::<T>::step(
+					migrations::MIGRATION_BLOCK_SHARE * T::BlockWeights::get().max_block,
+				)
+				.saturating_add(stall_detection);
+			}
+			Self::accrue_pools(T::MaxPoolsPerBlock::get()).saturating_add(stall_detection)
+		}
+
+		fn on_finalize(_block_number: BlockNumberFor<T>) {
+			Self::detect_stall();
+		}
+
+		fn on_idle(_block_number: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			if MigrationCursor::<T>::exists() {
+				return T::DbWeight::get().reads(1);
+			}
+			Self::sweep_positions(remaining_weight)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_block_number: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_try_state()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<frame_support::sp_std::vec::Vec<u8>, sp_runtime::TryRuntimeError>
+		{
+			Self::do_pre_upgrade()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(
+			state: frame_support::sp_std::vec::Vec<u8>,
+		) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_post_upgrade(state)
+		}
+
+		fn offchain_worker(block_number: BlockNumberFor<T>) {
+			if MigrationCursor::<T>::exists() {
+				return;
+			}
+			Self::offchain_keeper(block_number);
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// The `create_lending_pool` function allows a user to Create a new reserve and then
+		/// supply it with some liquidity. Given an asset and its amount, it creates a
 		/// new lending pool, if it does not already exist, and adds the provided liquidity
 		///
 		/// The user will receive LP tokens in return in ratio.
@@ -768,6 +2417,7 @@ pub mod pallet {
 		/// * If the origin is not signed (i.e., the function was not called by a user).
 		/// * If the provided assets do not exist.
 		/// * If `amount` is 0 or less.
+		/// * If `amount` is below the `MinPoolLiquidity`.
 		/// * If adding liquidity to the pool fails for any reason due to arithmetic overflows or
 		///   underflows
 		///
@@ -776,20 +2426,20 @@ pub mod pallet {
 		/// If the function succeeds, it triggers two events:
 		///
 		/// * `LendingPoolAdded(who, asset_a)` if a new lending pool was created.
-		/// * `DepositSupplied(who, asset_a, amount_a)` after the liquidity has been successfully
-		///   added.
+		/// * `DepositSupplied(who, asset_a, amount_a, minted, exchange_rate, borrow_index,
+		///   lp_tokens, utilisation)` after the liquidity has been successfully added.
 		#[pallet::call_index(0)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::WeightInfo::create_lending_pool())]
 		pub fn create_lending_pool(
 			origin: OriginFor<T>,
-			id: LendingPoolId,
+			id: AssetIdOf<T>,
 			asset: AssetIdOf<T>,
 			balance: BalanceOf<T>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			Self::do_create_lending_pool(&who, id, asset, balance)?;
+			let minted = Self::do_create_lending_pool(&who, id, asset, balance)?;
 			Self::deposit_event(Event::LendingPoolAdded { who: who.clone(), asset });
-			Self::deposit_event(Event::DepositSupplied { who, asset, balance });
+			Self::deposit_supplied_event(who, asset, balance, minted);
 			Ok(())
 		}
 
@@ -819,7 +2469,7 @@ pub mod pallet {
 		///
 		/// * `LendingPoolActivated(asset_a)` if the lending pool was activated.
 		#[pallet::call_index(1)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::WeightInfo::activate_lending_pool())]
 		pub fn activate_lending_pool(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			Self::do_activate_lending_pool(asset)?;
@@ -835,6 +2485,7 @@ pub mod pallet {
 		/// that creates the lending pool and add some liquidity.
 		/// * `asset` - The identifier for the type of asset that the user wants to provide.
 		/// * `balance` - The amount of `asset` that the user is providing.
+		/// * `referral` - An optional referral code to attach to the user.
 		///
 		/// # Errors
 		///
@@ -845,19 +2496,23 @@ pub mod pallet {
 		/// * If the pool does not exist.
 		/// * If the pool is not active.
 		/// * If the user has not enough liquidity to supply.
-		/// * If the balance amount to supply is not valid.
+		/// * If the balance amount to supply is zero.
+		/// * If the supply would exceed a supply cap of the user.
+		/// * If the referral code is not registered or owned by the user.
 		/// * If adding liquidity to the pool fails for any reason due to arithmetic overflows or
 		///  underflows
 		#[pallet::call_index(2)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::WeightInfo::supply())]
 		pub fn supply(
 			origin: OriginFor<T>,
 			asset: AssetIdOf<T>,
 			balance: BalanceOf<T>,
+			referral: Option<ReferralCode>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			Self::do_supply(&who, asset, balance)?;
-			Self::deposit_event(Event::DepositSupplied { who, asset, balance });
+			Self::attach_referral(&who, referral)?;
+			let minted = Self::do_supply(&who, asset, balance)?;
+			Self::deposit_supplied_event(who, asset, balance, minted);
 			Ok(())
 		}
 
@@ -880,6 +2535,9 @@ pub mod pallet {
 		/// * If the pool is not active.
 		/// * If the user has not enough liquidity to supply.
 		/// * If the balance amount to supply is not valid.
+		/// * If a borrow of the user has a shortfall.
+		/// * If the user supplied to the pool less than `WithdrawCooldown` blocks ago.
+		/// * If the withdrawals of the pool are paused.
 		/// * If adding liquidity to the pool fails for any reason due to arithmetic overflows or
 		/// underflows
 		///
@@ -887,18 +2545,24 @@ pub mod pallet {
 		///
 		/// If the function succeeds, it triggers an event:
 		///
-		/// * `DepositWithdrawn(who, balance)` if the lending pool was activated.
+		/// * `DepositWithdrawn(who, asset, balance, burned, exchange_rate, borrow_index,
+		///   lp_tokens, utilisation)` if the lending pool was activated.
+		///
+		/// # Weight
+		///
+		/// Charged for `MaxMarketsPerAccount` borrows of the user, the unused weight is refunded.
 		#[pallet::call_index(3)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::WeightInfo::withdraw(T::MaxMarketsPerAccount::get()))]
 		pub fn withdraw(
 			origin: OriginFor<T>,
 			asset: AssetIdOf<T>,
 			balance: BalanceOf<T>,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			Self::do_withdrawal(&who, asset, balance)?;
-			Self::deposit_event(Event::DepositWithdrawn { who, balance });
-			Ok(())
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let (balance, burned) = Self::do_withdrawal(&who, asset, balance, Precision::Exact)?;
+			Self::deposit_withdrawn_event(who, asset, balance, burned);
+			Ok(Some(T::WeightInfo::withdraw(markets)).into())
 		}
 
 		/// The `borrow` function allows a user to borrow liquidity from a lending pool.
@@ -909,6 +2573,8 @@ pub mod pallet {
 		/// that creates the lending pool and add some liquidity.
 		/// * `asset` - The identifier for the type of asset that the user wants to provide.
 		/// * `balance` - The amount of `asset` that the user is providing.
+		/// * `referral` - An optional referral code to attach to the user. The referrer earns a
+		///   `ReferralShare` of the protocol revenue on the interest of the user's borrows.
 		///
 		/// # Errors
 		///
@@ -920,6 +2586,11 @@ pub mod pallet {
 		/// * If the pool is not active.
 		/// * If the user has not enough liquidity to supply.
 		/// * If the balance amount to supply is not valid.
+		/// * If the referral code is not registered or owned by the user.
+		/// * If a borrow of the user has a shortfall.
+		/// * If the borrows from the pool are paused.
+		/// * If the borrow would exceed the borrow cap of the pool.
+		/// * If the price of the collateral is not available.
 		/// * If adding liquidity to the pool fails for any reason due to arithmetic overflows or
 		/// underflows
 		///
@@ -927,20 +2598,31 @@ pub mod pallet {
 		///
 		/// If the function succeeds, it triggers an event:
 		///
-		/// * `DepositBorrowed(who, balance)` if the lending pool was activated.
+		/// * `DepositBorrowed(who, asset, collateral_asset, balance, fee, exchange_rate,
+		///   borrow_index, debt, utilisation)` if the lending pool was activated. The user receives
+		///   `balance` minus the origination `fee` credited to the pool reserves.
+		/// * `ReferralAttached(who, code)` if the referral code was attached to the user.
+		///
+		/// # Weight
+		///
+		/// Charged for `MaxMarketsPerAccount` borrows of the user, the unused weight is refunded.
 		#[pallet::call_index(4)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::WeightInfo::borrow(T::MaxMarketsPerAccount::get()))]
 		pub fn borrow(
 			origin: OriginFor<T>,
 			asset: AssetIdOf<T>,
 			balance: AssetBalanceOf<T>,
 			collateral_asset: AssetIdOf<T>,
 			collateral_balance: AssetBalanceOf<T>,
-		) -> DispatchResult {
+			referral: Option<ReferralCode>,
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			Self::do_borrow(&who, asset, balance, collateral_asset, collateral_balance)?;
-			Self::deposit_event(Event::DepositBorrowed { who, balance });
-			Ok(())
+			let markets = Self::ensure_no_shortfall(&who)?;
+			Self::attach_referral(&who, referral)?;
+			let fee =
+				Self::do_borrow(&who, asset, balance, collateral_asset, collateral_balance, false)?;
+			Self::deposit_borrowed_event(who, asset, collateral_asset, balance, fee);
+			Ok(Some(T::WeightInfo::borrow(markets)).into())
 		}
 
 		/// The `repay` function allows a user to repay liquidity from a lending pool.
@@ -969,9 +2651,10 @@ pub mod pallet {
 		///
 		/// If the function succeeds, it triggers an event:
 		///
-		/// * `DepositRepaid(who, balance)` if the lending pool was activated.
+		/// * `DepositRepaid(who, asset, collateral_asset, balance, exchange_rate, borrow_index,
+		///   debt, utilisation)` if the lending pool was activated.
 		#[pallet::call_index(5)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::WeightInfo::repay())]
 		pub fn repay(
 			origin: OriginFor<T>,
 			asset: AssetIdOf<T>,
@@ -980,113 +2663,2177 @@ pub mod pallet {
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			Self::do_repay(&who, asset, balance, collateral_asset)?;
-			Self::deposit_event(Event::DepositRepaid { who, balance });
-			Ok(())
-		}
-
-		#[pallet::call_index(6)]
-		#[pallet::weight(Weight::default())]
-		pub fn claim_rewards(origin: OriginFor<T>, balance: BalanceOf<T>) -> DispatchResult {
-			let who = ensure_signed(origin)?;
-			Self::deposit_event(Event::RewardsClaimed { who, balance });
+			Self::deposit_repaid_event(who, asset, collateral_asset, balance);
 			Ok(())
 		}
 
-		/// The `deactivate_lending_pool` function allows a user to deactivate a lending pool that
-		/// is not empty. Once a liquidity pool gets deactivated supplies operations can not be
+		/// The `claim_rewards` function allows a user to claim the rewards accrued in the lending
+		/// pools they supplied to.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of this function. This should be signed by the user
+		/// that claims the rewards.
+		/// * `asset` - The lending pool to claim the rewards from, or all the lending pools the
+		///   user participates in (up to `MaxMarketsPerAccount`) if `None`.
+		/// * `dest` - The account the rewards are paid to, or vest for, e.g. a custodian or a
+		///   treasury. The user itself if `None`.
+		///
+		/// # Errors
+		///
+		/// This function will return an error in the following scenarios:
+		///
+		/// * If the origin is not signed (i.e., the function was not called by a user).
+		/// * If the lending pool has no reward program.
+		/// * If the pallet has not enough reward assets to pay the rewards.
+		///
+		/// # Events
+		///
+		/// If the function succeeds, it triggers for every lending pool:
+		///
+		/// * `RewardsClaimed(who, dest, balance)` if some rewards were claimed.
+		/// * `RewardsVested(dest, asset, balance)` if the claimed rewards vest.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::claim_rewards(match asset {
+			Some(_) => 1,
+			None => T::MaxMarketsPerAccount::get(),
+		}))]
+		pub fn claim_rewards(
+			origin: OriginFor<T>,
+			asset: Option<AssetIdOf<T>>,
+			dest: Option<T::AccountId>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let dest = dest.unwrap_or_else(|| who.clone());
+			let pools = match asset {
+				Some(asset) => {
+					Self::do_claim_rewards(&who, asset, &dest)?;
+					1
+				},
+				None => Self::do_claim_all_rewards(&who, &dest)?,
+			};
+			Ok(Some(T::WeightInfo::claim_rewards(pools)).into())
+		}
+
+		/// The `deactivate_lending_pool` function allows a user to deactivate a lending pool that
+		/// is not empty. Once a liquidity pool gets deactivated supplies operations can not be
 		/// performed otherwise only withdrawals.
 		///
-		/// # Arguments
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of this function. This should be signed by the user
+		/// that creates the lending pool and add some liquidity.
+		/// * `asset` - The identifier for the type of asset that the user wants to provide.
+		///
+		/// # Errors
+		///
+		/// This function will return an error in the following scenarios:
+		///
+		/// * If the origin is not signed (i.e., the function was not called by a user).
+		/// * If the provided assets do not exist.
+		/// * If the pool does not exist.
+		/// * If the pool is already deactivated.
+		/// * If the pool is empty.
+		///
+		/// # Events
+		///
+		/// If the function succeeds, it triggers an event:
+		///
+		/// * `LendingPoolDeactivated(asset_a)` if the lending pool was deactivated.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::deactivate_lending_pool())]
+		pub fn deactivate_lending_pool(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::deposit_event(Event::LendingPoolDeactivated { who, asset });
+			Ok(())
+		}
+
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::update_pool_rate_model())]
+		pub fn update_pool_rate_model(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::deposit_event(Event::LendingPoolRateModelUpdated { who, asset });
+			Ok(())
+		}
+
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::update_pool_kink())]
+		pub fn update_pool_kink(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::deposit_event(Event::LendingPoolKinkUpdated { who, asset });
+			Ok(())
+		}
+
+		/// Sets the price of one asset in terms of another asset.
+		///
+		/// The `set_asset_price` extrinsic allows a user to specify the relative price of one asset
+		/// (`asset_1`) in terms of another asset (`asset_2`).
+		///
+		/// # Parameters
+		/// - `origin`: Must be `OracleOrigin`.
+		/// - `asset_1`: The identifier for the first asset. This is the asset whose price is being
+		///   set.
+		/// - `asset_2`: The identifier for the second asset. This is the asset relative to which
+		///   the price is measured.
+		/// - `price`: The price of `asset_1` in terms of `asset_2`. This must be a non-zero value
+		///   to avoid errors. It is the price of a whole unit of `asset_1` in whole units of
+		///   `asset_2`, whatever their decimals.
+		///
+		/// # Emits
+		/// - `AssetPriceAdded`: This event is emitted after the price is successfully set. It
+		///   contains the asset identifiers and the new price.
+		///
+		/// # Errors
+		/// - `InvalidAssetPrice`: This error is thrown if the `price` parameter is zero.
+		///
+		/// # Note this should be moved to a new pallet `prices`
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::set_asset_price())]
+		pub fn set_asset_price(
+			origin: OriginFor<T>,
+			asset_1: AssetIdOf<T>,
+			asset_2: AssetIdOf<T>,
+			price: FixedU128,
+		) -> DispatchResult {
+			T::OracleOrigin::ensure_origin(origin)?;
+			// price should not be zero
+			ensure!(price > FixedU128::zero(), Error::<T>::InvalidAssetPrice);
+
+			AssetPrices::<T>::set((asset_1, asset_2), Some(price));
+			// the pools of the repriced assets are revalued
+			for asset in [asset_1, asset_2] {
+				if let Some(pool) = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset)) {
+					Self::update_pool_totals(&pool);
+				}
+			}
+
+			// Emit an event.
+			Self::deposit_event(Event::AssetPriceAdded { asset_1, asset_2, price });
+
+			Ok(())
+		}
+
+		/// Creates or updates the reward program of a lending pool.
+		///
+		/// The rewards accrued so far are kept, the new speed applies from now on and a paused
+		/// emission resumes. Suppliers start earning once their working balance is recorded, on
+		/// their next interaction with the pool or through `update_reward_boost`.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `reward_asset`: The asset the rewards are paid in. It can not be changed once the
+		///   program exists.
+		/// - `speed`: The amount of `reward_asset` distributed to the suppliers every second.
+		/// - `vesting`: If set, claimed rewards are locked in a vesting schedule with the given
+		///   cliff and linear unlock duration (in seconds) instead of being paid out immediately.
+		///
+		/// # Emits
+		/// - `RewardProgramUpdated`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `RewardAssetMismatch`: The program exists with another reward asset.
+		/// - `InvalidVestingTerms`: The duration is zero or shorter than the cliff.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::set_reward_program())]
+		pub fn set_reward_program(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			reward_asset: AssetIdOf<T>,
+			speed: AssetBalanceOf<T>,
+			vesting: Option<VestingTerms>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_set_reward_program(asset, reward_asset, speed, vesting.clone())?;
+			Self::deposit_event(Event::RewardProgramUpdated {
+				asset,
+				reward_asset,
+				speed,
+				vesting,
+			});
+			Ok(())
+		}
+
+		/// Releases the vested part of the rewards claimed from the lending pool of `asset`.
+		///
+		/// # Emits
+		/// - `RewardsReleased`
+		///
+		/// # Errors
+		/// - `NoVestedRewards`: The user has no rewards vesting in this lending pool.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::claim_vested_rewards())]
+		pub fn claim_vested_rewards(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_release_vested_rewards(&who, asset)?;
+			Ok(())
+		}
+
+		/// Claims the rewards accrued in the lending pool of `asset` and supplies them into the
+		/// lending pool of the reward asset, if there is an active one.
+		///
+		/// # Emits
+		/// - `RewardsClaimed`
+		/// - `RewardsCompounded` if the rewards were supplied.
+		///
+		/// # Errors
+		/// - `RewardProgramDoesNotExist`: The lending pool has no reward program.
+		/// - `CannotCompoundVestedRewards`: The claimed rewards of the program vest.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::compound_rewards())]
+		pub fn compound_rewards(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_compound_rewards(&who, asset, None)?;
+			Ok(())
+		}
+
+		/// Allows or disallows keepers to compound the rewards of the caller accrued in the
+		/// lending pool of `asset`, in exchange for a `CompoundTip` share of the rewards.
+		///
+		/// # Emits
+		/// - `AutoCompoundSet`
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::set_auto_compound())]
+		pub fn set_auto_compound(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			enabled: bool,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			if enabled {
+				AutoCompound::<T>::insert(&who, asset, true);
+			} else {
+				AutoCompound::<T>::remove(&who, asset);
+			}
+			Self::deposit_event(Event::AutoCompoundSet { who, asset, enabled });
+			Ok(())
+		}
+
+		/// Compounds the rewards of `who` accrued in the lending pool of `asset` on their
+		/// behalf. The caller receives a `CompoundTip` share of the rewards.
+		///
+		/// # Emits
+		/// - `RewardsClaimed`
+		/// - `RewardsCompounded` if the rewards were supplied.
+		///
+		/// # Errors
+		/// - `AutoCompoundNotEnabled`: `who` did not opt in to auto compounding.
+		/// - `RewardProgramDoesNotExist`: The lending pool has no reward program.
+		/// - `CannotCompoundVestedRewards`: The claimed rewards of the program vest.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::compound_rewards_for())]
+		pub fn compound_rewards_for(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let keeper = ensure_signed(origin)?;
+			ensure!(AutoCompound::<T>::get(&who, asset), Error::<T>::AutoCompoundNotEnabled);
+			Self::do_compound_rewards(&who, asset, Some(&keeper))?;
+			Ok(())
+		}
+
+		/// Accrues the rewards of `who` in the lending pool of `asset` and refreshes their
+		/// working balance, e.g. after `who` changed their governance token locks.
+		///
+		/// Anyone can call it, which allows to remove the boost of accounts whose locks expired.
+		///
+		/// # Emits
+		/// - `RewardBoostUpdated` with the new working balance of `who`.
+		///
+		/// # Errors
+		/// - `RewardProgramDoesNotExist`: The lending pool has no reward program.
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::update_reward_boost())]
+		pub fn update_reward_boost(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			ensure!(
+				RewardPrograms::<T>::contains_key(asset),
+				Error::<T>::RewardProgramDoesNotExist
+			);
+			Self::accrue_rewards(&who, asset)?;
+			let balance = UserRewards::<T>::get(&who, asset).working_balance;
+			Self::deposit_event(Event::RewardBoostUpdated { who, asset, balance });
+			Ok(())
+		}
+
+		/// Funds the rewards pot with `balance` of the reward asset of the program of `asset`.
+		///
+		/// The emission of a reward program pauses when the rewards pot can not cover the
+		/// accrued rewards anymore. It resumes once the pot is funded again.
+		///
+		/// # Emits
+		/// - `RewardsFunded`
+		/// - `RewardEmissionResumed` if the emission was paused and the pot is solvent again.
+		///
+		/// # Errors
+		/// - `RewardProgramDoesNotExist`: The lending pool has no reward program.
+		/// - `AmountTooLow`: The balance is zero.
+		#[pallet::call_index(17)]
+		#[pallet::weight(T::WeightInfo::fund_rewards())]
+		pub fn fund_rewards(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_fund_rewards(&who, asset, balance)?;
+			Self::deposit_event(Event::RewardsFunded { who, asset, balance });
+			Ok(())
+		}
+
+		/// Registers a referral code owned by the caller. Users attach it when supplying or
+		/// borrowing, the caller then earns a `ReferralShare` of the protocol revenue on the
+		/// interest of their borrows.
+		///
+		/// # Emits
+		/// - `ReferralCodeRegistered`
+		///
+		/// # Errors
+		/// - `InvalidReferralCode`: The code is empty.
+		/// - `ReferralCodeAlreadyExists`: The code is already registered.
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::register_referral_code())]
+		pub fn register_referral_code(origin: OriginFor<T>, code: ReferralCode) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_register_referral_code(&who, code.clone())?;
+			Self::deposit_event(Event::ReferralCodeRegistered { who, code });
+			Ok(())
+		}
+
+		/// Claims the earnings of a referral code in the `asset` of a lending pool.
+		///
+		/// # Emits
+		/// - `ReferralClaimed`
+		///
+		/// # Errors
+		/// - `ReferralCodeDoesNotExist`: The code is not registered.
+		/// - `NotReferralCodeOwner`: The caller does not own the code.
+		/// - `NoReferralEarnings`: The code has no earnings in `asset`.
+		/// - `LendingPoolDoesNotExist`: There is no lending pool of `asset`.
+		#[pallet::call_index(19)]
+		#[pallet::weight(T::WeightInfo::claim_referral_earnings())]
+		pub fn claim_referral_earnings(
+			origin: OriginFor<T>,
+			code: ReferralCode,
+			asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let balance = Self::do_claim_referral_earnings(&who, code, asset)?;
+			Self::deposit_event(Event::ReferralClaimed { who, asset, balance });
+			Ok(())
+		}
+
+		/// Sets the origination fee charged on the borrows of a lending pool. The fee is
+		/// deducted from the borrowed balance and credited to the pool reserves, the full
+		/// balance is owed by the borrower.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `fee`: The share of the borrowed balance charged as a fee, zero to disable it.
+		///
+		/// # Emits
+		/// - `BorrowFeeSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `InvalidBorrowFee`: The fee is 100%.
+		#[pallet::call_index(20)]
+		#[pallet::weight(T::WeightInfo::set_borrow_fee())]
+		pub fn set_borrow_fee(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			fee: Ratio,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(fee < Ratio::one(), Error::<T>::InvalidBorrowFee);
+			LendingPoolStorage::<T>::try_mutate(AssetPool::<T>::from(asset), |pool| {
+				pool.as_mut().ok_or(Error::<T>::LendingPoolDoesNotExist)?.borrow_fee = fee;
+				Ok::<(), Error<T>>(())
+			})?;
+			Self::deposit_event(Event::BorrowFeeSet { asset, fee });
+			Ok(())
+		}
+
+		/// Sets the protocol fee switch. The fee is the share of the interest paid on all the
+		/// lending pools redirected to the `TreasuryAccount`, on top of the pool reserve factors.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `fee`: The share of the interest revenue, zero to switch the fee off.
+		///
+		/// # Emits
+		/// - `ProtocolFeeSet`
+		///
+		/// # Errors
+		/// - `InvalidProtocolFee`: The fee is 100%.
+		#[pallet::call_index(21)]
+		#[pallet::weight(T::WeightInfo::set_protocol_fee())]
+		pub fn set_protocol_fee(origin: OriginFor<T>, fee: Ratio) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(fee < Ratio::one(), Error::<T>::InvalidProtocolFee);
+			ProtocolFee::<T>::put(fee);
+			Self::deposit_event(Event::ProtocolFeeSet { fee });
+			Ok(())
+		}
+
+		/// Liquidates a borrow with a shortfall, i.e. whose debt exceeds its collateral value
+		/// weighted with the liquidation threshold of the lending pool. The liquidator repays the
+		/// whole debt and seizes all the collateral. A liquidation assigned to a keeper is
+		/// reserved to it for the `KeeperExclusivity`, and counts as a miss of the keeper when
+		/// liquidated by anyone else afterwards. The borrow must have been margin called for the
+		/// `MarginCallGracePeriod`, unless its shortfall is severe. No borrow can be liquidated
+		/// during the `StallGracePeriod` following a stall of the chain.
+		///
+		/// # Parameters
+		/// - `origin`: The liquidator, or none for the liquidations submitted by the offchain
+		///   worker, which are repaid by the `TreasuryAccount`.
+		/// - `borrower`: The account whose borrow is liquidated.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `BorrowLiquidated`
+		/// - `KeeperMissed`, and `KeeperSlashed` at its `MaxKeeperMisses`-th miss in a row, when
+		///   the keeper assigned to the liquidation missed it
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - `BorrowNotLiquidatable`: The borrow has no shortfall.
+		/// - `LiquidationReserved`: The liquidation is reserved to the keeper it is assigned to.
+		/// - `MarginCallGracePeriod`: The grace period of the margin call is not over.
+		/// - `StallGracePeriod`: The chain stalled recently.
+		///
+		/// # Weight
+		///
+		/// Charged for a borrower with `MaxMarketsPerAccount` borrows.
+		#[pallet::call_index(22)]
+		#[pallet::weight(T::WeightInfo::liquidate_borrow(T::MaxMarketsPerAccount::get()))]
+		pub fn liquidate_borrow(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let liquidator = match ensure_signed(origin.clone()) {
+				Ok(who) => who,
+				Err(_) => {
+					ensure_none(origin)?;
+					T::TreasuryAccount::get()
+				},
+			};
+			let balance =
+				Self::do_liquidate_borrow(&liquidator, &borrower, asset, collateral_asset, false)?;
+			let (exchange_rate, borrow_index) = Self::pool_indexes(asset);
+			Self::deposit_event(Event::BorrowLiquidated {
+				who: liquidator,
+				borrower,
+				asset,
+				collateral_asset,
+				balance,
+				exchange_rate,
+				borrow_index,
+			});
+			Ok(())
+		}
+
+		/// Accrues the interest of a lending pool. Submitted by the offchain worker, so that the
+		/// indexes of the pools do not drift in between the user operations.
+		///
+		/// # Parameters
+		/// - `origin`: Must be none.
+		/// - `asset`: The underlying asset of the lending pool.
+		///
+		/// # Emits
+		/// - `InterestAccrued`, as every accrual of the interest of a pool does
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `NoInterestToAccrue`: The pool is not active, has no borrows or was accrued already.
+		#[pallet::call_index(23)]
+		#[pallet::weight(T::WeightInfo::accrue_interest())]
+		pub fn accrue_interest(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::do_accrue_interest(asset)
+		}
+
+		/// Sets the minimum debt of the borrows of a lending pool, so that no borrow is too small
+		/// to be worth liquidating. Borrows may not be opened nor partially repaid below it.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `min_borrow`: The minimum debt, zero to disable it.
+		///
+		/// # Emits
+		/// - `MinBorrowSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(24)]
+		#[pallet::weight(T::WeightInfo::set_min_borrow())]
+		pub fn set_min_borrow(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			min_borrow: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			MinBorrow::<T>::insert(asset, min_borrow);
+			Self::deposit_event(Event::MinBorrowSet { asset, min_borrow });
+			Ok(())
+		}
+
+		/// Writes off a borrow with a shortfall whose debt is below the `DustBalance`, too small
+		/// to be worth liquidating. The debt is forgiven at the expense of the lending pool and
+		/// the collateral goes to the `TreasuryAccount`.
+		///
+		/// # Parameters
+		/// - `origin`: Any signed account.
+		/// - `borrower`: The account whose borrow is written off.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `DustDebtWrittenOff`
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - `BorrowNotLiquidatable`: The borrow has no shortfall.
+		/// - `DebtNotDust`: The debt is not below the `DustBalance`.
+		#[pallet::call_index(25)]
+		#[pallet::weight(T::WeightInfo::write_off_dust_debt())]
+		pub fn write_off_dust_debt(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let balance = Self::do_write_off_dust_debt(&borrower, asset, collateral_asset)?;
+			Self::deposit_event(Event::DustDebtWrittenOff { who: borrower, asset, balance });
+			Ok(())
+		}
+
+		/// Withdraws up to `balance` of liquidity from a lending pool. Unlike `withdraw`, the
+		/// balance is capped to the deposit of the user and the reserves of the pool instead of
+		/// failing, so that a deposit can be withdrawn in full while its interest accrues.
+		///
+		/// # Parameters
+		/// - `origin`: The signed supplier.
+		/// - `asset`: The asset of the lending pool.
+		/// - `balance`: The maximum balance to withdraw.
+		///
+		/// # Emits
+		/// - `DepositWithdrawn` with the withdrawn balance
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `AmountTooLow`: Nothing can be withdrawn.
+		/// - `AccountHasShortfall`: A borrow of the user has a shortfall.
+		#[pallet::call_index(26)]
+		#[pallet::weight(T::WeightInfo::withdraw(T::MaxMarketsPerAccount::get()))]
+		pub fn withdraw_best_effort(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let (balance, burned) =
+				Self::do_withdrawal(&who, asset, balance, Precision::BestEffort)?;
+			Self::deposit_withdrawn_event(who, asset, balance, burned);
+			Ok(Some(T::WeightInfo::withdraw(markets)).into())
+		}
+
+		/// Supplies liquidity to a lending pool on behalf of a user of another chain. Meant to be
+		/// the `Transact` of an XCM message whose reserve transferred assets were deposited
+		/// into the account derived from the location of the user, so that a supply takes a
+		/// single message. The position is credited to the derived account.
+		///
+		/// # Parameters
+		/// - `origin`: The `RemoteOrigin`, resolving to the derived account of the user.
+		/// - `asset`: The asset of the lending pool.
+		/// - `balance`: The balance to supply out of the derived account.
+		///
+		/// # Emits
+		/// - `DepositSupplied`
+		///
+		/// # Errors
+		/// - `BadOrigin`: The origin is not a remote user.
+		/// - The errors of `supply`.
+		#[pallet::call_index(27)]
+		#[pallet::weight(T::WeightInfo::supply())]
+		pub fn supply_from_remote(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = T::RemoteOrigin::ensure_origin(origin)?;
+			let minted = Self::do_supply(&who, asset, balance)?;
+			Self::deposit_supplied_event(who, asset, balance, minted);
+			Ok(())
+		}
+
+		/// Borrows from a lending pool like `borrow`, and delivers the proceeds to the beneficiary
+		/// `dest` of another chain with the `TransferToChain`, so that the borrower can deploy
+		/// the liquidity where it is needed. The borrow is reverted if the delivery fails.
+		///
+		/// # Parameters
+		/// - `origin`: The signed borrower.
+		/// - `asset`: The borrowed asset.
+		/// - `balance`: The borrowed balance, the proceeds being the balance minus the fee.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		/// - `collateral_balance`: The collateral balance to lock.
+		/// - `dest`: The beneficiary of the proceeds on another chain.
+		///
+		/// # Emits
+		/// - `DepositBorrowed`
+		/// - `BorrowSentToChain` with the proceeds
+		///
+		/// # Errors
+		/// - The errors of `borrow`.
+		/// - The errors of the `TransferToChain`.
+		#[pallet::call_index(28)]
+		#[pallet::weight(
+			T::WeightInfo::borrow(T::MaxMarketsPerAccount::get())
+				.saturating_add(T::TransferToChain::transfer_weight())
+		)]
+		pub fn borrow_to_chain(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			collateral_balance: AssetBalanceOf<T>,
+			dest: Box<T::Location>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let fee =
+				Self::do_borrow(&who, asset, balance, collateral_asset, collateral_balance, false)?;
+			let proceeds = balance.ensure_sub(fee)?;
+			T::TransferToChain::transfer_to_chain(&who, asset, proceeds, (*dest).clone())?;
+			Self::deposit_borrowed_event(who.clone(), asset, collateral_asset, balance, fee);
+			Self::deposit_event(Event::BorrowSentToChain { who, asset, dest: *dest });
+			Ok(Some(
+				T::WeightInfo::borrow(markets)
+					.saturating_add(T::TransferToChain::transfer_weight()),
+			)
+			.into())
+		}
+
+		/// Liquidates a borrow with a shortfall like `liquidate_borrow`, but the debt is bought
+		/// with the collateral on the `SwapProvider`, so that the liquidator needs no inventory of
+		/// the borrowed asset. The liquidator seizes the rest of the collateral.
+		///
+		/// # Parameters
+		/// - `origin`: The liquidator, or none for the liquidations submitted by the offchain
+		///   worker, whose collateral goes to the `TreasuryAccount`.
+		/// - `borrower`: The account whose borrow is liquidated.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `BorrowLiquidated`
+		///
+		/// # Errors
+		/// - The errors of `liquidate_borrow`.
+		/// - The errors of the `SwapProvider`, e.g. when the collateral does not buy the debt.
+		#[pallet::call_index(29)]
+		#[pallet::weight(
+			T::WeightInfo::liquidate_borrow(T::MaxMarketsPerAccount::get())
+				.saturating_add(T::SwapProvider::swap_weight())
+		)]
+		pub fn liquidate_borrow_with_swap(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let liquidator = match ensure_signed(origin.clone()) {
+				Ok(who) => who,
+				Err(_) => {
+					ensure_none(origin)?;
+					T::TreasuryAccount::get()
+				},
+			};
+			let balance =
+				Self::do_liquidate_borrow(&liquidator, &borrower, asset, collateral_asset, true)?;
+			let (exchange_rate, borrow_index) = Self::pool_indexes(asset);
+			Self::deposit_event(Event::BorrowLiquidated {
+				who: liquidator,
+				borrower,
+				asset,
+				collateral_asset,
+				balance,
+				exchange_rate,
+				borrow_index,
+			});
+			Ok(())
+		}
+
+		/// Transfers `balance` LP tokens of the lending pool of `asset` to `dest`. The rewards
+		/// of both accounts are accrued and their working balances follow the LP tokens, so that
+		/// the sender does not keep earning on the LP tokens it sent, and `dest` becomes a
+		/// supplier of the pool, swept like any other once its balance turns to dust.
+		///
+		/// The runtime filters out the transfers of the LP tokens through `pallet-assets`, which
+		/// would bypass this accounting.
+		///
+		/// # Emits
+		/// - `LPTokenTransferred`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `AmountTooLow`: The balance is zero.
+		/// - `TierNotEligible`: The tier of `dest` is not eligible to the lending pool.
+		/// - `SupplyCapExceeded`: `dest` would hold more than its share of the pool or than the
+		///   cap of its tier.
+		/// - The errors of the transfer, e.g. when the LP tokens of the sender are frozen.
+		#[pallet::call_index(30)]
+		#[pallet::weight(T::WeightInfo::transfer_lp_tokens())]
+		pub fn transfer_lp_tokens(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			dest: T::AccountId,
+			balance: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_transfer_lp_tokens(&who, &dest, asset, balance)?;
+			Self::deposit_event(Event::LPTokenTransferred { who, dest, balance });
+			Ok(())
+		}
+
+		/// Accepts the LP tokens of the lending pool of `asset` as collateral, valued at
+		/// `factor` of the collateral factor of the lending pools they are borrowed from, or
+		/// bans them as collateral with `None`, as by default. The borrows open already are
+		/// left as they are.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool of the LP tokens.
+		/// - `factor`: The share of the collateral factor, `None` to ban the LP tokens.
+		///
+		/// # Emits
+		/// - `LpCollateralFactorSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(31)]
+		#[pallet::weight(T::WeightInfo::set_lp_collateral_factor())]
+		pub fn set_lp_collateral_factor(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			factor: Option<Ratio>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			LpCollateralFactor::<T>::set(asset, factor);
+			Self::deposit_event(Event::LpCollateralFactorSet { asset, factor });
+			Ok(())
+		}
+
+		/// Borrows like `borrow`, at a stable rate fixed at origination to the variable borrow
+		/// rate of the lending pool plus its `StableRatePremium`. Borrowing more against the same
+		/// collateral averages the stable rate of the borrow with the current one.
+		///
+		/// # Emits
+		/// - `DepositBorrowed`
+		///
+		/// # Errors
+		/// - The errors of `borrow`.
+		/// - `StableBorrowNotAllowed`: The lending pool offers no stable rate.
+		/// - `BorrowRateModeMismatch`: The borrow against this collateral is at a variable rate.
+		#[pallet::call_index(32)]
+		#[pallet::weight(T::WeightInfo::borrow(T::MaxMarketsPerAccount::get()))]
+		pub fn borrow_stable(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			collateral_balance: AssetBalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let fee =
+				Self::do_borrow(&who, asset, balance, collateral_asset, collateral_balance, true)?;
+			Self::deposit_borrowed_event(who, asset, collateral_asset, balance, fee);
+			Ok(Some(T::WeightInfo::borrow(markets)).into())
+		}
+
+		/// Raises the stable rate of a borrow to the current stable rate of its lending pool,
+		/// once the utilisation of the pool reached the `StableRateRebalanceUtilisation`, so that
+		/// the borrows at a stable rate do not drain a pool whose suppliers can not withdraw.
+		/// Anyone can call it.
+		///
+		/// # Emits
+		/// - `StableRateRebalanced` with the new rate of the borrow.
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow is not at a stable rate.
+		/// - `StableRateNotRebalanceable`: The utilisation of the pool is below the
+		///   `StableRateRebalanceUtilisation`, or the rate of the borrow is not below the current
+		///   stable rate.
+		#[pallet::call_index(33)]
+		#[pallet::weight(T::WeightInfo::rebalance_stable_rate())]
+		pub fn rebalance_stable_rate(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let rate = Self::do_rebalance_stable_rate(&who, asset, collateral_asset)?;
+			Self::deposit_event(Event::StableRateRebalanced { who, asset, rate });
+			Ok(())
+		}
+
+		/// Sets the premium over the variable borrow rate at which the stable rates of the
+		/// borrows from a lending pool are fixed, or stops offering a stable rate with `None`.
+		/// The borrows at a stable rate keep their rate.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `premium`: The premium, `None` to offer no stable rate.
+		///
+		/// # Emits
+		/// - `StableRatePremiumSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(34)]
+		#[pallet::weight(T::WeightInfo::set_stable_rate_premium())]
+		pub fn set_stable_rate_premium(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			premium: Option<Ratio>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			StableRatePremium::<T>::set(asset, premium);
+			Self::deposit_event(Event::StableRatePremiumSet { asset, premium });
+			Ok(())
+		}
+
+		/// Switches a borrow of the caller between the variable and the stable rate. The interest
+		/// accrues at the old rate up to the switch, the debt then accrues at the new rate.
+		///
+		/// # Parameters
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `BorrowRateSwitched` with the old and the new rate of the borrow.
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - `StableBorrowNotAllowed`: The lending pool offers no stable rate, or its utilisation
+		///   reached the `StableRateRebalanceUtilisation`.
+		#[pallet::call_index(35)]
+		#[pallet::weight(T::WeightInfo::switch_borrow_rate_mode())]
+		pub fn switch_borrow_rate_mode(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (from, to) = Self::do_switch_borrow_rate_mode(&who, asset, collateral_asset)?;
+			Self::deposit_event(Event::BorrowRateSwitched { who, asset, from, to });
+			Ok(())
+		}
+
+		/// Borrows like `borrow_stable` for a fixed `term` in seconds, at the stable rate quoted
+		/// by the lending pool. Past its maturity, the borrow accrues at its rate plus the
+		/// `TermPenaltyRate`, and once the `TermGracePeriod` elapsed its whole debt can be
+		/// liquidated, whatever the value of its collateral. A fixed-term borrow can be repaid
+		/// at any time, but not added to.
+		///
+		/// # Parameters
+		/// - `term`: The term of the borrow in seconds.
+		/// - `max_rate`: The maximum annual rate the borrower accepts.
+		///
+		/// # Emits
+		/// - `DepositBorrowed`
+		/// - `FixedTermBorrowed` with the rate and the maturity of the borrow.
+		///
+		/// # Errors
+		/// - The errors of `borrow_stable`.
+		/// - `InvalidBorrowTerm`: The term is zero.
+		/// - `FixedTermBorrowNotExtendable`: The borrow against this collateral exists already.
+		/// - `BorrowRateTooHigh`: The quoted rate is above `max_rate`.
+		#[pallet::call_index(36)]
+		#[pallet::weight(T::WeightInfo::borrow(T::MaxMarketsPerAccount::get()))]
+		pub fn borrow_fixed_term(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			collateral_balance: AssetBalanceOf<T>,
+			term: Timestamp,
+			max_rate: Rate,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let (fee, rate, maturity) = Self::do_borrow_fixed_term(
+				&who,
+				asset,
+				balance,
+				collateral_asset,
+				collateral_balance,
+				term,
+				max_rate,
+			)?;
+			Self::deposit_borrowed_event(who.clone(), asset, collateral_asset, balance, fee);
+			Self::deposit_event(Event::FixedTermBorrowed { who, asset, rate, maturity });
+			Ok(Some(T::WeightInfo::borrow(markets)).into())
+		}
+
+		/// Bounds the borrow rate of a lending pool between a floor and a cap, whatever its
+		/// interest rate model yields, or lifts the bounds with `None`. The interest accrued so
+		/// far is accounted at the former rate.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `bounds`: The floor and the cap of the annual borrow rate, `None` to lift them.
+		///
+		/// # Emits
+		/// - `BorrowRateBoundsSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `InvalidRateBounds`: The floor is above the cap.
+		#[pallet::call_index(37)]
+		#[pallet::weight(T::WeightInfo::set_borrow_rate_bounds())]
+		pub fn set_borrow_rate_bounds(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			bounds: Option<(Rate, Rate)>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			if let Some((floor, cap)) = bounds {
+				ensure!(floor <= cap, Error::<T>::InvalidRateBounds);
+			}
+			let asset_pool = AssetPool::<T>::from(asset);
+			let mut pool = LendingPoolStorage::<T>::get(&asset_pool)
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+			pool.update_indexes()?;
+			LendingPoolStorage::<T>::insert(&asset_pool, pool);
+
+			BorrowRateBounds::<T>::set(asset, bounds);
+			Self::deposit_event(Event::BorrowRateBoundsSet { asset, bounds });
+			Ok(())
+		}
+
+		/// Caps the outflows of a lending pool, i.e. its withdrawals and borrows, over the
+		/// `OutflowWindow`, or lifts the cap with `None`. The outflows decay linearly over the
+		/// window, so that a drain of the pool is throttled rather than stopped.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `cap`: The cap on the outflows, `None` to lift it.
+		///
+		/// # Emits
+		/// - `OutflowCapSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(38)]
+		#[pallet::weight(T::WeightInfo::set_outflow_cap())]
+		pub fn set_outflow_cap(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			cap: Option<AssetBalanceOf<T>>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			OutflowCaps::<T>::set(asset, cap);
+			Self::deposit_event(Event::OutflowCapSet { asset, cap });
+			Ok(())
+		}
+
+		/// Turns the maintenance mode on or off. In maintenance mode, the `MaintenanceFilter`
+		/// of the runtime blocks all the calls of the pallet but the repayments and this call,
+		/// so that the protocol can be frozen during an incident without deactivating each
+		/// pool.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `enabled`: Whether the maintenance mode is on.
+		///
+		/// # Emits
+		/// - `MaintenanceModeSet`
+		#[pallet::call_index(39)]
+		#[pallet::weight(T::WeightInfo::set_maintenance_mode())]
+		pub fn set_maintenance_mode(origin: OriginFor<T>, enabled: bool) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			MaintenanceMode::<T>::put(enabled);
+			Self::deposit_event(Event::MaintenanceModeSet { enabled });
+			Ok(())
+		}
+
+		/// Announces a change of the risk parameters of a lending pool, i.e. its collateral
+		/// factor, its liquidation threshold or its rate model. The change is scheduled to be
+		/// executed by `execute_param_change` after the `ParamChangeDelay`, so that the
+		/// borrowers can adjust their positions before it takes effect.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `change`: The parameter and its new value.
+		///
+		/// # Emits
+		/// - `ParamChangeAnnounced`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `InvalidRiskParameters`: The change would leave the parameters inconsistent.
+		#[pallet::call_index(40)]
+		#[pallet::weight(T::WeightInfo::announce_param_change())]
+		pub fn announce_param_change(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			change: ParamChange,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			let (id, execute_at) = Self::do_announce_param_change(asset, change.clone())?;
+			Self::deposit_event(Event::ParamChangeAnnounced { id, asset, change, execute_at });
+			Ok(())
+		}
+
+		/// Executes an announced parameter change once its delay elapsed, as dispatched by the
+		/// `Scheduler`. The interest of the lending pool is accrued at its former parameters
+		/// first.
+		///
+		/// # Parameters
+		/// - `origin`: Must be root.
+		/// - `id`: The id of the parameter change.
+		///
+		/// # Emits
+		/// - `ParamChangeExecuted`
+		///
+		/// # Errors
+		/// - `ParamChangeDoesNotExist`: The change is not pending.
+		/// - `ParamChangeNotDue`: The delay of the change has not elapsed yet.
+		/// - `InvalidRiskParameters`: The change would leave the parameters inconsistent.
+		#[pallet::call_index(41)]
+		#[pallet::weight(T::WeightInfo::execute_param_change())]
+		pub fn execute_param_change(origin: OriginFor<T>, id: ParamChangeId) -> DispatchResult {
+			ensure_root(origin)?;
+			let PendingParamChange { asset, change, .. } = Self::do_execute_param_change(id)?;
+			Self::deposit_event(Event::ParamChangeExecuted { id, asset, change });
+			Ok(())
+		}
+
+		/// Cancels an announced parameter change along with its scheduled execution.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `id`: The id of the parameter change.
+		///
+		/// # Emits
+		/// - `ParamChangeCancelled`
+		///
+		/// # Errors
+		/// - `ParamChangeDoesNotExist`: The change is not pending.
+		#[pallet::call_index(42)]
+		#[pallet::weight(T::WeightInfo::cancel_param_change())]
+		pub fn cancel_param_change(origin: OriginFor<T>, id: ParamChangeId) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_cancel_param_change(id)?;
+			Self::deposit_event(Event::ParamChangeCancelled { id });
+			Ok(())
+		}
+
+		/// Restricts the supplies to and the borrows from a lending pool to the accounts of its
+		/// whitelist, e.g. for a KYC-gated market, or opens it to all accounts again. The
+		/// positions open already are left as they are, and can always be withdrawn and repaid.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `permissioned`: Whether only the whitelisted accounts can supply and borrow.
+		///
+		/// # Emits
+		/// - `PermissionedPoolSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(43)]
+		#[pallet::weight(T::WeightInfo::set_permissioned_pool())]
+		pub fn set_permissioned_pool(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			permissioned: bool,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			if permissioned {
+				PermissionedPools::<T>::insert(asset, true);
+			} else {
+				PermissionedPools::<T>::remove(asset);
+			}
+			Self::deposit_event(Event::PermissionedPoolSet { asset, permissioned });
+			Ok(())
+		}
+
+		/// Adds an account to the whitelist of a lending pool, or removes it. The whitelist only
+		/// applies while the pool is permissioned.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `who`: The account.
+		/// - `whitelisted`: Whether the account can supply to and borrow from the pool.
+		///
+		/// # Emits
+		/// - `WhitelistUpdated`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(44)]
+		#[pallet::weight(T::WeightInfo::set_whitelisted())]
+		pub fn set_whitelisted(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			who: T::AccountId,
+			whitelisted: bool,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			if whitelisted {
+				PoolWhitelist::<T>::insert(asset, &who, true);
+			} else {
+				PoolWhitelist::<T>::remove(asset, &who);
+			}
+			Self::deposit_event(Event::WhitelistUpdated { asset, who, whitelisted });
+			Ok(())
+		}
+
+		/// Settles a borrow at the oracle prices, whether it has a shortfall or not, for the
+		/// legal or emergency cases where it can not be liquidated normally, e.g. a frozen or
+		/// blacklisted account. The `TreasuryAccount` repays the whole debt and keeps the
+		/// collateral worth it, the rest of the collateral goes back to the borrower.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `borrower`: The account whose borrow is settled.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `BorrowForceLiquidated`
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - `PriceUnavailable`: The collateral can not be valued in the borrowed asset.
+		#[pallet::call_index(45)]
+		#[pallet::weight(T::WeightInfo::force_liquidate())]
+		pub fn force_liquidate(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			let (balance, seized) = Self::do_force_liquidate(&borrower, asset, collateral_asset)?;
+			Self::deposit_event(Event::BorrowForceLiquidated {
+				borrower,
+				asset,
+				collateral_asset,
+				balance,
+				seized,
+			});
+			Ok(())
+		}
+
+		/// Settles a borrow of the caller with its own collateral, e.g. when it is close to a
+		/// liquidation: the debt is bought with the collateral on the `SwapProvider` and repaid,
+		/// and the rest of the collateral is released, without any liquidation bonus.
+		///
+		/// # Parameters
+		/// - `origin`: The borrower.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `BorrowSelfLiquidated`
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - The errors of the `SwapProvider`, e.g. when the collateral does not buy the debt.
+		#[pallet::call_index(46)]
+		#[pallet::weight(
+			T::WeightInfo::liquidate_borrow(T::MaxMarketsPerAccount::get())
+				.saturating_add(T::SwapProvider::swap_weight())
+		)]
+		pub fn self_liquidate(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (balance, sold) = Self::do_self_liquidate(&who, asset, collateral_asset)?;
+			Self::deposit_event(Event::BorrowSelfLiquidated {
+				who,
+				asset,
+				collateral_asset,
+				balance,
+				sold,
+			});
+			Ok(())
+		}
+
+		/// Sweeps the tokens sent by mistake to the account of a lending pool, or to the pallet
+		/// account, to the `TreasuryAccount`. Only the balance over what the account owes is
+		/// swept: the reserves of the pool, the collaterals of its borrows and the referral
+		/// earnings in its asset are left untouched.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool, or `None` for the pallet account.
+		/// - `token`: The token to sweep.
+		/// - `witness`: An upper bound of the number of borrows and referral earnings.
+		///
+		/// # Emits
+		/// - `FundsRescued`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `BadWitness`: There are more borrows and referral earnings than `witness`.
+		/// - `NothingToRescue`: The account holds no balance of `token` over what it owes.
+		#[pallet::call_index(47)]
+		#[pallet::weight(T::WeightInfo::rescue_funds(*witness))]
+		pub fn rescue_funds(
+			origin: OriginFor<T>,
+			asset: Option<AssetIdOf<T>>,
+			token: AssetIdOf<T>,
+			witness: u32,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			let (account, balance) = Self::do_rescue_funds(asset, token, witness)?;
+			Self::deposit_event(Event::FundsRescued { account, token, balance });
+			Ok(())
+		}
+
+		/// Updates several parameters of a lending pool at once and without any delay, so that
+		/// a single referendum can retune a pool. The interest accrued so far is accounted at
+		/// the former parameters.
+		///
+		/// # Parameters
+		/// - `origin`: Must be root.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `update`: The parameters to update, those left to `None` are unchanged.
+		///
+		/// # Emits
+		/// - `PoolParamsUpdated`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `InvalidBorrowFee`: The borrow fee is 100%.
+		/// - `InvalidRiskParameters`: The collateral factor would exceed the liquidation threshold.
+		#[pallet::call_index(48)]
+		#[pallet::weight(T::WeightInfo::force_update_pool_params())]
+		pub fn force_update_pool_params(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			update: PoolParamsUpdate<AssetBalanceOf<T>>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::do_force_update_pool_params(asset, &update)?;
+			Self::deposit_event(Event::PoolParamsUpdated { asset, update });
+			Ok(())
+		}
+
+		/// Wraps a borrow of the caller, its debt and its collateral, into a position whose NFT
+		/// is minted to the caller, so that the position can be transferred or sold as a whole.
+		/// The borrow must have no shortfall. It can still be liquidated once wrapped.
+		///
+		/// # Parameters
+		/// - `origin`: The borrower.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `PositionWrapped`
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - `AccountHasShortfall`: The borrow has a shortfall.
+		/// - The errors of the `PositionNfts`, e.g. when the positions can not be wrapped.
+		#[pallet::call_index(49)]
+		#[pallet::weight(T::WeightInfo::wrap_position())]
+		pub fn wrap_position(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let id = Self::do_wrap_position(&who, asset, collateral_asset)?;
+			Self::deposit_event(Event::PositionWrapped { who, id, asset, collateral_asset });
+			Ok(())
+		}
+
+		/// Transfers a position owned by the caller. The borrow of the position must have no
+		/// shortfall, so that unhealthy positions are liquidated rather than passed on.
+		///
+		/// # Parameters
+		/// - `origin`: The owner of the NFT of the position.
+		/// - `id`: The position.
+		/// - `dest`: The new owner of the position.
+		///
+		/// # Emits
+		/// - `PositionTransferred`
+		///
+		/// # Errors
+		/// - `PositionDoesNotExist`: The position does not exist.
+		/// - `NotPositionOwner`: The caller does not own the position.
+		/// - `AccountHasShortfall`: The borrow of the position has a shortfall.
+		#[pallet::call_index(50)]
+		#[pallet::weight(T::WeightInfo::transfer_position())]
+		pub fn transfer_position(
+			origin: OriginFor<T>,
+			id: PositionId,
+			dest: T::AccountId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_transfer_position(&who, id, &dest)?;
+			Self::deposit_event(Event::PositionTransferred { id, from: who, to: dest });
+			Ok(())
+		}
+
+		/// Burns the NFT of a position owned by the caller, and moves its borrow, unless it was
+		/// repaid or liquidated meanwhile, to the caller.
+		///
+		/// # Parameters
+		/// - `origin`: The owner of the NFT of the position.
+		/// - `id`: The position.
+		///
+		/// # Emits
+		/// - `PositionUnwrapped`
+		///
+		/// # Errors
+		/// - `PositionDoesNotExist`: The position does not exist.
+		/// - `NotPositionOwner`: The caller does not own the position.
+		/// - `BorrowAlreadyExists`: The caller holds a borrow with the same assets already.
+		/// - `TooManyMarkets`: The caller holds `MaxMarketsPerAccount` borrows already.
+		#[pallet::call_index(51)]
+		#[pallet::weight(T::WeightInfo::unwrap_position())]
+		pub fn unwrap_position(origin: OriginFor<T>, id: PositionId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_unwrap_position(&who, id)?;
+			Self::deposit_event(Event::PositionUnwrapped { who, id });
+			Ok(())
+		}
+
+		/// Accepts to take over up to `allowance` of the debt of `from` in a lending pool onto a
+		/// borrow of the caller, replacing the former allowance. Zero revokes the allowance.
+		///
+		/// # Parameters
+		/// - `origin`: The account taking the debt over.
+		/// - `from`: The account whose debt is taken over.
+		/// - `asset`: The borrowed asset.
+		/// - `allowance`: The debt the caller accepts to take over.
+		///
+		/// # Emits
+		/// - `DebtApproved`
+		#[pallet::call_index(52)]
+		#[pallet::weight(T::WeightInfo::approve_debt())]
+		pub fn approve_debt(
+			origin: OriginFor<T>,
+			from: T::AccountId,
+			asset: AssetIdOf<T>,
+			allowance: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			if allowance.is_zero() {
+				DebtAllowances::<T>::remove((&who, &from, asset));
+			} else {
+				DebtAllowances::<T>::insert((&who, &from, asset), allowance);
+			}
+			Self::deposit_event(Event::DebtApproved { who, from, asset, allowance });
+			Ok(())
+		}
+
+		/// Moves debt from a borrow of the caller onto a borrow of `to` in the same asset,
+		/// within the allowance `to` approved, so that positions can be restructured or traded
+		/// without repaying and borrowing again. The collaterals stay where they are, but the
+		/// collateral of a borrow left without debt is released. Both borrows must be at the
+		/// variable rate, and both accounts must be left without a shortfall.
+		///
+		/// # Parameters
+		/// - `origin`: The borrower moving its debt.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow of the caller.
+		/// - `balance`: The debt to move, capped to the debt of the borrow.
+		/// - `to`: The account taking the debt over.
+		/// - `to_collateral_asset`: The collateral asset of the borrow of `to`.
+		///
+		/// # Emits
+		/// - `DebtTransferred`
+		///
+		/// # Errors
+		/// - `LoanDoesNotExists`: One of the borrows does not exist.
+		/// - `DebtNotApproved`: `to` did not approve to take over as much debt.
+		/// - `BorrowRateModeMismatch`: One of the borrows is at a stable rate.
+		/// - `BorrowTooSmall`: The debt left would be below the minimum borrow.
+		/// - `AccountHasShortfall`: One of the accounts would have a shortfall.
+		#[pallet::call_index(53)]
+		#[pallet::weight(T::WeightInfo::transfer_debt(T::MaxMarketsPerAccount::get()))]
+		pub fn transfer_debt(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			to: T::AccountId,
+			to_collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let balance = Self::do_transfer_debt(
+				&who,
+				asset,
+				collateral_asset,
+				balance,
+				&to,
+				to_collateral_asset,
+			)?;
+			Self::deposit_event(Event::DebtTransferred {
+				from: who,
+				to,
+				asset,
+				collateral_asset,
+				to_collateral_asset,
+				balance,
+			});
+			Ok(())
+		}
+
+		/// Accepts the LP tokens of a liquidity pool of the DEX as collateral, valued at their
+		/// share of the fair value of the pool at the oracle prices of its assets rather than at
+		/// its reserves, or bans them with `enabled` false. A lending pool of the LP tokens must
+		/// be created for them to be supplied.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `lp_token`: The LP token of the liquidity pool.
+		/// - `enabled`: Whether the LP tokens are accepted as collateral.
+		///
+		/// # Emits
+		/// - `DexLpCollateralSet`
+		///
+		/// # Errors
+		/// - `NotDexLpToken`: The asset is not an LP token of the DEX.
+		#[pallet::call_index(69)]
+		#[pallet::weight(T::WeightInfo::set_dex_lp_collateral())]
+		pub fn set_dex_lp_collateral(
+			origin: OriginFor<T>,
+			lp_token: AssetIdOf<T>,
+			enabled: bool,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			let assets = Self::do_set_dex_lp_collateral(lp_token, enabled)?;
+			Self::deposit_event(Event::DexLpCollateralSet { lp_token, assets });
+			Ok(())
+		}
+
+		/// Registers the caller as a keeper, bonding the `KeeperBond`. The liquidations detected
+		/// by the offchain worker are assigned to the keepers in turn, each one the only
+		/// liquidator of its assignments for the `KeeperExclusivity`. A keeper missing
+		/// `MaxKeeperMisses` liquidations in a row loses its bond to the `TreasuryAccount`.
+		///
+		/// # Parameters
+		/// - `origin`: The keeper.
+		///
+		/// # Emits
+		/// - `KeeperRegistered`
+		///
+		/// # Errors
+		/// - `KeeperAlreadyRegistered`: The caller is a keeper already.
+		#[pallet::call_index(70)]
+		#[pallet::weight(T::WeightInfo::register_keeper())]
+		pub fn register_keeper(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let bond = Self::do_register_keeper(&who)?;
+			Self::deposit_event(Event::KeeperRegistered { who, bond });
+			Ok(())
+		}
+
+		/// Deregisters the caller as a keeper and returns its bond, once the liquidations
+		/// assigned to it are settled.
+		///
+		/// # Parameters
+		/// - `origin`: The keeper.
+		///
+		/// # Emits
+		/// - `KeeperDeregistered`
+		///
+		/// # Errors
+		/// - `NotKeeper`: The caller is not a keeper.
+		/// - `KeeperBusy`: Liquidations assigned to the caller may still be settled.
+		#[pallet::call_index(71)]
+		#[pallet::weight(T::WeightInfo::deregister_keeper())]
+		pub fn deregister_keeper(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let bond = Self::do_deregister_keeper(&who)?;
+			Self::deposit_event(Event::KeeperDeregistered { who, bond });
+			Ok(())
+		}
+
+		/// Assigns the liquidation of a borrow with a shortfall to the next keeper in turn.
+		/// Submitted by the offchain worker when keepers are registered, which then submits the
+		/// liquidation itself only once the exclusivity of the keeper is over.
+		///
+		/// # Parameters
+		/// - `origin`: Must be none.
+		/// - `borrower`: The account whose borrow is to be liquidated.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		///
+		/// # Emits
+		/// - `LiquidationAssigned`
+		///
+		/// # Errors
+		/// - `BorrowNotLiquidatable`: The borrow has no shortfall.
+		/// - `LiquidationAlreadyAssigned`: The liquidation is assigned already.
+		/// - `NoKeepers`: No keeper is registered.
+		/// - `StallGracePeriod`: The chain stalled recently.
+		#[pallet::call_index(72)]
+		#[pallet::weight(T::WeightInfo::assign_liquidation())]
+		pub fn assign_liquidation(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let (keeper, until) = Self::do_assign_liquidation(&borrower, asset, collateral_asset)?;
+			Self::deposit_event(Event::LiquidationAssigned {
+				borrower,
+				asset,
+				collateral_asset,
+				keeper,
+				until,
+			});
+			Ok(())
+		}
+
+		/// Issues the margin call of a borrow with a shortfall, which can only be liquidated by
+		/// a third party once the `MarginCallGracePeriod` is over, unless its shortfall exceeds
+		/// the `MarginCallSeverity` of its debt. Submitted by the offchain worker when a borrow
+		/// first crosses the liquidation threshold, or by anyone.
+		///
+		/// # Parameters
+		/// - `origin`: Any signed account, or none.
+		/// - `borrower`: The account whose borrow has a shortfall.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
 		///
-		/// * `origin` - The origin caller of this function. This should be signed by the user
-		/// that creates the lending pool and add some liquidity.
-		/// * `asset` - The identifier for the type of asset that the user wants to provide.
+		/// # Emits
+		/// - `MarginCall`
 		///
 		/// # Errors
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		/// - `BorrowNotLiquidatable`: The borrow has no shortfall.
+		/// - `MarginCallAlreadyIssued`: The margin call of the borrow is issued already.
+		#[pallet::call_index(73)]
+		#[pallet::weight(T::WeightInfo::margin_call())]
+		pub fn margin_call(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> DispatchResult {
+			if ensure_signed(origin.clone()).is_err() {
+				ensure_none(origin)?;
+			}
+			let (shortfall, until) = Self::do_margin_call(&borrower, asset, collateral_asset)?;
+			Self::deposit_event(Event::MarginCall {
+				borrower,
+				asset,
+				collateral_asset,
+				shortfall,
+				until,
+			});
+			Ok(())
+		}
+
+		/// Adds collateral to a borrow, e.g. to answer its margin call, which is cleared once
+		/// the borrow is healthy.
 		///
-		/// This function will return an error in the following scenarios:
+		/// # Parameters
+		/// - `origin`: The borrower.
+		/// - `asset`: The borrowed asset.
+		/// - `collateral_asset`: The collateral asset of the borrow.
+		/// - `balance`: The collateral to add.
 		///
-		/// * If the origin is not signed (i.e., the function was not called by a user).
-		/// * If the provided assets do not exist.
-		/// * If the pool does not exist.
-		/// * If the pool is already deactivated.
-		/// * If the pool is empty.
+		/// # Emits
+		/// - `CollateralAdded`
 		///
-		/// # Events
+		/// # Errors
+		/// - `AmountTooLow`: The balance is zero.
+		/// - `LoanDoesNotExists`: The borrow does not exist.
+		#[pallet::call_index(74)]
+		#[pallet::weight(T::WeightInfo::add_collateral())]
+		pub fn add_collateral(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_add_collateral(&who, asset, collateral_asset, balance)?;
+			Self::deposit_event(Event::CollateralAdded { who, asset, collateral_asset, balance });
+			Ok(())
+		}
+
+		/// Pauses the borrows from and the withdrawals of the lending pools of two assets when
+		/// the price of the one in the other deviates from its `PriceReference` by more than the
+		/// `MaxPriceDeviation`. Submitted by the offchain worker, the deviation is checked again
+		/// on-chain. The pools stay paused until the `ManagerOrigin` resumes them.
 		///
-		/// If the function succeeds, it triggers an event:
+		/// # Parameters
+		/// - `origin`: Must be none.
+		/// - `asset`: The priced asset.
+		/// - `quote`: The asset the price is quoted in.
 		///
-		/// * `LendingPoolDeactivated(asset_a)` if the lending pool was deactivated.
-		#[pallet::call_index(7)]
-		#[pallet::weight(Weight::default())]
-		pub fn deactivate_lending_pool(
+		/// # Emits
+		/// - `PriceDeviationReported`
+		/// - `PoolPauseSet` for each pool paused
+		///
+		/// # Errors
+		/// - `PriceWithinDeviation`: The price does not deviate from its reference.
+		/// - `PoolPaused`: The pools of both assets are paused already, or do not exist.
+		#[pallet::call_index(75)]
+		#[pallet::weight(T::WeightInfo::report_price_deviation())]
+		pub fn report_price_deviation(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			quote: AssetIdOf<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let (price, reference, pools) = Self::do_report_price_deviation(asset, quote)?;
+			Self::deposit_event(Event::PriceDeviationReported { asset, quote, price, reference });
+			for asset in pools {
+				Self::deposit_event(Event::PoolPauseSet { asset, paused: true });
+			}
+			Ok(())
+		}
+
+		/// Pauses or resumes the borrows from and the withdrawals of a lending pool.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `paused`: Whether the pool is paused.
+		///
+		/// # Emits
+		/// - `PoolPauseSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(76)]
+		#[pallet::weight(T::WeightInfo::set_pool_paused())]
+		pub fn set_pool_paused(
 			origin: OriginFor<T>,
 			asset: AssetIdOf<T>,
+			paused: bool,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			PausedPools::<T>::set(asset, paused);
+			Self::deposit_event(Event::PoolPauseSet { asset, paused });
+			Ok(())
+		}
+
+		/// Approves `operator` to dispatch the calls of the caller within `permissions`, e.g. a
+		/// bot repaying its borrows or a vault pallet managing its collateral, replacing the
+		/// former permissions. `None` revokes the operator.
+		///
+		/// # Parameters
+		/// - `origin`: The account approving the operator.
+		/// - `operator`: The account dispatching the calls through `operate`.
+		/// - `permissions`: The scope of the calls allowed, `None` to revoke the operator.
+		///
+		/// # Emits
+		/// - `OperatorApproved`
+		#[pallet::call_index(77)]
+		#[pallet::weight(T::WeightInfo::approve_operator())]
+		pub fn approve_operator(
+			origin: OriginFor<T>,
+			operator: T::AccountId,
+			permissions: Option<OperatorPermissions>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			Self::deposit_event(Event::LendingPoolDeactivated { who, asset });
+			Operators::<T>::set(&who, &operator, permissions);
+			Self::deposit_event(Event::OperatorApproved { who, operator, permissions });
 			Ok(())
 		}
 
-		#[pallet::call_index(8)]
-		#[pallet::weight(Weight::default())]
-		pub fn update_pool_rate_model(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+		/// Dispatches a call of this pallet as `who`, which approved the caller as an operator
+		/// with the permissions to. The call is checked as if `who` dispatched it.
+		///
+		/// # Parameters
+		/// - `origin`: The operator.
+		/// - `who`: The account the call is dispatched as.
+		/// - `call`: The call.
+		///
+		/// # Emits
+		/// - `OperatorCalled`, and the events of the call
+		///
+		/// # Errors
+		/// - `OperatorNotApproved`: `who` did not approve the caller as an operator.
+		/// - `OperatorNotPermitted`: The permissions of the caller do not allow the call.
+		/// - `CallFiltered`: The call is blocked by the maintenance mode.
+		/// - The errors of the call.
+		#[pallet::call_index(78)]
+		#[pallet::weight({
+			let info = call.get_dispatch_info();
+			(T::WeightInfo::operate().saturating_add(info.weight), info.class)
+		})]
+		pub fn operate(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			call: Box<Call<T>>,
+		) -> DispatchResultWithPostInfo {
+			let operator = ensure_signed(origin)?;
+			let weight = Self::do_operate(&operator, &who, *call)?;
+			Self::deposit_event(Event::OperatorCalled { who, operator });
+			Ok(Some(weight).into())
+		}
+
+		/// Withdraws the entire deposit of the caller from a lending pool, redeeming all its LP
+		/// tokens whatever the exchange rate at the inclusion of the transaction.
+		///
+		/// # Parameters
+		/// - `origin`: The signed supplier.
+		/// - `asset`: The asset of the lending pool.
+		///
+		/// # Emits
+		/// - `DepositWithdrawn` with the withdrawn balance
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `AmountTooLow`: The caller has no deposit in the pool.
+		/// - `InsufficientLiquidity`: The reserves of the pool do not cover the deposit.
+		/// - `AccountHasShortfall`: A borrow of the user has a shortfall.
+		#[pallet::call_index(79)]
+		#[pallet::weight(T::WeightInfo::withdraw(T::MaxMarketsPerAccount::get()))]
+		pub fn withdraw_all(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			Self::deposit_event(Event::LendingPoolRateModelUpdated { who, asset });
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let (balance, burned) = Self::do_withdraw_shares(&who, asset, None)?;
+			Self::deposit_withdrawn_event(who, asset, balance, burned);
+			Ok(Some(T::WeightInfo::withdraw(markets)).into())
+		}
+
+		/// Withdraws liquidity from a lending pool by the LP tokens to redeem rather than by the
+		/// balance to withdraw, so that the exchange rate moving between the signing and the
+		/// inclusion of the transaction leaves no dust of LP tokens behind.
+		///
+		/// # Parameters
+		/// - `origin`: The signed supplier.
+		/// - `asset`: The asset of the lending pool.
+		/// - `shares`: The LP tokens to redeem.
+		///
+		/// # Emits
+		/// - `DepositWithdrawn` with the withdrawn balance
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `NotEnoughElegibleLiquidityToWithdraw`: The caller can not redeem as many LP tokens.
+		/// - `AmountTooLow`: The LP tokens redeem nothing.
+		/// - `InsufficientLiquidity`: The reserves of the pool do not cover the withdrawal.
+		/// - `AccountHasShortfall`: A borrow of the user has a shortfall.
+		#[pallet::call_index(80)]
+		#[pallet::weight(T::WeightInfo::withdraw(T::MaxMarketsPerAccount::get()))]
+		pub fn withdraw_shares(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			shares: AssetBalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let markets = Self::ensure_no_shortfall(&who)?;
+			let (balance, burned) = Self::do_withdraw_shares(&who, asset, Some(shares))?;
+			Self::deposit_withdrawn_event(who, asset, balance, burned);
+			Ok(Some(T::WeightInfo::withdraw(markets)).into())
+		}
+
+		/// Sets the loyalty tiers of a lending pool: the suppliers supplying to it continuously
+		/// for the duration of a tier at least earn its bonus on the rewards of the pool. The
+		/// working balances of the suppliers reaching a tier are refreshed on their next
+		/// accrual, e.g. by `update_reward_boost`.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `tiers`: The tiers, by strictly increasing duration and bonus. None turn the bonus
+		///   off.
+		///
+		/// # Emits
+		/// - `LoyaltyTiersSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `InvalidLoyaltyTiers`: The tiers are not increasing or a bonus exceeds the
+		///   `MaxLoyaltyBonus`.
+		#[pallet::call_index(81)]
+		#[pallet::weight(T::WeightInfo::set_loyalty_tiers())]
+		pub fn set_loyalty_tiers(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			tiers: BoundedVec<LoyaltyTier, T::MaxLoyaltyTiers>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_set_loyalty_tiers(asset, tiers.clone())?;
+			Self::deposit_event(Event::LoyaltyTiersSet { asset, tiers });
 			Ok(())
 		}
 
-		#[pallet::call_index(9)]
-		#[pallet::weight(Weight::default())]
-		pub fn update_pool_kink(origin: OriginFor<T>, asset: AssetIdOf<T>) -> DispatchResult {
+		/// Repays several borrows of the caller in a single call, e.g. to settle all its debts.
+		/// Each repayment goes as with `repay`, and the call fails as a whole if any of them
+		/// fails.
+		///
+		/// # Parameters
+		/// - `origin`: The borrower.
+		/// - `repayments`: The borrowed asset, the collateral asset and the balance to repay of
+		///   each borrow, the balances exceeding the debt repaying it in full.
+		///
+		/// # Emits
+		/// - `BorrowsRepaid`
+		///
+		/// # Errors
+		/// - `DuplicateRepayment`: A borrow is repaid more than once.
+		/// - The errors of `repay`.
+		#[pallet::call_index(82)]
+		#[pallet::weight(T::WeightInfo::repay_many(repayments.len() as u32))]
+		pub fn repay_many(
+			origin: OriginFor<T>,
+			repayments: BoundedVec<
+				(AssetIdOf<T>, AssetIdOf<T>, BalanceOf<T>),
+				T::MaxMarketsPerAccount,
+			>,
+		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			Self::deposit_event(Event::LendingPoolKinkUpdated { who, asset });
+			Self::do_repay_many(&who, &repayments)?;
+			Self::deposit_event(Event::BorrowsRepaid { who, repayments });
 			Ok(())
 		}
 
-		/// Sets the price of one asset in terms of another asset.
+		/// Replaces the lending pool of an asset with a new pool under another LP token, e.g. to
+		/// list the asset again, taking the parameters of the former pool updated by `update`.
+		/// The former pool is retired until its positions are migrated with
+		/// `migrate_positions`: its borrows can still be repaid and liquidated meanwhile.
 		///
-		/// The `set_asset_price` extrinsic allows a user to specify the relative price of one asset
-		/// (`asset_1`) in terms of another asset (`asset_2`).
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `id`: The LP token of the new pool, which must not exist.
+		/// - `update`: The parameters of the new pool differing from the former one.
+		/// - `witness`: The number of entries read: all the position NFTs, borrows, supply
+		///   positions and referral earnings of the protocol.
+		///
+		/// # Emits
+		/// - `LendingPoolReplaced`
+		/// - `PoolParamsUpdated`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `IdAlreadyExists`: The LP token `id` already exists.
+		/// - `PoolNotReplaceable`: The pool has stable-rate, fixed-term or wrapped borrows, or its
+		///   LP tokens are used as collateral or beyond the lending pools.
+		/// - `InvalidBorrowFee`, `InvalidRiskParameters`: The updated parameters are inconsistent.
+		/// - `BadWitness`: There are more entries to read than `witness`.
+		///
+		/// # Weight
+		/// Linear in `witness`.
+		#[pallet::call_index(83)]
+		#[pallet::weight(T::WeightInfo::replace_lending_pool(*witness))]
+		pub fn replace_lending_pool(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			id: AssetIdOf<T>,
+			update: PoolParamsUpdate<AssetBalanceOf<T>>,
+			witness: u32,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			let old_pool = Self::reserve_pools(AssetPool::<T>::from(asset))
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?
+				.id;
+			let (borrows, _) = Self::do_replace_pool(asset, id, &update, witness)?;
+			Self::deposit_event(Event::LendingPoolReplaced {
+				asset,
+				old_pool,
+				new_pool: id,
+				borrows,
+			});
+			Self::deposit_event(Event::PoolParamsUpdated { asset, update });
+			Ok(())
+		}
+
+		/// Migrates the positions of accounts in a retired lending pool to the pool replacing
+		/// it, without repaying: the LP tokens of each account are exchanged for LP tokens of
+		/// the new pool worth as much, and its borrows carried over with their debts and
+		/// collaterals. Anyone may migrate the positions of any account once the pool is
+		/// replaced, the call failing as a whole if any of them fails.
 		///
 		/// # Parameters
-		/// - `origin`: The transaction origin. This must be a signed extrinsic.
-		/// - `asset_1`: The identifier for the first asset. This is the asset whose price is being
-		///   set.
-		/// - `asset_2`: The identifier for the second asset. This is the asset relative to which
-		///   the price is measured.
-		/// - `price`: The price of `asset_1` in terms of `asset_2`. This must be a non-zero value
-		///   to avoid errors.
+		/// - `origin`: Any signed account.
+		/// - `old_pool`: The LP token of the retired pool.
+		/// - `new_pool`: The LP token of the pool replacing it.
+		/// - `accounts`: The accounts whose positions are migrated, up to
+		///   `MAX_POSITION_MIGRATIONS`.
 		///
 		/// # Emits
-		/// - `AssetPriceAdded`: This event is emitted after the price is successfully set. It
-		///   contains the asset identifiers and the new price.
+		/// - `PositionMigrated` for each account
 		///
 		/// # Errors
-		/// - `InvalidAssetPrice`: This error is thrown if the `price` parameter is zero.
+		/// - `PoolNotRetired`: `old_pool` is not a retired pool.
+		/// - `NotReplacementPool`: `new_pool` is not the lending pool of the asset.
+		/// - `NothingToMigrate`: An account has no position in the retired pool.
+		/// - `InsufficientLiquidity`: The pool owing the difference between the supply and the
+		///   debt migrated does not hold it.
+		/// - `AccountHasShortfall`: A borrow carried over has a shortfall in the new pool.
 		///
-		/// # Note this should be moved to a new pallet `prices`
-		#[pallet::call_index(10)]
-		#[pallet::weight(Weight::default())]
-		pub fn set_asset_price(
+		/// # Weight
+		/// Linear in the number of accounts, each with up to `MaxMarketsPerAccount` borrows.
+		#[pallet::call_index(84)]
+		#[pallet::weight(T::WeightInfo::migrate_positions(accounts.len() as u32))]
+		pub fn migrate_positions(
 			origin: OriginFor<T>,
-			asset_1: AssetIdOf<T>,
-			asset_2: AssetIdOf<T>,
-			price: FixedU128,
+			old_pool: AssetIdOf<T>,
+			new_pool: AssetIdOf<T>,
+			accounts: BoundedVec<T::AccountId, ConstU32<MAX_POSITION_MIGRATIONS>>,
 		) -> DispatchResult {
-			let _who = ensure_signed(origin)?;
-			// price should not be zero
-			ensure!(price > FixedU128::zero(), Error::<T>::InvalidAssetPrice);
+			ensure_signed(origin)?;
+			for who in accounts {
+				let (supply, debt) = Self::do_migrate_position(&who, old_pool, new_pool)?;
+				Self::deposit_event(Event::PositionMigrated {
+					who,
+					old_pool,
+					new_pool,
+					supply,
+					debt,
+				});
+			}
+			Ok(())
+		}
 
-			AssetPrices::<T>::set((asset_1, asset_2), Some(price));
+		/// Remaps the lending pool of an asset to another asset id, e.g. when a bridged asset is
+		/// re-registered or redenominated, converting the balances of the pool and of all its
+		/// positions at a fixed ratio. The pool keeps its LP tokens and its parameters.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `new_asset`: The asset the lending pool is remapped to, without a pool of its own.
+		/// - `ratio`: The balance of `new_asset` a unit of `asset` converts into.
+		/// - `witness`: The number of entries read: all the borrows, markets of the accounts,
+		///   position NFTs, debt allowances, supply positions, referral earnings and prices of the
+		///   protocol, and the whitelist of the pool.
+		///
+		/// # Emits
+		/// - `PoolRedenominated`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		/// - `LendingPoolAlreadyExists`: `new_asset` already has a lending pool.
+		/// - `InvalidRedenomination`: The ratio is zero, `new_asset` does not exist or is the
+		///   native asset, or `asset` is used beyond its lending pool.
+		/// - `BadWitness`: There are more entries to read than `witness`.
+		///
+		/// # Weight
+		/// Linear in `witness`.
+		#[pallet::call_index(85)]
+		#[pallet::weight(T::WeightInfo::redenominate_pool(*witness))]
+		pub fn redenominate_pool(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			new_asset: AssetIdOf<T>,
+			ratio: Rate,
+			witness: u32,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			let (positions, _) = Self::do_redenominate_pool(asset, new_asset, ratio, witness)?;
+			Self::deposit_event(Event::PoolRedenominated { asset, new_asset, ratio, positions });
+			Ok(())
+		}
 
-			// Emit an event.
-			Self::deposit_event(Event::AssetPriceAdded { asset_1, asset_2, price });
+		/// Caps the share of a lending pool a single account may hold, so that no supplier
+		/// concentrates the liquidity of the pool, or lifts the cap with `None`. The cap is
+		/// enforced on supply and on the receipt of LP tokens: the accounts already above it keep
+		/// their LP tokens but can not supply or receive more.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `cap`: The share of the supplied balance of the pool an account may hold, `None` to
+		///   lift it.
+		///
+		/// # Emits
+		/// - `AccountSupplyCapSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(86)]
+		#[pallet::weight(T::WeightInfo::set_account_supply_cap())]
+		pub fn set_account_supply_cap(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			cap: Option<Permill>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			AccountSupplyCaps::<T>::set(asset, cap);
+			Self::deposit_event(Event::AccountSupplyCapSet { asset, cap });
+			Ok(())
+		}
+
+		/// Assigns a tier to an account, e.g. an institution vetted offchain, or leaves it to
+		/// the `AccountTierProvider` with `None`. The accounts without a tier are `Retail`.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `who`: The account.
+		/// - `tier`: The tier of the account, `None` to remove it.
+		///
+		/// # Emits
+		/// - `AccountTierSet`
+		#[pallet::call_index(87)]
+		#[pallet::weight(T::WeightInfo::set_account_tier())]
+		pub fn set_account_tier(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			tier: Option<AccountTier>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			AccountTiers::<T>::set(&who, tier);
+			Self::deposit_event(Event::AccountTierSet { who, tier });
+			Ok(())
+		}
 
+		/// Opens a lending pool to the accounts of a tier, capping the balance each of them may
+		/// supply, or closes it to them with `None`. Once a pool has terms for a tier, only the
+		/// tiers it has terms for may supply to it or receive its LP tokens. The accounts already
+		/// supplying keep their LP tokens.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `tier`: The tier of the accounts.
+		/// - `terms`: The terms of the pool for the tier, `None` to close it to the tier.
+		///
+		/// # Emits
+		/// - `TierTermsSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(88)]
+		#[pallet::weight(T::WeightInfo::set_tier_terms())]
+		pub fn set_tier_terms(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			tier: AccountTier,
+			terms: Option<TierTerms<AssetBalanceOf<T>>>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_set_tier_terms(asset, tier, terms)?;
+			Self::deposit_event(Event::TierTermsSet { asset, tier, terms });
+			Ok(())
+		}
+
+		/// Overrides a risk parameter of a lending pool at once, e.g. on a governance track for
+		/// the risk parameters, or restores the one stored in the pool with `None`. The pool
+		/// itself is left as it is.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The underlying asset of the lending pool.
+		/// - `parameter`: The risk parameter overridden.
+		/// - `value`: The value in effect, `None` to remove the override.
+		///
+		/// # Emits
+		/// - `RiskParameterSet`
+		///
+		/// # Errors
+		/// - `LendingPoolDoesNotExist`: The lending pool does not exist.
+		#[pallet::call_index(89)]
+		#[pallet::weight(T::WeightInfo::set_risk_parameter())]
+		pub fn set_risk_parameter(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			parameter: RiskParameter,
+			value: Option<Ratio>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+				Error::<T>::LendingPoolDoesNotExist
+			);
+			RiskParameterOverrides::<T>::set(asset, parameter, value);
+			Self::deposit_event(Event::RiskParameterSet { asset, parameter, value });
 			Ok(())
 		}
 	}
 
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			match call {
+				Call::liquidate_borrow { borrower, asset, collateral_asset } |
+				Call::liquidate_borrow_with_swap { borrower, asset, collateral_asset } =>
+					Self::validate_liquidation(borrower, *asset, *collateral_asset),
+				Call::assign_liquidation { borrower, asset, collateral_asset } =>
+					Self::validate_liquidation_assignment(borrower, *asset, *collateral_asset),
+				Call::margin_call { borrower, asset, collateral_asset } =>
+					Self::validate_margin_call(borrower, *asset, *collateral_asset),
+				Call::report_price_deviation { asset, quote } =>
+					Self::validate_price_deviation(*asset, *quote),
+				Call::accrue_interest { asset } => Self::validate_accrual(*asset),
+				_ => InvalidTransaction::Call.into(),
+			}
+		}
+	}
+
 	// the main logic of the pallet
 	impl<T: Config> Pallet<T> {
 		// This method creates a NEW lending pool and mints LP tokens back to the user.
@@ -1094,12 +4841,14 @@ pub mod pallet {
 		// The pool must not exist and the user must have enough liquidity to supply.
 		pub fn do_create_lending_pool(
 			who: &T::AccountId,
-			id: LendingPoolId,
+			id: AssetIdOf<T>,
 			asset: AssetIdOf<T>,
 			balance: BalanceOf<T>,
-		) -> DispatchResult {
+		) -> Result<AssetBalanceOf<T>, DispatchError> {
 			// First, let's check the balance amount is valid
-			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::InvalidLiquiditySupply);
+			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+			ensure!(balance >= T::MinPoolLiquidity::get(), Error::<T>::InitialLiquidityTooLow);
+			T::ComplianceCheck::check_supply(who, asset, balance)?;
 
 			// Second, let's check the if user has enough liquidity
 			let user_balance = T::Fungibles::balance(asset.clone(), who);
@@ -1111,6 +4860,10 @@ pub mod pallet {
 				!LendingPoolStorage::<T>::contains_key(&asset_pool),
 				Error::<T>::LendingPoolAlreadyExists
 			);
+			ensure!(
+				LendingPoolStorage::<T>::count() < T::MaxPools::get(),
+				Error::<T>::TooManyPools
+			);
 
 			// make sure id does not exist already
 			ensure!(!T::Fungibles::asset_exists(id.clone()), Error::<T>::IdAlreadyExists);
@@ -1119,30 +4872,48 @@ pub mod pallet {
 			let asset_pool = AssetPool::from(asset);
 			let lending_pool = LendingPool::<T>::from(id, asset, balance)?;
 
+			Self::update_pool_totals(&lending_pool);
 			LendingPoolStorage::<T>::insert(asset_pool, &lending_pool);
 
-			// let's transfers the tokens (asset) from the users account into pallet account
+			// let's transfers the tokens (asset) from the users account into the pool account
+			let pool_account = Self::pool_account_id(id);
+			Self::provide_for(&pool_account);
 			T::Fungibles::transfer(
 				asset.clone(),
 				who,
-				&Self::account_id(),
+				&pool_account,
 				balance,
 				Preservation::Expendable,
 			)?;
 
 			// create liquidity token
 			T::Fungibles::create(id.clone(), Self::account_id(), true, One::one())?;
-
-			let scaled_minted_tokens = lending_pool.scaled_supply_balance(balance)?;
+			LpTokenPools::<T>::insert(id, asset);
+
+			// the dead shares are locked forever, the user gets the rest of the lp tokens
+			let dead_shares = T::DeadShares::get();
+			let scaled_minted_tokens = lending_pool
+				.scaled_supply_balance(balance, Rounding::Down)?
+				.checked_sub(&dead_shares)
+				.ok_or(Error::<T>::InitialLiquidityTooLow)?;
+			if !dead_shares.is_zero() {
+				let dead = Self::dead_shares_account_id();
+				Self::accrue_rewards(&dead, asset)?;
+				Self::update_and_mint(&dead, asset, id, dead_shares, lending_pool.supply_index)?;
+				Self::accrue_rewards(&dead, asset)?;
+			}
 			// mints the lp tokens into the users account
+			Self::accrue_rewards(who, asset)?;
 			Self::update_and_mint(who, asset, id, scaled_minted_tokens, lending_pool.supply_index)?;
+			Self::accrue_rewards(who, asset)?;
 
 			Self::deposit_event(Event::LPTokenMinted {
 				who: who.clone(),
 				asset: id,
 				balance: scaled_minted_tokens,
 			});
-			Ok(())
+			T::LendingHooks::on_supply(who, asset, balance);
+			Ok(scaled_minted_tokens)
 		}
 
 		// This method activates an existing lending pool that is not empty.
@@ -1151,30 +4922,30 @@ pub mod pallet {
 		pub fn do_activate_lending_pool(asset: AssetIdOf<T>) -> DispatchResult {
 			// let's check if our pool does exist before activating it
 			let asset_pool = AssetPool::<T>::from(asset);
-			ensure!(
-				LendingPoolStorage::<T>::contains_key(&asset_pool),
-				Error::<T>::LendingPoolDoesNotExist
-			);
+			let mut pool = LendingPoolStorage::<T>::get(&asset_pool)
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
 
 			// let's check if our pool is actually already active and balance > 0
-			let pool = LendingPoolStorage::<T>::get(asset_pool.clone());
 			ensure!(pool.is_active() == false, Error::<T>::LendingPoolAlreadyActivated);
 			ensure!(!pool.is_empty(), Error::<T>::LendingPoolIsEmpty);
 
 			// ok now we can activate it
-			LendingPoolStorage::<T>::mutate(asset_pool, |v| v.activated = true);
+			pool.activated = true;
+			LendingPoolStorage::<T>::insert(asset_pool, pool);
 			Ok(())
 		}
 
 		// This method supplies liquidity to a lending pool and mints LP tokens back to the user.
 		// The pool must be active and the user must have enough liquidity to supply.
+		// Returns the LP tokens minted.
 		pub fn do_supply(
 			who: &T::AccountId,
 			asset: AssetIdOf<T>,
 			balance: BalanceOf<T>,
-		) -> DispatchResult {
+		) -> Result<AssetBalanceOf<T>, DispatchError> {
 			// First, let's check the balance amount to supply is valid
-			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::InvalidLiquiditySupply);
+			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+			T::ComplianceCheck::check_supply(who, asset, balance)?;
 
 			// Second, let's check the if user has enough liquidity tp supply
 			let user_balance = T::Fungibles::balance(asset.clone(), who);
@@ -1182,142 +4953,322 @@ pub mod pallet {
 
 			// let's check if our pool does exist
 			let asset_pool = AssetPool::<T>::from(asset);
-			ensure!(
-				LendingPoolStorage::<T>::contains_key(&asset_pool),
-				Error::<T>::LendingPoolDoesNotExist
-			);
-
-			let mut pool = LendingPoolStorage::<T>::get(&asset_pool);
+			let mut pool = LendingPoolStorage::<T>::get(&asset_pool)
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+			let pool_account = Self::pool_account_id(pool.id);
 
 			// let's ensure that the lending pool is active
 			ensure!(pool.is_active() == true, Error::<T>::LendingPoolNotActive);
+			Self::ensure_whitelisted(who, asset)?;
 
 			// Update pool supply index
 			pool.update_indexes()?;
-			pool.reserve_balance =
-				pool.reserve_balance.checked_add(&balance).ok_or(Error::<T>::OverflowError)?;
+			pool.reserve_balance = pool.reserve_balance.ensure_add(balance)?;
+
+			// the LP tokens are priced with the supply index alone, which only accrued interest
+			// moves: donations to the pallet account can not inflate it, and no supply may be
+			// rounded away into the reserves
+			let scaled_minted_tokens = pool.scaled_supply_balance(balance, Rounding::Down)?;
+			ensure!(!scaled_minted_tokens.is_zero(), Error::<T>::SupplyTooSmall);
 
 			// let's transfers the tokens (asset) from the users account into pallet account
 			T::Fungibles::transfer(
 				asset.clone(),
 				who,
-				&Self::account_id(),
+				&pool_account,
 				balance,
 				Preservation::Expendable,
 			)?;
 
-			let scaled_minted_tokens = pool.scaled_supply_balance(balance)?;
 			let current_supply_index = pool.supply_index;
+			Self::accrue_rewards(who, asset)?;
 			Self::update_and_mint(who, asset, pool.id, scaled_minted_tokens, current_supply_index)?;
+			Self::accrue_rewards(who, asset)?;
+			if !T::WithdrawCooldown::get().is_zero() {
+				LastSupplies::<T>::insert((who, asset), <frame_system::Pallet<T>>::block_number());
+			}
+
+			Self::ensure_within_supply_cap(who, &pool)?;
+			Self::ensure_tier_allows(who, &pool)?;
 
 			// let's update the balances of the pool now
-			LendingPoolStorage::<T>::set(&asset_pool, pool);
+			Self::update_pool_totals(&pool);
+			LendingPoolStorage::<T>::insert(&asset_pool, pool);
 
-			Ok(())
+			T::LendingHooks::on_supply(who, asset, balance);
+			Ok(scaled_minted_tokens)
 		}
 
 		/// This method allows a user to withdraw liquidity from a lending pool.
 		/// The pool can be deactivated or not, but the user must have enough LP tokens to withdraw.
-		/// This method withdraw some liquidity from a liquidy pool and burns LP tokens of the user
+		/// This method withdraw some liquidity from a liquidy pool and burns LP tokens of the user.
+		/// With `Precision::BestEffort` the balance is capped to what the user is eligible to and
+		/// the pool reserves, rather than failing. Returns the withdrawn balance and the LP tokens
+		/// burned.
 		pub fn do_withdrawal(
 			who: &T::AccountId,
 			asset: AssetIdOf<T>,
 			balance: BalanceOf<T>,
-		) -> DispatchResult {
-			// First, let's check the balance amount to supply is valid
-			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::InvalidLiquidityWithdrawal);
+			precision: Precision,
+		) -> Result<(BalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+			let (asset_pool, pool) = Self::withdrawal_pool(who, asset)?;
 
-			// let's check if our pool does exist
-			let asset_pool = AssetPool::<T>::from(asset);
-			ensure!(
-				LendingPoolStorage::<T>::contains_key(&asset_pool),
-				Error::<T>::LendingPoolDoesNotExist
-			);
+			// let's check if the user is actually elegible to withdraw! Frozen or held LP tokens
+			// can not be redeemed
+			let scaled_lp_tokens = Self::reducible_k_tokens(who, pool.id);
+			let eligible_lp_tokens = pool.accrued_deposit(scaled_lp_tokens)?;
+			let balance = match precision {
+				Precision::Exact => balance,
+				Precision::BestEffort => balance.min(eligible_lp_tokens).min(pool.reserve_balance),
+			};
+			let burnable_amount = pool.scaled_supply_balance(balance, Rounding::Up)?;
+
+			Self::withdraw_liquidity(
+				who,
+				asset,
+				asset_pool,
+				pool,
+				balance,
+				burnable_amount,
+				eligible_lp_tokens,
+			)
+		}
+
+		/// Withdraws the liquidity redeemed by exactly `shares` LP tokens of the user, or by all
+		/// the LP tokens it can redeem with `None`. Unlike `do_withdrawal`, the LP tokens burned
+		/// do not depend on the exchange rate, which leaves no dust behind when it moves. Returns
+		/// the withdrawn balance and the LP tokens burned.
+		pub fn do_withdraw_shares(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			shares: Option<AssetBalanceOf<T>>,
+		) -> Result<(BalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+			let (asset_pool, pool) = Self::withdrawal_pool(who, asset)?;
 
-			let mut pool = LendingPoolStorage::<T>::get(asset_pool.clone());
+			let scaled_lp_tokens = Self::reducible_k_tokens(who, pool.id);
+			let shares = shares.unwrap_or(scaled_lp_tokens);
+			ensure!(scaled_lp_tokens >= shares, Error::<T>::NotEnoughElegibleLiquidityToWithdraw);
+			let eligible_lp_tokens = pool.accrued_deposit(scaled_lp_tokens)?;
+			let balance = pool.accrued_deposit(shares)?;
 
-			// let's check the if the pool has enough liquidity
-			ensure!(pool.reserve_balance >= balance, Error::<T>::NotEnoughLiquiditySupply);
+			Self::withdraw_liquidity(
+				who,
+				asset,
+				asset_pool,
+				pool,
+				balance,
+				shares,
+				eligible_lp_tokens,
+			)
+		}
+
+		/// Returns the lending pool of `asset` to withdraw from, with its indexes updated, once
+		/// the last supply of `who` is out of its `WithdrawCooldown`
+		fn withdrawal_pool(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> Result<(AssetPool<T>, LendingPool<T>), DispatchError> {
+			// let's check if our pool does exist
+			let asset_pool = AssetPool::<T>::from(asset);
+			let mut pool = LendingPoolStorage::<T>::get(&asset_pool)
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+
+			// no flash deposits: a supply stays in the pool for the `WithdrawCooldown`
+			if let Some(supplied_at) = LastSupplies::<T>::take((who, asset)) {
+				ensure!(
+					<frame_system::Pallet<T>>::block_number() >=
+						supplied_at.saturating_add(T::WithdrawCooldown::get()),
+					Error::<T>::WithdrawalInCooldown
+				);
+			}
 
 			// Update pool's indexes
 			pool.update_indexes()?;
+			Ok((asset_pool, pool))
+		}
 
-			// let's check if the user is actually elegible to withdraw!
-			let scaled_lp_tokens = T::Fungibles::balance(pool.id.clone(), &who);
-			let eligible_lp_tokens = pool.accrued_deposit(scaled_lp_tokens)?;
+		/// Transfers `balance` of the pool to `who` and burns `burnable_amount` of its LP tokens,
+		/// within the `eligible_lp_tokens` of the user
+		fn withdraw_liquidity(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			asset_pool: AssetPool<T>,
+			mut pool: LendingPool<T>,
+			balance: BalanceOf<T>,
+			burnable_amount: AssetBalanceOf<T>,
+			eligible_lp_tokens: AssetBalanceOf<T>,
+		) -> Result<(BalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+			let pool_account = Self::pool_account_id(pool.id);
+
+			// let's check the balance amount to withdraw is valid
+			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+			T::ComplianceCheck::check_withdraw(who, asset, balance)?;
+
+			// let's check the if the pool has enough liquidity
+			ensure!(pool.reserve_balance >= balance, Error::<T>::InsufficientLiquidity);
 			ensure!(
 				eligible_lp_tokens >= balance,
 				Error::<T>::NotEnoughElegibleLiquidityToWithdraw
 			);
+			Self::ensure_not_paused(asset)?;
+			Self::record_outflow(asset, balance)?;
 
 			// Transfer the asset to the user
 			T::Fungibles::transfer(
 				asset.clone(),
-				&Self::account_id(),
+				&pool_account,
 				who,
 				balance,
-				Preservation::Preserve,
+				Preservation::Expendable,
 			)?;
 
 			// burn the LP asset
-			let burnable_amount = pool.scaled_supply_balance(balance)?;
+			Self::accrue_rewards(who, asset)?;
 			T::Fungibles::burn_from(
 				pool.id,
 				who,
 				burnable_amount,
 				Precision::Exact,
-				Fortitude::Force,
+				Fortitude::Polite,
 			)?;
-			pool.reserve_balance =
-				pool.reserve_balance.checked_sub(&balance).ok_or(Error::<T>::OverflowError)?;
+			Self::accrue_rewards(who, asset)?;
+			pool.reserve_balance = pool.reserve_balance.ensure_sub(balance)?;
 
 			// let's update the balances of the pool now
-			LendingPoolStorage::<T>::set(&asset_pool, pool);
+			Self::update_pool_totals(&pool);
+			LendingPoolStorage::<T>::insert(&asset_pool, pool);
+
+			Ok((balance, burnable_amount))
+		}
 
+		/// Records `balance` flowing out of the lending pool of `asset`, and ensures that its
+		/// outflows stay within its `OutflowCaps`. The outflows recorded earlier decay linearly
+		/// to zero over the `OutflowWindow`.
+		pub(crate) fn record_outflow(
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let Some(cap) = OutflowCaps::<T>::get(asset) else {
+				return Ok(());
+			};
+			let outflow = Self::decayed_outflow(asset).ensure_add(balance)?;
+			ensure!(outflow <= cap, Error::<T>::OutflowCapExceeded);
+			PoolOutflows::<T>::insert(asset, (outflow, <frame_system::Pallet<T>>::block_number()));
 			Ok(())
 		}
 
-		///
-		fn do_borrow(
+		/// Returns the outflows of the lending pool of `asset` recorded over the `OutflowWindow`,
+		/// decayed up to now
+		pub(crate) fn decayed_outflow(asset: AssetIdOf<T>) -> AssetBalanceOf<T> {
+			let now = <frame_system::Pallet<T>>::block_number();
+			let window = T::OutflowWindow::get();
+			match PoolOutflows::<T>::get(asset) {
+				Some((outflow, at)) if now.saturating_sub(at) < window => {
+					let remaining = window.saturating_sub(now.saturating_sub(at));
+					Ratio::from_rational(
+						remaining.saturated_into::<u64>(),
+						window.saturated_into::<u64>(),
+					)
+					.mul_ceil(outflow)
+				},
+				_ => AssetBalanceOf::<T>::zero(),
+			}
+		}
+
+		/// Transfers `balance` LP tokens of the lending pool of `asset` from `who` to `dest`,
+		/// accruing the rewards of both accounts before their working balances are refreshed.
+		/// `dest` gets a supply index of its own if it did not supply to the pool yet.
+		pub fn do_transfer_lp_tokens(
+			who: &T::AccountId,
+			dest: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			ensure!(balance > AssetBalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+			let pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+
+			Self::ensure_k_tokens_unfrozen(who, pool.id, balance)?;
+			Self::accrue_rewards(who, asset)?;
+			Self::accrue_rewards(dest, asset)?;
+			T::Fungibles::transfer(pool.id, who, dest, balance, Preservation::Expendable)?;
+			// the LP tokens received count towards the supply caps of `dest` as if it supplied them
+			Self::ensure_within_supply_cap(dest, &pool)?;
+			Self::ensure_tier_allows(dest, &pool)?;
+			if !SupplyIndexStorage::<T>::contains_key((dest, asset)) {
+				SupplyIndexStorage::<T>::insert(
+					(dest, asset),
+					SupplyIndex::from(pool.supply_index, Self::now_in_seconds()),
+				);
+			}
+			// the LP tokens carry the cooldown of their last supply along
+			if let Some(supplied_at) = LastSupplies::<T>::get((who, asset)) {
+				LastSupplies::<T>::mutate((dest, asset), |at| {
+					*at = Some(at.map_or(supplied_at, |at| at.max(supplied_at)))
+				});
+			}
+			Self::accrue_rewards(who, asset)?;
+			Self::accrue_rewards(dest, asset)?;
+			Ok(())
+		}
+
+		/// Borrows `balance` of `asset` against `collateral_balance` of `collateral_asset`, at a
+		/// stable rate with `stable`, at the variable rate of the pool otherwise. Returns the
+		/// origination fee.
+		pub(crate) fn do_borrow(
 			who: &T::AccountId,
 			asset: AssetIdOf<T>,
 			balance: AssetBalanceOf<T>,
 			collateral_asset: AssetIdOf<T>,
 			collateral_balance: AssetBalanceOf<T>,
-		) -> DispatchResult {
-			// First, let's check the balance amount to supply is valid
-			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::InvalidLiquidityWithdrawal);
+			stable: bool,
+		) -> Result<AssetBalanceOf<T>, DispatchError> {
+			// First, let's check the balances to borrow and to lock are valid
+			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+			ensure!(collateral_balance > AssetBalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+			T::ComplianceCheck::check_borrow(who, asset, balance)?;
+
+			// the borrows owed to a retired pool can not be increased
+			Self::ensure_not_retired(who, asset, collateral_asset)?;
+			// the rate mode of a borrow is fixed at its origination
+			ensure!(
+				!BorrowMaturities::<T>::contains_key((who, asset, collateral_asset)),
+				Error::<T>::FixedTermBorrowNotExtendable
+			);
 			ensure!(
-				collateral_balance > AssetBalanceOf::<T>::zero(),
-				Error::<T>::InvalidLiquidityWithdrawal
+				!Borrows::<T>::contains_key((who, asset, collateral_asset)) ||
+					StableBorrows::<T>::contains_key((who, asset, collateral_asset)) == stable,
+				Error::<T>::BorrowRateModeMismatch
 			);
 
 			// let's check if our pool does exist
 			let asset_pool = AssetPool::<T>::from(asset);
-			ensure!(
-				LendingPoolStorage::<T>::contains_key(&asset_pool),
-				Error::<T>::LendingPoolDoesNotExist
-			);
-			let user_collateral_balance = T::Fungibles::reducible_balance(
-				collateral_asset,
-				who,
-				Preservation::Preserve,
-				Fortitude::Polite,
-			);
+			let mut pool = LendingPoolStorage::<T>::get(&asset_pool)
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+			let pool_account = Self::pool_account_id(pool.id);
+			let user_collateral_balance = Self::reducible_collateral(who, collateral_asset);
 			ensure!(
 				user_collateral_balance >= collateral_balance,
-				Error::<T>::NotEnoughLiquiditySupply
+				Error::<T>::InsufficientCollateral
 			);
 
 			// let's check if the pool is active
-			let mut pool = LendingPoolStorage::<T>::get(asset_pool.clone());
 			ensure!(pool.is_active() == true, Error::<T>::LendingPoolNotActive);
+			Self::ensure_whitelisted(who, asset)?;
 
 			// let's check the if the pool has enough liquidity
-			ensure!(pool.reserve_balance >= balance, Error::<T>::NotEnoughLiquiditySupply);
+			ensure!(pool.reserve_balance >= balance, Error::<T>::InsufficientLiquidity);
+			Self::ensure_not_paused(asset)?;
+			Self::record_outflow(asset, balance)?;
 
 			// Update pool's indexex
 			pool.update_indexes()?;
+			if let Some(cap) = BorrowCaps::<T>::get(asset) {
+				ensure!(
+					pool.borrowed_balance.ensure_add(balance)? <= cap,
+					Error::<T>::BorrowCapExceeded
+				);
+			}
 
 			// check sufficiency of collateral asset
 			// get collateral asset value in terms of borrow-asset
@@ -1329,17 +5280,42 @@ pub mod pallet {
 			)?;
 			// get elligible borrow quantity based on reserve_factor
 			let eligible_asset_amount = pool.max_borrow_amount(equivalent_asset_balace)?;
+			// LP tokens are only accepted at a share of the collateral factor, if at all
+			let eligible_asset_amount = match LpTokenPools::<T>::get(collateral_asset) {
+				Some(lp_asset) => LpCollateralFactor::<T>::get(lp_asset)
+					.ok_or(Error::<T>::LpTokenCollateralNotAllowed)?
+					.mul_floor(eligible_asset_amount),
+				None => eligible_asset_amount,
+			};
 			// error if borrow is more than eligibility
 			ensure!(eligible_asset_amount >= balance, Error::<T>::NotEnoughCollateral);
 
-			// Save sacled balance as per current borrow_index
-			let scaled_balance = pool.scaled_borrow_balance(balance)?;
+			// the stable rate is priced off the utilisation of the pool after the borrow
+			if stable {
+				let mut after = pool.clone();
+				after.move_asset_on_borrow(balance)?;
+				Self::fix_stable_rate(who, asset, collateral_asset, &after, balance)?;
+			}
+
+			// the debt of the borrow must reach the minimum borrow of the pool
+			let debt = match Borrows::<T>::get((who, asset, collateral_asset)) {
+				Some(loan) => Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?
+					.ensure_add(balance)?,
+				None => balance,
+			};
+			ensure!(debt >= MinBorrow::<T>::get(asset), Error::<T>::BorrowTooSmall);
+
+			// Save sacled balance as per current debt index
+			let index = Self::debt_index(who, asset, collateral_asset, &pool)?;
+			let scaled_balance =
+				math::div_rate(balance, index, Rounding::Up).ok_or(Error::<T>::OverflowError)?;
 
 			let borrow: UserBorrow<T> = UserBorrow {
 				borrowed_asset: asset,
 				borrowed_balance: scaled_balance,
 				collateral_asset,
 				collateral_balance,
+				principal: Some(balance),
 			};
 
 			Borrows::<T>::try_mutate(
@@ -1352,6 +5328,8 @@ pub mod pallet {
 						// The entry does not exist, so we assign `Some(borrow)` to it to store the
 						// new value
 						*maybe_borrow = Some(borrow);
+						AccountBorrows::<T>::try_append(who, (asset, collateral_asset))
+							.map_err(|_| Error::<T>::TooManyMarkets)?;
 					}
 					Ok(())
 				},
@@ -1360,130 +5338,344 @@ pub mod pallet {
 			// Update pool: transfer asset from reserved_balance to borrowed_balance
 			pool.move_asset_on_borrow(balance)?;
 
-			LendingPoolStorage::<T>::set(&asset_pool, pool);
+			// the origination fee stays in the pool reserves
+			let fee = pool.borrow_fee.mul_floor(balance);
+			pool.reserve_balance = pool.reserve_balance.ensure_add(fee)?;
 
-			// Transfer the asset to the user
+			Self::update_pool_totals(&pool);
+			LendingPoolStorage::<T>::insert(&asset_pool, pool);
+
+			// Transfer the asset minus the fee to the user
 			T::Fungibles::transfer(
 				asset.clone(),
-				&Self::account_id(),
+				&pool_account,
 				who,
-				balance,
-				Preservation::Preserve,
+				balance.ensure_sub(fee)?,
+				Preservation::Expendable,
 			)?;
 
 			// Transfer the collateral to the pallet
-			T::Fungibles::transfer(
-				collateral_asset.clone(),
+			Self::ensure_k_tokens_unfrozen(who, collateral_asset, collateral_balance)?;
+			Self::checkpoint_k_tokens(who, collateral_asset);
+			Self::post_collateral(
 				who,
-				&Self::account_id(),
+				collateral_asset,
+				&pool_account,
 				collateral_balance,
 				Preservation::Preserve,
 			)?;
 
-			Ok(())
+			T::LendingHooks::on_borrow(who, asset, balance, collateral_asset, collateral_balance);
+			Ok(fee)
 		}
 
-		fn do_repay(
+		pub(crate) fn do_repay(
 			who: &T::AccountId,
 			asset: AssetIdOf<T>,
 			balance: AssetBalanceOf<T>,
 			collateral_asset: AssetIdOf<T>,
 		) -> DispatchResult {
-			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::InvalidLiquidityWithdrawal);
+			Self::do_repay_on_behalf(
+				who,
+				who,
+				asset,
+				balance,
+				collateral_asset,
+				Preservation::Preserve,
+			)
+		}
+
+		/// Repays the borrows of `who` of the (asset, collateral asset, balance) `repayments`,
+		/// all or none of them
+		pub(crate) fn do_repay_many(
+			who: &T::AccountId,
+			repayments: &[(AssetIdOf<T>, AssetIdOf<T>, AssetBalanceOf<T>)],
+		) -> DispatchResult {
+			frame_support::storage::with_storage_layer(|| {
+				for (i, (asset, collateral_asset, balance)) in repayments.iter().enumerate() {
+					ensure!(
+						!repayments[..i].iter().any(|(other_asset, other_collateral, _)| {
+							(other_asset, other_collateral) == (asset, collateral_asset)
+						}),
+						Error::<T>::DuplicateRepayment
+					);
+					Self::do_repay(who, *asset, *balance, *collateral_asset)?;
+				}
+				Ok(())
+			})
+		}
 
-			// let's check if our pool does exist
-			let asset_pool = AssetPool::<T>::from(asset);
-			ensure!(
-				LendingPoolStorage::<T>::contains_key(&asset_pool),
-				Error::<T>::LendingPoolDoesNotExist
-			);
+		/// Repays the borrow of `who` with the assets of `payer`, who receives the released
+		/// collateral. The `preservation` of the payment may let a liquidator, who is credited the
+		/// collateral in return, spend its whole balance.
+		pub(crate) fn do_repay_on_behalf(
+			payer: &T::AccountId,
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: AssetBalanceOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			preservation: Preservation,
+		) -> DispatchResult {
+			ensure!(balance > BalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
 
-			// get the lending pool and update the indexes
-			let mut pool = LendingPoolStorage::<T>::get(&asset_pool);
+			// get the lending pool the borrow is owed to and update the indexes
+			let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+			let pool_account = Self::pool_account_id(pool.id);
 			pool.update_indexes()?;
 
 			// get the repay amount and check if loan exists
 			let mut loan = Borrows::<T>::get((who, asset, collateral_asset))
 				.ok_or(Error::<T>::LoanDoesNotExists)?;
-			let repayable_balance = pool.repayable_amount(loan.borrowed_balance)?;
+			let index = Self::debt_index(who, asset, collateral_asset, &pool)?;
+			let repayable_balance =
+				Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
 
 			// take max upto repayable amount
-			let (pay, is_full_payment) = if balance <= repayable_balance {
+			let (pay, is_full_payment) = if balance < repayable_balance {
 				(balance, false)
 			} else {
 				(repayable_balance, true)
 			};
+			// a partial repayment must leave at least the minimum borrow of the pool
+			ensure!(
+				is_full_payment || repayable_balance.ensure_sub(pay)? >= MinBorrow::<T>::get(asset),
+				Error::<T>::BorrowTooSmall
+			);
 
-			// Update pool: transfer asset from reserved_balance to borrowed_balance
-			pool.move_asset_on_repay(pay)?;
+			// Update pool: transfer asset from borrowed_balance to reserved_balance
+			let (principal, interest) = loan.split_repayment(pay, repayable_balance)?;
+			pool.move_asset_on_repay(principal, interest)?;
+
+			// set the protocol fee and the referrer's share of the interest aside from the pool
+			let protocol_fee = ProtocolFee::<T>::get().mul_floor(interest);
+			let referral_share = Self::credit_referral(who, &pool, asset, interest)?;
+			pool.reserve_balance =
+				pool.reserve_balance.ensure_sub(protocol_fee)?.ensure_sub(referral_share)?;
 
 			// transfer repay amount to the market
-			T::Fungibles::transfer(
-				asset.clone(),
-				who,
-				&Self::account_id(),
-				pay,
-				Preservation::Preserve,
-			)?;
+			T::Fungibles::transfer(asset.clone(), payer, &pool_account, pay, preservation)?;
+
+			// redirect the protocol fee to the treasury
+			if !protocol_fee.is_zero() {
+				T::Fungibles::transfer(
+					asset,
+					&pool_account,
+					&T::TreasuryAccount::get(),
+					protocol_fee,
+					Preservation::Expendable,
+				)?;
+			}
+
+			// the referral earnings are paid out of the account of the live pool of the asset
+			if !referral_share.is_zero() && RetiredPools::<T>::contains_key(pool.id) {
+				let live_pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+					.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+				T::Fungibles::transfer(
+					asset,
+					&pool_account,
+					&Self::pool_account_id(live_pool.id),
+					referral_share,
+					Preservation::Expendable,
+				)?;
+			}
 
 			if is_full_payment {
 				// clear the borrow
 				Borrows::<T>::remove((who, asset, collateral_asset));
+				RetiredBorrows::<T>::remove((who, asset, collateral_asset));
+				StableBorrows::<T>::remove((who, asset, collateral_asset));
+				BorrowMaturities::<T>::remove((who, asset, collateral_asset));
+				MarginCalls::<T>::remove((who, asset, collateral_asset));
+				AccountBorrows::<T>::mutate(who, |borrows| {
+					borrows.retain(|borrow| borrow != &(asset, collateral_asset))
+				});
 				// release all the collateral
-				T::Fungibles::transfer(
-					collateral_asset.clone(),
-					&Self::account_id(),
+				Self::checkpoint_k_tokens(payer, collateral_asset);
+				Self::release_collateral(
 					who,
+					collateral_asset,
+					&pool_account,
+					payer,
 					loan.collateral_balance,
-					Preservation::Preserve,
 				)?;
 			} else {
-				// get the amount of collateral to release
-				// pay / repayable_balance * collateral_balance
+				// repay the borrow
+				let scaled_pay =
+					math::div_rate(pay, index, Rounding::Down).ok_or(Error::<T>::OverflowError)?;
+				// get the amount of collateral to release, pro rata of the debt actually cleared
+				// scaled_pay / scaled_balance * collateral_balance
 				let release_collateral_amount: AssetBalanceOf<T> =
 					Self::get_release_collateral_amount(
-						pay,
-						repayable_balance,
+						scaled_pay,
+						loan.borrowed_balance,
 						loan.collateral_balance,
 					)?;
-				// repay the borrow
-				let scaled_pay = pool.scaled_borrow_balance(pay)?;
 				loan.repay_partial(scaled_pay, release_collateral_amount)?;
 				Borrows::<T>::set((who, asset, collateral_asset), Some(loan));
 				// release partial collateral
-				T::Fungibles::transfer(
-					collateral_asset.clone(),
-					&Self::account_id(),
+				Self::checkpoint_k_tokens(payer, collateral_asset);
+				Self::release_collateral(
 					who,
+					collateral_asset,
+					&pool_account,
+					payer,
 					release_collateral_amount,
-					Preservation::Preserve,
 				)?;
 			}
 
-			// emit event
+			Self::store_pool(pool);
 
+			T::LendingHooks::on_repay(payer, who, asset, pay, collateral_asset);
 			Ok(())
 		}
 
-		/// This method de-activates an existing lending pool
-		pub fn do_deactivate_lending_pool(asset: AssetIdOf<T>) -> DispatchResult {
-			// let's check if our pool does exist before de-activating it
+		/// This method creates or updates the reward program of a lending pool.
+		/// The reward index of an existing program is accrued before the new speed applies.
+		pub fn do_set_reward_program(
+			asset: AssetIdOf<T>,
+			reward_asset: AssetIdOf<T>,
+			speed: AssetBalanceOf<T>,
+			vesting: Option<VestingTerms>,
+		) -> DispatchResult {
 			let asset_pool = AssetPool::<T>::from(asset);
 			ensure!(
 				LendingPoolStorage::<T>::contains_key(&asset_pool),
 				Error::<T>::LendingPoolDoesNotExist
 			);
+			if let Some(terms) = &vesting {
+				ensure!(terms.is_valid(), Error::<T>::InvalidVestingTerms);
+			}
+
+			RewardPrograms::<T>::try_mutate(asset, |maybe_program| -> DispatchResult {
+				if let Some(program) = maybe_program {
+					ensure!(program.reward_asset == reward_asset, Error::<T>::RewardAssetMismatch);
+					program.accrue(asset)?;
+					program.supply_speed = speed;
+					program.vesting = vesting;
+					program.paused = false;
+				} else {
+					*maybe_program = Some(RewardProgram::from(reward_asset, speed, vesting));
+				}
+				Ok(())
+			})
+		}
+
+		/// This method accrues the interest of a lending pool up to now
+		pub fn do_accrue_interest(asset: AssetIdOf<T>) -> DispatchResult {
+			let asset_pool = AssetPool::<T>::from(asset);
+			let mut pool = LendingPoolStorage::<T>::try_get(&asset_pool)
+				.map_err(|_| Error::<T>::LendingPoolDoesNotExist)?;
+			ensure!(Self::accrual_due(&pool), Error::<T>::NoInterestToAccrue);
+
+			pool.update_indexes()?;
+			Self::record_rate_snapshot(&pool);
+			LendingPoolStorage::<T>::insert(&asset_pool, pool);
+			Ok(())
+		}
+
+		/// Records a snapshot of the rates of `pool`, unless one was recorded in the last
+		/// `RateSnapshotInterval` blocks. The oldest snapshot is dropped once `MaxRateSnapshots`
+		/// are kept. Returns whether a snapshot was recorded.
+		pub(crate) fn record_rate_snapshot(pool: &LendingPool<T>) -> bool {
+			let now = <frame_system::Pallet<T>>::block_number();
+			let mut snapshots = RateSnapshots::<T>::get(pool.lend_token_id);
+			if snapshots.last().map_or(false, |last| {
+				now < last.block.saturating_add(T::RateSnapshotInterval::get())
+			}) {
+				return false;
+			}
+			let (Ok(borrow_rate), Ok(supply_rate)) =
+				(pool.borrow_interest_rate(), pool.supply_interest_rate())
+			else {
+				return false;
+			};
+			if snapshots.len() as u32 >= T::MaxRateSnapshots::get() && !snapshots.is_empty() {
+				snapshots.remove(0);
+			}
+			let snapshot = RateSnapshot {
+				block: now,
+				borrow_rate,
+				supply_rate,
+				exchange_rate: pool.supply_index,
+			};
+			if snapshots.try_push(snapshot).is_err() {
+				return false;
+			}
+			RateSnapshots::<T>::insert(pool.lend_token_id, snapshots);
+			true
+		}
+
+		/// This method accrues the interest of up to `limit` active lending pools, round-robin
+		/// from the `AccrualCursor`, so that idle pools do not drift in between the user
+		/// operations. Returns the consumed weight.
+		pub fn accrue_pools(limit: u32) -> Weight {
+			let cursor = AccrualCursor::<T>::get();
+			let after_cursor = cursor.map(|asset| {
+				LendingPoolStorage::<T>::iter_from(LendingPoolStorage::<T>::hashed_key_for(
+					AssetPool::<T>::from(asset),
+				))
+			});
+			// carry on after the cursor, then wrap around up to the cursor
+			let pools = after_cursor
+				.into_iter()
+				.flatten()
+				.chain(LendingPoolStorage::<T>::iter())
+				.take_while(|(_, pool)| Some(pool.lend_token_id) != cursor)
+				.take(limit as usize);
+
+			let now = Self::now_in_seconds();
+			let (mut reads, mut writes) = (1, 0);
+			let mut last = None;
+			for (asset_pool, mut pool) in pools {
+				reads += 1;
+				last = Some(pool.lend_token_id);
+				if pool.is_active() &&
+					pool.last_accrued_interest_at < now &&
+					pool.update_indexes().is_ok()
+				{
+					reads += 1;
+					if Self::record_rate_snapshot(&pool) {
+						writes += 1;
+					}
+					LendingPoolStorage::<T>::insert(&asset_pool, pool);
+					writes += 1;
+				}
+			}
+			if let Some(asset) = last {
+				AccrualCursor::<T>::put(asset);
+				writes += 1;
+			}
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		/// This method de-activates an existing lending pool
+		pub fn do_deactivate_lending_pool(asset: AssetIdOf<T>) -> DispatchResult {
+			// let's check if our pool does exist before de-activating it
+			let asset_pool = AssetPool::<T>::from(asset);
+			let mut pool = LendingPoolStorage::<T>::get(&asset_pool)
+				.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
 
 			// let's check if our pool is actually already non-active
-			let pool = LendingPoolStorage::<T>::get(asset_pool.clone());
 			ensure!(pool.is_active() == true, Error::<T>::LendingPoolAlreadyDeactivated);
 
 			// ok now we can de-activate it
-			LendingPoolStorage::<T>::mutate(asset_pool, |v| v.activated = false);
+			pool.activated = false;
+			LendingPoolStorage::<T>::insert(asset_pool, pool);
 			Ok(())
 		}
 
-		/// This method returns the palled account id
+		/// This method returns the account id of the rewards pot, a sub-account of the pallet
+		/// account funding the emissions of the reward programs
+		pub fn rewards_account_id() -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(b"rewards")
+		}
+
+		/// The account holding the dead shares of the lending pools, which nobody controls
+		pub fn dead_shares_account_id() -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(b"dead")
+		}
+
+		/// This method returns the palled account id, the owner of the LP tokens
 		///
 		/// This actually does computation. If you need to keep using it,
 		/// then make sure to cache the value and only call this once.
@@ -1491,6 +5683,26 @@ pub mod pallet {
 			T::PalletId::get().into_account_truncating()
 		}
 
+		/// This method returns the account id of a lending pool, a sub-account of the pallet
+		/// account holding the reserves of the pool, the collaterals of its borrows and the
+		/// referral earnings in its asset. A pool can only pay out of its own account.
+		///
+		/// The pool accounts have a provider of their own, so the transfers out of them are
+		/// `Preservation::Expendable`: a withdrawal or a repayment may take the last unit of an
+		/// asset. The transfers debiting the users are `Preservation::Preserve`, except for the
+		/// supplies which may empty an account.
+		pub fn pool_account_id(id: AssetIdOf<T>) -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(id)
+		}
+
+		/// Keeps `account` alive with a provider of its own, so that it is not reaped when its
+		/// balances are drained, whatever the sufficiency of the assets
+		pub(crate) fn provide_for(account: &T::AccountId) {
+			if frame_system::Pallet::<T>::providers(account).is_zero() {
+				frame_system::Pallet::<T>::inc_providers(account);
+			}
+		}
+
 		/// Calculates the new_mint amount as follows,
 		/// 	let interest_on_old_deposit = old_balance * (current_supply_index - last_supply_index)
 		/// 	let total_new_mint = interest_on_old_deposit + new balance
@@ -1506,15 +5718,14 @@ pub mod pallet {
 			let supply_index = SupplyIndexStorage::<T>::get((who, asset));
 			let old_balance = T::Fungibles::balance(lp_id, who);
 
-			let interest_on_old_deposit = (current_supply_index
-				.checked_sub(&supply_index.supply_index)
-				.ok_or(Error::<T>::OverflowError)?)
-			.checked_mul(&FixedU128::from(old_balance.saturated_into::<u128>()))
-			.ok_or(Error::<T>::OverflowError)?;
+			let interest_on_old_deposit = math::mul_rate(
+				old_balance,
+				current_supply_index.ensure_sub(supply_index.supply_index)?,
+				Rounding::Down,
+			)
+			.ok_or(ArithmeticError::Overflow)?;
 
-			let total_new_mint = scaled_balance
-				.checked_add(&interest_on_old_deposit.into_inner().saturated_into())
-				.ok_or(Error::<T>::OverflowError)?;
+			let total_new_mint = scaled_balance.ensure_add(interest_on_old_deposit)?;
 			let updated_supply_index = SupplyIndex {
 				supply_index: current_supply_index,
 				last_accrued_interest_at: Self::now_in_seconds(),
@@ -1531,10 +5742,8 @@ pub mod pallet {
 		}
 
 		/// Returns the the block's timestamp in seconds as u64
-		fn now_in_seconds() -> u64 {
-			core::time::Duration::from_millis(T::Time::now().saturated_into::<u64>())
-				.as_secs()
-				.saturated_into::<u64>()
+		pub(crate) fn now_in_seconds() -> u64 {
+			T::Time::now().as_secs()
 		}
 
 		/// Returns the amount of asset equivalent to the collateral
@@ -1544,59 +5753,222 @@ pub mod pallet {
 		/// check if price of asset available in terms of collateral asset then
 		/// return `collateral_balance / price`
 		/// else fallback to a common base asset
-		/// get the prices of both assets in terms of asset 0 (USDT)  and
+		/// get the prices of both assets in terms of the `BaseAsset` (USDT) and
 		/// return `collateral_asset_price * collateral_balance / asset_price`
 		/// else
-		/// return error `AssetPriceNotSet`
-		fn get_equivalent_asset_amount(
-			_who: &T::AccountId,
+		/// return error `PriceUnavailable`
+		/// The prices are quoted for whole units, the balances are normalized with the decimals
+		/// of the assets.
+		/// The LP tokens of the DEX accepted as collateral are valued at their share of the fair
+		/// value of their liquidity pool instead.
+		pub(crate) fn get_equivalent_asset_amount(
+			who: &T::AccountId,
 			asset: AssetIdOf<T>,
 			collateral_asset: AssetIdOf<T>,
 			collateral_balance: AssetBalanceOf<T>,
-		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let amount = if let Some(p) = AssetPrices::<T>::get((collateral_asset, asset)) {
-				p.checked_mul(&FixedU128::from_inner(collateral_balance.saturated_into()))
-					.ok_or(Error::<T>::OverflowError)?
+		) -> Result<AssetBalanceOf<T>, DispatchError> {
+			if let Some(assets) = DexLpCollaterals::<T>::get(collateral_asset) {
+				let (value, issued) = Self::dex_lp_value(who, asset, collateral_asset, assets)?;
+				return Ok(math::mul_div(collateral_balance, value, issued, Rounding::Down)
+					.ok_or(ArithmeticError::Overflow)?);
+			}
+			if let Some(assets) = DexLpCollaterals::<T>::get(asset) {
+				let (value, issued) = Self::dex_lp_value(who, collateral_asset, asset, assets)?;
+				ensure!(!value.is_zero(), ArithmeticError::DivisionByZero);
+				return Ok(math::mul_div(collateral_balance, issued, value, Rounding::Down)
+					.ok_or(ArithmeticError::Overflow)?);
+			}
+			let (price, quote) = if let Some(p) = AssetPrices::<T>::get((collateral_asset, asset)) {
+				(p, FixedU128::one())
 			} else if let Some(p) = AssetPrices::<T>::get((asset, collateral_asset)) {
-				FixedU128::from_inner(collateral_balance.saturated_into())
-					.checked_div(&p)
-					.ok_or(Error::<T>::OverflowError)?
+				(FixedU128::one(), p)
 			} else {
-				let asset_price =
-					AssetPrices::<T>::get((asset, 0)).ok_or(Error::<T>::AssetPriceNotSet)?;
-				let collateral_price = AssetPrices::<T>::get((collateral_asset, 0))
-					.ok_or(Error::<T>::AssetPriceNotSet)?;
+				let asset_price = AssetPrices::<T>::get((asset, T::BaseAsset::get()))
+					.ok_or(Error::<T>::PriceUnavailable)?;
+				let collateral_price =
+					AssetPrices::<T>::get((collateral_asset, T::BaseAsset::get()))
+						.ok_or(Error::<T>::PriceUnavailable)?;
+				(collateral_price, asset_price)
+			};
+			ensure!(!quote.is_zero(), ArithmeticError::DivisionByZero);
 
-				collateral_price
-					.checked_div(&asset_price)
-					.ok_or(Error::<T>::OverflowError)?
-					.checked_mul(&FixedU128::from_inner(collateral_balance.saturated_into()))
-					.ok_or(Error::<T>::OverflowError)?
-			}
-			.into_inner()
-			.saturated_into();
+			let amount = math::convert(
+				collateral_balance,
+				Self::asset_decimals(collateral_asset),
+				price,
+				quote,
+				Self::asset_decimals(asset),
+				Rounding::Down,
+			)
+			.ok_or(ArithmeticError::Overflow)?;
 			Ok(amount)
 		}
 
+		/// Returns the decimals of `asset` from its metadata
+		pub(crate) fn asset_decimals(asset: AssetIdOf<T>) -> u8 {
+			<T::Fungibles as fungibles::metadata::Inspect<T::AccountId>>::decimals(asset)
+		}
+
+		/// Ensures that `who` may supply to and borrow from the lending pool of `asset`, i.e. that
+		/// the pool is permissionless or that `who` is on its whitelist
+		pub fn ensure_whitelisted(who: &T::AccountId, asset: AssetIdOf<T>) -> DispatchResult {
+			ensure!(
+				!PermissionedPools::<T>::get(asset) || PoolWhitelist::<T>::get(asset, who),
+				Error::<T>::NotWhitelisted
+			);
+			Ok(())
+		}
+
+		/// Ensures that the LP tokens of `who` are worth no more than the `AccountSupplyCaps` share
+		/// of the supplied balance of `pool`, i.e. its reserves and borrowed balance
+		pub(crate) fn ensure_within_supply_cap(
+			who: &T::AccountId,
+			pool: &LendingPool<T>,
+		) -> DispatchResult {
+			let Some(cap) = AccountSupplyCaps::<T>::get(pool.lend_token_id) else { return Ok(()) };
+			let supplied = pool.reserve_balance.ensure_add(pool.borrowed_balance)?;
+			let held = pool.accrued_deposit(T::Fungibles::balance(pool.id, who))?;
+			ensure!(held <= cap.mul_floor(supplied), Error::<T>::SupplyCapExceeded);
+			Ok(())
+		}
+
+		/// Returns the supply and the borrow index of the lending pool of `asset`, as carried by
+		/// the events so that the indexers can follow the pools from the events alone
+		pub(crate) fn pool_indexes(asset: AssetIdOf<T>) -> (Rate, Rate) {
+			LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+				.map_or((Rate::one(), Rate::one()), |pool| (pool.supply_index, pool.borrow_index))
+		}
+
+		/// Returns the LP tokens of `who` in the lending pool of `asset` and the utilisation of
+		/// the pool, as carried by the events so that the clients need not query them after a
+		/// supply or a withdrawal
+		pub(crate) fn supply_state(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+		) -> (AssetBalanceOf<T>, Ratio) {
+			LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset)).map_or(
+				(Zero::zero(), Ratio::zero()),
+				|pool| {
+					let utilisation = pool.utilisation_ratio().unwrap_or_default();
+					(T::Fungibles::balance(pool.id, who), utilisation)
+				},
+			)
+		}
+
+		/// Returns the debt of the borrow of `who` in `asset` against `collateral_asset` and the
+		/// utilisation of the lending pool, as carried by the events like `supply_state`
+		pub(crate) fn borrow_state(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+		) -> (AssetBalanceOf<T>, Ratio) {
+			let Ok(pool) = Self::borrow_pool(who, asset, collateral_asset) else {
+				return (Zero::zero(), Ratio::zero());
+			};
+			let debt = Borrows::<T>::get((who, asset, collateral_asset))
+				.and_then(|loan| {
+					Self::repayable_balance(who, asset, collateral_asset, &pool, &loan).ok()
+				})
+				.unwrap_or_else(Zero::zero);
+			(debt, pool.utilisation_ratio().unwrap_or_default())
+		}
+
+		/// Deposits a `DepositSupplied` event with the state of the pool after the supply
+		pub(crate) fn deposit_supplied_event(
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			minted: AssetBalanceOf<T>,
+		) {
+			let (exchange_rate, borrow_index) = Self::pool_indexes(asset);
+			let (lp_tokens, utilisation) = Self::supply_state(&who, asset);
+			Self::deposit_event(Event::DepositSupplied {
+				who,
+				asset,
+				balance,
+				minted,
+				exchange_rate,
+				borrow_index,
+				lp_tokens,
+				utilisation,
+			});
+		}
+
+		/// Deposits a `DepositWithdrawn` event with the state of the pool after the withdrawal
+		pub(crate) fn deposit_withdrawn_event(
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			burned: AssetBalanceOf<T>,
+		) {
+			let (exchange_rate, borrow_index) = Self::pool_indexes(asset);
+			let (lp_tokens, utilisation) = Self::supply_state(&who, asset);
+			Self::deposit_event(Event::DepositWithdrawn {
+				who,
+				asset,
+				balance,
+				burned,
+				exchange_rate,
+				borrow_index,
+				lp_tokens,
+				utilisation,
+			});
+		}
+
+		/// Deposits a `DepositBorrowed` event with the state of the pool after the borrow
+		pub(crate) fn deposit_borrowed_event(
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			fee: BalanceOf<T>,
+		) {
+			let (exchange_rate, borrow_index) = Self::pool_indexes(asset);
+			let (debt, utilisation) = Self::borrow_state(&who, asset, collateral_asset);
+			Self::deposit_event(Event::DepositBorrowed {
+				who,
+				asset,
+				collateral_asset,
+				balance,
+				fee,
+				exchange_rate,
+				borrow_index,
+				debt,
+				utilisation,
+			});
+		}
+
+		/// Deposits a `DepositRepaid` event with the state of the pool after the repayment
+		pub(crate) fn deposit_repaid_event(
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			collateral_asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) {
+			let (exchange_rate, borrow_index) = Self::pool_indexes(asset);
+			let (debt, utilisation) = Self::borrow_state(&who, asset, collateral_asset);
+			Self::deposit_event(Event::DepositRepaid {
+				who,
+				asset,
+				collateral_asset,
+				balance,
+				exchange_rate,
+				borrow_index,
+				debt,
+				utilisation,
+			});
+		}
+
 		/// Returns the amount of collateral asset to be released on partila repayement
 		/// Returns release_amount = pay / repayable_balance * collateral_balance
 		fn get_release_collateral_amount(
 			payment: AssetBalanceOf<T>,
 			total_due: AssetBalanceOf<T>,
 			collateral_balance: AssetBalanceOf<T>,
-		) -> Result<AssetBalanceOf<T>, Error<T>> {
-			let f_payment = FixedU128::from(payment.saturated_into::<u128>());
-			let f_total_due = FixedU128::from(total_due.saturated_into::<u128>());
-			let f_collateral_balance = FixedU128::from(collateral_balance.saturated_into::<u128>());
-
-			let amount = f_payment
-				.checked_div(&f_total_due)
-				.ok_or(Error::<T>::OverflowError)?
-				.checked_mul(&f_collateral_balance)
-				.ok_or(Error::<T>::OverflowError)?
-				.into_inner()
-				.saturated_into();
-
+		) -> Result<AssetBalanceOf<T>, DispatchError> {
+			ensure!(!total_due.is_zero(), ArithmeticError::DivisionByZero);
+			let amount = math::mul_div(payment, collateral_balance, total_due, Rounding::Down)
+				.ok_or(ArithmeticError::Overflow)?;
 			Ok(amount)
 		}
 	}
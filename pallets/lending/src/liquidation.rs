@@ -0,0 +1,297 @@
+use crate::*;
+use frame_support::storage::with_storage_layer;
+
+impl<T: Config> Pallet<T> {
+	/// Returns the shortfall of the borrow of `who`, that is the part of its debt not covered
+	/// by its collateral weighted with the liquidation threshold
+	/// 	shortfall = repayable_balance - liquidation_threshold * collateral_value
+	/// The borrow is healthy when the shortfall is zero. The whole debt of a fixed-term borrow
+	/// overdue past its grace period is shortfall.
+	pub fn shortfall(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let loan = Borrows::<T>::get((who, asset, collateral_asset))
+			.ok_or(Error::<T>::LoanDoesNotExists)?;
+		let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+		pool.update_indexes()?;
+
+		let repayable_balance =
+			Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+		// the whole debt of a fixed-term borrow overdue past its grace period is shortfall
+		if Self::is_overdue(who, asset, collateral_asset) {
+			return Ok(repayable_balance);
+		}
+		let collateral_value = Self::get_equivalent_asset_amount(
+			who,
+			asset,
+			collateral_asset,
+			loan.collateral_balance,
+		)?;
+		Ok(repayable_balance
+			.saturating_sub(pool.liquidation_threshold().mul_floor(collateral_value)))
+	}
+
+	/// Ensures that no borrow of `who` has a shortfall. Returns the number of borrows of `who`,
+	/// i.e. the markets visited.
+	pub fn ensure_no_shortfall(who: &T::AccountId) -> Result<u32, DispatchError> {
+		let borrows = AccountBorrows::<T>::get(who);
+		for (asset, collateral_asset) in borrows.iter().copied() {
+			ensure!(
+				Self::shortfall(who, asset, collateral_asset)?.is_zero(),
+				Error::<T>::AccountHasShortfall
+			);
+		}
+		Ok(borrows.len() as u32)
+	}
+
+	/// Liquidates the borrow of `who` with a shortfall: the `liquidator` repays the whole debt
+	/// and seizes all the collateral. With a `swap`, the debt is first bought with the collateral
+	/// on the `SwapProvider` and the liquidator seizes the rest. The swap is rolled back along
+	/// with the repayment if it fails. Returns the repaid balance.
+	pub fn do_liquidate_borrow(
+		liquidator: &T::AccountId,
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		swap: bool,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		with_storage_layer(|| {
+			let shortfall = Self::shortfall(who, asset, collateral_asset)?;
+			ensure!(!shortfall.is_zero(), Error::<T>::BorrowNotLiquidatable);
+			ensure!(!Self::stall_grace_period(), Error::<T>::StallGracePeriod);
+			ensure!(
+				Self::grace_period_over(who, asset, collateral_asset, shortfall)?,
+				Error::<T>::MarginCallGracePeriod
+			);
+			Self::settle_liquidation_assignment(liquidator, who, asset, collateral_asset)?;
+
+			let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+			pool.update_indexes()?;
+			let loan = Borrows::<T>::get((who, asset, collateral_asset))
+				.ok_or(Error::<T>::LoanDoesNotExists)?;
+			let repayable_balance =
+				Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+
+			if swap {
+				Self::swap_collateral_for_debt(
+					who,
+					asset,
+					collateral_asset,
+					&pool,
+					&loan,
+					repayable_balance,
+					liquidator,
+				)?;
+			}
+			Self::do_repay_on_behalf(
+				liquidator,
+				who,
+				asset,
+				repayable_balance,
+				collateral_asset,
+				Preservation::Expendable,
+			)?;
+			T::LendingHooks::on_liquidation(
+				liquidator,
+				who,
+				asset,
+				repayable_balance,
+				collateral_asset,
+			);
+			Ok(repayable_balance)
+		})
+	}
+
+	/// Settles the borrow of `who` with its own collateral, whatever its health: the debt is
+	/// bought with the collateral on the `SwapProvider` and repaid, and the rest of the
+	/// collateral goes back to `who`, so that no liquidation bonus is paid. Returns the repaid
+	/// balance and the collateral sold.
+	pub fn do_self_liquidate(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		with_storage_layer(|| {
+			let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+			pool.update_indexes()?;
+			let loan = Borrows::<T>::get((who, asset, collateral_asset))
+				.ok_or(Error::<T>::LoanDoesNotExists)?;
+			let repayable_balance =
+				Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+
+			let sold = Self::swap_collateral_for_debt(
+				who,
+				asset,
+				collateral_asset,
+				&pool,
+				&loan,
+				repayable_balance,
+				who,
+			)?;
+			Self::do_repay_on_behalf(
+				who,
+				who,
+				asset,
+				repayable_balance,
+				collateral_asset,
+				Preservation::Expendable,
+			)?;
+			Ok((repayable_balance, sold))
+		})
+	}
+
+	/// Buys `balance` of `asset` for `dest` with the collateral of the borrow of `who` on the
+	/// `SwapProvider`, and deducts the collateral sold from the borrow. Returns the collateral
+	/// sold.
+	fn swap_collateral_for_debt(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		pool: &LendingPool<T>,
+		loan: &UserBorrow<T>,
+		balance: AssetBalanceOf<T>,
+		dest: &T::AccountId,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		// the collateral is held by the pool account until the debt is repaid, the native
+		// currency by `who`: it is released for the swap and the rest is held again
+		let pool_account = Self::pool_account_id(pool.id);
+		let native = Self::is_native_asset(collateral_asset);
+		let holder = if native {
+			Self::release_collateral(
+				who,
+				collateral_asset,
+				&pool_account,
+				who,
+				loan.collateral_balance,
+			)?;
+			who
+		} else {
+			&pool_account
+		};
+		let sold = T::SwapProvider::swap_exact_out(
+			holder,
+			collateral_asset,
+			asset,
+			balance,
+			loan.collateral_balance,
+			dest,
+		)?;
+		if native {
+			Self::post_collateral(
+				who,
+				collateral_asset,
+				&pool_account,
+				loan.collateral_balance.saturating_sub(sold),
+				Preservation::Expendable,
+			)?;
+		}
+		Borrows::<T>::mutate((who, asset, collateral_asset), |loan| {
+			if let Some(loan) = loan {
+				loan.collateral_balance = loan.collateral_balance.saturating_sub(sold);
+			}
+		});
+		Ok(sold)
+	}
+
+	/// Settles the borrow of `who` at the oracle prices, whatever its health: the
+	/// `TreasuryAccount` repays the whole debt and keeps the collateral worth it, the rest of the
+	/// collateral goes back to `who`. The treasury bears the debt the collateral does not cover.
+	/// Returns the repaid balance and the seized collateral.
+	pub fn do_force_liquidate(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		with_storage_layer(|| {
+			let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+			pool.update_indexes()?;
+			let loan = Borrows::<T>::get((who, asset, collateral_asset))
+				.ok_or(Error::<T>::LoanDoesNotExists)?;
+			let repayable_balance =
+				Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+			let seized =
+				Self::get_equivalent_asset_amount(who, collateral_asset, asset, repayable_balance)?
+					.min(loan.collateral_balance);
+
+			// the treasury receives all the collateral with the full repayment
+			let treasury = T::TreasuryAccount::get();
+			Self::do_repay_on_behalf(
+				&treasury,
+				who,
+				asset,
+				repayable_balance,
+				collateral_asset,
+				Preservation::Expendable,
+			)?;
+			let remainder = loan.collateral_balance.saturating_sub(seized);
+			if !remainder.is_zero() {
+				Self::checkpoint_k_tokens(who, collateral_asset);
+				T::Fungibles::transfer(
+					collateral_asset,
+					&treasury,
+					who,
+					remainder,
+					Preservation::Expendable,
+				)?;
+			}
+			T::LendingHooks::on_liquidation(
+				&treasury,
+				who,
+				asset,
+				repayable_balance,
+				collateral_asset,
+			);
+			Ok((repayable_balance, seized))
+		})
+	}
+
+	/// Writes off the borrow of `who` with a shortfall and a debt below the `DustBalance`: its
+	/// principal is removed from the borrowed balance of the pool and its collateral goes to the
+	/// `TreasuryAccount`. Nothing is written off if any step fails. Returns the forgiven debt.
+	pub fn do_write_off_dust_debt(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		with_storage_layer(|| {
+			ensure!(
+				!Self::shortfall(who, asset, collateral_asset)?.is_zero(),
+				Error::<T>::BorrowNotLiquidatable
+			);
+
+			let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+			pool.update_indexes()?;
+			let loan = Borrows::<T>::take((who, asset, collateral_asset))
+				.ok_or(Error::<T>::LoanDoesNotExists)?;
+			RetiredBorrows::<T>::remove((who, asset, collateral_asset));
+			let repayable_balance =
+				Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+			ensure!(repayable_balance < T::DustBalance::get(), Error::<T>::DebtNotDust);
+			StableBorrows::<T>::remove((who, asset, collateral_asset));
+			BorrowMaturities::<T>::remove((who, asset, collateral_asset));
+
+			// loans opened before the principals were tracked owe their whole debt as principal
+			let principal = loan.principal.unwrap_or(repayable_balance).min(pool.borrowed_balance);
+			pool.borrowed_balance = pool.borrowed_balance.ensure_sub(principal)?;
+			let pool_account = Self::pool_account_id(pool.id);
+			Self::record_bad_debt(asset, repayable_balance);
+			Self::store_pool(pool);
+			AccountBorrows::<T>::mutate(who, |borrows| {
+				borrows.retain(|borrow| borrow != &(asset, collateral_asset))
+			});
+
+			// the pool account may hold nothing but this collateral
+			Self::checkpoint_k_tokens(&T::TreasuryAccount::get(), collateral_asset);
+			Self::release_collateral(
+				who,
+				collateral_asset,
+				&pool_account,
+				&T::TreasuryAccount::get(),
+				loan.collateral_balance,
+			)?;
+			Ok(repayable_balance)
+		})
+	}
+}
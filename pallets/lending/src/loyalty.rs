@@ -0,0 +1,57 @@
+use crate::*;
+
+/// A loyalty tier of the suppliers of a lending pool: the accounts supplying to it continuously
+/// for `min_duration` seconds at least accrue its rewards on their working balance increased by
+/// `bonus`
+#[derive(
+	Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default,
+)]
+pub struct LoyaltyTier {
+	pub min_duration: Timestamp,
+	pub bonus: Permill,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Sets the loyalty tiers of the lending pool of `asset`, ordered by their duration and
+	/// their bonus, both strictly increasing. No tiers turn the loyalty bonus off.
+	pub fn do_set_loyalty_tiers(
+		asset: AssetIdOf<T>,
+		tiers: BoundedVec<LoyaltyTier, T::MaxLoyaltyTiers>,
+	) -> DispatchResult {
+		ensure!(
+			LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+			Error::<T>::LendingPoolDoesNotExist
+		);
+		ensure!(
+			tiers.iter().all(|tier| tier.bonus <= T::MaxLoyaltyBonus::get()) &&
+				tiers.windows(2).all(|pair| {
+					pair[0].min_duration < pair[1].min_duration && pair[0].bonus < pair[1].bonus
+				}),
+			Error::<T>::InvalidLoyaltyTiers
+		);
+		LoyaltyTiers::<T>::insert(asset, tiers);
+		Ok(())
+	}
+
+	/// Returns the bonus of the highest loyalty tier `who` reached in the lending pool of
+	/// `asset`, by the time it has been supplying to it continuously
+	pub fn loyalty_bonus(who: &T::AccountId, asset: AssetIdOf<T>) -> Permill {
+		let Some(since) = SupplyingSince::<T>::get(who, asset) else { return Permill::zero() };
+		let duration = Self::now_in_seconds().saturating_sub(since);
+		LoyaltyTiers::<T>::get(asset)
+			.iter()
+			.rev()
+			.find(|tier| duration >= tier.min_duration)
+			.map_or(Permill::zero(), |tier| tier.bonus)
+	}
+
+	/// Tracks the continuous supply of `who` to the lending pool of `asset`, from its first LP
+	/// tokens `lp_id` until it holds none anymore
+	pub(crate) fn track_supply(who: &T::AccountId, asset: AssetIdOf<T>, lp_id: AssetIdOf<T>) {
+		if T::Fungibles::balance(lp_id, who).is_zero() {
+			SupplyingSince::<T>::remove(who, asset);
+		} else if !SupplyingSince::<T>::contains_key(who, asset) {
+			SupplyingSince::<T>::insert(who, asset, Self::now_in_seconds());
+		}
+	}
+}
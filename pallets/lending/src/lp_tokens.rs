@@ -0,0 +1,192 @@
+use crate::*;
+use frame_support::{
+	sp_std::vec::Vec,
+	traits::{
+		fungibles::Dust,
+		tokens::{DepositConsequence, Provenance, WithdrawConsequence},
+	},
+};
+use pallet_assets::FrozenBalance;
+
+/// The `Fungibles` of the pallet, moving the LP tokens the `Freezer` of the Assets Pallet keeps
+/// frozen otherwise. The LP tokens carry the reward and the collateral accounting of their
+/// holders along, so that they may only move through the pallet, e.g. by `transfer_lp_tokens`,
+/// and not through the Assets Pallet, another pallet or a call escaping the call filter of the
+/// runtime.
+///
+/// The Assets Pallet of the LP tokens must have this pallet as its `Freezer`.
+pub struct LpTokenGuard<T, Fungibles>(PhantomData<(T, Fungibles)>);
+
+impl<T: Config, Fungibles> LpTokenGuard<T, Fungibles> {
+	/// Runs `f` with the LP tokens unfrozen
+	fn unfrozen<R>(f: impl FnOnce() -> R) -> R {
+		if LpTokensUnfrozen::<T>::get() {
+			return f();
+		}
+		LpTokensUnfrozen::<T>::put(true);
+		let result = f();
+		LpTokensUnfrozen::<T>::kill();
+		result
+	}
+}
+
+impl<T, Fungibles> fungibles::Inspect<T::AccountId> for LpTokenGuard<T, Fungibles>
+where
+	T: Config,
+	Fungibles: fungibles::Inspect<T::AccountId>,
+{
+	type AssetId = Fungibles::AssetId;
+	type Balance = Fungibles::Balance;
+
+	fn total_issuance(asset: Self::AssetId) -> Self::Balance {
+		Fungibles::total_issuance(asset)
+	}
+	fn active_issuance(asset: Self::AssetId) -> Self::Balance {
+		Fungibles::active_issuance(asset)
+	}
+	fn minimum_balance(asset: Self::AssetId) -> Self::Balance {
+		Fungibles::minimum_balance(asset)
+	}
+	fn total_balance(asset: Self::AssetId, who: &T::AccountId) -> Self::Balance {
+		Fungibles::total_balance(asset, who)
+	}
+	fn balance(asset: Self::AssetId, who: &T::AccountId) -> Self::Balance {
+		Fungibles::balance(asset, who)
+	}
+	fn reducible_balance(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		preservation: Preservation,
+		force: Fortitude,
+	) -> Self::Balance {
+		Self::unfrozen(|| Fungibles::reducible_balance(asset, who, preservation, force))
+	}
+	fn can_deposit(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+		provenance: Provenance,
+	) -> DepositConsequence {
+		Fungibles::can_deposit(asset, who, amount, provenance)
+	}
+	fn can_withdraw(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> WithdrawConsequence<Self::Balance> {
+		Self::unfrozen(|| Fungibles::can_withdraw(asset, who, amount))
+	}
+	fn asset_exists(asset: Self::AssetId) -> bool {
+		Fungibles::asset_exists(asset)
+	}
+}
+
+impl<T, Fungibles> fungibles::Unbalanced<T::AccountId> for LpTokenGuard<T, Fungibles>
+where
+	T: Config,
+	Fungibles: fungibles::Unbalanced<T::AccountId>,
+{
+	fn handle_dust(dust: Dust<T::AccountId, Self>) {
+		Fungibles::handle_dust(Dust(dust.0, dust.1))
+	}
+	fn write_balance(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> Result<Option<Self::Balance>, DispatchError> {
+		Fungibles::write_balance(asset, who, amount)
+	}
+	fn set_total_issuance(asset: Self::AssetId, amount: Self::Balance) {
+		Fungibles::set_total_issuance(asset, amount)
+	}
+}
+
+impl<T, Fungibles> fungibles::Mutate<T::AccountId> for LpTokenGuard<T, Fungibles>
+where
+	T: Config,
+	Fungibles: fungibles::Mutate<T::AccountId>,
+{
+	fn mint_into(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError> {
+		Fungibles::mint_into(asset, who, amount)
+	}
+	fn burn_from(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+		precision: Precision,
+		force: Fortitude,
+	) -> Result<Self::Balance, DispatchError> {
+		Self::unfrozen(|| Fungibles::burn_from(asset, who, amount, precision, force))
+	}
+	fn shelve(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError> {
+		Self::unfrozen(|| Fungibles::shelve(asset, who, amount))
+	}
+	fn restore(
+		asset: Self::AssetId,
+		who: &T::AccountId,
+		amount: Self::Balance,
+	) -> Result<Self::Balance, DispatchError> {
+		Fungibles::restore(asset, who, amount)
+	}
+	fn transfer(
+		asset: Self::AssetId,
+		source: &T::AccountId,
+		dest: &T::AccountId,
+		amount: Self::Balance,
+		preservation: Preservation,
+	) -> Result<Self::Balance, DispatchError> {
+		Self::unfrozen(|| Fungibles::transfer(asset, source, dest, amount, preservation))
+	}
+}
+
+impl<T, Fungibles> fungibles::Create<T::AccountId> for LpTokenGuard<T, Fungibles>
+where
+	T: Config,
+	Fungibles: fungibles::Create<T::AccountId>,
+{
+	fn create(
+		asset: Self::AssetId,
+		admin: T::AccountId,
+		is_sufficient: bool,
+		min_balance: Self::Balance,
+	) -> DispatchResult {
+		Fungibles::create(asset, admin, is_sufficient, min_balance)
+	}
+}
+
+impl<T, Fungibles> fungibles::metadata::Inspect<T::AccountId> for LpTokenGuard<T, Fungibles>
+where
+	T: Config,
+	Fungibles: fungibles::metadata::Inspect<T::AccountId>,
+{
+	fn name(asset: Self::AssetId) -> Vec<u8> {
+		Fungibles::name(asset)
+	}
+	fn symbol(asset: Self::AssetId) -> Vec<u8> {
+		Fungibles::symbol(asset)
+	}
+	fn decimals(asset: Self::AssetId) -> u8 {
+		Fungibles::decimals(asset)
+	}
+}
+
+/// Freezes all the LP tokens of the lending pools held by an account, but while they are moved
+/// by the `LpTokenGuard`
+impl<T: Config> FrozenBalance<AssetIdOf<T>, T::AccountId, AssetBalanceOf<T>> for Pallet<T> {
+	fn frozen_balance(asset: AssetIdOf<T>, who: &T::AccountId) -> Option<AssetBalanceOf<T>> {
+		if LpTokensUnfrozen::<T>::get() || !LpTokenPools::<T>::contains_key(asset) {
+			return None;
+		}
+		Some(T::Fungibles::balance(asset, who))
+	}
+
+	fn died(_asset: AssetIdOf<T>, _who: &T::AccountId) {}
+}
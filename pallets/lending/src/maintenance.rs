@@ -0,0 +1,24 @@
+use crate::*;
+use frame_support::traits::Contains;
+
+/// Lets the calls of the lending pallet through unless the `MaintenanceMode` is on, in which
+/// case only the repayments, including the self-liquidations, and the call lifting the
+/// maintenance mode, go through, also when dispatched by an operator. Meant to be
+/// plugged into the `BaseCallFilter` of the runtime for the calls of the pallet.
+pub struct MaintenanceFilter<T>(PhantomData<T>);
+
+impl<T: Config> Contains<Call<T>> for MaintenanceFilter<T> {
+	fn contains(call: &Call<T>) -> bool {
+		if let Call::operate { call, .. } = call {
+			return Self::contains(call);
+		}
+		!MaintenanceMode::<T>::get() ||
+			matches!(
+				call,
+				Call::repay { .. } |
+					Call::repay_many { .. } |
+					Call::self_liquidate { .. } |
+					Call::set_maintenance_mode { .. }
+			)
+	}
+}
@@ -0,0 +1,80 @@
+use crate::*;
+
+impl<T: Config> Pallet<T> {
+	/// Issues the margin call of the borrow of `who` with a shortfall, starting the
+	/// `MarginCallGracePeriod` before it can be liquidated. Returns the shortfall and the last
+	/// block of the grace period.
+	pub fn do_margin_call(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<(AssetBalanceOf<T>, BlockNumberFor<T>), DispatchError> {
+		let shortfall = Self::shortfall(who, asset, collateral_asset)?;
+		ensure!(!shortfall.is_zero(), Error::<T>::BorrowNotLiquidatable);
+		let key = (who.clone(), asset, collateral_asset);
+		ensure!(!MarginCalls::<T>::contains_key(&key), Error::<T>::MarginCallAlreadyIssued);
+
+		let now = frame_system::Pallet::<T>::block_number();
+		MarginCalls::<T>::insert(&key, now);
+		Ok((shortfall, now.saturating_add(T::MarginCallGracePeriod::get())))
+	}
+
+	/// Returns whether the borrow of `who` with `shortfall` can be liquidated by a third party:
+	/// its margin call was issued and its grace period is over, or the shortfall exceeds the
+	/// `MarginCallSeverity` of the debt
+	pub fn grace_period_over(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		shortfall: AssetBalanceOf<T>,
+	) -> Result<bool, DispatchError> {
+		let grace_period = T::MarginCallGracePeriod::get();
+		if grace_period.is_zero() {
+			return Ok(true);
+		}
+		if let Some(called_at) = MarginCalls::<T>::get((who, asset, collateral_asset)) {
+			if frame_system::Pallet::<T>::block_number() > called_at.saturating_add(grace_period) {
+				return Ok(true);
+			}
+		}
+
+		let loan = Borrows::<T>::get((who, asset, collateral_asset))
+			.ok_or(Error::<T>::LoanDoesNotExists)?;
+		let mut pool = Self::borrow_pool(who, asset, collateral_asset)?;
+		pool.update_indexes()?;
+		let repayable_balance =
+			Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+		Ok(shortfall > T::MarginCallSeverity::get().mul_floor(repayable_balance))
+	}
+
+	/// Adds `balance` of the collateral asset to the borrow of `who`, clearing its margin call
+	/// once the borrow is healthy
+	pub fn do_add_collateral(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+		let key = (who.clone(), asset, collateral_asset);
+		let mut loan = Borrows::<T>::get(&key).ok_or(Error::<T>::LoanDoesNotExists)?;
+		let pool = Self::borrow_pool(who, asset, collateral_asset)?;
+
+		Self::ensure_k_tokens_unfrozen(who, collateral_asset, balance)?;
+		Self::checkpoint_k_tokens(who, collateral_asset);
+		Self::post_collateral(
+			who,
+			collateral_asset,
+			&Self::pool_account_id(pool.id),
+			balance,
+			Preservation::Preserve,
+		)?;
+		loan.collateral_balance = loan.collateral_balance.ensure_add(balance)?;
+		Borrows::<T>::insert(&key, loan);
+
+		if Self::shortfall(who, asset, collateral_asset)?.is_zero() {
+			MarginCalls::<T>::remove(&key);
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,220 @@
+use crate::*;
+use frame_support::storage::with_storage_layer;
+
+/// The money market as seen by the other pallets of Kylix, e.g. the stablecoin or the vaults,
+/// which can supply, borrow and repay on behalf of their accounts without dispatching the
+/// extrinsics. The operations emit the events of the matching extrinsics, and are rolled back
+/// as a whole when they fail, whether the caller dispatches them or not.
+pub trait Lending<AccountId, AssetId, Balance> {
+	/// Supplies `balance` of `asset` of `who` to its lending pool, minting LP tokens to `who`.
+	fn supply(who: &AccountId, asset: AssetId, balance: Balance) -> DispatchResult;
+
+	/// Withdraws up to `balance` of `asset` of `who` from its lending pool, burning LP tokens of
+	/// `who`. The balance is capped to what `who` may redeem and to the reserves of the pool.
+	/// Returns the balance withdrawn.
+	fn withdraw(
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+	) -> Result<Balance, DispatchError>;
+
+	/// Borrows `balance` of `asset` for `who` against `collateral_balance` of `collateral_asset`.
+	/// Returns the origination fee withheld from the borrowed balance.
+	fn borrow(
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+		collateral_balance: Balance,
+	) -> Result<Balance, DispatchError>;
+
+	/// Repays up to `balance` of the borrow of `who` in `asset` against `collateral_asset` with
+	/// the assets of `payer`, who receives the collateral released.
+	fn repay_for(
+		payer: &AccountId,
+		who: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+		collateral_asset: AssetId,
+	) -> DispatchResult;
+
+	/// Returns the value in `asset` of the collateral of the borrow of `who` in `asset` against
+	/// `collateral_asset`.
+	fn collateral_value(
+		who: &AccountId,
+		asset: AssetId,
+		collateral_asset: AssetId,
+	) -> Result<Balance, DispatchError>;
+
+	/// Returns the LP token of the lending pool of `asset`, if any.
+	fn lp_token(asset: AssetId) -> Option<AssetId>;
+
+	/// Returns the balance of `asset` that `lp_tokens` of its lending pool redeem for, interest
+	/// accrued until now.
+	fn redeemable_balance(asset: AssetId, lp_tokens: Balance) -> Result<Balance, DispatchError>;
+
+	/// Transfers `balance` of the LP tokens of the lending pool of `asset` of `who` to `dest`,
+	/// along with their rewards accounting. The LP tokens may not move otherwise.
+	fn transfer_lp_tokens(
+		who: &AccountId,
+		dest: &AccountId,
+		asset: AssetId,
+		balance: Balance,
+	) -> DispatchResult;
+
+	/// Returns the value in `quote` of `balance` of `asset`, at the prices of the money market.
+	fn equivalent_balance(
+		asset: AssetId,
+		balance: Balance,
+		quote: AssetId,
+	) -> Result<Balance, DispatchError>;
+}
+
+impl<T: Config> Lending<T::AccountId, AssetIdOf<T>, AssetBalanceOf<T>> for Pallet<T> {
+	fn supply(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		with_storage_layer(|| {
+			let minted = Self::do_supply(who, asset, balance)?;
+			Self::deposit_supplied_event(who.clone(), asset, balance, minted);
+			Ok(())
+		})
+	}
+
+	fn withdraw(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		with_storage_layer(|| {
+			Self::ensure_no_shortfall(who)?;
+			let (balance, burned) =
+				Self::do_withdrawal(who, asset, balance, Precision::BestEffort)?;
+			Self::deposit_withdrawn_event(who.clone(), asset, balance, burned);
+			Ok(balance)
+		})
+	}
+
+	fn borrow(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		collateral_balance: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		with_storage_layer(|| {
+			Self::ensure_no_shortfall(who)?;
+			let fee =
+				Self::do_borrow(who, asset, balance, collateral_asset, collateral_balance, false)?;
+			Self::deposit_borrowed_event(who.clone(), asset, collateral_asset, balance, fee);
+			Ok(fee)
+		})
+	}
+
+	fn repay_for(
+		payer: &T::AccountId,
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> DispatchResult {
+		with_storage_layer(|| {
+			Self::do_repay_on_behalf(
+				payer,
+				who,
+				asset,
+				balance,
+				collateral_asset,
+				Preservation::Preserve,
+			)?;
+			Self::deposit_repaid_event(who.clone(), asset, collateral_asset, balance);
+			Ok(())
+		})
+	}
+
+	fn collateral_value(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let loan = Borrows::<T>::get((who, asset, collateral_asset))
+			.ok_or(Error::<T>::LoanDoesNotExists)?;
+		Self::get_equivalent_asset_amount(who, asset, collateral_asset, loan.collateral_balance)
+	}
+	fn lp_token(asset: AssetIdOf<T>) -> Option<AssetIdOf<T>> {
+		LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset)).map(|pool| pool.id)
+	}
+
+	fn redeemable_balance(
+		asset: AssetIdOf<T>,
+		lp_tokens: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+		Ok(pool.accrued_deposit(lp_tokens)?)
+	}
+
+	fn transfer_lp_tokens(
+		who: &T::AccountId,
+		dest: &T::AccountId,
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		with_storage_layer(|| {
+			Self::do_transfer_lp_tokens(who, dest, asset, balance)?;
+			Self::deposit_event(Event::LPTokenTransferred {
+				who: who.clone(),
+				dest: dest.clone(),
+				balance,
+			});
+			Ok(())
+		})
+	}
+
+	fn equivalent_balance(
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+		quote: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		Self::get_equivalent_asset_amount(&Self::account_id(), quote, asset, balance)
+	}
+}
+
+/// Sets the money market up for the benchmarks of the pallets built on the `Lending` trait.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait LendingBenchmarkHelper<AssetId> {
+	/// Creates `asset`, if needed.
+	fn create_asset(asset: AssetId);
+
+	/// Creates an active lending pool of `asset` whose LP token is `lp_token`, supplied by an
+	/// account of its own. `asset` is created if needed.
+	fn create_pool(lp_token: AssetId, asset: AssetId);
+
+	/// Sets the price of `asset` in `quote`.
+	fn set_price(asset: AssetId, quote: AssetId, price: Rate);
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<T: Config> LendingBenchmarkHelper<AssetIdOf<T>> for Pallet<T> {
+	fn create_asset(asset: AssetIdOf<T>) {
+		if !T::Fungibles::asset_exists(asset) {
+			T::Fungibles::create(asset, Self::account_id(), true, One::one()).unwrap();
+		}
+	}
+
+	fn create_pool(lp_token: AssetIdOf<T>, asset: AssetIdOf<T>) {
+		let creator: T::AccountId = frame_benchmarking::account("creator", 0, 0);
+		let balance = T::MinPoolLiquidity::get().saturating_mul(1_000u32.into());
+		Self::create_asset(asset);
+		T::Fungibles::mint_into(asset, &creator, balance.saturating_mul(2u32.into())).unwrap();
+		Self::do_create_lending_pool(&creator, lp_token, asset, balance).unwrap();
+		Self::do_activate_lending_pool(asset).unwrap();
+	}
+
+	fn set_price(asset: AssetIdOf<T>, quote: AssetIdOf<T>, price: Rate) {
+		AssetPrices::<T>::insert((asset, quote), price);
+	}
+}
@@ -0,0 +1,187 @@
+//! Fixed-point math of the lending pallet.
+//!
+//! Balances are unsigned integers of at most 128 bits, rates, indexes and prices are [`Rate`]s,
+//! i.e. `FixedU128` with 18 decimals. The products of a balance with a rate are done with a
+//! `U256` intermediate, so that they neither overflow before the division nor lose the decimals
+//! of the rate. The helpers return `None` on overflow or division by zero, but for the
+//! compounded interest which saturates.
+//!
+//! The balances are rounded in favour of the protocol, so that repeating operations on dust
+//! balances can not drain a pool:
+//! - the LP tokens minted on supply are rounded down, the ones burnt on withdrawal up
+//! - the debt recorded on borrow and the repayable debt are rounded up, the debt cleared by a
+//!   repayment down, and the interest share of a repayment up
+//! - the value of a collateral, the eligible borrow and the collateral released are rounded down
+//! - the accrued deposits and rewards are rounded down
+//!
+//! The rates are rounded down.
+//!
+//! The prices are quoted for whole units of the assets. The balances of assets with different
+//! decimals are normalized to a common 18-decimal base when they are converted into each other,
+//! so that a 6-decimal stablecoin is valued correctly against a 12-decimal native asset.
+
+use crate::{Rate, SECONDS_PER_YEAR};
+use frame_support::sp_runtime::{
+	traits::{AtLeast32BitUnsigned, CheckedAdd, CheckedMul, One, Saturating},
+	FixedPointNumber, Rounding,
+};
+use sp_core::U256;
+
+/// Returns `a * b / c`, rounded in the `rounding` direction
+pub fn mul_div<B: AtLeast32BitUnsigned>(a: B, b: B, c: B, rounding: Rounding) -> Option<B> {
+	let (a, b, c): (u128, u128, u128) =
+		(a.try_into().ok()?, b.try_into().ok()?, c.try_into().ok()?);
+	let quotient = mul_div_u256(U256::from(a), U256::from(b), U256::from(c), rounding)?;
+	u128::try_from(quotient).ok()?.try_into().ok()
+}
+
+/// Returns the balance of an asset with `to_decimals` worth `balance` of an asset with
+/// `decimals`, given the price of a whole unit of the latter in whole units of the former as the
+/// ratio of `price` to `quote`, rounded in the `rounding` direction
+/// 	normalize(balance, decimals) * price / quote, denormalized to `to_decimals`
+pub fn convert<B: AtLeast32BitUnsigned>(
+	balance: B,
+	decimals: u8,
+	price: Rate,
+	quote: Rate,
+	to_decimals: u8,
+	rounding: Rounding,
+) -> Option<B> {
+	let balance: u128 = balance.try_into().ok()?;
+	// the normalization to 18 decimals and back simplify to a single scale of the balance
+	let numerator = U256::from(price.into_inner()).checked_mul(scale(to_decimals)?)?;
+	let denominator = U256::from(quote.into_inner()).checked_mul(scale(decimals)?)?;
+	let converted = mul_div_u256(U256::from(balance), numerator, denominator, rounding)?;
+	u128::try_from(converted).ok()?.try_into().ok()
+}
+
+/// Returns `a * b / c` computed on 256 bits, rounded in the `rounding` direction
+fn mul_div_u256(a: U256, b: U256, c: U256, rounding: Rounding) -> Option<U256> {
+	if c.is_zero() {
+		return None;
+	}
+	let (quotient, remainder) = a.checked_mul(b)?.div_mod(c);
+	let round_up = match rounding {
+		Rounding::Down => false,
+		Rounding::Up => !remainder.is_zero(),
+		Rounding::NearestPrefDown => remainder > c - remainder,
+		Rounding::NearestPrefUp => remainder >= c - remainder,
+	};
+	if round_up {
+		quotient.checked_add(U256::one())
+	} else {
+		Some(quotient)
+	}
+}
+
+/// Returns `10^decimals`, the raw balance of a whole unit of an asset with `decimals`
+fn scale(decimals: u8) -> Option<U256> {
+	U256::from(10).checked_pow(U256::from(decimals))
+}
+
+/// Returns `balance * rate`, rounded in the `rounding` direction
+pub fn mul_rate<B: AtLeast32BitUnsigned>(balance: B, rate: Rate, rounding: Rounding) -> Option<B> {
+	mul_div(balance.try_into().ok()?, rate.into_inner(), Rate::DIV, rounding)?
+		.try_into()
+		.ok()
+}
+
+/// Returns `balance / rate`, rounded in the `rounding` direction
+pub fn div_rate<B: AtLeast32BitUnsigned>(balance: B, rate: Rate, rounding: Rounding) -> Option<B> {
+	mul_div(balance.try_into().ok()?, Rate::DIV, rate.into_inner(), rounding)?
+		.try_into()
+		.ok()
+}
+
+/// Returns the rate `a / b`
+pub fn ratio<B: AtLeast32BitUnsigned>(a: B, b: B) -> Option<Rate> {
+	mul_div(a.try_into().ok()?, Rate::DIV, b.try_into().ok()?, Rounding::Down).map(Rate::from_inner)
+}
+
+/// Returns the fair value of a constant product pool whose reserves are worth `value1` and
+/// `value2`, rounded down
+/// 	2 * sqrt(value1 * value2)
+pub fn fair_lp_value<B: AtLeast32BitUnsigned>(value1: B, value2: B) -> Option<B> {
+	let (value1, value2): (u128, u128) = (value1.try_into().ok()?, value2.try_into().ok()?);
+	let root = U256::from(value1).checked_mul(U256::from(value2))?.integer_sqrt();
+	u128::try_from(root.checked_mul(U256::from(2))?).ok()?.try_into().ok()
+}
+
+/// Returns the interest factor of the annual `rate` accrued linearly over `seconds`
+/// 	1 + rate * seconds / SECONDS_PER_YEAR
+pub fn linear_interest(rate: Rate, seconds: u64) -> Option<Rate> {
+	Rate::one().checked_add(&exposure(rate, seconds)?)
+}
+
+/// The longest period compounded at once by the binomial expansion of `compounded_interest`, a
+/// day, over which the terms it neglects are negligible for any rate of a lending pool
+pub const COMPOUNDING_PERIOD: u64 = 24 * 60 * 60;
+
+/// Returns the interest factor of the annual `rate` compounded every second over `seconds`. The
+/// longer periods are compounded day by day, so that the interest stays exact however long the
+/// pool was left untouched
+/// 	(1 + r)^t = ((1 + r)^day)^(t / day) * (1 + r)^(t % day)
+/// The factor saturates at the largest `Rate` rather than overflow over the longest periods.
+pub fn compounded_interest(rate: Rate, seconds: u64) -> Option<Rate> {
+	let day = binomial_interest(rate, COMPOUNDING_PERIOD)?;
+	Some(
+		saturating_pow(day, seconds / COMPOUNDING_PERIOD)
+			.saturating_mul(binomial_interest(rate, seconds % COMPOUNDING_PERIOD)?),
+	)
+}
+
+/// Returns the interest factor of the annual `rate` compounded every second over `seconds`,
+/// approximated by the binomial expansion to the third term
+/// 	(1 + r)^t ≈ 1 + rt + t/2 * (t - 1) * r^2 + t/6 * (t - 1) * (t - 2) * r^3
+/// with r = rate / SECONDS_PER_YEAR. The terms are computed from the exposure x = rt as
+/// 	1 + x + x^2 * (t - 1) / 2t + x^3 * (t - 1) * (t - 2) / 6t^2
+/// since a rate per second has too few significant digits in 18 decimals to be squared.
+fn binomial_interest(rate: Rate, seconds: u64) -> Option<Rate> {
+	if seconds == 0 {
+		return Some(Rate::one());
+	}
+	let t = seconds as u128;
+	// the terms with t - 1 and t - 2 are zero for t < 2
+	let t_minus_one = t.saturating_sub(1);
+	let t_minus_two = t.saturating_sub(2);
+
+	let x = exposure(rate, seconds)?;
+	let x_square = x.checked_mul(&x)?;
+	let x_cube = x_square.checked_mul(&x)?;
+
+	let second_term =
+		mul_div(x_square.into_inner(), t_minus_one, t.checked_mul(2)?, Rounding::Down)?;
+	let third_term = mul_div(
+		mul_div(x_cube.into_inner(), t_minus_one, t.checked_mul(2)?, Rounding::Down)?,
+		t_minus_two,
+		t.checked_mul(3)?,
+		Rounding::Down,
+	)?;
+
+	Rate::one()
+		.checked_add(&x)?
+		.checked_add(&Rate::from_inner(second_term))?
+		.checked_add(&Rate::from_inner(third_term))
+}
+
+/// Returns `base^exp` by squaring, saturating at the largest `Rate`
+fn saturating_pow(mut base: Rate, mut exp: u64) -> Rate {
+	let mut result = Rate::one();
+	while exp > 0 {
+		if exp & 1 == 1 {
+			result = result.saturating_mul(base);
+		}
+		exp >>= 1;
+		if exp > 0 {
+			base = base.saturating_mul(base);
+		}
+	}
+	result
+}
+
+/// Returns the share of the annual `rate` accrued over `seconds`
+/// 	rate * seconds / SECONDS_PER_YEAR
+fn exposure(rate: Rate, seconds: u64) -> Option<Rate> {
+	mul_div(rate.into_inner(), seconds as u128, SECONDS_PER_YEAR as u128, Rounding::Down)
+		.map(Rate::from_inner)
+}
@@ -0,0 +1,414 @@
+//! Storage migrations of the lending pallet.
+
+use crate::*;
+use frame_support::{
+	storage_alias,
+	traits::{Contains, GetStorageVersion, OnRuntimeUpgrade},
+};
+use sp_runtime::Perbill;
+
+/// The share of the maximum weight of a block the lazy migration of the `MigrationCursor` takes
+/// up in `on_initialize`
+pub const MIGRATION_BLOCK_SHARE: Perbill = Perbill::from_percent(20);
+
+/// The progress of the lazy migration of the positions, kept in the `MigrationCursor`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub enum MigrationStage<T: Config> {
+	/// Translating the borrows and moving their collateral, after the given one
+	Borrows(Option<(AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>)>),
+	/// Moving the balances of the lending pools, after the pool of the given asset
+	Pools(Option<AssetIdOf<T>>),
+}
+
+/// Lets the calls of the lending pallet through once the lazy migration of the
+/// `MigrationCursor` is done: the positions it has not reached yet can not be read. Meant to be
+/// plugged into the `BaseCallFilter` of the runtime for the calls of the pallet.
+pub struct MigrationFilter<T>(PhantomData<T>);
+
+impl<T: Config> Contains<Call<T>> for MigrationFilter<T> {
+	fn contains(_call: &Call<T>) -> bool {
+		!MigrationCursor::<T>::exists()
+	}
+}
+
+pub mod v1 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use frame_support::sp_std::vec::Vec;
+
+	/// The lending pool of the V0 layout, without its origination fee and creation timestamp
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct LendingPoolV0<T: Config> {
+		pub id: AssetIdOf<T>,
+		pub lend_token_id: AssetIdOf<T>,
+		pub reserve_balance: AssetBalanceOf<T>,
+		pub borrowed_balance: AssetBalanceOf<T>,
+		pub activated: bool,
+		pub interest_model: InterestRateModel,
+		pub reserve_factor: Ratio,
+		pub exchange_rate: Ratio,
+		pub collateral_factor: Ratio,
+		pub liquidation_threshold: Ratio,
+		pub borrow_rate: Ratio,
+		pub supply_rate: Ratio,
+		pub last_accrued_interest_at: Timestamp,
+		pub borrow_index: Rate,
+		pub supply_index: Rate,
+	}
+
+	/// The origination fees of the V0 layout, a field of the lending pools since V1
+	#[storage_alias]
+	pub type BorrowFees<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, AssetIdOf<T>, Ratio, ValueQuery>;
+
+	/// The borrow of the V0 layout, its principal kept in a map of its own
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub struct UserBorrowV0<T: Config> {
+		pub borrowed_asset: AssetIdOf<T>,
+		pub borrowed_balance: AssetBalanceOf<T>,
+		pub collateral_asset: AssetIdOf<T>,
+		pub collateral_balance: AssetBalanceOf<T>,
+	}
+
+	/// The borrows of the V0 layout
+	#[storage_alias]
+	pub type Borrows<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		(AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>),
+		UserBorrowV0<T>,
+	>;
+
+	/// The principals of the borrows of the V0 layout, a field of the borrows since V1
+	#[storage_alias]
+	pub type BorrowPrincipals<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		(AccountOf<T>, AssetIdOf<T>, AssetIdOf<T>),
+		AssetBalanceOf<T>,
+	>;
+
+	/// Migrates the storage from the V0 layout to V1, only if the on-chain storage version is
+	/// V0. This is the single migration of the pallet from the layout deployed before its
+	/// storage was versioned to the current one.
+	///
+	/// The lending pools are translated in the upgrade block, moving in their origination fees,
+	/// and indexed by LP token; the pools created before V1 get the time of the migration as
+	/// creation timestamp, and their current utilisation as average utilisation. The
+	/// `PoolTotals` and `ProtocolStats` are seeded from the translated pools, and the counter of
+	/// `LendingPoolStorage` is initialised. The pools beyond `MaxPools`, if any, are kept but no
+	/// pool can be created until some are gone. There
+	/// may be millions of borrows, so they are translated and indexed by account lazily from the
+	/// next block on, within `MIGRATION_BLOCK_SHARE` of each block, the `MigrationCursor` holding
+	/// the key of the last translated one. The collateral of each borrow is moved from the pallet
+	/// account into the account of the pool the borrow is from; then what is left of the asset
+	/// of each pool in the pallet account, i.e. its reserves and referral earnings, is moved into
+	/// the account of the pool. The storage version is set in the upgrade block, the
+	/// `MigrationCursor` telling whether the lazy part is done. Meanwhile the calls of the pallet
+	/// are blocked by the `MigrationFilter` and its hooks are idle.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> MigrateToV1<T> {
+		/// The weight of migrating one entry in a step of the lazy migration, dominated by the
+		/// transfer of its balance into the account of its pool
+		pub fn entry_weight() -> Weight {
+			T::DbWeight::get().reads_writes(8, 8)
+		}
+
+		/// Migrates the entries after the `MigrationCursor` as long as their weight fits in
+		/// `limit`, clearing the cursor once all are migrated. Returns the weight consumed.
+		pub fn step(limit: Weight) -> Weight {
+			let Some(mut stage) = MigrationCursor::<T>::get() else {
+				return T::DbWeight::get().reads(1);
+			};
+
+			let mut consumed = Weight::zero();
+			while consumed.saturating_add(Self::entry_weight()).all_lte(limit) {
+				consumed.saturating_accrue(Self::entry_weight());
+				match Self::migrate_next(stage) {
+					Some(next) => stage = next,
+					None => {
+						MigrationCursor::<T>::kill();
+						log::info!(target: "runtime::lending", "migrated the positions to V1");
+						return consumed.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+					},
+				}
+			}
+			MigrationCursor::<T>::put(stage);
+			consumed.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+		}
+
+		// Migrates the entry following `stage`, returning the stage reached, `None` once all the
+		// entries are migrated
+		fn migrate_next(stage: MigrationStage<T>) -> Option<MigrationStage<T>> {
+			match stage {
+				// translated borrows keep their key, so the borrows after the cursor are the
+				// ones still in the V0 layout
+				MigrationStage::Borrows(last) => {
+					let mut borrows = match last {
+						Some(key) => Borrows::<T>::iter_from(Borrows::<T>::hashed_key_for(key)),
+						None => Borrows::<T>::iter(),
+					};
+					let Some((key, old)) = borrows.next() else {
+						return Self::migrate_next(MigrationStage::Pools(None));
+					};
+					if AccountBorrows::<T>::try_append(&key.0, (key.1, key.2)).is_err() {
+						log::warn!(
+							target: "runtime::lending",
+							"the borrows of an account exceed MaxMarketsPerAccount",
+						);
+					}
+					match LendingPoolStorage::<T>::get(AssetPool::<T>::from(old.borrowed_asset)) {
+						Some(pool) => Self::move_into_pool(
+							pool.id,
+							old.collateral_asset,
+							old.collateral_balance,
+						),
+						None => log::warn!(
+							target: "runtime::lending",
+							"a borrow has no lending pool, its collateral is not moved",
+						),
+					}
+					crate::Borrows::<T>::insert(
+						&key,
+						UserBorrow {
+							borrowed_asset: old.borrowed_asset,
+							borrowed_balance: old.borrowed_balance,
+							collateral_asset: old.collateral_asset,
+							collateral_balance: old.collateral_balance,
+							principal: BorrowPrincipals::<T>::take(&key),
+						},
+					);
+					Some(MigrationStage::Borrows(Some(key)))
+				},
+				// the collaterals are moved, what is left of the asset of a pool is its own
+				MigrationStage::Pools(last) => {
+					let mut pools = match last {
+						Some(asset) => LendingPoolStorage::<T>::iter_from(
+							LendingPoolStorage::<T>::hashed_key_for(AssetPool::<T>::from(asset)),
+						),
+						None => LendingPoolStorage::<T>::iter(),
+					};
+					let (_, pool) = pools.next()?;
+					let asset = pool.lend_token_id;
+					let balance = T::Fungibles::balance(asset, &Pallet::<T>::account_id());
+					Self::move_into_pool(pool.id, asset, balance);
+					Some(MigrationStage::Pools(Some(asset)))
+				},
+			}
+		}
+
+		// Moves `balance` of `asset` from the pallet account into the account of the pool `id`
+		fn move_into_pool(id: AssetIdOf<T>, asset: AssetIdOf<T>, balance: AssetBalanceOf<T>) {
+			if balance.is_zero() {
+				return;
+			}
+			let pool_account = Pallet::<T>::pool_account_id(id);
+			Pallet::<T>::provide_for(&pool_account);
+			if T::Fungibles::transfer(
+				asset,
+				&Pallet::<T>::account_id(),
+				&pool_account,
+				balance,
+				Preservation::Expendable,
+			)
+			.is_err()
+			{
+				log::warn!(
+					target: "runtime::lending",
+					"a balance can not be moved into the account of pool {:?}",
+					id
+				);
+			}
+		}
+	}
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let on_chain = Pallet::<T>::on_chain_storage_version();
+			if on_chain != 0 {
+				log::warn!(
+					target: "runtime::lending",
+					"skipping the migration to V1, the storage is at {:?}",
+					on_chain
+				);
+				return T::DbWeight::get().reads(1);
+			}
+
+			let now = Pallet::<T>::now_in_seconds();
+			let mut translated = 0u64;
+			LendingPoolStorage::<T>::translate::<LendingPoolV0<T>, _>(|_, old| {
+				translated += 1;
+				LpTokenPools::<T>::insert(old.id, old.lend_token_id);
+				let mut pool = LendingPool {
+					id: old.id,
+					lend_token_id: old.lend_token_id,
+					reserve_balance: old.reserve_balance,
+					borrowed_balance: old.borrowed_balance,
+					activated: old.activated,
+					interest_model: old.interest_model,
+					reserve_factor: old.reserve_factor,
+					exchange_rate: old.exchange_rate,
+					collateral_factor: old.collateral_factor,
+					liquidation_threshold: old.liquidation_threshold,
+					borrow_rate: old.borrow_rate,
+					supply_rate: old.supply_rate,
+					last_accrued_interest_at: old.last_accrued_interest_at,
+					borrow_index: old.borrow_index,
+					supply_index: old.supply_index,
+					borrow_fee: BorrowFees::<T>::take(old.lend_token_id),
+					created_at: now,
+					average_utilisation: Ratio::zero(),
+				};
+				pool.average_utilisation = pool.utilisation_ratio().unwrap_or_default();
+				Some(pool)
+			});
+			LendingPoolStorage::<T>::initialize_counter();
+			for pool in LendingPoolStorage::<T>::iter_values() {
+				Pallet::<T>::update_pool_totals(&pool);
+			}
+			MigrationCursor::<T>::put(MigrationStage::Borrows(None));
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			log::info!(
+				target: "runtime::lending",
+				"migrated {} lending pools to V1, the positions follow lazily",
+				translated
+			);
+			T::DbWeight::get().reads_writes(
+				translated.saturating_mul(8).saturating_add(1),
+				translated.saturating_mul(4).saturating_add(3),
+			)
+		}
+
+		/// The numbers of lending pools and borrows, `None` if the migration is skipped
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, sp_runtime::TryRuntimeError> {
+			let state = (Pallet::<T>::on_chain_storage_version() == 0).then(|| {
+				(
+					LendingPoolStorage::<T>::iter_keys().count() as u32,
+					Borrows::<T>::iter_keys().count() as u32,
+				)
+			});
+			Ok(state.encode())
+		}
+
+		/// The lazy part of the migration is checked by `try_state` once it is done.
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			let Some((pools, borrows)) = Option::<(u32, u32)>::decode(&mut &state[..])
+				.map_err(|_| "the pre-upgrade state can not be decoded")?
+			else {
+				return Ok(());
+			};
+			ensure!(Pallet::<T>::on_chain_storage_version() == 1, "the storage is not at V1");
+			ensure!(
+				LendingPoolStorage::<T>::iter_values().count() as u32 == pools,
+				"lending pools were lost by the migration"
+			);
+			ensure!(
+				LendingPoolStorage::<T>::count() == pools,
+				"the counter of the lending pools does not match their number"
+			);
+			for pool in LendingPoolStorage::<T>::iter_values() {
+				ensure!(
+					LpTokenPools::<T>::get(pool.id) == Some(pool.lend_token_id),
+					"the LP token of a lending pool was not indexed"
+				);
+			}
+			let supplied = PoolTotals::<T>::iter_values()
+				.fold(AssetBalanceOf::<T>::zero(), |sum, totals| {
+					sum.saturating_add(totals.supplied)
+				});
+			ensure!(
+				ProtocolStats::<T>::get().supplied == supplied,
+				"the protocol totals do not add up the pool totals"
+			);
+			ensure!(
+				BorrowFees::<T>::iter_keys().next().is_none(),
+				"origination fees were left behind by the migration"
+			);
+			ensure!(
+				Borrows::<T>::iter_keys().count() as u32 == borrows,
+				"borrows were lost by the migration"
+			);
+			ensure!(
+				MigrationCursor::<T>::get() == Some(MigrationStage::Borrows(None)),
+				"the lazy migration of the positions was not started"
+			);
+			Ok(())
+		}
+	}
+}
+
+pub mod redenomination {
+	use super::*;
+	use frame_support::storage::with_storage_layer;
+	#[cfg(feature = "try-runtime")]
+	use frame_support::sp_std::vec::Vec;
+
+	/// Remaps the lending pool of the asset `Old` to the asset `New` on a runtime upgrade, at the
+	/// `ConversionRatio` of `Pallet::do_redenominate_pool`, e.g. along with the upgrade
+	/// re-registering a bridged asset. Unlike the `redenominate_pool` call, it is not bound by the
+	/// weight of a block. It is not versioned: it is meant for a single upgrade, and does nothing
+	/// once the pool of `Old` is gone.
+	pub struct RedenominatePool<T, Old, New, ConversionRatio>(
+		PhantomData<(T, Old, New, ConversionRatio)>,
+	);
+
+	impl<T, Old, New, ConversionRatio> OnRuntimeUpgrade
+		for RedenominatePool<T, Old, New, ConversionRatio>
+	where
+		T: Config,
+		Old: Get<AssetIdOf<T>>,
+		New: Get<AssetIdOf<T>>,
+		ConversionRatio: Get<Rate>,
+	{
+		fn on_runtime_upgrade() -> Weight {
+			let (old, new, ratio) = (Old::get(), New::get(), ConversionRatio::get());
+			if !LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(old)) {
+				return T::DbWeight::get().reads(1);
+			}
+
+			match with_storage_layer(|| {
+				Pallet::<T>::do_redenominate_pool(old, new, ratio, u32::MAX)
+			}) {
+				Ok((positions, entries)) => {
+					log::info!(
+						target: "runtime::lending",
+						"redenominated the lending pool of {:?} into {:?} with {} positions",
+						old,
+						new,
+						positions
+					);
+					Pallet::<T>::deposit_event(Event::PoolRedenominated {
+						asset: old,
+						new_asset: new,
+						ratio,
+						positions,
+					});
+					T::WeightInfo::redenominate_pool(entries)
+				},
+				Err(e) => {
+					log::error!(
+						target: "runtime::lending",
+						"the lending pool of {:?} can not be redenominated: {:?}",
+						old,
+						e
+					);
+					T::DbWeight::get().reads(1)
+				},
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			ensure!(
+				!LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(Old::get())),
+				"the lending pool was not redenominated"
+			);
+			Pallet::<T>::do_try_state()
+		}
+	}
+}
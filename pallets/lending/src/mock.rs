@@ -1,15 +1,33 @@
+//! The mock runtime of the pallet, exported by the `test-utils` feature along with the helpers
+//! setting up its markets, for the pallets and the integration tests building on the lending.
+
 use crate as pallet_template;
+use crate::{
+	AccountTier, AccountTierProvider, ComplianceCheck, DexPools, GovernanceLocks, LendingHooks,
+	LpTokenGuard, PositionId, PositionNfts, PriceReference, SwapProvider, TransferToChain,
+};
 use frame_support::{
-	derive_impl, parameter_types,
-	traits::{AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64},
+	assert_ok, derive_impl, ord_parameter_types, parameter_types,
+	traits::{
+		fungible,
+		fungibles::{self, Dust, Mutate},
+		tokens::{
+			DepositConsequence, Fortitude, Precision, Preservation, Provenance, WithdrawConsequence,
+		},
+		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64, EitherOfDiverse,
+		EnsureOrigin, EqualPrivilegeOnly, Nothing,
+	},
+	weights::{constants::RocksDbWeight, Weight},
 	PalletId,
 };
-use frame_system::{EnsureRoot, EnsureSigned};
+use frame_system::{EnsureRoot, EnsureSigned, EnsureSignedBy};
 use sp_core::H256;
 use sp_runtime::{
+	testing::TestXt,
 	traits::{BlakeTwo256, IdentityLookup},
-	BuildStorage,
+	BuildStorage, DispatchError, DispatchResult, FixedU128, Permill,
 };
+use std::{cell::RefCell, collections::BTreeMap};
 
 type Block = frame_system::mocking::MockBlock<Test>;
 type Balance = u128;
@@ -23,6 +41,9 @@ frame_support::construct_runtime!(
 		Assets: pallet_assets,
 		TemplateModule: pallet_template,
 		Timestamp: pallet_timestamp,
+		ForeignAssets: pallet_assets::<Instance2>,
+		Scheduler: pallet_scheduler,
+		Preimage: pallet_preimage,
 	}
 );
 #[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
@@ -30,13 +51,13 @@ impl frame_system::Config for Test {
 	type BaseCallFilter = frame_support::traits::Everything;
 	type BlockWeights = ();
 	type BlockLength = ();
-	type DbWeight = ();
+	type DbWeight = RocksDbWeight;
 	type RuntimeOrigin = RuntimeOrigin;
 	type RuntimeCall = RuntimeCall;
 	type Nonce = u64;
 	type Hash = H256;
 	type Hashing = BlakeTwo256;
-	type AccountId = u64;
+	type AccountId = u128;
 	type Lookup = IdentityLookup<Self::AccountId>;
 	type Block = Block;
 	type RuntimeEvent = RuntimeEvent;
@@ -60,7 +81,7 @@ impl pallet_balances::Config for Test {
 	type ExistentialDeposit = ConstU128<1>;
 	type AccountStore = System;
 	type ReserveIdentifier = [u8; 8];
-	type RuntimeHoldReason = ();
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type FreezeIdentifier = ();
 	type MaxLocks = ConstU32<10>;
 	type MaxReserves = ();
@@ -90,7 +111,7 @@ impl pallet_assets::Config for Test {
 	type MetadataDepositPerByte = ConstU128<1>;
 	type ApprovalDeposit = ConstU128<1>;
 	type StringLimit = ConstU32<50>;
-	type Freezer = ();
+	type Freezer = TemplateModule;
 	type Extra = ();
 	type CallbackHandle = ();
 	type WeightInfo = ();
@@ -98,24 +119,706 @@ impl pallet_assets::Config for Test {
 	type BenchmarkHelper = ();
 }
 
+// The bridged assets, the asset ids from `FOREIGN_ASSETS` on
+impl pallet_assets::Config<pallet_assets::Instance2> for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type RemoveItemsLimit = ConstU32<1000>;
+	type AssetId = u32;
+	type AssetIdParameter = codec::Compact<u32>;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<Self::AccountId>>;
+	type ForceOrigin = EnsureRoot<Self::AccountId>;
+	type AssetDeposit = ConstU128<100>;
+	type AssetAccountDeposit = ConstU128<1>;
+	type MetadataDepositBase = ConstU128<10>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = TemplateModule;
+	type Extra = ();
+	type CallbackHandle = ();
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl pallet_preimage::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type Currency = Balances;
+	type ManagerOrigin = EnsureRoot<u128>;
+	type BaseDeposit = ConstU128<0>;
+	type ByteDeposit = ConstU128<0>;
+}
+
+impl pallet_scheduler::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type PalletsOrigin = OriginCaller;
+	type RuntimeCall = RuntimeCall;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<u128>;
+	type MaxScheduledPerBlock = ConstU32<10>;
+	type WeightInfo = ();
+	type OriginPrivilegeCmp = EqualPrivilegeOnly;
+	type Preimages = Preimage;
+}
+
+/// The first asset id of the `ForeignAssets`
+pub const FOREIGN_ASSETS: u32 = 1_000_000;
+
+/// The assets of the lending pools, routed by asset id to the local `Assets` or, from
+/// `FOREIGN_ASSETS` on, to the `ForeignAssets`
+pub struct LocalOrForeignAssets;
+
+/// Calls the method `$f` of the trait `$t` on the instance of the Assets Pallet `$asset` is
+/// routed to
+macro_rules! route {
+	($t:path, $asset:expr, $f:ident($($arg:expr),*)) => {
+		if $asset < FOREIGN_ASSETS {
+			<Assets as $t>::$f($asset, $($arg),*)
+		} else {
+			<ForeignAssets as $t>::$f($asset, $($arg),*)
+		}
+	};
+}
+
+impl fungibles::Inspect<u128> for LocalOrForeignAssets {
+	type AssetId = u32;
+	type Balance = Balance;
+
+	fn total_issuance(asset: u32) -> Balance {
+		route!(fungibles::Inspect<u128>, asset, total_issuance())
+	}
+	fn active_issuance(asset: u32) -> Balance {
+		route!(fungibles::Inspect<u128>, asset, active_issuance())
+	}
+	fn minimum_balance(asset: u32) -> Balance {
+		route!(fungibles::Inspect<u128>, asset, minimum_balance())
+	}
+	fn total_balance(asset: u32, who: &u128) -> Balance {
+		route!(fungibles::Inspect<u128>, asset, total_balance(who))
+	}
+	fn balance(asset: u32, who: &u128) -> Balance {
+		route!(fungibles::Inspect<u128>, asset, balance(who))
+	}
+	fn reducible_balance(
+		asset: u32,
+		who: &u128,
+		preservation: Preservation,
+		force: Fortitude,
+	) -> Balance {
+		route!(fungibles::Inspect<u128>, asset, reducible_balance(who, preservation, force))
+	}
+	fn can_deposit(
+		asset: u32,
+		who: &u128,
+		amount: Balance,
+		provenance: Provenance,
+	) -> DepositConsequence {
+		route!(fungibles::Inspect<u128>, asset, can_deposit(who, amount, provenance))
+	}
+	fn can_withdraw(asset: u32, who: &u128, amount: Balance) -> WithdrawConsequence<Balance> {
+		route!(fungibles::Inspect<u128>, asset, can_withdraw(who, amount))
+	}
+	fn asset_exists(asset: u32) -> bool {
+		route!(fungibles::Inspect<u128>, asset, asset_exists())
+	}
+}
+
+impl fungibles::Unbalanced<u128> for LocalOrForeignAssets {
+	fn handle_dust(dust: Dust<u128, Self>) {
+		if dust.0 < FOREIGN_ASSETS {
+			<Assets as fungibles::Unbalanced<u128>>::handle_dust(Dust(dust.0, dust.1))
+		} else {
+			<ForeignAssets as fungibles::Unbalanced<u128>>::handle_dust(Dust(dust.0, dust.1))
+		}
+	}
+	fn write_balance(
+		asset: u32,
+		who: &u128,
+		amount: Balance,
+	) -> Result<Option<Balance>, DispatchError> {
+		route!(fungibles::Unbalanced<u128>, asset, write_balance(who, amount))
+	}
+	fn set_total_issuance(asset: u32, amount: Balance) {
+		route!(fungibles::Unbalanced<u128>, asset, set_total_issuance(amount))
+	}
+}
+
+impl fungibles::Mutate<u128> for LocalOrForeignAssets {
+	fn mint_into(asset: u32, who: &u128, amount: Balance) -> Result<Balance, DispatchError> {
+		route!(fungibles::Mutate<u128>, asset, mint_into(who, amount))
+	}
+	fn burn_from(
+		asset: u32,
+		who: &u128,
+		amount: Balance,
+		precision: Precision,
+		force: Fortitude,
+	) -> Result<Balance, DispatchError> {
+		route!(fungibles::Mutate<u128>, asset, burn_from(who, amount, precision, force))
+	}
+	fn shelve(asset: u32, who: &u128, amount: Balance) -> Result<Balance, DispatchError> {
+		route!(fungibles::Mutate<u128>, asset, shelve(who, amount))
+	}
+	fn restore(asset: u32, who: &u128, amount: Balance) -> Result<Balance, DispatchError> {
+		route!(fungibles::Mutate<u128>, asset, restore(who, amount))
+	}
+	fn transfer(
+		asset: u32,
+		source: &u128,
+		dest: &u128,
+		amount: Balance,
+		preservation: Preservation,
+	) -> Result<Balance, DispatchError> {
+		route!(fungibles::Mutate<u128>, asset, transfer(source, dest, amount, preservation))
+	}
+}
+
+impl fungibles::Create<u128> for LocalOrForeignAssets {
+	fn create(
+		asset: u32,
+		admin: u128,
+		is_sufficient: bool,
+		min_balance: Balance,
+	) -> DispatchResult {
+		route!(fungibles::Create<u128>, asset, create(admin, is_sufficient, min_balance))
+	}
+}
+
+impl fungibles::metadata::Inspect<u128> for LocalOrForeignAssets {
+	fn name(asset: u32) -> Vec<u8> {
+		route!(fungibles::metadata::Inspect<u128>, asset, name())
+	}
+	fn symbol(asset: u32) -> Vec<u8> {
+		route!(fungibles::metadata::Inspect<u128>, asset, symbol())
+	}
+	fn decimals(asset: u32) -> u8 {
+		route!(fungibles::metadata::Inspect<u128>, asset, decimals())
+	}
+}
+
+/// The first account derived from the location of a user of another chain
+pub const REMOTE_ACCOUNTS: u128 = 1_000;
+
+/// Models the XCM origins of the sibling parachains: a signed origin stands for the location of
+/// a remote user, resolving to its derived account shifted by `REMOTE_ACCOUNTS`
+pub struct EnsureRemoteAccount;
+impl EnsureOrigin<RuntimeOrigin> for EnsureRemoteAccount {
+	type Success = u128;
+
+	fn try_origin(o: RuntimeOrigin) -> Result<u128, RuntimeOrigin> {
+		o.into().and_then(|o| match o {
+			frame_system::RawOrigin::Signed(who) => Ok(who + REMOTE_ACCOUNTS),
+			o => Err(RuntimeOrigin::from(o)),
+		})
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		Ok(RuntimeOrigin::signed(0))
+	}
+}
+
+/// The sovereign account of the sibling parachain in this chain, holding the reserve of the
+/// assets sent to it
+pub const SIBLING_SOVEREIGN: u128 = 2_000;
+
+thread_local! {
+	static SENT_TO_CHAIN: RefCell<Vec<(u128, u32, Balance)>> = RefCell::new(Vec::new());
+}
+
+/// Models the reserve transfers to the sibling parachain: the assets move to its sovereign
+/// account and the beneficiaries credited there are recorded
+pub struct MockTransferToChain;
+impl MockTransferToChain {
+	pub fn sent() -> Vec<(u128, u32, Balance)> {
+		SENT_TO_CHAIN.with(|sent| sent.borrow().clone())
+	}
+}
+impl TransferToChain<u128, u32, Balance, u128> for MockTransferToChain {
+	fn transfer_to_chain(who: &u128, asset: u32, balance: Balance, dest: u128) -> DispatchResult {
+		<<Test as pallet_template::Config>::Fungibles as Mutate<u128>>::transfer(
+			asset,
+			who,
+			&SIBLING_SOVEREIGN,
+			balance,
+			Preservation::Expendable,
+		)?;
+		SENT_TO_CHAIN.with(|sent| sent.borrow_mut().push((dest, asset, balance)));
+		Ok(())
+	}
+
+	fn transfer_weight() -> Weight {
+		Weight::zero()
+	}
+}
+
+/// The account holding the liquidity of the DEX
+pub const DEX_ACCOUNT: u128 = 4_000;
+
+/// Models a DEX swapping at the prices of the pallet out of the liquidity of `DEX_ACCOUNT`
+pub struct MockSwapProvider;
+impl SwapProvider<u128, u32, Balance> for MockSwapProvider {
+	fn swap_exact_out(
+		who: &u128,
+		asset_in: u32,
+		asset_out: u32,
+		amount_out: Balance,
+		max_amount_in: Balance,
+		dest: &u128,
+	) -> Result<Balance, DispatchError> {
+		let amount_in =
+			TemplateModule::get_equivalent_asset_amount(who, asset_in, asset_out, amount_out)?;
+		if amount_in > max_amount_in {
+			return Err(DispatchError::Other("slippage exceeded"));
+		}
+		type Fungibles = <Test as pallet_template::Config>::Fungibles;
+		<Fungibles as Mutate<u128>>::transfer(
+			asset_in,
+			who,
+			&DEX_ACCOUNT,
+			amount_in,
+			Preservation::Expendable,
+		)?;
+		<Fungibles as Mutate<u128>>::transfer(
+			asset_out,
+			&DEX_ACCOUNT,
+			dest,
+			amount_out,
+			Preservation::Expendable,
+		)?;
+		Ok(amount_in)
+	}
+
+	fn swap_weight() -> Weight {
+		Weight::zero()
+	}
+}
+
+thread_local! {
+	static HOOK_CALLS: RefCell<Vec<(&'static str, u128, u32, Balance)>> = RefCell::new(Vec::new());
+}
+
+/// Records the lending hooks called, with the account, the asset and the balance of the
+/// operations
+pub struct MockLendingHooks;
+impl MockLendingHooks {
+	pub fn calls() -> Vec<(&'static str, u128, u32, Balance)> {
+		HOOK_CALLS.with(|calls| calls.borrow().clone())
+	}
+	fn record(hook: &'static str, who: &u128, asset: u32, balance: Balance) {
+		HOOK_CALLS.with(|calls| calls.borrow_mut().push((hook, *who, asset, balance)));
+	}
+}
+impl LendingHooks<u128, u32, Balance> for MockLendingHooks {
+	fn on_supply(who: &u128, asset: u32, balance: Balance) {
+		Self::record("supply", who, asset, balance);
+	}
+	fn on_borrow(who: &u128, asset: u32, balance: Balance, _: u32, _: Balance) {
+		Self::record("borrow", who, asset, balance);
+	}
+	fn on_repay(_payer: &u128, who: &u128, asset: u32, balance: Balance, _: u32) {
+		Self::record("repay", who, asset, balance);
+	}
+	fn on_liquidation(_liquidator: &u128, who: &u128, asset: u32, balance: Balance, _: u32) {
+		Self::record("liquidation", who, asset, balance);
+	}
+}
+
+thread_local! {
+	static SANCTIONED: RefCell<Vec<u128>> = RefCell::new(Vec::new());
+}
+
+/// Rejects the operations of the accounts the tests sanction
+pub struct MockComplianceCheck;
+impl MockComplianceCheck {
+	pub fn sanction(who: u128) {
+		SANCTIONED.with(|sanctioned| sanctioned.borrow_mut().push(who));
+	}
+	fn check(who: &u128) -> DispatchResult {
+		match SANCTIONED.with(|sanctioned| sanctioned.borrow().contains(who)) {
+			true => Err(DispatchError::Other("sanctioned account")),
+			false => Ok(()),
+		}
+	}
+}
+impl ComplianceCheck<u128, u32, Balance> for MockComplianceCheck {
+	fn check_supply(who: &u128, _asset: u32, _balance: Balance) -> DispatchResult {
+		Self::check(who)
+	}
+	fn check_borrow(who: &u128, _asset: u32, _balance: Balance) -> DispatchResult {
+		Self::check(who)
+	}
+	fn check_withdraw(who: &u128, _asset: u32, _balance: Balance) -> DispatchResult {
+		Self::check(who)
+	}
+}
+
+thread_local! {
+	static TIERS: RefCell<BTreeMap<u128, AccountTier>> = RefCell::new(BTreeMap::new());
+}
+
+/// Provides the tiers the tests register, e.g. from an identity pallet
+pub struct MockAccountTierProvider;
+impl MockAccountTierProvider {
+	pub fn set_tier(who: u128, tier: AccountTier) {
+		TIERS.with(|tiers| tiers.borrow_mut().insert(who, tier));
+	}
+}
+impl AccountTierProvider<u128> for MockAccountTierProvider {
+	fn tier(who: &u128) -> Option<AccountTier> {
+		TIERS.with(|tiers| tiers.borrow().get(who).copied())
+	}
+}
+
+thread_local! {
+	static POSITION_NFTS: RefCell<BTreeMap<PositionId, u128>> = RefCell::new(BTreeMap::new());
+}
+
+/// Keeps the owners of the position NFTs
+pub struct MockPositionNfts;
+impl PositionNfts<u128> for MockPositionNfts {
+	fn mint(id: PositionId, who: &u128) -> DispatchResult {
+		POSITION_NFTS.with(|nfts| nfts.borrow_mut().insert(id, *who));
+		Ok(())
+	}
+	fn burn(id: PositionId) -> DispatchResult {
+		POSITION_NFTS.with(|nfts| nfts.borrow_mut().remove(&id));
+		Ok(())
+	}
+	fn owner(id: PositionId) -> Option<u128> {
+		POSITION_NFTS.with(|nfts| nfts.borrow().get(&id).copied())
+	}
+	fn transfer(id: PositionId, dest: &u128) -> DispatchResult {
+		POSITION_NFTS.with(|nfts| nfts.borrow_mut().insert(id, *dest));
+		Ok(())
+	}
+}
+
+/// The id the native currency of the `Balances` is priced and borrowed against with
+pub const NATIVE_ASSET: u32 = 40;
+
+/// The account standing for the governance of the relay chain
+pub const RELAY_GOVERNANCE: u128 = 3_000;
+
+/// Models the XCM origin of the relay chain governance, as converted by the `OriginConverter` of
+/// the XCM executor: a signed origin of `RELAY_GOVERNANCE`
+pub struct EnsureRelayGovernance;
+impl EnsureOrigin<RuntimeOrigin> for EnsureRelayGovernance {
+	type Success = ();
+
+	fn try_origin(o: RuntimeOrigin) -> Result<(), RuntimeOrigin> {
+		o.into().and_then(|o| match o {
+			frame_system::RawOrigin::Signed(RELAY_GOVERNANCE) => Ok(()),
+			o => Err(RuntimeOrigin::from(o)),
+		})
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		Ok(RuntimeOrigin::signed(RELAY_GOVERNANCE))
+	}
+}
+
 parameter_types! {
 	pub const KylixPalletId: PalletId = PalletId(*b"kylixpdl");
+	pub const CompoundTip: Permill = Permill::from_percent(1);
+	pub const ReferralShare: Permill = Permill::from_percent(20);
+	pub const StableRateRebalanceUtilisation: Permill = Permill::from_percent(95);
+	pub const TermGracePeriod: u64 = 24 * 60 * 60;
+	pub TermPenaltyRate: FixedU128 = FixedU128::from_rational(1, 10);
+	pub const TreasuryAccount: u128 = 99;
+	pub const OffchainScanInterval: u64 = 10;
+	pub const OutflowWindow: u64 = 10;
+	pub const UtilisationAveragePeriod: u64 = 24 * 60 * 60;
+	pub const MaxAccrualPeriod: u64 = 365 * 24 * 60 * 60;
+	pub const RateSnapshotInterval: u64 = 10;
+	pub static WithdrawCooldown: u64 = 0;
+	pub static MaxPools: u32 = 16;
+	pub const UnsignedPriority: u64 = 1 << 20;
+	pub static SwapOffchainLiquidations: bool = false;
+	pub static MarginCallGracePeriod: u64 = 0;
+	pub const MarginCallSeverity: Permill = Permill::from_percent(50);
+	pub const StallThreshold: u64 = 60;
+	pub static StallGracePeriod: u64 = 0;
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(10);
+	pub static MinPoolLiquidity: u128 = 0;
+	pub static DeadShares: u128 = 0;
+	pub MaxRewardBoost: FixedU128 = FixedU128::from_rational(5, 2);
+	pub const MaxLoyaltyBonus: Permill = Permill::from_percent(20);
+	pub const ParamChangeDelay: u64 = 10;
+	pub const NativeAsset: Option<u32> = Some(NATIVE_ASSET);
+	pub MaximumSchedulerWeight: Weight = Weight::from_parts(1_000_000_000_000, u64::MAX);
+}
+
+thread_local! {
+	static DEX_POOLS: RefCell<BTreeMap<u32, ((u32, u32), (Balance, Balance))>> =
+		RefCell::new(BTreeMap::new());
+}
+
+/// Liquidity pools of a DEX whose reserves can be set freely by the tests, the LP tokens are
+/// issued by the `Assets`
+pub struct MockDexPools;
+impl MockDexPools {
+	pub fn set_pool(lp_token: u32, assets: (u32, u32), reserves: (Balance, Balance)) {
+		DEX_POOLS.with(|pools| pools.borrow_mut().insert(lp_token, (assets, reserves)));
+	}
+}
+impl DexPools<u32, Balance> for MockDexPools {
+	fn pool_assets(lp_token: u32) -> Option<(u32, u32)> {
+		DEX_POOLS.with(|pools| pools.borrow().get(&lp_token).map(|(assets, _)| *assets))
+	}
+	fn pool_reserves(lp_token: u32) -> Option<(Balance, Balance, Balance)> {
+		let (reserve1, reserve2) =
+			DEX_POOLS.with(|pools| pools.borrow().get(&lp_token).map(|(_, reserves)| *reserves))?;
+		Some((reserve1, reserve2, Assets::total_supply(lp_token)))
+	}
+}
+
+thread_local! {
+	static PRICE_REFERENCES: RefCell<BTreeMap<(u32, u32), FixedU128>> =
+		RefCell::new(BTreeMap::new());
+}
+
+/// Reference prices which can be set freely by the tests
+pub struct MockPriceReference;
+impl MockPriceReference {
+	pub fn set(asset: u32, quote: u32, price: Option<FixedU128>) {
+		PRICE_REFERENCES.with(|prices| match price {
+			Some(price) => prices.borrow_mut().insert((asset, quote), price),
+			None => prices.borrow_mut().remove(&(asset, quote)),
+		});
+	}
+}
+impl PriceReference<u32> for MockPriceReference {
+	fn price(asset: u32, quote: u32) -> Option<FixedU128> {
+		PRICE_REFERENCES.with(|prices| prices.borrow().get(&(asset, quote)).copied())
+	}
+}
+
+thread_local! {
+	static GOVERNANCE_LOCKS: RefCell<BTreeMap<u128, Balance>> = RefCell::new(BTreeMap::new());
+}
+
+/// Governance token locks which can be set freely by the tests
+pub struct MockGovernanceLocks;
+impl MockGovernanceLocks {
+	pub fn lock(who: u128, amount: Balance) {
+		GOVERNANCE_LOCKS.with(|locks| locks.borrow_mut().insert(who, amount));
+	}
+}
+impl GovernanceLocks<u128, Balance> for MockGovernanceLocks {
+	fn locked_balance(who: &u128) -> Balance {
+		GOVERNANCE_LOCKS.with(|locks| locks.borrow().get(who).copied().unwrap_or_default())
+	}
+	fn total_locked() -> Balance {
+		GOVERNANCE_LOCKS.with(|locks| locks.borrow().values().sum())
+	}
 }
 
 impl pallet_template::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeFreezeReason = RuntimeFreezeReason;
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type Time = Timestamp;
 	#[doc = r" Type to access the Balances Pallet."]
 	type NativeBalance = Balances;
 	type WeightInfo = ();
 	#[doc = r" Type to access the Assets Pallet."]
-	type Fungibles = Assets;
+	type Fungibles = LpTokenGuard<Test, LocalOrForeignAssets>;
 	type PalletId = KylixPalletId;
-	//	#[doc = r" The origin which can add or remove LendingPools and update LendingPools (interest
-	// rate model, kink, activate, deactivate)."] 	type ManagerOrigin = ();
+	type ManagerOrigin = EitherOfDiverse<EnsureRoot<u128>, EnsureRelayGovernance>;
+	type OracleOrigin = EnsureSignedBy<Oracle, u128>;
+	type RemoteOrigin = EnsureRemoteAccount;
+	type Location = u128;
+	type TransferToChain = MockTransferToChain;
+	type SwapProvider = MockSwapProvider;
+	type DexPools = MockDexPools;
+	type LendingHooks = MockLendingHooks;
+	type ComplianceCheck = MockComplianceCheck;
+	type AccountTierProvider = MockAccountTierProvider;
+	type PositionNfts = MockPositionNfts;
+	type AssetsInUse = Nothing;
+	type CompoundTip = CompoundTip;
+	type MaxMarketsPerAccount = ConstU32<8>;
+	type MaxPools = MaxPools;
+	type GovernanceLocks = MockGovernanceLocks;
+	type MaxRewardBoost = MaxRewardBoost;
+	type MaxLoyaltyTiers = ConstU32<3>;
+	type MaxLoyaltyBonus = MaxLoyaltyBonus;
+	type ReferralShare = ReferralShare;
+	type TreasuryAccount = TreasuryAccount;
+	type OffchainScanInterval = OffchainScanInterval;
+	type UnsignedPriority = UnsignedPriority;
+	type SwapOffchainLiquidations = SwapOffchainLiquidations;
+	type KeeperBond = ConstU128<1_000>;
+	type KeeperExclusivity = ConstU64<5>;
+	type MaxKeeperMisses = ConstU32<2>;
+	type MarginCallGracePeriod = MarginCallGracePeriod;
+	type MarginCallSeverity = MarginCallSeverity;
+	type StallThreshold = StallThreshold;
+	type StallGracePeriod = StallGracePeriod;
+	type PriceReference = MockPriceReference;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MaxPoolsPerBlock = ConstU32<1>;
+	type DustBalance = ConstU128<10>;
+	type MinPoolLiquidity = MinPoolLiquidity;
+	type DeadShares = DeadShares;
+	type StableRateRebalanceUtilisation = StableRateRebalanceUtilisation;
+	type TermGracePeriod = TermGracePeriod;
+	type TermPenaltyRate = TermPenaltyRate;
+	type UtilisationAveragePeriod = UtilisationAveragePeriod;
+	type MaxAccrualPeriod = MaxAccrualPeriod;
+	type OutflowWindow = OutflowWindow;
+	type WithdrawCooldown = WithdrawCooldown;
+	type RateSnapshotInterval = RateSnapshotInterval;
+	type MaxRateSnapshots = ConstU32<3>;
+	type KTokenSnapshotEra = ConstU64<10>;
+	type MaxKTokenCheckpoints = ConstU32<3>;
+	type MaxKTokenFreezes = ConstU32<2>;
+	type RuntimeCall = RuntimeCall;
+	type Scheduler = Scheduler;
+	type Preimages = Preimage;
+	type ParamChangeDelay = ParamChangeDelay;
+	type AssetId = u32;
+	type BaseAsset = ConstU32<0>;
+	type NativeAsset = NativeAsset;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = Extrinsic;
+}
+
+/// The owner of the assets created by `create_asset_and_mint`
+pub const ASSET_OWNER: u128 = 1;
+
+/// The account feeding the prices of the `MockOracle`
+pub const ORACLE: u128 = 5_000;
+
+ord_parameter_types! {
+	pub const Oracle: u128 = ORACLE;
+}
+
+/// Feeds the prices of the assets to the pallet, as an oracle would
+pub struct MockOracle;
+impl MockOracle {
+	pub fn set_price(asset: u32, quote: u32, price: FixedU128) {
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			asset,
+			quote,
+			price
+		));
+	}
+}
+
+/// Creates `asset`, owned by the `ASSET_OWNER`, and mints `amount` of it into `who`
+pub fn create_asset_and_mint(asset: u32, who: u128, amount: Balance) {
+	assert_ok!(Assets::force_create(RuntimeOrigin::root(), asset.into(), ASSET_OWNER, true, 1));
+	assert_ok!(Assets::mint(RuntimeOrigin::signed(ASSET_OWNER), asset.into(), who, amount));
+}
+
+/// Mints `amount` of the existing `asset` into `who`, the native currency for the
+/// `NATIVE_ASSET`
+pub fn fund_account(who: u128, asset: u32, amount: Balance) {
+	if asset == NATIVE_ASSET {
+		assert_ok!(<Balances as fungible::Mutate<u128>>::mint_into(&who, amount));
+	} else {
+		type Fungibles = <Test as pallet_template::Config>::Fungibles;
+		assert_ok!(<Fungibles as Mutate<u128>>::mint_into(asset, &who, amount));
+	}
+}
+
+/// Creates the lending pool `id` of `asset` with the `supply` of `supplier`, and activates it
+pub fn create_active_pool(id: u32, asset: u32, supplier: u128, supply: Balance) {
+	assert_ok!(TemplateModule::create_lending_pool(
+		RuntimeOrigin::signed(supplier),
+		id,
+		asset,
+		supply
+	));
+	assert_ok!(TemplateModule::activate_lending_pool(RuntimeOrigin::signed(supplier), asset));
+}
+
+/// Builds the genesis storage of the mock runtime, then seeds its markets: the assets with the
+/// balances of their holders, the prices, and the active lending pools, in that order. The
+/// markets are seeded at the genesis block, without events.
+#[derive(Default)]
+pub struct ExtBuilder {
+	balances: Vec<(u128, Balance)>,
+	assets: Vec<(u32, Vec<(u128, Balance)>)>,
+	prices: Vec<(u32, u32, FixedU128)>,
+	pools: Vec<(u32, u32, u128, Balance)>,
+}
+
+impl ExtBuilder {
+	/// Endows the accounts with the native currency
+	pub fn with_balances(mut self, balances: Vec<(u128, Balance)>) -> Self {
+		self.balances = balances;
+		self
+	}
+
+	/// Creates `asset` and mints the balances of its `holders`
+	pub fn with_asset(mut self, asset: u32, holders: Vec<(u128, Balance)>) -> Self {
+		self.assets.push((asset, holders));
+		self
+	}
+
+	/// Sets the price of `asset` in `quote`
+	pub fn with_price(mut self, asset: u32, quote: u32, price: FixedU128) -> Self {
+		self.prices.push((asset, quote, price));
+		self
+	}
+
+	/// Creates the lending pool `id` of `asset` with the `supply` of `supplier`, who must hold
+	/// it, and activates it
+	pub fn with_pool(mut self, id: u32, asset: u32, supplier: u128, supply: Balance) -> Self {
+		self.pools.push((id, asset, supplier, supply));
+		self
+	}
+
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		pallet_balances::GenesisConfig::<Test> { balances: self.balances }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+		let mut ext: sp_io::TestExternalities = storage.into();
+		ext.execute_with(|| {
+			for (asset, holders) in self.assets {
+				assert_ok!(Assets::force_create(
+					RuntimeOrigin::root(),
+					asset.into(),
+					ASSET_OWNER,
+					true,
+					1
+				));
+				for (who, amount) in holders {
+					fund_account(who, asset, amount);
+				}
+			}
+			for (asset, quote, price) in self.prices {
+				MockOracle::set_price(asset, quote, price);
+			}
+			for (id, asset, supplier, supply) in self.pools {
+				create_active_pool(id, asset, supplier, supply);
+			}
+		});
+		ext
+	}
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into()
+	ExtBuilder::default().build()
 }
@@ -0,0 +1,211 @@
+use crate::*;
+use frame_system::offchain::SubmitTransaction;
+
+impl<T: Config> Pallet<T> {
+	/// Scans the lending pools and the borrows every `OffchainScanInterval` blocks, and submits
+	/// the unsigned keeper transactions:
+	/// - `accrue_interest` for the active lending pools with outstanding borrows
+	/// - `report_price_deviation` for the prices deviating from their `PriceReference`, while the
+	///   lending pools of their assets are not paused
+	/// - `margin_call` for the borrows with a shortfall not margin called yet, whose grace period
+	///   must be over before the next transactions
+	/// - `assign_liquidation` for the borrows with a shortfall when keepers are registered
+	/// - `liquidate_borrow` for the borrows with a shortfall, the protocol, through its treasury,
+	///   being thus the liquidator of last resort, or `liquidate_borrow_with_swap` with the
+	///   `SwapOffchainLiquidations`, once the exclusivity of the keeper is over if any
+	///
+	/// The borrows are not scanned during the `StallGracePeriod` following a stall of the chain.
+	pub fn offchain_keeper(block_number: BlockNumberFor<T>) {
+		let interval = T::OffchainScanInterval::get().max(One::one());
+		if !(block_number % interval).is_zero() {
+			return;
+		}
+
+		for pool in LendingPoolStorage::<T>::iter_values() {
+			if Self::accrual_due(&pool) {
+				Self::submit_unsigned(Call::accrue_interest { asset: pool.lend_token_id });
+			}
+		}
+
+		for (asset, quote) in AssetPrices::<T>::iter_keys() {
+			if Self::price_deviation(asset, quote).is_some() &&
+				!Self::pools_to_pause(asset, quote).is_empty()
+			{
+				Self::submit_unsigned(Call::report_price_deviation { asset, quote });
+			}
+		}
+
+		// the borrowers are left the grace period following a stall to restore their health
+		if Self::stall_grace_period() {
+			return;
+		}
+		let keepers = Keepers::<T>::iter_keys().next().is_some();
+		for (borrower, asset, collateral_asset) in Borrows::<T>::iter_keys() {
+			let shortfall = match Self::shortfall(&borrower, asset, collateral_asset) {
+				Ok(shortfall) if !shortfall.is_zero() => shortfall,
+				_ => continue,
+			};
+			if !matches!(
+				Self::grace_period_over(&borrower, asset, collateral_asset, shortfall),
+				Ok(true)
+			) {
+				if !MarginCalls::<T>::contains_key((&borrower, asset, collateral_asset)) {
+					Self::submit_unsigned(Call::margin_call { borrower, asset, collateral_asset });
+				}
+			} else if keepers &&
+				!LiquidationAssignments::<T>::contains_key((&borrower, asset, collateral_asset))
+			{
+				Self::submit_unsigned(Call::assign_liquidation {
+					borrower,
+					asset,
+					collateral_asset,
+				});
+			} else if !Self::liquidation_reserved(&borrower, asset, collateral_asset) {
+				Self::submit_unsigned(if T::SwapOffchainLiquidations::get() {
+					Call::liquidate_borrow_with_swap { borrower, asset, collateral_asset }
+				} else {
+					Call::liquidate_borrow { borrower, asset, collateral_asset }
+				});
+			}
+		}
+	}
+
+	fn submit_unsigned(call: Call<T>) {
+		if SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()).is_err() {
+			log::warn!(target: "runtime::lending", "failed to submit an unsigned transaction");
+		}
+	}
+
+	/// Returns whether the interest of the lending pool can be accrued, that is the pool is
+	/// active, has outstanding borrows and was not accrued yet at this time.
+	pub fn accrual_due(pool: &LendingPool<T>) -> bool {
+		pool.is_active() &&
+			!pool.borrowed_balance.is_zero() &&
+			pool.last_accrued_interest_at < Self::now_in_seconds()
+	}
+
+	/// Validates the unsigned `liquidate_borrow` transactions, which are only valid for the
+	/// borrows with a shortfall past their grace period and not reserved to a keeper. The larger
+	/// the shortfall, the higher the priority.
+	pub fn validate_liquidation(
+		borrower: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> TransactionValidity {
+		let shortfall = match Self::shortfall(borrower, asset, collateral_asset) {
+			Ok(shortfall) if !shortfall.is_zero() => shortfall,
+			_ => return InvalidTransaction::Stale.into(),
+		};
+		if Self::liquidation_reserved(borrower, asset, collateral_asset) ||
+			Self::stall_grace_period() ||
+			!matches!(
+				Self::grace_period_over(borrower, asset, collateral_asset, shortfall),
+				Ok(true)
+			) {
+			return InvalidTransaction::Stale.into();
+		}
+
+		ValidTransaction::with_tag_prefix("LendingLiquidation")
+			.priority(T::UnsignedPriority::get().saturating_add(shortfall.saturated_into::<u64>()))
+			.and_provides((borrower, asset, collateral_asset))
+			.longevity(Self::unsigned_longevity())
+			.propagate(true)
+			.build()
+	}
+
+	/// Validates the unsigned `assign_liquidation` transactions, which are only valid for the
+	/// borrows with a shortfall past their grace period whose liquidation is not assigned yet,
+	/// while keepers are registered.
+	pub fn validate_liquidation_assignment(
+		borrower: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> TransactionValidity {
+		let shortfall = match Self::shortfall(borrower, asset, collateral_asset) {
+			Ok(shortfall) if !shortfall.is_zero() => shortfall,
+			_ => return InvalidTransaction::Stale.into(),
+		};
+		if LiquidationAssignments::<T>::contains_key((borrower, asset, collateral_asset)) ||
+			Keepers::<T>::iter_keys().next().is_none() ||
+			Self::stall_grace_period() ||
+			!matches!(
+				Self::grace_period_over(borrower, asset, collateral_asset, shortfall),
+				Ok(true)
+			) {
+			return InvalidTransaction::Stale.into();
+		}
+
+		ValidTransaction::with_tag_prefix("LendingLiquidationAssignment")
+			.priority(T::UnsignedPriority::get())
+			.and_provides((borrower, asset, collateral_asset))
+			.longevity(Self::unsigned_longevity())
+			.propagate(true)
+			.build()
+	}
+
+	/// Validates the unsigned `margin_call` transactions, which are only valid for the borrows
+	/// with a shortfall not margin called yet, with a grace period.
+	pub fn validate_margin_call(
+		borrower: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> TransactionValidity {
+		match Self::shortfall(borrower, asset, collateral_asset) {
+			Ok(shortfall) if !shortfall.is_zero() => (),
+			_ => return InvalidTransaction::Stale.into(),
+		}
+		if T::MarginCallGracePeriod::get().is_zero() ||
+			MarginCalls::<T>::contains_key((borrower, asset, collateral_asset))
+		{
+			return InvalidTransaction::Stale.into();
+		}
+
+		ValidTransaction::with_tag_prefix("LendingMarginCall")
+			.priority(T::UnsignedPriority::get())
+			.and_provides((borrower, asset, collateral_asset))
+			.longevity(Self::unsigned_longevity())
+			.propagate(true)
+			.build()
+	}
+
+	/// Validates the unsigned `report_price_deviation` transactions, which are only valid for
+	/// the prices deviating from their reference while the lending pools of their assets are
+	/// not paused.
+	pub fn validate_price_deviation(
+		asset: AssetIdOf<T>,
+		quote: AssetIdOf<T>,
+	) -> TransactionValidity {
+		if Self::price_deviation(asset, quote).is_none() ||
+			Self::pools_to_pause(asset, quote).is_empty()
+		{
+			return InvalidTransaction::Stale.into();
+		}
+
+		ValidTransaction::with_tag_prefix("LendingPriceDeviation")
+			.priority(T::UnsignedPriority::get())
+			.and_provides((asset, quote))
+			.longevity(Self::unsigned_longevity())
+			.propagate(true)
+			.build()
+	}
+
+	/// Validates the unsigned `accrue_interest` transactions, which are only valid once per
+	/// block for the lending pools with an accrual due.
+	pub fn validate_accrual(asset: AssetIdOf<T>) -> TransactionValidity {
+		match LendingPoolStorage::<T>::try_get(AssetPool::<T>::from(asset)) {
+			Ok(pool) if Self::accrual_due(&pool) => (),
+			_ => return InvalidTransaction::Stale.into(),
+		}
+
+		ValidTransaction::with_tag_prefix("LendingAccrual")
+			.priority(T::UnsignedPriority::get())
+			.and_provides((asset, <frame_system::Pallet<T>>::block_number()))
+			.longevity(Self::unsigned_longevity())
+			.propagate(true)
+			.build()
+	}
+
+	fn unsigned_longevity() -> TransactionLongevity {
+		T::OffchainScanInterval::get().saturated_into::<u64>().max(1)
+	}
+}
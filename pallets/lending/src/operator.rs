@@ -0,0 +1,58 @@
+use crate::*;
+use frame_support::{
+	dispatch::{extract_actual_weight, GetDispatchInfo},
+	traits::{Contains, UnfilteredDispatchable},
+};
+
+/// The scope of the calls an operator may dispatch for the account which approved it
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum OperatorPermissions {
+	/// Repaying the borrows of the account, with the assets of the account
+	RepayOnly,
+	/// Repaying the borrows of the account and moving their collaterals in and out, without
+	/// borrowing nor withdrawing any deposit
+	ManageCollateral,
+	/// All the calls of the account but the approvals of operators
+	Full,
+}
+
+impl OperatorPermissions {
+	/// Returns whether the permissions allow an operator to dispatch `call`
+	pub fn allows<T: Config>(&self, call: &Call<T>) -> bool {
+		match self {
+			Self::RepayOnly => matches!(call, Call::repay { .. } | Call::repay_many { .. }),
+			Self::ManageCollateral => matches!(
+				call,
+				Call::repay { .. } |
+					Call::repay_many { .. } |
+					Call::add_collateral { .. } |
+					Call::self_liquidate { .. }
+			),
+			// an operator can not approve other operators, nor act through them
+			Self::Full => !matches!(call, Call::approve_operator { .. } | Call::operate { .. }),
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Dispatches `call` as `owner`, on behalf of which `operator` must be approved with the
+	/// permissions to. The `MaintenanceFilter`, bypassed by the dispatch, is applied to `call`.
+	/// Returns the weight of the operation.
+	pub fn do_operate(
+		operator: &T::AccountId,
+		owner: &T::AccountId,
+		call: Call<T>,
+	) -> Result<Weight, DispatchError> {
+		let permissions =
+			Operators::<T>::get(owner, operator).ok_or(Error::<T>::OperatorNotApproved)?;
+		ensure!(permissions.allows(&call), Error::<T>::OperatorNotPermitted);
+		ensure!(MaintenanceFilter::<T>::contains(&call), frame_system::Error::<T>::CallFiltered);
+
+		let info = call.get_dispatch_info();
+		let result =
+			call.dispatch_bypass_filter(frame_system::RawOrigin::Signed(owner.clone()).into());
+		let weight = T::WeightInfo::operate().saturating_add(extract_actual_weight(&result, &info));
+		result.map_err(|e| e.error)?;
+		Ok(weight)
+	}
+}
@@ -0,0 +1,203 @@
+use crate::*;
+use frame_support::traits::{
+	schedule::{
+		v3::{Named as ScheduleNamed, TaskName},
+		DispatchTime, HIGHEST_PRIORITY,
+	},
+	StorePreimage,
+};
+
+/// The id of a parameter change announced by `announce_param_change`
+pub type ParamChangeId = u32;
+
+/// A change of the risk parameters of a lending pool
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum ParamChange {
+	/// The collateral factor, i.e. the loan-to-value ratio the pool lends up to
+	CollateralFactor(Ratio),
+	/// The loan-to-value ratio the borrows of the pool are liquidated above
+	LiquidationThreshold(Ratio),
+	/// The interest rate model
+	RateModel(InterestRateModel),
+}
+
+/// A risk parameter of the lending pools the governance can override with
+/// `set_risk_parameter`, in effect in place of the one stored in the pool
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum RiskParameter {
+	/// The collateral factor, capped to the liquidation threshold in effect
+	CollateralFactor,
+	/// The liquidation threshold
+	LiquidationThreshold,
+	/// The share of the interest going to the reserves
+	ReserveFactor,
+}
+
+/// An update of the parameters of a lending pool applied at once by `force_update_pool_params`,
+/// the parameters left to `None` are unchanged
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default)]
+pub struct PoolParamsUpdate<Balance> {
+	pub collateral_factor: Option<Ratio>,
+	pub liquidation_threshold: Option<Ratio>,
+	pub reserve_factor: Option<Ratio>,
+	pub borrow_fee: Option<Ratio>,
+	pub min_borrow: Option<Balance>,
+	/// The cap on the outflows of the pool, `Some(None)` lifts it
+	pub outflow_cap: Option<Option<Balance>>,
+	/// The cap on the balance borrowed from the pool, `Some(None)` lifts it
+	pub borrow_cap: Option<Option<Balance>>,
+}
+
+/// A parameter change of the lending pool of `asset`, taking effect at block `execute_at`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PendingParamChange<AssetId, BlockNumber> {
+	pub asset: AssetId,
+	pub change: ParamChange,
+	pub execute_at: BlockNumber,
+}
+
+impl<T: Config> LendingPool<T> {
+	/// The collateral factor in effect, capped to the liquidation threshold in effect
+	pub fn collateral_factor(&self) -> Ratio {
+		RiskParameterOverrides::<T>::get(self.lend_token_id, RiskParameter::CollateralFactor)
+			.unwrap_or(self.collateral_factor)
+			.min(self.liquidation_threshold())
+	}
+
+	/// The liquidation threshold in effect
+	pub fn liquidation_threshold(&self) -> Ratio {
+		RiskParameterOverrides::<T>::get(self.lend_token_id, RiskParameter::LiquidationThreshold)
+			.unwrap_or(self.liquidation_threshold)
+	}
+
+	/// The reserve factor in effect
+	pub fn reserve_factor(&self) -> Ratio {
+		RiskParameterOverrides::<T>::get(self.lend_token_id, RiskParameter::ReserveFactor)
+			.unwrap_or(self.reserve_factor)
+	}
+
+	/// Applies `change` to the parameters of the pool, which must remain consistent: the
+	/// collateral factor can not exceed the liquidation threshold, and the kink of the rate
+	/// model must lie in (0, 1].
+	pub(crate) fn apply_param_change(&mut self, change: &ParamChange) -> Result<(), Error<T>> {
+		match change {
+			ParamChange::CollateralFactor(factor) => self.collateral_factor = *factor,
+			ParamChange::LiquidationThreshold(threshold) => self.liquidation_threshold = *threshold,
+			ParamChange::RateModel(model) => self.interest_model = model.clone(),
+		}
+		ensure!(
+			self.collateral_factor <= self.liquidation_threshold && self.interest_model.is_valid(),
+			Error::<T>::InvalidRiskParameters
+		);
+		Ok(())
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The name the execution of the parameter change `id` is scheduled under
+	fn param_change_task(id: ParamChangeId) -> TaskName {
+		(b"kylix/param-change", id).using_encoded(sp_core::hashing::blake2_256)
+	}
+
+	/// Checks `change` against the current parameters of the lending pool of `asset`, and
+	/// schedules `execute_param_change` with a root origin `ParamChangeDelay` blocks from now.
+	/// Returns the id of the change and the block it takes effect at.
+	pub fn do_announce_param_change(
+		asset: AssetIdOf<T>,
+		change: ParamChange,
+	) -> Result<(ParamChangeId, BlockNumberFor<T>), DispatchError> {
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.apply_param_change(&change)?;
+
+		let id = NextParamChangeId::<T>::get();
+		let execute_at =
+			frame_system::Pallet::<T>::block_number().saturating_add(T::ParamChangeDelay::get());
+		let call = <T as Config>::RuntimeCall::from(Call::<T>::execute_param_change { id });
+		T::Scheduler::schedule_named(
+			Self::param_change_task(id),
+			DispatchTime::At(execute_at),
+			None,
+			HIGHEST_PRIORITY,
+			frame_system::RawOrigin::Root.into(),
+			T::Preimages::bound(call)?,
+		)?;
+
+		PendingParamChanges::<T>::insert(id, PendingParamChange { asset, change, execute_at });
+		NextParamChangeId::<T>::put(id.checked_add(1).ok_or(Error::<T>::OverflowError)?);
+		Ok((id, execute_at))
+	}
+
+	/// Applies the parameter change `id` to its lending pool once due, after accruing the
+	/// interest of the pool at its former parameters. Returns the change applied.
+	pub fn do_execute_param_change(
+		id: ParamChangeId,
+	) -> Result<PendingParamChange<AssetIdOf<T>, BlockNumberFor<T>>, DispatchError> {
+		let pending =
+			PendingParamChanges::<T>::take(id).ok_or(Error::<T>::ParamChangeDoesNotExist)?;
+		ensure!(
+			frame_system::Pallet::<T>::block_number() >= pending.execute_at,
+			Error::<T>::ParamChangeNotDue
+		);
+
+		let asset_pool = AssetPool::<T>::from(pending.asset);
+		let mut pool =
+			LendingPoolStorage::<T>::get(&asset_pool).ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+		pool.apply_param_change(&pending.change)?;
+		LendingPoolStorage::<T>::insert(&asset_pool, pool);
+		Ok(pending)
+	}
+
+	/// Applies `update` to the lending pool of `asset` without any delay, after accruing the
+	/// interest of the pool at its former parameters. The parameters must remain consistent.
+	pub fn do_force_update_pool_params(
+		asset: AssetIdOf<T>,
+		update: &PoolParamsUpdate<AssetBalanceOf<T>>,
+	) -> DispatchResult {
+		let asset_pool = AssetPool::<T>::from(asset);
+		let mut pool =
+			LendingPoolStorage::<T>::get(&asset_pool).ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+		if let Some(factor) = update.collateral_factor {
+			pool.collateral_factor = factor;
+		}
+		if let Some(threshold) = update.liquidation_threshold {
+			pool.liquidation_threshold = threshold;
+		}
+		if let Some(factor) = update.reserve_factor {
+			pool.reserve_factor = factor;
+		}
+		if let Some(fee) = update.borrow_fee {
+			ensure!(fee < Ratio::one(), Error::<T>::InvalidBorrowFee);
+			pool.borrow_fee = fee;
+		}
+		ensure!(
+			pool.collateral_factor <= pool.liquidation_threshold,
+			Error::<T>::InvalidRiskParameters
+		);
+		LendingPoolStorage::<T>::insert(&asset_pool, pool);
+
+		if let Some(min_borrow) = update.min_borrow {
+			MinBorrow::<T>::insert(asset, min_borrow);
+		}
+		if let Some(cap) = update.outflow_cap {
+			OutflowCaps::<T>::set(asset, cap);
+		}
+		if let Some(cap) = update.borrow_cap {
+			BorrowCaps::<T>::set(asset, cap);
+		}
+		Ok(())
+	}
+
+	/// Drops the parameter change `id`, and its scheduled execution if still to come
+	pub fn do_cancel_param_change(id: ParamChangeId) -> DispatchResult {
+		let pending =
+			PendingParamChanges::<T>::take(id).ok_or(Error::<T>::ParamChangeDoesNotExist)?;
+		// a change whose execution failed is no longer scheduled
+		if frame_system::Pallet::<T>::block_number() < pending.execute_at {
+			T::Scheduler::cancel_named(Self::param_change_task(id))?;
+		}
+		Ok(())
+	}
+}
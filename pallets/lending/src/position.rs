@@ -0,0 +1,170 @@
+use crate::*;
+use frame_support::traits::tokens::nonfungibles_v2;
+
+/// The id of a borrow wrapped into a position NFT, also the id of the item
+pub type PositionId = u32;
+
+/// Mints, moves and burns the NFTs of the wrapped positions, e.g. the items of a collection of
+/// the `pallet-nfts` through the `NonfungiblesPositions` adapter.
+pub trait PositionNfts<AccountId> {
+	/// Mints the item `id` to `who`. The item can only be moved by `transfer`.
+	fn mint(id: PositionId, who: &AccountId) -> DispatchResult;
+	/// Burns the item `id`
+	fn burn(id: PositionId) -> DispatchResult;
+	/// The owner of the item `id`, if it exists
+	fn owner(id: PositionId) -> Option<AccountId>;
+	/// Moves the item `id` to `dest`
+	fn transfer(id: PositionId, dest: &AccountId) -> DispatchResult;
+}
+
+/// The positions can not be wrapped
+impl<AccountId> PositionNfts<AccountId> for () {
+	fn mint(_id: PositionId, _who: &AccountId) -> DispatchResult {
+		Err(DispatchError::Other("the positions can not be wrapped"))
+	}
+	fn burn(_id: PositionId) -> DispatchResult {
+		Err(DispatchError::Other("the positions can not be wrapped"))
+	}
+	fn owner(_id: PositionId) -> Option<AccountId> {
+		None
+	}
+	fn transfer(_id: PositionId, _dest: &AccountId) -> DispatchResult {
+		Err(DispatchError::Other("the positions can not be wrapped"))
+	}
+}
+
+/// The position NFTs as the items of the `Collection` of a `nonfungibles_v2` implementation,
+/// e.g. the `pallet-nfts`. The collection must exist. The items are locked, so that they are
+/// only moved by `transfer_position` and its health check.
+pub struct NonfungiblesPositions<Nfts, Collection, ItemConfig>(
+	PhantomData<(Nfts, Collection, ItemConfig)>,
+);
+
+impl<AccountId, Nfts, Collection, ItemConfig> PositionNfts<AccountId>
+	for NonfungiblesPositions<Nfts, Collection, ItemConfig>
+where
+	Nfts: nonfungibles_v2::Inspect<AccountId, ItemId = PositionId>
+		+ nonfungibles_v2::Mutate<AccountId, ItemConfig>
+		+ nonfungibles_v2::Transfer<AccountId>,
+	Collection: Get<Nfts::CollectionId>,
+	ItemConfig: Default,
+{
+	fn mint(id: PositionId, who: &AccountId) -> DispatchResult {
+		Nfts::mint_into(&Collection::get(), &id, who, &ItemConfig::default(), true)?;
+		Nfts::disable_transfer(&Collection::get(), &id)
+	}
+	fn burn(id: PositionId) -> DispatchResult {
+		Nfts::burn(&Collection::get(), &id, None)
+	}
+	fn owner(id: PositionId) -> Option<AccountId> {
+		Nfts::owner(&Collection::get(), &id)
+	}
+	fn transfer(id: PositionId, dest: &AccountId) -> DispatchResult {
+		Nfts::enable_transfer(&Collection::get(), &id)?;
+		Nfts::transfer(&Collection::get(), &id, dest)?;
+		Nfts::disable_transfer(&Collection::get(), &id)
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The account holding the borrow wrapped into the position `id`, on behalf of the owner of
+	/// its NFT
+	pub fn position_account_id(id: PositionId) -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating((b"position", id))
+	}
+
+	/// Moves the borrow of `from` in `asset` against `collateral_asset` to `to`, with its stable
+	/// rate, maturity and margin call. The collateral stays in the pool account, the native
+	/// collateral moves on hold to `to`.
+	fn move_borrow(
+		from: &T::AccountId,
+		to: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> DispatchResult {
+		ensure!(
+			!Borrows::<T>::contains_key((to, asset, collateral_asset)),
+			Error::<T>::BorrowAlreadyExists
+		);
+		Self::ensure_not_retired(from, asset, collateral_asset)?;
+		let loan = Borrows::<T>::take((from, asset, collateral_asset))
+			.ok_or(Error::<T>::LoanDoesNotExists)?;
+		Self::move_collateral(from, to, collateral_asset, loan.collateral_balance)?;
+		Borrows::<T>::insert((to, asset, collateral_asset), loan);
+		if let Some(rate) = StableBorrows::<T>::take((from, asset, collateral_asset)) {
+			StableBorrows::<T>::insert((to, asset, collateral_asset), rate);
+		}
+		if let Some(maturity) = BorrowMaturities::<T>::take((from, asset, collateral_asset)) {
+			BorrowMaturities::<T>::insert((to, asset, collateral_asset), maturity);
+		}
+		if let Some(called_at) = MarginCalls::<T>::take((from, asset, collateral_asset)) {
+			MarginCalls::<T>::insert((to, asset, collateral_asset), called_at);
+		}
+		AccountBorrows::<T>::mutate(from, |borrows| {
+			borrows.retain(|borrow| borrow != &(asset, collateral_asset))
+		});
+		AccountBorrows::<T>::try_append(to, (asset, collateral_asset))
+			.map_err(|_| Error::<T>::TooManyMarkets)?;
+		Ok(())
+	}
+
+	/// Ensures that `who` owns the NFT of the position `id`. Returns the key of its borrow.
+	fn ensure_position_owner(
+		who: &T::AccountId,
+		id: PositionId,
+	) -> Result<(AssetIdOf<T>, AssetIdOf<T>), DispatchError> {
+		let key = Positions::<T>::get(id).ok_or(Error::<T>::PositionDoesNotExist)?;
+		ensure!(T::PositionNfts::owner(id).as_ref() == Some(who), Error::<T>::NotPositionOwner);
+		Ok(key)
+	}
+
+	/// Wraps the healthy borrow of `who` in `asset` against `collateral_asset` into a new
+	/// position, whose NFT is minted to `who`. Returns the id of the position.
+	pub fn do_wrap_position(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<PositionId, DispatchError> {
+		ensure!(
+			Self::shortfall(who, asset, collateral_asset)?.is_zero(),
+			Error::<T>::AccountHasShortfall
+		);
+		let id = NextPositionId::<T>::get();
+		Self::move_borrow(who, &Self::position_account_id(id), asset, collateral_asset)?;
+		T::PositionNfts::mint(id, who)?;
+		Positions::<T>::insert(id, (asset, collateral_asset));
+		NextPositionId::<T>::put(id.checked_add(1).ok_or(Error::<T>::OverflowError)?);
+		Ok(id)
+	}
+
+	/// Transfers the position `id` of `who` to `dest`, as long as its borrow has no shortfall.
+	/// A position whose borrow was repaid or liquidated is transferred as it is.
+	pub fn do_transfer_position(
+		who: &T::AccountId,
+		id: PositionId,
+		dest: &T::AccountId,
+	) -> DispatchResult {
+		let (asset, collateral_asset) = Self::ensure_position_owner(who, id)?;
+		let account = Self::position_account_id(id);
+		if Borrows::<T>::contains_key((&account, asset, collateral_asset)) {
+			ensure!(
+				Self::shortfall(&account, asset, collateral_asset)?.is_zero(),
+				Error::<T>::AccountHasShortfall
+			);
+		}
+		T::PositionNfts::transfer(id, dest)
+	}
+
+	/// Burns the NFT of the position `id` of `who`, and moves its borrow, if still open, to
+	/// `who`
+	pub fn do_unwrap_position(who: &T::AccountId, id: PositionId) -> DispatchResult {
+		let (asset, collateral_asset) = Self::ensure_position_owner(who, id)?;
+		let account = Self::position_account_id(id);
+		if Borrows::<T>::contains_key((&account, asset, collateral_asset)) {
+			Self::move_borrow(&account, who, asset, collateral_asset)?;
+		}
+		T::PositionNfts::burn(id)?;
+		Positions::<T>::remove(id);
+		Ok(())
+	}
+}
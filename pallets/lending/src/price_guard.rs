@@ -0,0 +1,69 @@
+use crate::*;
+use frame_support::sp_std::vec::Vec;
+
+/// A secondary source of the prices the `AssetPrices` are checked against, e.g. another oracle
+/// or the time-weighted prices of a DEX, readable on-chain so that the reports of the offchain
+/// worker can be re-validated
+pub trait PriceReference<AssetId> {
+	/// The price of a unit of `asset` in `quote`, `None` when the source has none
+	fn price(asset: AssetId, quote: AssetId) -> Option<Rate>;
+}
+
+/// No secondary source is available, no price deviates
+impl<AssetId> PriceReference<AssetId> for () {
+	fn price(_asset: AssetId, _quote: AssetId) -> Option<Rate> {
+		None
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Returns the price of `asset` in `quote` and its reference when they deviate from each
+	/// other by more than the `MaxPriceDeviation` of the reference
+	pub fn price_deviation(asset: AssetIdOf<T>, quote: AssetIdOf<T>) -> Option<(Rate, Rate)> {
+		let price = AssetPrices::<T>::get((asset, quote))?;
+		let reference = T::PriceReference::price(asset, quote)?;
+		let deviation = match price > reference {
+			true => price.saturating_sub(reference),
+			false => reference.saturating_sub(price),
+		};
+		match deviation.checked_div(&reference) {
+			Some(deviation) if deviation <= Rate::from(T::MaxPriceDeviation::get()) => None,
+			_ => Some((price, reference)),
+		}
+	}
+
+	/// Returns the lending pools of `asset` and `quote` which are not paused yet
+	pub(crate) fn pools_to_pause(asset: AssetIdOf<T>, quote: AssetIdOf<T>) -> Vec<AssetIdOf<T>> {
+		[asset, quote]
+			.into_iter()
+			.filter(|asset| {
+				LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(*asset)) &&
+					!PausedPools::<T>::get(asset)
+			})
+			.collect()
+	}
+
+	/// Pauses the borrows from and the withdrawals of the lending pools of `asset` and `quote`
+	/// when the price of `asset` in `quote` deviates from its reference. Returns the price, its
+	/// reference and the pools paused.
+	pub fn do_report_price_deviation(
+		asset: AssetIdOf<T>,
+		quote: AssetIdOf<T>,
+	) -> Result<(Rate, Rate, Vec<AssetIdOf<T>>), DispatchError> {
+		let (price, reference) =
+			Self::price_deviation(asset, quote).ok_or(Error::<T>::PriceWithinDeviation)?;
+		let pools = Self::pools_to_pause(asset, quote);
+		ensure!(!pools.is_empty(), Error::<T>::PoolPaused);
+		for pool in &pools {
+			PausedPools::<T>::insert(pool, true);
+		}
+		Ok((price, reference, pools))
+	}
+
+	/// Ensures that the borrows from and the withdrawals of the lending pool of `asset` are not
+	/// paused
+	pub(crate) fn ensure_not_paused(asset: AssetIdOf<T>) -> DispatchResult {
+		ensure!(!PausedPools::<T>::get(asset), Error::<T>::PoolPaused);
+		Ok(())
+	}
+}
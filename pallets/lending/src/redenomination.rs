@@ -0,0 +1,249 @@
+use crate::*;
+use frame_support::sp_std::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+	/// Remaps the lending pool of `old` to the asset `new`, e.g. when a bridged asset is
+	/// re-registered under another id or redenominated, a balance of `old` being worth `ratio`
+	/// times as much of `new`. All the borrows, markets of the accounts, position NFTs, debt
+	/// allowances, supply positions, referral earnings and prices are read, as well as the
+	/// whitelist of the pool, up to `witness` entries. Returns the number of positions remapped
+	/// and of the entries read.
+	///
+	/// The pool keeps its id, i.e. its LP tokens, and its parameters. Its reserves, the debts and
+	/// principals of its borrows, the collaterals in `old`, the referral earnings, debt
+	/// allowances, minimum borrow, outflow cap and tier supply caps are converted at `ratio`, the
+	/// supply index of the pool and of its suppliers scaled by it so that the LP tokens are worth
+	/// as much of `new`. The holdings of `old` of the pool accounts and the rewards pot are burnt
+	/// and minted in `new`, and the prices of `old` are carried over to `new` at `ratio`, taking
+	/// the decimals of both assets into account.
+	///
+	/// The converted debts, as the borrowed balance of the pool, are rounded up, the converted
+	/// reserves and collaterals down. The assets used beyond the lending pools, e.g. as the
+	/// collateral of the CDPs, by the peg stability module or as the reward of a program, can not
+	/// be redenominated.
+	pub fn do_redenominate_pool(
+		old: AssetIdOf<T>,
+		new: AssetIdOf<T>,
+		ratio: Rate,
+		witness: u32,
+	) -> Result<(u32, u32), DispatchError> {
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(old))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		ensure!(
+			!LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(new)),
+			Error::<T>::LendingPoolAlreadyExists
+		);
+		ensure!(
+			old != new &&
+				!ratio.is_zero() &&
+				T::Fungibles::asset_exists(new) &&
+				!Self::is_native_asset(old) &&
+				!Self::is_native_asset(new),
+			Error::<T>::InvalidRedenomination
+		);
+		ensure!(!Self::used_beyond_lending_pools(old), Error::<T>::InvalidRedenomination);
+
+		let convert = |balance: AssetBalanceOf<T>, rounding: Rounding| {
+			math::mul_rate(balance, ratio, rounding).ok_or(Error::<T>::OverflowError)
+		};
+		let remap = |asset: AssetIdOf<T>| if asset == old { new } else { asset };
+		let (mut remapped, mut entries) = (0u32, 0u32);
+
+		// the borrows
+		let borrows = Self::collect_witnessed(
+			Borrows::<T>::iter(),
+			|((_, asset, collateral_asset), _)| *asset == old || *collateral_asset == old,
+			&mut entries,
+			witness,
+		)?;
+		for ((who, asset, collateral_asset), mut borrow) in borrows {
+			if asset == old {
+				borrow.borrowed_asset = new;
+				borrow.borrowed_balance = convert(borrow.borrowed_balance, Rounding::Up)?;
+				borrow.principal = borrow
+					.principal
+					.map(|principal| convert(principal, Rounding::Up))
+					.transpose()?;
+			}
+			if collateral_asset == old {
+				borrow.collateral_asset = new;
+				borrow.collateral_balance = convert(borrow.collateral_balance, Rounding::Down)?;
+			}
+			let key = (who.clone(), asset, collateral_asset);
+			let new_key = (who, remap(asset), remap(collateral_asset));
+			Borrows::<T>::remove(&key);
+			Borrows::<T>::insert(&new_key, borrow);
+			StableBorrows::<T>::swap(&key, &new_key);
+			BorrowMaturities::<T>::swap(&key, &new_key);
+			MarginCalls::<T>::swap(&key, &new_key);
+			LiquidationAssignments::<T>::swap(&key, &new_key);
+			remapped = remapped.saturating_add(1);
+		}
+		let markets = Self::collect_witnessed(
+			AccountBorrows::<T>::iter(),
+			|(_, markets)| {
+				markets.iter().any(|(asset, collateral)| *asset == old || *collateral == old)
+			},
+			&mut entries,
+			witness,
+		)?;
+		for (who, mut markets) in markets {
+			for (asset, collateral_asset) in markets.iter_mut() {
+				*asset = remap(*asset);
+				*collateral_asset = remap(*collateral_asset);
+			}
+			AccountBorrows::<T>::insert(who, markets);
+		}
+		let positions = Self::collect_witnessed(
+			Positions::<T>::iter(),
+			|(_, (asset, collateral_asset))| *asset == old || *collateral_asset == old,
+			&mut entries,
+			witness,
+		)?;
+		for (id, (asset, collateral_asset)) in positions {
+			Positions::<T>::insert(id, (remap(asset), remap(collateral_asset)));
+		}
+		let allowances = Self::collect_witnessed(
+			DebtAllowances::<T>::iter(),
+			|((_, _, asset), _)| *asset == old,
+			&mut entries,
+			witness,
+		)?;
+		for ((owner, delegate, _), allowance) in allowances {
+			DebtAllowances::<T>::remove((&owner, &delegate, old));
+			let allowance = convert(allowance, Rounding::Down)?;
+			DebtAllowances::<T>::insert((owner, delegate, new), allowance);
+		}
+
+		// the suppliers, their LP tokens worth as much of `new` with the scaled supply index
+		let suppliers = Self::collect_witnessed(
+			SupplyIndexStorage::<T>::iter_keys(),
+			|(_, asset)| *asset == old,
+			&mut entries,
+			witness,
+		)?;
+		for (who, _) in suppliers {
+			let mut index = SupplyIndexStorage::<T>::take((&who, old));
+			index.supply_index =
+				index.supply_index.checked_mul(&ratio).ok_or(Error::<T>::OverflowError)?;
+			SupplyIndexStorage::<T>::insert((&who, new), index);
+			LastSupplies::<T>::swap((&who, old), (&who, new));
+			UserRewards::<T>::swap(&who, old, &who, new);
+			RewardVesting::<T>::swap(&who, old, &who, new);
+			AutoCompound::<T>::swap(&who, old, &who, new);
+			SupplyingSince::<T>::swap(&who, old, &who, new);
+			remapped = remapped.saturating_add(1);
+		}
+		let earnings = Self::collect_witnessed(
+			ReferralEarnings::<T>::iter(),
+			|(_, asset, _)| *asset == old,
+			&mut entries,
+			witness,
+		)?;
+		for (code, _, balance) in earnings {
+			ReferralEarnings::<T>::remove(code, old);
+			ReferralEarnings::<T>::insert(code, new, convert(balance, Rounding::Down)?);
+		}
+		let whitelisted = Self::collect_witnessed(
+			PoolWhitelist::<T>::drain_prefix(old),
+			|_| true,
+			&mut entries,
+			witness,
+		)?;
+		for (who, allowed) in whitelisted {
+			PoolWhitelist::<T>::insert(new, who, allowed);
+		}
+		let tier_terms: Vec<_> = PoolTierTerms::<T>::drain_prefix(old).collect();
+		for (tier, terms) in tier_terms {
+			let max_supply = terms
+				.max_supply
+				.map(|max_supply| convert(max_supply, Rounding::Down))
+				.transpose()?;
+			PoolTierTerms::<T>::insert(new, tier, TierTerms { max_supply });
+		}
+		let overrides: Vec<_> = RiskParameterOverrides::<T>::drain_prefix(old).collect();
+		for (parameter, value) in overrides {
+			RiskParameterOverrides::<T>::insert(new, parameter, value);
+		}
+
+		// the configuration of the pool
+		let min_borrow = MinBorrow::<T>::take(old);
+		if !min_borrow.is_zero() {
+			MinBorrow::<T>::insert(new, convert(min_borrow, Rounding::Up)?);
+		}
+		if let Some(cap) = OutflowCaps::<T>::take(old) {
+			OutflowCaps::<T>::insert(new, convert(cap, Rounding::Down)?);
+		}
+		if let Some(cap) = BorrowCaps::<T>::take(old) {
+			BorrowCaps::<T>::insert(new, convert(cap, Rounding::Down)?);
+		}
+		if let Some((outflow, at)) = PoolOutflows::<T>::take(old) {
+			PoolOutflows::<T>::insert(new, (convert(outflow, Rounding::Up)?, at));
+		}
+		RewardPrograms::<T>::swap(old, new);
+		StableRatePremium::<T>::swap(old, new);
+		BorrowRateBounds::<T>::swap(old, new);
+		RateSnapshots::<T>::swap(old, new);
+		LoyaltyTiers::<T>::swap(old, new);
+		PermissionedPools::<T>::swap(old, new);
+		PausedPools::<T>::swap(old, new);
+		AccountSupplyCaps::<T>::swap(old, new);
+		PoolTotals::<T>::swap(old, new);
+		LpTokenPools::<T>::insert(pool.id, new);
+
+		// the prices, a whole unit of `new` being worth `unit_ratio` whole units of `old`
+		let unit_ratio = math::convert(
+			Rate::one().into_inner(),
+			Self::asset_decimals(old),
+			Rate::one(),
+			ratio,
+			Self::asset_decimals(new),
+			Rounding::Down,
+		)
+		.map(Rate::from_inner)
+		.ok_or(Error::<T>::OverflowError)?;
+		let prices = Self::collect_witnessed(
+			AssetPrices::<T>::iter(),
+			|((a, b), _)| *a == old || *b == old,
+			&mut entries,
+			witness,
+		)?;
+		for ((asset, quote), price) in prices {
+			let mut price = Some(price);
+			if asset == old {
+				price = price.and_then(|price| price.checked_mul(&unit_ratio));
+			}
+			if quote == old {
+				price = price.and_then(|price| price.checked_div(&unit_ratio));
+			}
+			AssetPrices::<T>::remove((asset, quote));
+			AssetPrices::<T>::insert(
+				(remap(asset), remap(quote)),
+				price.ok_or(Error::<T>::OverflowError)?,
+			);
+		}
+
+		// the holdings of `old` of the pallet accounts
+		let holders = LendingPoolStorage::<T>::iter_values()
+			.map(|pool| Self::pool_account_id(pool.id))
+			.chain([Self::rewards_account_id()]);
+		for holder in holders.collect::<Vec<_>>() {
+			let balance = T::Fungibles::balance(old, &holder);
+			if !balance.is_zero() {
+				T::Fungibles::burn_from(old, &holder, balance, Precision::Exact, Fortitude::Force)?;
+				T::Fungibles::mint_into(new, &holder, convert(balance, Rounding::Down)?)?;
+			}
+		}
+
+		// the pool itself
+		LendingPoolStorage::<T>::remove(AssetPool::<T>::from(old));
+		pool.lend_token_id = new;
+		pool.reserve_balance = convert(pool.reserve_balance, Rounding::Down)?;
+		pool.borrowed_balance = convert(pool.borrowed_balance, Rounding::Up)?;
+		pool.supply_index =
+			pool.supply_index.checked_mul(&ratio).ok_or(Error::<T>::OverflowError)?;
+		Self::update_pool_totals(&pool);
+		LendingPoolStorage::<T>::insert(AssetPool::<T>::from(new), pool);
+		Ok((remapped, entries))
+	}
+}
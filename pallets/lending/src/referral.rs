@@ -0,0 +1,72 @@
+use crate::*;
+
+/// A referral code registered by its owner, e.g. `b"KYLIX"`
+pub type ReferralCode = BoundedVec<u8, ConstU32<16>>;
+
+impl<T: Config> Pallet<T> {
+	/// Registers the referral `code` owned by `who`.
+	pub fn do_register_referral_code(who: &T::AccountId, code: ReferralCode) -> DispatchResult {
+		ensure!(!code.is_empty(), Error::<T>::InvalidReferralCode);
+		ensure!(!ReferralCodes::<T>::contains_key(&code), Error::<T>::ReferralCodeAlreadyExists);
+		ReferralCodes::<T>::insert(code, who);
+		Ok(())
+	}
+
+	/// Attaches the referral `code` to `who`. The first attached code sticks, attaching another
+	/// one later on is a no-op.
+	pub fn attach_referral(who: &T::AccountId, code: Option<ReferralCode>) -> DispatchResult {
+		let Some(code) = code else { return Ok(()) };
+		let referrer =
+			ReferralCodes::<T>::get(&code).ok_or(Error::<T>::ReferralCodeDoesNotExist)?;
+		ensure!(&referrer != who, Error::<T>::SelfReferral);
+
+		if !Referrals::<T>::contains_key(who) {
+			Referrals::<T>::insert(who, code.clone());
+			Self::deposit_event(Event::ReferralAttached { who: who.clone(), code });
+		}
+		Ok(())
+	}
+
+	/// Credits the referrer of `who` with a `ReferralShare` of the protocol revenue on the
+	/// `interest` paid on a repayment, that is the `reserve_factor` share of it, and returns the
+	/// credited amount.
+	pub fn credit_referral(
+		who: &T::AccountId,
+		pool: &LendingPool<T>,
+		asset: AssetIdOf<T>,
+		interest: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, Error<T>> {
+		let Some(code) = Referrals::<T>::get(who) else {
+			return Ok(AssetBalanceOf::<T>::zero());
+		};
+
+		let share = T::ReferralShare::get().mul_floor(pool.reserve_factor().mul_floor(interest));
+		if !share.is_zero() {
+			ReferralEarnings::<T>::try_mutate(code, asset, |earnings| -> Result<(), Error<T>> {
+				*earnings = earnings.checked_add(&share).ok_or(Error::<T>::OverflowError)?;
+				Ok(())
+			})?;
+		}
+		Ok(share)
+	}
+
+	/// Pays the earnings of the referral `code` in `asset` out to its owner `who`.
+	pub fn do_claim_referral_earnings(
+		who: &T::AccountId,
+		code: ReferralCode,
+		asset: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let owner = ReferralCodes::<T>::get(&code).ok_or(Error::<T>::ReferralCodeDoesNotExist)?;
+		ensure!(&owner == who, Error::<T>::NotReferralCodeOwner);
+
+		let pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		let balance = ReferralEarnings::<T>::take(&code, asset);
+		ensure!(!balance.is_zero(), Error::<T>::NoReferralEarnings);
+
+		// the earnings were set aside from the interest paid into the pool account
+		let pool_account = Self::pool_account_id(pool.id);
+		T::Fungibles::transfer(asset, &pool_account, who, balance, Preservation::Expendable)?;
+		Ok(balance)
+	}
+}
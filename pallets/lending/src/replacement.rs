@@ -0,0 +1,302 @@
+use crate::*;
+use frame_support::{sp_std::vec::Vec, storage::with_storage_layer};
+
+/// The maximum number of accounts whose positions a single `migrate_positions` migrates
+pub const MAX_POSITION_MIGRATIONS: u32 = 16;
+
+impl<T: Config> Pallet<T> {
+	/// Replaces the lending pool of `asset` with a new pool of LP token `id`, e.g. to list the
+	/// asset again with new risk parameters. The new pool takes the parameters of the replaced
+	/// one, updated by `update`, and serves the asset from now on. The replaced pool is retired
+	/// along with its balances, its LP tokens and its borrows until `do_migrate_position` moves
+	/// them to the new pool. All the position NFTs, borrows, supply positions and referral
+	/// earnings are read, up to `witness` entries. Returns the number of borrows retired and of
+	/// the entries read.
+	///
+	/// The borrows of the retired pool stay in `Borrows`, owed to the retired pool as recorded
+	/// in `RetiredBorrows`: they can still be repaid and liquidated, but not increased. The
+	/// rewards of the suppliers are accrued before their supply indexes restart at the one of
+	/// the new pool. The pools with stable-rate, fixed-term or wrapped borrows, and those whose
+	/// LP tokens are used as collateral, can not be replaced.
+	pub fn do_replace_pool(
+		asset: AssetIdOf<T>,
+		id: AssetIdOf<T>,
+		update: &PoolParamsUpdate<AssetBalanceOf<T>>,
+		witness: u32,
+	) -> Result<(u32, u32), DispatchError> {
+		let asset_pool = AssetPool::<T>::from(asset);
+		let mut pool =
+			LendingPoolStorage::<T>::get(&asset_pool).ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		let old_pool = pool.id;
+		ensure!(!T::Fungibles::asset_exists(id), Error::<T>::IdAlreadyExists);
+		ensure!(!Self::used_beyond_lending_pools(old_pool), Error::<T>::PoolNotReplaceable);
+		pool.update_indexes()?;
+		let mut entries = 0u32;
+
+		let wrapped = Self::collect_witnessed(
+			Positions::<T>::iter_values(),
+			|(borrowed_asset, collateral_asset)| {
+				*borrowed_asset == asset || *collateral_asset == old_pool
+			},
+			&mut entries,
+			witness,
+		)?;
+		ensure!(wrapped.is_empty(), Error::<T>::PoolNotReplaceable);
+
+		// the borrows, owed to the retired pool until migrated
+		let borrows = Self::collect_witnessed(
+			Borrows::<T>::iter_keys(),
+			|(_, borrowed_asset, collateral_asset)| {
+				*borrowed_asset == asset || *collateral_asset == old_pool
+			},
+			&mut entries,
+			witness,
+		)?;
+		let mut retired = 0u32;
+		for key in borrows {
+			ensure!(
+				key.2 != old_pool &&
+					!StableBorrows::<T>::contains_key(&key) &&
+					!BorrowMaturities::<T>::contains_key(&key),
+				Error::<T>::PoolNotReplaceable
+			);
+			// the borrows still owed to a pool retired before stay with it
+			if !RetiredBorrows::<T>::contains_key(&key) {
+				RetiredBorrows::<T>::insert(key, old_pool);
+				retired += 1;
+			}
+		}
+
+		// the new pool, with the parameters of the replaced one
+		let mut replacement = LendingPool::<T>::from(id, asset, Zero::zero())?;
+		replacement.activated = pool.activated;
+		replacement.interest_model = pool.interest_model.clone();
+		replacement.reserve_factor = pool.reserve_factor;
+		replacement.exchange_rate = pool.exchange_rate;
+		replacement.collateral_factor = pool.collateral_factor;
+		replacement.liquidation_threshold = pool.liquidation_threshold;
+		replacement.borrow_fee = pool.borrow_fee;
+
+		// the rewards of the suppliers are accrued with their LP tokens of the replaced pool
+		let suppliers = Self::collect_witnessed(
+			SupplyIndexStorage::<T>::iter_keys(),
+			|(_, supplied_asset)| *supplied_asset == asset,
+			&mut entries,
+			witness,
+		)?;
+		for (who, _) in suppliers.iter() {
+			Self::accrue_rewards(who, asset)?;
+		}
+		let index = SupplyIndex::from(replacement.supply_index, Self::now_in_seconds());
+		for key in suppliers {
+			SupplyIndexStorage::<T>::insert(key, index.clone());
+		}
+		let earnings = Self::collect_witnessed(
+			ReferralEarnings::<T>::iter(),
+			|(_, earned_asset, _)| *earned_asset == asset,
+			&mut entries,
+			witness,
+		)?
+		.into_iter()
+		.try_fold(AssetBalanceOf::<T>::zero(), |total, (_, _, balance)| {
+			total.ensure_add(balance)
+		})?;
+
+		// the referral earnings are paid out of the account of the new pool
+		T::Fungibles::create(id, Self::account_id(), true, One::one())?;
+		let new_account = Self::pool_account_id(id);
+		Self::provide_for(&new_account);
+		if !earnings.is_zero() {
+			T::Fungibles::transfer(
+				asset,
+				&Self::pool_account_id(old_pool),
+				&new_account,
+				earnings,
+				Preservation::Expendable,
+			)?;
+		}
+		LpTokenPools::<T>::remove(old_pool);
+		LpTokenPools::<T>::insert(id, asset);
+		RetiredPools::<T>::insert(old_pool, pool);
+		Self::update_pool_totals(&replacement);
+		LendingPoolStorage::<T>::insert(&asset_pool, replacement);
+		Self::do_force_update_pool_params(asset, update)?;
+		Ok((retired, entries))
+	}
+
+	/// Migrates the position of `who` in the retired pool of LP token `old_pool` to the lending
+	/// pool `new_pool` of its asset, all at once and without repaying: the LP tokens of `who`
+	/// are exchanged for LP tokens of the new pool worth as much, and its borrows carried over
+	/// with their debts, principals and collaterals. The new pool buys the debts from the
+	/// retired pool, which pays the supply out to the new pool: the pool owing the difference
+	/// must hold it in its reserves. The borrows carried over must have no shortfall in the new
+	/// pool. Returns the balance supplied and the debt migrated.
+	///
+	/// The frozen LP tokens stay in the retired pool.
+	pub fn do_migrate_position(
+		who: &T::AccountId,
+		old_pool: AssetIdOf<T>,
+		new_pool: AssetIdOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let mut retired = RetiredPools::<T>::get(old_pool).ok_or(Error::<T>::PoolNotRetired)?;
+		let asset = retired.lend_token_id;
+		let asset_pool = AssetPool::<T>::from(asset);
+		let mut pool =
+			LendingPoolStorage::<T>::get(&asset_pool).ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		ensure!(pool.id == new_pool, Error::<T>::NotReplacementPool);
+		retired.update_indexes()?;
+		pool.update_indexes()?;
+		let (old_account, new_account) =
+			(Self::pool_account_id(old_pool), Self::pool_account_id(new_pool));
+
+		with_storage_layer(|| {
+			// the borrows, bought by the new pool at their debt
+			let mut debt = AssetBalanceOf::<T>::zero();
+			let borrows: Vec<_> = AccountBorrows::<T>::get(who)
+				.into_iter()
+				.map(|(borrowed_asset, collateral_asset)| {
+					(who.clone(), borrowed_asset, collateral_asset)
+				})
+				.filter(|key| RetiredBorrows::<T>::get(key) == Some(old_pool))
+				.collect();
+			for key in borrows.iter() {
+				RetiredBorrows::<T>::remove(key);
+				let mut borrow = Borrows::<T>::get(key).ok_or(Error::<T>::LoanDoesNotExists)?;
+				let owed = retired.repayable_amount(borrow.borrowed_balance)?;
+				let principal = borrow.principal.unwrap_or_default();
+				retired.borrowed_balance = retired.borrowed_balance.ensure_sub(principal)?;
+				pool.borrowed_balance = pool.borrowed_balance.ensure_add(principal)?;
+				debt = debt.ensure_add(owed)?;
+				borrow.borrowed_balance = pool.scaled_borrow_balance(owed, Rounding::Up)?;
+
+				// the native collateral is held in the account of `who`
+				if !Self::is_native_asset(borrow.collateral_asset) {
+					T::Fungibles::transfer(
+						borrow.collateral_asset,
+						&old_account,
+						&new_account,
+						borrow.collateral_balance,
+						Preservation::Expendable,
+					)?;
+				}
+				Borrows::<T>::insert(key, borrow);
+			}
+
+			// the LP tokens, exchanged for LP tokens of the new pool worth as much
+			let lp_tokens = Self::reducible_k_tokens(who, old_pool);
+			let supply = retired.accrued_deposit(lp_tokens)?;
+			ensure!(!borrows.is_empty() || !lp_tokens.is_zero(), Error::<T>::NothingToMigrate);
+			if !lp_tokens.is_zero() {
+				Self::accrue_rewards(who, asset)?;
+				T::Fungibles::burn_from(
+					old_pool,
+					who,
+					lp_tokens,
+					Precision::Exact,
+					Fortitude::Polite,
+				)?;
+				let minted = pool.scaled_supply_balance(supply, Rounding::Down)?;
+				Self::update_and_mint(who, asset, new_pool, minted, pool.supply_index)?;
+				Self::accrue_rewards(who, asset)?;
+			}
+
+			// the pools settle the debts bought against the supply paid out
+			let (payer, payee, net) = if supply >= debt {
+				(&mut retired, &mut pool, supply.ensure_sub(debt)?)
+			} else {
+				(&mut pool, &mut retired, debt.ensure_sub(supply)?)
+			};
+			ensure!(payer.reserve_balance >= net, Error::<T>::InsufficientLiquidity);
+			payer.reserve_balance = payer.reserve_balance.ensure_sub(net)?;
+			payee.reserve_balance = payee.reserve_balance.ensure_add(net)?;
+			if !net.is_zero() {
+				T::Fungibles::transfer(
+					asset,
+					&Self::pool_account_id(payer.id),
+					&Self::pool_account_id(payee.id),
+					net,
+					Preservation::Expendable,
+				)?;
+			}
+
+			RetiredPools::<T>::insert(old_pool, retired);
+			Self::update_pool_totals(&pool);
+			LendingPoolStorage::<T>::insert(&asset_pool, pool);
+			if !borrows.is_empty() {
+				Self::ensure_no_shortfall(who)?;
+			}
+			Ok((supply, debt))
+		})
+	}
+
+	/// Returns the lending pool the borrow of `who` in `asset` against `collateral_asset` is
+	/// owed to: the retired pool it was opened in until migrated, the pool of `asset` otherwise
+	pub(crate) fn borrow_pool(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<LendingPool<T>, Error<T>> {
+		match RetiredBorrows::<T>::get((who, asset, collateral_asset)) {
+			Some(old_pool) => RetiredPools::<T>::get(old_pool).ok_or(Error::<T>::PoolNotRetired),
+			None => LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+				.ok_or(Error::<T>::LendingPoolDoesNotExist),
+		}
+	}
+
+	/// Stores `pool` back, as a retired pool or as the lending pool of its asset
+	pub(crate) fn store_pool(pool: LendingPool<T>) {
+		if RetiredPools::<T>::contains_key(pool.id) {
+			RetiredPools::<T>::insert(pool.id, pool);
+		} else {
+			Self::update_pool_totals(&pool);
+			LendingPoolStorage::<T>::insert(AssetPool::<T>::from(pool.lend_token_id), pool);
+		}
+	}
+
+	/// Ensures that the borrow of `who` in `asset` against `collateral_asset` is not owed to a
+	/// retired pool, which only takes repayments and liquidations until the borrow is migrated
+	pub(crate) fn ensure_not_retired(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> DispatchResult {
+		ensure!(
+			!RetiredBorrows::<T>::contains_key((who, asset, collateral_asset)),
+			Error::<T>::BorrowNotMigrated
+		);
+		Ok(())
+	}
+
+	/// Collects the items of `iter` matching `filter`, counting all the items read in `entries`,
+	/// which may not exceed `witness`
+	pub(crate) fn collect_witnessed<I: Iterator>(
+		iter: I,
+		filter: impl Fn(&I::Item) -> bool,
+		entries: &mut u32,
+		witness: u32,
+	) -> Result<Vec<I::Item>, DispatchError> {
+		let mut matching = Vec::new();
+		for item in iter {
+			*entries = entries.saturating_add(1);
+			ensure!(*entries <= witness, Error::<T>::BadWitness);
+			if filter(&item) {
+				matching.push(item);
+			}
+		}
+		Ok(matching)
+	}
+
+	/// Returns whether `asset` is used beyond its lending pool, in a way a redenomination or a
+	/// replacement of the pool can not carry over
+	pub(crate) fn used_beyond_lending_pools(asset: AssetIdOf<T>) -> bool {
+		T::AssetsInUse::contains(&asset) ||
+			DexLpCollaterals::<T>::iter_values().any(|(a, b)| a == asset || b == asset) ||
+			RewardPrograms::<T>::iter_values().any(|program| program.reward_asset == asset) ||
+			!RewardLiabilities::<T>::get(asset).is_zero() ||
+			PendingParamChanges::<T>::iter_values().any(|pending| pending.asset == asset) ||
+			RetiredPools::<T>::iter_values().any(|pool| pool.lend_token_id == asset) ||
+			RetiredBorrows::<T>::iter_keys().any(|(_, borrowed, collateral_asset)| {
+				borrowed == asset || collateral_asset == asset
+			})
+	}
+}
@@ -0,0 +1,67 @@
+use crate::*;
+
+impl<T: Config> Pallet<T> {
+	/// The balance of `token` the account of `pool` owes: the reserves of the pool, the
+	/// collaterals of its borrows and the referral earnings in its asset, as checked by the
+	/// `try_state` of the pallet. Iterates the borrows and the referral earnings, which must
+	/// not number more than `witness`.
+	fn owed_by_pool(
+		pool: &LendingPool<T>,
+		token: AssetIdOf<T>,
+		witness: u32,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let asset = pool.lend_token_id;
+		let mut entries = 0u32;
+		let mut owed =
+			if token == asset { pool.reserve_balance } else { AssetBalanceOf::<T>::zero() };
+
+		for borrow in Borrows::<T>::iter_values() {
+			entries = entries.saturating_add(1);
+			ensure!(entries <= witness, Error::<T>::BadWitness);
+			if borrow.borrowed_asset == asset &&
+				borrow.collateral_asset == token &&
+				!Self::is_native_asset(token)
+			{
+				owed = owed.saturating_add(borrow.collateral_balance);
+			}
+		}
+		for (_, earned_asset, earnings) in ReferralEarnings::<T>::iter() {
+			entries = entries.saturating_add(1);
+			ensure!(entries <= witness, Error::<T>::BadWitness);
+			if earned_asset == asset && token == asset {
+				owed = owed.saturating_add(earnings);
+			}
+		}
+		Ok(owed)
+	}
+
+	/// Sweeps the balance of `token` held by the account of the lending pool of `asset`, or by
+	/// the pallet account if `None`, over what it owes to the `TreasuryAccount`. The pallet
+	/// account owes nothing since the balances moved to the pool accounts. Returns the account
+	/// swept and the balance rescued.
+	pub fn do_rescue_funds(
+		asset: Option<AssetIdOf<T>>,
+		token: AssetIdOf<T>,
+		witness: u32,
+	) -> Result<(T::AccountId, AssetBalanceOf<T>), DispatchError> {
+		let (account, owed) = match asset {
+			Some(asset) => {
+				let pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+					.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+				(Self::pool_account_id(pool.id), Self::owed_by_pool(&pool, token, witness)?)
+			},
+			None => (Self::account_id(), Zero::zero()),
+		};
+
+		let surplus = T::Fungibles::balance(token, &account).saturating_sub(owed);
+		ensure!(!surplus.is_zero(), Error::<T>::NothingToRescue);
+		T::Fungibles::transfer(
+			token,
+			&account,
+			&T::TreasuryAccount::get(),
+			surplus,
+			Preservation::Expendable,
+		)?;
+		Ok((account, surplus))
+	}
+}
@@ -0,0 +1,494 @@
+use crate::*;
+use frame_support::{sp_std::vec::Vec, storage::with_storage_layer};
+
+/// The maximum number of vesting schedules of the rewards of an account in a lending pool
+pub const MAX_VESTING_SCHEDULES: u32 = 8;
+
+/// Source of the governance tokens locked by accounts, used to boost their rewards.
+pub trait GovernanceLocks<AccountId, Balance> {
+	/// The amount of governance tokens locked by `who`
+	fn locked_balance(who: &AccountId) -> Balance;
+	/// The amount of governance tokens locked by all accounts
+	fn total_locked() -> Balance;
+}
+
+impl<AccountId, Balance: Zero> GovernanceLocks<AccountId, Balance> for () {
+	fn locked_balance(_who: &AccountId) -> Balance {
+		Balance::zero()
+	}
+	fn total_locked() -> Balance {
+		Balance::zero()
+	}
+}
+
+/// Vesting terms of a reward program, expressed in seconds.
+/// Nothing can be released before `cliff` has elapsed, and the claimed rewards unlock linearly
+/// until `duration` has elapsed.
+#[derive(
+	Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, PartialOrd, Default,
+)]
+pub struct VestingTerms {
+	pub cliff: Timestamp,
+	pub duration: Timestamp,
+}
+
+impl VestingTerms {
+	pub fn is_valid(&self) -> bool {
+		!self.duration.is_zero() && self.cliff <= self.duration
+	}
+}
+
+/// Definition of a reward program attached to a lending pool
+/// Rewards are distributed among the suppliers of the pool pro-rata to their working balances,
+/// i.e. their LP tokens boosted by their share of locked governance tokens
+#[derive(
+	Clone,
+	Encode,
+	Decode,
+	Eq,
+	PartialEq,
+	RuntimeDebug,
+	MaxEncodedLen,
+	TypeInfo,
+	PartialOrd,
+	DefaultNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RewardProgram<T: Config> {
+	pub reward_asset: AssetIdOf<T>, // the asset the rewards are paid in
+	pub supply_speed: AssetBalanceOf<T>, // the amount of rewards distributed per second
+	pub supply_index: Rate,         // accumulator of the rewards per working balance unit
+	pub last_accrued_at: Timestamp, // the timestamp of the last update of the index
+	pub vesting: Option<VestingTerms>, // claimed rewards vest if set, otherwise paid out
+	pub working_supply: AssetBalanceOf<T>, // the sum of the working balances of the suppliers
+	pub paused: bool,               // is the emission paused because the rewards pot ran dry?
+}
+
+impl<T: Config> RewardProgram<T> {
+	pub fn from(
+		reward_asset: AssetIdOf<T>,
+		supply_speed: AssetBalanceOf<T>,
+		vesting: Option<VestingTerms>,
+	) -> Self {
+		RewardProgram {
+			reward_asset,
+			supply_speed,
+			supply_index: Rate::zero(),
+			last_accrued_at: Pallet::<T>::now_in_seconds(),
+			vesting,
+			working_supply: AssetBalanceOf::<T>::zero(),
+			paused: false,
+		}
+	}
+
+	/// Updates the supply index as
+	/// 	supply_index = supply_index + supply_speed * duration / working_supply
+	///
+	/// The distributed rewards are recorded as liabilities of the rewards pot. If the pot can
+	/// not cover them, the emission of the program of `asset` gets paused instead.
+	pub fn accrue(&mut self, asset: AssetIdOf<T>) -> Result<(), Error<T>> {
+		let now = Pallet::<T>::now_in_seconds();
+		if now <= self.last_accrued_at {
+			return Ok(());
+		}
+		let dur = now - self.last_accrued_at;
+		self.last_accrued_at = now;
+
+		if self.paused || self.working_supply.is_zero() || self.supply_speed.is_zero() {
+			return Ok(());
+		}
+
+		let distributed = self
+			.supply_speed
+			.saturated_into::<u128>()
+			.checked_mul(dur as u128)
+			.ok_or(Error::<T>::OverflowError)?;
+		let liabilities = RewardLiabilities::<T>::get(self.reward_asset)
+			.checked_add(&distributed.saturated_into())
+			.ok_or(Error::<T>::OverflowError)?;
+		if !Pallet::<T>::rewards_pot_covers(self.reward_asset, liabilities) {
+			self.paused = true;
+			Pallet::<T>::deposit_event(Event::RewardEmissionPaused { asset });
+			return Ok(());
+		}
+		RewardLiabilities::<T>::insert(self.reward_asset, liabilities);
+
+		let delta = math::ratio(distributed, self.working_supply.saturated_into::<u128>())
+			.ok_or(Error::<T>::OverflowError)?;
+		self.supply_index =
+			self.supply_index.checked_add(&delta).ok_or(Error::<T>::OverflowError)?;
+		Ok(())
+	}
+}
+
+/// The reward distribution state of an account in a lending pool
+#[derive(
+	Clone,
+	Encode,
+	Decode,
+	Eq,
+	PartialEq,
+	RuntimeDebug,
+	MaxEncodedLen,
+	TypeInfo,
+	PartialOrd,
+	DefaultNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct UserReward<T: Config> {
+	pub supply_index: Rate, // the program index the rewards were last accrued at
+	pub accrued: AssetBalanceOf<T>, // the rewards accrued but not claimed yet
+	pub working_balance: AssetBalanceOf<T>, // the boosted balance the rewards accrue on
+}
+
+/// Claimed rewards locked in a vesting schedule, one per claim
+/// The schedule unlocks `total` linearly from `start` over `duration` seconds, nothing before
+/// `start + cliff`.
+#[derive(
+	Clone,
+	Encode,
+	Decode,
+	Eq,
+	PartialEq,
+	RuntimeDebug,
+	MaxEncodedLen,
+	TypeInfo,
+	PartialOrd,
+	DefaultNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct VestingSchedule<T: Config> {
+	pub reward_asset: AssetIdOf<T>,
+	pub total: AssetBalanceOf<T>,
+	pub released: AssetBalanceOf<T>,
+	pub start: Timestamp,
+	pub terms: VestingTerms,
+}
+
+impl<T: Config> VestingSchedule<T> {
+	/// Calculates the amount that can be released at `now` as
+	/// 	total * min(now - start, duration) / duration - released
+	pub fn releasable(&self, now: Timestamp) -> Result<AssetBalanceOf<T>, Error<T>> {
+		let elapsed = now.saturating_sub(self.start);
+		if elapsed < self.terms.cliff {
+			return Ok(AssetBalanceOf::<T>::zero());
+		}
+		let vested = if elapsed >= self.terms.duration {
+			self.total
+		} else {
+			math::mul_div(
+				self.total,
+				elapsed.saturated_into(),
+				self.terms.duration.saturated_into(),
+				Rounding::Down,
+			)
+			.ok_or(Error::<T>::OverflowError)?
+		};
+		vested.checked_sub(&self.released).ok_or(Error::<T>::OverflowError)
+	}
+
+	/// The amount which is still locked in the schedule
+	pub fn locked(&self) -> Result<AssetBalanceOf<T>, Error<T>> {
+		self.total.checked_sub(&self.released).ok_or(Error::<T>::OverflowError)
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Accrues the rewards of `who` in the lending pool of `asset` up to now, then refreshes
+	/// their working balance from their current LP tokens, governance locks and loyalty, and
+	/// checkpoints their LP tokens for the governance snapshots.
+	/// Must be called both before and after any change of the LP token balance of `who`.
+	pub fn accrue_rewards(who: &T::AccountId, asset: AssetIdOf<T>) -> DispatchResult {
+		// the balances of the kTokens change in between the accruals of the rewards
+		if let Some(pool) = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset)) {
+			Self::checkpoint_k_tokens(who, pool.id);
+			Self::track_supply(who, asset, pool.id);
+		}
+		let Some(mut program) = RewardPrograms::<T>::get(asset) else { return Ok(()) };
+		program.accrue(asset)?;
+
+		let pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		UserRewards::<T>::try_mutate(who, asset, |user| -> DispatchResult {
+			let delta = program
+				.supply_index
+				.checked_sub(&user.supply_index)
+				.ok_or(Error::<T>::OverflowError)?;
+			let earned = math::mul_rate(user.working_balance, delta, Rounding::Down)
+				.ok_or(Error::<T>::OverflowError)?;
+			user.accrued = user.accrued.checked_add(&earned).ok_or(Error::<T>::OverflowError)?;
+			user.supply_index = program.supply_index;
+
+			let working_balance = Self::working_balance(who, asset, pool.id)?;
+			program.working_supply = program
+				.working_supply
+				.checked_sub(&user.working_balance)
+				.ok_or(Error::<T>::OverflowError)?
+				.checked_add(&working_balance)
+				.ok_or(Error::<T>::OverflowError)?;
+			user.working_balance = working_balance;
+			Ok(())
+		})?;
+
+		RewardPrograms::<T>::insert(asset, program);
+		Ok(())
+	}
+
+	/// Calculates the working balance of `who` in the lending pool of `asset` with LP token
+	/// `lp_id` as
+	/// 	boost = total_supply * locked / total_locked * (1 - 1 / max_boost)
+	/// 	working_balance = min(balance / max_boost + boost, balance) * (1 + loyalty_bonus)
+	/// so that an account without locked governance tokens earns `1 / max_boost` of the
+	/// rewards of an account with the maximum boost, and a long-term supplier the bonus of its
+	/// loyalty tier on top.
+	fn working_balance(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		lp_id: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, Error<T>> {
+		let balance = T::Fungibles::balance(lp_id, who).saturated_into::<u128>();
+		let base_share = Rate::one()
+			.checked_div(&T::MaxRewardBoost::get().max(Rate::one()))
+			.ok_or(Error::<T>::OverflowError)?;
+		let mut working_balance =
+			math::mul_rate(balance, base_share, Rounding::Down).ok_or(Error::<T>::OverflowError)?;
+
+		let total_locked = T::GovernanceLocks::total_locked();
+		if !total_locked.is_zero() {
+			let locked_share = math::mul_div(
+				T::Fungibles::total_issuance(lp_id).saturated_into::<u128>(),
+				T::GovernanceLocks::locked_balance(who).saturated_into::<u128>(),
+				total_locked.saturated_into::<u128>(),
+				Rounding::Down,
+			)
+			.ok_or(Error::<T>::OverflowError)?;
+			let boost = math::mul_rate(locked_share, Rate::one() - base_share, Rounding::Down)
+				.ok_or(Error::<T>::OverflowError)?;
+			working_balance = working_balance.saturating_add(boost);
+		}
+
+		let working_balance = working_balance.min(balance);
+		let loyalty = Self::loyalty_bonus(who, asset).mul_floor(working_balance);
+		Ok(working_balance.saturating_add(loyalty).saturated_into())
+	}
+
+	/// Claims the rewards accrued by `who` in the lending pool of `asset` to `dest`.
+	/// Depending on the reward program, the rewards are either transferred to `dest` or
+	/// locked in a vesting schedule of `dest`. Returns the claimed amount.
+	pub fn do_claim_rewards(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		dest: &T::AccountId,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let program =
+			RewardPrograms::<T>::get(asset).ok_or(Error::<T>::RewardProgramDoesNotExist)?;
+		Self::accrue_rewards(who, asset)?;
+
+		let amount =
+			UserRewards::<T>::mutate(who, asset, |user| core::mem::take(&mut user.accrued));
+		if amount.is_zero() {
+			return Ok(amount);
+		}
+
+		match program.vesting {
+			Some(terms) => Self::vest_rewards(dest, asset, program.reward_asset, amount, terms)?,
+			None => Self::pay_rewards(dest, program.reward_asset, amount)?,
+		}
+
+		Self::deposit_event(Event::RewardsClaimed {
+			who: who.clone(),
+			dest: dest.clone(),
+			balance: amount,
+		});
+		Ok(amount)
+	}
+
+	/// Claims the rewards accrued by `who` in all the lending pools they participate in, up to
+	/// `MaxMarketsPerAccount` of them, to `dest`. Returns the number of lending pools claimed
+	/// from.
+	pub fn do_claim_all_rewards(
+		who: &T::AccountId,
+		dest: &T::AccountId,
+	) -> Result<u32, DispatchError> {
+		let assets: Vec<AssetIdOf<T>> = UserRewards::<T>::iter_key_prefix(who)
+			.take(T::MaxMarketsPerAccount::get() as usize)
+			.collect();
+		for asset in assets.iter() {
+			Self::do_claim_rewards(who, *asset, dest)?;
+		}
+		Ok(assets.len() as u32)
+	}
+
+	/// Transfers rewards from the rewards pot to `who` and settles the pot's liabilities.
+	fn pay_rewards(
+		who: &T::AccountId,
+		reward_asset: AssetIdOf<T>,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		T::Fungibles::transfer(
+			reward_asset,
+			&Self::rewards_account_id(),
+			who,
+			amount,
+			Preservation::Preserve,
+		)?;
+		RewardLiabilities::<T>::mutate(reward_asset, |liabilities| {
+			*liabilities = liabilities.saturating_sub(amount)
+		});
+		Ok(())
+	}
+
+	/// Returns true if the rewards pot holds enough `reward_asset` to cover `liabilities`.
+	pub fn rewards_pot_covers(reward_asset: AssetIdOf<T>, liabilities: AssetBalanceOf<T>) -> bool {
+		T::Fungibles::reducible_balance(
+			reward_asset,
+			&Self::rewards_account_id(),
+			Preservation::Preserve,
+			Fortitude::Polite,
+		) >= liabilities
+	}
+
+	/// Transfers `amount` of the reward asset of the program of `asset` from `who` to the
+	/// rewards pot. A paused emission resumes if the pot covers its liabilities again.
+	pub fn do_fund_rewards(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(amount > AssetBalanceOf::<T>::zero(), Error::<T>::AmountTooLow);
+		let mut program =
+			RewardPrograms::<T>::get(asset).ok_or(Error::<T>::RewardProgramDoesNotExist)?;
+		program.accrue(asset)?;
+
+		T::Fungibles::transfer(
+			program.reward_asset,
+			who,
+			&Self::rewards_account_id(),
+			amount,
+			Preservation::Expendable,
+		)?;
+
+		if program.paused &&
+			Self::rewards_pot_covers(
+				program.reward_asset,
+				RewardLiabilities::<T>::get(program.reward_asset),
+			) {
+			program.paused = false;
+			Self::deposit_event(Event::RewardEmissionResumed { asset });
+		}
+		RewardPrograms::<T>::insert(asset, program);
+		Ok(())
+	}
+
+	/// Locks claimed rewards into a vesting schedule of their own for `who`, so that the rewards
+	/// claimed earlier keep vesting on their schedules. Once `who` has `MAX_VESTING_SCHEDULES`
+	/// schedules, the rewards join its latest schedule, vesting on from its start.
+	fn vest_rewards(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		reward_asset: AssetIdOf<T>,
+		amount: AssetBalanceOf<T>,
+		terms: VestingTerms,
+	) -> DispatchResult {
+		if RewardVesting::<T>::contains_key(who, asset) {
+			Self::do_release_vested_rewards(who, asset)?;
+		}
+
+		RewardVesting::<T>::try_mutate(who, asset, |maybe_schedules| -> DispatchResult {
+			let schedules = maybe_schedules.get_or_insert_with(Default::default);
+			let schedule = VestingSchedule::<T> {
+				reward_asset,
+				total: amount,
+				released: AssetBalanceOf::<T>::zero(),
+				start: Self::now_in_seconds(),
+				terms,
+			};
+			if let Err(schedule) = schedules.try_push(schedule) {
+				let latest = schedules.last_mut().ok_or(Error::<T>::OverflowError)?;
+				latest.total = latest.total.ensure_add(schedule.total)?;
+			}
+			Ok(())
+		})?;
+
+		Self::deposit_event(Event::RewardsVested { who: who.clone(), asset, balance: amount });
+		Ok(())
+	}
+
+	/// Releases the vested part of the rewards of `who` in the lending pool of `asset`, over all
+	/// its vesting schedules. Returns the released amount.
+	pub fn do_release_vested_rewards(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut schedules =
+			RewardVesting::<T>::get(who, asset).ok_or(Error::<T>::NoVestedRewards)?;
+		let now = Self::now_in_seconds();
+		let mut amount = AssetBalanceOf::<T>::zero();
+		for schedule in schedules.iter_mut() {
+			let releasable = schedule.releasable(now)?;
+			schedule.released = schedule.released.ensure_add(releasable)?;
+			amount = amount.ensure_add(releasable)?;
+		}
+		if amount.is_zero() {
+			return Ok(amount);
+		}
+
+		Self::pay_rewards(who, schedules[0].reward_asset, amount)?;
+
+		schedules.retain(|schedule| schedule.total > schedule.released);
+		if schedules.is_empty() {
+			RewardVesting::<T>::remove(who, asset);
+		} else {
+			RewardVesting::<T>::insert(who, asset, schedules);
+		}
+
+		Self::deposit_event(Event::RewardsReleased { who: who.clone(), asset, balance: amount });
+		Ok(amount)
+	}
+
+	/// Claims the rewards accrued by `who` in the lending pool of `asset` and supplies them into
+	/// the lending pool of the reward asset, if there is an active one.
+	/// When compounded by a `keeper`, the keeper is paid a `CompoundTip` share of the rewards.
+	/// The claim is rolled back if the tip or the supply fails. Returns the supplied amount.
+	pub fn do_compound_rewards(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		keeper: Option<&T::AccountId>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		with_storage_layer(|| {
+			let program =
+				RewardPrograms::<T>::get(asset).ok_or(Error::<T>::RewardProgramDoesNotExist)?;
+			ensure!(program.vesting.is_none(), Error::<T>::CannotCompoundVestedRewards);
+
+			let mut amount = Self::do_claim_rewards(who, asset, who)?;
+			if let Some(keeper) = keeper {
+				let tip = T::CompoundTip::get().mul_floor(amount);
+				if !tip.is_zero() {
+					T::Fungibles::transfer(
+						program.reward_asset,
+						who,
+						keeper,
+						tip,
+						Preservation::Expendable,
+					)?;
+					amount = amount.ensure_sub(tip)?;
+				}
+			}
+
+			let reward_pool = AssetPool::<T>::from(program.reward_asset);
+			let compoundable =
+				LendingPoolStorage::<T>::get(&reward_pool).map_or(false, |pool| pool.is_active());
+			if amount.is_zero() || !compoundable {
+				return Ok(AssetBalanceOf::<T>::zero());
+			}
+
+			Self::do_supply(who, program.reward_asset, amount)?;
+			Self::deposit_event(Event::RewardsCompounded {
+				who: who.clone(),
+				asset: program.reward_asset,
+				balance: amount,
+			});
+			Ok(amount)
+		})
+	}
+}
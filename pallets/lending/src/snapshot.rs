@@ -0,0 +1,55 @@
+use crate::*;
+
+impl<T: Config> Pallet<T> {
+	/// Returns the current era of the kToken snapshots, the block number divided by the
+	/// `KTokenSnapshotEra`
+	pub fn k_token_era() -> BlockNumberFor<T> {
+		let era = T::KTokenSnapshotEra::get().max(One::one());
+		frame_system::Pallet::<T>::block_number() / era
+	}
+
+	/// Records the balance of the kTokens `lp_token` of `who` at the start of the current era,
+	/// on the first change of the balance in the era. The oldest checkpoint is dropped once
+	/// `MaxKTokenCheckpoints` are kept. Does nothing for the assets which are not kTokens.
+	pub(crate) fn checkpoint_k_tokens(who: &T::AccountId, lp_token: AssetIdOf<T>) {
+		if !LpTokenPools::<T>::contains_key(lp_token) {
+			return;
+		}
+		let era = Self::k_token_era();
+		KTokenCheckpoints::<T>::mutate(who, lp_token, |checkpoints| {
+			if checkpoints.last().is_some_and(|(at, _)| *at == era) {
+				return;
+			}
+			if checkpoints.len() as u32 >= T::MaxKTokenCheckpoints::get() && !checkpoints.is_empty()
+			{
+				checkpoints.remove(0);
+			}
+			let _ = checkpoints.try_push((era, T::Fungibles::balance(lp_token, who)));
+		});
+	}
+
+	/// Returns the balance of the kTokens `lp_token` of `who` at the start of `era`, so that the
+	/// supplied balances can be counted as governance voting power as of the start of a vote
+	/// without being withdrawn. `None` for the eras to come, and for the eras whose checkpoints
+	/// may have been dropped.
+	pub fn k_token_balance_at(
+		who: &T::AccountId,
+		lp_token: AssetIdOf<T>,
+		era: BlockNumberFor<T>,
+	) -> Option<AssetBalanceOf<T>> {
+		if era > Self::k_token_era() {
+			return None;
+		}
+		let checkpoints = KTokenCheckpoints::<T>::get(who, lp_token);
+		if checkpoints.len() as u32 >= T::MaxKTokenCheckpoints::get() &&
+			checkpoints.first().is_some_and(|(at, _)| era < *at)
+		{
+			return None;
+		}
+		// the balance did not change from the start of `era` up to its first checkpoint after
+		match checkpoints.iter().find(|(at, _)| *at >= era) {
+			Some((_, balance)) => Some(*balance),
+			None => Some(T::Fungibles::balance(lp_token, who)),
+		}
+	}
+}
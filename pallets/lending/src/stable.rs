@@ -0,0 +1,233 @@
+use crate::*;
+use frame_support::storage::with_storage_layer;
+
+/// The rate of a borrow at a stable rate, fixed at its origination. The debt of the borrow is
+/// scaled by an index of its own, compounding at `rate` since `updated_at`.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct StableRate {
+	/// The annual borrow rate
+	pub rate: Rate,
+	/// The index of the debt at `updated_at`
+	pub index: Rate,
+	pub updated_at: Timestamp,
+}
+
+impl StableRate {
+	/// Returns the index of the debt at `now`
+	pub fn index_at(&self, now: Timestamp) -> Option<Rate> {
+		let t = now.checked_sub(self.updated_at)?;
+		self.index.checked_mul(&math::compounded_interest(self.rate, t)?)
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Returns the index by which the debt of the borrow of `who` in `asset` against
+	/// `collateral_asset` is scaled: its own at a stable rate, the borrow index of the lending
+	/// pool otherwise.
+	pub(crate) fn debt_index(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		pool: &LendingPool<T>,
+	) -> Result<Rate, Error<T>> {
+		let Some(stable) = StableBorrows::<T>::get((who, asset, collateral_asset)) else {
+			return Ok(pool.borrow_index);
+		};
+		let now = Self::now_in_seconds();
+		let index = match BorrowMaturities::<T>::get((who, asset, collateral_asset)) {
+			// past its maturity, a fixed-term borrow accrues at the penalty rate on top
+			Some(maturity) if now > maturity => {
+				let rate = stable.rate.checked_add(&T::TermPenaltyRate::get());
+				stable.index_at(maturity).and_then(|index| {
+					index.checked_mul(&math::compounded_interest(rate?, now - maturity)?)
+				})
+			},
+			_ => stable.index_at(now),
+		};
+		index.ok_or(Error::<T>::OverflowError)
+	}
+
+	/// Returns whether the borrow of `who` in `asset` against `collateral_asset` is a fixed-term
+	/// borrow past its maturity and its `TermGracePeriod`
+	pub(crate) fn is_overdue(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> bool {
+		BorrowMaturities::<T>::get((who, asset, collateral_asset)).map_or(false, |maturity| {
+			Self::now_in_seconds() > maturity.saturating_add(T::TermGracePeriod::get())
+		})
+	}
+
+	/// Borrows at a stable rate for `term` seconds, provided that the rate is at most
+	/// `max_rate`, the borrow being rolled back otherwise. Returns the origination fee, the rate
+	/// and the maturity of the borrow.
+	pub fn do_borrow_fixed_term(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		balance: AssetBalanceOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		collateral_balance: AssetBalanceOf<T>,
+		term: Timestamp,
+		max_rate: Rate,
+	) -> Result<(AssetBalanceOf<T>, Rate, Timestamp), DispatchError> {
+		with_storage_layer(|| {
+			ensure!(term > 0, Error::<T>::InvalidBorrowTerm);
+			ensure!(
+				!Borrows::<T>::contains_key((who, asset, collateral_asset)),
+				Error::<T>::FixedTermBorrowNotExtendable
+			);
+			let fee =
+				Self::do_borrow(who, asset, balance, collateral_asset, collateral_balance, true)?;
+			let rate = StableBorrows::<T>::get((who, asset, collateral_asset))
+				.ok_or(Error::<T>::LoanDoesNotExists)?
+				.rate;
+			ensure!(rate <= max_rate, Error::<T>::BorrowRateTooHigh);
+
+			let maturity = Self::now_in_seconds().ensure_add(term)?;
+			BorrowMaturities::<T>::insert((who, asset, collateral_asset), maturity);
+			Ok((fee, rate, maturity))
+		})
+	}
+
+	/// Returns the debt of the borrow `loan` of `who` in `asset` against `collateral_asset`,
+	/// rounded up
+	pub(crate) fn repayable_balance(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		pool: &LendingPool<T>,
+		loan: &UserBorrow<T>,
+	) -> Result<AssetBalanceOf<T>, Error<T>> {
+		let index = Self::debt_index(who, asset, collateral_asset, pool)?;
+		math::mul_rate(loan.borrowed_balance, index, Rounding::Up).ok_or(Error::<T>::OverflowError)
+	}
+
+	/// Returns the stable rate of the new borrows from the lending pool of `asset`, priced off
+	/// its variable borrow rate plus its `StableRatePremium`
+	pub fn stable_borrow_rate(
+		asset: AssetIdOf<T>,
+		pool: &LendingPool<T>,
+	) -> Result<Rate, DispatchError> {
+		let premium =
+			StableRatePremium::<T>::get(asset).ok_or(Error::<T>::StableBorrowNotAllowed)?;
+		let rate = pool
+			.borrow_interest_rate()?
+			.checked_add(&premium.into())
+			.ok_or(Error::<T>::OverflowError)?;
+		Ok(rate)
+	}
+
+	/// Fixes the stable rate of the borrow of `who` in `asset` against `collateral_asset`, to
+	/// which `balance` is being added. The rate of the debt already borrowed is averaged with the
+	/// current stable rate, weighted by the balances.
+	pub(crate) fn fix_stable_rate(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+		pool: &LendingPool<T>,
+		balance: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let rate = Self::stable_borrow_rate(asset, pool)?;
+		let now = Self::now_in_seconds();
+		let stable = match (
+			StableBorrows::<T>::get((who, asset, collateral_asset)),
+			Borrows::<T>::get((who, asset, collateral_asset)),
+		) {
+			(Some(stable), Some(loan)) => {
+				let index = stable.index_at(now).ok_or(Error::<T>::OverflowError)?;
+				let debt = math::mul_rate(loan.borrowed_balance, index, Rounding::Up)
+					.ok_or(Error::<T>::OverflowError)?;
+				let weight = Rate::checked_from_rational(debt, debt.ensure_add(balance)?)
+					.ok_or(Error::<T>::OverflowError)?;
+				let rate = Rate::one()
+					.checked_sub(&weight)
+					.and_then(|w| rate.checked_mul(&w))
+					.and_then(|r| stable.rate.checked_mul(&weight)?.checked_add(&r))
+					.ok_or(Error::<T>::OverflowError)?;
+				StableRate { rate, index, updated_at: now }
+			},
+			_ => StableRate { rate, index: Rate::one(), updated_at: now },
+		};
+		StableBorrows::<T>::insert((who, asset, collateral_asset), stable);
+		Ok(())
+	}
+
+	/// Raises the stable rate of the borrow of `who` in `asset` against `collateral_asset` to
+	/// the current stable rate of the lending pool, once its utilisation reached the
+	/// `StableRateRebalanceUtilisation`. Returns the new rate.
+	pub fn do_rebalance_stable_rate(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<Rate, DispatchError> {
+		let mut stable = StableBorrows::<T>::get((who, asset, collateral_asset))
+			.ok_or(Error::<T>::LoanDoesNotExists)?;
+		ensure!(
+			!BorrowMaturities::<T>::contains_key((who, asset, collateral_asset)),
+			Error::<T>::FixedTermBorrowNotExtendable
+		);
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+		ensure!(
+			pool.utilisation_ratio()? >= T::StableRateRebalanceUtilisation::get(),
+			Error::<T>::StableRateNotRebalanceable
+		);
+		let rate = Self::stable_borrow_rate(asset, &pool)?;
+		ensure!(rate > stable.rate, Error::<T>::StableRateNotRebalanceable);
+
+		let now = Self::now_in_seconds();
+		stable.index = stable.index_at(now).ok_or(Error::<T>::OverflowError)?;
+		stable.rate = rate;
+		stable.updated_at = now;
+		StableBorrows::<T>::insert((who, asset, collateral_asset), stable);
+		Ok(rate)
+	}
+
+	/// Switches the borrow of `who` in `asset` against `collateral_asset` between the variable
+	/// and the stable rate. The debt accrued at the old rate until now is carried over to the new
+	/// one. Switching to the stable rate is not allowed once the utilisation of the pool reached
+	/// the `StableRateRebalanceUtilisation`. Returns the old and the new rate.
+	pub fn do_switch_borrow_rate_mode(
+		who: &T::AccountId,
+		asset: AssetIdOf<T>,
+		collateral_asset: AssetIdOf<T>,
+	) -> Result<(Rate, Rate), DispatchError> {
+		let mut loan = Borrows::<T>::get((who, asset, collateral_asset))
+			.ok_or(Error::<T>::LoanDoesNotExists)?;
+		Self::ensure_not_retired(who, asset, collateral_asset)?;
+		ensure!(
+			!BorrowMaturities::<T>::contains_key((who, asset, collateral_asset)),
+			Error::<T>::FixedTermBorrowNotExtendable
+		);
+		let mut pool = LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+			.ok_or(Error::<T>::LendingPoolDoesNotExist)?;
+		pool.update_indexes()?;
+		let debt = Self::repayable_balance(who, asset, collateral_asset, &pool, &loan)?;
+		let variable_rate = pool.borrow_interest_rate()?;
+
+		let rates = match StableBorrows::<T>::take((who, asset, collateral_asset)) {
+			Some(stable) => {
+				loan.borrowed_balance = pool.scaled_borrow_balance(debt, Rounding::Up)?;
+				(stable.rate, variable_rate)
+			},
+			None => {
+				ensure!(
+					pool.utilisation_ratio()? < T::StableRateRebalanceUtilisation::get(),
+					Error::<T>::StableBorrowNotAllowed
+				);
+				let rate = Self::stable_borrow_rate(asset, &pool)?;
+				let now = Self::now_in_seconds();
+				StableBorrows::<T>::insert(
+					(who, asset, collateral_asset),
+					StableRate { rate, index: Rate::one(), updated_at: now },
+				);
+				loan.borrowed_balance = debt;
+				(variable_rate, rate)
+			},
+		};
+		Borrows::<T>::insert((who, asset, collateral_asset), loan);
+		Ok(rates)
+	}
+}
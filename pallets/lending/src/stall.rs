@@ -0,0 +1,34 @@
+use crate::*;
+
+impl<T: Config> Pallet<T> {
+	/// Records the timestamp of the block in `on_finalize`, and starts the `StallGracePeriod`
+	/// when it comes more than `StallThreshold` after the previous block, i.e. the chain stalled,
+	/// e.g. with an outage of the relay chain.
+	pub(crate) fn detect_stall() {
+		let now = Self::now_in_seconds();
+		if let Some(last) = LastBlockTimestamp::<T>::get() {
+			let gap = now.saturating_sub(last);
+			let grace_period = T::StallGracePeriod::get();
+			if gap > T::StallThreshold::get() && !grace_period.is_zero() {
+				let until = now.saturating_add(grace_period);
+				StallGraceUntil::<T>::put(until);
+				Self::deposit_event(Event::ChainStallDetected { gap, until });
+			}
+		}
+		LastBlockTimestamp::<T>::put(now);
+	}
+
+	/// Returns whether the liquidations are suppressed by the grace period following a stall of
+	/// the chain, so that the borrowers unable to transact during the stall can restore the
+	/// health of their borrows first. The stall is detected from the first block after it.
+	pub fn stall_grace_period() -> bool {
+		let grace_period = T::StallGracePeriod::get();
+		if grace_period.is_zero() {
+			return false;
+		}
+		let now = Self::now_in_seconds();
+		LastBlockTimestamp::<T>::get()
+			.is_some_and(|last| now.saturating_sub(last) > T::StallThreshold::get()) ||
+			StallGraceUntil::<T>::get().is_some_and(|until| now < until)
+	}
+}
@@ -0,0 +1,49 @@
+use crate::*;
+
+/// The totals of the lending pools valued in the `BaseAsset`, as kept by `PoolTotals` for each
+/// pool and by `ProtocolStats` for all of them
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ProtocolTotals<Balance> {
+	pub supplied: Balance, // reserve + borrowed
+	pub borrowed: Balance, // the borrowed principal
+	pub reserves: Balance, // the liquidity available in the pools
+	pub bad_debt: Balance, // the debt written off
+}
+
+impl<T: Config> Pallet<T> {
+	/// Values the balances of `pool` at the current price of its asset into its `PoolTotals`,
+	/// and moves the `ProtocolStats` by the difference. The assets without a price are valued
+	/// at zero until their next update.
+	pub(crate) fn update_pool_totals(pool: &LendingPool<T>) {
+		let asset = pool.lend_token_id;
+		let value = |balance| Self::asset_value(asset, balance).unwrap_or_default();
+		let old = PoolTotals::<T>::get(asset);
+		let new = ProtocolTotals {
+			supplied: value(pool.reserve_balance.saturating_add(pool.borrowed_balance)),
+			borrowed: value(pool.borrowed_balance),
+			reserves: value(pool.reserve_balance),
+			bad_debt: old.bad_debt,
+		};
+		ProtocolStats::<T>::mutate(|stats| {
+			stats.supplied =
+				stats.supplied.saturating_sub(old.supplied).saturating_add(new.supplied);
+			stats.borrowed =
+				stats.borrowed.saturating_sub(old.borrowed).saturating_add(new.borrowed);
+			stats.reserves =
+				stats.reserves.saturating_sub(old.reserves).saturating_add(new.reserves);
+		});
+		PoolTotals::<T>::insert(asset, new);
+	}
+
+	/// Adds the value of `balance` of `asset`, written off, to the bad debt of its lending pool
+	/// and of the protocol
+	pub(crate) fn record_bad_debt(asset: AssetIdOf<T>, balance: AssetBalanceOf<T>) {
+		let value = Self::asset_value(asset, balance).unwrap_or_default();
+		PoolTotals::<T>::mutate(asset, |totals| {
+			totals.bad_debt = totals.bad_debt.saturating_add(value)
+		});
+		ProtocolStats::<T>::mutate(|stats| stats.bad_debt = stats.bad_debt.saturating_add(value));
+	}
+}
@@ -0,0 +1,37 @@
+use crate::*;
+
+/// Swaps assets on a DEX, e.g. the `pallet-asset-conversion` through its `Swap` trait.
+pub trait SwapProvider<AccountId, AssetId, Balance> {
+	/// Swaps at most `max_amount_in` of `asset_in` out of the account of `who` for exactly
+	/// `amount_out` of `asset_out`, credited to `dest`. The account of `who` may be emptied.
+	/// Returns the balance of `asset_in` spent.
+	fn swap_exact_out(
+		who: &AccountId,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount_out: Balance,
+		max_amount_in: Balance,
+		dest: &AccountId,
+	) -> Result<Balance, DispatchError>;
+
+	/// The weight of a swap
+	fn swap_weight() -> Weight;
+}
+
+/// No DEX is available
+impl<AccountId, AssetId, Balance> SwapProvider<AccountId, AssetId, Balance> for () {
+	fn swap_exact_out(
+		_who: &AccountId,
+		_asset_in: AssetId,
+		_asset_out: AssetId,
+		_amount_out: Balance,
+		_max_amount_in: Balance,
+		_dest: &AccountId,
+	) -> Result<Balance, DispatchError> {
+		Err(DispatchError::Other("no swap provider"))
+	}
+
+	fn swap_weight() -> Weight {
+		Weight::zero()
+	}
+}
@@ -1,38 +1,59 @@
-use crate::{mock::*, Error, LendingPool, LendingPoolId};
+use crate::{
+	math, mock::*, AccountBorrows, AccountTier, Action, AssetPool, AssetPrices, BorrowMaturities,
+	BorrowPosition, Borrows, Error, Event, FreezeReason, LastSupplies, Lending, LendingPool,
+	LendingPoolStorage, LpTokenPools, MarginCalls, PausedPools, PoolOutflows, PoolParamsUpdate,
+	PoolTotals, ProtocolStats, ProtocolTotals, RateSnapshots, ReferralCode, ReferralEarnings,
+	RetiredBorrows, RetiredPools, RewardLiabilities, RewardPrograms, RewardVesting, RiskParameter,
+	StableBorrows, SupplyIndexStorage, SupplyPosition, TierTerms, UserRewards, VestingTerms,
+};
 
-use frame_support::{assert_noop, assert_ok};
+use codec::Decode;
+use frame_support::{
+	assert_noop, assert_ok,
+	pallet_prelude::{InvalidTransaction, TransactionSource, ValidateUnsigned},
+	traits::{Get, Hooks},
+	weights::Weight,
+};
 //use frame_system::Origin;
 
+use sp_core::offchain::{testing::TestTransactionPoolExt, TransactionPoolExt};
 use sp_runtime::{
-	FixedU128,
-	//traits::BadOrigin,
-	Permill,
+	traits::BadOrigin, ArithmeticError, DispatchError, FixedPointNumber, FixedU128, Permill,
+	Rounding,
 };
 
 pub type Rate = FixedU128;
 //pub type Ratio = Permill;
 
 type Token = u32;
-type SignedOrigin = u64;
+type SignedOrigin = u128;
 type BalanceAmount = u128;
 
-const ALICE: SignedOrigin = 1u64;
-const BOB: SignedOrigin = 2u64;
+const ALICE: SignedOrigin = 1u128;
+const BOB: SignedOrigin = 2u128;
+const TREASURY: SignedOrigin = 99u128;
 
 const DOT: Token = 1u32;
+const KYL: Token = 2u32;
+const KYL_POOL_ID: Token = 12;
 
-const LENDING_POOL_ID: LendingPoolId = 0;
+const LENDING_POOL_ID: Token = 0;
 
 // Test helper for creating an account and minting a specific token
 //scaled balance use scaled_deposit fn
-fn setup_test_account(token: Token, address: u64, amount: BalanceAmount) {
+fn setup_test_account(token: Token, address: SignedOrigin, amount: BalanceAmount) {
 	let _ = TemplateModule::update_and_mint(&ALICE, token, LENDING_POOL_ID, balance(token, ALICE), sp_runtime::FixedU128::from(1));
 	let res = balance(token, address);
 	assert_eq!(res, amount);
 }
 
+// Test helper for creating an active DOT lending pool supplied by ALICE
+fn setup_active_pool(supply: BalanceAmount) {
+	create_active_pool(LENDING_POOL_ID, DOT, ALICE, supply);
+}
+
 // Test helper for fetching am account Balance amount
-fn balance(token: Token, address: u64) -> BalanceAmount {
+fn balance(token: Token, address: SignedOrigin) -> BalanceAmount {
 	pallet_assets::Pallet::<Test>::balance(token, address)
 }
 
@@ -63,19 +84,21 @@ fn test_the_default_utilisation_rate() {
 
 #[test]
 fn test_utilisation_rate_with_some_supply_and_borrowing() {
-	let mut pool: LendingPool<Test> = LendingPool::from(0, DOT, 5000).expect("failed");
-	pool.borrowed_balance = 5000;
+	new_test_ext().execute_with(|| {
+		let mut pool: LendingPool<Test> = LendingPool::from(0, DOT, 5000).expect("failed");
+		pool.borrowed_balance = 5000;
 
-	println!("Test Pool1: {:#?}", pool);
+		println!("Test Pool1: {:#?}", pool);
 
-	let ut = pool.utilisation_ratio().unwrap();
-	assert_eq!(ut, Permill::from_percent(50)); // 5000/10000 = 50%
+		let ut = pool.utilisation_ratio().unwrap();
+		assert_eq!(ut, Permill::from_percent(50)); // 5000/10000 = 50%
 
-	let br = pool.borrow_interest_rate().unwrap();
-	assert_eq!(br, Rate::from_float(0.045)); // 4.5%
+		let br = pool.borrow_interest_rate().unwrap();
+		assert_eq!(br, Rate::from_float(0.045)); // 4.5%
 
-	// 4.5% borrow interest rate for 50% utilisation rate.
-	// it can be aslso verified visually from https://www.desmos.com/calculator/fnj0ctpqn9
+		// 4.5% borrow interest rate for 50% utilisation rate.
+		// it can be aslso verified visually from https://www.desmos.com/calculator/fnj0ctpqn9
+	});
 }
 
 #[test]
@@ -89,25 +112,29 @@ fn test_utilisation_rate_with_some_supply_and_borrowing2() {
 
 #[test]
 fn test_supply_rate() {
-	let mut pool: LendingPool<Test> = LendingPool::from(0, DOT, 5000).expect("failed");
-	pool.borrowed_balance = 5000;
+	new_test_ext().execute_with(|| {
+		let mut pool: LendingPool<Test> = LendingPool::from(0, DOT, 5000).expect("failed");
+		pool.borrowed_balance = 5000;
 
-	println!("Test Reserve Factor: {:#?}", pool.reserve_factor);
-	let reserved = Permill::from_percent(100) - (pool.reserve_factor);
+		println!("Test Reserve Factor: {:#?}", pool.reserve_factor);
+		let reserved = Permill::from_percent(100) - (pool.reserve_factor);
 
-	println!("Test Reserved: {:#?}", reserved);
+		println!("Test Reserved: {:#?}", reserved);
 
-	let ut = pool.supply_interest_rate().unwrap();
-	assert_eq!(ut, Rate::from_float(0.02025)); // 20.25%
+		let ut = pool.supply_interest_rate().unwrap();
+		assert_eq!(ut, Rate::from_float(0.02025)); // 20.25%
+	});
 }
 
 #[test]
 fn test_supply_rate2() {
-	let mut pool: LendingPool<Test> = LendingPool::from(0, DOT, 1000).expect("failed");
-	pool.borrowed_balance = 9000;
+	new_test_ext().execute_with(|| {
+		let mut pool: LendingPool<Test> = LendingPool::from(0, DOT, 1000).expect("failed");
+		pool.borrowed_balance = 9000;
 
-	let ut = pool.supply_interest_rate().unwrap();
-	assert_eq!(ut, Rate::from_float(0.018225)); // 18.225%
+		let ut = pool.supply_interest_rate().unwrap();
+		assert_eq!(ut, Rate::from_float(0.018225)); // 18.225%
+	});
 }
 
 #[test]
@@ -117,7 +144,7 @@ fn try_to_supply_no_lending_pool() {
 
 		// Supply
 		assert_noop!(
-			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000),
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, None),
 			Error::<Test>::LendingPoolDoesNotExist
 		);
 	});
@@ -128,7 +155,7 @@ fn try_to_supply_no_liquidity() {
 	new_test_ext().execute_with(|| {
 		// Supply
 		assert_noop!(
-			TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 1_000),
+			TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 1_000, None),
 			Error::<Test>::NotEnoughLiquiditySupply
 		);
 	});
@@ -160,7 +187,7 @@ fn try_to_create_lending_pool_and_supply_not_yet_active() {
 			1_000
 		));
 		assert_noop!(
-			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000),
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, None),
 			Error::<Test>::LendingPoolNotActive
 		);
 	});
@@ -180,8 +207,4858 @@ fn try_to_create_lending_pool_and_supply_active() {
 		//TemplateModule::activate_lending_pool(RuntimeOrigin::signed(ALICE),
 		// LENDING_POOL_ID).unwrap();
 		assert_noop!(
-			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000),
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, None),
 			Error::<Test>::LendingPoolNotActive
 		);
 	});
 }
+
+#[test]
+fn set_reward_program_checks_origin_and_vesting_terms() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+
+		assert_noop!(
+			TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+
+		setup_active_pool(1_000);
+
+		assert_noop!(
+			TemplateModule::set_reward_program(RuntimeOrigin::signed(ALICE), DOT, KYL, 10, None),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_reward_program(
+				RuntimeOrigin::root(),
+				DOT,
+				KYL,
+				10,
+				Some(VestingTerms { cliff: 10, duration: 5 })
+			),
+			Error::<Test>::InvalidVestingTerms
+		);
+		assert_noop!(
+			TemplateModule::set_reward_program(
+				RuntimeOrigin::root(),
+				DOT,
+				KYL,
+				10,
+				Some(VestingTerms { cliff: 0, duration: 0 })
+			),
+			Error::<Test>::InvalidVestingTerms
+		);
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		System::assert_last_event(
+			Event::<Test>::RewardProgramUpdated {
+				asset: DOT,
+				reward_asset: KYL,
+				speed: 10,
+				vesting: None,
+			}
+			.into(),
+		);
+		assert_noop!(
+			TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, DOT, 10, None),
+			Error::<Test>::RewardAssetMismatch
+		);
+	});
+}
+
+#[test]
+fn claimed_rewards_vest_with_cliff_and_linear_unlock() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+
+		assert_ok!(TemplateModule::set_reward_program(
+			RuntimeOrigin::root(),
+			DOT,
+			KYL,
+			10,
+			Some(VestingTerms { cliff: 100, duration: 1_000 })
+		));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+
+		assert_noop!(
+			TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT),
+			Error::<Test>::NoVestedRewards
+		);
+
+		// 100 seconds later ALICE, the only supplier, accrued 10 * 100 rewards
+		Timestamp::set_timestamp(100_000);
+		assert_eq!(TemplateModule::do_claim_rewards(&ALICE, DOT, &ALICE), Ok(1_000));
+		assert_eq!(balance(KYL, ALICE), 900_000);
+		assert!(RewardVesting::<Test>::contains_key(ALICE, DOT));
+
+		// nothing is released before the cliff
+		Timestamp::set_timestamp(150_000);
+		assert_ok!(TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(KYL, ALICE), 900_000);
+
+		// 10% of the vesting duration elapsed
+		Timestamp::set_timestamp(200_000);
+		assert_ok!(TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(KYL, ALICE), 900_100);
+
+		// everything is released at the end of the vesting duration
+		Timestamp::set_timestamp(1_100_000);
+		assert_ok!(TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(KYL, ALICE), 901_000);
+		assert!(!RewardVesting::<Test>::contains_key(ALICE, DOT));
+		assert_noop!(TemplateModule::claim_vested_rewards(RuntimeOrigin::none(), DOT), BadOrigin);
+		assert_noop!(
+			TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT),
+			Error::<Test>::NoVestedRewards
+		);
+	});
+}
+
+#[test]
+fn rewards_claimed_again_vest_on_a_schedule_of_their_own() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+
+		assert_ok!(TemplateModule::set_reward_program(
+			RuntimeOrigin::root(),
+			DOT,
+			KYL,
+			10,
+			Some(VestingTerms { cliff: 100, duration: 1_000 })
+		));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+
+		Timestamp::set_timestamp(100_000);
+		assert_eq!(TemplateModule::do_claim_rewards(&ALICE, DOT, &ALICE), Ok(1_000));
+
+		// half of the first claim vested and is released, the second claim starts vesting
+		Timestamp::set_timestamp(600_000);
+		assert_eq!(TemplateModule::do_claim_rewards(&ALICE, DOT, &ALICE), Ok(5_000));
+		assert_eq!(balance(KYL, ALICE), 900_500);
+		assert_eq!(RewardVesting::<Test>::get(ALICE, DOT).map(|s| s.len()), Some(2));
+
+		// the first claim is not locked again, it vests fully alongside half of the second
+		Timestamp::set_timestamp(1_100_000);
+		assert_ok!(TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(KYL, ALICE), 903_500);
+		assert_eq!(RewardVesting::<Test>::get(ALICE, DOT).map(|s| s.len()), Some(1));
+
+		Timestamp::set_timestamp(1_600_000);
+		assert_ok!(TemplateModule::claim_vested_rewards(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(KYL, ALICE), 906_000);
+		assert!(!RewardVesting::<Test>::contains_key(ALICE, DOT));
+	});
+}
+
+#[test]
+fn compound_rewards_supplies_into_the_reward_asset_pool() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(ALICE),
+			KYL_POOL_ID,
+			KYL,
+			1_000
+		));
+		assert_ok!(TemplateModule::activate_lending_pool(RuntimeOrigin::signed(ALICE), KYL));
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+
+		Timestamp::set_timestamp(100_000);
+		assert_ok!(TemplateModule::compound_rewards(RuntimeOrigin::signed(ALICE), DOT));
+
+		// the claimed rewards were supplied right away
+		assert_eq!(balance(KYL, ALICE), 899_000);
+		assert_eq!(balance(KYL_POOL_ID, ALICE), 2_000);
+	});
+}
+
+#[test]
+fn keepers_compound_rewards_only_for_opted_in_users() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(ALICE),
+			KYL_POOL_ID,
+			KYL,
+			1_000
+		));
+		assert_ok!(TemplateModule::activate_lending_pool(RuntimeOrigin::signed(ALICE), KYL));
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		Timestamp::set_timestamp(100_000);
+
+		assert_noop!(
+			TemplateModule::compound_rewards_for(RuntimeOrigin::signed(BOB), ALICE, DOT),
+			Error::<Test>::AutoCompoundNotEnabled
+		);
+
+		assert_ok!(TemplateModule::set_auto_compound(RuntimeOrigin::signed(ALICE), DOT, true));
+		assert_ok!(TemplateModule::compound_rewards_for(RuntimeOrigin::signed(BOB), ALICE, DOT));
+
+		// BOB got 1% of the rewards as a tip, the rest was supplied
+		assert_eq!(balance(KYL, BOB), 10);
+		assert_eq!(balance(KYL_POOL_ID, ALICE), 1_990);
+	});
+}
+
+#[test]
+fn locked_governance_tokens_boost_rewards() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 1_000_000));
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+
+		// BOB holds all the locked governance tokens, ALICE none
+		MockGovernanceLocks::lock(BOB, 500);
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(BOB), ALICE, DOT));
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 1_000, None));
+
+		// ALICE earns on 1_000 / 2.5, BOB on his full balance
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).working_balance, 400);
+		assert_eq!(UserRewards::<Test>::get(BOB, DOT).working_balance, 1_000);
+
+		Timestamp::set_timestamp(100_000);
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(BOB), ALICE, DOT));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(BOB), BOB, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).accrued, 285);
+		assert_eq!(UserRewards::<Test>::get(BOB, DOT).accrued, 714);
+	});
+}
+
+#[test]
+fn transferred_lp_tokens_carry_their_rewards_along() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).working_balance, 400);
+
+		Timestamp::set_timestamp(100_000);
+		assert_noop!(
+			TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(ALICE), DOT, BOB, 0),
+			Error::<Test>::AmountTooLow
+		);
+		assert_ok!(TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(ALICE), DOT, BOB, 500));
+		System::assert_last_event(
+			Event::<Test>::LPTokenTransferred { who: ALICE, dest: BOB, balance: 500 }.into(),
+		);
+
+		// ALICE keeps what she earned before the transfer, the working balances follow the tokens
+		let earned = UserRewards::<Test>::get(ALICE, DOT).accrued;
+		assert!(earned > 0);
+		assert_eq!(UserRewards::<Test>::get(BOB, DOT).accrued, 0);
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).working_balance, 200);
+		assert_eq!(UserRewards::<Test>::get(BOB, DOT).working_balance, 200);
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 500);
+		assert!(SupplyIndexStorage::<Test>::contains_key((BOB, DOT)));
+
+		Timestamp::set_timestamp(200_000);
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(BOB), ALICE, DOT));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(BOB), BOB, DOT));
+		assert_eq!(
+			UserRewards::<Test>::get(ALICE, DOT).accrued - earned,
+			UserRewards::<Test>::get(BOB, DOT).accrued
+		);
+	});
+}
+
+#[test]
+fn lp_tokens_are_frozen_in_the_assets_pallet() {
+	use crate::LpTokensUnfrozen;
+	use pallet_assets::FrozenBalance;
+
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_eq!(TemplateModule::frozen_balance(LENDING_POOL_ID, &ALICE), Some(1_000));
+		assert_eq!(TemplateModule::frozen_balance(DOT, &ALICE), None);
+
+		// neither the transfers nor the approvals of the Assets Pallet move the LP tokens
+		assert_noop!(
+			Assets::transfer(RuntimeOrigin::signed(ALICE), LENDING_POOL_ID.into(), BOB, 500),
+			pallet_assets::Error::<Test>::BalanceLow
+		);
+		assert_ok!(Assets::approve_transfer(
+			RuntimeOrigin::signed(ALICE),
+			LENDING_POOL_ID.into(),
+			BOB,
+			500
+		));
+		assert_noop!(
+			Assets::transfer_approved(
+				RuntimeOrigin::signed(BOB),
+				LENDING_POOL_ID.into(),
+				ALICE,
+				BOB,
+				500
+			),
+			pallet_assets::Error::<Test>::BalanceLow
+		);
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 0);
+
+		// the pallet moves and burns them, and leaves them frozen behind
+		assert_ok!(TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(ALICE), DOT, BOB, 500));
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(BOB), DOT, 500));
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 0);
+		assert!(!LpTokensUnfrozen::<Test>::exists());
+		assert_eq!(TemplateModule::frozen_balance(LENDING_POOL_ID, &ALICE), Some(500));
+	});
+}
+
+#[test]
+fn claim_rewards_from_one_or_all_pools() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(ALICE),
+			KYL_POOL_ID,
+			KYL,
+			1_000
+		));
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), KYL, KYL, 5, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, KYL));
+
+		Timestamp::set_timestamp(100_000);
+		assert_ok!(TemplateModule::claim_rewards(RuntimeOrigin::signed(ALICE), Some(DOT), None));
+		assert_eq!(balance(KYL, ALICE), 900_000);
+
+		Timestamp::set_timestamp(200_000);
+		assert_ok!(TemplateModule::claim_rewards(RuntimeOrigin::signed(ALICE), None, None));
+		// 10 * 100 from the DOT pool and 5 * 200 from the KYL pool
+		assert_eq!(balance(KYL, ALICE), 902_000);
+
+		// the rewards can be paid to another account
+		System::set_block_number(1);
+		Timestamp::set_timestamp(300_000);
+		assert_ok!(TemplateModule::claim_rewards(
+			RuntimeOrigin::signed(ALICE),
+			Some(DOT),
+			Some(BOB)
+		));
+		assert_eq!(balance(KYL, ALICE), 902_000);
+		assert_eq!(balance(KYL, BOB), 1_000);
+		System::assert_last_event(
+			Event::<Test>::RewardsClaimed { who: ALICE, dest: BOB, balance: 1_000 }.into(),
+		);
+
+		assert_noop!(
+			TemplateModule::claim_rewards(RuntimeOrigin::signed(BOB), Some(42), None),
+			Error::<Test>::RewardProgramDoesNotExist
+		);
+	});
+}
+
+#[test]
+fn reward_emission_pauses_when_the_pot_runs_dry() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_noop!(
+			TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 500),
+			Error::<Test>::RewardProgramDoesNotExist
+		);
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 500));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+
+		// 10 * 100 rewards can not be covered by the pot
+		Timestamp::set_timestamp(100_000);
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).accrued, 0);
+		assert!(RewardPrograms::<Test>::get(DOT).unwrap().paused);
+		System::assert_has_event(Event::<Test>::RewardEmissionPaused { asset: DOT }.into());
+
+		// funding the pot resumes the emission from now on
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 1_000));
+		assert!(!RewardPrograms::<Test>::get(DOT).unwrap().paused);
+		System::assert_has_event(Event::<Test>::RewardEmissionResumed { asset: DOT }.into());
+
+		Timestamp::set_timestamp(150_000);
+		assert_ok!(TemplateModule::claim_rewards(RuntimeOrigin::signed(ALICE), Some(DOT), None));
+		assert_eq!(balance(KYL, ALICE), 999_000);
+		assert_eq!(RewardLiabilities::<Test>::get(KYL), 0);
+	});
+}
+
+#[test]
+fn referrers_earn_a_share_of_the_interest_revenue() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 100_000));
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		let code = ReferralCode::try_from(b"ALICE".to_vec()).unwrap();
+		assert_ok!(TemplateModule::register_referral_code(
+			RuntimeOrigin::signed(ALICE),
+			code.clone()
+		));
+		assert_noop!(
+			TemplateModule::register_referral_code(RuntimeOrigin::signed(BOB), code.clone()),
+			Error::<Test>::ReferralCodeAlreadyExists
+		);
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, Some(code.clone())),
+			Error::<Test>::SelfReferral
+		);
+
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			KYL,
+			400_000,
+			Some(code.clone())
+		));
+		System::assert_has_event(
+			Event::<Test>::ReferralAttached { who: BOB, code: code.clone() }.into(),
+		);
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().principal, Some(200_000));
+
+		// BOB repays half of the borrow 30 days later
+		Timestamp::set_timestamp(30 * 24 * 60 * 60 * 1_000);
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 100_000, KYL));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let repayable = math::mul_rate(200_000u128, pool.borrow_index, Rounding::Up).unwrap();
+		let interest =
+			math::mul_div(100_000u128, repayable - 200_000, repayable, Rounding::Up).unwrap();
+		assert!(interest > 0);
+		assert_eq!(
+			Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().principal,
+			Some(200_000 - (100_000 - interest))
+		);
+
+		// ALICE earns 20% of the 10% reserve factor share of the interest
+		let earnings =
+			Permill::from_percent(20).mul_floor(Permill::from_percent(10).mul_floor(interest));
+		assert_eq!(ReferralEarnings::<Test>::get(&code, DOT), earnings);
+		assert_eq!(pool.reserve_balance, 400_000 - earnings);
+
+		assert_noop!(
+			TemplateModule::claim_referral_earnings(RuntimeOrigin::signed(BOB), code.clone(), DOT),
+			Error::<Test>::NotReferralCodeOwner
+		);
+		let alice_balance = balance(DOT, ALICE);
+		assert_ok!(TemplateModule::claim_referral_earnings(
+			RuntimeOrigin::signed(ALICE),
+			code.clone(),
+			DOT
+		));
+		assert_eq!(balance(DOT, ALICE), alice_balance + earnings);
+		assert_noop!(
+			TemplateModule::claim_referral_earnings(RuntimeOrigin::signed(ALICE), code, DOT),
+			Error::<Test>::NoReferralEarnings
+		);
+	});
+}
+
+#[test]
+fn borrow_fee_is_credited_to_the_reserves() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		assert_noop!(
+			TemplateModule::set_borrow_fee(
+				RuntimeOrigin::signed(ALICE),
+				DOT,
+				Permill::from_percent(1)
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_borrow_fee(RuntimeOrigin::root(), DOT, Permill::from_percent(100)),
+			Error::<Test>::InvalidBorrowFee
+		);
+		assert_ok!(TemplateModule::set_borrow_fee(
+			RuntimeOrigin::root(),
+			DOT,
+			Permill::from_percent(1)
+		));
+
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			KYL,
+			400_000,
+			None
+		));
+		System::assert_last_event(
+			Event::<Test>::DepositBorrowed {
+				who: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				fee: 2_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+				debt: 200_000,
+				utilisation: Permill::from_rational(200_000u128, 502_000),
+			}
+			.into(),
+		);
+
+		// BOB receives the borrowed balance minus the fee, which stays in the pool reserves
+		assert_eq!(balance(DOT, BOB), 198_000);
+		assert_eq!(
+			LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap().reserve_balance,
+			302_000
+		);
+	});
+}
+
+#[test]
+fn protocol_fee_redirects_interest_to_the_treasury() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 100_000));
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		assert_noop!(
+			TemplateModule::set_protocol_fee(RuntimeOrigin::root(), Permill::from_percent(100)),
+			Error::<Test>::InvalidProtocolFee
+		);
+		assert_ok!(TemplateModule::set_protocol_fee(
+			RuntimeOrigin::root(),
+			Permill::from_percent(50)
+		));
+
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			KYL,
+			400_000,
+			None
+		));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().principal, Some(200_000));
+
+		// BOB repays everything 30 days later
+		Timestamp::set_timestamp(30 * 24 * 60 * 60 * 1_000);
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 300_000, KYL));
+		let interest = 100_000 - balance(DOT, BOB);
+		assert!(interest > 0);
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)), None);
+
+		// half of the interest went to the treasury, the rest to the pool
+		let fee = Permill::from_percent(50).mul_floor(interest);
+		assert_eq!(balance(DOT, TreasuryAccount::get()), fee);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.borrowed_balance, 0);
+		assert_eq!(pool.reserve_balance, 500_000 + interest - fee);
+	});
+}
+
+#[test]
+fn pool_info_reports_the_pool_statistics() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			100_000,
+			KYL,
+			200_000,
+			None
+		));
+
+		let info = TemplateModule::pool_info(DOT).unwrap();
+		assert_eq!(info.id, LENDING_POOL_ID);
+		assert!(info.activated);
+		assert_eq!(info.utilisation, Permill::from_percent(20));
+		// 2% base rate + 20% / 80% kink * 4% slope
+		assert_eq!(info.borrow_apy, Rate::saturating_from_rational(3, 100));
+		assert_eq!(info.total_supplied, 500_000);
+		assert_eq!(info.total_borrowed, 100_000);
+		assert_eq!(info.reserve_balance, 400_000);
+		assert_eq!(info.exchange_rate, Rate::from(1));
+
+		assert_eq!(TemplateModule::pools_info(), vec![info]);
+		assert_eq!(TemplateModule::pool_info(42), None);
+	});
+}
+
+#[test]
+fn account_overview_values_positions_and_health() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			DOT,
+			0,
+			FixedU128::from(2)
+		));
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			0,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			100_000,
+			KYL,
+			400_000,
+			None
+		));
+
+		let alice = TemplateModule::account_overview(&ALICE);
+		assert_eq!(
+			alice.supplied,
+			vec![SupplyPosition { asset: DOT, balance: 500_000, value: Some(1_000_000) }]
+		);
+		assert_eq!(alice.health_factor, None);
+
+		let bob = TemplateModule::account_overview(&BOB);
+		assert!(bob.supplied.is_empty());
+		assert_eq!(
+			bob.borrowed,
+			vec![BorrowPosition {
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 100_000,
+				collateral_balance: 400_000,
+				value: Some(200_000),
+				collateral_value: Some(400_000),
+			}]
+		);
+		assert_eq!(bob.total_borrowed_value, 200_000);
+		assert_eq!(bob.total_collateral_value, 400_000);
+		// 80% liquidation threshold of 400_000 against 200_000 of debt
+		assert_eq!(bob.health_factor, Some(Rate::saturating_from_rational(16, 10)));
+		// 50% collateral factor of 400_000, all used
+		assert_eq!(bob.borrow_power, 0);
+
+		// the bounded queries of the contracts return the same positions
+		assert_eq!(TemplateModule::supply_position(&ALICE, DOT), alice.supplied.first().cloned());
+		assert_eq!(TemplateModule::supply_position(&BOB, DOT), None);
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL), bob.borrowed.first().cloned());
+		assert_eq!(TemplateModule::borrow_position(&ALICE, DOT, KYL), None);
+	});
+}
+
+#[test]
+fn simulate_previews_an_action_without_dispatching_it() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			DOT,
+			0,
+			FixedU128::from(2)
+		));
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			0,
+			FixedU128::from(1)
+		));
+
+		let action = Action::Borrow {
+			asset: DOT,
+			balance: 100_000,
+			collateral_asset: KYL,
+			collateral_balance: 400_000,
+		};
+		let simulation = TemplateModule::simulate(&BOB, action).unwrap();
+		assert_eq!(simulation.health_factor, Some(Rate::saturating_from_rational(16, 10)));
+		assert_eq!(simulation.borrow_power, 0);
+		assert_eq!(simulation.pool.total_borrowed, 100_000);
+		assert_eq!(simulation.pool.utilisation, Permill::from_percent(20));
+
+		// nothing was dispatched
+		assert!(TemplateModule::account_overview(&BOB).borrowed.is_empty());
+		assert_eq!(TemplateModule::pool_info(DOT).unwrap().total_borrowed, 0);
+		assert_eq!(balance(DOT, BOB), 0);
+
+		let action = Action::Borrow {
+			asset: DOT,
+			balance: 300_000,
+			collateral_asset: KYL,
+			collateral_balance: 400_000,
+		};
+		assert_noop!(TemplateModule::simulate(&BOB, action), Error::<Test>::NotEnoughCollateral);
+	});
+}
+
+// Test helper for opening a borrow of 200_000 DOT by BOB against 400_000 KYL, at par
+fn setup_borrow() {
+	System::set_block_number(1);
+	create_asset_and_mint(DOT, ALICE, 1_000_000);
+	create_asset_and_mint(KYL, BOB, 1_000_000);
+	setup_active_pool(500_000);
+	assert_ok!(TemplateModule::set_asset_price(
+		RuntimeOrigin::signed(ORACLE),
+		KYL,
+		DOT,
+		FixedU128::from(1)
+	));
+	assert_ok!(TemplateModule::borrow(
+		RuntimeOrigin::signed(BOB),
+		DOT,
+		200_000,
+		KYL,
+		400_000,
+		None
+	));
+}
+
+#[test]
+fn borrows_and_withdrawals_are_refused_with_a_shortfall() {
+	use crate::WeightInfo;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_eq!(AccountBorrows::<Test>::get(BOB).to_vec(), vec![(DOT, KYL)]);
+
+		// the weight of the markets BOB does not participate in is refunded
+		let info =
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 20_000, None)
+				.unwrap();
+		assert_eq!(info.actual_weight, Some(<() as WeightInfo>::borrow(1)));
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 50_000, None));
+		let info = TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 10_000).unwrap();
+		assert_eq!(info.actual_weight, Some(<() as WeightInfo>::withdraw(0)));
+
+		// the collateral halves in value, 80% of it covers only 168_000
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::saturating_from_rational(1, 2)
+		));
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 100_000, None),
+			Error::<Test>::AccountHasShortfall
+		);
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(BOB), DOT, 10_000),
+			Error::<Test>::AccountHasShortfall
+		);
+
+		// the index follows the borrow once repaid
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 100_000));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 300_000, KYL));
+		assert!(AccountBorrows::<Test>::get(BOB).is_empty());
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(BOB), DOT, 10_000));
+	});
+}
+
+#[test]
+fn operations_fail_with_the_error_of_their_cause() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+
+		// zero balances
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 0, KYL, 1_000, None),
+			Error::<Test>::AmountTooLow
+		);
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1_000, KYL, 0, None),
+			Error::<Test>::AmountTooLow
+		);
+		assert_noop!(
+			TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 0, KYL),
+			Error::<Test>::AmountTooLow
+		);
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 0, None),
+			Error::<Test>::AmountTooLow
+		);
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 0),
+			Error::<Test>::AmountTooLow
+		);
+
+		// the collateral has no price
+		create_asset_and_mint(10, BOB, 100_000);
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1_000, 10, 2_000, None),
+			Error::<Test>::PriceUnavailable
+		);
+
+		// BOB locked 400_000 of its 1_000_000 KYL already
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1_000, KYL, 700_000, None),
+			Error::<Test>::InsufficientCollateral
+		);
+
+		// 300_000 DOT are left in the reserves of the pool
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 300_001, KYL, 500_000, None),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 300_001),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 300_000));
+	});
+}
+
+#[test]
+fn borrows_are_capped_to_the_borrow_cap_of_the_pool() {
+	use crate::{BorrowCaps, PoolParamsUpdate};
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let update = PoolParamsUpdate { borrow_cap: Some(Some(210_000)), ..Default::default() };
+		assert_ok!(TemplateModule::force_update_pool_params(RuntimeOrigin::root(), DOT, update));
+		assert_eq!(BorrowCaps::<Test>::get(DOT), Some(210_000));
+
+		// 200_000 DOT are borrowed already
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_001, KYL, 30_000, None),
+			Error::<Test>::BorrowCapExceeded
+		);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			10_000,
+			KYL,
+			30_000,
+			None
+		));
+
+		// the repaid principal frees the cap
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 5_000, KYL));
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 5_001, KYL, 10_000, None),
+			Error::<Test>::BorrowCapExceeded
+		);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			5_000,
+			KYL,
+			10_000,
+			None
+		));
+	});
+}
+
+#[test]
+fn accounts_hold_up_to_max_markets_borrows() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+		let max = <Test as crate::Config>::MaxMarketsPerAccount::get();
+		for collateral in 10..=10 + max {
+			create_asset_and_mint(collateral, BOB, 100_000);
+			assert_ok!(TemplateModule::set_asset_price(
+				RuntimeOrigin::signed(ORACLE),
+				collateral,
+				DOT,
+				FixedU128::from(1)
+			));
+		}
+		for collateral in 10..10 + max {
+			assert_ok!(TemplateModule::borrow(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				1_000,
+				collateral,
+				2_000,
+				None
+			));
+		}
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1_000, 10 + max, 2_000, None),
+			Error::<Test>::TooManyMarkets
+		);
+
+		// topping up a borrow does not take another slot, repaying one frees its slot
+		assert_ok!(TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1_000, 10, 2_000, None));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 2_000, 10));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			1_000,
+			10 + max,
+			2_000,
+			None
+		));
+		assert_eq!(AccountBorrows::<Test>::get(BOB).len() as u32, max);
+	});
+}
+
+#[test]
+fn math_mul_div_rounds_without_overflowing_the_product() {
+	assert_eq!(math::mul_div(u128::MAX, u128::MAX, u128::MAX, Rounding::Down), Some(u128::MAX));
+	assert_eq!(math::mul_div(u128::MAX, 2, 1, Rounding::Down), None);
+	assert_eq!(math::mul_div(1u128, 1, 0, Rounding::Down), None);
+
+	assert_eq!(math::mul_div(10u128, 1, 3, Rounding::Down), Some(3));
+	assert_eq!(math::mul_div(10u128, 1, 3, Rounding::Up), Some(4));
+	assert_eq!(math::mul_div(10u128, 1, 4, Rounding::NearestPrefDown), Some(2));
+	assert_eq!(math::mul_div(10u128, 1, 4, Rounding::NearestPrefUp), Some(3));
+	assert_eq!(math::mul_div(9u128, 1, 3, Rounding::Up), Some(3));
+	assert_eq!(math::mul_div(u128::MAX, 2, 2, Rounding::Up), Some(u128::MAX));
+
+	let rate = Rate::saturating_from_rational(3, 2);
+	assert_eq!(math::mul_rate(1_000u128, rate, Rounding::Down), Some(1_500));
+	assert_eq!(math::div_rate(1_000u128, rate, Rounding::Down), Some(666));
+	assert_eq!(math::div_rate(1_000u128, rate, Rounding::Up), Some(667));
+	assert_eq!(math::div_rate(1_000u128, Rate::from(0), Rounding::Down), None);
+	assert_eq!(math::mul_rate(u128::MAX, Rate::from(1), Rounding::Up), Some(u128::MAX));
+	assert_eq!(math::ratio(1u128, 3), Some(Rate::from_inner(333_333_333_333_333_333)));
+}
+
+#[test]
+fn math_values_large_balances_with_a_256_bits_intermediate() {
+	// the product of the balance with the price does not fit 128 bits, the value does
+	let price = Rate::saturating_from_rational(19, 10);
+	assert_eq!(
+		math::mul_rate(u128::MAX / 2, price, Rounding::Down),
+		Some(323_268_248_574_891_540_290_205_877_060_179_800_881)
+	);
+	assert_eq!(math::mul_rate(u128::MAX / 2, Rate::from(3), Rounding::Down), None);
+	assert_eq!(
+		math::div_rate(u128::MAX / 2, Rate::saturating_from_rational(1, 3), Rounding::Down),
+		None
+	);
+}
+
+#[test]
+fn math_normalizes_the_decimals_of_the_converted_balances() {
+	let one = Rate::from(1);
+	// 1.5 units of a 6-decimal stablecoin are worth 3 units of a 12-decimal asset at 2
+	assert_eq!(
+		math::convert(1_500_000u128, 6, Rate::from(2), one, 12, Rounding::Down),
+		Some(3_000_000_000_000)
+	);
+	// and back, quoted the other way
+	assert_eq!(
+		math::convert(3_000_000_000_000u128, 12, one, Rate::from(2), 6, Rounding::Down),
+		Some(1_500_000)
+	);
+	// the units below the precision of the target asset are rounded
+	assert_eq!(math::convert(1_999_999u128, 12, one, one, 6, Rounding::Down), Some(1));
+	assert_eq!(math::convert(1_999_999u128, 12, one, one, 6, Rounding::Up), Some(2));
+	assert_eq!(math::convert(1u128, 0, one, one, 38, Rounding::Down), Some(10u128.pow(38)));
+	assert_eq!(math::convert(4u128, 0, one, one, 38, Rounding::Down), None);
+	assert_eq!(math::convert(1u128, 6, one, Rate::from(0), 6, Rounding::Down), None);
+}
+
+#[test]
+fn math_compounds_the_interest_every_second() {
+	let year = 365 * 24 * 60 * 60;
+	let rate = Rate::saturating_from_rational(10, 100);
+
+	assert_eq!(math::linear_interest(rate, 0), Some(Rate::from(1)));
+	assert_eq!(math::compounded_interest(rate, 0), Some(Rate::from(1)));
+	assert_eq!(math::linear_interest(rate, year), Some(Rate::saturating_from_rational(11, 10)));
+	// the interest of a single second does not compound
+	assert_eq!(math::compounded_interest(rate, 1), math::linear_interest(rate, 1));
+
+	// e^0.1 ≈ 1.1051709
+	let compounded = math::compounded_interest(rate, year).unwrap();
+	assert!(compounded > Rate::saturating_from_rational(1_105_170, 1_000_000));
+	assert!(compounded < Rate::saturating_from_rational(1_105_171, 1_000_000));
+
+	// the days of long periods compound too: 100% over 10 years is e^10 ≈ 22026.47, where the
+	// expansion of a single period would give 1 + 10 + 10^2 / 2 + 10^3 / 6 ≈ 227.67
+	let compounded = math::compounded_interest(Rate::from(1), 10 * year).unwrap();
+	assert!(compounded > Rate::from(22_026));
+	assert!(compounded < Rate::from(22_027));
+	// the seconds past the last whole day compound on top of it
+	let day = math::COMPOUNDING_PERIOD;
+	assert_eq!(
+		math::compounded_interest(rate, day + 1).unwrap(),
+		math::compounded_interest(rate, day).unwrap() * math::compounded_interest(rate, 1).unwrap()
+	);
+
+	// the factor saturates rather than overflow: 1000% over a century is e^1000
+	assert_eq!(
+		math::compounded_interest(Rate::from(10), 100 * year),
+		Some(Rate::from_inner(u128::MAX))
+	);
+}
+
+#[test]
+fn a_pool_left_untouched_accrues_the_interest_of_the_max_accrual_period_only() {
+	let borrow_index_after = |seconds: u64| {
+		new_test_ext().execute_with(|| {
+			setup_borrow();
+			Timestamp::set_timestamp(seconds * 1_000);
+			let mut pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+			pool.update_indexes().unwrap();
+			pool.borrow_index
+		})
+	};
+	let period = MaxAccrualPeriod::get();
+	assert!(borrow_index_after(period) > Rate::from(1));
+	assert_eq!(borrow_index_after(1_000 * period), borrow_index_after(period));
+}
+
+#[test]
+fn dust_rounding_favours_the_pools() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		Timestamp::set_timestamp(30 * 24 * 60 * 60 * 1_000);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert!(pool.supply_index > Rate::from(1));
+		assert!(pool.borrow_index > Rate::from(1));
+
+		// a single unit is worth less than an LP token, withdrawing one burns a whole LP token
+		let lp_tokens = balance(LENDING_POOL_ID, ALICE);
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1, None),
+			Error::<Test>::SupplyTooSmall
+		);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 1));
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), lp_tokens - 1);
+
+		// borrowing a single unit records a whole unit of scaled debt
+		let debt = Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().borrowed_balance;
+		assert_ok!(TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1, KYL, 10, None));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().borrowed_balance, debt + 1);
+
+		// repaying a single unit clears no scaled debt and releases no collateral
+		let collateral = Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().collateral_balance;
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 1, KYL));
+		let loan = Borrows::<Test>::get((BOB, DOT, KYL)).unwrap();
+		assert_eq!((loan.borrowed_balance, loan.collateral_balance), (debt + 1, collateral));
+	});
+}
+
+#[test]
+fn donations_do_not_inflate_the_price_of_the_lp_tokens() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 2_000_000);
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 1_000_000));
+
+		// ALICE creates the pool with a single unit, then donates to the pool account
+		setup_active_pool(1);
+		assert_ok!(Assets::transfer(
+			RuntimeOrigin::signed(ALICE),
+			DOT.into(),
+			TemplateModule::pool_account_id(LENDING_POOL_ID),
+			1_000_000
+		));
+
+		// BOB, the second depositor, gets LP tokens for the whole supply and can withdraw it back
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 500_000, None));
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 500_000);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(BOB), DOT, 500_000));
+		assert_eq!(balance(DOT, BOB), 1_000_000);
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 1);
+	});
+}
+
+#[test]
+fn borrows_are_kept_above_the_min_borrow() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		assert_noop!(
+			TemplateModule::set_min_borrow(RuntimeOrigin::signed(ALICE), DOT, 1_000),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_min_borrow(RuntimeOrigin::root(), KYL, 1_000),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		assert_ok!(TemplateModule::set_min_borrow(RuntimeOrigin::root(), DOT, 1_000));
+		System::assert_last_event(
+			Event::<Test>::MinBorrowSet { asset: DOT, min_borrow: 1_000 }.into(),
+		);
+
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 999, KYL, 2_000, None),
+			Error::<Test>::BorrowTooSmall
+		);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			1_000,
+			KYL,
+			2_000,
+			None
+		));
+		// topping up counts the debt of the borrow
+		assert_ok!(TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1, KYL, 2, None));
+
+		// a partial repayment must leave the min borrow, a full one is always possible
+		assert_noop!(
+			TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 2, KYL),
+			Error::<Test>::BorrowTooSmall
+		);
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 1, KYL));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 1_000, KYL));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)), None);
+	});
+}
+
+#[test]
+fn stable_borrows_accrue_at_the_rate_fixed_at_origination() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_noop!(
+			TemplateModule::borrow_stable(RuntimeOrigin::signed(BOB), DOT, 200_000, KYL, 400_000),
+			Error::<Test>::StableBorrowNotAllowed
+		);
+		assert_ok!(TemplateModule::set_stable_rate_premium(
+			RuntimeOrigin::root(),
+			DOT,
+			Some(Permill::from_percent(1))
+		));
+
+		// the rate is fixed off the variable rate at the utilisation after the borrow
+		assert_ok!(TemplateModule::borrow_stable(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			KYL,
+			400_000
+		));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let rate = pool.borrow_interest_rate().unwrap() + Rate::from(Permill::from_percent(1));
+		assert_eq!(StableBorrows::<Test>::get((BOB, DOT, KYL)).unwrap().rate, rate);
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 20_000, None),
+			Error::<Test>::BorrowRateModeMismatch
+		);
+
+		// a year later, the debt compounded at the stable rate
+		let year = 365 * 24 * 60 * 60;
+		Timestamp::set_timestamp(year * 1_000);
+		let debt = math::mul_rate(
+			200_000u128,
+			math::compounded_interest(rate, year).unwrap(),
+			Rounding::Up,
+		)
+		.unwrap();
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+
+		// the rate is only rebalanced once the pool is drained
+		assert_noop!(
+			TemplateModule::rebalance_stable_rate(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::StableRateNotRebalanceable
+		);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 290_000));
+		assert_ok!(TemplateModule::rebalance_stable_rate(
+			RuntimeOrigin::signed(ALICE),
+			BOB,
+			DOT,
+			KYL
+		));
+		let rebalanced = StableBorrows::<Test>::get((BOB, DOT, KYL)).unwrap().rate;
+		assert!(rebalanced > rate);
+		System::assert_last_event(
+			Event::<Test>::StableRateRebalanced { who: BOB, asset: DOT, rate: rebalanced }.into(),
+		);
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+
+		// the stable rate goes with the borrow
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 100_000));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 300_000, KYL));
+		assert!(!StableBorrows::<Test>::contains_key((BOB, DOT, KYL)));
+	});
+}
+
+#[test]
+fn borrows_switch_between_the_variable_and_the_stable_rate() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_noop!(
+			TemplateModule::switch_borrow_rate_mode(RuntimeOrigin::signed(BOB), DOT, KYL),
+			Error::<Test>::StableBorrowNotAllowed
+		);
+		assert_ok!(TemplateModule::set_stable_rate_premium(
+			RuntimeOrigin::root(),
+			DOT,
+			Some(Permill::from_percent(1))
+		));
+
+		// the interest accrues at the variable rate up to the switch
+		let year = 365 * 24 * 60 * 60;
+		Timestamp::set_timestamp(year * 1_000);
+		let debt = TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance;
+		assert_ok!(TemplateModule::switch_borrow_rate_mode(RuntimeOrigin::signed(BOB), DOT, KYL));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let variable = pool.borrow_interest_rate().unwrap();
+		let stable = variable + Rate::from(Permill::from_percent(1));
+		System::assert_last_event(
+			Event::<Test>::BorrowRateSwitched { who: BOB, asset: DOT, from: variable, to: stable }
+				.into(),
+		);
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+
+		// then at the stable rate
+		Timestamp::set_timestamp(2 * year * 1_000);
+		let debt =
+			math::mul_rate(debt, math::compounded_interest(stable, year).unwrap(), Rounding::Up)
+				.unwrap();
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+
+		assert_ok!(TemplateModule::switch_borrow_rate_mode(RuntimeOrigin::signed(BOB), DOT, KYL));
+		assert!(!StableBorrows::<Test>::contains_key((BOB, DOT, KYL)));
+		let balance = TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance;
+		assert!(balance >= debt && balance <= debt + 1);
+	});
+}
+
+#[test]
+fn fixed_term_borrows_are_liquidatable_once_overdue() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::set_stable_rate_premium(
+			RuntimeOrigin::root(),
+			DOT,
+			Some(Permill::from_percent(1))
+		));
+
+		let day = 24 * 60 * 60;
+		let borrow_for = |term, max_rate| {
+			TemplateModule::borrow_fixed_term(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				200_000,
+				KYL,
+				400_000,
+				term,
+				max_rate,
+			)
+		};
+		assert_noop!(borrow_for(0, Rate::one()), Error::<Test>::InvalidBorrowTerm);
+		assert_noop!(borrow_for(30 * day, Rate::zero()), Error::<Test>::BorrowRateTooHigh);
+		assert_ok!(borrow_for(30 * day, Rate::one()));
+		let rate = StableBorrows::<Test>::get((BOB, DOT, KYL)).unwrap().rate;
+		System::assert_last_event(
+			Event::<Test>::FixedTermBorrowed { who: BOB, asset: DOT, rate, maturity: 30 * day }
+				.into(),
+		);
+		assert_noop!(
+			borrow_for(30 * day, Rate::one()),
+			Error::<Test>::FixedTermBorrowNotExtendable
+		);
+		assert_noop!(
+			TemplateModule::switch_borrow_rate_mode(RuntimeOrigin::signed(BOB), DOT, KYL),
+			Error::<Test>::FixedTermBorrowNotExtendable
+		);
+
+		// past its maturity, the borrow accrues at the penalty rate on top of its rate
+		Timestamp::set_timestamp(30 * day * 1_000 + day * 500);
+		let index = math::compounded_interest(rate, 30 * day)
+			.unwrap()
+			.checked_mul(
+				&math::compounded_interest(rate + TermPenaltyRate::get(), day / 2).unwrap(),
+			)
+			.unwrap();
+		let debt = math::mul_rate(200_000u128, index, Rounding::Up).unwrap();
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::BorrowNotLiquidatable
+		);
+
+		// after the grace period, the healthy borrow is liquidatable
+		Timestamp::set_timestamp(32 * day * 1_000);
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL));
+		assert!(!Borrows::<Test>::contains_key((BOB, DOT, KYL)));
+		assert!(!BorrowMaturities::<Test>::contains_key((BOB, DOT, KYL)));
+		assert!(!StableBorrows::<Test>::contains_key((BOB, DOT, KYL)));
+	});
+}
+
+#[test]
+fn borrow_rates_are_bounded_by_the_floor_and_the_cap_of_the_pool() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		let bounds = (Rate::from_rational(1, 100), Rate::from_rational(3, 100));
+		assert_noop!(
+			TemplateModule::set_borrow_rate_bounds(RuntimeOrigin::signed(ALICE), DOT, Some(bounds)),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_borrow_rate_bounds(RuntimeOrigin::root(), KYL, Some(bounds)),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		assert_noop!(
+			TemplateModule::set_borrow_rate_bounds(
+				RuntimeOrigin::root(),
+				DOT,
+				Some((bounds.1, bounds.0))
+			),
+			Error::<Test>::InvalidRateBounds
+		);
+		assert_ok!(TemplateModule::set_borrow_rate_bounds(
+			RuntimeOrigin::root(),
+			DOT,
+			Some(bounds)
+		));
+		System::assert_last_event(
+			Event::<Test>::BorrowRateBoundsSet { asset: DOT, bounds: Some(bounds) }.into(),
+		);
+
+		// an idle pool borrows at the floor
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.borrow_interest_rate().unwrap(), bounds.0);
+
+		// at 40% utilisation the model yields 4%, capped at 3%
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			KYL,
+			400_000,
+			None
+		));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.borrow_interest_rate().unwrap(), bounds.1);
+
+		let year = 365 * 24 * 60 * 60;
+		Timestamp::set_timestamp(year * 1_000);
+		let index = math::compounded_interest(bounds.1, year).unwrap();
+		let debt = math::mul_rate(200_000u128, index, Rounding::Up).unwrap();
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+
+		// lifting the bounds accrues the interest at the capped rate up to now
+		assert_ok!(TemplateModule::set_borrow_rate_bounds(RuntimeOrigin::root(), DOT, None));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.borrow_index, index);
+		assert_eq!(TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap().balance, debt);
+	});
+}
+
+#[test]
+fn pool_outflows_are_capped_over_a_sliding_window() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_noop!(
+			TemplateModule::set_outflow_cap(RuntimeOrigin::signed(ALICE), DOT, Some(100_000)),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_outflow_cap(RuntimeOrigin::root(), KYL, Some(100_000)),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		assert_ok!(TemplateModule::set_outflow_cap(RuntimeOrigin::root(), DOT, Some(100_000)));
+		System::assert_last_event(
+			Event::<Test>::OutflowCapSet { asset: DOT, cap: Some(100_000) }.into(),
+		);
+
+		// the withdrawals and the borrows add up against the cap
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 60_000));
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 50_000, KYL, 100_000, None),
+			Error::<Test>::OutflowCapExceeded
+		);
+
+		// half the window later, half the outflow is left
+		System::set_block_number(6);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			50_000,
+			KYL,
+			100_000,
+			None
+		));
+		assert_eq!(PoolOutflows::<Test>::get(DOT), Some((80_000, 6)));
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 30_000),
+			Error::<Test>::OutflowCapExceeded
+		);
+
+		// a whole window later, the cap is available again
+		System::set_block_number(16);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+
+		assert_ok!(TemplateModule::set_outflow_cap(RuntimeOrigin::root(), DOT, None));
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 150_000));
+	});
+}
+
+#[test]
+fn supplies_can_not_be_withdrawn_before_the_cooldown() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		WithdrawCooldown::set(2);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 10_000, None));
+		assert_eq!(LastSupplies::<Test>::get((ALICE, DOT)), Some(1));
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 10_000),
+			Error::<Test>::WithdrawalInCooldown
+		);
+
+		// the LP tokens carry the cooldown along
+		assert_ok!(TemplateModule::transfer_lp_tokens(
+			RuntimeOrigin::signed(ALICE),
+			DOT,
+			BOB,
+			10_000
+		));
+		System::set_block_number(2);
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(BOB), DOT, 10_000),
+			Error::<Test>::WithdrawalInCooldown
+		);
+
+		System::set_block_number(3);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(BOB), DOT, 10_000));
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 10_000));
+		assert!(!LastSupplies::<Test>::contains_key((ALICE, DOT)));
+		assert!(!LastSupplies::<Test>::contains_key((BOB, DOT)));
+	});
+}
+
+#[test]
+fn the_average_utilisation_is_weighted_by_time() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let pool = || LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let utilisation = pool().utilisation_ratio().unwrap();
+		assert_ne!(utilisation, Permill::zero());
+		assert_eq!(pool().average_utilisation, Permill::zero());
+
+		// a quarter of the period at the current utilisation moves the average a quarter of the
+		// way towards it
+		let period = UtilisationAveragePeriod::get();
+		Timestamp::set_timestamp(period / 4 * 1_000);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		assert_eq!(pool().average_utilisation, Permill::from_percent(25) * utilisation);
+
+		// a whole period later, the average is the utilisation
+		Timestamp::set_timestamp(2 * period * 1_000);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		assert_eq!(pool().average_utilisation, utilisation);
+		assert_eq!(
+			TemplateModule::pool_info(DOT).unwrap().average_utilisation,
+			pool().average_utilisation
+		);
+	});
+}
+
+#[test]
+fn interest_accruals_keep_a_bounded_history_of_the_rates() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let accrue_at = |block: u64, seconds: u64| {
+			System::set_block_number(block);
+			Timestamp::set_timestamp(seconds * 1_000);
+			assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		};
+		let blocks = || RateSnapshots::<Test>::get(DOT).iter().map(|s| s.block).collect::<Vec<_>>();
+
+		accrue_at(1, 1);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let snapshot = RateSnapshots::<Test>::get(DOT)[0].clone();
+		assert_eq!(snapshot.borrow_rate, pool.borrow_interest_rate().unwrap());
+		assert_eq!(snapshot.supply_rate, pool.supply_interest_rate().unwrap());
+		assert_eq!(snapshot.exchange_rate, pool.supply_index);
+
+		// at most one snapshot per `RateSnapshotInterval`
+		accrue_at(5, 2);
+		assert_eq!(blocks(), vec![1]);
+
+		// the oldest snapshots make way for the new ones
+		accrue_at(11, 3);
+		accrue_at(21, 4);
+		accrue_at(31, 5);
+		assert_eq!(blocks(), vec![11, 21, 31]);
+	});
+}
+
+#[test]
+fn each_interest_accrual_emits_the_interest_and_the_reserves() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let year = 365 * 24 * 60 * 60;
+		Timestamp::set_timestamp(year * 1_000);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+
+		// 4% compounded every second over a year on the 200_000 borrowed, 10% of it reserved
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		System::assert_last_event(
+			Event::<Test>::InterestAccrued {
+				asset: DOT,
+				borrow_index: pool.borrow_index,
+				interest: 8_162,
+				reserves: 816,
+			}
+			.into(),
+		);
+
+		// the operations of the users accrue the interest as well
+		System::reset_events();
+		Timestamp::set_timestamp(2 * year * 1_000);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, None));
+		assert!(System::events().iter().any(|record| matches!(
+			record.event,
+			RuntimeEvent::TemplateModule(Event::InterestAccrued { asset: DOT, .. })
+		)));
+	});
+}
+
+#[test]
+fn the_protocol_totals_add_up_the_pools_valued_in_the_base_asset() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			DOT,
+			0,
+			FixedU128::from(2)
+		));
+		setup_borrow();
+
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let totals = PoolTotals::<Test>::get(DOT);
+		assert_eq!(
+			totals,
+			ProtocolTotals {
+				supplied: 2 * (pool.reserve_balance + pool.borrowed_balance),
+				borrowed: 2 * pool.borrowed_balance,
+				reserves: 2 * pool.reserve_balance,
+				bad_debt: 0,
+			}
+		);
+		assert_eq!(ProtocolStats::<Test>::get(), totals);
+		assert_eq!(TemplateModule::protocol_stats(), totals);
+
+		// a pool without a price is valued at zero until its asset is priced
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(BOB),
+			KYL_POOL_ID,
+			KYL,
+			100_000
+		));
+		assert_eq!(PoolTotals::<Test>::get(KYL).supplied, 0);
+		assert_eq!(ProtocolStats::<Test>::get(), totals);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			0,
+			FixedU128::from(3)
+		));
+		assert_eq!(PoolTotals::<Test>::get(KYL).supplied, 300_000);
+		assert_eq!(ProtocolStats::<Test>::get().supplied, totals.supplied + 300_000);
+		assert_eq!(ProtocolStats::<Test>::get().reserves, totals.reserves + 300_000);
+		assert_eq!(ProtocolStats::<Test>::get().borrowed, totals.borrowed);
+	});
+}
+
+#[test]
+fn lp_tokens_are_only_accepted_as_collateral_at_a_share_of_the_collateral_factor() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(BOB),
+			KYL_POOL_ID,
+			KYL,
+			500_000
+		));
+		assert_ok!(TemplateModule::activate_lending_pool(RuntimeOrigin::signed(BOB), KYL));
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			LENDING_POOL_ID,
+			KYL,
+			FixedU128::from(1)
+		));
+		assert_eq!(LpTokenPools::<Test>::get(LENDING_POOL_ID), Some(DOT));
+
+		// ALICE can not loop her supply of DOT into a borrow
+		assert_noop!(
+			TemplateModule::borrow(
+				RuntimeOrigin::signed(ALICE),
+				KYL,
+				100_000,
+				LENDING_POOL_ID,
+				400_000,
+				None
+			),
+			Error::<Test>::LpTokenCollateralNotAllowed
+		);
+
+		assert_noop!(
+			TemplateModule::set_lp_collateral_factor(
+				RuntimeOrigin::signed(ALICE),
+				DOT,
+				Some(Permill::from_percent(50))
+			),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::set_lp_collateral_factor(
+			RuntimeOrigin::root(),
+			DOT,
+			Some(Permill::from_percent(50))
+		));
+		System::assert_last_event(
+			Event::<Test>::LpCollateralFactorSet {
+				asset: DOT,
+				factor: Some(Permill::from_percent(50)),
+			}
+			.into(),
+		);
+
+		// the LP tokens are worth half of the 50% collateral factor
+		assert_noop!(
+			TemplateModule::borrow(
+				RuntimeOrigin::signed(ALICE),
+				KYL,
+				100_001,
+				LENDING_POOL_ID,
+				400_000,
+				None
+			),
+			Error::<Test>::NotEnoughCollateral
+		);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(ALICE),
+			KYL,
+			100_000,
+			LENDING_POOL_ID,
+			400_000,
+			None
+		));
+	});
+}
+
+#[test]
+fn dust_debts_with_a_shortfall_can_be_written_off() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_noop!(
+			TemplateModule::write_off_dust_debt(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::BorrowNotLiquidatable
+		);
+
+		// the collateral loses its value, the debt is worth liquidating
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::saturating_from_rational(1, 100)
+		));
+		assert_noop!(
+			TemplateModule::write_off_dust_debt(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::DebtNotDust
+		);
+
+		// BOB repays all but 5, below the `DustBalance` of 10, leaving 10 KYL of collateral
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 199_995, KYL));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().collateral_balance, 10);
+		assert_ok!(TemplateModule::write_off_dust_debt(
+			RuntimeOrigin::signed(ALICE),
+			BOB,
+			DOT,
+			KYL
+		));
+		System::assert_last_event(
+			Event::<Test>::DustDebtWrittenOff { who: BOB, asset: DOT, balance: 5 }.into(),
+		);
+
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)), None);
+		assert!(AccountBorrows::<Test>::get(BOB).is_empty());
+		assert_eq!(balance(KYL, TreasuryAccount::get()), 10);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.borrowed_balance, 0);
+		assert_ok!(TemplateModule::do_try_state());
+	});
+}
+
+#[test]
+fn partial_repayments_release_the_collateral_of_large_borrows() {
+	new_test_ext().execute_with(|| {
+		let unit = 10u128.pow(28);
+		create_asset_and_mint(DOT, ALICE, 200 * unit);
+		create_asset_and_mint(KYL, BOB, 1_000 * unit);
+		setup_active_pool(100 * unit);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			10 * unit,
+			KYL,
+			40 * unit,
+			None
+		));
+
+		// half of the debt is repaid, half of the collateral is released
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 5 * unit, KYL));
+		assert_eq!(balance(KYL, BOB), 980 * unit);
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().collateral_balance, 20 * unit);
+	});
+}
+
+#[test]
+fn overflowing_collateral_values_surface_an_arithmetic_error() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, u128::MAX / 2);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1_000)
+		));
+		assert_noop!(
+			TemplateModule::borrow(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				1_000,
+				KYL,
+				u128::MAX / 4,
+				None
+			),
+			ArithmeticError::Overflow
+		);
+	});
+}
+
+#[test]
+fn best_effort_withdrawals_drain_the_pool_without_reaping_the_pallet_account() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(1_000_000);
+
+		// exact withdrawals fail beyond the deposit, best effort ones are capped to it
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 1_000_001),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_ok!(TemplateModule::withdraw_best_effort(
+			RuntimeOrigin::signed(ALICE),
+			DOT,
+			u128::MAX
+		));
+		System::assert_last_event(
+			Event::<Test>::DepositWithdrawn {
+				who: ALICE,
+				asset: DOT,
+				balance: 1_000_000,
+				burned: 1_000_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+				lp_tokens: 0,
+				utilisation: Permill::zero(),
+			}
+			.into(),
+		);
+
+		// the last unit of the reserves left the pool account, which is still alive
+		let pool_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		assert_eq!(balance(DOT, ALICE), 1_000_000);
+		assert_eq!(balance(DOT, pool_account), 0);
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 0);
+		assert!(System::providers(&pool_account) > 0);
+		assert_noop!(
+			TemplateModule::withdraw_best_effort(RuntimeOrigin::signed(ALICE), DOT, 1),
+			Error::<Test>::AmountTooLow
+		);
+	});
+}
+
+#[test]
+fn each_pool_holds_its_balances_in_its_own_account() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(BOB),
+			KYL_POOL_ID,
+			KYL,
+			100_000
+		));
+
+		// the DOT pool holds its reserves and the collateral of its borrow, the KYL pool its
+		// reserves, the pallet account nothing
+		let dot_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		let kyl_account = TemplateModule::pool_account_id(KYL_POOL_ID);
+		assert_ne!(dot_account, kyl_account);
+		assert_eq!((balance(DOT, dot_account), balance(KYL, dot_account)), (300_000, 400_000));
+		assert_eq!((balance(DOT, kyl_account), balance(KYL, kyl_account)), (0, 100_000));
+		let pallet_account = TemplateModule::account_id();
+		assert_eq!((balance(DOT, pallet_account), balance(KYL, pallet_account)), (0, 0));
+
+		// the collateral is released out of the DOT pool account
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 200_000, KYL));
+		assert_eq!((balance(DOT, dot_account), balance(KYL, dot_account)), (500_000, 0));
+		assert_eq!(balance(KYL, kyl_account), 100_000);
+	});
+}
+
+#[test]
+fn foreign_assets_back_lending_pools_through_a_union_of_asset_providers() {
+	const FOREIGN_DOT: Token = FOREIGN_ASSETS + 1;
+	const FOREIGN_POOL_ID: Token = FOREIGN_ASSETS + 2;
+
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(ForeignAssets::force_create(
+			RuntimeOrigin::root(),
+			FOREIGN_DOT.into(),
+			ALICE,
+			true,
+			1
+		));
+		assert_ok!(ForeignAssets::mint(
+			RuntimeOrigin::signed(ALICE),
+			FOREIGN_DOT.into(),
+			BOB,
+			1_000_000
+		));
+
+		// BOB borrows local DOT against bridged DOT, held in the account of the DOT pool
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			FOREIGN_DOT,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			FOREIGN_DOT,
+			400_000,
+			None
+		));
+		let dot_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		assert_eq!(ForeignAssets::balance(FOREIGN_DOT, dot_account), 400_000);
+		assert_eq!(balance(DOT, BOB), 200_000);
+
+		// a pool of the bridged DOT holds it, and mints its LP tokens in the foreign assets
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(BOB),
+			FOREIGN_POOL_ID,
+			FOREIGN_DOT,
+			100_000
+		));
+		let foreign_account = TemplateModule::pool_account_id(FOREIGN_POOL_ID);
+		assert_eq!(ForeignAssets::balance(FOREIGN_DOT, foreign_account), 100_000);
+		assert_eq!(ForeignAssets::balance(FOREIGN_POOL_ID, BOB), 100_000);
+		assert_eq!(balance(FOREIGN_POOL_ID, BOB), 0);
+	});
+}
+
+#[test]
+fn users_of_other_chains_supply_from_their_derived_account() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+
+		// the reserve transferred DOT of the remote BOB were deposited into its derived account
+		let remote_bob = BOB + REMOTE_ACCOUNTS;
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), remote_bob, 100_000));
+		assert_noop!(
+			TemplateModule::supply_from_remote(RuntimeOrigin::root(), DOT, 100_000),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::supply_from_remote(RuntimeOrigin::signed(BOB), DOT, 100_000));
+		System::assert_last_event(
+			Event::<Test>::DepositSupplied {
+				who: remote_bob,
+				asset: DOT,
+				balance: 100_000,
+				minted: 100_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+				lp_tokens: 100_000,
+				utilisation: Permill::zero(),
+			}
+			.into(),
+		);
+
+		// the position is credited to the derived account
+		assert_eq!(balance(LENDING_POOL_ID, remote_bob), 100_000);
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 0);
+		assert_eq!(balance(DOT, remote_bob), 0);
+	});
+}
+
+#[test]
+fn borrowed_assets_are_delivered_to_other_chains() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		// the beneficiary of BOB on the sibling chain
+		let beneficiary = 7;
+		assert_ok!(TemplateModule::borrow_to_chain(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			KYL,
+			400_000,
+			Box::new(beneficiary)
+		));
+		System::assert_has_event(
+			Event::<Test>::DepositBorrowed {
+				who: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				fee: 0,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+				debt: 200_000,
+				utilisation: Permill::from_percent(40),
+			}
+			.into(),
+		);
+		System::assert_last_event(
+			Event::<Test>::BorrowSentToChain { who: BOB, asset: DOT, dest: beneficiary }.into(),
+		);
+
+		// the proceeds left through the reserve of the sibling chain, the debt stays with BOB
+		assert_eq!(balance(DOT, BOB), 0);
+		assert_eq!(balance(DOT, SIBLING_SOVEREIGN), 200_000);
+		assert_eq!(MockTransferToChain::sent(), vec![(beneficiary, DOT, 200_000)]);
+		assert!(Borrows::<Test>::contains_key((BOB, DOT, KYL)));
+	});
+}
+
+#[test]
+fn the_governance_of_the_relay_chain_manages_the_risk_parameters() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+
+		// the origin of any other account is refused
+		assert_noop!(
+			TemplateModule::set_borrow_fee(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				Permill::from_percent(1)
+			),
+			BadOrigin
+		);
+
+		assert_ok!(TemplateModule::set_borrow_fee(
+			RuntimeOrigin::signed(RELAY_GOVERNANCE),
+			DOT,
+			Permill::from_percent(1)
+		));
+		System::assert_last_event(
+			Event::<Test>::BorrowFeeSet { asset: DOT, fee: Permill::from_percent(1) }.into(),
+		);
+		assert_ok!(TemplateModule::set_protocol_fee(
+			RuntimeOrigin::signed(RELAY_GOVERNANCE),
+			Permill::from_percent(10)
+		));
+		assert_ok!(TemplateModule::set_min_borrow(
+			RuntimeOrigin::signed(RELAY_GOVERNANCE),
+			DOT,
+			1_000
+		));
+		System::assert_last_event(
+			Event::<Test>::MinBorrowSet { asset: DOT, min_borrow: 1_000 }.into(),
+		);
+	});
+}
+
+#[test]
+fn collateral_is_valued_with_the_decimals_of_the_assets() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		// a whole KYL is worth a whole DOT, but KYL has 2 more decimals
+		for (asset, decimals) in [(DOT, 10), (KYL, 12)] {
+			assert_ok!(Assets::force_set_metadata(
+				RuntimeOrigin::root(),
+				asset.into(),
+				b"Token".to_vec(),
+				b"TKN".to_vec(),
+				decimals,
+				false
+			));
+		}
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		assert_eq!(TemplateModule::asset_decimals(KYL), 12);
+		assert_eq!(TemplateModule::get_equivalent_asset_amount(&BOB, DOT, KYL, 400_000), Ok(4_000));
+		// the same price quoted the other way
+		AssetPrices::<Test>::remove((KYL, DOT));
+		AssetPrices::<Test>::insert((DOT, KYL), FixedU128::from(1));
+		assert_eq!(TemplateModule::get_equivalent_asset_amount(&BOB, DOT, KYL, 400_000), Ok(4_000));
+
+		// 400_000 KYL are worth 4_000 DOT only
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 200_000, KYL, 400_000, None),
+			Error::<Test>::NotEnoughCollateral
+		);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			2_000,
+			KYL,
+			400_000,
+			None
+		));
+	});
+}
+
+#[test]
+fn other_pallets_use_the_money_market_through_the_lending_trait() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(100_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+
+		assert_ok!(<TemplateModule as Lending<_, _, _>>::supply(&ALICE, DOT, 400_000));
+		System::assert_last_event(
+			Event::<Test>::DepositSupplied {
+				who: ALICE,
+				asset: DOT,
+				balance: 400_000,
+				minted: 400_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+				lp_tokens: 500_000,
+				utilisation: Permill::zero(),
+			}
+			.into(),
+		);
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 500_000);
+
+		assert_eq!(
+			<TemplateModule as Lending<_, _, _>>::borrow(&BOB, DOT, 200_000, KYL, 400_000),
+			Ok(0)
+		);
+		assert_eq!(balance(DOT, BOB), 200_000);
+		assert_eq!(
+			<TemplateModule as Lending<_, _, _>>::collateral_value(&BOB, DOT, KYL),
+			Ok(400_000)
+		);
+		assert_noop!(
+			<TemplateModule as Lending<_, _, _>>::collateral_value(&ALICE, DOT, KYL),
+			Error::<Test>::LoanDoesNotExists
+		);
+
+		// ALICE repays the debt of BOB and receives its collateral
+		assert_ok!(<TemplateModule as Lending<_, _, _>>::repay_for(
+			&ALICE, &BOB, DOT, 200_000, KYL
+		));
+		System::assert_last_event(
+			Event::<Test>::DepositRepaid {
+				who: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+				debt: 0,
+				utilisation: Permill::zero(),
+			}
+			.into(),
+		);
+		assert!(Borrows::<Test>::get((BOB, DOT, KYL)).is_none());
+		assert_eq!(balance(KYL, ALICE), 400_000);
+
+		// the withdrawals are capped to the supply of ALICE
+		assert_eq!(
+			<TemplateModule as Lending<_, _, _>>::withdraw(&ALICE, DOT, 1_000_000),
+			Ok(500_000)
+		);
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 0);
+	});
+}
+
+#[test]
+fn failing_composite_operations_are_rolled_back_out_of_a_dispatch() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, BOB, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::set_stable_rate_premium(
+			RuntimeOrigin::root(),
+			DOT,
+			Some(Permill::from_percent(1))
+		));
+		assert_ok!(TemplateModule::set_outflow_cap(RuntimeOrigin::root(), DOT, Some(300_000)));
+
+		// the borrow went through before its rate is found above the maximum rate
+		assert_noop!(
+			TemplateModule::do_borrow_fixed_term(
+				&BOB,
+				DOT,
+				200_000,
+				KYL,
+				400_000,
+				30 * 24 * 60 * 60,
+				Rate::zero()
+			),
+			Error::<Test>::BorrowRateTooHigh
+		);
+
+		// the outflow is recorded before the collateral is found short of the borrow
+		assert_noop!(
+			<TemplateModule as Lending<_, _, _>>::borrow(&BOB, DOT, 200_000, KYL, 100_000),
+			Error::<Test>::NotEnoughCollateral
+		);
+		assert!(PoolOutflows::<Test>::get(DOT).is_none());
+	});
+}
+
+#[test]
+fn the_maintenance_mode_blocks_all_calls_but_the_repayments() {
+	use crate::{Call, MaintenanceFilter};
+	use frame_support::traits::Contains;
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let supply = Call::<Test>::supply { asset: DOT, balance: 1_000, referral: None };
+		let repay = Call::<Test>::repay { asset: DOT, balance: 1_000, collateral_asset: KYL };
+		let lift = Call::<Test>::set_maintenance_mode { enabled: false };
+		assert!(MaintenanceFilter::<Test>::contains(&supply));
+
+		assert_noop!(
+			TemplateModule::set_maintenance_mode(RuntimeOrigin::signed(ALICE), true),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::set_maintenance_mode(RuntimeOrigin::root(), true));
+		System::assert_last_event(Event::<Test>::MaintenanceModeSet { enabled: true }.into());
+		assert!(!MaintenanceFilter::<Test>::contains(&supply));
+		assert!(MaintenanceFilter::<Test>::contains(&repay));
+		assert!(MaintenanceFilter::<Test>::contains(&lift));
+
+		assert_ok!(TemplateModule::set_maintenance_mode(RuntimeOrigin::root(), false));
+		assert!(MaintenanceFilter::<Test>::contains(&supply));
+	});
+}
+
+#[test]
+fn lending_hooks_are_called_on_each_operation() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 100_000, None));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 50_000, KYL));
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 4)
+		));
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), TREASURY, 300_000));
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::none(), BOB, DOT, KYL));
+
+		assert_eq!(
+			MockLendingHooks::calls(),
+			vec![
+				("supply", ALICE, DOT, 500_000),
+				("borrow", BOB, DOT, 200_000),
+				("supply", ALICE, DOT, 100_000),
+				("repay", BOB, DOT, 50_000),
+				("repay", BOB, DOT, 150_000),
+				("liquidation", BOB, DOT, 150_000),
+			]
+		);
+	});
+}
+
+#[test]
+fn borrows_with_a_shortfall_can_be_liquidated() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), TREASURY, 300_000));
+
+		// 80% of the 400_000 collateral covers the 200_000 debt
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(0));
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::BorrowNotLiquidatable
+		);
+		let call =
+			crate::Call::liquidate_borrow { borrower: BOB, asset: DOT, collateral_asset: KYL };
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::Local, &call),
+			InvalidTransaction::Stale.into()
+		);
+
+		// the collateral halves in value, 80% of it covers only 160_000
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(40_000));
+		assert!(TemplateModule::validate_unsigned(TransactionSource::Local, &call).is_ok());
+
+		// unsigned liquidations are repaid by the treasury
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::root(), BOB, DOT, KYL),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::none(), BOB, DOT, KYL));
+		System::assert_last_event(
+			Event::<Test>::BorrowLiquidated {
+				who: TREASURY,
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+			}
+			.into(),
+		);
+		assert!(Borrows::<Test>::get((BOB, DOT, KYL)).is_none());
+		assert_eq!(balance(DOT, TREASURY), 100_000);
+		assert_eq!(balance(KYL, TREASURY), 400_000);
+		assert_eq!(balance(KYL, BOB), 600_000);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!((pool.reserve_balance, pool.borrowed_balance), (500_000, 0));
+	});
+}
+
+#[test]
+fn liquidators_without_inventory_swap_the_collateral_for_the_debt() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), DEX_ACCOUNT, 300_000));
+		let keeper = 3;
+
+		// at 0.4 DOT the collateral does not buy the debt back
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(2, 5)
+		));
+		assert_noop!(
+			TemplateModule::liquidate_borrow_with_swap(
+				RuntimeOrigin::signed(keeper),
+				BOB,
+				DOT,
+				KYL
+			),
+			DispatchError::Other("slippage exceeded")
+		);
+
+		// at 0.6 DOT, 333_333 KYL buy the 200_000 DOT of debt
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(3, 5)
+		));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(8_000));
+		assert_ok!(TemplateModule::liquidate_borrow_with_swap(
+			RuntimeOrigin::signed(keeper),
+			BOB,
+			DOT,
+			KYL
+		));
+		System::assert_last_event(
+			Event::<Test>::BorrowLiquidated {
+				who: keeper,
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				exchange_rate: Rate::one(),
+				borrow_index: Rate::one(),
+			}
+			.into(),
+		);
+
+		// the keeper seizes the rest of the collateral
+		assert!(Borrows::<Test>::get((BOB, DOT, KYL)).is_none());
+		assert_eq!(balance(DOT, keeper), 0);
+		assert_eq!(balance(KYL, keeper), 66_667);
+		assert_eq!(balance(KYL, DEX_ACCOUNT), 333_333);
+		assert_eq!(balance(DOT, DEX_ACCOUNT), 100_000);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!((pool.reserve_balance, pool.borrowed_balance), (500_000, 0));
+	});
+}
+
+#[test]
+fn offchain_worker_submits_liquidations() {
+	let mut ext = new_test_ext();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		setup_borrow();
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+
+		// the borrows are only scanned every `OffchainScanInterval` blocks
+		TemplateModule::offchain_worker(5);
+		assert!(pool_state.read().transactions.is_empty());
+
+		TemplateModule::offchain_worker(10);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(
+			tx.call,
+			RuntimeCall::TemplateModule(crate::Call::liquidate_borrow {
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL
+			})
+		);
+
+		// the treasury may swap the seized collateral instead of repaying with its inventory
+		SwapOffchainLiquidations::set(true);
+		TemplateModule::offchain_worker(20);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert_eq!(
+			Extrinsic::decode(&mut &*tx).unwrap().call,
+			RuntimeCall::TemplateModule(crate::Call::liquidate_borrow_with_swap {
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL
+			})
+		);
+	});
+}
+
+#[test]
+fn keeper_transactions_are_validated_and_prioritised() {
+	let mut ext = new_test_ext();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		setup_borrow();
+
+		// nothing to accrue in the block the pool was accrued in
+		let accrual = crate::Call::accrue_interest { asset: DOT };
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &accrual),
+			InvalidTransaction::Stale.into()
+		);
+		assert_noop!(
+			TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT),
+			Error::<Test>::NoInterestToAccrue
+		);
+
+		// a second later the interest is due
+		Timestamp::set_timestamp(1_000);
+		assert!(TemplateModule::validate_unsigned(TransactionSource::External, &accrual).is_ok());
+		assert_noop!(TemplateModule::accrue_interest(RuntimeOrigin::signed(ALICE), DOT), BadOrigin);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert!(pool.borrow_index > Rate::from(1));
+		assert_eq!(pool.last_accrued_interest_at, 1);
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &accrual),
+			InvalidTransaction::Stale.into()
+		);
+
+		// liquidations are prioritised by their shortfall
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+		let shortfall = TemplateModule::shortfall(&BOB, DOT, KYL).unwrap();
+		assert!(shortfall > 40_000);
+		let liquidation =
+			crate::Call::liquidate_borrow { borrower: BOB, asset: DOT, collateral_asset: KYL };
+		let validity =
+			TemplateModule::validate_unsigned(TransactionSource::External, &liquidation).unwrap();
+		assert_eq!(validity.priority, UnsignedPriority::get() + shortfall as u64);
+		assert_eq!(validity.provides.len(), 1);
+
+		// the offchain worker submits both the accrual and the liquidation
+		Timestamp::set_timestamp(2_000);
+		TemplateModule::offchain_worker(10);
+		let calls: Vec<_> = pool_state
+			.read()
+			.transactions
+			.iter()
+			.map(|tx| Extrinsic::decode(&mut &**tx).unwrap().call)
+			.collect();
+		assert_eq!(
+			calls,
+			vec![RuntimeCall::TemplateModule(accrual), RuntimeCall::TemplateModule(liquidation)]
+		);
+	});
+}
+
+#[test]
+fn on_initialize_accrues_the_pools_round_robin() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(ALICE),
+			KYL_POOL_ID,
+			KYL,
+			500_000
+		));
+		assert_ok!(TemplateModule::activate_lending_pool(RuntimeOrigin::signed(ALICE), KYL));
+		let accrued_at = |asset| {
+			LendingPoolStorage::<Test>::get(AssetPool::from(asset))
+				.unwrap()
+				.last_accrued_interest_at
+		};
+
+		// one pool per block with `MaxPoolsPerBlock` = 1
+		Timestamp::set_timestamp(10_000);
+		TemplateModule::on_initialize(2);
+		let (first, second) = if accrued_at(DOT) == 10 { (DOT, KYL) } else { (KYL, DOT) };
+		assert_eq!((accrued_at(first), accrued_at(second)), (10, 0));
+
+		TemplateModule::on_initialize(3);
+		assert_eq!((accrued_at(first), accrued_at(second)), (10, 10));
+
+		// the cursor wraps around
+		Timestamp::set_timestamp(20_000);
+		TemplateModule::on_initialize(4);
+		assert_eq!((accrued_at(first), accrued_at(second)), (20, 10));
+
+		// inactive pools are skipped
+		assert_ok!(TemplateModule::do_deactivate_lending_pool(second));
+		TemplateModule::on_initialize(5);
+		assert_eq!((accrued_at(first), accrued_at(second)), (20, 10));
+	});
+}
+
+#[test]
+fn on_idle_sweeps_dust_positions() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 1_000));
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 5, None));
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 5);
+
+		// nothing is swept without enough weight
+		assert_eq!(TemplateModule::on_idle(1, Weight::zero()), Weight::zero());
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 5);
+
+		assert!(TemplateModule::on_idle(1, Weight::MAX).all_gt(Weight::zero()));
+		System::assert_has_event(
+			Event::<Test>::DustSwept { who: BOB, asset: DOT, balance: 5 }.into(),
+		);
+		assert_eq!(balance(LENDING_POOL_ID, BOB), 0);
+		assert!(!SupplyIndexStorage::<Test>::contains_key((BOB, DOT)));
+
+		// positions above the dust threshold are left untouched
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 500_000);
+		assert!(SupplyIndexStorage::<Test>::contains_key((ALICE, DOT)));
+
+		// the underlying assets stay in the pool reserves
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.reserve_balance, 500_005);
+	});
+}
+
+#[test]
+fn try_state_checks_the_accounting_invariants() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(TemplateModule::do_try_state());
+
+		// BOB repays half of the borrow with the interest 30 days later
+		Timestamp::set_timestamp(30 * 24 * 60 * 60 * 1_000);
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 100_000, KYL));
+		assert_ok!(TemplateModule::do_try_state());
+
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		LendingPoolStorage::<Test>::mutate(AssetPool::from(DOT), |p| {
+			p.as_mut().unwrap().borrowed_balance += 1;
+		});
+		assert!(TemplateModule::do_try_state().is_err());
+
+		LendingPoolStorage::<Test>::mutate(AssetPool::from(DOT), |p| {
+			let p = p.as_mut().unwrap();
+			p.borrowed_balance -= 1;
+			p.reserve_balance += 1_000_000;
+		});
+		assert!(TemplateModule::do_try_state().is_err());
+
+		LendingPoolStorage::<Test>::insert(AssetPool::from(DOT), pool);
+		assert_ok!(TemplateModule::do_try_state());
+	});
+}
+
+#[test]
+fn upgrades_preserve_the_pool_and_position_aggregates() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+
+		let state = TemplateModule::do_pre_upgrade().unwrap();
+		TemplateModule::on_runtime_upgrade();
+		assert_ok!(TemplateModule::do_post_upgrade(state.clone()));
+
+		// a migration losing collateral is caught
+		Borrows::<Test>::mutate((BOB, DOT, KYL), |borrow| {
+			borrow.as_mut().unwrap().collateral_balance -= 1;
+		});
+		assert!(TemplateModule::do_post_upgrade(state).is_err());
+	});
+}
+
+#[test]
+fn v1_migration_translates_the_lending_pools() {
+	use crate::migrations::v1;
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		Timestamp::set_timestamp(10_000);
+		let old = v1::LendingPoolV0::<Test> {
+			id: LENDING_POOL_ID,
+			lend_token_id: DOT,
+			reserve_balance: 500_000,
+			borrowed_balance: 100_000,
+			activated: true,
+			interest_model: crate::InterestRateModel::hardcoded_default_interest(),
+			reserve_factor: Permill::from_percent(10),
+			exchange_rate: Permill::from_percent(0),
+			collateral_factor: Permill::from_percent(50),
+			liquidation_threshold: Permill::from_percent(80),
+			borrow_rate: Permill::from_percent(20),
+			supply_rate: Permill::from_percent(0),
+			last_accrued_interest_at: 5,
+			borrow_index: Rate::saturating_from_rational(11, 10),
+			supply_index: Rate::saturating_from_rational(105, 100),
+		};
+		frame_support::storage::unhashed::put(
+			&LendingPoolStorage::<Test>::hashed_key_for(AssetPool::from(DOT)),
+			&old,
+		);
+		v1::BorrowFees::<Test>::insert(DOT, Permill::from_percent(1));
+		AssetPrices::<Test>::insert((DOT, 0), FixedU128::from(1));
+		StorageVersion::new(0).put::<TemplateModule>();
+
+		v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(TemplateModule::on_chain_storage_version(), 1);
+		assert!(!v1::BorrowFees::<Test>::contains_key(DOT));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!((pool.reserve_balance, pool.borrowed_balance), (500_000, 100_000));
+		assert_eq!(pool.borrow_index, old.borrow_index);
+		assert_eq!(pool.supply_index, old.supply_index);
+		assert_eq!(pool.last_accrued_interest_at, 5);
+		assert_eq!(pool.borrow_fee, Permill::from_percent(1));
+		assert_eq!(pool.created_at, 10);
+		assert_eq!(LendingPoolStorage::<Test>::count(), 1);
+		assert_eq!(pool.average_utilisation, Permill::from_rational(1u32, 6u32));
+		assert_eq!(PoolTotals::<Test>::get(DOT).supplied, 600_000);
+		assert_eq!(ProtocolStats::<Test>::get(), PoolTotals::<Test>::get(DOT));
+		assert_eq!(LpTokenPools::<Test>::get(LENDING_POOL_ID), Some(DOT));
+
+		// the migration only runs once
+		v1::MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_eq!(LendingPoolStorage::<Test>::get(AssetPool::from(DOT)), Some(pool));
+	});
+}
+
+#[test]
+fn v1_migration_translates_the_borrows_over_several_blocks() {
+	use crate::migrations::{v1, MigrationFilter};
+	use frame_support::traits::{Contains, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		for who in [ALICE, BOB, TREASURY] {
+			v1::Borrows::<Test>::insert(
+				(who, DOT, KYL),
+				v1::UserBorrowV0 {
+					borrowed_asset: DOT,
+					borrowed_balance: 100_000,
+					collateral_asset: KYL,
+					collateral_balance: 200_000,
+				},
+			);
+		}
+		v1::BorrowPrincipals::<Test>::insert((ALICE, DOT, KYL), 90_000);
+		v1::BorrowPrincipals::<Test>::insert((BOB, DOT, KYL), 95_000);
+		StorageVersion::new(0).put::<TemplateModule>();
+
+		v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+		// the calls of the pallet are blocked until the borrows are translated
+		let call = crate::Call::<Test>::withdraw { asset: DOT, balance: 1 };
+		assert!(!MigrationFilter::<Test>::contains(&call));
+
+		// two borrows are translated per block
+		let block = v1::MigrateToV1::<Test>::entry_weight() * 2;
+		v1::MigrateToV1::<Test>::step(block);
+		assert!(crate::MigrationCursor::<Test>::exists());
+		assert!(!MigrationFilter::<Test>::contains(&call));
+		v1::MigrateToV1::<Test>::step(block);
+		assert!(!crate::MigrationCursor::<Test>::exists());
+		assert!(MigrationFilter::<Test>::contains(&call));
+
+		assert!(v1::BorrowPrincipals::<Test>::iter_keys().next().is_none());
+		assert_eq!(
+			AccountBorrows::<Test>::iter_values()
+				.map(|borrows| borrows.len())
+				.sum::<usize>(),
+			3
+		);
+		let principals: Vec<_> = [ALICE, BOB, TREASURY]
+			.into_iter()
+			.map(|who| Borrows::<Test>::get((who, DOT, KYL)).unwrap())
+			.map(|borrow| (borrow.borrowed_balance, borrow.collateral_balance, borrow.principal))
+			.collect();
+		assert_eq!(
+			principals,
+			vec![
+				(100_000, 200_000, Some(90_000)),
+				(100_000, 200_000, Some(95_000)),
+				(100_000, 200_000, None)
+			]
+		);
+	});
+}
+
+#[test]
+fn v1_migration_moves_the_balances_into_the_pool_accounts() {
+	use crate::migrations::{v1, MigrationStage};
+	use frame_support::traits::{fungibles::Mutate, tokens::Preservation};
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let pool_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		let pallet_account = TemplateModule::account_id();
+		for (asset, balance) in [(DOT, 300_000), (KYL, 400_000)] {
+			assert_ok!(<Assets as Mutate<_>>::transfer(
+				asset,
+				&pool_account,
+				&pallet_account,
+				balance,
+				Preservation::Expendable
+			));
+		}
+		// the borrow of BOB back in the V0 layout
+		let borrow = Borrows::<Test>::take((BOB, DOT, KYL)).unwrap();
+		AccountBorrows::<Test>::remove(BOB);
+		v1::Borrows::<Test>::insert(
+			(BOB, DOT, KYL),
+			v1::UserBorrowV0 {
+				borrowed_asset: borrow.borrowed_asset,
+				borrowed_balance: borrow.borrowed_balance,
+				collateral_asset: borrow.collateral_asset,
+				collateral_balance: borrow.collateral_balance,
+			},
+		);
+		crate::MigrationCursor::<Test>::put(MigrationStage::Borrows(None));
+
+		// the collateral of the borrow, then the reserves of the pool, are moved one per block
+		let mut blocks = 0;
+		while crate::MigrationCursor::<Test>::exists() {
+			v1::MigrateToV1::<Test>::step(v1::MigrateToV1::<Test>::entry_weight());
+			blocks += 1;
+		}
+		assert_eq!(blocks, 3);
+
+		assert_eq!(AccountBorrows::<Test>::get(BOB).to_vec(), vec![(DOT, KYL)]);
+		assert_eq!((balance(DOT, pool_account), balance(KYL, pool_account)), (300_000, 400_000));
+		assert_eq!((balance(DOT, pallet_account), balance(KYL, pallet_account)), (0, 0));
+		assert_ok!(TemplateModule::do_try_state());
+	});
+}
+
+#[test]
+fn risk_parameters_change_only_once_the_announced_delay_elapsed() {
+	use crate::{InterestRateModel, ParamChange, PendingParamChanges};
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let asset_pool = AssetPool::<Test>::from(DOT);
+		let factor = Permill::from_percent(60);
+
+		assert_noop!(
+			TemplateModule::announce_param_change(
+				RuntimeOrigin::root(),
+				DOT,
+				ParamChange::CollateralFactor(Permill::from_percent(90))
+			),
+			Error::<Test>::InvalidRiskParameters
+		);
+		let model = InterestRateModel::new(Rate::zero(), Rate::zero(), Rate::zero(), Rate::zero());
+		assert_noop!(
+			TemplateModule::announce_param_change(
+				RuntimeOrigin::root(),
+				DOT,
+				ParamChange::RateModel(model)
+			),
+			Error::<Test>::InvalidRiskParameters
+		);
+
+		let change = ParamChange::CollateralFactor(factor);
+		assert_ok!(TemplateModule::announce_param_change(
+			RuntimeOrigin::root(),
+			DOT,
+			change.clone()
+		));
+		System::assert_last_event(
+			Event::ParamChangeAnnounced {
+				id: 0,
+				asset: DOT,
+				change: change.clone(),
+				execute_at: 11,
+			}
+			.into(),
+		);
+		assert_noop!(
+			TemplateModule::execute_param_change(RuntimeOrigin::root(), 0),
+			Error::<Test>::ParamChangeNotDue
+		);
+
+		// the scheduler leaves the pool as it is until the delay elapsed
+		System::set_block_number(10);
+		Scheduler::on_initialize(10);
+		let pool = LendingPoolStorage::<Test>::get(&asset_pool).unwrap();
+		assert_eq!(pool.collateral_factor, Permill::from_percent(50));
+
+		System::set_block_number(11);
+		Scheduler::on_initialize(11);
+		let pool = LendingPoolStorage::<Test>::get(&asset_pool).unwrap();
+		assert_eq!(pool.collateral_factor, factor);
+		assert!(!PendingParamChanges::<Test>::contains_key(0));
+		System::assert_has_event(Event::ParamChangeExecuted { id: 0, asset: DOT, change }.into());
+
+		// a cancelled change is never executed
+		let change = ParamChange::LiquidationThreshold(Permill::from_percent(70));
+		assert_ok!(TemplateModule::announce_param_change(RuntimeOrigin::root(), DOT, change));
+		assert_noop!(
+			TemplateModule::cancel_param_change(RuntimeOrigin::signed(ALICE), 1),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::cancel_param_change(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::ParamChangeCancelled { id: 1 }.into());
+
+		System::set_block_number(21);
+		Scheduler::on_initialize(21);
+		let pool = LendingPoolStorage::<Test>::get(&asset_pool).unwrap();
+		assert_eq!(pool.liquidation_threshold, Permill::from_percent(80));
+		assert_noop!(
+			TemplateModule::execute_param_change(RuntimeOrigin::root(), 1),
+			Error::<Test>::ParamChangeDoesNotExist
+		);
+	});
+}
+
+#[test]
+fn the_risk_parameters_of_the_governance_override_those_of_the_pools() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(0));
+		let set = |parameter, value: Option<u32>| {
+			TemplateModule::set_risk_parameter(
+				RuntimeOrigin::root(),
+				DOT,
+				parameter,
+				value.map(Permill::from_percent),
+			)
+		};
+		let factor = Some(Permill::from_percent(70));
+		assert_noop!(
+			TemplateModule::set_risk_parameter(
+				RuntimeOrigin::signed(ALICE),
+				DOT,
+				RiskParameter::CollateralFactor,
+				factor
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_risk_parameter(
+				RuntimeOrigin::root(),
+				KYL,
+				RiskParameter::CollateralFactor,
+				factor
+			),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+
+		assert_ok!(set(RiskParameter::CollateralFactor, Some(70)));
+		System::assert_last_event(
+			Event::RiskParameterSet {
+				asset: DOT,
+				parameter: RiskParameter::CollateralFactor,
+				value: factor,
+			}
+			.into(),
+		);
+		let pool_info = TemplateModule::pool_info(DOT).unwrap();
+		assert_eq!(pool_info.collateral_factor, Permill::from_percent(70));
+
+		// 40% of the 400_000 collateral covers only 160_000 of the 200_000 debt, and the
+		// collateral factor is capped to the liquidation threshold
+		assert_ok!(set(RiskParameter::LiquidationThreshold, Some(40)));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(40_000));
+		let pool_info = TemplateModule::pool_info(DOT).unwrap();
+		assert_eq!(pool_info.collateral_factor, Permill::from_percent(40));
+
+		assert_ok!(set(RiskParameter::ReserveFactor, Some(50)));
+		assert_eq!(
+			TemplateModule::pool_info(DOT).unwrap().reserve_factor,
+			Permill::from_percent(50)
+		);
+
+		// the stored parameters of the pool are left as they are
+		assert_ok!(set(RiskParameter::LiquidationThreshold, None));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(0));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		assert_eq!(pool.collateral_factor, Permill::from_percent(50));
+	});
+}
+
+#[test]
+fn only_the_whitelisted_accounts_supply_to_and_borrow_from_permissioned_pools() {
+	use crate::PoolWhitelist;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_noop!(
+			TemplateModule::set_permissioned_pool(RuntimeOrigin::signed(ALICE), DOT, true),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_permissioned_pool(RuntimeOrigin::root(), KYL, true),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		assert_ok!(TemplateModule::set_permissioned_pool(RuntimeOrigin::root(), DOT, true));
+		System::assert_last_event(
+			Event::PermissionedPoolSet { asset: DOT, permissioned: true }.into(),
+		);
+
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 100_000, None),
+			Error::<Test>::NotWhitelisted
+		);
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 20_000, None),
+			Error::<Test>::NotWhitelisted
+		);
+		// the positions open already can still be closed
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 50_000, KYL));
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+
+		assert_ok!(TemplateModule::set_whitelisted(RuntimeOrigin::root(), DOT, ALICE, true));
+		System::assert_last_event(
+			Event::WhitelistUpdated { asset: DOT, who: ALICE, whitelisted: true }.into(),
+		);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 100_000, None));
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 20_000, None),
+			Error::<Test>::NotWhitelisted
+		);
+
+		assert_ok!(TemplateModule::set_whitelisted(RuntimeOrigin::root(), DOT, ALICE, false));
+		assert!(!PoolWhitelist::<Test>::contains_key(DOT, ALICE));
+		assert_ok!(TemplateModule::set_permissioned_pool(RuntimeOrigin::root(), DOT, false));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			10_000,
+			KYL,
+			20_000,
+			None
+		));
+	});
+}
+
+#[test]
+fn the_compliance_check_screens_the_supplies_borrows_and_withdrawals() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		MockComplianceCheck::sanction(ALICE);
+		MockComplianceCheck::sanction(BOB);
+		let sanctioned = DispatchError::Other("sanctioned account");
+
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 100_000, None),
+			sanctioned
+		);
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 100_000),
+			sanctioned
+		);
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 20_000, None),
+			sanctioned
+		);
+		// the debts can still be repaid
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 50_000, KYL));
+	});
+}
+
+#[test]
+fn the_governance_settles_borrows_at_the_oracle_prices() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), TREASURY, 300_000));
+		assert_noop!(
+			TemplateModule::force_liquidate(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			BadOrigin
+		);
+
+		// only the oracle prices the assets
+		assert_noop!(
+			TemplateModule::set_asset_price(
+				RuntimeOrigin::signed(ALICE),
+				KYL,
+				DOT,
+				FixedU128::from(2)
+			),
+			BadOrigin
+		);
+
+		// at 2 DOT, 100_000 of the 400_000 KYL cover the 200_000 DOT of debt of a healthy borrow
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(2)
+		));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(0));
+		assert_ok!(TemplateModule::force_liquidate(RuntimeOrigin::root(), BOB, DOT, KYL));
+		System::assert_last_event(
+			Event::BorrowForceLiquidated {
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				seized: 100_000,
+			}
+			.into(),
+		);
+
+		assert!(!Borrows::<Test>::contains_key((BOB, DOT, KYL)));
+		assert_eq!(balance(DOT, TREASURY), 100_000);
+		assert_eq!(balance(KYL, TREASURY), 100_000);
+		assert_eq!(balance(KYL, BOB), 900_000);
+		assert_noop!(
+			TemplateModule::force_liquidate(RuntimeOrigin::root(), BOB, DOT, KYL),
+			Error::<Test>::LoanDoesNotExists
+		);
+	});
+}
+
+#[test]
+fn borrowers_settle_their_borrows_with_their_collateral() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), DEX_ACCOUNT, 300_000));
+		assert_noop!(
+			TemplateModule::self_liquidate(RuntimeOrigin::signed(ALICE), DOT, KYL),
+			Error::<Test>::LoanDoesNotExists
+		);
+
+		// 200_000 of the 400_000 KYL buy the 200_000 DOT of debt back, the rest is released
+		assert_ok!(TemplateModule::self_liquidate(RuntimeOrigin::signed(BOB), DOT, KYL));
+		System::assert_last_event(
+			Event::BorrowSelfLiquidated {
+				who: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+				sold: 200_000,
+			}
+			.into(),
+		);
+		assert!(!Borrows::<Test>::contains_key((BOB, DOT, KYL)));
+		assert_eq!(balance(KYL, BOB), 800_000);
+		assert_eq!(balance(DOT, BOB), 200_000);
+		assert_eq!(balance(KYL, DEX_ACCOUNT), 200_000);
+		assert_eq!(balance(DOT, DEX_ACCOUNT), 100_000);
+	});
+}
+
+#[test]
+fn only_the_tokens_not_owed_by_the_pools_are_rescued() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let pool_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), pool_account, 1_000));
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), KYL.into(), pool_account, 500));
+		assert_noop!(
+			TemplateModule::rescue_funds(RuntimeOrigin::signed(ALICE), Some(DOT), DOT, 1),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::rescue_funds(RuntimeOrigin::root(), Some(DOT), DOT, 0),
+			Error::<Test>::BadWitness
+		);
+
+		// the reserves and the collateral of BOB stay in the pool account
+		assert_ok!(TemplateModule::rescue_funds(RuntimeOrigin::root(), Some(DOT), DOT, 1));
+		System::assert_last_event(
+			Event::FundsRescued { account: pool_account, token: DOT, balance: 1_000 }.into(),
+		);
+		assert_ok!(TemplateModule::rescue_funds(RuntimeOrigin::root(), Some(DOT), KYL, 1));
+		assert_eq!(balance(DOT, pool_account), 300_000);
+		assert_eq!(balance(KYL, pool_account), 400_000);
+		assert_eq!(balance(DOT, TREASURY), 1_000);
+		assert_eq!(balance(KYL, TREASURY), 500);
+		assert_noop!(
+			TemplateModule::rescue_funds(RuntimeOrigin::root(), Some(DOT), DOT, 1),
+			Error::<Test>::NothingToRescue
+		);
+
+		// the pallet account owes nothing
+		let pallet_account = TemplateModule::account_id();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), pallet_account, 100));
+		assert_ok!(TemplateModule::rescue_funds(RuntimeOrigin::root(), None, DOT, 0));
+		assert_eq!(balance(DOT, pallet_account), 0);
+		assert_eq!(balance(DOT, TREASURY), 1_100);
+	});
+}
+
+#[test]
+fn the_governance_updates_the_parameters_of_a_pool_at_once() {
+	use crate::{MinBorrow, OutflowCaps, PoolParamsUpdate};
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let update = PoolParamsUpdate {
+			collateral_factor: Some(Permill::from_percent(60)),
+			reserve_factor: Some(Permill::from_percent(20)),
+			min_borrow: Some(1_000),
+			outflow_cap: Some(Some(100_000)),
+			..Default::default()
+		};
+		assert_noop!(
+			TemplateModule::force_update_pool_params(
+				RuntimeOrigin::signed(ALICE),
+				DOT,
+				update.clone()
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::force_update_pool_params(
+				RuntimeOrigin::root(),
+				DOT,
+				PoolParamsUpdate {
+					collateral_factor: Some(Permill::from_percent(90)),
+					..update.clone()
+				}
+			),
+			Error::<Test>::InvalidRiskParameters
+		);
+
+		assert_ok!(TemplateModule::force_update_pool_params(
+			RuntimeOrigin::root(),
+			DOT,
+			update.clone()
+		));
+		System::assert_last_event(Event::PoolParamsUpdated { asset: DOT, update }.into());
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(DOT)).unwrap();
+		assert_eq!(pool.collateral_factor, Permill::from_percent(60));
+		assert_eq!(pool.liquidation_threshold, Permill::from_percent(80));
+		assert_eq!(pool.reserve_factor, Permill::from_percent(20));
+		assert_eq!(MinBorrow::<Test>::get(DOT), 1_000);
+		assert_eq!(OutflowCaps::<Test>::get(DOT), Some(100_000));
+
+		// the parameters left out are unchanged
+		let update = PoolParamsUpdate { outflow_cap: Some(None), ..Default::default() };
+		assert_ok!(TemplateModule::force_update_pool_params(RuntimeOrigin::root(), DOT, update));
+		assert_eq!(OutflowCaps::<Test>::get(DOT), None);
+		assert_eq!(MinBorrow::<Test>::get(DOT), 1_000);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(DOT)).unwrap();
+		assert_eq!(pool.collateral_factor, Permill::from_percent(60));
+	});
+}
+
+#[test]
+fn account_snapshots_report_the_ktokens_debt_and_exchange_rate() {
+	use crate::AccountSnapshot;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_eq!(
+			TemplateModule::get_account_snapshot(&ALICE, DOT),
+			Ok(AccountSnapshot {
+				k_token_balance: 500_000,
+				borrow_balance: 0,
+				exchange_rate: Rate::one(),
+			})
+		);
+		assert_eq!(
+			TemplateModule::get_account_snapshot(&BOB, KYL),
+			Err(Error::<Test>::LendingPoolDoesNotExist.into())
+		);
+
+		// the debt and the exchange rate include the interest accrued up to now
+		Timestamp::set_timestamp(30 * 24 * 60 * 60 * 1000);
+		let snapshot = TemplateModule::get_account_snapshot(&BOB, DOT).unwrap();
+		let position = TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap();
+		assert_eq!(snapshot.k_token_balance, 0);
+		assert_eq!(snapshot.borrow_balance, position.balance);
+		assert!(snapshot.borrow_balance > 200_000);
+		assert!(snapshot.exchange_rate > Rate::one());
+		assert_eq!(
+			TemplateModule::get_account_snapshot(&ALICE, DOT).unwrap().exchange_rate,
+			snapshot.exchange_rate
+		);
+	});
+}
+
+#[test]
+fn no_more_than_max_pools_lending_pools_are_created() {
+	new_test_ext().execute_with(|| {
+		MaxPools::set(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+		assert_eq!(LendingPoolStorage::<Test>::count(), 1);
+		assert_noop!(
+			TemplateModule::create_lending_pool(
+				RuntimeOrigin::signed(ALICE),
+				KYL_POOL_ID,
+				KYL,
+				1_000
+			),
+			Error::<Test>::TooManyPools
+		);
+
+		MaxPools::set(2);
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(ALICE),
+			KYL_POOL_ID,
+			KYL,
+			1_000
+		));
+		assert_eq!(LendingPoolStorage::<Test>::count(), 2);
+	});
+}
+
+#[test]
+fn pools_and_positions_are_paged_with_a_cursor() {
+	use crate::Page;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), KYL.into(), ALICE, 1_000_000));
+		assert_ok!(TemplateModule::create_lending_pool(
+			RuntimeOrigin::signed(ALICE),
+			KYL_POOL_ID,
+			KYL,
+			1_000
+		));
+		assert_ok!(TemplateModule::activate_lending_pool(RuntimeOrigin::signed(ALICE), KYL));
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), KYL, 1_000, None));
+
+		// the pages chain up to all the pools
+		let first = TemplateModule::pools_page(None, 1);
+		assert_eq!(first.items.len(), 1);
+		let cursor = first.next.expect("a second page");
+		let second = TemplateModule::pools_page(Some(cursor), 1);
+		assert_eq!(second.next, None);
+		let mut assets: Vec<_> =
+			first.items.iter().chain(second.items.iter()).map(|pool| pool.asset).collect();
+		assets.sort();
+		assert_eq!(assets, vec![DOT, KYL]);
+		assert_eq!(TemplateModule::pools_page(None, 10).items.len(), 2);
+
+		let mut supplied = Vec::new();
+		let mut page = TemplateModule::supply_positions_page(None, 1);
+		loop {
+			supplied.extend(page.items.iter().map(|(who, position)| (*who, position.asset)));
+			let Some(cursor) = page.next else { break };
+			page = TemplateModule::supply_positions_page(Some(cursor), 1);
+		}
+		supplied.sort();
+		assert_eq!(supplied, vec![(ALICE, DOT), (ALICE, KYL), (BOB, KYL)]);
+
+		let Page { items, next } = TemplateModule::borrow_positions_page(None, 10);
+		assert_eq!(next, None);
+		assert_eq!(items, vec![(BOB, TemplateModule::borrow_position(&BOB, DOT, KYL).unwrap())]);
+	});
+}
+
+#[test]
+fn wrapped_positions_are_transferred_as_a_whole_while_healthy() {
+	use crate::{mock::MockPositionNfts, PositionNfts, Positions};
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let loan = Borrows::<Test>::get((BOB, DOT, KYL)).unwrap();
+		assert_noop!(
+			TemplateModule::wrap_position(RuntimeOrigin::signed(ALICE), DOT, KYL),
+			Error::<Test>::LoanDoesNotExists
+		);
+
+		assert_ok!(TemplateModule::wrap_position(RuntimeOrigin::signed(BOB), DOT, KYL));
+		System::assert_last_event(
+			Event::PositionWrapped { who: BOB, id: 0, asset: DOT, collateral_asset: KYL }.into(),
+		);
+		let account = TemplateModule::position_account_id(0);
+		assert_eq!(MockPositionNfts::owner(0), Some(BOB));
+		assert_eq!(Positions::<Test>::get(0), Some((DOT, KYL)));
+		assert_eq!(Borrows::<Test>::get((account, DOT, KYL)), Some(loan.clone()));
+		assert!(AccountBorrows::<Test>::get(BOB).is_empty());
+
+		// only the owner transfers the position
+		assert_noop!(
+			TemplateModule::transfer_position(RuntimeOrigin::signed(ALICE), 0, ALICE),
+			Error::<Test>::NotPositionOwner
+		);
+		assert_ok!(TemplateModule::transfer_position(RuntimeOrigin::signed(BOB), 0, ALICE));
+		assert_eq!(MockPositionNfts::owner(0), Some(ALICE));
+
+		// an unhealthy position is liquidated rather than passed on
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+		assert_noop!(
+			TemplateModule::transfer_position(RuntimeOrigin::signed(ALICE), 0, BOB),
+			Error::<Test>::AccountHasShortfall
+		);
+
+		// the new owner takes the borrow over
+		assert_ok!(TemplateModule::unwrap_position(RuntimeOrigin::signed(ALICE), 0));
+		System::assert_last_event(Event::PositionUnwrapped { who: ALICE, id: 0 }.into());
+		assert_eq!(Borrows::<Test>::get((ALICE, DOT, KYL)), Some(loan));
+		assert!(!Borrows::<Test>::contains_key((account, DOT, KYL)));
+		assert_eq!(MockPositionNfts::owner(0), None);
+		assert_noop!(
+			TemplateModule::unwrap_position(RuntimeOrigin::signed(ALICE), 0),
+			Error::<Test>::PositionDoesNotExist
+		);
+	});
+}
+
+#[test]
+fn debt_is_transferred_to_consenting_accounts_left_healthy() {
+	use crate::DebtAllowances;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), KYL.into(), ALICE, 400_000));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(ALICE),
+			DOT,
+			50_000,
+			KYL,
+			400_000,
+			None
+		));
+		let bob_debt = Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().borrowed_balance;
+		let alice_debt = Borrows::<Test>::get((ALICE, DOT, KYL)).unwrap().borrowed_balance;
+
+		// ALICE did not take the debt of BOB over
+		assert_noop!(
+			TemplateModule::transfer_debt(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				KYL,
+				100_000,
+				ALICE,
+				KYL
+			),
+			Error::<Test>::DebtNotApproved
+		);
+		assert_ok!(TemplateModule::approve_debt(RuntimeOrigin::signed(ALICE), BOB, DOT, 300_000));
+		System::assert_last_event(
+			Event::DebtApproved { who: ALICE, from: BOB, asset: DOT, allowance: 300_000 }.into(),
+		);
+
+		// the collateral halves in value, 80% of it covers only 160_000 on either side
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+		assert_noop!(
+			TemplateModule::transfer_debt(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				KYL,
+				150_000,
+				ALICE,
+				KYL
+			),
+			Error::<Test>::AccountHasShortfall
+		);
+		assert_ok!(TemplateModule::transfer_debt(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			KYL,
+			100_000,
+			ALICE,
+			KYL
+		));
+		System::assert_last_event(
+			Event::DebtTransferred {
+				from: BOB,
+				to: ALICE,
+				asset: DOT,
+				collateral_asset: KYL,
+				to_collateral_asset: KYL,
+				balance: 100_000,
+			}
+			.into(),
+		);
+		assert_eq!(
+			Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().borrowed_balance,
+			bob_debt - 100_000
+		);
+		assert_eq!(
+			Borrows::<Test>::get((ALICE, DOT, KYL)).unwrap().borrowed_balance,
+			alice_debt + 100_000
+		);
+		assert_eq!(DebtAllowances::<Test>::get((ALICE, BOB, DOT)), 200_000);
+
+		// the whole debt is moved at most, BOB gets his collateral back
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::transfer_debt(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			KYL,
+			500_000,
+			ALICE,
+			KYL
+		));
+		assert!(!Borrows::<Test>::contains_key((BOB, DOT, KYL)));
+		assert!(AccountBorrows::<Test>::get(BOB).is_empty());
+		assert_eq!(balance(KYL, BOB), 1_000_000);
+		assert_eq!(
+			Borrows::<Test>::get((ALICE, DOT, KYL)).unwrap().borrowed_balance,
+			alice_debt + bob_debt
+		);
+		assert_eq!(DebtAllowances::<Test>::get((ALICE, BOB, DOT)), 200_000 - (bob_debt - 100_000));
+	});
+}
+
+#[test]
+fn dex_lp_tokens_are_valued_at_the_fair_value_of_their_pool() {
+	const DEX_LP: Token = 40;
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 100_000);
+		create_asset_and_mint(KYL, ALICE, 100_000);
+		create_asset_and_mint(DEX_LP, BOB, 20_000);
+		// a KYL is worth 4 DOT
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from(4)
+		));
+
+		// only the LP tokens of the DEX are listed, by the governance
+		assert_noop!(
+			TemplateModule::set_dex_lp_collateral(RuntimeOrigin::root(), DEX_LP, true),
+			Error::<Test>::NotDexLpToken
+		);
+		MockDexPools::set_pool(DEX_LP, (DOT, KYL), (40_000, 10_000));
+		assert_noop!(
+			TemplateModule::set_dex_lp_collateral(RuntimeOrigin::signed(ALICE), DEX_LP, true),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::set_dex_lp_collateral(RuntimeOrigin::root(), DEX_LP, true));
+		System::assert_last_event(
+			Event::<Test>::DexLpCollateralSet { lp_token: DEX_LP, assets: Some((DOT, KYL)) }.into(),
+		);
+
+		// 1_000 of the 20_000 LP tokens of a pool worth 80_000 DOT
+		assert_eq!(
+			TemplateModule::get_equivalent_asset_amount(&BOB, DOT, DEX_LP, 1_000),
+			Ok(4_000)
+		);
+		assert_eq!(
+			TemplateModule::get_equivalent_asset_amount(&BOB, DEX_LP, DOT, 4_000),
+			Ok(1_000)
+		);
+		assert_eq!(
+			TemplateModule::get_equivalent_asset_amount(&BOB, KYL, DEX_LP, 1_000),
+			Ok(1_000)
+		);
+
+		// a swap skewing the pool to 170_000 DOT at its reserves does not move its fair value
+		MockDexPools::set_pool(DEX_LP, (DOT, KYL), (160_000, 2_500));
+		assert_eq!(
+			TemplateModule::get_equivalent_asset_amount(&BOB, DOT, DEX_LP, 1_000),
+			Ok(4_000)
+		);
+
+		assert_ok!(TemplateModule::set_dex_lp_collateral(RuntimeOrigin::root(), DEX_LP, false));
+		assert_eq!(
+			TemplateModule::get_equivalent_asset_amount(&BOB, DOT, DEX_LP, 1_000),
+			Err(Error::<Test>::PriceUnavailable.into())
+		);
+	});
+}
+
+#[test]
+fn liquidations_are_reserved_to_the_bonded_keepers_they_are_assigned_to() {
+	const KEEPER: SignedOrigin = 5;
+	let mut ext = new_test_ext();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), ALICE, 1_000_000));
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), KEEPER, 1_000_000));
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), KYL.into(), BOB, 1_000_000));
+		let set_price = |price| {
+			assert_ok!(TemplateModule::set_asset_price(
+				RuntimeOrigin::signed(ORACLE),
+				KYL,
+				DOT,
+				price
+			));
+		};
+		let reopen = || {
+			set_price(FixedU128::from(1));
+			assert_ok!(TemplateModule::borrow(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				200_000,
+				KYL,
+				400_000,
+				None
+			));
+			set_price(FixedU128::from_rational(1, 2));
+		};
+		set_price(FixedU128::from_rational(1, 2));
+
+		assert_ok!(Balances::force_set_balance(RuntimeOrigin::root(), KEEPER, 10_000));
+		assert_ok!(TemplateModule::register_keeper(RuntimeOrigin::signed(KEEPER)));
+		System::assert_last_event(
+			Event::<Test>::KeeperRegistered { who: KEEPER, bond: 1_000 }.into(),
+		);
+		assert_eq!(Balances::free_balance(KEEPER), 9_000);
+		assert_noop!(
+			TemplateModule::register_keeper(RuntimeOrigin::signed(KEEPER)),
+			Error::<Test>::KeeperAlreadyRegistered
+		);
+
+		// with keepers registered, the offchain worker assigns the liquidations first
+		TemplateModule::offchain_worker(10);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let assignment =
+			crate::Call::assign_liquidation { borrower: BOB, asset: DOT, collateral_asset: KYL };
+		assert_eq!(
+			Extrinsic::decode(&mut &*tx).unwrap().call,
+			RuntimeCall::TemplateModule(assignment.clone())
+		);
+		assert!(TemplateModule::validate_unsigned(TransactionSource::External, &assignment).is_ok());
+		assert_ok!(TemplateModule::assign_liquidation(RuntimeOrigin::none(), BOB, DOT, KYL));
+		System::assert_last_event(
+			Event::<Test>::LiquidationAssigned {
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				keeper: KEEPER,
+				until: 6,
+			}
+			.into(),
+		);
+
+		// the keeper acting within its exclusivity
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::LiquidationReserved
+		);
+		System::set_block_number(2);
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::signed(KEEPER), BOB, DOT, KYL));
+		assert!(!TemplateModule::liquidation_reserved(&BOB, DOT, KYL));
+
+		// the keeper missing a liquidation, which the offchain worker falls back to once the
+		// exclusivity is over
+		reopen();
+		assert_ok!(TemplateModule::assign_liquidation(RuntimeOrigin::none(), BOB, DOT, KYL));
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &assignment),
+			InvalidTransaction::Stale.into()
+		);
+		let liquidation =
+			crate::Call::liquidate_borrow { borrower: BOB, asset: DOT, collateral_asset: KYL };
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &liquidation),
+			InvalidTransaction::Stale.into()
+		);
+		TemplateModule::offchain_worker(20);
+		assert!(pool_state.read().transactions.is_empty());
+		assert_noop!(
+			TemplateModule::deregister_keeper(RuntimeOrigin::signed(KEEPER)),
+			Error::<Test>::KeeperBusy
+		);
+
+		System::set_block_number(8);
+		TemplateModule::offchain_worker(30);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert_eq!(
+			Extrinsic::decode(&mut &*tx).unwrap().call,
+			RuntimeCall::TemplateModule(liquidation)
+		);
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL));
+		System::assert_has_event(Event::<Test>::KeeperMissed { who: KEEPER, misses: 1 }.into());
+
+		// its bond goes to the treasury at its second miss in a row
+		reopen();
+		assert_ok!(TemplateModule::assign_liquidation(RuntimeOrigin::none(), BOB, DOT, KYL));
+		System::set_block_number(14);
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL));
+		System::assert_has_event(Event::<Test>::KeeperMissed { who: KEEPER, misses: 2 }.into());
+		System::assert_has_event(Event::<Test>::KeeperSlashed { who: KEEPER, bond: 1_000 }.into());
+		assert_eq!(Balances::free_balance(TREASURY), 1_000);
+		assert_noop!(
+			TemplateModule::deregister_keeper(RuntimeOrigin::signed(KEEPER)),
+			Error::<Test>::NotKeeper
+		);
+
+		// a keeper without assignments leaves with its bond
+		assert_ok!(TemplateModule::register_keeper(RuntimeOrigin::signed(KEEPER)));
+		assert_ok!(TemplateModule::deregister_keeper(RuntimeOrigin::signed(KEEPER)));
+		assert_eq!(Balances::free_balance(KEEPER), 9_000);
+	});
+}
+
+#[test]
+fn borrows_are_margin_called_for_a_grace_period_before_their_liquidation() {
+	let mut ext = new_test_ext();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		MarginCallGracePeriod::set(5);
+		setup_borrow();
+		let set_price = |price| {
+			assert_ok!(TemplateModule::set_asset_price(
+				RuntimeOrigin::signed(ORACLE),
+				KYL,
+				DOT,
+				price
+			));
+		};
+		// a shortfall of a fifth of the debt
+		set_price(FixedU128::from_rational(1, 2));
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::MarginCallGracePeriod
+		);
+
+		// the offchain worker margin calls the borrow first
+		TemplateModule::offchain_worker(10);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let margin_call =
+			crate::Call::margin_call { borrower: BOB, asset: DOT, collateral_asset: KYL };
+		assert_eq!(
+			Extrinsic::decode(&mut &*tx).unwrap().call,
+			RuntimeCall::TemplateModule(margin_call.clone())
+		);
+		assert!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &margin_call).is_ok()
+		);
+		assert_ok!(TemplateModule::margin_call(RuntimeOrigin::none(), BOB, DOT, KYL));
+		System::assert_last_event(
+			Event::<Test>::MarginCall {
+				borrower: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				shortfall: 40_000,
+				until: 6,
+			}
+			.into(),
+		);
+		assert_noop!(
+			TemplateModule::margin_call(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::MarginCallAlreadyIssued
+		);
+
+		// nobody can liquidate the borrow during the grace period
+		System::set_block_number(6);
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::MarginCallGracePeriod
+		);
+		let liquidation =
+			crate::Call::liquidate_borrow { borrower: BOB, asset: DOT, collateral_asset: KYL };
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &liquidation),
+			InvalidTransaction::Stale.into()
+		);
+
+		// BOB tops the collateral up, clearing the margin call
+		assert_ok!(TemplateModule::add_collateral(RuntimeOrigin::signed(BOB), DOT, KYL, 200_000));
+		System::assert_last_event(
+			Event::<Test>::CollateralAdded {
+				who: BOB,
+				asset: DOT,
+				collateral_asset: KYL,
+				balance: 200_000,
+			}
+			.into(),
+		);
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(0));
+		assert!(!MarginCalls::<Test>::contains_key((BOB, DOT, KYL)));
+
+		// a shortfall of more than half the debt is liquidated without a grace period
+		set_price(FixedU128::from_rational(1, 5));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(104_000));
+		assert!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &liquidation).is_ok()
+		);
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL));
+	});
+}
+
+#[test]
+fn lending_pools_are_paused_on_prices_deviating_from_their_reference() {
+	let mut ext = new_test_ext();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		setup_borrow();
+		let report = crate::Call::report_price_deviation { asset: KYL, quote: DOT };
+
+		// a deviation of 5% is tolerated
+		MockPriceReference::set(KYL, DOT, Some(FixedU128::from_rational(105, 100)));
+		assert_noop!(
+			TemplateModule::report_price_deviation(RuntimeOrigin::none(), KYL, DOT),
+			Error::<Test>::PriceWithinDeviation
+		);
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &report),
+			InvalidTransaction::Stale.into()
+		);
+
+		// the offchain worker reports a deviation of half the reference
+		MockPriceReference::set(KYL, DOT, Some(FixedU128::from(2)));
+		TemplateModule::offchain_worker(10);
+		let calls: Vec<_> = pool_state
+			.write()
+			.transactions
+			.drain(..)
+			.map(|tx| Extrinsic::decode(&mut &*tx).unwrap().call)
+			.collect();
+		assert!(calls.contains(&RuntimeCall::TemplateModule(report.clone())));
+		assert!(TemplateModule::validate_unsigned(TransactionSource::External, &report).is_ok());
+		assert_ok!(TemplateModule::report_price_deviation(RuntimeOrigin::none(), KYL, DOT));
+		System::assert_has_event(
+			Event::<Test>::PriceDeviationReported {
+				asset: KYL,
+				quote: DOT,
+				price: FixedU128::from(1),
+				reference: FixedU128::from(2),
+			}
+			.into(),
+		);
+		// KYL has no lending pool, only the one of DOT is paused
+		System::assert_last_event(Event::<Test>::PoolPauseSet { asset: DOT, paused: true }.into());
+		assert!(PausedPools::<Test>::get(DOT));
+		assert!(!PausedPools::<Test>::get(KYL));
+
+		// nobody can borrow from nor withdraw from the pool
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 10_000, KYL, 40_000, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 10_000),
+			Error::<Test>::PoolPaused
+		);
+
+		// the deviation is reported once
+		assert_noop!(
+			TemplateModule::report_price_deviation(RuntimeOrigin::none(), KYL, DOT),
+			Error::<Test>::PoolPaused
+		);
+		assert_eq!(
+			TemplateModule::validate_unsigned(TransactionSource::External, &report),
+			InvalidTransaction::Stale.into()
+		);
+
+		// until the governance resumes the pool
+		assert_noop!(
+			TemplateModule::set_pool_paused(RuntimeOrigin::signed(ALICE), DOT, false),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_pool_paused(RuntimeOrigin::root(), KYL, false),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		assert_ok!(TemplateModule::set_pool_paused(RuntimeOrigin::root(), DOT, false));
+		System::assert_last_event(Event::<Test>::PoolPauseSet { asset: DOT, paused: false }.into());
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 10_000));
+	});
+}
+
+#[test]
+fn operators_dispatch_the_calls_of_an_account_within_their_permissions() {
+	use crate::{Call, OperatorPermissions, Operators};
+	const OPERATOR: SignedOrigin = 5;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let operate = |call: Call<Test>| {
+			TemplateModule::operate(RuntimeOrigin::signed(OPERATOR), BOB, Box::new(call))
+		};
+		let approve = |permissions| {
+			assert_ok!(TemplateModule::approve_operator(
+				RuntimeOrigin::signed(BOB),
+				OPERATOR,
+				permissions
+			));
+			System::assert_last_event(
+				Event::<Test>::OperatorApproved { who: BOB, operator: OPERATOR, permissions }
+					.into(),
+			);
+		};
+		let repay = Call::repay { asset: DOT, balance: 10_000, collateral_asset: KYL };
+		let add_collateral =
+			Call::add_collateral { asset: DOT, collateral_asset: KYL, balance: 10_000 };
+		let borrow = Call::borrow {
+			asset: DOT,
+			balance: 10_000,
+			collateral_asset: KYL,
+			collateral_balance: 20_000,
+			referral: None,
+		};
+
+		assert_noop!(operate(repay.clone()), Error::<Test>::OperatorNotApproved);
+
+		// the operator repays with the assets of BOB
+		approve(Some(OperatorPermissions::RepayOnly));
+		assert_eq!(Operators::<Test>::get(BOB, OPERATOR), Some(OperatorPermissions::RepayOnly));
+		let bob_balance = Assets::balance(DOT, BOB);
+		assert_ok!(operate(repay.clone()));
+		System::assert_last_event(
+			Event::<Test>::OperatorCalled { who: BOB, operator: OPERATOR }.into(),
+		);
+		assert_eq!(Assets::balance(DOT, BOB), bob_balance - 10_000);
+		assert_noop!(operate(add_collateral.clone()), Error::<Test>::OperatorNotPermitted);
+
+		// then tops the collateral up, but can not borrow
+		approve(Some(OperatorPermissions::ManageCollateral));
+		assert_ok!(operate(add_collateral));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().collateral_balance, 410_000);
+		assert_noop!(operate(borrow.clone()), Error::<Test>::OperatorNotPermitted);
+
+		// with the full permissions it borrows, but can not approve other operators
+		approve(Some(OperatorPermissions::Full));
+		assert_ok!(operate(borrow.clone()));
+		assert_eq!(Assets::balance(DOT, BOB), bob_balance);
+		assert_noop!(
+			operate(Call::approve_operator {
+				operator: ALICE,
+				permissions: Some(OperatorPermissions::Full)
+			}),
+			Error::<Test>::OperatorNotPermitted
+		);
+
+		// the maintenance mode applies to the calls of the operators
+		assert_ok!(TemplateModule::set_maintenance_mode(RuntimeOrigin::root(), true));
+		assert_noop!(operate(borrow), frame_system::Error::<Test>::CallFiltered);
+		assert_ok!(operate(repay.clone()));
+		assert_ok!(TemplateModule::set_maintenance_mode(RuntimeOrigin::root(), false));
+
+		// until BOB revokes the operator
+		approve(None);
+		assert_noop!(operate(repay), Error::<Test>::OperatorNotApproved);
+	});
+}
+
+#[test]
+fn deposits_are_withdrawn_by_shares_without_leaving_dust() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 100_000));
+
+		// the exchange rate grows with the interest BOB repays 30 days later
+		Timestamp::set_timestamp(30 * 24 * 60 * 60 * 1_000);
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 300_000, KYL));
+		let exchange_rate =
+			LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap().supply_index;
+		assert!(exchange_rate > Rate::one());
+
+		// exactly the shares are redeemed, at the exchange rate
+		assert_noop!(
+			TemplateModule::withdraw_shares(RuntimeOrigin::signed(ALICE), DOT, 500_001),
+			Error::<Test>::NotEnoughElegibleLiquidityToWithdraw
+		);
+		let alice_balance = balance(DOT, ALICE);
+		assert_ok!(TemplateModule::withdraw_shares(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		let withdrawn = exchange_rate.saturating_mul_int(100_000u128);
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		System::assert_last_event(
+			Event::<Test>::DepositWithdrawn {
+				who: ALICE,
+				asset: DOT,
+				balance: withdrawn,
+				burned: 100_000,
+				exchange_rate,
+				borrow_index: pool.borrow_index,
+				lp_tokens: 400_000,
+				utilisation: pool.utilisation_ratio().unwrap(),
+			}
+			.into(),
+		);
+		assert_eq!(balance(DOT, ALICE), alice_balance + withdrawn);
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 400_000);
+
+		// the rest of the deposit is withdrawn in full
+		assert_ok!(TemplateModule::withdraw_all(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 0);
+		assert_eq!(
+			balance(DOT, ALICE),
+			alice_balance + withdrawn + exchange_rate.saturating_mul_int(400_000u128)
+		);
+		assert_noop!(
+			TemplateModule::withdraw_all(RuntimeOrigin::signed(ALICE), DOT),
+			Error::<Test>::AmountTooLow
+		);
+	});
+}
+
+#[test]
+fn max_borrows_are_quoted_within_the_collateral_liquidity_and_caps_of_the_pool() {
+	use crate::MaxBorrow;
+
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		// ALICE holds no priced collateral, KYL has no lending pool
+		assert_eq!(TemplateModule::max_borrowable(&ALICE, DOT), None);
+		assert_eq!(TemplateModule::max_borrowable(&BOB, KYL), None);
+
+		// the 599_999 KYL BOB can post borrow half their value, less the margin
+		let quote = TemplateModule::max_borrowable(&BOB, DOT).unwrap();
+		assert_eq!(
+			quote,
+			MaxBorrow { balance: 296_999, collateral_asset: KYL, collateral_balance: 599_999 }
+		);
+		assert_eq!(TemplateModule::max_borrowable_against(&BOB, DOT, KYL), Some(quote));
+
+		// the outflow cap binds first
+		assert_ok!(TemplateModule::set_outflow_cap(RuntimeOrigin::root(), DOT, Some(100_000)));
+		let quote = TemplateModule::max_borrowable(&BOB, DOT).unwrap();
+		assert_eq!(quote.balance, 99_000);
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			quote.balance,
+			quote.collateral_asset,
+			quote.collateral_balance,
+			None
+		));
+
+		// BOB has no collateral left to post
+		assert_eq!(TemplateModule::max_borrowable(&BOB, DOT), None);
+	});
+}
+
+#[test]
+fn lending_pools_are_created_with_a_minimum_liquidity_locked_in_dead_shares() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		MinPoolLiquidity::set(1_000);
+		DeadShares::set(5);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		assert_noop!(
+			TemplateModule::create_lending_pool(
+				RuntimeOrigin::signed(ALICE),
+				LENDING_POOL_ID,
+				DOT,
+				999
+			),
+			Error::<Test>::InitialLiquidityTooLow
+		);
+
+		// the dead shares are taken out of the LP tokens of the first deposit
+		setup_active_pool(1_000_000);
+		let dead = TemplateModule::dead_shares_account_id();
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 999_995);
+		assert_eq!(balance(LENDING_POOL_ID, dead), 5);
+
+		// the pool is never emptied, nor are the dead shares swept as dust
+		assert_ok!(TemplateModule::withdraw_all(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(DOT, TemplateModule::pool_account_id(LENDING_POOL_ID)), 5);
+		assert_ok!(TemplateModule::sweep_position(&dead, DOT));
+		assert_eq!(balance(LENDING_POOL_ID, dead), 5);
+	});
+}
+
+#[test]
+fn k_token_balances_are_snapshotted_at_the_start_of_each_era() {
+	new_test_ext().execute_with(|| {
+		// eras of 10 blocks, the pool is created during era 0
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(1_000_000);
+		let balance_at = |who, era| TemplateModule::k_token_balance_at(&who, LENDING_POOL_ID, era);
+		assert_eq!(balance_at(ALICE, 0), Some(0));
+		assert_eq!(balance_at(ALICE, 1), None);
+
+		// the changes during era 2 do not move its snapshot
+		System::set_block_number(25);
+		assert_eq!(TemplateModule::k_token_era(), 2);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 400_000));
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 100_000, None));
+		assert_eq!(balance_at(ALICE, 1), Some(1_000_000));
+		assert_eq!(balance_at(ALICE, 2), Some(1_000_000));
+
+		// the kTokens sent during era 3 count for the sender
+		System::set_block_number(35);
+		assert_eq!(balance_at(ALICE, 3), Some(700_000));
+		assert_ok!(TemplateModule::transfer_lp_tokens(
+			RuntimeOrigin::signed(ALICE),
+			DOT,
+			BOB,
+			200_000
+		));
+		assert_eq!(balance_at(ALICE, 3), Some(700_000));
+		assert_eq!(balance_at(BOB, 3), Some(0));
+
+		// the oldest checkpoints are dropped, with the snapshots they back
+		System::set_block_number(45);
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_eq!(balance_at(ALICE, 1), None);
+		assert_eq!(balance_at(ALICE, 2), Some(1_000_000));
+		assert_eq!(balance_at(ALICE, 4), Some(500_000));
+		assert_eq!(balance_at(ALICE, 5), None);
+		assert_eq!(balance_at(BOB, 4), Some(200_000));
+	});
+}
+
+#[test]
+fn frozen_k_tokens_stay_with_their_owner_but_can_not_leave_the_account() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(1_000_000);
+		let staking: RuntimeFreezeReason = FreezeReason::Staking.into();
+		let boost: RuntimeFreezeReason = FreezeReason::Boost.into();
+
+		// only the kTokens held can be frozen
+		assert_noop!(
+			TemplateModule::set_k_token_freeze(&staking, &ALICE, DOT, 600_000),
+			Error::<Test>::NotKToken
+		);
+		assert_noop!(
+			TemplateModule::set_k_token_freeze(&staking, &ALICE, LENDING_POOL_ID, 1_000_001),
+			Error::<Test>::InsufficientKTokens
+		);
+		assert_ok!(TemplateModule::set_k_token_freeze(&staking, &ALICE, LENDING_POOL_ID, 600_000));
+		System::assert_last_event(
+			Event::<Test>::KTokensFrozen {
+				who: ALICE,
+				lp_token: LENDING_POOL_ID,
+				reason: staking,
+				balance: 600_000,
+			}
+			.into(),
+		);
+
+		// the freezes overlap
+		assert_ok!(TemplateModule::set_k_token_freeze(&boost, &ALICE, LENDING_POOL_ID, 300_000));
+		assert_eq!(TemplateModule::k_tokens_frozen(&ALICE, LENDING_POOL_ID), 600_000);
+		assert_eq!(TemplateModule::reducible_k_tokens(&ALICE, LENDING_POOL_ID), 400_000);
+
+		// the withdrawals and the transfers stay out of the frozen kTokens
+		assert_noop!(
+			TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 400_001),
+			Error::<Test>::NotEnoughElegibleLiquidityToWithdraw
+		);
+		assert_ok!(TemplateModule::withdraw_all(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 600_000);
+		assert_noop!(
+			TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(ALICE), DOT, BOB, 1),
+			Error::<Test>::KTokensFrozen
+		);
+
+		// thawing the staking leaves the boost frozen
+		assert_ok!(TemplateModule::thaw_k_tokens(&staking, &ALICE, LENDING_POOL_ID));
+		assert_eq!(TemplateModule::k_tokens_frozen(&ALICE, LENDING_POOL_ID), 300_000);
+		assert_ok!(TemplateModule::transfer_lp_tokens(
+			RuntimeOrigin::signed(ALICE),
+			DOT,
+			BOB,
+			300_000
+		));
+		assert_noop!(
+			TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(ALICE), DOT, BOB, 1),
+			Error::<Test>::KTokensFrozen
+		);
+		assert_ok!(TemplateModule::thaw_k_tokens(&boost, &ALICE, LENDING_POOL_ID));
+		assert!(!crate::KTokenFreezes::<Test>::contains_key(ALICE, LENDING_POOL_ID));
+	});
+}
+
+#[test]
+fn native_collateral_is_held_in_the_account_of_the_borrower() {
+	use crate::HoldReason;
+	use frame_support::traits::fungible::InspectHold;
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		setup_active_pool(500_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			NATIVE_ASSET,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(Balances::force_set_balance(RuntimeOrigin::root(), BOB, 1_000_000));
+		let collateral: RuntimeHoldReason = HoldReason::BorrowCollateral.into();
+
+		// the native collateral stays with BOB, on hold
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			200_000,
+			NATIVE_ASSET,
+			400_000,
+			None
+		));
+		assert_eq!(Balances::balance_on_hold(&collateral, &BOB), 400_000);
+		assert_eq!(Balances::free_balance(BOB), 600_000);
+		assert_eq!(Balances::free_balance(TemplateModule::pool_account_id(LENDING_POOL_ID)), 0);
+		assert_ok!(TemplateModule::add_collateral(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			NATIVE_ASSET,
+			100_000
+		));
+		assert_eq!(Balances::balance_on_hold(&collateral, &BOB), 500_000);
+
+		// the repayment releases it
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 100_000));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 300_000, NATIVE_ASSET));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, NATIVE_ASSET)), None);
+		assert_eq!(Balances::balance_on_hold(&collateral, &BOB), 0);
+		assert_eq!(Balances::free_balance(BOB), 1_000_000);
+	});
+}
+
+#[test]
+fn long_term_suppliers_earn_the_bonus_of_their_loyalty_tier() {
+	use crate::{LoyaltyTier, SupplyingSince};
+
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		let tier =
+			|min_duration, bonus| LoyaltyTier { min_duration, bonus: Permill::from_percent(bonus) };
+
+		// the tiers increase, up to the `MaxLoyaltyBonus`
+		for tiers in [vec![tier(100, 10), tier(50, 20)], vec![tier(100, 10), tier(1_000, 30)]] {
+			assert_noop!(
+				TemplateModule::set_loyalty_tiers(
+					RuntimeOrigin::root(),
+					DOT,
+					tiers.try_into().unwrap()
+				),
+				Error::<Test>::InvalidLoyaltyTiers
+			);
+		}
+		let tiers = vec![tier(100, 10), tier(1_000, 20)].try_into().unwrap();
+		assert_ok!(TemplateModule::set_loyalty_tiers(RuntimeOrigin::root(), DOT, tiers));
+
+		// ALICE has been supplying since the creation of the pool
+		assert_eq!(SupplyingSince::<Test>::get(ALICE, DOT), Some(0));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).working_balance, 400);
+
+		// 1_000 / 2.5 boosted by 10% after 100 seconds, by 20% after 1_000 seconds
+		Timestamp::set_timestamp(100_000);
+		assert_eq!(TemplateModule::loyalty_bonus(&ALICE, DOT), Permill::from_percent(10));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).working_balance, 440);
+		Timestamp::set_timestamp(1_000_000);
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).working_balance, 480);
+
+		// the loyalty is lost with the whole deposit
+		assert_ok!(TemplateModule::withdraw_all(RuntimeOrigin::signed(ALICE), DOT));
+		assert_eq!(SupplyingSince::<Test>::get(ALICE, DOT), None);
+		assert_eq!(TemplateModule::loyalty_bonus(&ALICE, DOT), Permill::zero());
+	});
+}
+
+#[test]
+fn ext_builder_seeds_the_markets_at_genesis() {
+	ExtBuilder::default()
+		.with_balances(vec![(BOB, 1_000)])
+		.with_asset(DOT, vec![(ALICE, 1_000_000)])
+		.with_asset(KYL, vec![(BOB, 1_000_000)])
+		.with_price(KYL, DOT, FixedU128::from(1))
+		.with_pool(LENDING_POOL_ID, DOT, ALICE, 500_000)
+		.build()
+		.execute_with(|| {
+			assert!(System::events().is_empty());
+			assert_eq!(Balances::free_balance(BOB), 1_000);
+			assert_eq!(balance(DOT, ALICE), 500_000);
+			assert_eq!(AssetPrices::<Test>::get((KYL, DOT)), Some(FixedU128::from(1)));
+			let pool = LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+			assert!(pool.is_active());
+			assert_eq!(pool.reserve_balance, 500_000);
+
+			// the markets are ready to borrow from
+			System::set_block_number(1);
+			assert_ok!(TemplateModule::borrow(
+				RuntimeOrigin::signed(BOB),
+				DOT,
+				200_000,
+				KYL,
+				400_000,
+				None
+			));
+			assert_eq!(AccountBorrows::<Test>::get(BOB).to_vec(), vec![(DOT, KYL)]);
+		});
+}
+
+#[test]
+fn interest_accrues_per_second_of_the_wall_clock() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let pool = || LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let borrow_index = pool().borrow_index;
+
+		// less than a second accrues nothing, whatever the blocks produced meanwhile
+		System::set_block_number(100);
+		Timestamp::set_timestamp(999);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		assert_eq!(pool().last_accrued_interest_at, 0);
+		assert_eq!(pool().borrow_index, borrow_index);
+
+		// the second is accrued as soon as it is over, in the same block
+		Timestamp::set_timestamp(1_000);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		assert_eq!(pool().last_accrued_interest_at, 1);
+		assert!(pool().borrow_index > borrow_index);
+	});
+}
+
+#[test]
+fn interest_accrues_over_multi_year_gaps() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		let pool = || LendingPoolStorage::<Test>::get(AssetPool::from(DOT)).unwrap();
+		let rate = pool().borrow_interest_rate().unwrap();
+
+		// the pool is left untouched for 5 years, then compounds them all at once
+		let years = 5 * 365 * 24 * 60 * 60;
+		Timestamp::set_timestamp(years * 1_000);
+		assert_ok!(TemplateModule::accrue_interest(RuntimeOrigin::none(), DOT));
+		assert_eq!(pool().borrow_index, math::compounded_interest(rate, years).unwrap());
+		assert_eq!(pool().last_accrued_interest_at, years);
+
+		// the debt can still be repaid in full
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), BOB, 1_000_000));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 1_000_000, KYL));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)), None);
+	});
+}
+
+#[test]
+fn liquidations_are_suppressed_after_a_chain_stall() {
+	new_test_ext().execute_with(|| {
+		StallGracePeriod::set(600);
+		setup_borrow();
+		TemplateModule::on_finalize(1);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+
+		// a minute between blocks is no stall
+		System::set_block_number(2);
+		Timestamp::set_timestamp(60_000);
+		assert!(!TemplateModule::stall_grace_period());
+		TemplateModule::on_finalize(2);
+
+		// an hour is, from the first block after it
+		System::set_block_number(3);
+		Timestamp::set_timestamp(3_660_000);
+		assert_noop!(
+			TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL),
+			Error::<Test>::StallGracePeriod
+		);
+		assert!(TemplateModule::validate_unsigned(
+			TransactionSource::External,
+			&crate::Call::liquidate_borrow { borrower: BOB, asset: DOT, collateral_asset: KYL }
+		)
+		.is_err());
+		TemplateModule::on_finalize(3);
+		System::assert_last_event(Event::ChainStallDetected { gap: 3_600, until: 4_260 }.into());
+
+		// until the end of the grace period, the blocks being produced again
+		for (block, seconds) in (4..).zip((3_720..=4_260).step_by(60)) {
+			System::set_block_number(block);
+			Timestamp::set_timestamp(seconds * 1_000);
+			assert_eq!(TemplateModule::stall_grace_period(), seconds < 4_260);
+			TemplateModule::on_finalize(block);
+		}
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::signed(ALICE), BOB, DOT, KYL));
+	});
+}
+
+#[test]
+fn repay_many_settles_several_borrows_at_once() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		const ETH: Token = 5;
+		create_asset_and_mint(ETH, BOB, 100_000);
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			ETH,
+			DOT,
+			FixedU128::from(1)
+		));
+		assert_ok!(TemplateModule::borrow(
+			RuntimeOrigin::signed(BOB),
+			DOT,
+			40_000,
+			ETH,
+			100_000,
+			None
+		));
+		let repayments =
+			|repayments: Vec<(Token, Token, BalanceAmount)>| repayments.try_into().unwrap();
+
+		// the repayments go through all or none
+		assert_noop!(
+			TemplateModule::repay_many(
+				RuntimeOrigin::signed(BOB),
+				repayments(vec![(DOT, KYL, 300_000), (DOT, DOT, 10_000)])
+			),
+			Error::<Test>::LoanDoesNotExists
+		);
+		assert_noop!(
+			TemplateModule::repay_many(
+				RuntimeOrigin::signed(BOB),
+				repayments(vec![(DOT, ETH, 10_000), (DOT, ETH, 10_000)])
+			),
+			Error::<Test>::DuplicateRepayment
+		);
+
+		// the KYL borrow is repaid in full, half of the ETH one
+		let settlement = vec![(DOT, KYL, 300_000), (DOT, ETH, 20_000)];
+		assert_ok!(TemplateModule::repay_many(
+			RuntimeOrigin::signed(BOB),
+			repayments(settlement.clone())
+		));
+		System::assert_last_event(
+			Event::BorrowsRepaid { who: BOB, repayments: repayments(settlement) }.into(),
+		);
+		assert_eq!(AccountBorrows::<Test>::get(BOB).to_vec(), vec![(DOT, ETH)]);
+		assert_eq!(balance(KYL, BOB), 1_000_000);
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, ETH)).unwrap().collateral_balance, 50_000);
+	});
+}
+
+#[test]
+fn positions_migrate_from_a_retired_pool_to_its_replacement() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		const CAROL: SignedOrigin = 3;
+		const NEW_POOL_ID: Token = 13;
+		fund_account(CAROL, DOT, 1_000_000);
+		let (old_account, new_account) = (
+			TemplateModule::pool_account_id(LENDING_POOL_ID),
+			TemplateModule::pool_account_id(NEW_POOL_ID),
+		);
+		let update = PoolParamsUpdate {
+			collateral_factor: Some(Permill::from_percent(60)),
+			..Default::default()
+		};
+		assert_noop!(
+			TemplateModule::replace_lending_pool(
+				RuntimeOrigin::signed(ALICE),
+				DOT,
+				NEW_POOL_ID,
+				update.clone(),
+				2
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::replace_lending_pool(
+				RuntimeOrigin::root(),
+				DOT,
+				KYL,
+				update.clone(),
+				2
+			),
+			Error::<Test>::IdAlreadyExists
+		);
+		// the borrow of BOB and the supply of ALICE are read
+		assert_noop!(
+			TemplateModule::replace_lending_pool(
+				RuntimeOrigin::root(),
+				DOT,
+				NEW_POOL_ID,
+				update.clone(),
+				1
+			),
+			Error::<Test>::BadWitness
+		);
+		assert_ok!(TemplateModule::replace_lending_pool(
+			RuntimeOrigin::root(),
+			DOT,
+			NEW_POOL_ID,
+			update,
+			2
+		));
+		System::assert_has_event(
+			Event::LendingPoolReplaced {
+				asset: DOT,
+				old_pool: LENDING_POOL_ID,
+				new_pool: NEW_POOL_ID,
+				borrows: 1,
+			}
+			.into(),
+		);
+
+		// the new pool serves DOT, the borrow of BOB is still owed to the retired one
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(DOT)).unwrap();
+		assert_eq!(pool.id, NEW_POOL_ID);
+		assert_eq!(pool.collateral_factor, Permill::from_percent(60));
+		assert_eq!(RetiredPools::<Test>::get(LENDING_POOL_ID).unwrap().reserve_balance, 300_000);
+		assert_eq!(RetiredBorrows::<Test>::get((BOB, DOT, KYL)), Some(LENDING_POOL_ID));
+		assert_eq!(AccountBorrows::<Test>::get(BOB).to_vec(), vec![(DOT, KYL)]);
+		assert_ok!(TemplateModule::do_try_state());
+
+		let accounts = |accounts: Vec<SignedOrigin>| accounts.try_into().unwrap();
+		assert_noop!(
+			TemplateModule::migrate_positions(
+				RuntimeOrigin::signed(CAROL),
+				NEW_POOL_ID,
+				NEW_POOL_ID,
+				accounts(vec![BOB])
+			),
+			Error::<Test>::PoolNotRetired
+		);
+		assert_noop!(
+			TemplateModule::migrate_positions(
+				RuntimeOrigin::signed(CAROL),
+				LENDING_POOL_ID,
+				KYL_POOL_ID,
+				accounts(vec![BOB])
+			),
+			Error::<Test>::NotReplacementPool
+		);
+		assert_noop!(
+			TemplateModule::migrate_positions(
+				RuntimeOrigin::signed(CAROL),
+				LENDING_POOL_ID,
+				NEW_POOL_ID,
+				accounts(vec![CAROL])
+			),
+			Error::<Test>::NothingToMigrate
+		);
+		// the new pool buys the debt of BOB once supplied, the retired pool then pays the supply
+		// of ALICE out: the positions migrate in that order or not at all
+		assert_noop!(
+			TemplateModule::migrate_positions(
+				RuntimeOrigin::signed(CAROL),
+				LENDING_POOL_ID,
+				NEW_POOL_ID,
+				accounts(vec![BOB])
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(CAROL), DOT, 200_000, None));
+		assert_noop!(
+			TemplateModule::migrate_positions(
+				RuntimeOrigin::signed(CAROL),
+				LENDING_POOL_ID,
+				NEW_POOL_ID,
+				accounts(vec![ALICE, BOB])
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_ok!(TemplateModule::migrate_positions(
+			RuntimeOrigin::signed(CAROL),
+			LENDING_POOL_ID,
+			NEW_POOL_ID,
+			accounts(vec![BOB, ALICE])
+		));
+		System::assert_has_event(
+			Event::PositionMigrated {
+				who: BOB,
+				old_pool: LENDING_POOL_ID,
+				new_pool: NEW_POOL_ID,
+				supply: 0,
+				debt: 200_000,
+			}
+			.into(),
+		);
+		System::assert_last_event(
+			Event::PositionMigrated {
+				who: ALICE,
+				old_pool: LENDING_POOL_ID,
+				new_pool: NEW_POOL_ID,
+				supply: 500_000,
+				debt: 0,
+			}
+			.into(),
+		);
+
+		// the borrow of BOB is owed to the new pool, with its collateral
+		assert!(!RetiredBorrows::<Test>::contains_key((BOB, DOT, KYL)));
+		assert_eq!(Borrows::<Test>::get((BOB, DOT, KYL)).unwrap().principal, Some(200_000));
+		assert_eq!(balance(KYL, new_account), 400_000);
+		assert_eq!(balance(KYL, old_account), 0);
+		// the LP tokens of ALICE are exchanged for LP tokens of the new pool worth as much
+		assert_eq!(balance(LENDING_POOL_ID, ALICE), 0);
+		assert_eq!(balance(NEW_POOL_ID, ALICE), 500_000);
+		assert_eq!(balance(DOT, old_account), 0);
+		let retired = RetiredPools::<Test>::get(LENDING_POOL_ID).unwrap();
+		assert_eq!((retired.reserve_balance, retired.borrowed_balance), (0, 0));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(DOT)).unwrap();
+		assert_eq!((pool.reserve_balance, pool.borrowed_balance), (500_000, 200_000));
+		assert_ok!(TemplateModule::do_try_state());
+
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 200_000, KYL));
+	});
+}
+
+#[test]
+fn borrows_of_a_retired_pool_are_repaid_and_liquidated_until_migrated() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		const NEW_POOL_ID: Token = 13;
+		assert_ok!(Assets::mint(RuntimeOrigin::signed(ALICE), DOT.into(), TREASURY, 300_000));
+		let old_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		assert_ok!(TemplateModule::replace_lending_pool(
+			RuntimeOrigin::root(),
+			DOT,
+			NEW_POOL_ID,
+			Default::default(),
+			2
+		));
+
+		// the borrow can not grow until migrated
+		assert_noop!(
+			TemplateModule::borrow(RuntimeOrigin::signed(BOB), DOT, 1_000, KYL, 2_000, None),
+			Error::<Test>::BorrowNotMigrated
+		);
+
+		// the repayments go to the retired pool, which releases the collateral
+		assert_ok!(TemplateModule::repay(RuntimeOrigin::signed(BOB), DOT, 50_000, KYL));
+		let retired = RetiredPools::<Test>::get(LENDING_POOL_ID).unwrap();
+		assert_eq!((retired.reserve_balance, retired.borrowed_balance), (350_000, 150_000));
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(DOT)).unwrap();
+		assert_eq!((pool.reserve_balance, pool.borrowed_balance), (0, 0));
+		assert_eq!(balance(KYL, old_account), 300_000);
+		assert_ok!(TemplateModule::do_try_state());
+
+		// the collateral halves in value, 80% of it covers only 120_000
+		assert_ok!(TemplateModule::set_asset_price(
+			RuntimeOrigin::signed(ORACLE),
+			KYL,
+			DOT,
+			FixedU128::from_rational(1, 2)
+		));
+		assert_eq!(TemplateModule::shortfall(&BOB, DOT, KYL), Ok(30_000));
+		assert_ok!(TemplateModule::liquidate_borrow(RuntimeOrigin::none(), BOB, DOT, KYL));
+		assert!(Borrows::<Test>::get((BOB, DOT, KYL)).is_none());
+		assert!(!RetiredBorrows::<Test>::contains_key((BOB, DOT, KYL)));
+		assert_eq!(balance(KYL, TREASURY), 300_000);
+		assert_eq!(balance(KYL, old_account), 0);
+		let retired = RetiredPools::<Test>::get(LENDING_POOL_ID).unwrap();
+		assert_eq!((retired.reserve_balance, retired.borrowed_balance), (500_000, 0));
+		assert_ok!(TemplateModule::do_try_state());
+	});
+}
+
+#[test]
+fn replacing_a_pool_accrues_the_rewards_of_its_suppliers() {
+	new_test_ext().execute_with(|| {
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		create_asset_and_mint(KYL, ALICE, 1_000_000);
+		setup_active_pool(1_000);
+		assert_ok!(TemplateModule::set_reward_program(RuntimeOrigin::root(), DOT, KYL, 10, None));
+		assert_ok!(TemplateModule::fund_rewards(RuntimeOrigin::signed(ALICE), DOT, 100_000));
+		assert_ok!(TemplateModule::update_reward_boost(RuntimeOrigin::signed(ALICE), ALICE, DOT));
+		assert_eq!(UserRewards::<Test>::get(ALICE, DOT).accrued, 0);
+
+		// ALICE is credited what she earned with her LP tokens of the replaced pool
+		Timestamp::set_timestamp(100_000);
+		assert_ok!(TemplateModule::replace_lending_pool(
+			RuntimeOrigin::root(),
+			DOT,
+			13,
+			Default::default(),
+			1
+		));
+		let rewards = UserRewards::<Test>::get(ALICE, DOT);
+		assert!(rewards.accrued > 0);
+		assert_eq!(rewards.supply_index, RewardPrograms::<Test>::get(DOT).unwrap().supply_index);
+	});
+}
+
+#[test]
+fn redenomination_remaps_a_pool_and_its_positions() {
+	new_test_ext().execute_with(|| {
+		setup_borrow();
+		// DOT is re-registered as NEW_DOT, a hundred NEW_DOT per DOT
+		const NEW_DOT: Token = 6;
+		create_asset_and_mint(NEW_DOT, TREASURY, 1);
+		let pool_account = TemplateModule::pool_account_id(LENDING_POOL_ID);
+		let held = balance(DOT, pool_account);
+		let ratio = FixedU128::from(100);
+
+		let zero = FixedU128::from(0);
+		assert_noop!(
+			TemplateModule::redenominate_pool(RuntimeOrigin::root(), DOT, NEW_DOT, zero, 4),
+			Error::<Test>::InvalidRedenomination
+		);
+		// the borrow of BOB, its markets, the supply of ALICE and the price of KYL are read
+		assert_noop!(
+			TemplateModule::redenominate_pool(RuntimeOrigin::root(), DOT, NEW_DOT, ratio, 3),
+			Error::<Test>::BadWitness
+		);
+		assert_ok!(TemplateModule::redenominate_pool(
+			RuntimeOrigin::root(),
+			DOT,
+			NEW_DOT,
+			ratio,
+			4
+		));
+		System::assert_last_event(
+			Event::PoolRedenominated { asset: DOT, new_asset: NEW_DOT, ratio, positions: 2 }.into(),
+		);
+
+		// the pool keeps its LP tokens, its balances are converted
+		assert!(LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(DOT)).is_none());
+		let pool = LendingPoolStorage::<Test>::get(AssetPool::<Test>::from(NEW_DOT)).unwrap();
+		assert_eq!(pool.id, LENDING_POOL_ID);
+		assert_eq!(pool.reserve_balance, 30_000_000);
+		assert_eq!(pool.borrowed_balance, 20_000_000);
+		assert_eq!(balance(DOT, pool_account), 0);
+		assert_eq!(balance(NEW_DOT, pool_account), held * 100);
+
+		// the borrow of BOB is remapped, its collateral worth as much in NEW_DOT
+		assert!(Borrows::<Test>::get((BOB, DOT, KYL)).is_none());
+		let borrow = Borrows::<Test>::get((BOB, NEW_DOT, KYL)).unwrap();
+		assert_eq!(borrow.principal, Some(20_000_000));
+		assert_eq!(AccountBorrows::<Test>::get(BOB).to_vec(), vec![(NEW_DOT, KYL)]);
+		assert_eq!(AssetPrices::<Test>::get((KYL, NEW_DOT)), Some(FixedU128::from(100)));
+		assert_ok!(TemplateModule::do_try_state());
+
+		// the LP tokens of ALICE are withdrawn in NEW_DOT
+		assert_ok!(TemplateModule::withdraw(RuntimeOrigin::signed(ALICE), NEW_DOT, 10_000_000));
+		assert_eq!(balance(NEW_DOT, ALICE), 10_000_000);
+	});
+}
+
+#[test]
+fn supplies_are_capped_to_a_share_of_the_pool_per_account() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		fund_account(BOB, DOT, 1_000_000);
+		setup_active_pool(500_000);
+		let cap = Some(Permill::from_percent(25));
+		assert_noop!(
+			TemplateModule::set_account_supply_cap(RuntimeOrigin::signed(ALICE), DOT, cap),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_account_supply_cap(RuntimeOrigin::root(), KYL, cap),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+		assert_ok!(TemplateModule::set_account_supply_cap(RuntimeOrigin::root(), DOT, cap));
+		System::assert_last_event(Event::<Test>::AccountSupplyCapSet { asset: DOT, cap }.into());
+
+		// 200_000 of 700_000 exceeds a quarter of the pool, 150_000 of 650_000 does not
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 200_000, None),
+			Error::<Test>::SupplyCapExceeded
+		);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 150_000, None));
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 20_000, None),
+			Error::<Test>::SupplyCapExceeded
+		);
+
+		// nor can it receive LP tokens beyond the cap
+		assert_noop!(
+			TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(ALICE), DOT, BOB, 20_000),
+			Error::<Test>::SupplyCapExceeded
+		);
+		assert_ok!(TemplateModule::transfer_lp_tokens(
+			RuntimeOrigin::signed(ALICE),
+			DOT,
+			BOB,
+			10_000
+		));
+
+		// ALICE keeps its LP tokens above the cap, but can not supply more until it is lifted
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, None),
+			Error::<Test>::SupplyCapExceeded
+		);
+		assert_ok!(TemplateModule::set_account_supply_cap(RuntimeOrigin::root(), DOT, None));
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(ALICE), DOT, 1_000, None));
+	});
+}
+
+#[test]
+fn supplies_follow_the_terms_of_the_tier_of_the_account() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		const CAROL: SignedOrigin = 3;
+		create_asset_and_mint(DOT, ALICE, 1_000_000);
+		fund_account(BOB, DOT, 1_000_000);
+		fund_account(CAROL, DOT, 1_000_000);
+		setup_active_pool(500_000);
+		let institutional = Some(TierTerms { max_supply: None });
+		assert_noop!(
+			TemplateModule::set_account_tier(
+				RuntimeOrigin::signed(ALICE),
+				BOB,
+				Some(AccountTier::Institutional)
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			TemplateModule::set_tier_terms(
+				RuntimeOrigin::root(),
+				KYL,
+				AccountTier::Institutional,
+				institutional
+			),
+			Error::<Test>::LendingPoolDoesNotExist
+		);
+
+		// the pool opens to the institutions only
+		assert_ok!(TemplateModule::set_tier_terms(
+			RuntimeOrigin::root(),
+			DOT,
+			AccountTier::Institutional,
+			institutional
+		));
+		System::assert_last_event(
+			Event::<Test>::TierTermsSet {
+				asset: DOT,
+				tier: AccountTier::Institutional,
+				terms: institutional,
+			}
+			.into(),
+		);
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 300_000, None),
+			Error::<Test>::TierNotEligible
+		);
+		assert_ok!(TemplateModule::set_account_tier(
+			RuntimeOrigin::root(),
+			BOB,
+			Some(AccountTier::Institutional)
+		));
+		System::assert_last_event(
+			Event::<Test>::AccountTierSet { who: BOB, tier: Some(AccountTier::Institutional) }
+				.into(),
+		);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(BOB), DOT, 300_000, None));
+		assert_noop!(
+			TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(BOB), DOT, CAROL, 1_000),
+			Error::<Test>::TierNotEligible
+		);
+
+		// then to the retail accounts, up to 100_000 each
+		let retail = Some(TierTerms { max_supply: Some(100_000) });
+		assert_ok!(TemplateModule::set_tier_terms(
+			RuntimeOrigin::root(),
+			DOT,
+			AccountTier::Retail,
+			retail
+		));
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(CAROL), DOT, 150_000, None),
+			Error::<Test>::SupplyCapExceeded
+		);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(CAROL), DOT, 100_000, None));
+		assert_noop!(
+			TemplateModule::transfer_lp_tokens(RuntimeOrigin::signed(BOB), DOT, CAROL, 1_000),
+			Error::<Test>::SupplyCapExceeded
+		);
+
+		// the tier of the provider applies, unless one is assigned
+		MockAccountTierProvider::set_tier(CAROL, AccountTier::Institutional);
+		assert_ok!(TemplateModule::supply(RuntimeOrigin::signed(CAROL), DOT, 100_000, None));
+		assert_ok!(TemplateModule::set_account_tier(
+			RuntimeOrigin::root(),
+			CAROL,
+			Some(AccountTier::Retail)
+		));
+		assert_noop!(
+			TemplateModule::supply(RuntimeOrigin::signed(CAROL), DOT, 1_000, None),
+			Error::<Test>::SupplyCapExceeded
+		);
+	});
+}
@@ -0,0 +1,76 @@
+use crate::*;
+
+/// The tier of an account, segmenting the access to the lending pools of regulated deployments
+#[derive(
+	Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default,
+)]
+pub enum AccountTier {
+	/// The accounts without a tier of their own
+	#[default]
+	Retail,
+	/// The accounts vetted as professional investors
+	Institutional,
+}
+
+/// The terms a lending pool opens to the accounts of a tier
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct TierTerms<Balance> {
+	/// The balance an account of the tier may supply to the pool at most, `None` for no cap
+	pub max_supply: Option<Balance>,
+}
+
+/// Provides the tiers of the accounts from outside the pallet, e.g. from the KYC records of an
+/// identity pallet. The tiers assigned with `set_account_tier` take precedence.
+pub trait AccountTierProvider<AccountId> {
+	/// Returns the tier of `who`, `None` if it has none
+	fn tier(who: &AccountId) -> Option<AccountTier>;
+}
+
+/// No account has a tier
+impl<AccountId> AccountTierProvider<AccountId> for () {
+	fn tier(_who: &AccountId) -> Option<AccountTier> {
+		None
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Returns the tier of `who`: the one assigned with `set_account_tier`, otherwise the one of
+	/// the `AccountTierProvider`, `Retail` by default
+	pub fn account_tier(who: &T::AccountId) -> AccountTier {
+		AccountTiers::<T>::get(who)
+			.or_else(|| T::AccountTierProvider::tier(who))
+			.unwrap_or_default()
+	}
+
+	/// Sets the terms of the lending pool of `asset` for the accounts of `tier`, `None` making
+	/// them ineligible. The pools without terms are open to all the tiers, the pools with some
+	/// only to the tiers they have terms for.
+	pub fn do_set_tier_terms(
+		asset: AssetIdOf<T>,
+		tier: AccountTier,
+		terms: Option<TierTerms<AssetBalanceOf<T>>>,
+	) -> DispatchResult {
+		ensure!(
+			LendingPoolStorage::<T>::contains_key(AssetPool::<T>::from(asset)),
+			Error::<T>::LendingPoolDoesNotExist
+		);
+		PoolTierTerms::<T>::set(asset, tier, terms);
+		Ok(())
+	}
+
+	/// Ensures that the tier of `who` is eligible to `pool`, and that the LP tokens of `who` are
+	/// worth no more than the `max_supply` of its tier
+	pub(crate) fn ensure_tier_allows(who: &T::AccountId, pool: &LendingPool<T>) -> DispatchResult {
+		let asset = pool.lend_token_id;
+		if !PoolTierTerms::<T>::contains_prefix(asset) {
+			return Ok(());
+		}
+		let terms = PoolTierTerms::<T>::get(asset, Self::account_tier(who))
+			.ok_or(Error::<T>::TierNotEligible)?;
+		if let Some(max_supply) = terms.max_supply {
+			let held = pool.accrued_deposit(T::Fungibles::balance(pool.id, who))?;
+			ensure!(held <= max_supply, Error::<T>::SupplyCapExceeded);
+		}
+		Ok(())
+	}
+}
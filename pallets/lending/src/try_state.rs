@@ -0,0 +1,138 @@
+use crate::*;
+use frame_support::{
+	sp_runtime::TryRuntimeError,
+	sp_std::{collections::btree_map::BTreeMap, vec::Vec},
+	traits::GetStorageVersion,
+};
+
+/// The aggregates of the lending pools and positions snapshot before a runtime upgrade
+#[derive(Encode, Decode, PartialEq, RuntimeDebug)]
+struct UpgradeSnapshot<AssetId, Balance> {
+	// (asset, reserve_balance, borrowed_balance, LP token issuance) of the pools by asset
+	pools: Vec<(AssetId, Balance, Balance, Balance)>,
+	borrows: u32,
+	collateral: Balance,
+	supply_positions: u32,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Checks the accounting invariants of the lending pools:
+	/// - the principal of the borrows of a pool adds up to its borrowed balance
+	/// - the LP tokens of the suppliers of a pool do not exceed their total issuance
+	/// - the account of a pool holds its reserves, the collaterals of its borrows but the native
+	///   ones and the referral earnings in its asset
+	/// - so do the retired pools, for the borrows still owed to them
+	pub fn do_try_state() -> Result<(), TryRuntimeError> {
+		// the positions can only be read once their lazy migration is done
+		if MigrationCursor::<T>::exists() {
+			return Ok(());
+		}
+
+		let zero = AssetBalanceOf::<T>::zero();
+		let mut owed: BTreeMap<(AssetIdOf<T>, AssetIdOf<T>), AssetBalanceOf<T>> = BTreeMap::new();
+		let mut owe = |pool_id, asset, balance: AssetBalanceOf<T>| {
+			let total = owed.entry((pool_id, asset)).or_insert(zero);
+			*total = total.saturating_add(balance);
+		};
+		let pool_id = |asset| {
+			LendingPoolStorage::<T>::get(AssetPool::<T>::from(asset))
+				.map(|pool| pool.id)
+				.ok_or("a borrow or a referral earning has no lending pool")
+		};
+		// the borrows owed to a retired pool until migrated
+		let borrow_pool_id = |key: &(T::AccountId, AssetIdOf<T>, AssetIdOf<T>), asset| {
+			RetiredBorrows::<T>::get(key).map_or_else(|| pool_id(asset), Ok)
+		};
+
+		for pool in LendingPoolStorage::<T>::iter_values().chain(RetiredPools::<T>::iter_values()) {
+			let asset = pool.lend_token_id;
+
+			let principal = Borrows::<T>::iter()
+				.filter(|(key, borrow)| {
+					borrow_pool_id(key, borrow.borrowed_asset).ok() == Some(pool.id)
+				})
+				.fold(zero, |total, (_, borrow)| {
+					total.saturating_add(borrow.principal.unwrap_or_default())
+				});
+			ensure!(
+				principal == pool.borrowed_balance,
+				"the borrowed balance of a lending pool differs from the principal of its borrows"
+			);
+
+			let supplied = SupplyIndexStorage::<T>::iter_keys()
+				.filter(|(_, supplied_asset)| *supplied_asset == asset)
+				.fold(zero, |total, (who, _)| {
+					total.saturating_add(T::Fungibles::balance(pool.id, &who))
+				});
+			ensure!(
+				supplied <= T::Fungibles::total_issuance(pool.id),
+				"the LP tokens of the suppliers exceed their total issuance"
+			);
+
+			owe(pool.id, asset, pool.reserve_balance);
+		}
+
+		// the native collateral is held in the accounts of the borrowers
+		for (key, borrow) in Borrows::<T>::iter()
+			.filter(|(_, borrow)| !Self::is_native_asset(borrow.collateral_asset))
+		{
+			owe(
+				borrow_pool_id(&key, borrow.borrowed_asset)?,
+				borrow.collateral_asset,
+				borrow.collateral_balance,
+			);
+		}
+		for (_, asset, earnings) in ReferralEarnings::<T>::iter() {
+			owe(pool_id(asset)?, asset, earnings);
+		}
+
+		for ((pool_id, asset), balance) in owed {
+			ensure!(
+				T::Fungibles::balance(asset, &Self::pool_account_id(pool_id)) >= balance,
+				"a pool account does not hold the balances owed by its lending pool"
+			);
+		}
+		Ok(())
+	}
+
+	fn upgrade_snapshot() -> UpgradeSnapshot<AssetIdOf<T>, AssetBalanceOf<T>> {
+		let mut pools: Vec<_> = LendingPoolStorage::<T>::iter_values()
+			.map(|pool| {
+				let issuance = T::Fungibles::total_issuance(pool.id);
+				(pool.lend_token_id, pool.reserve_balance, pool.borrowed_balance, issuance)
+			})
+			.collect();
+		pools.sort_by_key(|(asset, ..)| *asset);
+
+		UpgradeSnapshot {
+			pools,
+			borrows: Borrows::<T>::iter_keys().count() as u32,
+			collateral: Borrows::<T>::iter_values().fold(Zero::zero(), |total, borrow| {
+				total.saturating_add(borrow.collateral_balance)
+			}),
+			supply_positions: SupplyIndexStorage::<T>::iter_keys().count() as u32,
+		}
+	}
+
+	/// Snapshots the aggregates of the lending pools and positions before a runtime upgrade,
+	/// unless the upgrade migrates the storage of the pallet, which its migration checks.
+	pub fn do_pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		let migrated = Self::on_chain_storage_version() != Self::current_storage_version();
+		Ok((!migrated).then(Self::upgrade_snapshot).encode())
+	}
+
+	/// Verifies that the aggregates of the lending pools and positions survived the runtime
+	/// upgrade, along with the accounting invariants.
+	pub fn do_post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let before =
+			Option::<UpgradeSnapshot<AssetIdOf<T>, AssetBalanceOf<T>>>::decode(&mut &state[..])
+				.map_err(|_| "the pre-upgrade snapshot can not be decoded")?;
+		if let Some(before) = before {
+			ensure!(
+				before == Self::upgrade_snapshot(),
+				"the aggregates of the lending pools changed during the upgrade"
+			);
+		}
+		Self::do_try_state()
+	}
+}
@@ -1,29 +1,24 @@
-
-//! Autogenerated weights for pallet_template
+//! Weights for the lending pallet
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
-//! DATE: 2023-04-06, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! WORST CASE MAP SIZE: `1000000`
-//! HOSTNAME: `Alexs-MacBook-Pro-2.local`, CPU: `<UNKNOWN>`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
+//! The weights are estimated from the storage accesses of the benchmarks in `benchmarking.rs`
+//! until they are generated on the reference hardware with the command below. The proof sizes
+//! allow 3_600 bytes per storage read, the size of the proof of an entry of an `Assets` account.
 
-// Executed Command:
-// ../../target/release/node-template
+// Command:
+// ./target/release/kylix-node
 // benchmark
 // pallet
 // --chain
 // dev
 // --pallet
-// pallet_template
+// lending
 // --extrinsic
 // *
 // --steps=50
 // --repeat=20
 // --wasm-execution=compiled
 // --output
-// pallets/template/src/weights.rs
-// --template
-// ../../.maintain/frame-weight-template.hbs
+// pallets/lending/src/weights.rs
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -32,33 +27,734 @@
 use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
 use core::marker::PhantomData;
 
-/// Weight functions needed for pallet_template.
+/// Weight functions needed for lending.
 pub trait WeightInfo {
-	fn do_something() -> Weight;
-	fn cause_error() -> Weight;
+	fn create_lending_pool() -> Weight;
+	fn activate_lending_pool() -> Weight;
+	fn supply() -> Weight;
+	fn withdraw(m: u32, ) -> Weight;
+	fn borrow(m: u32, ) -> Weight;
+	fn repay() -> Weight;
+	fn claim_rewards(p: u32, ) -> Weight;
+	fn deactivate_lending_pool() -> Weight;
+	fn update_pool_rate_model() -> Weight;
+	fn update_pool_kink() -> Weight;
+	fn set_asset_price() -> Weight;
+	fn set_reward_program() -> Weight;
+	fn claim_vested_rewards() -> Weight;
+	fn compound_rewards() -> Weight;
+	fn set_auto_compound() -> Weight;
+	fn compound_rewards_for() -> Weight;
+	fn update_reward_boost() -> Weight;
+	fn fund_rewards() -> Weight;
+	fn register_referral_code() -> Weight;
+	fn claim_referral_earnings() -> Weight;
+	fn set_borrow_fee() -> Weight;
+	fn set_protocol_fee() -> Weight;
+	fn liquidate_borrow(m: u32, ) -> Weight;
+	fn accrue_interest() -> Weight;
+	fn set_min_borrow() -> Weight;
+	fn write_off_dust_debt() -> Weight;
+	fn transfer_lp_tokens() -> Weight;
+	fn set_lp_collateral_factor() -> Weight;
+	fn rebalance_stable_rate() -> Weight;
+	fn set_stable_rate_premium() -> Weight;
+	fn switch_borrow_rate_mode() -> Weight;
+	fn set_borrow_rate_bounds() -> Weight;
+	fn set_outflow_cap() -> Weight;
+	fn set_maintenance_mode() -> Weight;
+	fn announce_param_change() -> Weight;
+	fn execute_param_change() -> Weight;
+	fn cancel_param_change() -> Weight;
+	fn set_permissioned_pool() -> Weight;
+	fn set_whitelisted() -> Weight;
+	fn force_liquidate() -> Weight;
+	fn rescue_funds(b: u32, ) -> Weight;
+	fn force_update_pool_params() -> Weight;
+	fn wrap_position() -> Weight;
+	fn transfer_position() -> Weight;
+	fn unwrap_position() -> Weight;
+	fn approve_debt() -> Weight;
+	fn transfer_debt(m: u32, ) -> Weight;
+	fn set_dex_lp_collateral() -> Weight;
+	fn register_keeper() -> Weight;
+	fn deregister_keeper() -> Weight;
+	fn assign_liquidation() -> Weight;
+	fn margin_call() -> Weight;
+	fn add_collateral() -> Weight;
+	fn report_price_deviation() -> Weight;
+	fn set_pool_paused() -> Weight;
+	fn approve_operator() -> Weight;
+	fn operate() -> Weight;
+	fn set_loyalty_tiers() -> Weight;
+	fn repay_many(n: u32, ) -> Weight;
+	fn replace_lending_pool(e: u32, ) -> Weight;
+	fn migrate_positions(a: u32, ) -> Weight;
+	fn redenominate_pool(e: u32, ) -> Weight;
+	fn set_account_supply_cap() -> Weight;
+	fn set_account_tier() -> Weight;
+	fn set_tier_terms() -> Weight;
+	fn set_risk_parameter() -> Weight;
 }
 
-/// Weights for pallet_template using the Substrate node and recommended hardware.
+/// Weights for lending using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
-			.saturating_add(T::DbWeight::get().writes(1_u64))
-	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:2 w:3)
+	/// Storage: Lending RewardPrograms (r:2 w:0)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: System Account (r:0 w:1)
+	fn create_lending_pool() -> Weight {
+		Weight::from_parts(60_000_000, 28_800)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	fn activate_lending_pool() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Referrals (r:1 w:1)
+	/// Storage: Lending ReferralCodes (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Lending RewardPrograms (r:2 w:1)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:0 w:1)
+	/// Storage: Lending AccountSupplyCaps (r:1 w:0)
+	/// Storage: Lending PoolTierTerms (r:2 w:0)
+	/// Storage: Lending AccountTiers (r:1 w:0)
+	fn supply() -> Weight {
+		Weight::from_parts(90_000_000, 57_600)
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Lending RewardPrograms (r:2 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:0)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:1 w:0)
+	/// Storage: Lending Borrows (r:m w:0)
+	/// Storage: Lending LendingPoolStorage (r:m w:0)
+	/// Storage: Lending AssetPrices (r:m w:0)
+	/// The range of component `m` is `[0, 16]`.
+	fn withdraw(m: u32, ) -> Weight {
+		Weight::from_parts(85_000_000, 36_000)
+			.saturating_add(Weight::from_parts(20_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(0, 14_400).saturating_mul(m.into()))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Lending Referrals (r:1 w:1)
+	/// Storage: Lending ReferralCodes (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Lending AccountBorrows (r:1 w:1)
+	/// Storage: Lending Borrows (r:m w:0)
+	/// Storage: Lending LendingPoolStorage (r:m w:0)
+	/// Storage: Lending AssetPrices (r:m w:0)
+	/// The range of component `m` is `[0, 15]`.
+	fn borrow(m: u32, ) -> Weight {
+		Weight::from_parts(95_000_000, 43_200)
+			.saturating_add(Weight::from_parts(20_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(0, 14_400).saturating_mul(m.into()))
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending ProtocolFee (r:1 w:0)
+	/// Storage: Lending Referrals (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:5)
+	fn repay() -> Weight {
+		Weight::from_parts(100_000_000, 46_800)
+			.saturating_add(T::DbWeight::get().reads(13_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Lending RewardVesting (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// The range of component `p` is `[1, 16]`.
+	fn claim_rewards(p: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_500)
+			.saturating_add(Weight::from_parts(40_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(Weight::from_parts(0, 21_600).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads((6_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().writes((5_u64).saturating_mul(p.into())))
+	}
+	fn deactivate_lending_pool() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+	}
+	fn update_pool_rate_model() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+	}
+	fn update_pool_kink() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+	}
+	/// Storage: Lending AssetPrices (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Assets Metadata (r:4 w:0)
+	/// Storage: Lending PoolTotals (r:2 w:2)
+	/// Storage: Lending ProtocolStats (r:2 w:2)
+	fn set_asset_price() -> Weight {
+		Weight::from_parts(30_000_000, 43_200)
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Assets Account (r:1 w:0)
+	fn set_reward_program() -> Weight {
+		Weight::from_parts(25_000_000, 14_400)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending RewardVesting (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_vested_rewards() -> Weight {
+		Weight::from_parts(45_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:3 w:2)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:4)
+	fn compound_rewards() -> Weight {
+		Weight::from_parts(140_000_000, 64_800)
+			.saturating_add(T::DbWeight::get().reads(18_u64))
+			.saturating_add(T::DbWeight::get().writes(14_u64))
+	}
+	/// Storage: Lending AutoCompound (r:0 w:1)
+	fn set_auto_compound() -> Weight {
+		Weight::from_parts(14_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending AutoCompound (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:3 w:2)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:7 w:6)
+	fn compound_rewards_for() -> Weight {
+		Weight::from_parts(160_000_000, 75_600)
+			.saturating_add(T::DbWeight::get().reads(21_u64))
+			.saturating_add(T::DbWeight::get().writes(16_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:2 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:0)
+	/// Storage: Assets Account (r:1 w:0)
+	fn update_reward_boost() -> Weight {
+		Weight::from_parts(40_000_000, 25_200)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:3 w:2)
+	fn fund_rewards() -> Weight {
+		Weight::from_parts(50_000_000, 21_600)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Lending ReferralCodes (r:1 w:1)
+	fn register_referral_code() -> Weight {
+		Weight::from_parts(18_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending ReferralCodes (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_referral_earnings() -> Weight {
+		Weight::from_parts(45_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	fn set_borrow_fee() -> Weight {
+		Weight::from_parts(18_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending ProtocolFee (r:0 w:1)
+	fn set_protocol_fee() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending ProtocolFee (r:1 w:0)
+	/// Storage: Lending Referrals (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Lending LiquidationAssignments (r:1 w:1)
+	/// Storage: Lending Keepers (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	/// Storage: Lending MarginCalls (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:1 w:1)
+	/// The range of component `m` is `[0, 15]`.
+	fn liquidate_borrow(m: u32, ) -> Weight {
+		Weight::from_parts(150_000_000, 82_800)
+			.saturating_add(Weight::from_parts(1_500_000, 0).saturating_mul(m.into()))
+			.saturating_add(T::DbWeight::get().reads(23_u64))
+			.saturating_add(T::DbWeight::get().writes(16_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending RateSnapshots (r:1 w:1)
+	fn accrue_interest() -> Weight {
+		Weight::from_parts(30_000_000, 7_200)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending MinBorrow (r:0 w:1)
+	fn set_min_borrow() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:1)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Lending AccountBorrows (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn write_off_dust_debt() -> Weight {
+		Weight::from_parts(70_000_000, 32_400)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:4 w:4)
+	/// Storage: Lending UserRewards (r:4 w:4)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:6 w:2)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	fn transfer_lp_tokens() -> Weight {
+		Weight::from_parts(60_000_000, 61_200)
+			.saturating_add(T::DbWeight::get().reads(17_u64))
+			.saturating_add(T::DbWeight::get().writes(12_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending LpCollateralFactor (r:0 w:1)
+	fn set_lp_collateral_factor() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending StableBorrows (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending StableRatePremium (r:1 w:0)
+	fn rebalance_stable_rate() -> Weight {
+		Weight::from_parts(20_000_000, 10_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending StableRatePremium (r:0 w:1)
+	fn set_stable_rate_premium() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending StableBorrows (r:2 w:1)
+	/// Storage: Lending StableRatePremium (r:1 w:0)
+	fn switch_borrow_rate_mode() -> Weight {
+		Weight::from_parts(25_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending BorrowRateBounds (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	fn set_borrow_rate_bounds() -> Weight {
+		Weight::from_parts(18_000_000, 10_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending OutflowCaps (r:0 w:1)
+	fn set_outflow_cap() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending MaintenanceMode (r:0 w:1)
+	fn set_maintenance_mode() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending NextParamChangeId (r:1 w:1)
+	/// Storage: Scheduler Lookup (r:1 w:1)
+	/// Storage: Scheduler Agenda (r:1 w:1)
+	/// Storage: Lending PendingParamChanges (r:0 w:1)
+	fn announce_param_change() -> Weight {
+		Weight::from_parts(32_000_000, 14_400)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending PendingParamChanges (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Lending BorrowRateBounds (r:1 w:0)
+	fn execute_param_change() -> Weight {
+		Weight::from_parts(22_000_000, 14_400)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending PendingParamChanges (r:1 w:1)
+	/// Storage: Scheduler Lookup (r:1 w:1)
+	/// Storage: Scheduler Agenda (r:1 w:1)
+	fn cancel_param_change() -> Weight {
+		Weight::from_parts(26_000_000, 10_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PermissionedPools (r:0 w:1)
+	fn set_permissioned_pool() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PoolWhitelist (r:0 w:1)
+	fn set_whitelisted() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending AssetPrices (r:4 w:0)
+	/// Storage: Lending ProtocolFee (r:1 w:0)
+	/// Storage: Lending Referrals (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:6)
+	fn force_liquidate() -> Weight {
+		Weight::from_parts(145_000_000, 72_000)
+			.saturating_add(T::DbWeight::get().reads(20_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending Borrows (r:b w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:0)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: System Account (r:1 w:1)
+	/// The range of component `b` is `[1, 1000]`.
+	fn rescue_funds(b: u32, ) -> Weight {
+		Weight::from_parts(40_000_000, 21_600)
+			.saturating_add(Weight::from_parts(6_000_000, 0).saturating_mul(b.into()))
+			.saturating_add(Weight::from_parts(0, 3_600).saturating_mul(b.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(b.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending BorrowRateBounds (r:1 w:0)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Lending MinBorrow (r:0 w:1)
+	/// Storage: Lending OutflowCaps (r:0 w:1)
+	/// Storage: Lending BorrowCaps (r:0 w:1)
+	fn force_update_pool_params() -> Weight {
+		Weight::from_parts(30_000_000, 10_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending Borrows (r:3 w:2)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending NextPositionId (r:1 w:1)
+	/// Storage: Lending StableBorrows (r:1 w:0)
+	/// Storage: Lending BorrowMaturities (r:2 w:0)
+	/// Storage: Lending AccountBorrows (r:2 w:2)
+	/// Storage: Lending Positions (r:0 w:1)
+	fn wrap_position() -> Weight {
+		Weight::from_parts(70_000_000, 43_200)
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Lending Positions (r:1 w:0)
+	/// Storage: Lending Borrows (r:2 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending BorrowMaturities (r:1 w:0)
+	fn transfer_position() -> Weight {
+		Weight::from_parts(45_000_000, 25_200)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Positions (r:1 w:1)
+	/// Storage: Lending Borrows (r:3 w:2)
+	/// Storage: Lending StableBorrows (r:1 w:0)
+	/// Storage: Lending BorrowMaturities (r:1 w:0)
+	/// Storage: Lending AccountBorrows (r:2 w:2)
+	fn unwrap_position() -> Weight {
+		Weight::from_parts(50_000_000, 28_800)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Lending DebtAllowances (r:0 w:1)
+	fn approve_debt() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending StableBorrows (r:2 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending Borrows (r:2 w:2)
+	/// Storage: Lending MinBorrow (r:1 w:0)
+	/// Storage: Lending DebtAllowances (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:2 w:0)
+	/// Storage: Lending Borrows (r:m w:0)
+	/// Storage: Lending AssetPrices (r:m w:0)
+	/// Storage: Lending BorrowMaturities (r:m w:0)
+	/// The range of component `m` is `[1, 16]`.
+	fn transfer_debt(m: u32, ) -> Weight {
+		Weight::from_parts(60_000_000, 32_400)
+			.saturating_add(Weight::from_parts(20_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(0, 10_800).saturating_mul(m.into()))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending DexLpCollaterals (r:0 w:1)
+	fn set_dex_lp_collateral() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Keepers (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn register_keeper() -> Weight {
+		Weight::from_parts(40_000_000, 10_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending Keepers (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn deregister_keeper() -> Weight {
+		Weight::from_parts(40_000_000, 10_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending LiquidationAssignments (r:1 w:1)
+	/// Storage: Lending KeeperCursor (r:1 w:1)
+	/// Storage: Lending Keepers (r:2 w:1)
+	/// Storage: Lending MarginCalls (r:1 w:0)
+	fn assign_liquidation() -> Weight {
+		Weight::from_parts(50_000_000, 32_400)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending MarginCalls (r:1 w:1)
+	fn margin_call() -> Weight {
+		Weight::from_parts(35_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending MarginCalls (r:0 w:1)
+	/// Storage: Assets Asset (r:1 w:0)
+	/// Storage: Assets Account (r:2 w:2)
+	fn add_collateral() -> Weight {
+		Weight::from_parts(50_000_000, 25_200)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Lending PausedPools (r:2 w:2)
+	fn report_price_deviation() -> Weight {
+		Weight::from_parts(25_000_000, 18_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PausedPools (r:0 w:1)
+	fn set_pool_paused() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Operators (r:0 w:1)
+	fn approve_operator() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Operators (r:1 w:0)
+	/// Storage: Lending MaintenanceMode (r:1 w:0)
+	fn operate() -> Weight {
+		Weight::from_parts(15_000_000, 7_200)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending LoyaltyTiers (r:0 w:1)
+	fn set_loyalty_tiers() -> Weight {
+		Weight::from_parts(16_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:n w:n)
+	/// Storage: Lending Borrows (r:n w:n)
+	/// Storage: Lending ProtocolFee (r:n w:0)
+	/// Storage: Lending Referrals (r:n w:0)
+	/// Storage: Lending ReferralEarnings (r:n w:0)
+	/// Storage: Lending AccountBorrows (r:n w:n)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:n w:n)
+	/// The range of component `n` is `[1, 16]`.
+	fn repay_many(n: u32, ) -> Weight {
+		Weight::from_parts(15_000_000, 7_200)
+			.saturating_add(Weight::from_parts(90_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 43_200).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((12_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+			.saturating_add(T::DbWeight::get().writes((9_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Strategies Vaults (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:e w:e)
+	/// Storage: Lending RetiredPools (r:1 w:1)
+	/// Storage: Lending RetiredBorrows (r:e w:e)
+	/// Storage: Lending Positions (r:e w:0)
+	/// Storage: Lending Borrows (r:e w:0)
+	/// Storage: Lending StableBorrows (r:e w:0)
+	/// Storage: Lending BorrowMaturities (r:e w:0)
+	/// Storage: Lending SupplyIndexStorage (r:e w:e)
+	/// Storage: Lending UserRewards (r:e w:e)
+	/// Storage: Lending KTokenCheckpoints (r:e w:e)
+	/// Storage: Lending ReferralEarnings (r:e w:0)
+	/// Storage: Lending LpTokenPools (r:0 w:2)
+	/// Storage: Lending PoolTotals (r:1 w:1)
+	/// Storage: Lending ProtocolStats (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:2+e w:2)
+	/// Storage: System Account (r:1 w:1)
+	/// The range of component `e` is `[0, 2000]`.
+	fn replace_lending_pool(e: u32, ) -> Weight {
+		Weight::from_parts(140_000_000, 64_800)
+			.saturating_add(Weight::from_parts(42_000_000, 0).saturating_mul(e.into()))
+			.saturating_add(Weight::from_parts(0, 28_800).saturating_mul(e.into()))
+			.saturating_add(T::DbWeight::get().reads(18_u64))
+			.saturating_add(T::DbWeight::get().reads((11_u64).saturating_mul(e.into())))
+			.saturating_add(T::DbWeight::get().writes(13_u64))
+			.saturating_add(T::DbWeight::get().writes((5_u64).saturating_mul(e.into())))
+	}
+	/// Storage: Lending RetiredPools (r:a w:a)
+	/// Storage: Lending LendingPoolStorage (r:16a w:a)
+	/// Storage: Lending RetiredBorrows (r:16a w:16a)
+	/// Storage: Lending Borrows (r:32a w:16a)
+	/// Storage: Lending StableBorrows (r:16a w:0)
+	/// Storage: Lending BorrowMaturities (r:16a w:0)
+	/// Storage: Lending AccountBorrows (r:2a w:0)
+	/// Storage: Lending AssetPrices (r:16a w:0)
+	/// Storage: Lending KTokenFreezes (r:a w:0)
+	/// Storage: Lending SupplyIndexStorage (r:a w:a)
+	/// Storage: Lending RewardPrograms (r:2a w:2a)
+	/// Storage: Lending UserRewards (r:2a w:2a)
+	/// Storage: Lending PoolTotals (r:a w:a)
+	/// Storage: Lending ProtocolStats (r:a w:a)
+	/// Storage: Assets Asset (r:3a w:3a)
+	/// Storage: Assets Account (r:20a w:20a)
+	/// The range of component `a` is `[1, 16]`.
+	fn migrate_positions(a: u32, ) -> Weight {
+		Weight::from_parts(20_000_000, 3_500)
+			.saturating_add(Weight::from_parts(1_200_000_000, 0).saturating_mul(a.into()))
+			.saturating_add(Weight::from_parts(0, 464_400).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads((177_u64).saturating_mul(a.into())))
+			.saturating_add(T::DbWeight::get().writes((78_u64).saturating_mul(a.into())))
+	}
+	/// Storage: Lending LendingPoolStorage (r:2 w:2)
+	/// Storage: Lending Borrows (r:e w:e)
+	/// Storage: Lending AccountBorrows (r:e w:e)
+	/// Storage: Lending Positions (r:e w:e)
+	/// Storage: Lending DebtAllowances (r:e w:e)
+	/// Storage: Lending SupplyIndexStorage (r:e w:e)
+	/// Storage: Lending ReferralEarnings (r:e w:e)
+	/// Storage: Lending PoolWhitelist (r:e w:e)
+	/// Storage: Lending AssetPrices (r:e w:e)
+	/// Storage: Lending PoolTotals (r:1 w:2)
+	/// Storage: Lending ProtocolStats (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// The range of component `e` is `[0, 2000]`.
+	fn redenominate_pool(e: u32, ) -> Weight {
+		Weight::from_parts(180_000_000, 144_000)
+			.saturating_add(Weight::from_parts(25_000_000, 0).saturating_mul(e.into()))
+			.saturating_add(Weight::from_parts(0, 18_000).saturating_mul(e.into()))
+			.saturating_add(T::DbWeight::get().reads(40_u64))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(e.into())))
+			.saturating_add(T::DbWeight::get().writes(30_u64))
+			.saturating_add(T::DbWeight::get().writes((10_u64).saturating_mul(e.into())))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AccountSupplyCaps (r:0 w:1)
+	fn set_account_supply_cap() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending AccountTiers (r:0 w:1)
+	fn set_account_tier() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PoolTierTerms (r:0 w:1)
+	fn set_tier_terms() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending RiskParameterOverrides (r:0 w:1)
+	fn set_risk_parameter() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
@@ -66,24 +762,661 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
-			.saturating_add(RocksDbWeight::get().writes(1_u64))
-	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:2 w:3)
+	/// Storage: Lending RewardPrograms (r:2 w:0)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: System Account (r:0 w:1)
+	fn create_lending_pool() -> Weight {
+		Weight::from_parts(60_000_000, 28_800)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	fn activate_lending_pool() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Referrals (r:1 w:1)
+	/// Storage: Lending ReferralCodes (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Lending RewardPrograms (r:2 w:1)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:0 w:1)
+	/// Storage: Lending AccountSupplyCaps (r:1 w:0)
+	/// Storage: Lending PoolTierTerms (r:2 w:0)
+	/// Storage: Lending AccountTiers (r:1 w:0)
+	fn supply() -> Weight {
+		Weight::from_parts(90_000_000, 57_600)
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Lending RewardPrograms (r:2 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:0)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:1 w:0)
+	/// Storage: Lending Borrows (r:m w:0)
+	/// Storage: Lending LendingPoolStorage (r:m w:0)
+	/// Storage: Lending AssetPrices (r:m w:0)
+	/// The range of component `m` is `[0, 16]`.
+	fn withdraw(m: u32, ) -> Weight {
+		Weight::from_parts(85_000_000, 36_000)
+			.saturating_add(Weight::from_parts(20_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(0, 14_400).saturating_mul(m.into()))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	/// Storage: Lending Referrals (r:1 w:1)
+	/// Storage: Lending ReferralCodes (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Lending AccountBorrows (r:1 w:1)
+	/// Storage: Lending Borrows (r:m w:0)
+	/// Storage: Lending LendingPoolStorage (r:m w:0)
+	/// Storage: Lending AssetPrices (r:m w:0)
+	/// The range of component `m` is `[0, 15]`.
+	fn borrow(m: u32, ) -> Weight {
+		Weight::from_parts(95_000_000, 43_200)
+			.saturating_add(Weight::from_parts(20_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(0, 14_400).saturating_mul(m.into()))
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending ProtocolFee (r:1 w:0)
+	/// Storage: Lending Referrals (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:5)
+	fn repay() -> Weight {
+		Weight::from_parts(100_000_000, 46_800)
+			.saturating_add(RocksDbWeight::get().reads(13_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Lending RewardVesting (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// The range of component `p` is `[1, 16]`.
+	fn claim_rewards(p: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_500)
+			.saturating_add(Weight::from_parts(40_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(Weight::from_parts(0, 21_600).saturating_mul(p.into()))
+			.saturating_add(RocksDbWeight::get().reads((6_u64).saturating_mul(p.into())))
+			.saturating_add(RocksDbWeight::get().writes((5_u64).saturating_mul(p.into())))
+	}
+	fn deactivate_lending_pool() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+	}
+	fn update_pool_rate_model() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+	}
+	fn update_pool_kink() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+	}
+	/// Storage: Lending AssetPrices (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Assets Metadata (r:4 w:0)
+	/// Storage: Lending PoolTotals (r:2 w:2)
+	/// Storage: Lending ProtocolStats (r:2 w:2)
+	fn set_asset_price() -> Weight {
+		Weight::from_parts(30_000_000, 43_200)
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Assets Account (r:1 w:0)
+	fn set_reward_program() -> Weight {
+		Weight::from_parts(25_000_000, 14_400)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending RewardVesting (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_vested_rewards() -> Weight {
+		Weight::from_parts(45_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:3 w:2)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:4)
+	fn compound_rewards() -> Weight {
+		Weight::from_parts(140_000_000, 64_800)
+			.saturating_add(RocksDbWeight::get().reads(18_u64))
+			.saturating_add(RocksDbWeight::get().writes(14_u64))
+	}
+	/// Storage: Lending AutoCompound (r:0 w:1)
+	fn set_auto_compound() -> Weight {
+		Weight::from_parts(14_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending AutoCompound (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:3 w:2)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:7 w:6)
+	fn compound_rewards_for() -> Weight {
+		Weight::from_parts(160_000_000, 75_600)
+			.saturating_add(RocksDbWeight::get().reads(21_u64))
+			.saturating_add(RocksDbWeight::get().writes(16_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:2 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending UserRewards (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:0)
+	/// Storage: Assets Account (r:1 w:0)
+	fn update_reward_boost() -> Weight {
+		Weight::from_parts(40_000_000, 25_200)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending RewardPrograms (r:1 w:1)
+	/// Storage: Lending RewardLiabilities (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:3 w:2)
+	fn fund_rewards() -> Weight {
+		Weight::from_parts(50_000_000, 21_600)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	/// Storage: Lending ReferralCodes (r:1 w:1)
+	fn register_referral_code() -> Weight {
+		Weight::from_parts(18_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending ReferralCodes (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_referral_earnings() -> Weight {
+		Weight::from_parts(45_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	fn set_borrow_fee() -> Weight {
+		Weight::from_parts(18_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending ProtocolFee (r:0 w:1)
+	fn set_protocol_fee() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending ProtocolFee (r:1 w:0)
+	/// Storage: Lending Referrals (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Lending LiquidationAssignments (r:1 w:1)
+	/// Storage: Lending Keepers (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	/// Storage: Lending MarginCalls (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:1 w:1)
+	/// The range of component `m` is `[0, 15]`.
+	fn liquidate_borrow(m: u32, ) -> Weight {
+		Weight::from_parts(150_000_000, 82_800)
+			.saturating_add(Weight::from_parts(1_500_000, 0).saturating_mul(m.into()))
+			.saturating_add(RocksDbWeight::get().reads(23_u64))
+			.saturating_add(RocksDbWeight::get().writes(16_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending RateSnapshots (r:1 w:1)
+	fn accrue_interest() -> Weight {
+		Weight::from_parts(30_000_000, 7_200)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending MinBorrow (r:0 w:1)
+	fn set_min_borrow() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:2 w:1)
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Lending AccountBorrows (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn write_off_dust_debt() -> Weight {
+		Weight::from_parts(70_000_000, 32_400)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:4 w:4)
+	/// Storage: Lending UserRewards (r:4 w:4)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:6 w:2)
+	/// Storage: Lending SupplyIndexStorage (r:1 w:1)
+	fn transfer_lp_tokens() -> Weight {
+		Weight::from_parts(60_000_000, 61_200)
+			.saturating_add(RocksDbWeight::get().reads(17_u64))
+			.saturating_add(RocksDbWeight::get().writes(12_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending LpCollateralFactor (r:0 w:1)
+	fn set_lp_collateral_factor() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending StableBorrows (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending StableRatePremium (r:1 w:0)
+	fn rebalance_stable_rate() -> Weight {
+		Weight::from_parts(20_000_000, 10_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending StableRatePremium (r:0 w:1)
+	fn set_stable_rate_premium() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending StableBorrows (r:2 w:1)
+	/// Storage: Lending StableRatePremium (r:1 w:0)
+	fn switch_borrow_rate_mode() -> Weight {
+		Weight::from_parts(25_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending BorrowRateBounds (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	fn set_borrow_rate_bounds() -> Weight {
+		Weight::from_parts(18_000_000, 10_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending OutflowCaps (r:0 w:1)
+	fn set_outflow_cap() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending MaintenanceMode (r:0 w:1)
+	fn set_maintenance_mode() -> Weight {
+		Weight::from_parts(10_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending NextParamChangeId (r:1 w:1)
+	/// Storage: Scheduler Lookup (r:1 w:1)
+	/// Storage: Scheduler Agenda (r:1 w:1)
+	/// Storage: Lending PendingParamChanges (r:0 w:1)
+	fn announce_param_change() -> Weight {
+		Weight::from_parts(32_000_000, 14_400)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending PendingParamChanges (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Lending BorrowRateBounds (r:1 w:0)
+	fn execute_param_change() -> Weight {
+		Weight::from_parts(22_000_000, 14_400)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending PendingParamChanges (r:1 w:1)
+	/// Storage: Scheduler Lookup (r:1 w:1)
+	/// Storage: Scheduler Agenda (r:1 w:1)
+	fn cancel_param_change() -> Weight {
+		Weight::from_parts(26_000_000, 10_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PermissionedPools (r:0 w:1)
+	fn set_permissioned_pool() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PoolWhitelist (r:0 w:1)
+	fn set_whitelisted() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:2 w:1)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending AssetPrices (r:4 w:0)
+	/// Storage: Lending ProtocolFee (r:1 w:0)
+	/// Storage: Lending Referrals (r:1 w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:6)
+	fn force_liquidate() -> Weight {
+		Weight::from_parts(145_000_000, 72_000)
+			.saturating_add(RocksDbWeight::get().reads(20_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending Borrows (r:b w:0)
+	/// Storage: Lending ReferralEarnings (r:1 w:0)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: System Account (r:1 w:1)
+	/// The range of component `b` is `[1, 1000]`.
+	fn rescue_funds(b: u32, ) -> Weight {
+		Weight::from_parts(40_000_000, 21_600)
+			.saturating_add(Weight::from_parts(6_000_000, 0).saturating_mul(b.into()))
+			.saturating_add(Weight::from_parts(0, 3_600).saturating_mul(b.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(b.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending BorrowRateBounds (r:1 w:0)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Lending MinBorrow (r:0 w:1)
+	/// Storage: Lending OutflowCaps (r:0 w:1)
+	/// Storage: Lending BorrowCaps (r:0 w:1)
+	fn force_update_pool_params() -> Weight {
+		Weight::from_parts(30_000_000, 10_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending Borrows (r:3 w:2)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending NextPositionId (r:1 w:1)
+	/// Storage: Lending StableBorrows (r:1 w:0)
+	/// Storage: Lending BorrowMaturities (r:2 w:0)
+	/// Storage: Lending AccountBorrows (r:2 w:2)
+	/// Storage: Lending Positions (r:0 w:1)
+	fn wrap_position() -> Weight {
+		Weight::from_parts(70_000_000, 43_200)
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Lending Positions (r:1 w:0)
+	/// Storage: Lending Borrows (r:2 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending BorrowMaturities (r:1 w:0)
+	fn transfer_position() -> Weight {
+		Weight::from_parts(45_000_000, 25_200)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Positions (r:1 w:1)
+	/// Storage: Lending Borrows (r:3 w:2)
+	/// Storage: Lending StableBorrows (r:1 w:0)
+	/// Storage: Lending BorrowMaturities (r:1 w:0)
+	/// Storage: Lending AccountBorrows (r:2 w:2)
+	fn unwrap_position() -> Weight {
+		Weight::from_parts(50_000_000, 28_800)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Lending DebtAllowances (r:0 w:1)
+	fn approve_debt() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending StableBorrows (r:2 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Lending Borrows (r:2 w:2)
+	/// Storage: Lending MinBorrow (r:1 w:0)
+	/// Storage: Lending DebtAllowances (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:2 w:0)
+	/// Storage: Lending Borrows (r:m w:0)
+	/// Storage: Lending AssetPrices (r:m w:0)
+	/// Storage: Lending BorrowMaturities (r:m w:0)
+	/// The range of component `m` is `[1, 16]`.
+	fn transfer_debt(m: u32, ) -> Weight {
+		Weight::from_parts(60_000_000, 32_400)
+			.saturating_add(Weight::from_parts(20_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(Weight::from_parts(0, 10_800).saturating_mul(m.into()))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending DexLpCollaterals (r:0 w:1)
+	fn set_dex_lp_collateral() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Keepers (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn register_keeper() -> Weight {
+		Weight::from_parts(40_000_000, 10_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending Keepers (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn deregister_keeper() -> Weight {
+		Weight::from_parts(40_000_000, 10_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending LiquidationAssignments (r:1 w:1)
+	/// Storage: Lending KeeperCursor (r:1 w:1)
+	/// Storage: Lending Keepers (r:2 w:1)
+	/// Storage: Lending MarginCalls (r:1 w:0)
+	fn assign_liquidation() -> Weight {
+		Weight::from_parts(50_000_000, 32_400)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending MarginCalls (r:1 w:1)
+	fn margin_call() -> Weight {
+		Weight::from_parts(35_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Borrows (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AssetPrices (r:2 w:0)
+	/// Storage: Lending MarginCalls (r:0 w:1)
+	/// Storage: Assets Asset (r:1 w:0)
+	/// Storage: Assets Account (r:2 w:2)
+	fn add_collateral() -> Weight {
+		Weight::from_parts(50_000_000, 25_200)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Lending AssetPrices (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:2 w:0)
+	/// Storage: Lending PausedPools (r:2 w:2)
+	fn report_price_deviation() -> Weight {
+		Weight::from_parts(25_000_000, 18_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PausedPools (r:0 w:1)
+	fn set_pool_paused() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Operators (r:0 w:1)
+	fn approve_operator() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending Operators (r:1 w:0)
+	/// Storage: Lending MaintenanceMode (r:1 w:0)
+	fn operate() -> Weight {
+		Weight::from_parts(15_000_000, 7_200)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending LoyaltyTiers (r:0 w:1)
+	fn set_loyalty_tiers() -> Weight {
+		Weight::from_parts(16_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:n w:n)
+	/// Storage: Lending Borrows (r:n w:n)
+	/// Storage: Lending ProtocolFee (r:n w:0)
+	/// Storage: Lending Referrals (r:n w:0)
+	/// Storage: Lending ReferralEarnings (r:n w:0)
+	/// Storage: Lending AccountBorrows (r:n w:n)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:n w:n)
+	/// The range of component `n` is `[1, 16]`.
+	fn repay_many(n: u32, ) -> Weight {
+		Weight::from_parts(15_000_000, 7_200)
+			.saturating_add(Weight::from_parts(90_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 43_200).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((12_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+			.saturating_add(RocksDbWeight::get().writes((9_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:1)
+	/// Storage: Strategies Vaults (r:1 w:0)
+	/// Storage: Lending RewardPrograms (r:e w:e)
+	/// Storage: Lending RetiredPools (r:1 w:1)
+	/// Storage: Lending RetiredBorrows (r:e w:e)
+	/// Storage: Lending Positions (r:e w:0)
+	/// Storage: Lending Borrows (r:e w:0)
+	/// Storage: Lending StableBorrows (r:e w:0)
+	/// Storage: Lending BorrowMaturities (r:e w:0)
+	/// Storage: Lending SupplyIndexStorage (r:e w:e)
+	/// Storage: Lending UserRewards (r:e w:e)
+	/// Storage: Lending KTokenCheckpoints (r:e w:e)
+	/// Storage: Lending ReferralEarnings (r:e w:0)
+	/// Storage: Lending LpTokenPools (r:0 w:2)
+	/// Storage: Lending PoolTotals (r:1 w:1)
+	/// Storage: Lending ProtocolStats (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:2+e w:2)
+	/// Storage: System Account (r:1 w:1)
+	/// The range of component `e` is `[0, 2000]`.
+	fn replace_lending_pool(e: u32, ) -> Weight {
+		Weight::from_parts(140_000_000, 64_800)
+			.saturating_add(Weight::from_parts(42_000_000, 0).saturating_mul(e.into()))
+			.saturating_add(Weight::from_parts(0, 28_800).saturating_mul(e.into()))
+			.saturating_add(RocksDbWeight::get().reads(18_u64))
+			.saturating_add(RocksDbWeight::get().reads((11_u64).saturating_mul(e.into())))
+			.saturating_add(RocksDbWeight::get().writes(13_u64))
+			.saturating_add(RocksDbWeight::get().writes((5_u64).saturating_mul(e.into())))
+	}
+	/// Storage: Lending RetiredPools (r:a w:a)
+	/// Storage: Lending LendingPoolStorage (r:16a w:a)
+	/// Storage: Lending RetiredBorrows (r:16a w:16a)
+	/// Storage: Lending Borrows (r:32a w:16a)
+	/// Storage: Lending StableBorrows (r:16a w:0)
+	/// Storage: Lending BorrowMaturities (r:16a w:0)
+	/// Storage: Lending AccountBorrows (r:2a w:0)
+	/// Storage: Lending AssetPrices (r:16a w:0)
+	/// Storage: Lending KTokenFreezes (r:a w:0)
+	/// Storage: Lending SupplyIndexStorage (r:a w:a)
+	/// Storage: Lending RewardPrograms (r:2a w:2a)
+	/// Storage: Lending UserRewards (r:2a w:2a)
+	/// Storage: Lending PoolTotals (r:a w:a)
+	/// Storage: Lending ProtocolStats (r:a w:a)
+	/// Storage: Assets Asset (r:3a w:3a)
+	/// Storage: Assets Account (r:20a w:20a)
+	/// The range of component `a` is `[1, 16]`.
+	fn migrate_positions(a: u32, ) -> Weight {
+		Weight::from_parts(20_000_000, 3_500)
+			.saturating_add(Weight::from_parts(1_200_000_000, 0).saturating_mul(a.into()))
+			.saturating_add(Weight::from_parts(0, 464_400).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads((177_u64).saturating_mul(a.into())))
+			.saturating_add(RocksDbWeight::get().writes((78_u64).saturating_mul(a.into())))
+	}
+	/// Storage: Lending LendingPoolStorage (r:2 w:2)
+	/// Storage: Lending Borrows (r:e w:e)
+	/// Storage: Lending AccountBorrows (r:e w:e)
+	/// Storage: Lending Positions (r:e w:e)
+	/// Storage: Lending DebtAllowances (r:e w:e)
+	/// Storage: Lending SupplyIndexStorage (r:e w:e)
+	/// Storage: Lending ReferralEarnings (r:e w:e)
+	/// Storage: Lending PoolWhitelist (r:e w:e)
+	/// Storage: Lending AssetPrices (r:e w:e)
+	/// Storage: Lending PoolTotals (r:1 w:2)
+	/// Storage: Lending ProtocolStats (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// The range of component `e` is `[0, 2000]`.
+	fn redenominate_pool(e: u32, ) -> Weight {
+		Weight::from_parts(180_000_000, 144_000)
+			.saturating_add(Weight::from_parts(25_000_000, 0).saturating_mul(e.into()))
+			.saturating_add(Weight::from_parts(0, 18_000).saturating_mul(e.into()))
+			.saturating_add(RocksDbWeight::get().reads(40_u64))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(e.into())))
+			.saturating_add(RocksDbWeight::get().writes(30_u64))
+			.saturating_add(RocksDbWeight::get().writes((10_u64).saturating_mul(e.into())))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending AccountSupplyCaps (r:0 w:1)
+	fn set_account_supply_cap() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending AccountTiers (r:0 w:1)
+	fn set_account_tier() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending PoolTierTerms (r:0 w:1)
+	fn set_tier_terms() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Lending RiskParameterOverrides (r:0 w:1)
+	fn set_risk_parameter() -> Weight {
+		Weight::from_parts(15_000_000, 3_600)
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
@@ -0,0 +1,103 @@
+//! Benchmarking setup for the psm pallet
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Psm;
+use frame_benchmarking::v2::*;
+use frame_support::{
+	sp_runtime::SaturatedConversion,
+	traits::fungibles::{Create, Inspect},
+};
+use frame_system::RawOrigin;
+use lending::BenchmarkHelper;
+
+const EXTERNAL_STABLE: u32 = 4;
+const BALANCE: u128 = 1_000_000_000_000;
+
+fn asset_id<T: Config>(id: u32) -> AssetIdOf<T> {
+	T::BenchmarkHelper::asset_id(id)
+}
+
+fn units<T: Config>(balance: u128) -> BalanceOf<T> {
+	balance.saturated_into()
+}
+
+// Creates `asset` if needed, owned by the pallet
+fn create_asset<T: Config>(asset: AssetIdOf<T>)
+where
+	T::Fungibles: Create<T::AccountId>,
+{
+	if !T::Fungibles::asset_exists(asset) {
+		T::Fungibles::create(asset, Psm::<T>::account_id(), true, One::one()).unwrap();
+	}
+}
+
+// Creates the stablecoin and the external stablecoin, swapped with fees, and mints the external
+// stablecoin to `who`
+fn setup_swaps<T: Config>(who: &T::AccountId)
+where
+	T::Fungibles: Create<T::AccountId>,
+{
+	create_asset::<T>(T::StableAsset::get());
+	create_asset::<T>(asset_id::<T>(EXTERNAL_STABLE));
+	T::Fungibles::mint_into(asset_id::<T>(EXTERNAL_STABLE), who, units::<T>(BALANCE)).unwrap();
+	let fees = Fees { mint_fee: Ratio::from_parts(1_000), redeem_fee: Ratio::from_parts(1_000) };
+	AllowedAssets::<T>::insert(asset_id::<T>(EXTERNAL_STABLE), fees);
+}
+
+#[benchmarks(where T::Fungibles: Create<T::AccountId>)]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn set_asset() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let fees =
+			Fees { mint_fee: Ratio::from_parts(1_000), redeem_fee: Ratio::from_parts(1_000) };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(EXTERNAL_STABLE), Some(fees.clone()));
+
+		assert_eq!(AllowedAssets::<T>::get(asset_id::<T>(EXTERNAL_STABLE)), Some(fees));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn mint() {
+		let caller: T::AccountId = whitelisted_caller();
+		setup_swaps::<T>(&caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(EXTERNAL_STABLE),
+			units::<T>(BALANCE / 2),
+		);
+
+		assert!(!T::Fungibles::balance(T::StableAsset::get(), &caller).is_zero());
+	}
+
+	#[benchmark]
+	fn redeem() {
+		let caller: T::AccountId = whitelisted_caller();
+		setup_swaps::<T>(&caller);
+		Psm::<T>::do_mint(&caller, asset_id::<T>(EXTERNAL_STABLE), units::<T>(BALANCE / 2))
+			.unwrap();
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset_id::<T>(EXTERNAL_STABLE),
+			units::<T>(BALANCE / 4),
+		);
+
+		assert_eq!(
+			T::Fungibles::balance(asset_id::<T>(EXTERNAL_STABLE), &caller),
+			units::<T>(BALANCE / 2 + BALANCE / 4 - BALANCE / 4_000)
+		);
+	}
+
+	impl_benchmark_test_suite!(Psm, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,346 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # The PSM pallet of Kylix
+//!
+//! ## Overview
+//!
+//! The peg stability module swaps external stablecoins, e.g. USDC, at par with the stablecoin of
+//! the protocol, e.g. kUSD, whatever their decimals. The external stablecoins swapped in are
+//! held in the reserve of the pallet, out of which the swaps back are paid. Both directions
+//! take a fee in the stablecoin for the treasury.
+//!
+//! Implemented Extrinsics:
+//!
+//! 0. set_asset()
+//! 1. mint()
+//! 2. redeem()
+
+pub use frame_support::{
+	pallet_prelude::*,
+	sp_runtime::{
+		traits::{AccountIdConversion, EnsureSub, One, Zero},
+		ArithmeticError, Rounding,
+	},
+	traits::{
+		fungibles::{self, Mutate},
+		tokens::{Fortitude, Precision, Preservation},
+		Contains,
+	},
+	PalletId,
+};
+pub use frame_system::pallet_prelude::*;
+use lending::{math, Rate, Ratio};
+pub use pallet::*;
+
+/// Fungible Asset Id
+pub type AssetIdOf<T> = <T as Config>::AssetId;
+
+/// Account Type Definition
+pub type AccountOf<T> = <T as frame_system::Config>::AccountId;
+
+/// Fungible Balance
+pub type BalanceOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<AccountOf<T>>>::Balance;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+/// The fees of the swaps of an external stablecoin with the `StableAsset`, taken in the
+/// `StableAsset` for the `TreasuryAccount`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Fees {
+	/// The fee on the stablecoin minted against the external stablecoin
+	pub mint_fee: Ratio,
+	/// The fee on the stablecoin burned for the external stablecoin
+	pub redeem_fee: Ratio,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The account of the pallet, holding the reserves of the external stablecoins.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The identifier of the assets, the external stablecoins as well as the `StableAsset`.
+		type AssetId: Parameter + MaxEncodedLen + Copy;
+
+		/// Type to access the Assets Pallet, minting and burning the `StableAsset` and reading
+		/// the decimals of the assets swapped.
+		type Fungibles: fungibles::Inspect<Self::AccountId, AssetId = Self::AssetId>
+			+ fungibles::Mutate<Self::AccountId>
+			+ fungibles::metadata::Inspect<Self::AccountId>;
+
+		/// The stablecoin of the protocol, e.g. kUSD, minted against the external stablecoins.
+		/// The asset must exist, the pallet mints and burns it.
+		#[pallet::constant]
+		type StableAsset: Get<AssetIdOf<Self>>;
+
+		/// The treasury account receiving the fees of the swaps.
+		#[pallet::constant]
+		type TreasuryAccount: Get<Self::AccountId>;
+
+		/// The origin which sets the external stablecoins swapped.
+		type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Builds the asset ids of the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: lending::BenchmarkHelper<AssetIdOf<Self>>;
+	}
+
+	/// The external stablecoins swapped at par with the `StableAsset`, and the fees of their
+	/// swaps
+	#[pallet::storage]
+	pub type AllowedAssets<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Fees, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The external stablecoin `asset` is swapped with `fees`, or no longer if `None`
+		AssetSet {
+			asset: AssetIdOf<T>,
+			fees: Option<Fees>,
+		},
+		/// `who` swapped `balance` of `asset` for `minted` of the stablecoin, and paid `fee` of
+		/// the stablecoin
+		Minted {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			minted: BalanceOf<T>,
+			fee: BalanceOf<T>,
+		},
+		/// `who` swapped `balance` of the stablecoin, `fee` included, for `redeemed` of `asset`
+		Redeemed {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			redeemed: BalanceOf<T>,
+			fee: BalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The balance swapped is worth less than a unit of the asset received
+		AmountTooLow,
+		/// The asset is not swapped by the peg stability module
+		AssetNotAllowed,
+		/// The reserve holds too little of the asset
+		InsufficientReserve,
+		/// A fee is 100%
+		InvalidFee,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Lets the peg stability module swap an external stablecoin at par with the
+		/// `StableAsset`, or updates its fees. The swaps of an asset removed stop, its reserve is
+		/// left in the `account_id`.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The external stablecoin.
+		/// - `fees`: The fees of the swaps, `None` to remove the asset.
+		///
+		/// # Emits
+		/// - `AssetSet`
+		///
+		/// # Errors
+		/// - `InvalidFee`: A fee is 100%.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_asset())]
+		pub fn set_asset(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			fees: Option<Fees>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			match &fees {
+				Some(fees) => {
+					ensure!(
+						fees.mint_fee < Ratio::one() && fees.redeem_fee < Ratio::one(),
+						Error::<T>::InvalidFee
+					);
+					AllowedAssets::<T>::insert(asset, fees);
+				},
+				None => AllowedAssets::<T>::remove(asset),
+			}
+			Self::deposit_event(Event::AssetSet { asset, fees });
+			Ok(())
+		}
+
+		/// Swaps an external stablecoin for the `StableAsset` at par. The mint fee is taken out
+		/// of the stablecoin minted.
+		///
+		/// # Parameters
+		/// - `origin`: The account swapping.
+		/// - `asset`: The external stablecoin, added to the reserve.
+		/// - `balance`: The external stablecoin to swap.
+		///
+		/// # Emits
+		/// - `Minted`
+		///
+		/// # Errors
+		/// - `AssetNotAllowed`: The asset is not swapped by the peg stability module.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::mint())]
+		pub fn mint(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (minted, fee) = Self::do_mint(&who, asset, balance)?;
+			Self::deposit_event(Event::Minted { who, asset, balance, minted, fee });
+			Ok(())
+		}
+
+		/// Swaps the `StableAsset` for an external stablecoin at par, out of the reserve. The
+		/// redeem fee is taken out of the stablecoin swapped.
+		///
+		/// # Parameters
+		/// - `origin`: The account swapping.
+		/// - `asset`: The external stablecoin, paid out of the reserve.
+		/// - `balance`: The stablecoin to swap, fee included.
+		///
+		/// # Emits
+		/// - `Redeemed`
+		///
+		/// # Errors
+		/// - `AssetNotAllowed`: The asset is not swapped by the peg stability module.
+		/// - `InsufficientReserve`: The reserve holds too little of the asset.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::redeem())]
+		pub fn redeem(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (redeemed, fee) = Self::do_redeem(&who, asset, balance)?;
+			Self::deposit_event(Event::Redeemed { who, asset, balance, redeemed, fee });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding the reserves of the external stablecoins
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		/// Converts `balance` of `from` into as many units of `to` at par, whatever their
+		/// decimals
+		fn at_par(
+			balance: BalanceOf<T>,
+			from: AssetIdOf<T>,
+			to: AssetIdOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			Ok(math::convert(
+				balance,
+				<T::Fungibles as fungibles::metadata::Inspect<T::AccountId>>::decimals(from),
+				Rate::one(),
+				Rate::one(),
+				<T::Fungibles as fungibles::metadata::Inspect<T::AccountId>>::decimals(to),
+				Rounding::Down,
+			)
+			.ok_or(ArithmeticError::Overflow)?)
+		}
+
+		/// Takes `balance` of the external stablecoin `asset` of `who` into the reserve, and
+		/// mints as much of the `StableAsset` at par, the mint fee to the `TreasuryAccount` and
+		/// the rest to `who`. Returns the stablecoin minted to `who` and the fee.
+		pub fn do_mint(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+			let fees = AllowedAssets::<T>::get(asset).ok_or(Error::<T>::AssetNotAllowed)?;
+			let stable = T::StableAsset::get();
+			let minted = Self::at_par(balance, asset, stable)?;
+			ensure!(!minted.is_zero(), Error::<T>::AmountTooLow);
+			let fee = fees.mint_fee.mul_ceil(minted);
+			let received = minted.ensure_sub(fee)?;
+
+			T::Fungibles::transfer(
+				asset,
+				who,
+				&Self::account_id(),
+				balance,
+				Preservation::Preserve,
+			)?;
+			T::Fungibles::mint_into(stable, who, received)?;
+			if !fee.is_zero() {
+				T::Fungibles::mint_into(stable, &T::TreasuryAccount::get(), fee)?;
+			}
+			Ok((received, fee))
+		}
+
+		/// Burns `balance` of the `StableAsset` of `who` but the redeem fee, which goes to the
+		/// `TreasuryAccount`, and pays as much of the external stablecoin `asset` at par out of
+		/// the reserve. Returns the external stablecoin paid and the fee.
+		pub fn do_redeem(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+			let fees = AllowedAssets::<T>::get(asset).ok_or(Error::<T>::AssetNotAllowed)?;
+			let stable = T::StableAsset::get();
+			let fee = fees.redeem_fee.mul_ceil(balance);
+			let burned = balance.ensure_sub(fee)?;
+			let redeemed = Self::at_par(burned, stable, asset)?;
+			ensure!(!redeemed.is_zero(), Error::<T>::AmountTooLow);
+			ensure!(
+				T::Fungibles::balance(asset, &Self::account_id()) >= redeemed,
+				Error::<T>::InsufficientReserve
+			);
+
+			T::Fungibles::burn_from(stable, who, burned, Precision::Exact, Fortitude::Polite)?;
+			if !fee.is_zero() {
+				T::Fungibles::transfer(
+					stable,
+					who,
+					&T::TreasuryAccount::get(),
+					fee,
+					Preservation::Expendable,
+				)?;
+			}
+			T::Fungibles::transfer(
+				asset,
+				&Self::account_id(),
+				who,
+				redeemed,
+				Preservation::Expendable,
+			)?;
+			Ok((redeemed, fee))
+		}
+	}
+
+	/// The assets the peg stability module relies on: the stablecoin and the external
+	/// stablecoins swapped
+	impl<T: Config> Contains<AssetIdOf<T>> for Pallet<T> {
+		fn contains(asset: &AssetIdOf<T>) -> bool {
+			*asset == T::StableAsset::get() || AllowedAssets::<T>::contains_key(asset)
+		}
+	}
+}
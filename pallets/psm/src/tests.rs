@@ -0,0 +1,109 @@
+use crate::{mock::*, AllowedAssets, Error, Event, Fees};
+use frame_support::{assert_noop, assert_ok, traits::fungibles::Mutate};
+use sp_runtime::{
+	traits::{One, Zero},
+	Permill,
+};
+
+// Creates kUSD and USDC, minting 1 USDC to ALICE. A whole USDC is a whole kUSD, but kUSD has 6
+// more decimals.
+fn setup_assets() {
+	create_asset(KUSD);
+	create_asset(USDC);
+	assert_ok!(Assets::mint_into(USDC, &ALICE, 1_000_000));
+	for (asset, decimals) in [(USDC, 6), (KUSD, 12)] {
+		assert_ok!(Assets::force_set_metadata(
+			RuntimeOrigin::root(),
+			asset.into(),
+			b"Token".to_vec(),
+			b"TKN".to_vec(),
+			decimals,
+			false
+		));
+	}
+}
+
+#[test]
+fn external_stablecoins_are_swapped_at_par() {
+	new_test_ext().execute_with(|| {
+		setup_assets();
+		assert_noop!(
+			Psm::mint(RuntimeOrigin::signed(ALICE), USDC, 100_000),
+			Error::<Test>::AssetNotAllowed
+		);
+		let fees =
+			Fees { mint_fee: Permill::from_parts(1_000), redeem_fee: Permill::from_parts(2_000) };
+		assert_noop!(
+			Psm::set_asset(
+				RuntimeOrigin::root(),
+				USDC,
+				Some(Fees { mint_fee: Permill::one(), ..fees.clone() })
+			),
+			Error::<Test>::InvalidFee
+		);
+		assert_ok!(Psm::set_asset(RuntimeOrigin::root(), USDC, Some(fees.clone())));
+		assert_eq!(AllowedAssets::<Test>::get(USDC), Some(fees));
+
+		// 0.1 USDC mints 0.1 kUSD, 0.1% of it to the treasury
+		assert_ok!(Psm::mint(RuntimeOrigin::signed(ALICE), USDC, 100_000));
+		System::assert_last_event(
+			Event::Minted {
+				who: ALICE,
+				asset: USDC,
+				balance: 100_000,
+				minted: 99_900_000_000,
+				fee: 100_000_000,
+			}
+			.into(),
+		);
+		assert_eq!(balance(USDC, Psm::account_id()), 100_000);
+		assert_eq!(balance(KUSD, ALICE), 99_900_000_000);
+		assert_eq!(balance(KUSD, TREASURY), 100_000_000);
+
+		// the redemptions are capped by the reserve, and pay 0.2% to the treasury
+		assert_noop!(
+			Psm::redeem(RuntimeOrigin::signed(ALICE), USDC, 200_000_000_000),
+			Error::<Test>::InsufficientReserve
+		);
+		assert_ok!(Psm::redeem(RuntimeOrigin::signed(ALICE), USDC, 50_000_000_000));
+		System::assert_last_event(
+			Event::Redeemed {
+				who: ALICE,
+				asset: USDC,
+				balance: 50_000_000_000,
+				redeemed: 49_900,
+				fee: 100_000_000,
+			}
+			.into(),
+		);
+		assert_eq!(balance(USDC, ALICE), 1_000_000 - 100_000 + 49_900);
+		assert_eq!(balance(KUSD, ALICE), 49_900_000_000);
+		assert_eq!(balance(KUSD, TREASURY), 200_000_000);
+
+		// the swaps of a removed asset stop
+		assert_ok!(Psm::set_asset(RuntimeOrigin::root(), USDC, None));
+		assert_noop!(
+			Psm::redeem(RuntimeOrigin::signed(ALICE), USDC, 1_000_000_000),
+			Error::<Test>::AssetNotAllowed
+		);
+	});
+}
+
+#[test]
+fn swaps_worth_less_than_a_unit_are_rejected() {
+	new_test_ext().execute_with(|| {
+		setup_assets();
+		let fees = Fees { mint_fee: Permill::zero(), redeem_fee: Permill::zero() };
+		assert_ok!(Psm::set_asset(RuntimeOrigin::root(), USDC, Some(fees)));
+		assert_ok!(Psm::mint(RuntimeOrigin::signed(ALICE), USDC, 1_000));
+		assert_eq!(balance(KUSD, ALICE), 1_000_000_000);
+
+		// less than a millionth of a kUSD redeems for no USDC
+		assert_noop!(
+			Psm::redeem(RuntimeOrigin::signed(ALICE), USDC, 999_999),
+			Error::<Test>::AmountTooLow
+		);
+		assert_ok!(Psm::redeem(RuntimeOrigin::signed(ALICE), USDC, 1_000_000));
+		assert_eq!(balance(USDC, Psm::account_id()), 999);
+	});
+}
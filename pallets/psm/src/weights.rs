@@ -0,0 +1,90 @@
+//! Weights for the psm pallet
+//!
+//! The weights are estimated from the storage accesses of the benchmarks in `benchmarking.rs`
+//! until they are generated on the reference hardware with the command below. The proof sizes
+//! allow 3_600 bytes per storage read, the size of the proof of an entry of an `Assets` account.
+
+// Command:
+// ./target/release/kylix-node
+// benchmark
+// pallet
+// --chain
+// dev
+// --pallet
+// psm
+// --extrinsic
+// *
+// --steps=50
+// --repeat=20
+// --wasm-execution=compiled
+// --output
+// pallets/psm/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for psm.
+pub trait WeightInfo {
+	fn set_asset() -> Weight;
+	fn mint() -> Weight;
+	fn redeem() -> Weight;
+}
+
+/// Weights for psm using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Psm AllowedAssets (r:0 w:1)
+	fn set_asset() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Psm AllowedAssets (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn mint() -> Weight {
+		Weight::from_parts(55_000_000, 32_400)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Psm AllowedAssets (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn redeem() -> Weight {
+		Weight::from_parts(55_000_000, 32_400)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Psm AllowedAssets (r:0 w:1)
+	fn set_asset() -> Weight {
+		Weight::from_parts(12_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Psm AllowedAssets (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn mint() -> Weight {
+		Weight::from_parts(55_000_000, 32_400)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Psm AllowedAssets (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn redeem() -> Weight {
+		Weight::from_parts(55_000_000, 32_400)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+}
@@ -0,0 +1,87 @@
+//! Benchmarking setup for the savings pallet
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Savings;
+use frame_benchmarking::v2::*;
+use frame_support::{sp_runtime::SaturatedConversion, traits::fungibles::Create};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+const BALANCE: u128 = 1_000_000_000_000;
+
+fn units<T: Config>(balance: u128) -> BalanceOf<T> {
+	balance.saturated_into()
+}
+
+// Moves the clock forward, so that the savings rate accrues
+fn set_time<T: Config + pallet_timestamp::Config>(seconds: u32) {
+	pallet_timestamp::Pallet::<T>::set_timestamp((seconds * 1_000).into());
+}
+
+// Sets the savings rate, deposits the stablecoin of `who` into the savings, and funds the
+// revenue of the treasury paying their interest
+fn fund_savings<T: Config>(who: &T::AccountId)
+where
+	T::Fungibles: Create<T::AccountId>,
+{
+	let stable = T::StableAsset::get();
+	if !T::Fungibles::asset_exists(stable) {
+		T::Fungibles::create(stable, Savings::<T>::account_id(), true, One::one()).unwrap();
+	}
+	T::Fungibles::mint_into(stable, who, units::<T>(BALANCE)).unwrap();
+	T::Fungibles::mint_into(stable, &T::TreasuryAccount::get(), units::<T>(BALANCE)).unwrap();
+	Savings::<T>::do_set_rate(Rate::from_rational(5, 100)).unwrap();
+	Savings::<T>::do_deposit(who, units::<T>(BALANCE / 2)).unwrap();
+}
+
+#[benchmarks(where
+	T: pallet_timestamp::Config,
+	T::Fungibles: Create<T::AccountId>,
+)]
+mod benchmarks {
+	use super::*;
+
+	// the deposits accrue at the former rate out of the revenue of the treasury
+	#[benchmark]
+	fn set_rate() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let depositor: T::AccountId = account("depositor", 0, SEED);
+		fund_savings::<T>(&depositor);
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, Rate::from_rational(2, 100));
+
+		assert_eq!(Pot::<T>::get().unwrap().rate, Rate::from_rational(2, 100));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn deposit() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund_savings::<T>(&caller);
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), units::<T>(BALANCE / 4));
+
+		assert!(Deposits::<T>::get(&caller) > units::<T>(BALANCE / 4));
+	}
+
+	#[benchmark]
+	fn withdraw() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund_savings::<T>(&caller);
+		set_time::<T>(60);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), units::<T>(BALANCE / 8));
+
+		assert!(!Deposits::<T>::get(&caller).is_zero());
+	}
+
+	impl_benchmark_test_suite!(Savings, crate::mock::new_test_ext(), crate::mock::Test);
+}
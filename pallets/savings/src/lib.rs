@@ -0,0 +1,376 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # The Savings pallet of Kylix
+//!
+//! ## Overview
+//!
+//! The savings pallet pays the savings rate on the deposits of the stablecoin of the protocol,
+//! e.g. kUSD. The interest is paid out of the stablecoin revenue of the treasury, i.e. the
+//! stability fees of the CDPs and the fees of the peg stability module, and accrues no faster
+//! than the revenue covers.
+//!
+//! The savings are also a strategy of the strategy vaults of the strategies pallet, through the
+//! `Savings` trait.
+//!
+//! Implemented Extrinsics:
+//!
+//! 0. set_rate()
+//! 1. deposit()
+//! 2. withdraw()
+
+pub use frame_support::{
+	pallet_prelude::*,
+	sp_runtime::{
+		traits::{AccountIdConversion, CheckedMul, EnsureAdd, One, Zero},
+		FixedPointNumber, Rounding,
+	},
+	traits::{
+		fungibles::{self, Inspect, Mutate},
+		tokens::{Fortitude, Preservation},
+		UnixTime,
+	},
+	PalletId,
+};
+pub use frame_system::pallet_prelude::*;
+use lending::{math, Rate, Timestamp};
+pub use pallet::*;
+
+/// Fungible Asset Id
+pub type AssetIdOf<T> = <T as Config>::AssetId;
+
+/// Account Type Definition
+pub type AccountOf<T> = <T as frame_system::Config>::AccountId;
+
+/// Fungible Balance
+pub type BalanceOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<AccountOf<T>>>::Balance;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+/// The deposits of the `StableAsset` earning the savings rate, scaled by an index compounding
+/// at the rate since `last_accrued_at`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct SavingsPot<Balance> {
+	/// The annual savings rate
+	pub rate: Rate,
+	/// The index of the deposits at `last_accrued_at`
+	pub index: Rate,
+	/// The deposits, scaled by `index`
+	pub scaled_deposits: Balance,
+	pub last_accrued_at: Timestamp,
+}
+
+/// The savings as seen by the other pallets of Kylix, e.g. the strategy vaults, which deposit and
+/// withdraw the `StableAsset` on behalf of their accounts without dispatching the extrinsics.
+pub trait Savings<AccountId, Balance> {
+	/// The savings of `who`, interest included
+	fn balance(who: &AccountId) -> Balance;
+	/// Deposits `balance` of the stablecoin of `who` into the savings
+	fn deposit(who: &AccountId, balance: Balance) -> DispatchResult;
+	/// Withdraws up to `balance` of the savings of `who`, interest included. Returns the balance
+	/// withdrawn.
+	fn withdraw(who: &AccountId, balance: Balance) -> Result<Balance, DispatchError>;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The account of the pallet, holding the deposits and the interest paid on them.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The identifier of the assets.
+		type AssetId: Parameter + MaxEncodedLen + Copy;
+
+		/// Type to access the Assets Pallet, moving the `StableAsset`.
+		type Fungibles: fungibles::Inspect<Self::AccountId, AssetId = Self::AssetId>
+			+ fungibles::Mutate<Self::AccountId>;
+
+		/// The stablecoin of the protocol, e.g. kUSD, deposited into the savings.
+		#[pallet::constant]
+		type StableAsset: Get<AssetIdOf<Self>>;
+
+		/// The treasury account paying the interest out of its stablecoin revenue.
+		#[pallet::constant]
+		type TreasuryAccount: Get<Self::AccountId>;
+
+		/// The wall clock the savings rate accrues with, per second whatever the block time.
+		type Time: UnixTime;
+
+		/// The origin which sets the savings rate.
+		type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// The deposits of the `StableAsset` earning the savings rate, none until the rate is set
+	#[pallet::storage]
+	pub type Pot<T: Config> = StorageValue<_, SavingsPot<BalanceOf<T>>, OptionQuery>;
+
+	/// The deposits of the accounts, scaled by the index of the savings
+	/// AccountId => Balance
+	#[pallet::storage]
+	pub type Deposits<T: Config> =
+		StorageMap<_, Blake2_128Concat, AccountOf<T>, BalanceOf<T>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The annual savings rate was set
+		RateSet {
+			rate: Rate,
+		},
+		/// `who` deposited `balance` of the stablecoin into the savings
+		Deposited {
+			who: T::AccountId,
+			balance: BalanceOf<T>,
+		},
+		/// `who` withdrew `balance` of the stablecoin from the savings, interest included
+		Withdrawn {
+			who: T::AccountId,
+			balance: BalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The balance is zero, or worth less than a scaled unit of the savings
+		AmountTooLow,
+		/// The savings rate is not set
+		SavingsNotEnabled,
+		/// An arithmetic operation overflowed
+		OverflowError,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Sets the annual savings rate of the deposits of the `StableAsset`, after accruing them
+		/// at the former one. The interest is paid out of the stablecoin revenue of the
+		/// `TreasuryAccount`, and accrues no faster than the revenue covers.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `rate`: The annual savings rate.
+		///
+		/// # Emits
+		/// - `RateSet`
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_rate())]
+		pub fn set_rate(origin: OriginFor<T>, rate: Rate) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_set_rate(rate)?;
+			Self::deposit_event(Event::RateSet { rate });
+			Ok(())
+		}
+
+		/// Deposits the `StableAsset` of the caller into the savings, earning the savings rate.
+		///
+		/// # Parameters
+		/// - `origin`: The depositor.
+		/// - `balance`: The stablecoin to deposit.
+		///
+		/// # Emits
+		/// - `Deposited`
+		///
+		/// # Errors
+		/// - `SavingsNotEnabled`: The savings rate is not set.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::deposit())]
+		pub fn deposit(origin: OriginFor<T>, balance: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_deposit(&who, balance)?;
+			Self::deposit_event(Event::Deposited { who, balance });
+			Ok(())
+		}
+
+		/// Withdraws the savings of the caller, interest included.
+		///
+		/// # Parameters
+		/// - `origin`: The depositor.
+		/// - `balance`: The stablecoin to withdraw, capped to the savings of the caller.
+		///
+		/// # Emits
+		/// - `Withdrawn`
+		///
+		/// # Errors
+		/// - `SavingsNotEnabled`: The savings rate is not set.
+		/// - `AmountTooLow`: The caller has no savings.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::withdraw())]
+		pub fn withdraw(origin: OriginFor<T>, balance: BalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let balance = Self::do_withdraw(&who, balance)?;
+			Self::deposit_event(Event::Withdrawn { who, balance });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding the deposits of the savings and the interest paid on them
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		fn now_in_seconds() -> Timestamp {
+			T::Time::now().as_secs()
+		}
+
+		/// Accrues the savings rate on the deposits since the last accrual, paying the interest
+		/// out of the stablecoin revenue of the `TreasuryAccount`. The index grows no faster than
+		/// the revenue covers. Returns the pot updated.
+		fn accrue() -> Result<SavingsPot<BalanceOf<T>>, DispatchError> {
+			let mut pot = Pot::<T>::get().ok_or(Error::<T>::SavingsNotEnabled)?;
+			let now = Self::now_in_seconds();
+			if pot.last_accrued_at < now {
+				let deposits = math::mul_rate(pot.scaled_deposits, pot.index, Rounding::Down)
+					.ok_or(Error::<T>::OverflowError)?;
+				let growth = math::compounded_interest(pot.rate, now - pot.last_accrued_at)
+					.ok_or(Error::<T>::OverflowError)?;
+				let mut index = pot.index.checked_mul(&growth).ok_or(Error::<T>::OverflowError)?;
+				let mut interest = math::mul_rate(pot.scaled_deposits, index, Rounding::Down)
+					.ok_or(Error::<T>::OverflowError)?
+					.saturating_sub(deposits);
+
+				let treasury = T::TreasuryAccount::get();
+				let revenue = T::Fungibles::reducible_balance(
+					T::StableAsset::get(),
+					&treasury,
+					Preservation::Expendable,
+					Fortitude::Polite,
+				);
+				if interest > revenue {
+					interest = revenue;
+					index = Rate::checked_from_rational(
+						deposits.ensure_add(interest)?,
+						pot.scaled_deposits,
+					)
+					.ok_or(Error::<T>::OverflowError)?
+					.max(pot.index);
+				}
+				if !interest.is_zero() {
+					T::Fungibles::transfer(
+						T::StableAsset::get(),
+						&treasury,
+						&Self::account_id(),
+						interest,
+						Preservation::Expendable,
+					)?;
+				}
+				pot.index = index;
+				pot.last_accrued_at = now;
+				Pot::<T>::put(&pot);
+			}
+			Ok(pot)
+		}
+
+		/// Sets the savings rate, after accruing the deposits at the former one
+		pub fn do_set_rate(rate: Rate) -> DispatchResult {
+			let pot = match Pot::<T>::exists() {
+				true => SavingsPot { rate, ..Self::accrue()? },
+				false => SavingsPot {
+					rate,
+					index: Rate::one(),
+					scaled_deposits: Zero::zero(),
+					last_accrued_at: Self::now_in_seconds(),
+				},
+			};
+			Pot::<T>::put(pot);
+			Ok(())
+		}
+
+		/// Deposits `balance` of the `StableAsset` of `who` into the savings
+		pub fn do_deposit(who: &T::AccountId, balance: BalanceOf<T>) -> DispatchResult {
+			let mut pot = Self::accrue()?;
+			let scaled_balance = math::div_rate(balance, pot.index, Rounding::Down)
+				.ok_or(Error::<T>::OverflowError)?;
+			ensure!(!scaled_balance.is_zero(), Error::<T>::AmountTooLow);
+
+			T::Fungibles::transfer(
+				T::StableAsset::get(),
+				who,
+				&Self::account_id(),
+				balance,
+				Preservation::Preserve,
+			)?;
+			Deposits::<T>::try_mutate(who, |scaled| -> DispatchResult {
+				*scaled = scaled.ensure_add(scaled_balance)?;
+				Ok(())
+			})?;
+			pot.scaled_deposits = pot.scaled_deposits.ensure_add(scaled_balance)?;
+			Pot::<T>::put(pot);
+			Ok(())
+		}
+
+		/// Withdraws up to `balance` of the savings of `who`, interest included. Returns the
+		/// balance withdrawn.
+		pub fn do_withdraw(
+			who: &T::AccountId,
+			balance: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+			let mut pot = Self::accrue()?;
+			let scaled = Deposits::<T>::get(who);
+			let savings = math::mul_rate(scaled, pot.index, Rounding::Down)
+				.ok_or(Error::<T>::OverflowError)?;
+			let balance = balance.min(savings);
+			ensure!(!balance.is_zero(), Error::<T>::AmountTooLow);
+			let scaled_balance = match balance == savings {
+				true => scaled,
+				false => math::div_rate(balance, pot.index, Rounding::Up)
+					.ok_or(Error::<T>::OverflowError)?
+					.min(scaled),
+			};
+
+			T::Fungibles::transfer(
+				T::StableAsset::get(),
+				&Self::account_id(),
+				who,
+				balance,
+				Preservation::Expendable,
+			)?;
+			match scaled_balance == scaled {
+				true => Deposits::<T>::remove(who),
+				false => Deposits::<T>::insert(who, scaled.saturating_sub(scaled_balance)),
+			}
+			pot.scaled_deposits = pot.scaled_deposits.saturating_sub(scaled_balance);
+			Pot::<T>::put(pot);
+			Ok(balance)
+		}
+	}
+
+	impl<T: Config> Savings<T::AccountId, BalanceOf<T>> for Pallet<T> {
+		fn balance(who: &T::AccountId) -> BalanceOf<T> {
+			Pot::<T>::get()
+				.and_then(|pot| math::mul_rate(Deposits::<T>::get(who), pot.index, Rounding::Down))
+				.unwrap_or_else(Zero::zero)
+		}
+
+		fn deposit(who: &T::AccountId, balance: BalanceOf<T>) -> DispatchResult {
+			Self::do_deposit(who, balance)
+		}
+
+		fn withdraw(
+			who: &T::AccountId,
+			balance: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			Self::do_withdraw(who, balance)
+		}
+	}
+}
@@ -0,0 +1,71 @@
+use crate::{mock::*, Deposits, Error, Event, Pot};
+use frame_support::{assert_noop, assert_ok, traits::fungibles::Mutate};
+use lending::SECONDS_PER_YEAR;
+use sp_runtime::{traits::BadOrigin, FixedPointNumber, FixedU128};
+
+// Creates kUSD, minting 1_000_000 to ALICE and 100_000 of revenue to the treasury
+fn setup_stable() {
+	create_asset(KUSD);
+	assert_ok!(Assets::mint_into(KUSD, &ALICE, 1_000_000));
+	assert_ok!(Assets::mint_into(KUSD, &TREASURY, 100_000));
+}
+
+#[test]
+fn savings_accrue_at_the_savings_rate_within_the_revenue_of_the_treasury() {
+	new_test_ext().execute_with(|| {
+		setup_stable();
+		assert_noop!(
+			Savings::deposit(RuntimeOrigin::signed(ALICE), 500_000),
+			Error::<Test>::SavingsNotEnabled
+		);
+		assert_noop!(
+			Savings::set_rate(RuntimeOrigin::signed(ALICE), FixedU128::from(1)),
+			BadOrigin
+		);
+		assert_ok!(Savings::set_rate(RuntimeOrigin::root(), FixedU128::from_rational(10, 100)));
+		assert_ok!(Savings::deposit(RuntimeOrigin::signed(ALICE), 500_000));
+		System::assert_last_event(Event::Deposited { who: ALICE, balance: 500_000 }.into());
+		assert_eq!(Deposits::<Test>::get(ALICE), 500_000);
+		assert_eq!(balance(KUSD, Savings::account_id()), 500_000);
+
+		// a year at 10% compounded, paid by the treasury
+		Timestamp::set_timestamp(SECONDS_PER_YEAR * 1_000);
+		assert_ok!(Savings::withdraw(RuntimeOrigin::signed(ALICE), 100_000));
+		let interest = 100_000 - balance(KUSD, TREASURY);
+		assert!(interest > 52_500 && interest < 52_600);
+		assert_eq!(balance(KUSD, ALICE), 600_000);
+		assert_eq!(Pot::<Test>::get().unwrap().last_accrued_at, SECONDS_PER_YEAR);
+
+		// another year would pay more than the treasury holds, the savings earn what it holds
+		Timestamp::set_timestamp(2 * SECONDS_PER_YEAR * 1_000);
+		assert_ok!(Savings::withdraw(RuntimeOrigin::signed(ALICE), 1_000_000));
+		assert_eq!(balance(KUSD, TREASURY), 0);
+		assert!(balance(KUSD, ALICE) >= 1_099_990);
+		assert!(!Deposits::<Test>::contains_key(ALICE));
+		assert_noop!(
+			Savings::withdraw(RuntimeOrigin::signed(ALICE), 1_000_000),
+			Error::<Test>::AmountTooLow
+		);
+	});
+}
+
+#[test]
+fn the_strategy_vaults_deposit_into_the_savings_through_the_savings_trait() {
+	new_test_ext().execute_with(|| {
+		setup_stable();
+		assert_eq!(<Savings as crate::Savings<_, _>>::balance(&ALICE), 0);
+		assert_ok!(Savings::set_rate(RuntimeOrigin::root(), FixedU128::from_rational(10, 100)));
+		assert_ok!(<Savings as crate::Savings<_, _>>::deposit(&ALICE, 500_000));
+
+		// the balance counts the interest accrued up to the last accrual
+		Timestamp::set_timestamp(SECONDS_PER_YEAR * 1_000);
+		assert_eq!(<Savings as crate::Savings<_, _>>::balance(&ALICE), 500_000);
+		assert_ok!(Savings::set_rate(RuntimeOrigin::root(), FixedU128::from_rational(10, 100)));
+		let savings = <Savings as crate::Savings<_, _>>::balance(&ALICE);
+		assert!(savings > 552_500 && savings < 552_600);
+
+		// the withdrawals are capped to the savings
+		assert_eq!(<Savings as crate::Savings<_, _>>::withdraw(&ALICE, 1_000_000), Ok(savings));
+		assert_eq!(balance(KUSD, ALICE), 500_000 + savings);
+	});
+}
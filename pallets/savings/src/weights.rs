@@ -0,0 +1,96 @@
+//! Weights for the savings pallet
+//!
+//! The weights are estimated from the storage accesses of the benchmarks in `benchmarking.rs`
+//! until they are generated on the reference hardware with the command below. The proof sizes
+//! allow 3_600 bytes per storage read, the size of the proof of an entry of an `Assets` account.
+
+// Command:
+// ./target/release/kylix-node
+// benchmark
+// pallet
+// --chain
+// dev
+// --pallet
+// savings
+// --extrinsic
+// *
+// --steps=50
+// --repeat=20
+// --wasm-execution=compiled
+// --output
+// pallets/savings/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for savings.
+pub trait WeightInfo {
+	fn set_rate() -> Weight;
+	fn deposit() -> Weight;
+	fn withdraw() -> Weight;
+}
+
+/// Weights for savings using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Savings Pot (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn set_rate() -> Weight {
+		Weight::from_parts(30_000_000, 14_400)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Savings Pot (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Savings Deposits (r:1 w:1)
+	fn deposit() -> Weight {
+		Weight::from_parts(45_000_000, 21_600)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Savings Pot (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Savings Deposits (r:1 w:1)
+	fn withdraw() -> Weight {
+		Weight::from_parts(45_000_000, 21_600)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Savings Pot (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn set_rate() -> Weight {
+		Weight::from_parts(30_000_000, 14_400)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: Savings Pot (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Savings Deposits (r:1 w:1)
+	fn deposit() -> Weight {
+		Weight::from_parts(45_000_000, 21_600)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Savings Pot (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:3 w:3)
+	/// Storage: Savings Deposits (r:1 w:1)
+	fn withdraw() -> Weight {
+		Weight::from_parts(45_000_000, 21_600)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+}
@@ -0,0 +1,105 @@
+//! Benchmarking setup for the strategies pallet
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Strategies;
+use frame_benchmarking::v2::*;
+use frame_support::sp_runtime::SaturatedConversion;
+use frame_system::RawOrigin;
+use lending::{BenchmarkHelper, LendingBenchmarkHelper};
+
+const SEED: u32 = 0;
+const ASSET: u32 = 1;
+const POOL_ID: u32 = 1_000;
+const BALANCE: u128 = 1_000_000_000_000;
+
+fn asset_id<T: Config>(id: u32) -> AssetIdOf<T> {
+	T::BenchmarkHelper::asset_id(id)
+}
+
+fn units<T: Config>(balance: u128) -> BalanceOf<T> {
+	balance.saturated_into()
+}
+
+// Moves the clock forward, so that the interest of the lending pool accrues
+fn set_time<T: Config + pallet_timestamp::Config>(seconds: u32) {
+	pallet_timestamp::Pallet::<T>::set_timestamp((seconds * 1_000).into());
+}
+
+// Creates the lending pool of `asset_id::<T>(ASSET)` if needed
+fn create_pool<T: Config>()
+where
+	T::Lending: LendingBenchmarkHelper<AssetIdOf<T>>,
+{
+	if T::Lending::lp_token(asset_id::<T>(ASSET)).is_none() {
+		T::Lending::create_pool(asset_id::<T>(POOL_ID), asset_id::<T>(ASSET));
+	}
+}
+
+// Opens the vault of `asset_id::<T>(ASSET)` allocating to its lending pool, and deposits the
+// assets of `who` into it, rebalanced
+fn open_vault<T: Config>(who: &T::AccountId)
+where
+	T::Lending: LendingBenchmarkHelper<AssetIdOf<T>>,
+{
+	create_pool::<T>();
+	let weights =
+		BoundedVec::truncate_from([(Strategy::LendingPool, Ratio::from_percent(90))].to_vec());
+	Strategies::<T>::do_set_weights(asset_id::<T>(ASSET), weights).unwrap();
+	T::Fungibles::mint_into(asset_id::<T>(ASSET), who, units::<T>(BALANCE)).unwrap();
+	Strategies::<T>::do_deposit(who, asset_id::<T>(ASSET), units::<T>(BALANCE / 2)).unwrap();
+	Strategies::<T>::rebalance_vault(asset_id::<T>(ASSET)).unwrap();
+}
+
+#[benchmarks(where
+	T: pallet_timestamp::Config,
+	T::Lending: LendingBenchmarkHelper<AssetIdOf<T>>,
+)]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn set_weights() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		create_pool::<T>();
+		let weights =
+			BoundedVec::truncate_from([(Strategy::LendingPool, Ratio::from_percent(80))].to_vec());
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset_id::<T>(ASSET), weights.clone());
+
+		assert_eq!(Vaults::<T>::get(asset_id::<T>(ASSET)).unwrap().weights, weights);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn deposit() {
+		let caller: T::AccountId = whitelisted_caller();
+		open_vault::<T>(&account("depositor", 0, SEED));
+		T::Fungibles::mint_into(asset_id::<T>(ASSET), &caller, units::<T>(BALANCE)).unwrap();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), units::<T>(BALANCE / 4));
+
+		assert!(!Shares::<T>::get(&caller, asset_id::<T>(ASSET)).is_zero());
+	}
+
+	// the vault withdraws from the lending pool, its idle assets do not cover the withdrawal
+	#[benchmark]
+	fn withdraw() {
+		let caller: T::AccountId = whitelisted_caller();
+		open_vault::<T>(&caller);
+		frame_system::Pallet::<T>::set_block_number(10u32.into());
+		set_time::<T>(60);
+		let shares = Shares::<T>::get(&caller, asset_id::<T>(ASSET));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), asset_id::<T>(ASSET), shares);
+
+		assert!(!Shares::<T>::contains_key(&caller, asset_id::<T>(ASSET)));
+	}
+
+	impl_benchmark_test_suite!(Strategies, crate::mock::new_test_ext(), crate::mock::Test);
+}
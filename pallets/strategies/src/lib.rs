@@ -0,0 +1,559 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # The Strategies pallet of Kylix
+//!
+//! ## Overview
+//!
+//! The strategies pallet runs the strategy vaults, which take the deposits of an asset for
+//! shares of the vault and allocate them across the strategies by weight: the lending pool of
+//! the asset and, for the stablecoin of the protocol only, the savings. The rest of the deposits
+//! is kept idle for the withdrawals. The vaults are rebalanced towards their weights by
+//! `on_idle`.
+//!
+//! The pallet builds on the money market through the `Lending` trait of the lending pallet, and
+//! on the savings through the `Savings` trait of the savings pallet.
+//!
+//! Implemented Extrinsics:
+//!
+//! 0. set_weights()
+//! 1. deposit()
+//! 2. withdraw()
+
+pub use frame_support::{
+	pallet_prelude::*,
+	sp_runtime::{
+		traits::{AccountIdConversion, CheckedAdd, EnsureAdd, One, Zero},
+		Rounding,
+	},
+	storage::with_storage_layer,
+	traits::{
+		fungibles::{self, Inspect, Mutate},
+		tokens::{Fortitude, Preservation},
+		Contains,
+	},
+	PalletId,
+};
+pub use frame_system::pallet_prelude::*;
+use lending::{math, Lending, Ratio};
+use savings::Savings;
+pub use pallet::*;
+
+/// Fungible Asset Id
+pub type AssetIdOf<T> = <T as Config>::AssetId;
+
+/// Account Type Definition
+pub type AccountOf<T> = <T as frame_system::Config>::AccountId;
+
+/// Fungible Balance
+pub type BalanceOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<AccountOf<T>>>::Balance;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+/// The maximum number of strategies a strategy vault allocates to
+pub const MAX_STRATEGIES: u32 = 4;
+
+/// A destination of the assets of a strategy vault
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum Strategy {
+	/// Supplied to the lending pool of the asset of the vault
+	LendingPool,
+	/// Deposited into the savings, for the vault of the `StableAsset` only
+	Savings,
+}
+
+impl Strategy {
+	/// All the strategies, the assets of a vault are valued and rebalanced across all of them,
+	/// so that a strategy dropped from its weights is emptied
+	pub const ALL: [Strategy; 2] = [Strategy::LendingPool, Strategy::Savings];
+}
+
+/// A vault allocating the deposits of an asset across the strategies by weight
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct StrategyVault<Balance> {
+	/// The share of the assets of the vault allocated to each strategy, the rest is kept idle
+	/// for the withdrawals
+	pub weights: BoundedVec<(Strategy, Ratio), ConstU32<MAX_STRATEGIES>>,
+	/// The shares of the vault issued to the depositors
+	pub total_shares: Balance,
+}
+
+impl<Balance> StrategyVault<Balance> {
+	/// The weight of `strategy`, zero if the vault does not allocate to it
+	pub fn weight(&self, strategy: Strategy) -> Ratio {
+		self.weights
+			.iter()
+			.find(|(s, _)| *s == strategy)
+			.map_or(Ratio::zero(), |(_, w)| *w)
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The account of the pallet, the vaults hold their assets in sub-accounts of it.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The identifier of the assets.
+		type AssetId: Parameter + MaxEncodedLen + Copy;
+
+		/// Type to access the Assets Pallet, moving the deposits of the vaults.
+		type Fungibles: fungibles::Inspect<Self::AccountId, AssetId = Self::AssetId>
+			+ fungibles::Mutate<Self::AccountId>;
+
+		/// The money market whose lending pools the vaults supply to, e.g. the lending pallet.
+		type Lending: Lending<Self::AccountId, AssetIdOf<Self>, BalanceOf<Self>>;
+
+		/// The savings of the `StableAsset` its vault deposits into, e.g. the savings pallet.
+		type Savings: Savings<Self::AccountId, BalanceOf<Self>>;
+
+		/// The stablecoin of the protocol, e.g. kUSD, the only asset whose vault may deposit
+		/// into the `Savings`.
+		#[pallet::constant]
+		type StableAsset: Get<AssetIdOf<Self>>;
+
+		/// The origin which sets the weights of the vaults.
+		type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Builds the asset ids of the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: lending::BenchmarkHelper<AssetIdOf<Self>>;
+	}
+
+	/// The strategy vaults, by asset
+	#[pallet::storage]
+	pub type Vaults<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, StrategyVault<BalanceOf<T>>, OptionQuery>;
+
+	/// The shares of the accounts in the strategy vaults
+	/// AccountId, asset_id => Balance
+	#[pallet::storage]
+	pub type Shares<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AccountOf<T>,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// The last vault rebalanced by `on_idle`, the next rebalancing carries on after it
+	#[pallet::storage]
+	pub type RebalanceCursor<T: Config> = StorageValue<_, AssetIdOf<T>, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The vault of `asset` allocates to the strategies by `weights`
+		WeightsSet {
+			asset: AssetIdOf<T>,
+			weights: BoundedVec<(Strategy, Ratio), ConstU32<MAX_STRATEGIES>>,
+		},
+		/// `who` deposited `balance` of `asset` into its vault for `shares`
+		Deposited {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			shares: BalanceOf<T>,
+		},
+		/// `who` redeemed `shares` of the vault of `asset` for `balance`
+		Withdrawn {
+			who: T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+			shares: BalanceOf<T>,
+		},
+		/// The vault of `asset`, worth `value`, was rebalanced towards its weights
+		Rebalanced {
+			asset: AssetIdOf<T>,
+			value: BalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The balance is zero, or worth less than a share of the vault
+		AmountTooLow,
+		/// The weights repeat a strategy or add up to more than 100%
+		InvalidWeights,
+		/// The strategy does not take the asset
+		StrategyNotAllowed,
+		/// The asset has no lending pool to supply
+		LendingPoolDoesNotExist,
+		/// There is no vault of the asset
+		VaultDoesNotExist,
+		/// The account holds fewer shares of the vault
+		NotEnoughShares,
+		/// The strategies can not return enough of the asset
+		InsufficientLiquidity,
+		/// An arithmetic operation overflowed
+		OverflowError,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(_block_number: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::rebalance_vaults(remaining_weight)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Sets the weights of the strategies the vault of an asset allocates its deposits to,
+		/// creating the vault if needed. The rest of the deposits is kept idle for the
+		/// withdrawals. The vaults are rebalanced towards their weights by `on_idle`.
+		///
+		/// # Parameters
+		/// - `origin`: Must be `ManagerOrigin`.
+		/// - `asset`: The asset of the vault.
+		/// - `weights`: The share of the deposits allocated to each strategy.
+		///
+		/// # Emits
+		/// - `WeightsSet`
+		///
+		/// # Errors
+		/// - `InvalidWeights`: A strategy repeats or the weights exceed 100%.
+		/// - `LendingPoolDoesNotExist`: The asset has no lending pool to supply.
+		/// - `StrategyNotAllowed`: The savings only take the `StableAsset`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_weights())]
+		pub fn set_weights(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			weights: BoundedVec<(Strategy, Ratio), ConstU32<MAX_STRATEGIES>>,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Self::do_set_weights(asset, weights.clone())?;
+			Self::deposit_event(Event::WeightsSet { asset, weights });
+			Ok(())
+		}
+
+		/// Deposits an asset into its vault for shares of the vault. The deposit stays idle
+		/// until the next rebalancing.
+		///
+		/// # Parameters
+		/// - `origin`: The depositor.
+		/// - `asset`: The asset of the vault.
+		/// - `balance`: The asset to deposit.
+		///
+		/// # Emits
+		/// - `Deposited`
+		///
+		/// # Errors
+		/// - `VaultDoesNotExist`: There is no vault of the asset.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::deposit())]
+		pub fn deposit(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let shares = Self::do_deposit(&who, asset, balance)?;
+			Self::deposit_event(Event::Deposited { who, asset, balance, shares });
+			Ok(())
+		}
+
+		/// Redeems shares of a vault for their value, taken from the idle assets of the vault
+		/// first, then from its strategies.
+		///
+		/// # Parameters
+		/// - `origin`: The depositor.
+		/// - `asset`: The asset of the vault.
+		/// - `shares`: The shares to redeem.
+		///
+		/// # Emits
+		/// - `Withdrawn`
+		///
+		/// # Errors
+		/// - `VaultDoesNotExist`: There is no vault of the asset.
+		/// - `NotEnoughShares`: The caller holds fewer shares.
+		/// - `InsufficientLiquidity`: The strategies can not return enough of the asset.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::withdraw())]
+		pub fn withdraw(
+			origin: OriginFor<T>,
+			asset: AssetIdOf<T>,
+			shares: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let balance = Self::do_withdraw(&who, asset, shares)?;
+			Self::deposit_event(Event::Withdrawn { who, asset, balance, shares });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account holding the assets of the vault of `asset`
+		pub fn vault_account_id(asset: AssetIdOf<T>) -> T::AccountId {
+			T::PalletId::get().into_sub_account_truncating(asset)
+		}
+
+		/// The assets of `account` allocated to `strategy`, in `asset`
+		fn strategy_balance(
+			asset: AssetIdOf<T>,
+			strategy: Strategy,
+			account: &T::AccountId,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			Ok(match strategy {
+				Strategy::LendingPool => match T::Lending::lp_token(asset) {
+					Some(lp_token) => T::Lending::redeemable_balance(
+						asset,
+						T::Fungibles::balance(lp_token, account),
+					)?,
+					None => Zero::zero(),
+				},
+				Strategy::Savings => T::Savings::balance(account),
+			})
+		}
+
+		/// The assets of the vault of `asset`, idle and allocated to the strategies
+		pub fn vault_value(asset: AssetIdOf<T>) -> Result<BalanceOf<T>, DispatchError> {
+			let account = Self::vault_account_id(asset);
+			Strategy::ALL
+				.into_iter()
+				.try_fold(T::Fungibles::balance(asset, &account), |value, s| {
+					Ok(value.ensure_add(Self::strategy_balance(asset, s, &account)?)?)
+				})
+		}
+
+		/// Moves `balance` of the idle assets of `account` to `strategy`
+		fn allocate(
+			asset: AssetIdOf<T>,
+			strategy: Strategy,
+			account: &T::AccountId,
+			balance: BalanceOf<T>,
+		) -> DispatchResult {
+			match strategy {
+				Strategy::LendingPool => T::Lending::supply(account, asset, balance),
+				Strategy::Savings => T::Savings::deposit(account, balance),
+			}
+		}
+
+		/// Moves up to `balance` of the assets of `account` allocated to `strategy` back to
+		/// idle. Returns the balance moved.
+		fn deallocate(
+			asset: AssetIdOf<T>,
+			strategy: Strategy,
+			account: &T::AccountId,
+			balance: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			match strategy {
+				Strategy::LendingPool => T::Lending::withdraw(account, asset, balance),
+				Strategy::Savings => T::Savings::withdraw(account, balance),
+			}
+		}
+
+		/// Sets the weights of the vault of `asset`, creating the vault if needed. The weights
+		/// can not add up to more than 100%, and the savings only take the `StableAsset`.
+		pub fn do_set_weights(
+			asset: AssetIdOf<T>,
+			weights: BoundedVec<(Strategy, Ratio), ConstU32<MAX_STRATEGIES>>,
+		) -> DispatchResult {
+			let mut total = Ratio::zero();
+			for (i, (strategy, weight)) in weights.iter().enumerate() {
+				ensure!(
+					weights.iter().skip(i + 1).all(|(s, _)| s != strategy),
+					Error::<T>::InvalidWeights
+				);
+				total = total.checked_add(weight).ok_or(Error::<T>::InvalidWeights)?;
+				match strategy {
+					Strategy::LendingPool => ensure!(
+						T::Lending::lp_token(asset).is_some(),
+						Error::<T>::LendingPoolDoesNotExist
+					),
+					Strategy::Savings =>
+						ensure!(asset == T::StableAsset::get(), Error::<T>::StrategyNotAllowed),
+				}
+			}
+			Vaults::<T>::mutate(asset, |vault| match vault {
+				Some(vault) => vault.weights = weights,
+				None => *vault = Some(StrategyVault { weights, total_shares: Zero::zero() }),
+			});
+			Ok(())
+		}
+
+		/// Deposits `balance` of `asset` of `who` into the vault of `asset`, idle until the next
+		/// rebalancing. The shares are issued at the value of the vault, offset by a unit so that
+		/// no donation inflates them. Returns the shares issued.
+		pub fn do_deposit(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			balance: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let mut vault = Vaults::<T>::get(asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+			let value = Self::vault_value(asset)?;
+			let shares = math::mul_div(
+				balance,
+				vault.total_shares.saturating_add(One::one()),
+				value.saturating_add(One::one()),
+				Rounding::Down,
+			)
+			.ok_or(Error::<T>::OverflowError)?;
+			ensure!(!shares.is_zero(), Error::<T>::AmountTooLow);
+
+			T::Fungibles::transfer(
+				asset,
+				who,
+				&Self::vault_account_id(asset),
+				balance,
+				Preservation::Preserve,
+			)?;
+			Shares::<T>::try_mutate(who, asset, |owned| -> DispatchResult {
+				*owned = owned.ensure_add(shares)?;
+				Ok(())
+			})?;
+			vault.total_shares = vault.total_shares.ensure_add(shares)?;
+			Vaults::<T>::insert(asset, vault);
+			Ok(shares)
+		}
+
+		/// Redeems `shares` of `who` in the vault of `asset` for their value, taken from the
+		/// idle assets first, then from the strategies. Returns the balance paid.
+		pub fn do_withdraw(
+			who: &T::AccountId,
+			asset: AssetIdOf<T>,
+			shares: BalanceOf<T>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			ensure!(!shares.is_zero(), Error::<T>::AmountTooLow);
+			let mut vault = Vaults::<T>::get(asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+			let owned = Shares::<T>::get(who, asset);
+			ensure!(owned >= shares, Error::<T>::NotEnoughShares);
+			let value = Self::vault_value(asset)?;
+			let balance = math::mul_div(
+				shares,
+				value.saturating_add(One::one()),
+				vault.total_shares.saturating_add(One::one()),
+				Rounding::Down,
+			)
+			.ok_or(Error::<T>::OverflowError)?;
+
+			let account = Self::vault_account_id(asset);
+			for strategy in Strategy::ALL {
+				let idle = T::Fungibles::balance(asset, &account);
+				if idle >= balance {
+					break;
+				}
+				let allocated = Self::strategy_balance(asset, strategy, &account)?;
+				let missing = balance.saturating_sub(idle).min(allocated);
+				if !missing.is_zero() {
+					Self::deallocate(asset, strategy, &account, missing)?;
+				}
+			}
+			ensure!(
+				T::Fungibles::balance(asset, &account) >= balance,
+				Error::<T>::InsufficientLiquidity
+			);
+
+			T::Fungibles::transfer(asset, &account, who, balance, Preservation::Expendable)?;
+			match owned == shares {
+				true => Shares::<T>::remove(who, asset),
+				false => Shares::<T>::insert(who, asset, owned.saturating_sub(shares)),
+			}
+			vault.total_shares = vault.total_shares.saturating_sub(shares);
+			Vaults::<T>::insert(asset, vault);
+			Ok(balance)
+		}
+
+		/// Moves the assets of the vault of `asset` towards its weights: the strategies over
+		/// their weight are drawn down first, then those under their weight are topped up out of
+		/// the idle assets. Returns the value of the vault.
+		pub fn rebalance_vault(asset: AssetIdOf<T>) -> Result<BalanceOf<T>, DispatchError> {
+			let vault = Vaults::<T>::get(asset).ok_or(Error::<T>::VaultDoesNotExist)?;
+			let account = Self::vault_account_id(asset);
+			let value = Self::vault_value(asset)?;
+
+			for strategy in Strategy::ALL {
+				let allocated = Self::strategy_balance(asset, strategy, &account)?;
+				let target = vault.weight(strategy).mul_floor(value);
+				if allocated > target {
+					Self::deallocate(asset, strategy, &account, allocated.saturating_sub(target))?;
+				}
+			}
+			for strategy in Strategy::ALL {
+				let allocated = Self::strategy_balance(asset, strategy, &account)?;
+				let target = vault.weight(strategy).mul_floor(value);
+				let idle = T::Fungibles::reducible_balance(
+					asset,
+					&account,
+					Preservation::Preserve,
+					Fortitude::Polite,
+				);
+				let balance = target.saturating_sub(allocated).min(idle);
+				if !balance.is_zero() {
+					Self::allocate(asset, strategy, &account, balance)?;
+				}
+			}
+			Ok(value)
+		}
+
+		/// Rebalances the vaults from the `RebalanceCursor` on, within the `remaining_weight`.
+		/// Returns the consumed weight.
+		pub fn rebalance_vaults(remaining_weight: Weight) -> Weight {
+			let per_vault = T::DbWeight::get().reads_writes(24, 16);
+			let mut consumed = T::DbWeight::get().reads_writes(1, 1);
+			if remaining_weight.any_lt(consumed.saturating_add(per_vault)) {
+				return Weight::zero();
+			}
+
+			// carry on after the cursor, the rebalancing starts over once the cursor reached the
+			// end
+			let vaults = match RebalanceCursor::<T>::get() {
+				Some(asset) => Vaults::<T>::iter_keys_from(Vaults::<T>::hashed_key_for(asset)),
+				None => Vaults::<T>::iter_keys(),
+			};
+
+			let mut last = None;
+			for asset in vaults {
+				if remaining_weight.any_lt(consumed.saturating_add(per_vault)) {
+					break;
+				}
+				consumed.saturating_accrue(per_vault);
+				match with_storage_layer(|| Self::rebalance_vault(asset)) {
+					Ok(value) => Self::deposit_event(Event::Rebalanced { asset, value }),
+					Err(e) => log::warn!(
+						target: "runtime::strategies",
+						"failed to rebalance a vault: {:?}",
+						e
+					),
+				}
+				last = Some(asset);
+			}
+
+			match last {
+				Some(asset) => RebalanceCursor::<T>::put(asset),
+				None => RebalanceCursor::<T>::kill(),
+			}
+			consumed
+		}
+	}
+
+	/// The assets the strategy vaults rely on beyond their lending pools: the assets of the
+	/// vaults
+	impl<T: Config> Contains<AssetIdOf<T>> for Pallet<T> {
+		fn contains(asset: &AssetIdOf<T>) -> bool {
+			Vaults::<T>::contains_key(asset)
+		}
+	}
+}
@@ -0,0 +1,122 @@
+use crate::{mock::*, Error, Event, Shares, Strategy};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{fungibles::Mutate, Hooks},
+	weights::Weight,
+	BoundedVec,
+};
+use lending::SECONDS_PER_YEAR;
+use sp_runtime::{FixedPointNumber, FixedU128, Permill};
+
+#[test]
+fn strategy_vaults_allocate_the_deposits_by_weight_in_on_idle() {
+	new_test_ext().execute_with(|| {
+		create_asset(DOT);
+		MockLending::create_pool(DOT_LP, DOT);
+		assert_ok!(Assets::mint_into(DOT, &BOB, 1_000_000));
+		let vault = Strategies::vault_account_id(DOT);
+		assert_noop!(
+			Strategies::deposit(RuntimeOrigin::signed(BOB), DOT, 100_000),
+			Error::<Test>::VaultDoesNotExist
+		);
+
+		// the savings only take the stablecoin, and the weights add up to 100% at most
+		for (asset, weights, error) in [
+			(
+				DOT,
+				vec![(Strategy::Savings, Permill::from_percent(50))],
+				Error::<Test>::StrategyNotAllowed,
+			),
+			(
+				DOT,
+				vec![
+					(Strategy::LendingPool, Permill::from_percent(50)),
+					(Strategy::LendingPool, Permill::from_percent(10)),
+				],
+				Error::<Test>::InvalidWeights,
+			),
+			(
+				KUSD,
+				vec![(Strategy::LendingPool, Permill::from_percent(50))],
+				Error::<Test>::LendingPoolDoesNotExist,
+			),
+		] {
+			assert_noop!(
+				Strategies::set_weights(
+					RuntimeOrigin::root(),
+					asset,
+					BoundedVec::truncate_from(weights)
+				),
+				error
+			);
+		}
+		assert_ok!(Strategies::set_weights(
+			RuntimeOrigin::root(),
+			DOT,
+			BoundedVec::truncate_from(vec![(Strategy::LendingPool, Permill::from_percent(80))])
+		));
+
+		// the deposit stays idle until on_idle supplies 80% of it to the lending pool
+		assert_ok!(Strategies::deposit(RuntimeOrigin::signed(BOB), DOT, 100_000));
+		assert_eq!(Shares::<Test>::get(BOB, DOT), 100_000);
+		assert_eq!(balance(DOT, vault), 100_000);
+		Strategies::on_idle(1, Weight::MAX);
+		System::assert_has_event(Event::Rebalanced { asset: DOT, value: 100_000 }.into());
+		assert_eq!(balance(DOT_LP, vault), 80_000);
+		assert_eq!(balance(DOT, vault), 20_000);
+
+		// the withdrawals are paid from the idle assets first, then from the lending pool
+		assert_noop!(
+			Strategies::withdraw(RuntimeOrigin::signed(BOB), DOT, 100_001),
+			Error::<Test>::NotEnoughShares
+		);
+		assert_ok!(Strategies::withdraw(RuntimeOrigin::signed(BOB), DOT, 50_000));
+		System::assert_last_event(
+			Event::Withdrawn { who: BOB, asset: DOT, balance: 50_000, shares: 50_000 }.into(),
+		);
+		assert_eq!(balance(DOT, BOB), 950_000);
+		assert_eq!(balance(DOT, vault), 0);
+		assert_eq!(balance(DOT_LP, vault), 50_000);
+
+		// a strategy dropped from the weights is emptied
+		assert_ok!(Strategies::set_weights(
+			RuntimeOrigin::root(),
+			DOT,
+			BoundedVec::truncate_from(vec![])
+		));
+		assert_ok!(Strategies::rebalance_vault(DOT));
+		assert_eq!(balance(DOT_LP, vault), 0);
+		assert_eq!(balance(DOT, vault), 50_000);
+	});
+}
+
+#[test]
+fn the_vault_of_the_stablecoin_earns_the_savings_rate() {
+	new_test_ext().execute_with(|| {
+		create_asset(KUSD);
+		assert_ok!(Assets::mint_into(KUSD, &BOB, 1_000_000));
+		assert_ok!(Assets::mint_into(KUSD, &TREASURY, 100_000));
+		assert_ok!(Savings::set_rate(RuntimeOrigin::root(), FixedU128::from_rational(10, 100)));
+		assert_ok!(Strategies::set_weights(
+			RuntimeOrigin::root(),
+			KUSD,
+			BoundedVec::truncate_from(vec![(Strategy::Savings, Permill::from_percent(50))])
+		));
+		assert_ok!(Strategies::deposit(RuntimeOrigin::signed(BOB), KUSD, 100_000));
+		let vault = Strategies::vault_account_id(KUSD);
+		Strategies::on_idle(1, Weight::MAX);
+		assert_eq!(<Savings as savings::Savings<_, _>>::balance(&vault), 50_000);
+		assert_eq!(balance(KUSD, vault), 50_000);
+
+		// a year at 10% compounded on half of the vault, accrued as the rate is set again
+		Timestamp::set_timestamp(SECONDS_PER_YEAR * 1_000);
+		assert_ok!(Savings::set_rate(RuntimeOrigin::root(), FixedU128::from_rational(10, 100)));
+		let value = Strategies::vault_value(KUSD).unwrap();
+		assert!(value > 105_200 && value < 105_300);
+
+		// the shares redeem for the interest, withdrawn from the savings
+		assert_ok!(Strategies::withdraw(RuntimeOrigin::signed(BOB), KUSD, 100_000));
+		assert!(balance(KUSD, BOB) >= 900_000 + value - 1);
+		assert!(!Shares::<Test>::contains_key(BOB, KUSD));
+	});
+}
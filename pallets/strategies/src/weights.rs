@@ -0,0 +1,118 @@
+//! Weights for the strategies pallet
+//!
+//! The weights are estimated from the storage accesses of the benchmarks in `benchmarking.rs`
+//! until they are generated on the reference hardware with the command below. The proof sizes
+//! allow 3_600 bytes per storage read, the size of the proof of an entry of an `Assets` account.
+
+// Command:
+// ./target/release/kylix-node
+// benchmark
+// pallet
+// --chain
+// dev
+// --pallet
+// strategies
+// --extrinsic
+// *
+// --steps=50
+// --repeat=20
+// --wasm-execution=compiled
+// --output
+// pallets/strategies/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for strategies.
+pub trait WeightInfo {
+	fn set_weights() -> Weight;
+	fn deposit() -> Weight;
+	fn withdraw() -> Weight;
+}
+
+/// Weights for strategies using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Strategies Vaults (r:1 w:1)
+	fn set_weights() -> Weight {
+		Weight::from_parts(15_000_000, 7_200)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Strategies Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Savings Pot (r:1 w:0)
+	/// Storage: Savings Deposits (r:1 w:0)
+	/// Storage: Assets Asset (r:2 w:1)
+	/// Storage: Assets Account (r:3 w:2)
+	/// Storage: Strategies Shares (r:1 w:1)
+	fn deposit() -> Weight {
+		Weight::from_parts(50_000_000, 36_000)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Strategies Vaults (r:1 w:1)
+	/// Storage: Strategies Shares (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending LastSupplies (r:1 w:1)
+	/// Storage: Savings Pot (r:1 w:0)
+	/// Storage: Savings Deposits (r:1 w:0)
+	/// Storage: Lending OutflowCaps (r:1 w:0)
+	/// Storage: Lending PoolOutflows (r:1 w:1)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Assets Asset (r:3 w:2)
+	/// Storage: Assets Account (r:5 w:5)
+	fn withdraw() -> Weight {
+		Weight::from_parts(120_000_000, 79_200)
+			.saturating_add(T::DbWeight::get().reads(22_u64))
+			.saturating_add(T::DbWeight::get().writes(14_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Strategies Vaults (r:1 w:1)
+	fn set_weights() -> Weight {
+		Weight::from_parts(15_000_000, 7_200)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Strategies Vaults (r:1 w:1)
+	/// Storage: Lending LendingPoolStorage (r:1 w:0)
+	/// Storage: Savings Pot (r:1 w:0)
+	/// Storage: Savings Deposits (r:1 w:0)
+	/// Storage: Assets Asset (r:2 w:1)
+	/// Storage: Assets Account (r:3 w:2)
+	/// Storage: Strategies Shares (r:1 w:1)
+	fn deposit() -> Weight {
+		Weight::from_parts(50_000_000, 36_000)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	/// Storage: Strategies Vaults (r:1 w:1)
+	/// Storage: Strategies Shares (r:1 w:1)
+	/// Storage: Lending AccountBorrows (r:1 w:0)
+	/// Storage: Lending LendingPoolStorage (r:3 w:1)
+	/// Storage: Lending LastSupplies (r:1 w:1)
+	/// Storage: Savings Pot (r:1 w:0)
+	/// Storage: Savings Deposits (r:1 w:0)
+	/// Storage: Lending OutflowCaps (r:1 w:0)
+	/// Storage: Lending PoolOutflows (r:1 w:1)
+	/// Storage: Lending UserRewards (r:2 w:2)
+	/// Storage: Lending RewardPrograms (r:1 w:0)
+	/// Storage: Assets Asset (r:3 w:2)
+	/// Storage: Assets Account (r:5 w:5)
+	fn withdraw() -> Weight {
+		Weight::from_parts(120_000_000, 79_200)
+			.saturating_add(RocksDbWeight::get().reads(22_u64))
+			.saturating_add(RocksDbWeight::get().writes(14_u64))
+	}
+}
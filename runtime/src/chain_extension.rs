@@ -0,0 +1,103 @@
+//! The chain extension of the lending pallet, through which the ink! contracts of this chain
+//! supply to, withdraw from, borrow from and repay the lending pools, and query the positions of
+//! any account.
+//!
+//! The contracts act with their own account. The function ids and their SCALE encoded inputs:
+//! - `1`: supply `(asset, balance)`
+//! - `2`: withdraw `(asset, balance)`
+//! - `3`: borrow `(asset, balance, collateral_asset, collateral_balance)`
+//! - `4`: repay `(asset, balance, collateral_asset)`
+//! - `5`: supply position `(account, asset)`, returns an `Option<SupplyPosition>`
+//! - `6`: borrow position `(account, asset, collateral_asset)`, returns an `Option<BorrowPosition>`
+//!
+//! The calls are dispatched through the call filters of the runtime, so that the maintenance mode
+//! of the lending pallet applies to the contracts too, and the contracts are refunded the weight
+//! the calls did not use. They return 0 on success. Their errors, as well as unknown function
+//! ids, revert the call of the contract.
+
+use crate::{AccountId, Balance, Lending, Runtime, RuntimeCall, RuntimeOrigin};
+use codec::Encode;
+use frame_support::{
+	dispatch::{DispatchError, GetDispatchInfo},
+	traits::Get,
+};
+use pallet_contracts::chain_extension::{ChainExtension, Environment, Ext, InitState, RetVal};
+use sp_runtime::traits::Dispatchable;
+
+type AssetId = u32;
+
+/// Exposes the lending pools to the contracts
+#[derive(Default)]
+pub struct LendingExtension;
+
+impl ChainExtension<Runtime> for LendingExtension {
+	fn call<E: Ext<T = Runtime>>(
+		&mut self,
+		env: Environment<E, InitState>,
+	) -> Result<RetVal, DispatchError> {
+		let func_id = env.func_id();
+		let mut env = env.buf_in_buf_out();
+		let origin = RuntimeOrigin::signed(env.ext().address().clone());
+		let db_weight = <Runtime as frame_system::Config>::DbWeight::get();
+
+		let call = match func_id {
+			1 => {
+				let (asset, balance): (AssetId, Balance) = env.read_as()?;
+				lending::Call::supply { asset, balance, referral: None }
+			},
+			2 => {
+				let (asset, balance): (AssetId, Balance) = env.read_as()?;
+				lending::Call::withdraw { asset, balance }
+			},
+			3 => {
+				let (asset, balance, collateral_asset, collateral_balance): (
+					AssetId,
+					Balance,
+					AssetId,
+					Balance,
+				) = env.read_as()?;
+				lending::Call::borrow {
+					asset,
+					balance,
+					collateral_asset,
+					collateral_balance,
+					referral: None,
+				}
+			},
+			4 => {
+				let (asset, balance, collateral_asset): (AssetId, Balance, AssetId) =
+					env.read_as()?;
+				lending::Call::repay { asset, balance, collateral_asset }
+			},
+			5 => {
+				let (who, asset): (AccountId, AssetId) = env.read_as()?;
+				env.charge_weight(db_weight.reads(6))?;
+				let position = Lending::supply_position(&who, asset);
+				env.write(&position.encode(), false, None)?;
+				return Ok(RetVal::Converging(0))
+			},
+			6 => {
+				let (who, asset, collateral_asset): (AccountId, AssetId, AssetId) =
+					env.read_as()?;
+				env.charge_weight(db_weight.reads(10))?;
+				let position = Lending::borrow_position(&who, asset, collateral_asset);
+				env.write(&position.encode(), false, None)?;
+				return Ok(RetVal::Converging(0))
+			},
+			_ => return Err(DispatchError::Other("unknown function of the lending extension")),
+		};
+
+		// charged upfront at the declared weight of the call, refunded down to its actual weight
+		let call = RuntimeCall::Lending(call);
+		let info = call.get_dispatch_info();
+		let charged = env.charge_weight(info.weight)?;
+		let result = call.dispatch(origin);
+		let post_info = match result {
+			Ok(post_info) => post_info,
+			Err(err) => err.post_info,
+		};
+		env.adjust_weight(charged, post_info.calc_actual_weight(&info));
+		result.map_err(|err| err.error)?;
+		Ok(RetVal::Converging(0))
+	}
+}
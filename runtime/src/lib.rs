@@ -6,8 +6,11 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
-use frame_support::traits::AsEnsureOriginWithArg;
-use frame_system::{EnsureRoot, EnsureSigned};
+use frame_support::traits::{
+	fungibles::roles::Inspect as _, AsEnsureOriginWithArg, Contains, EqualPrivilegeOnly,
+	NeverEnsureOrigin, Nothing,
+};
+use frame_system::{EnsureRoot, EnsureSigned, EnsureSignedBy};
 use pallet_grandpa::AuthorityId as GrandpaId;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -15,9 +18,10 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount,
+		NumberFor, One, Verify,
 	},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -51,6 +55,20 @@ pub use sp_runtime::{Perbill, Permill};
 /// Import the lending pallet.
 pub use lending;
 
+/// Import the CDPs of the stablecoin.
+pub use cdp;
+
+/// Import the peg stability module of the stablecoin.
+pub use psm;
+
+/// Import the savings of the stablecoin.
+pub use savings;
+
+/// Import the strategy vaults.
+pub use strategies;
+
+mod chain_extension;
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -152,11 +170,34 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 }
 
+/// Keeps the LP tokens of the lending pools out of the transfers of `pallet_assets`, which the
+/// `Freezer` of `Assets` fails anyway. They move through `Lending::transfer_lp_tokens`, which
+/// carries their reward accounting along. The calls of the lending pallet are blocked in its
+/// maintenance mode, but for the repayments, and while its storage is lazily migrated.
+pub struct BaseCallFilter;
+impl Contains<RuntimeCall> for BaseCallFilter {
+	fn contains(call: &RuntimeCall) -> bool {
+		let id = match call {
+			RuntimeCall::Lending(call) =>
+				return lending::migrations::MigrationFilter::<Runtime>::contains(call) &&
+					lending::MaintenanceFilter::<Runtime>::contains(call),
+			RuntimeCall::Assets(
+				pallet_assets::Call::transfer { id, .. } |
+				pallet_assets::Call::transfer_keep_alive { id, .. } |
+				pallet_assets::Call::approve_transfer { id, .. } |
+				pallet_assets::Call::transfer_approved { id, .. },
+			) => id.0,
+			_ => return true,
+		};
+		Assets::owner(id) != Some(Lending::account_id())
+	}
+}
+
 // Configure FRAME pallets to include in runtime.
 
 impl frame_system::Config for Runtime {
 	/// The basic call filter to use in dispatchable.
-	type BaseCallFilter = frame_support::traits::Everything;
+	type BaseCallFilter = BaseCallFilter;
 	/// The block type for the runtime.
 	type Block = Block;
 	/// Block & extrinsics weights: base values and limits.
@@ -247,8 +288,8 @@ impl pallet_balances::Config for Runtime {
 	type WeightInfo = pallet_balances::weights::SubstrateWeight<Runtime>;
 	type FreezeIdentifier = ();
 	type MaxFreezes = ();
-	type RuntimeHoldReason = ();
-	type MaxHolds = ();
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type MaxHolds = ConstU32<1>;
 }
 
 parameter_types! {
@@ -270,20 +311,240 @@ impl pallet_sudo::Config for Runtime {
 	type WeightInfo = pallet_sudo::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * BlockWeights::get().max_block;
+	pub const PreimageBaseDeposit: Balance = 1_000;
+	pub const PreimageByteDeposit: Balance = 1;
+}
+
+impl pallet_scheduler::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type PalletsOrigin = OriginCaller;
+	type RuntimeCall = RuntimeCall;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = ConstU32<50>;
+	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+	type OriginPrivilegeCmp = EqualPrivilegeOnly;
+	type Preimages = Preimage;
+}
+
+impl pallet_preimage::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = pallet_preimage::weights::SubstrateWeight<Runtime>;
+	type Currency = Balances;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type BaseDeposit = PreimageBaseDeposit;
+	type ByteDeposit = PreimageByteDeposit;
+}
+
 use frame_support::PalletId;
 
 parameter_types! {
 	pub const LendingPalletId: PalletId = PalletId(*b"kylix_id");
+	pub const CompoundTip: Permill = Permill::from_percent(1);
+	pub const ReferralShare: Permill = Permill::from_percent(20);
+	pub const StableRateRebalanceUtilisation: Permill = Permill::from_percent(95);
+	pub const TermGracePeriod: u64 = 24 * 60 * 60;
+	pub const UtilisationAveragePeriod: u64 = 24 * 60 * 60;
+	pub const MaxAccrualPeriod: u64 = 365 * 24 * 60 * 60;
+	pub TermPenaltyRate: sp_runtime::FixedU128 = sp_runtime::FixedU128::from_rational(1, 10);
+	pub TreasuryAccount: AccountId = PalletId(*b"py/trsry").into_account_truncating();
+	pub MaxRewardBoost: sp_runtime::FixedU128 = sp_runtime::FixedU128::from_rational(5, 2);
+	pub const MaxLoyaltyBonus: Permill = Permill::from_percent(10);
+	pub const OffchainScanInterval: BlockNumber = 10;
+	pub const OutflowWindow: BlockNumber = HOURS;
+	pub const LendingUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+	pub const ParamChangeDelay: BlockNumber = 2 * DAYS;
+	pub const MarginCallSeverity: Permill = Permill::from_percent(20);
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(10);
+}
+
+/// The accounts feeding the prices of the assets to the lending pallet
+impl pallet_membership::Config<pallet_membership::Instance1> for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AddOrigin = EnsureRoot<AccountId>;
+	type RemoveOrigin = EnsureRoot<AccountId>;
+	type SwapOrigin = EnsureRoot<AccountId>;
+	type ResetOrigin = EnsureRoot<AccountId>;
+	type PrimeOrigin = EnsureRoot<AccountId>;
+	type MembershipInitialized = ();
+	type MembershipChanged = ();
+	type MaxMembers = ConstU32<16>;
+	type WeightInfo = pallet_membership::weights::SubstrateWeight<Runtime>;
 }
 
 /// Configure the lending in pallets/lending.
 impl lending::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeFreezeReason = RuntimeFreezeReason;
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type WeightInfo = lending::weights::SubstrateWeight<Runtime>;
 	type NativeBalance = Balances;
-	type Fungibles = Assets;
+	// the LP tokens held in `Assets` are frozen outside of the lending pallet
+	type Fungibles = lending::LpTokenGuard<Runtime, Assets>;
 	type PalletId = LendingPalletId;
 	type Time = Timestamp;
+	// the governance of other chains can be admitted once this runtime processes XCM
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	// the prices are fed by the members of `OracleFeeders`, admitted by the governance
+	type OracleOrigin = EnsureSignedBy<OracleFeeders, AccountId>;
+	// this runtime does not process XCM yet, no user of another chain can supply
+	type RemoteOrigin = NeverEnsureOrigin<AccountId>;
+	// nor send the borrowed assets to other chains
+	type Location = ();
+	type TransferToChain = ();
+	// no DEX is deployed, the treasury repays the offchain liquidations with its inventory
+	type SwapProvider = ();
+	// nor are its LP tokens accepted as collateral
+	type DexPools = ();
+	type LendingHooks = ();
+	// no sanctions nor KYC pallet is deployed, all the accounts are allowed
+	type ComplianceCheck = ();
+	// nor are the tiers of the accounts provided, they are only assigned by the governance
+	type AccountTierProvider = ();
+	// no NFT pallet is deployed, the positions can not be wrapped until `NonfungiblesPositions`
+	// is set up over one
+	type PositionNfts = ();
+	// the collaterals of the CDPs, the stablecoins of the PSM and the assets of the strategy
+	// vaults are kept out of the redenominations and the replacements
+	type AssetsInUse = (Cdp, Psm, Strategies);
+	type CompoundTip = CompoundTip;
+	type MaxMarketsPerAccount = ConstU32<16>;
+	type MaxPools = ConstU32<64>;
+	type GovernanceLocks = ();
+	type MaxRewardBoost = MaxRewardBoost;
+	type MaxLoyaltyTiers = ConstU32<4>;
+	// the loyalty tiers add a small bonus to the rewards only
+	type MaxLoyaltyBonus = MaxLoyaltyBonus;
+	type ReferralShare = ReferralShare;
+	type TreasuryAccount = TreasuryAccount;
+	type OffchainScanInterval = OffchainScanInterval;
+	type UnsignedPriority = LendingUnsignedPriority;
+	type SwapOffchainLiquidations = ConstBool<false>;
+	type KeeperBond = ConstU128<{ 1_000 * EXISTENTIAL_DEPOSIT }>;
+	// the keepers have three scans of the offchain worker to act on a liquidation
+	type KeeperExclusivity = ConstU32<30>;
+	type MaxKeeperMisses = ConstU32<3>;
+	// the borrowers have ten minutes to answer a margin call, unless their shortfall exceeds a
+	// fifth of their debt
+	type MarginCallGracePeriod = ConstU32<{ 10 * MINUTES }>;
+	type MarginCallSeverity = MarginCallSeverity;
+	// a minute without blocks is a stall, the borrowers then have an hour to restore their
+	// health before the liquidations resume
+	type StallThreshold = ConstU64<60>;
+	type StallGracePeriod = ConstU64<{ 60 * 60 }>;
+	// no secondary oracle is deployed, the prices are not checked for deviations
+	type PriceReference = ();
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MaxPoolsPerBlock = ConstU32<8>;
+	type DustBalance = ConstU128<1_000_000>;
+	type MinPoolLiquidity = ConstU128<1_000_000_000>;
+	// a thousandth of the minimum first deposit is locked for good
+	type DeadShares = ConstU128<1_000_000>;
+	type StableRateRebalanceUtilisation = StableRateRebalanceUtilisation;
+	type TermGracePeriod = TermGracePeriod;
+	type TermPenaltyRate = TermPenaltyRate;
+	type UtilisationAveragePeriod = UtilisationAveragePeriod;
+	type MaxAccrualPeriod = MaxAccrualPeriod;
+	type OutflowWindow = OutflowWindow;
+	// a supply can not be withdrawn in the same block
+	type WithdrawCooldown = ConstU32<1>;
+	// a week of hourly rate snapshots
+	type RateSnapshotInterval = ConstU32<HOURS>;
+	type MaxRateSnapshots = ConstU32<168>;
+	// the votes count the supplied balances at the start of the day they opened
+	type KTokenSnapshotEra = ConstU32<DAYS>;
+	type MaxKTokenCheckpoints = ConstU32<32>;
+	type MaxKTokenFreezes = ConstU32<8>;
+	type RuntimeCall = RuntimeCall;
+	type Scheduler = Scheduler;
+	type Preimages = Preimage;
+	// the risk parameters change two days after their announcement
+	type ParamChangeDelay = ParamChangeDelay;
+	type AssetId = u32;
+	type BaseAsset = ConstU32<0>;
+	// the native currency is not routed by the `Assets`, it has no market yet
+	type NativeAsset = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+parameter_types! {
+	pub const CdpPalletId: PalletId = PalletId(*b"kylixcdp");
+	pub const PsmPalletId: PalletId = PalletId(*b"kylixpsm");
+	pub const SavingsPalletId: PalletId = PalletId(*b"kylixsav");
+	pub const StrategiesPalletId: PalletId = PalletId(*b"kylixstr");
+}
+
+/// Configure the CDPs in pallets/cdp, minting kUSD against the LP tokens of the lending pools.
+impl cdp::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = CdpPalletId;
+	type AssetId = u32;
+	type Fungibles = Assets;
+	type Lending = Lending;
+	// kUSD, to be created by the governance before the collaterals of the CDPs are set
+	type StableAsset = ConstU32<100>;
+	type TreasuryAccount = TreasuryAccount;
+	type Time = Timestamp;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type WeightInfo = cdp::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+/// Configure the peg stability module in pallets/psm, swapping external stablecoins with kUSD.
+impl psm::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = PsmPalletId;
+	type AssetId = u32;
+	type Fungibles = Assets;
+	type StableAsset = ConstU32<100>;
+	type TreasuryAccount = TreasuryAccount;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type WeightInfo = psm::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+/// Configure the savings in pallets/savings, paying the savings rate on kUSD.
+impl savings::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = SavingsPalletId;
+	type AssetId = u32;
+	type Fungibles = Assets;
+	type StableAsset = ConstU32<100>;
+	type TreasuryAccount = TreasuryAccount;
+	type Time = Timestamp;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type WeightInfo = savings::weights::SubstrateWeight<Runtime>;
+}
+
+/// Configure the strategy vaults in pallets/strategies, allocating the deposits across the
+/// lending pools and the savings.
+impl strategies::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = StrategiesPalletId;
+	type AssetId = u32;
+	type Fungibles = Assets;
+	type Lending = Lending;
+	type Savings = Savings;
+	// kUSD, the only asset whose vault may deposit into the savings
+	type StableAsset = ConstU32<100>;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type WeightInfo = strategies::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	RuntimeCall: From<C>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = UncheckedExtrinsic;
 }
 
 parameter_types! {
@@ -308,7 +569,7 @@ impl pallet_assets::Config for Runtime {
 	type MetadataDepositPerByte = MetadataDepositPerByte;
 	type ApprovalDeposit = ApprovalDeposit;
 	type StringLimit = StringLimit;
-	type Freezer = ();
+	type Freezer = Lending;
 	type Extra = ();
 	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
 	type RemoveItemsLimit = ConstU32<1000>;
@@ -317,6 +578,44 @@ impl pallet_assets::Config for Runtime {
 	type BenchmarkHelper = ();
 }
 
+impl pallet_insecure_randomness_collective_flip::Config for Runtime {}
+
+/// The deposit for `items` storage items of `bytes` bytes
+const fn deposit(items: u32, bytes: u32) -> Balance {
+	items as Balance * 100 * EXISTENTIAL_DEPOSIT + bytes as Balance * EXISTENTIAL_DEPOSIT
+}
+
+parameter_types! {
+	pub const DepositPerItem: Balance = deposit(1, 0);
+	pub const DepositPerByte: Balance = deposit(0, 1);
+	pub const DefaultDepositLimit: Balance = deposit(1024, 1024 * 1024);
+	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
+}
+
+impl pallet_contracts::Config for Runtime {
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	// the contracts can not dispatch calls, they reach the lending pools through the extension
+	type CallFilter = Nothing;
+	type DepositPerItem = DepositPerItem;
+	type DepositPerByte = DepositPerByte;
+	type DefaultDepositLimit = DefaultDepositLimit;
+	type CallStack = [pallet_contracts::Frame<Self>; 5];
+	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
+	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
+	type ChainExtension = chain_extension::LendingExtension;
+	type Schedule = Schedule;
+	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+	type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+	type MaxStorageKeyLen = ConstU32<128>;
+	type UnsafeUnstableInterface = ConstBool<false>;
+	type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+	type Migrations = ();
+}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 construct_runtime!(
 	pub struct Runtime {
@@ -328,8 +627,17 @@ construct_runtime!(
 		TransactionPayment: pallet_transaction_payment,
 		Sudo: pallet_sudo,
 		Assets: pallet_assets,
+		Scheduler: pallet_scheduler,
+		Preimage: pallet_preimage,
+		OracleFeeders: pallet_membership::<Instance1>,
 		// Include the custom logic from the lending in the runtime.
 		Lending: lending,
+		Cdp: cdp,
+		Psm: psm,
+		Savings: savings,
+		Strategies: strategies,
+		RandomnessCollectiveFlip: pallet_insecure_randomness_collective_flip,
+		Contracts: pallet_contracts,
 	}
 );
 
@@ -363,8 +671,12 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
+/// The storage migrations applied on the next runtime upgrade.
+pub type Migrations = (lending::migrations::v1::MigrateToV1<Runtime>,);
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -377,7 +689,14 @@ mod benches {
 		[pallet_balances, Balances]
 		[pallet_timestamp, Timestamp]
 		[pallet_sudo, Sudo]
+		[pallet_scheduler, Scheduler]
+		[pallet_preimage, Preimage]
+		[pallet_membership, OracleFeeders]
 		[lending, Lending]
+		[cdp, Cdp]
+		[psm, Psm]
+		[savings, Savings]
+		[strategies, Strategies]
 	);
 }
 
@@ -526,6 +845,72 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl lending_runtime_api::LendingApi<Block, AccountId, u32, Balance> for Runtime {
+		fn pool(asset: u32) -> Option<lending_runtime_api::PoolInfo<u32, Balance>> {
+			Lending::pool_info(asset)
+		}
+
+		fn pools() -> Vec<lending_runtime_api::PoolInfo<u32, Balance>> {
+			Lending::pools_info()
+		}
+
+		fn pools_page(
+			start_after: Option<u32>,
+			limit: u32,
+		) -> lending_runtime_api::Page<lending_runtime_api::PoolInfo<u32, Balance>, u32> {
+			Lending::pools_page(start_after, limit)
+		}
+
+		fn supply_positions_page(
+			start_after: Option<(AccountId, u32)>,
+			limit: u32,
+		) -> lending_runtime_api::Page<
+			(AccountId, lending_runtime_api::SupplyPosition<u32, Balance>),
+			(AccountId, u32),
+		> {
+			Lending::supply_positions_page(start_after, limit)
+		}
+
+		fn borrow_positions_page(
+			start_after: Option<(AccountId, u32, u32)>,
+			limit: u32,
+		) -> lending_runtime_api::Page<
+			(AccountId, lending_runtime_api::BorrowPosition<u32, Balance>),
+			(AccountId, u32, u32),
+		> {
+			Lending::borrow_positions_page(start_after, limit)
+		}
+
+		fn protocol_stats() -> lending_runtime_api::ProtocolTotals<Balance> {
+			Lending::protocol_stats()
+		}
+
+		fn account_overview(who: AccountId) -> lending_runtime_api::AccountOverview<u32, Balance> {
+			Lending::account_overview(&who)
+		}
+
+		fn account_snapshot(
+			who: AccountId,
+			asset: u32,
+		) -> Result<lending_runtime_api::AccountSnapshot<Balance>, sp_runtime::DispatchError> {
+			Lending::get_account_snapshot(&who, asset)
+		}
+
+		fn simulate(
+			who: AccountId,
+			action: lending_runtime_api::Action<u32, Balance>,
+		) -> Result<lending_runtime_api::Simulation<u32, Balance>, sp_runtime::DispatchError> {
+			Lending::simulate(&who, action)
+		}
+
+		fn max_borrowable(
+			who: AccountId,
+			asset: u32,
+		) -> Option<lending_runtime_api::MaxBorrow<u32, Balance>> {
+			Lending::max_borrowable(&who, asset)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi<Block, Balance, RuntimeCall>
 		for Runtime
 	{